@@ -0,0 +1,18 @@
+-- Your SQL goes here
+CREATE TABLE ai_channels (
+    channel_id BIGINT UNSIGNED PRIMARY KEY,
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
+
+CREATE TABLE ai_usage (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    channel_id BIGINT UNSIGNED NOT NULL,
+    user_id BIGINT UNSIGNED NOT NULL,
+    model VARCHAR(255) NOT NULL,
+    prompt_tokens INT UNSIGNED NOT NULL,
+    completion_tokens INT UNSIGNED NOT NULL,
+    cost DOUBLE NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE github_hooks (
+    channel_id BIGINT UNSIGNED PRIMARY KEY,
+    secret VARCHAR(255) NOT NULL,
+    push_template TEXT,
+    release_template TEXT,
+    issues_template TEXT,
+    pull_request_template TEXT,
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
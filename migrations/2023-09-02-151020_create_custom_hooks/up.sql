@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE custom_hooks (
+    channel_id BIGINT UNSIGNED,
+    id VARCHAR(127) NOT NULL,
+    token VARCHAR(255) NOT NULL,
+    action MEDIUMTEXT NOT NULL,
+    execution_mode VARCHAR(127) NOT NULL DEFAULT 'template',
+    PRIMARY KEY(channel_id, id),
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
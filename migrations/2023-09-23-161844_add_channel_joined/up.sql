@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE channels ADD COLUMN joined BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+CREATE TABLE blocked_users (
+    identifier VARCHAR(255) PRIMARY KEY
+);
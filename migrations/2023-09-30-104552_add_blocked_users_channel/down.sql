@@ -0,0 +1,5 @@
+DELETE FROM blocked_users WHERE channel_id IS NOT NULL;
+ALTER TABLE blocked_users DROP FOREIGN KEY blocked_users_ibfk_1;
+ALTER TABLE blocked_users DROP COLUMN channel_id;
+ALTER TABLE blocked_users DROP COLUMN id;
+ALTER TABLE blocked_users ADD PRIMARY KEY (identifier);
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE blocked_users DROP PRIMARY KEY;
+ALTER TABLE blocked_users ADD COLUMN id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY FIRST;
+ALTER TABLE blocked_users ADD COLUMN channel_id BIGINT UNSIGNED;
+ALTER TABLE blocked_users ADD FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE;
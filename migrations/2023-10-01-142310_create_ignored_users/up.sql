@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE ignored_users (
+    channel_id BIGINT UNSIGNED,
+    identifier VARCHAR(255) NOT NULL,
+    PRIMARY KEY(channel_id, identifier),
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
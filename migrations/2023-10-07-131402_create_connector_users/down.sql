@@ -0,0 +1,7 @@
+ALTER TABLE users ADD matrix_id TEXT UNIQUE;
+
+UPDATE users
+INNER JOIN connector_users ON connector_users.user_id = users.id AND connector_users.platform = 'matrix'
+SET users.matrix_id = connector_users.connector_user_id;
+
+DROP TABLE connector_users;
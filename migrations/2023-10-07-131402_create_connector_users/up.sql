@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE connector_users (
+    platform VARCHAR(127) NOT NULL,
+    connector_user_id VARCHAR(255) NOT NULL,
+    user_id BIGINT UNSIGNED NOT NULL,
+    PRIMARY KEY(platform, connector_user_id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+INSERT INTO connector_users(platform, connector_user_id, user_id)
+SELECT 'matrix', matrix_id, id FROM users WHERE matrix_id IS NOT NULL;
+
+ALTER TABLE users DROP COLUMN matrix_id;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE moderation_log (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    channel_id BIGINT UNSIGNED NOT NULL,
+    moderator_id BIGINT UNSIGNED,
+    target VARCHAR(255) NOT NULL,
+    action VARCHAR(16) NOT NULL,
+    duration INT UNSIGNED,
+    reason TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE,
+    FOREIGN KEY (moderator_id) REFERENCES users(id) ON DELETE SET NULL
+);
@@ -0,0 +1 @@
+DROP TABLE protection_settings;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE protection_settings (
+    channel_id BIGINT UNSIGNED PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    max_message_rate INT UNSIGNED,
+    max_repeats INT UNSIGNED,
+    max_emotes INT UNSIGNED,
+    max_caps_percent INT UNSIGNED,
+    max_links INT UNSIGNED,
+    max_mentions INT UNSIGNED,
+    action VARCHAR(16) NOT NULL DEFAULT 'delete',
+    timeout_duration INT UNSIGNED NOT NULL DEFAULT 60,
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
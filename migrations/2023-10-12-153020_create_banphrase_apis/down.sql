@@ -0,0 +1 @@
+DROP TABLE banphrase_apis;
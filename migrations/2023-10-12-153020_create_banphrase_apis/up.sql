@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE banphrase_apis (
+    channel_id BIGINT UNSIGNED PRIMARY KEY,
+    url VARCHAR(255) NOT NULL,
+    block_message BOOLEAN NOT NULL DEFAULT FALSE,
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE channel_settings (
+    channel_id BIGINT UNSIGNED PRIMARY KEY,
+    long_messages VARCHAR(16) NOT NULL DEFAULT 'split',
+    max_lines INT UNSIGNED NOT NULL DEFAULT 3,
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
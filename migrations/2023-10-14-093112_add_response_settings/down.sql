@@ -0,0 +1,4 @@
+ALTER TABLE channel_settings
+    DROP COLUMN reply_to_messages,
+    DROP COLUMN mention_user,
+    DROP COLUMN response_prefix;
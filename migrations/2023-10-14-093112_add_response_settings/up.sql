@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE channel_settings
+    ADD COLUMN reply_to_messages BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN mention_user BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN response_prefix VARCHAR(64);
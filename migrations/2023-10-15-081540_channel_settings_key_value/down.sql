@@ -0,0 +1,24 @@
+RENAME TABLE channel_settings TO new_channel_settings;
+
+CREATE TABLE channel_settings (
+    channel_id BIGINT UNSIGNED PRIMARY KEY,
+    long_messages VARCHAR(16) NOT NULL DEFAULT 'split',
+    max_lines INT UNSIGNED NOT NULL DEFAULT 3,
+    reply_to_messages BOOLEAN NOT NULL DEFAULT TRUE,
+    mention_user BOOLEAN NOT NULL DEFAULT FALSE,
+    response_prefix VARCHAR(64),
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
+
+INSERT INTO channel_settings (channel_id, long_messages, max_lines, reply_to_messages, mention_user, response_prefix)
+SELECT
+    channel_id,
+    COALESCE(MAX(IF(name = 'long_messages', value, NULL)), 'split'),
+    COALESCE(MAX(IF(name = 'max_lines', value, NULL)), 3),
+    COALESCE(MAX(IF(name = 'reply_to_messages', value = 'true', NULL)), TRUE),
+    COALESCE(MAX(IF(name = 'mention_user', value = 'true', NULL)), FALSE),
+    MAX(IF(name = 'response_prefix', value, NULL))
+FROM new_channel_settings
+GROUP BY channel_id;
+
+DROP TABLE new_channel_settings;
@@ -0,0 +1,24 @@
+-- Your SQL goes here
+RENAME TABLE channel_settings TO old_channel_settings;
+
+CREATE TABLE channel_settings (
+    channel_id BIGINT UNSIGNED NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    value VARCHAR(255) NOT NULL,
+    PRIMARY KEY (channel_id, name),
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
+
+INSERT INTO channel_settings (channel_id, name, value)
+SELECT channel_id, 'long_messages', long_messages FROM old_channel_settings;
+INSERT INTO channel_settings (channel_id, name, value)
+SELECT channel_id, 'max_lines', max_lines FROM old_channel_settings;
+INSERT INTO channel_settings (channel_id, name, value)
+SELECT channel_id, 'reply_to_messages', IF(reply_to_messages, 'true', 'false') FROM old_channel_settings;
+INSERT INTO channel_settings (channel_id, name, value)
+SELECT channel_id, 'mention_user', IF(mention_user, 'true', 'false') FROM old_channel_settings;
+INSERT INTO channel_settings (channel_id, name, value)
+SELECT channel_id, 'response_prefix', response_prefix FROM old_channel_settings
+WHERE response_prefix IS NOT NULL;
+
+DROP TABLE old_channel_settings;
@@ -0,0 +1,2 @@
+ALTER TABLE users DROP COLUMN github_id;
+ALTER TABLE users DROP COLUMN google_id;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE users ADD github_id TEXT UNIQUE;
+ALTER TABLE users ADD google_id TEXT UNIQUE;
@@ -0,0 +1,4 @@
+ALTER TABLE web_sessions
+    DROP COLUMN created_at,
+    DROP COLUMN expires_at,
+    DROP COLUMN user_agent;
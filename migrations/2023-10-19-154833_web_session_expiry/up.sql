@@ -0,0 +1,7 @@
+-- Your SQL goes here
+ALTER TABLE web_sessions
+    ADD created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD expires_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD user_agent TEXT;
+
+UPDATE web_sessions SET expires_at = created_at + INTERVAL 30 DAY;
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE command_tests (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    channel_id BIGINT UNSIGNED NOT NULL,
+    command_name VARCHAR(255) NOT NULL,
+    args TEXT NOT NULL,
+    expected TEXT NOT NULL,
+    FOREIGN KEY (channel_id, command_name) REFERENCES commands(channel_id, name)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE eventsub_triggers DROP COLUMN paused;
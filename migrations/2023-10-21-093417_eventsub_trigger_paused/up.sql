@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE eventsub_triggers ADD paused BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+ALTER TABLE eventsub_triggers DROP FOREIGN KEY eventsub_triggers_ibfk_1;
+ALTER TABLE eventsub_triggers DROP COLUMN secret_id;
+DROP TABLE eventsub_secrets;
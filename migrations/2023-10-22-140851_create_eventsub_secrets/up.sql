@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE eventsub_secrets (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    secret VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Triggers without a secret were subscribed with the legacy secret_key from the config
+ALTER TABLE eventsub_triggers
+    ADD secret_id BIGINT UNSIGNED,
+    ADD FOREIGN KEY (secret_id) REFERENCES eventsub_secrets(id);
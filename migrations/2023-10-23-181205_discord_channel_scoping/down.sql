@@ -0,0 +1,2 @@
+ALTER TABLE commands DROP COLUMN discord_channels;
+ALTER TABLE filters DROP COLUMN discord_channels;
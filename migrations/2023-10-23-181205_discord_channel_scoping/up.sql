@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE commands ADD discord_channels TEXT;
+ALTER TABLE filters ADD discord_channels TEXT;
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE pastes (
+    id VARCHAR(32) PRIMARY KEY,
+    filename VARCHAR(255) NOT NULL,
+    content MEDIUMBLOB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1 @@
+DROP TABLE geohub_results;
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE geohub_results (
+    geohub_name VARCHAR(255) NOT NULL,
+    date DATE NOT NULL,
+    total_points INT UNSIGNED NOT NULL,
+    total_time INT UNSIGNED NOT NULL,
+    PRIMARY KEY (geohub_name, date)
+);
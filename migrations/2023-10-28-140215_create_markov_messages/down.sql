@@ -0,0 +1 @@
+DROP TABLE markov_messages;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+-- Partitioned tables can't have foreign keys, the logs of deleted channels and users are
+-- removed along with their partitions
+CREATE TABLE chat_logs (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    channel_id BIGINT UNSIGNED NOT NULL,
+    user_id BIGINT UNSIGNED NOT NULL,
+    text TEXT NOT NULL,
+    sent_at DATETIME NOT NULL,
+    PRIMARY KEY (id, sent_at),
+    INDEX (channel_id, user_id, sent_at)
+)
+PARTITION BY RANGE COLUMNS(sent_at) (
+    PARTITION p_future VALUES LESS THAN (MAXVALUE)
+);
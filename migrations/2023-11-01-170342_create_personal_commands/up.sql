@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE personal_commands (
+    user_id BIGINT UNSIGNED NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    action TEXT NOT NULL,
+    mode VARCHAR(127) NOT NULL DEFAULT 'template',
+    PRIMARY KEY (user_id, name),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
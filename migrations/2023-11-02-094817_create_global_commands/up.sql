@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE global_commands (
+    name VARCHAR(255) NOT NULL PRIMARY KEY,
+    action TEXT NOT NULL,
+    mode VARCHAR(127) NOT NULL DEFAULT 'template',
+    cooldown BIGINT UNSIGNED
+);
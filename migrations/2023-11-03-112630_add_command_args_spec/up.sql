@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE commands ADD args_spec TEXT
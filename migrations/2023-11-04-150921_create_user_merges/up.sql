@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE user_merges (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    user_id BIGINT UNSIGNED NOT NULL,
+    -- The merged user is deleted, so it can't be a foreign key
+    merged_user_id BIGINT UNSIGNED NOT NULL,
+    identity VARCHAR(255) NOT NULL,
+    method VARCHAR(16) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE user_names (
+    user_id BIGINT UNSIGNED NOT NULL,
+    platform VARCHAR(16) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    first_seen_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_seen_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, platform, name),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
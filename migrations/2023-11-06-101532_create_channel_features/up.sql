@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE channel_features (
+    channel_id BIGINT UNSIGNED NOT NULL,
+    feature VARCHAR(64) NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    PRIMARY KEY (channel_id, feature),
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
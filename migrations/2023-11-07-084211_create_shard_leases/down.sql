@@ -0,0 +1 @@
+DROP TABLE shard_leases;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+CREATE TABLE shard_leases (
+    shard_id VARCHAR(64) NOT NULL PRIMARY KEY,
+    expires_at TIMESTAMP NOT NULL
+);
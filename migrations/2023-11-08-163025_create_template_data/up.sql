@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE template_data (
+    channel_id BIGINT UNSIGNED NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    value TEXT NOT NULL,
+    expires_at TIMESTAMP NULL,
+    PRIMARY KEY (channel_id, name),
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE template_partials (
+    channel_id BIGINT UNSIGNED NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    body TEXT NOT NULL,
+    PRIMARY KEY (channel_id, name),
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
@@ -0,0 +1,316 @@
+use super::{error::ApiError, state::AppState, Result};
+use crate::{
+    command_handler::{reconcile_eventsub_triggers, CommandHandler},
+    database::{
+        models::{ApiTokenScope, Channel, ChannelFeature, ChannelFeatureState, WebSession},
+        DatabaseError,
+    },
+    platform::UserIdentifier,
+};
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequestParts, Path, State},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use futures::future::join_all;
+use http::{request::Parts, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// A web session of the admin user. API tokens need the admin scope
+pub struct AdminSession(pub WebSession);
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminSession {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        let session = WebSession::from_request_parts(parts, state).await?;
+
+        if let Some(scope) = parts.extensions.get::<ApiTokenScope>() {
+            if *scope != ApiTokenScope::Admin {
+                return Err(StatusCode::FORBIDDEN);
+            }
+        }
+
+        match state.cmd.db.run(|db| db.get_admin_user()).await {
+            Ok(Some(admin)) if admin.id == session.user_id => Ok(Self(session)),
+            _ => Err(StatusCode::FORBIDDEN),
+        }
+    }
+}
+
+pub async fn get_channels(
+    _: AdminSession,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Vec<Channel>>> {
+    Ok(Json(cmd.db.run(|db| db.get_channels()).await?))
+}
+
+#[derive(Deserialize)]
+pub struct JoinChannelPayload {
+    pub login: String,
+}
+
+/// Only Twitch channels have to be joined, other platforms add channels on demand
+pub async fn join_channel(
+    _: AdminSession,
+    cmd: State<CommandHandler>,
+    Json(payload): Json<JoinChannelPayload>,
+) -> Result<Json<Channel>> {
+    let channel = cmd.join_twitch_channel(&payload.login).await?;
+
+    Ok(Json(channel))
+}
+
+pub async fn part_channel(
+    _: AdminSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<()> {
+    let channel = cmd
+        .db
+        .run(move |db| db.get_channel_by_id(channel_id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    cmd.part_channel(&channel).await?;
+
+    Ok(())
+}
+
+pub async fn get_channel_features(
+    _: AdminSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Vec<ChannelFeatureState>>> {
+    let features = cmd
+        .db
+        .run(move |db| {
+            db.get_channel_by_id(channel_id)?
+                .ok_or(ApiError::NotFound)?;
+
+            Ok::<_, ApiError>(db.get_channel_features(channel_id)?)
+        })
+        .await?;
+
+    Ok(Json(features))
+}
+
+/// The body is `true` or `false`
+pub async fn set_channel_feature(
+    _: AdminSession,
+    Path((channel_id, feature)): Path<(u64, String)>,
+    cmd: State<CommandHandler>,
+    Json(enabled): Json<bool>,
+) -> Result<()> {
+    let feature = parse_feature(&feature)?;
+    cmd.db
+        .run(move |db| {
+            db.get_channel_by_id(channel_id)?
+                .ok_or(ApiError::NotFound)?;
+
+            Ok::<_, ApiError>(db.set_channel_feature(channel_id, feature, enabled)?)
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// The channel uses the feature's default again
+pub async fn reset_channel_feature(
+    _: AdminSession,
+    Path((channel_id, feature)): Path<(u64, String)>,
+    cmd: State<CommandHandler>,
+) -> Result<()> {
+    let feature = parse_feature(&feature)?;
+
+    cmd.db
+        .run(move |db| db.reset_channel_feature(channel_id, feature))
+        .await?;
+
+    Ok(())
+}
+
+fn parse_feature(feature: &str) -> Result<ChannelFeature> {
+    ChannelFeature::from_str(feature)
+        .map_err(|_| ApiError::BadRequest(format!("Unknown feature {feature}")))
+}
+
+pub async fn get_stats(_: AdminSession, cmd: State<CommandHandler>) -> Result<Json<Value>> {
+    let (channels, users, commands) = cmd
+        .db
+        .run(|db| {
+            Ok::<_, DatabaseError>((
+                db.get_channels_amount()?,
+                db.get_users_amount()?,
+                db.get_commands_amount()?,
+            ))
+        })
+        .await?;
+
+    Ok(Json(json!({
+        "version": crate::get_version(),
+        "safe_mode": cmd.is_safe_mode(),
+        "channels": channels,
+        "users": users,
+        "commands": commands,
+        "blocked_users": cmd.blocked_users.get(None).len(),
+        "connectors": cmd.connectors.list(),
+        "shard": cmd.sharding.get_shard_id(),
+        "shards": cmd.sharding.get_shards(),
+    })))
+}
+
+/// Sends the message to every joined channel, returns the amount of successful and failed sends
+pub async fn broadcast(
+    _: AdminSession,
+    cmd: State<CommandHandler>,
+    message: String,
+) -> Result<Json<Value>> {
+    if message.trim().is_empty() {
+        return Err(ApiError::BadRequest("Empty message".to_owned()));
+    }
+
+    let channels = cmd.db.run(|db| db.get_channels()).await?;
+    let platform_handler = cmd.platform_handler.read().await;
+
+    let results = join_all(
+        channels
+            .into_iter()
+            .filter(|channel| channel.joined)
+            .map(|channel| {
+                platform_handler.send_to_channel(channel.get_identifier(), message.clone())
+            }),
+    )
+    .await;
+
+    let failed = results.iter().filter(|result| result.is_err()).count();
+
+    Ok(Json(json!({
+        "sent": results.len() - failed,
+        "failed": failed,
+    })))
+}
+
+/// Only returns the global blocks
+pub async fn get_blocked_users(_: AdminSession, cmd: State<CommandHandler>) -> Json<Vec<String>> {
+    Json(
+        cmd.blocked_users
+            .get(None)
+            .iter()
+            .map(|identifier| identifier.to_string())
+            .collect(),
+    )
+}
+
+/// The identifier is in the `platform:id` format, for example `twitch:12345`
+pub async fn block_user(
+    _: AdminSession,
+    cmd: State<CommandHandler>,
+    identifier: String,
+) -> Result<()> {
+    let identifier = UserIdentifier::from_string(identifier.trim())
+        .map_err(|_| ApiError::BadRequest(format!("Invalid user identifier {identifier}")))?;
+
+    cmd.blocked_users.block(identifier, None).await?;
+
+    Ok(())
+}
+
+pub async fn unblock_user(
+    _: AdminSession,
+    Path(identifier): Path<String>,
+    cmd: State<CommandHandler>,
+) -> Result<()> {
+    let identifier = UserIdentifier::from_string(&identifier)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid user identifier {identifier}")))?;
+
+    cmd.blocked_users.unblock(&identifier, None).await?;
+
+    Ok(())
+}
+
+/// Reloads the blocked users from the DB, including channel blocks
+pub async fn reload_blocked_users(_: AdminSession, cmd: State<CommandHandler>) -> Result<()> {
+    cmd.blocked_users.reload().await?;
+
+    Ok(())
+}
+
+pub async fn flush_caches(_: AdminSession, cmd: State<CommandHandler>) {
+    cmd.flush_caches().await;
+}
+
+pub async fn reload_mirror_connections(
+    _: AdminSession,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Value>> {
+    let count = cmd.reload_mirror_connections()?;
+
+    Ok(Json(json!({ "connections": count })))
+}
+
+/// Only the settings that don't need a restart are applied
+pub async fn reload_config(_: AdminSession, cmd: State<CommandHandler>) -> Result<Json<Value>> {
+    let restart_required = cmd.reload_config().await?;
+
+    Ok(Json(json!({ "restart_required": restart_required })))
+}
+
+/// EventSub subscriptions are recreated with the new secret right away.
+/// Subscriptions that fail to be recreated keep using their old secret until the next
+/// reconciliation.
+pub async fn rotate_eventsub_secret(
+    _: AdminSession,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Value>> {
+    let twitch_api = cmd
+        .platform_handler
+        .read()
+        .await
+        .twitch_api
+        .clone()
+        .ok_or_else(|| ApiError::BadRequest("Twitch is not configured".to_owned()))?;
+
+    let secret_id = cmd.db.run(|db| db.add_eventsub_secret()).await?.id;
+    reconcile_eventsub_triggers(&twitch_api, &cmd.db).await?;
+
+    let outdated = cmd
+        .db
+        .run(|db| db.get_eventsub_triggers())
+        .await?
+        .into_iter()
+        .filter(|trigger| trigger.secret_id != Some(secret_id))
+        .count();
+
+    Ok(Json(
+        json!({ "secret_id": secret_id, "outdated_triggers": outdated }),
+    ))
+}
+
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/channels", get(get_channels))
+        .route("/channels/join", post(join_channel))
+        .route("/channels/:id/part", post(part_channel))
+        .route("/channels/:id/features", get(get_channel_features))
+        .route(
+            "/channels/:id/features/:feature",
+            put(set_channel_feature).delete(reset_channel_feature),
+        )
+        .route("/stats", get(get_stats))
+        .route("/broadcast", post(broadcast))
+        .route("/blocked-users", get(get_blocked_users).post(block_user))
+        .route("/blocked-users/reload", post(reload_blocked_users))
+        .route("/blocked-users/:identifier", delete(unblock_user))
+        .route("/cache/flush", post(flush_caches))
+        .route("/mirrors/reload", post(reload_mirror_connections))
+        .route("/config/reload", post(reload_config))
+        .route("/eventsub/secret/rotate", post(rotate_eventsub_secret))
+}
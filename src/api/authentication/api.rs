@@ -2,10 +2,17 @@ use super::schema::{get_user_info, UserInfo};
 use crate::{
     api::error::ApiError,
     command_handler::CommandHandler,
-    database::models::{User, WebSession},
+    database::models::{ApiToken, ApiTokenScope, User, UserIdentity, WebSession},
+    platform::ChannelIdentifier,
+};
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
 };
-use axum::{extract::State, Json};
 use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::str::FromStr;
 
 pub async fn get_session(web_session: WebSession) -> Json<WebSession> {
     Json(web_session)
@@ -16,29 +23,225 @@ pub async fn get_user(cmd: State<CommandHandler>, user: User) -> Result<Json<Use
     Ok(Json(user_info))
 }
 
-pub async fn logout(cmd: State<CommandHandler>, web_session: WebSession) {
+pub async fn logout(cmd: State<CommandHandler>, web_session: WebSession) -> Result<(), ApiError> {
     cmd.db
-        .remove_web_session(&web_session.session_id)
-        .expect("DB Error");
+        .run(move |db| db.remove_web_session(&web_session.session_id))
+        .await?;
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct WebSessionInfo {
+    #[serde(flatten)]
+    pub session: WebSession,
+    /// Whether this is the session that made the request
+    pub current: bool,
+}
+
+pub async fn get_web_sessions(
+    web_session: WebSession,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Vec<WebSessionInfo>>, ApiError> {
+    let user_id = web_session.user_id;
+    let sessions = cmd
+        .db
+        .run(move |db| db.get_user_web_sessions(user_id))
+        .await?
+        .into_iter()
+        .map(|session| WebSessionInfo {
+            current: session.session_id == web_session.session_id,
+            session,
+        })
+        .collect();
+
+    Ok(Json(sessions))
+}
+
+/// Removes all sessions of the user, including the current one
+pub async fn logout_everywhere(
+    web_session: WebSession,
+    cmd: State<CommandHandler>,
+) -> Result<(), ApiError> {
+    cmd.db
+        .run(move |db| db.remove_user_web_sessions(web_session.user_id))
+        .await?;
+
+    Ok(())
 }
 
 pub async fn set_lastfm_name(
     web_session: WebSession,
     cmd: State<CommandHandler>,
     name: String,
-) -> StatusCode {
+) -> Result<StatusCode, ApiError> {
     cmd.db
-        .set_lastfm_name(web_session.user_id, &name)
-        .expect("DB Error");
+        .run(move |db| db.set_lastfm_name(web_session.user_id, &name))
+        .await?;
 
-    StatusCode::ACCEPTED
+    Ok(StatusCode::ACCEPTED)
 }
 
-pub async fn disconnect_spotify(session: WebSession, cmd: State<CommandHandler>) {
+pub async fn disconnect_spotify(
+    session: WebSession,
+    cmd: State<CommandHandler>,
+) -> Result<(), ApiError> {
     cmd.db
-        .remove_user_data(session.user_id, "spotify_access_token")
-        .expect("DB error");
+        .run(move |db| {
+            db.remove_user_data(session.user_id, "spotify_access_token")?;
+            db.remove_user_data(session.user_id, "spotify_refresh_token")
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Unlinks an account used for logging in, as long as the user has another one to log in with
+pub async fn unlink_identity(
+    user: User,
+    Path(platform): Path<String>,
+    cmd: State<CommandHandler>,
+) -> Result<(), ApiError> {
+    let identity = UserIdentity::from_str(&platform)
+        .map_err(|_| ApiError::BadRequest(format!("Unknown platform {platform}")))?;
+
+    if !user.has_identity(identity) {
+        return Err(ApiError::BadRequest(format!("{identity} is not linked")));
+    }
+
+    if !user.can_unlink(identity) {
+        return Err(ApiError::BadRequest(format!(
+            "{identity} is the only account you can log in with"
+        )));
+    }
+
+    cmd.db
+        .run(move |db| db.unlink_user_identity(&user, identity))
+        .await?;
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct NowPlayingSettings {
+    public: bool,
+}
+
+pub async fn set_now_playing_settings(
+    session: WebSession,
+    cmd: State<CommandHandler>,
+    Json(settings): Json<NowPlayingSettings>,
+) -> Result<(), ApiError> {
     cmd.db
-        .remove_user_data(session.user_id, "spotify_refresh_token")
-        .expect("DB error");
+        .run(move |db| db.set_now_playing_public(session.user_id, settings.public))
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_api_tokens(
+    session: WebSession,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Vec<ApiToken>>, ApiError> {
+    Ok(Json(
+        cmd.db
+            .run(move |db| db.get_api_tokens(session.user_id))
+            .await?,
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct CreateApiTokenPayload {
+    pub name: String,
+    pub scope: String,
+}
+
+/// The token is only shown once, the database only stores its hash
+pub async fn create_api_token(
+    session: WebSession,
+    token_scope: Option<Extension<ApiTokenScope>>,
+    cmd: State<CommandHandler>,
+    Json(payload): Json<CreateApiTokenPayload>,
+) -> Result<Json<Value>, ApiError> {
+    if token_scope.is_some() {
+        return Err(ApiError::Unauthorized(
+            "API tokens cannot be used to create other tokens".to_owned(),
+        ));
+    }
+
+    let scope = ApiTokenScope::from_str(&payload.scope)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid scope {}", payload.scope)))?;
+
+    if scope == ApiTokenScope::Admin {
+        match cmd.db.run(|db| db.get_admin_user()).await? {
+            Some(admin) if admin.id == session.user_id => (),
+            _ => return Err(ApiError::Unauthorized("Not admin user".to_owned())),
+        }
+    }
+
+    let name = payload.name.clone();
+    let token = cmd
+        .db
+        .run(move |db| db.create_api_token(session.user_id, &session.username, &name, scope))
+        .await?;
+
+    Ok(Json(json!({
+        "name": payload.name,
+        "scope": scope,
+        "token": token,
+    })))
+}
+
+pub async fn delete_api_token(
+    session: WebSession,
+    Path(id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<(), ApiError> {
+    cmd.db
+        .run(move |db| db.delete_api_token(session.user_id, id))
+        .await?;
+
+    Ok(())
+}
+
+/// Joins the bot to the Twitch channel of the current user
+pub async fn join_twitch_channel(user: User, cmd: State<CommandHandler>) -> Result<(), ApiError> {
+    let twitch_id = user
+        .twitch_id
+        .ok_or_else(|| ApiError::BadRequest("Twitch is not connected".to_owned()))?;
+
+    let login = {
+        let platform_handler = cmd.platform_handler.read().await;
+        let twitch_api = platform_handler
+            .twitch_api
+            .as_ref()
+            .ok_or_else(|| ApiError::Unconfigured("Twitch".to_owned()))?;
+
+        twitch_api
+            .helix_api
+            .get_user_by_id(&twitch_id)
+            .await
+            .map_err(|e| ApiError::upstream("Twitch", e))?
+            .login
+    };
+
+    cmd.join_twitch_channel(&login).await?;
+
+    Ok(())
+}
+
+pub async fn part_twitch_channel(user: User, cmd: State<CommandHandler>) -> Result<(), ApiError> {
+    let twitch_id = user
+        .twitch_id
+        .ok_or_else(|| ApiError::BadRequest("Twitch is not connected".to_owned()))?;
+
+    let channel = cmd
+        .db
+        .run(move |db| db.get_channel(&ChannelIdentifier::TwitchChannel((twitch_id, None))))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    cmd.part_channel(&channel).await?;
+
+    Ok(())
 }
@@ -3,26 +3,31 @@ use axum::extract::{FromRequestParts, Query, State};
 use axum::response::Redirect;
 use axum_extra::extract::cookie::{Cookie, Key, SameSite};
 use axum_extra::extract::PrivateCookieJar;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL, Engine};
 use chrono::{Duration, Utc};
 use dashmap::DashMap;
+use http::header::{AUTHORIZATION, USER_AGENT};
 use http::request::Parts;
-use http::StatusCode;
+use http::{HeaderMap, Method, StatusCode};
 use passwords::PasswordGenerator;
 use reqwest::Client;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt::Display;
 use std::sync::Arc;
-use std::{collections::HashMap, env};
+use std::time::Instant;
 use twitch_irc::login::{TokenStorage, UserAccessToken};
 
 use crate::api::error::ApiError;
 use crate::api::state::AppState;
-use crate::command_handler::twitch_api;
 use crate::command_handler::twitch_api::helix::HelixApi;
-use crate::database::models::User;
+use crate::config::Config;
+use crate::database::models::{NewUserMerge, User, UserMergeMethod};
 use crate::{
     command_handler::{discord_api::DiscordApi, spotify_api::SpotifyApi, CommandHandler},
     database::{
-        models::{UserData, WebSession},
+        models::{ApiTokenScope, UserData, WebSession},
         Database,
     },
     platform::UserIdentifier,
@@ -33,9 +38,15 @@ const TWITCH_MANAGE_SCOPES: &[&str] = &[
     "channel:read:predictions",
     "channel:read:redemptions",
     "channel:manage:redemptions",
+    "moderator:read:followers",
+    "channel:manage:broadcast",
+    "moderator:manage:shield_mode",
+    "moderator:manage:automod_settings",
 ];
 const DISCORD_SCOPES: &str = "identify";
 const SPOTIFY_SCOPES: &[&str] = &["user-read-playback-state", "user-read-recently-played"];
+const GITHUB_SCOPES: &str = "read:user";
+const GOOGLE_SCOPES: &str = "openid%20profile";
 
 const TWITCH_BOT_SCOPES: &[&str] = &[
     "chat:read",
@@ -44,42 +55,77 @@ const TWITCH_BOT_SCOPES: &[&str] = &[
     "whispers:edit",
     "channel:moderate",
     "moderator:manage:banned_users",
+    "moderator:manage:announcements",
+    "moderator:manage:shoutouts",
 ];
 
-type StateStorage = State<Arc<DashMap<String, String>>>;
+/// How long the user has to complete the authorization on the provider's side
+const OAUTH_STATE_LIFETIME_SECS: u64 = 600;
+
+type StateStorage = State<Arc<DashMap<String, OAuthState>>>;
+
+/// Kept between redirecting the user to the provider and the provider redirecting back
+pub struct OAuthState {
+    pub redirect_to: String,
+    /// PKCE code verifier, only used with providers that support it
+    pub code_verifier: Option<String>,
+    pub created_at: Instant,
+}
 
 #[derive(Deserialize)]
 pub struct Authenticateparams {
     pub redirect_to: Option<String>,
 }
 
+/// Providers redirect with either `code` or `error` set
 #[derive(Deserialize)]
 pub struct RedirectParams {
-    pub code: String,
+    pub code: Option<String>,
     pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+impl RedirectParams {
+    /// Consumes the state token and returns the authorization code along with the stored state
+    fn validate(self, state_storage: &StateStorage) -> Result<(String, OAuthState), ApiError> {
+        let state = self.state.ok_or_else(|| {
+            ApiError::AuthenticationFailed("The state parameter is missing".to_owned())
+        })?;
+        let oauth_state = consume_state(&state, state_storage)?;
+
+        if let Some(error) = self.error {
+            tracing::info!("Authorization was not granted: {error}");
+            return Err(ApiError::AuthenticationFailed(
+                "Authorization was not granted".to_owned(),
+            ));
+        }
+
+        let code = self.code.ok_or_else(|| {
+            ApiError::AuthenticationFailed("The authorization code is missing".to_owned())
+        })?;
+
+        Ok((code, oauth_state))
+    }
 }
 
 pub async fn authenticate_twitch(
+    cmd: State<CommandHandler>,
     state_storage: StateStorage,
     Query(Authenticateparams { redirect_to }): Query<Authenticateparams>,
-) -> Redirect {
+) -> Result<Redirect, ApiError> {
     tracing::info!("Authenticating with Twitch...");
 
-    let token = generate_state_token();
-
-    let client_id = twitch_api::get_client_id().expect("Twitch client ID not specified");
-
-    let redirect_uri = AuthPlatform::Twitch.construct_uri(
-        &client_id,
+    let uri = start_authentication(
+        &state_storage,
+        &cmd.config(),
+        AuthPlatform::Twitch,
         &TWITCH_AUTH_SCOPES.join(" "),
         false,
         None,
-        Some(&token),
-    );
-
-    state_storage.insert(token, redirect_to.unwrap_or_else(|| "/profile".to_string()));
+        redirect_to,
+    )?;
 
-    Redirect::to(&redirect_uri)
+    Ok(Redirect::to(&uri))
 }
 
 pub async fn admin_authenticate_twitch_bot(
@@ -87,70 +133,62 @@ pub async fn admin_authenticate_twitch_bot(
     current_session: WebSession,
     state_storage: StateStorage,
     Query(Authenticateparams { redirect_to }): Query<Authenticateparams>,
-) -> Result<Redirect, (StatusCode, &'static str)> {
-    if let Ok(Some(admin_user)) = cmd.db.get_admin_user() {
-        if admin_user.id == current_session.user_id {
-            tracing::info!("Authenticating the bot (Twitch):");
-
-            let client_id = twitch_api::get_client_id().expect("Twitch client ID not specified");
-
-            let token = generate_state_token();
+) -> Result<Redirect, ApiError> {
+    check_admin(&cmd, &current_session).await?;
 
-            let uri = AuthPlatform::Twitch.construct_uri(
-                &client_id,
-                &TWITCH_BOT_SCOPES.join("%20"),
-                true,
-                Some("bot"),
-                Some(&token),
-            );
+    tracing::info!("Authenticating the bot (Twitch):");
 
-            state_storage.insert(token, redirect_to.unwrap_or_else(|| "/profile".to_string()));
+    let uri = start_authentication(
+        &state_storage,
+        &cmd.config(),
+        AuthPlatform::Twitch,
+        &TWITCH_BOT_SCOPES.join("%20"),
+        true,
+        Some("bot"),
+        redirect_to,
+    )?;
 
-            tracing::info!("{}", uri);
+    tracing::info!("{}", uri);
 
-            Ok(Redirect::to(&uri))
-        } else {
-            Err((StatusCode::UNAUTHORIZED, "Not admin user!"))
-        }
-    } else {
-        Err((StatusCode::UNAUTHORIZED, "Admin user not configured!"))
-    }
+    Ok(Redirect::to(&uri))
 }
 
 pub async fn authenticate_twitch_manage(
+    cmd: State<CommandHandler>,
     state_storage: StateStorage,
     Query(Authenticateparams { redirect_to }): Query<Authenticateparams>,
-) -> Redirect {
-    let client_id = twitch_api::get_client_id().expect("Twitch client ID not specified");
-
-    let token = generate_state_token();
-
-    let uri = AuthPlatform::Twitch.construct_uri(
-        &client_id,
+) -> Result<Redirect, ApiError> {
+    let uri = start_authentication(
+        &state_storage,
+        &cmd.config(),
+        AuthPlatform::Twitch,
         &TWITCH_MANAGE_SCOPES.join("%20"),
         true,
         Some("manage"),
-        Some(&token),
-    );
-
-    state_storage.insert(token, redirect_to.unwrap_or_else(|| "/profile".to_string()));
+        redirect_to,
+    )?;
 
     tracing::info!("{}", uri);
 
-    Redirect::to(&uri)
+    Ok(Redirect::to(&uri))
 }
 
 pub async fn twitch_manage_redirect(
     cmd: State<CommandHandler>,
-    Query(RedirectParams { code, state: _ }): Query<RedirectParams>,
+    state_storage: StateStorage,
+    Query(params): Query<RedirectParams>,
     client: State<Client>,
     user: User,
 ) -> Result<Redirect, ApiError> {
+    let (code, oauth_state) = params.validate(&state_storage)?;
+
     let twitch_user_id = user.twitch_id.ok_or(ApiError::InvalidUser)?;
 
     let mut user_credentials = cmd.db.make_twitch_credentials(twitch_user_id);
 
-    let auth_response = trade_twitch_code(&client, &code).await?;
+    let auth_response = trade_twitch_code(&client, &cmd.config(), &code)
+        .await
+        .map_err(|e| authentication_failed("Twitch", e))?;
 
     let current = Utc::now();
 
@@ -163,118 +201,104 @@ pub async fn twitch_manage_redirect(
 
     user_credentials.update_token(&token).await?;
 
-    Ok(Redirect::to("/profile"))
+    Ok(Redirect::to(&oauth_state.redirect_to))
 }
 
 pub async fn twitch_redirect(
     cmd: State<CommandHandler>,
     client: State<Client>,
-    mut jar: PrivateCookieJar,
+    jar: PrivateCookieJar,
     current_session: Option<WebSession>,
     state_storage: StateStorage,
-    Query(RedirectParams { code, state }): Query<RedirectParams>,
-) -> Result<(PrivateCookieJar, Redirect), (StatusCode, &'static str)> {
-    let redirect_to = if let Some(state) = state {
-        consume_state(&state, &state_storage)?
-    } else {
-        "/profile".to_string()
-    };
+    headers: HeaderMap,
+    Query(params): Query<RedirectParams>,
+) -> Result<(PrivateCookieJar, Redirect), ApiError> {
+    let (code, oauth_state) = params.validate(&state_storage)?;
 
-    let auth_info = trade_twitch_code(&client, &code)
+    let auth_info = trade_twitch_code(&client, &cmd.config(), &code)
         .await
-        .expect("Failed to get tokens");
-
-    tracing::info!(
-        "User authenticated with access token {}",
-        auth_info.access_token
-    );
+        .map_err(|e| authentication_failed("Twitch", e))?;
 
     let helix_api = HelixApi::with_token(&auth_info.access_token)
         .await
-        .expect("Failed to initialize Twitch API");
+        .map_err(|e| authentication_failed("Twitch", e))?;
 
-    let twitch_user = helix_api.get_self_user().await.unwrap();
-
-    let user = cmd
-        .db
-        .get_or_create_user(&UserIdentifier::TwitchID(twitch_user.id))
-        .expect("DB error");
-
-    if let Some(web_session) = current_session {
-        let current_user = cmd
-            .db
-            .get_user_by_id(web_session.user_id)
-            .expect("DB Error")
-            .unwrap();
-
-        cmd.db.merge_users(current_user, user);
-    } else {
-        let cookie = create_user_session(&cmd.db, user.id, twitch_user.display_name);
-
-        jar = jar.add(cookie);
-    }
+    let twitch_user = helix_api
+        .get_self_user()
+        .await
+        .map_err(|e| authentication_failed("Twitch", e))?;
+
+    let jar = login_or_link_user(
+        &cmd.db,
+        jar,
+        current_session,
+        &UserIdentifier::TwitchID(twitch_user.id),
+        twitch_user.display_name,
+        &headers,
+    )
+    .await?;
 
-    Ok((jar, Redirect::to(&redirect_to)))
+    Ok((jar, Redirect::to(&oauth_state.redirect_to)))
 }
 
 pub async fn admin_twitch_bot_redirect(
     cmd: State<CommandHandler>,
     client: State<Client>,
-    Query(RedirectParams { code, state: _ }): Query<RedirectParams>,
+    state_storage: StateStorage,
+    Query(params): Query<RedirectParams>,
     current_session: WebSession,
-) -> Result<Redirect, (StatusCode, &'static str)> {
-    if let Ok(Some(admin_user)) = cmd.db.get_admin_user() {
-        if admin_user.id == current_session.user_id {
-            let auth_response = trade_twitch_code(&client, &code)
-                .await
-                .expect("Failed to get Twitch auth response");
+) -> Result<Redirect, ApiError> {
+    let (code, oauth_state) = params.validate(&state_storage)?;
 
-            let current = Utc::now();
+    check_admin(&cmd, &current_session).await?;
 
-            cmd.db
-                .set_auth("twitch_access_token", &auth_response.access_token)
-                .expect("DB error");
-            cmd.db
-                .set_auth("twitch_refresh_token", &auth_response.refresh_token)
-                .expect("DB error");
+    let auth_response = trade_twitch_code(&client, &cmd.config(), &code)
+        .await
+        .map_err(|e| authentication_failed("Twitch", e))?;
 
-            cmd.db
-                .set_auth("twitch_created_at", &current.to_rfc3339())
-                .expect("DB error");
+    let current = Utc::now();
 
-            let expires_at = current + Duration::seconds(auth_response.expires_in);
+    let expires_at = current + Duration::seconds(auth_response.expires_in);
 
-            cmd.db
-                .set_auth("twitch_expires_at", &expires_at.to_rfc3339())
-                .expect("DB error");
+    cmd.db
+        .run(move |db| {
+            db.set_auth("twitch_access_token", &auth_response.access_token)?;
+            db.set_auth("twitch_refresh_token", &auth_response.refresh_token)?;
+            db.set_auth("twitch_created_at", &current.to_rfc3339())?;
+            db.set_auth("twitch_expires_at", &expires_at.to_rfc3339())
+        })
+        .await?;
 
-            tracing::info!("Successfully authenticated the bot and saved the token!");
+    tracing::info!("Successfully authenticated the bot and saved the token!");
 
-            Ok(Redirect::to("/profile"))
-        } else {
-            Err((StatusCode::UNAUTHORIZED, "Not admin user!"))
-        }
-    } else {
-        Err((StatusCode::UNAUTHORIZED, "Admin user not configured!"))
+    Ok(Redirect::to(&oauth_state.redirect_to))
+}
+
+async fn check_admin(cmd: &CommandHandler, session: &WebSession) -> Result<(), ApiError> {
+    match cmd.db.run(|db| db.get_admin_user()).await? {
+        Some(admin_user) if admin_user.id == session.user_id => Ok(()),
+        Some(_) => Err(ApiError::Unauthorized("Not admin user!".to_owned())),
+        None => Err(ApiError::Unauthorized(
+            "Admin user not configured!".to_owned(),
+        )),
     }
 }
 
 async fn trade_twitch_code(
     client: &Client,
+    config: &Config,
     code: &str,
 ) -> Result<TwitchAuthenticationResponse, anyhow::Error> {
-    let client_id = twitch_api::get_client_id().expect("Twitch client ID not specified");
-    let client_secret =
-        twitch_api::get_client_secret().expect("Twitch client secret not specified");
+    let (client_id, client_secret) = config
+        .twitch
+        .credentials()
+        .ok_or_else(|| anyhow::anyhow!("Twitch client credentials not specified"))?;
 
-    let redirect_uri = format!(
-        "{}/authenticate/twitch/redirect",
-        env::var("BASE_URL").expect("BASE_URL missing")
-    );
+    let redirect_uri = AuthPlatform::Twitch.redirect_uri(&config.base_url, None);
 
     let params = [
-        ("client_id", client_id.as_str()),
-        ("client_secret", client_secret.as_str()),
+        ("client_id", client_id),
+        ("client_secret", client_secret),
         ("code", code),
         ("grant_type", "authorization_code"),
         ("redirect_uri", &redirect_uri),
@@ -284,192 +308,479 @@ async fn trade_twitch_code(
         .post("https://id.twitch.tv/oauth2/token")
         .form(&params)
         .send()
-        .await
-        .unwrap();
+        .await?;
 
     tracing::info!("POST {}: {}", &response.url(), &response.status());
 
-    if response.status().is_client_error() {
-        panic!("Received auth error: {}", response.text().await.unwrap());
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Received auth error: {}",
+            response.text().await?
+        ));
     }
 
     Ok(response.json::<TwitchAuthenticationResponse>().await?)
 }
 
 pub async fn authenticate_discord(
+    cmd: State<CommandHandler>,
     state_storage: StateStorage,
     Query(Authenticateparams { redirect_to }): Query<Authenticateparams>,
-) -> Redirect {
+) -> Result<Redirect, ApiError> {
     tracing::info!("Authenticating with Discord...");
 
-    let client_id = env::var("DISCORD_CLIENT_ID").expect("DISCORD_CLIENT_ID missing");
-
-    let token = generate_state_token();
-
-    let redirect_uri =
-        AuthPlatform::Discord.construct_uri(&client_id, DISCORD_SCOPES, false, None, Some(&token));
-
-    state_storage.insert(token, redirect_to.unwrap_or_else(|| "/profile".to_string()));
+    let uri = start_authentication(
+        &state_storage,
+        &cmd.config(),
+        AuthPlatform::Discord,
+        DISCORD_SCOPES,
+        false,
+        None,
+        redirect_to,
+    )?;
 
-    Redirect::to(&redirect_uri)
+    Ok(Redirect::to(&uri))
 }
 
 pub async fn discord_redirect(
     client: State<Client>,
     cmd: State<CommandHandler>,
-    mut jar: PrivateCookieJar,
+    jar: PrivateCookieJar,
     current_session: Option<WebSession>,
     state_storage: StateStorage,
-    Query(RedirectParams { code, state }): Query<RedirectParams>,
-) -> Result<(PrivateCookieJar, Redirect), (StatusCode, &'static str)> {
-    let redirect_to = if let Some(state) = state {
-        consume_state(&state, &state_storage)?
-    } else {
-        "/profile".to_string()
-    };
+    headers: HeaderMap,
+    Query(params): Query<RedirectParams>,
+) -> Result<(PrivateCookieJar, Redirect), ApiError> {
+    let (code, oauth_state) = params.validate(&state_storage)?;
 
-    let db = &cmd.db;
+    let platform = AuthPlatform::Discord;
+    let config = cmd.config();
+    let (client_id, client_secret) = platform.credentials(&config)?;
 
     let mut payload = HashMap::new();
 
-    payload.insert(
-        "client_id",
-        env::var("DISCORD_CLIENT_ID").expect("DISCORD_CLIENT_ID missing"),
-    );
-    payload.insert(
-        "client_secret",
-        env::var("DISCORD_CLIENT_SECRET").expect("DISCORD_CLIENT_SECRET missing"),
-    );
+    payload.insert("client_id", client_id.to_owned());
+    payload.insert("client_secret", client_secret.to_owned());
     payload.insert("grant_type", "authorization_code".to_owned());
     payload.insert("code", code);
     payload.insert(
         "redirect_uri",
-        format!(
-            "{}/authenticate/discord/redirect",
-            env::var("BASE_URL").expect("BASE_URL missing")
-        ),
+        platform.redirect_uri(&config.base_url, None),
     );
 
-    let response = client
+    let auth_info = client
         .post("https://discord.com/api/oauth2/token")
         .header("Content-Type", "application/x-www-form-urlencoded")
         .form(&payload)
         .send()
         .await
-        .expect("Discord API error");
-
-    tracing::info!("POST {}: {}", response.url(), response.status());
-
-    let auth_info = response
+        .and_then(|response| {
+            tracing::info!("POST {}: {}", response.url(), response.status());
+            response.error_for_status()
+        })
+        .map_err(|e| authentication_failed("Discord", e))?
         .json::<DiscordAuthenticationResponse>()
         .await
-        .expect("Discord JSON error");
-
-    tracing::info!(
-        "Authenticated Discord user with access_token {}",
-        auth_info.access_token
-    );
+        .map_err(|e| authentication_failed("Discord", e))?;
 
     let discord_api = DiscordApi::new(format!("Bearer {}", auth_info.access_token));
 
     let discord_user = discord_api
         .get_self_user()
         .await
-        .expect("Discord API Error");
+        .map_err(|e| authentication_failed("Discord", e))?;
+
+    let jar = login_or_link_user(
+        &cmd.db,
+        jar,
+        current_session,
+        &UserIdentifier::DiscordID(discord_user.id.to_string()),
+        discord_user.name,
+        &headers,
+    )
+    .await?;
+
+    Ok((jar, Redirect::to(&oauth_state.redirect_to)))
+}
 
-    let user = db
-        .get_or_create_user(&UserIdentifier::DiscordID(discord_user.id.to_string()))
-        .expect("DB Error");
+pub async fn authenticate_github(
+    cmd: State<CommandHandler>,
+    state_storage: StateStorage,
+    Query(Authenticateparams { redirect_to }): Query<Authenticateparams>,
+) -> Result<Redirect, ApiError> {
+    tracing::info!("Authenticating with GitHub...");
 
-    if let Some(web_session) = current_session {
-        let current_user = cmd
-            .db
-            .get_user_by_id(web_session.user_id)
-            .expect("DB Error")
-            .unwrap();
+    let uri = start_authentication(
+        &state_storage,
+        &cmd.config(),
+        AuthPlatform::Github,
+        GITHUB_SCOPES,
+        false,
+        None,
+        redirect_to,
+    )?;
 
-        cmd.db.merge_users(current_user, user);
-    } else {
-        let cookie = create_user_session(db, user.id, discord_user.name);
+    Ok(Redirect::to(&uri))
+}
 
-        jar = jar.add(cookie);
-    }
+pub async fn github_redirect(
+    client: State<Client>,
+    cmd: State<CommandHandler>,
+    jar: PrivateCookieJar,
+    current_session: Option<WebSession>,
+    state_storage: StateStorage,
+    headers: HeaderMap,
+    Query(params): Query<RedirectParams>,
+) -> Result<(PrivateCookieJar, Redirect), ApiError> {
+    let (code, oauth_state) = params.validate(&state_storage)?;
 
-    Ok((jar, Redirect::to(&redirect_to)))
+    let config = cmd.config();
+    let (client_id, client_secret) = AuthPlatform::Github.credentials(&config)?;
+
+    let params = [
+        ("client_id", client_id),
+        ("client_secret", client_secret),
+        ("code", code.as_str()),
+    ];
+
+    // GitHub responds with 200 and an `error` field on failure, so the access token is optional
+    let auth_info = client
+        .post("https://github.com/login/oauth/access_token")
+        .header("Accept", "application/json")
+        .form(&params)
+        .send()
+        .await
+        .map_err(|e| authentication_failed("GitHub", e))?
+        .json::<GithubAuthenticationResponse>()
+        .await
+        .map_err(|e| authentication_failed("GitHub", e))?;
+
+    let access_token = match auth_info {
+        GithubAuthenticationResponse::Success { access_token } => access_token,
+        GithubAuthenticationResponse::Error { error } => {
+            return Err(authentication_failed("GitHub", error))
+        }
+    };
+
+    let github_user = client
+        .get("https://api.github.com/user")
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", "foobot2")
+        .bearer_auth(&access_token)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| authentication_failed("GitHub", e))?
+        .json::<GithubUser>()
+        .await
+        .map_err(|e| authentication_failed("GitHub", e))?;
+
+    let jar = login_or_link_user(
+        &cmd.db,
+        jar,
+        current_session,
+        &UserIdentifier::GithubId(github_user.id.to_string()),
+        github_user.login,
+        &headers,
+    )
+    .await?;
+
+    Ok((jar, Redirect::to(&oauth_state.redirect_to)))
 }
 
-pub async fn authenticate_spotify(_session: WebSession) -> Redirect {
-    let client_id = env::var("SPOTIFY_CLIENT_ID").expect("SPOTIFY_CLIENT_ID missing");
+pub async fn authenticate_google(
+    cmd: State<CommandHandler>,
+    state_storage: StateStorage,
+    Query(Authenticateparams { redirect_to }): Query<Authenticateparams>,
+) -> Result<Redirect, ApiError> {
+    tracing::info!("Authenticating with Google...");
 
-    Redirect::to(&AuthPlatform::Spotify.construct_uri(
-        &client_id,
-        &SPOTIFY_SCOPES.join("%20"),
+    let uri = start_authentication(
+        &state_storage,
+        &cmd.config(),
+        AuthPlatform::Google,
+        GOOGLE_SCOPES,
         false,
         None,
-        None,
-    ))
+        redirect_to,
+    )?;
+
+    Ok(Redirect::to(&uri))
 }
 
-pub async fn spotify_redirect(
-    Query(RedirectParams { code, state: _ }): Query<RedirectParams>,
+pub async fn google_redirect(
+    client: State<Client>,
     cmd: State<CommandHandler>,
-    session: WebSession,
-) -> Redirect {
-    let db = &cmd.db;
+    jar: PrivateCookieJar,
+    current_session: Option<WebSession>,
+    state_storage: StateStorage,
+    headers: HeaderMap,
+    Query(params): Query<RedirectParams>,
+) -> Result<(PrivateCookieJar, Redirect), ApiError> {
+    let (code, oauth_state) = params.validate(&state_storage)?;
+
+    let platform = AuthPlatform::Google;
+    let config = cmd.config();
+    let (client_id, client_secret) = platform.credentials(&config)?;
 
-    let client_id = env::var("SPOTIFY_CLIENT_ID").expect("SPOTIFY_CLIENT_ID missing");
-    let client_secret = env::var("SPOTIFY_CLIENT_SECRET").expect("SPOTIFY_CLIENT_SECRET missing");
+    let mut payload = HashMap::new();
 
-    let redirect_uri = format!(
-        "{}/authenticate/spotify/redirect",
-        env::var("BASE_URL").expect("BASE_URL missing")
+    payload.insert("client_id", client_id.to_owned());
+    payload.insert("client_secret", client_secret.to_owned());
+    payload.insert("grant_type", "authorization_code".to_owned());
+    payload.insert("code", code);
+    payload.insert(
+        "redirect_uri",
+        platform.redirect_uri(&config.base_url, None),
     );
+    if let Some(code_verifier) = oauth_state.code_verifier {
+        payload.insert("code_verifier", code_verifier);
+    }
 
-    let auth = SpotifyApi::get_tokens(&code, &client_id, &client_secret, &redirect_uri)
+    let auth_info = client
+        .post("https://oauth2.googleapis.com/token")
+        .form(&payload)
+        .send()
         .await
-        .expect("Spotify API Error");
-
-    db.set_user_data(
-        &UserData {
-            name: "spotify_access_token".to_string(),
-            value: auth.access_token,
-            public: false,
-            user_id: session.user_id,
-        },
-        true,
+        .and_then(|response| {
+            tracing::info!("POST {}: {}", response.url(), response.status());
+            response.error_for_status()
+        })
+        .map_err(|e| authentication_failed("Google", e))?
+        .json::<OAuthAuthenticationResponse>()
+        .await
+        .map_err(|e| authentication_failed("Google", e))?;
+
+    let google_user = client
+        .get("https://openidconnect.googleapis.com/v1/userinfo")
+        .bearer_auth(&auth_info.access_token)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| authentication_failed("Google", e))?
+        .json::<GoogleUser>()
+        .await
+        .map_err(|e| authentication_failed("Google", e))?;
+
+    let jar = login_or_link_user(
+        &cmd.db,
+        jar,
+        current_session,
+        &UserIdentifier::GoogleId(google_user.sub),
+        google_user.name,
+        &headers,
     )
-    .expect("DB Error");
-
-    db.set_user_data(
-        &UserData {
-            name: "spotify_refresh_token".to_string(),
-            value: auth.refresh_token,
-            public: false,
-            user_id: session.user_id,
-        },
-        true,
+    .await?;
+
+    Ok((jar, Redirect::to(&oauth_state.redirect_to)))
+}
+
+/// Links the account to the current user if there is a session, otherwise logs in with it
+async fn login_or_link_user(
+    db: &Database,
+    jar: PrivateCookieJar,
+    current_session: Option<WebSession>,
+    user_identifier: &UserIdentifier,
+    display_name: String,
+    headers: &HeaderMap,
+) -> Result<PrivateCookieJar, ApiError> {
+    let user_identifier = user_identifier.clone();
+    let user_agent = headers
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+
+    let session_cookie = db
+        .run(move |db| {
+            let user = db.get_or_create_user(&user_identifier)?;
+
+            match current_session {
+                Some(web_session) => {
+                    let current_user = db
+                        .get_user_by_id(web_session.user_id)?
+                        .ok_or(ApiError::InvalidUser)?;
+
+                    let merged_user_id = user.id;
+                    let user = db.merge_users(current_user, user)?;
+
+                    db.add_user_merge(NewUserMerge {
+                        user_id: user.id,
+                        merged_user_id,
+                        identity: &user_identifier.to_string(),
+                        method: &UserMergeMethod::Oauth.to_string(),
+                    })?;
+
+                    Ok::<_, ApiError>(None)
+                }
+                None => Ok(Some(create_user_session(
+                    db,
+                    user.id,
+                    display_name,
+                    user_agent,
+                )?)),
+            }
+        })
+        .await?;
+
+    Ok(match session_cookie {
+        Some(cookie) => jar.add(cookie),
+        None => jar,
+    })
+}
+
+pub async fn authenticate_spotify(
+    _session: WebSession,
+    cmd: State<CommandHandler>,
+    state_storage: StateStorage,
+    Query(Authenticateparams { redirect_to }): Query<Authenticateparams>,
+) -> Result<Redirect, ApiError> {
+    let uri = start_authentication(
+        &state_storage,
+        &cmd.config(),
+        AuthPlatform::Spotify,
+        &SPOTIFY_SCOPES.join("%20"),
+        false,
+        None,
+        redirect_to,
+    )?;
+
+    Ok(Redirect::to(&uri))
+}
+
+pub async fn spotify_redirect(
+    state_storage: StateStorage,
+    Query(params): Query<RedirectParams>,
+    cmd: State<CommandHandler>,
+    session: WebSession,
+) -> Result<Redirect, ApiError> {
+    let (code, oauth_state) = params.validate(&state_storage)?;
+
+    let db = &cmd.db;
+
+    let platform = AuthPlatform::Spotify;
+    let config = cmd.config();
+    let (client_id, client_secret) = platform.credentials(&config)?;
+    let redirect_uri = platform.redirect_uri(&config.base_url, None);
+
+    let auth = SpotifyApi::get_tokens(
+        &code,
+        client_id,
+        client_secret,
+        &redirect_uri,
+        oauth_state.code_verifier.as_deref(),
     )
-    .expect("DB Error");
+    .await
+    .map_err(|e| authentication_failed("Spotify", e))?;
+
+    let user_id = session.user_id;
+    db.run(move |db| {
+        db.set_user_data(
+            &UserData {
+                name: "spotify_access_token".to_string(),
+                value: auth.access_token,
+                public: false,
+                user_id,
+            },
+            true,
+        )?;
+
+        db.set_user_data(
+            &UserData {
+                name: "spotify_refresh_token".to_string(),
+                value: auth.refresh_token,
+                public: false,
+                user_id,
+            },
+            true,
+        )
+    })
+    .await?;
 
-    Redirect::to("/profile")
+    Ok(Redirect::to(&oauth_state.redirect_to))
 }
 
-fn create_user_session(db: &Database, user_id: u64, display_name: String) -> Cookie<'static> {
-    let session_id = db
-        .create_web_session(user_id, display_name)
-        .expect("DB error");
+fn create_user_session(
+    db: &Database,
+    user_id: u64,
+    display_name: String,
+    user_agent: Option<String>,
+) -> Result<Cookie<'static>, ApiError> {
+    let session_id = db.create_web_session(user_id, display_name, user_agent)?;
 
-    Cookie::build("session_id", session_id)
+    Ok(Cookie::build("session_id", session_id)
         .secure(true)
         .path("/")
         .http_only(false)
         .same_site(SameSite::Lax)
-        .finish()
+        .finish())
+}
+
+/// Stores a new state token (and a PKCE verifier if the platform supports it)
+/// and returns the uri to send the user to
+fn start_authentication(
+    state_storage: &StateStorage,
+    config: &Config,
+    platform: AuthPlatform,
+    scopes: &str,
+    force_verify: bool,
+    suffix: Option<&str>,
+    redirect_to: Option<String>,
+) -> Result<String, ApiError> {
+    let (client_id, _) = platform.credentials(config)?;
+    let redirect_uri = platform.redirect_uri(&config.base_url, suffix);
+
+    let token = generate_random_string(16);
+
+    let code_verifier = platform.supports_pkce().then(|| generate_random_string(64));
+    let code_challenge = code_verifier
+        .as_deref()
+        .map(|verifier| BASE64_URL.encode(Sha256::digest(verifier.as_bytes())));
+
+    let uri = platform.construct_uri(
+        client_id,
+        &redirect_uri,
+        scopes,
+        force_verify,
+        &token,
+        code_challenge.as_deref(),
+    );
+
+    state_storage.insert(
+        token,
+        OAuthState {
+            redirect_to: redirect_to
+                .filter(|path| is_local_path(path))
+                .unwrap_or_else(|| "/profile".to_string()),
+            code_verifier,
+            created_at: Instant::now(),
+        },
+    );
+
+    Ok(uri)
+}
+
+/// Only local paths are allowed so that the login can't be used as an open redirect.
+/// Browsers treat `\` like `/`, so `/\evil.com` leads to another host as well
+fn is_local_path(path: &str) -> bool {
+    path.starts_with('/') && !path.starts_with("//") && !path.starts_with("/\\")
 }
 
-fn generate_state_token() -> String {
+/// Removes the states of login attempts that were abandoned on the provider's side
+pub fn start_state_cleanup(state_storage: Arc<DashMap<String, OAuthState>>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(OAUTH_STATE_LIFETIME_SECS)).await;
+
+            state_storage.retain(|_, oauth_state| {
+                oauth_state.created_at.elapsed().as_secs() <= OAUTH_STATE_LIFETIME_SECS
+            });
+        }
+    });
+}
+
+fn generate_random_string(length: usize) -> String {
     PasswordGenerator {
-        length: 16,
+        length,
         numbers: true,
         lowercase_letters: true,
         uppercase_letters: false,
@@ -482,18 +793,28 @@ fn generate_state_token() -> String {
     .expect("Failed to generate token")
 }
 
-// Returns the associated redirect uri
-fn consume_state(
-    state: &str,
-    state_storage: &StateStorage,
-) -> Result<String, (StatusCode, &'static str)> {
-    if let Some((_, redirect_to)) = state_storage.remove(state) {
-        Ok(redirect_to)
-    } else {
-        Err((StatusCode::UNAUTHORIZED, "State token not found"))
+fn consume_state(state: &str, state_storage: &StateStorage) -> Result<OAuthState, ApiError> {
+    match state_storage.remove(state) {
+        Some((_, oauth_state))
+            if oauth_state.created_at.elapsed().as_secs() <= OAUTH_STATE_LIFETIME_SECS =>
+        {
+            Ok(oauth_state)
+        }
+        Some(_) => Err(ApiError::AuthenticationFailed(
+            "The login attempt has expired, please try again".to_owned(),
+        )),
+        None => Err(ApiError::AuthenticationFailed(
+            "State token not found".to_owned(),
+        )),
     }
 }
 
+/// The details are only logged, the user is shown a generic error
+fn authentication_failed(provider: &str, error: impl Display) -> ApiError {
+    tracing::warn!("Authentication with {provider} failed: {error}");
+    ApiError::AuthenticationFailed(format!("Could not authenticate with {provider}"))
+}
+
 #[derive(serde::Deserialize)]
 struct TwitchAuthenticationResponse {
     pub access_token: String,
@@ -510,11 +831,38 @@ pub struct DiscordAuthenticationResponse {
     pub scope: String,
 }
 
+/// Token response of providers that don't need any of the other fields
+#[derive(serde::Deserialize)]
+struct OAuthAuthenticationResponse {
+    pub access_token: String,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum GithubAuthenticationResponse {
+    Success { access_token: String },
+    Error { error: String },
+}
+
+#[derive(serde::Deserialize)]
+struct GithubUser {
+    pub id: u64,
+    pub login: String,
+}
+
+#[derive(serde::Deserialize)]
+struct GoogleUser {
+    pub sub: String,
+    pub name: String,
+}
+
 #[derive(PartialEq, Eq)]
 pub enum AuthPlatform {
     Twitch,
     Discord,
     Spotify,
+    Github,
+    Google,
 }
 
 impl AuthPlatform {
@@ -523,6 +871,8 @@ impl AuthPlatform {
             Self::Spotify => "spotify",
             Self::Twitch => "twitch",
             Self::Discord => "discord",
+            Self::Github => "github",
+            Self::Google => "google",
         }
     }
 
@@ -531,39 +881,71 @@ impl AuthPlatform {
             Self::Spotify => "https://accounts.spotify.com/authorize",
             Self::Twitch => "https://id.twitch.tv/oauth2/authorize",
             Self::Discord => "https://discord.com/api/oauth2/authorize",
+            Self::Github => "https://github.com/login/oauth/authorize",
+            Self::Google => "https://accounts.google.com/o/oauth2/v2/auth",
         }
     }
 
-    pub fn construct_uri(
-        &self,
-        client_id: &str,
-        scopes: &str,
-        force_verify: bool,
-        suffix: Option<&str>,
-        state: Option<&str>,
-    ) -> String {
-        let mut redirect_uri = format!(
-            "{}/authenticate/{}/redirect",
-            env::var("BASE_URL").expect("BASE_URL missing"),
-            self.get_name(),
-        );
+    fn supports_pkce(&self) -> bool {
+        matches!(self, Self::Spotify | Self::Google)
+    }
+
+    /// The client id and secret of the bot's application on the platform
+    fn credentials<'a>(&self, config: &'a Config) -> Result<(&'a str, &'a str), ApiError> {
+        let credentials = match self {
+            Self::Twitch => config.twitch.credentials(),
+            Self::Spotify => config.spotify.credentials(),
+            Self::Github => config.github.credentials(),
+            Self::Google => config.google.credentials(),
+            Self::Discord => config
+                .discord
+                .client_id
+                .as_deref()
+                .zip(config.discord.client_secret.as_deref()),
+        };
+
+        credentials.ok_or_else(|| ApiError::Unconfigured(self.get_name().to_owned()))
+    }
+
+    pub fn redirect_uri(&self, base_url: &str, suffix: Option<&str>) -> String {
+        let mut redirect_uri = format!("{base_url}/authenticate/{}/redirect", self.get_name());
 
         if let Some(suffix) = suffix {
             redirect_uri.push('/');
             redirect_uri.push_str(suffix);
         }
 
+        redirect_uri
+    }
+
+    pub fn construct_uri(
+        &self,
+        client_id: &str,
+        redirect_uri: &str,
+        scopes: &str,
+        force_verify: bool,
+        state: &str,
+        code_challenge: Option<&str>,
+    ) -> String {
         tracing::info!("Using redirect_uri {}", redirect_uri);
 
-        format!(
+        let mut uri = format!(
             "{}?client_id={}&redirect_uri={}&response_type=code&scope={}&force_verify={}&state={}",
             self.get_auth_uri(),
             client_id,
             redirect_uri,
             scopes,
             force_verify,
-            state.unwrap_or_default(),
-        )
+            state,
+        );
+
+        if let Some(code_challenge) = code_challenge {
+            uri.push_str(&format!(
+                "&code_challenge={code_challenge}&code_challenge_method=S256"
+            ));
+        }
+
+        uri
     }
 }
 
@@ -580,20 +962,62 @@ impl FromRequestParts<AppState> for WebSession {
             .unwrap();
 
         match cookie_jar.get("session_id") {
-            Some(session_id) => match state
-                .cmd
-                .db
-                .get_web_session(session_id.value())
-                .expect("DB Error")
-            {
-                Some(web_session) => Ok(web_session),
-                None => Err(StatusCode::UNAUTHORIZED),
-            },
-            None => Err(StatusCode::UNAUTHORIZED),
+            Some(session_id) => {
+                let session_id = session_id.value().to_owned();
+
+                match state
+                    .cmd
+                    .db
+                    .run(move |db| db.get_web_session(&session_id))
+                    .await
+                    .map_err(session_error)?
+                {
+                    Some(web_session) => Ok(web_session),
+                    None => Err(StatusCode::UNAUTHORIZED),
+                }
+            }
+            None => get_token_session(parts, state).await,
         }
     }
 }
 
+/// Authenticates the request with an API token from the `Authorization: Bearer` header.
+/// The token scope is stored in the request extensions so that handlers can check it
+async fn get_token_session(parts: &mut Parts, state: &AppState) -> Result<WebSession, StatusCode> {
+    let token = parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?
+        .to_owned();
+
+    let api_token = state
+        .cmd
+        .db
+        .run(move |db| db.get_api_token(&token))
+        .await
+        .map_err(session_error)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    if api_token.scope == ApiTokenScope::Read && parts.method != Method::GET {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    parts.extensions.insert(api_token.scope);
+
+    let now = Utc::now().naive_utc();
+
+    Ok(WebSession {
+        session_id: String::new(),
+        user_id: api_token.user_id,
+        username: api_token.username,
+        created_at: now,
+        expires_at: now,
+        user_agent: None,
+    })
+}
+
 #[async_trait]
 impl FromRequestParts<AppState> for User {
     type Rejection = StatusCode;
@@ -603,13 +1027,34 @@ impl FromRequestParts<AppState> for User {
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
         let session = WebSession::from_request_parts(parts, state).await?;
+        let user_id = session.user_id;
 
-        // TODO handle this
-        Ok(state
+        state
             .cmd
             .db
-            .get_user_by_id(session.user_id)
-            .expect("DB error")
-            .expect("Invalid web session"))
+            .run(move |db| db.get_user_by_id(user_id))
+            .await
+            .map_err(session_error)?
+            .ok_or(StatusCode::UNAUTHORIZED)
+    }
+}
+
+fn session_error(error: impl Display) -> StatusCode {
+    tracing::error!("Failed to look up the session: {error}");
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_local_path;
+
+    #[test]
+    fn only_allows_local_redirects() {
+        assert!(is_local_path("/profile"));
+        assert!(is_local_path("/channels/1"));
+        assert!(!is_local_path("https://evil.com"));
+        assert!(!is_local_path("//evil.com"));
+        assert!(!is_local_path("/\\evil.com"));
+        assert!(!is_local_path("evil.com"));
     }
 }
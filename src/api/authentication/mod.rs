@@ -4,7 +4,7 @@ mod schema;
 
 use super::state::AppState;
 use axum::{
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Router,
 };
 
@@ -18,6 +18,10 @@ pub fn create_authentication_router() -> Router<AppState> {
         .route("/twitch/redirect/manage", get(flow::twitch_manage_redirect))
         .route("/discord", get(flow::authenticate_discord))
         .route("/discord/redirect", get(flow::discord_redirect))
+        .route("/github", get(flow::authenticate_github))
+        .route("/github/redirect", get(flow::github_redirect))
+        .route("/google", get(flow::authenticate_google))
+        .route("/google/redirect", get(flow::google_redirect))
         .route("/spotify", get(flow::authenticate_spotify))
         .route("/spotify/redirect", get(flow::spotify_redirect))
 }
@@ -27,6 +31,19 @@ pub fn create_session_router() -> Router<AppState> {
         .route("/", get(api::get_session))
         .route("/user", get(api::get_user))
         .route("/logout", post(api::logout))
+        .route(
+            "/sessions",
+            get(api::get_web_sessions).delete(api::logout_everywhere),
+        )
         .route("/lastfm", post(api::set_lastfm_name))
         .route("/spotify", delete(api::disconnect_spotify))
+        .route("/identities/:platform", delete(api::unlink_identity))
+        .route("/nowplaying", put(api::set_now_playing_settings))
+        .route("/twitch/join", post(api::join_twitch_channel))
+        .route("/twitch/part", post(api::part_twitch_channel))
+        .route(
+            "/tokens",
+            get(api::get_api_tokens).post(api::create_api_token),
+        )
+        .route("/tokens/:id", delete(api::delete_api_token))
 }
@@ -3,7 +3,11 @@ use serde::Serialize;
 use crate::{
     api::error::ApiError,
     command_handler::{twitch_api, CommandHandler},
-    database::models::User,
+    database::{
+        models::{User, UserName},
+        DatabaseError,
+    },
+    platform::ChannelIdentifier,
 };
 
 #[derive(Serialize)]
@@ -11,18 +15,43 @@ pub struct UserInfo {
     #[serde(flatten)]
     pub base_user: User,
     pub twitch_user: Option<twitch_api::model::User>,
+    pub twitch_channel_joined: bool,
     pub discord_user: Option<twilight_model::user::User>,
     pub admin: bool,
     pub lastfm_name: Option<String>,
     pub spotify_connected: bool,
+    pub now_playing_public: bool,
+    /// Names the user has been seen with, most recent first
+    pub names: Vec<UserName>,
 }
 
 pub async fn get_user_info(cmd: &CommandHandler, user: User) -> Result<UserInfo, ApiError> {
-    let admin = if let Some(admin) = cmd.db.get_admin_user()? {
-        admin.id == user.id
-    } else {
-        false
-    };
+    let user_id = user.id;
+    let twitch_id = user.twitch_id.clone();
+    let (admin, twitch_channel_joined, lastfm_name, spotify_connected, now_playing_public, names) =
+        cmd.db
+            .run(move |db| {
+                let admin = db
+                    .get_admin_user()?
+                    .map_or(false, |admin| admin.id == user_id);
+
+                let twitch_channel_joined = match twitch_id {
+                    Some(twitch_id) => db
+                        .get_channel(&ChannelIdentifier::TwitchChannel((twitch_id, None)))?
+                        .map_or(false, |channel| channel.joined),
+                    None => false,
+                };
+
+                Ok::<_, DatabaseError>((
+                    admin,
+                    twitch_channel_joined,
+                    db.get_lastfm_name(user_id)?,
+                    db.get_spotify_access_token(user_id)?.is_some(),
+                    db.get_now_playing_public(user_id)?,
+                    db.get_user_names(user_id)?,
+                ))
+            })
+            .await?;
 
     let platform_handler = cmd.platform_handler.read().await;
 
@@ -45,21 +74,29 @@ pub async fn get_user_info(cmd: &CommandHandler, user: User) -> Result<UserInfo,
 
     let discord_user = match (&user.discord_id, platform_handler.discord_api.as_ref()) {
         (Some(discord_id), Some(discord_api)) => {
-            Some(discord_api.get_user(discord_id.parse().unwrap()).await?)
+            let discord_id = discord_id
+                .parse()
+                .map_err(|_| ApiError::GenericError(format!("Invalid Discord id {discord_id}")))?;
+
+            Some(
+                discord_api
+                    .get_user(discord_id)
+                    .await
+                    .map_err(|e| ApiError::upstream("Discord", e))?,
+            )
         }
         _ => None,
     };
 
-    let lastfm_name = cmd.db.get_lastfm_name(user.id)?;
-
-    let spotify_connected = cmd.db.get_spotify_access_token(user.id)?.is_some();
-
     Ok(UserInfo {
         base_user: user,
         twitch_user,
+        twitch_channel_joined,
         discord_user,
         admin,
         lastfm_name,
         spotify_connected,
+        now_playing_public,
+        names,
     })
 }
@@ -0,0 +1,72 @@
+use super::{error::ApiError, Result};
+use crate::{
+    command_handler::{outgoing_webhooks::ChannelEvent, CommandHandler},
+    database::models::WebSession,
+    platform::Permissions,
+};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        Path, State, WebSocketUpgrade,
+    },
+    response::Response,
+};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::{debug, warn};
+
+/// Streams the channel's bot events as JSON text messages.
+/// The format is the same as the one used by outgoing webhooks
+pub async fn channel_events_ws(
+    ws: WebSocketUpgrade,
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<Response> {
+    if cmd
+        .get_permissions_in_channel_by_id(session.user_id, channel_id)
+        .await?
+        < Permissions::ChannelMod
+    {
+        return Err(ApiError::Unauthorized(
+            "Not a moderator in this channel".to_owned(),
+        ));
+    }
+
+    let events = cmd.webhook_dispatcher.subscribe();
+
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, channel_id, events)))
+}
+
+async fn handle_socket(mut socket: WebSocket, channel_id: u64, mut events: Receiver<ChannelEvent>) {
+    debug!("Event stream client connected to channel {channel_id}");
+
+    loop {
+        tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => (),
+                Some(Err(err)) => {
+                    warn!("Websocket error: {err}");
+                    break;
+                }
+            },
+            event = events.recv() => match event {
+                Ok(event) if event.channel_id == channel_id => {
+                    let text = serde_json::to_string(&event).expect("Failed to serialize event");
+
+                    if let Err(err) = socket.send(Message::Text(text)).await {
+                        warn!("Could not send websocket message: {err}");
+                        break;
+                    }
+                }
+                Ok(_) => (),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Event stream for channel {channel_id} skipped {skipped} events");
+                }
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+
+    debug!("Event stream client disconnected from channel {channel_id}");
+}
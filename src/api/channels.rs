@@ -1,7 +1,8 @@
 use axum::extract::{Path, Query, State};
-use axum::routing::{get, post};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
 use axum::{Json, Router};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -10,16 +11,30 @@ use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use super::channel_events::channel_events_ws;
 use super::state::AppState;
 use super::Result;
 use crate::api::error::ApiError;
+use crate::command_handler::action_validation::{ActionValidation, Diagnostic};
+use crate::command_handler::command_tests::CommandTestResult;
+use crate::command_handler::debug_report::{self, DebugReport, TraceEntry};
+use crate::command_handler::error::CommandError;
+use crate::command_handler::http_fetch::check_public_url;
+use crate::command_handler::i18n::Message;
+use crate::command_handler::twitch_api::model::{AutoModSettings, ShieldModeStatus};
+use crate::command_handler::twitch_api::{get_broadcaster_helix_api, BroadcasterHelixApi};
 use crate::command_handler::{CommandHandler, ExecutionContext};
 use crate::database;
-use crate::database::models::{Command, CommandMode, Filter, User, WebSession};
+use crate::database::models::{
+    BanphraseApiConfig, ChannelSetting, Command, CommandMode, CommandTest, CustomHook, Filter,
+    GithubEvent, GithubHook, ModerationAction, ModerationLogEntry, ModerationLogFilter,
+    OutgoingWebhook, ProtectionAction, ProtectionSettings, StreamHighlight, User, WebSession,
+    WebhookEvent,
+};
 use crate::platform::{ChannelIdentifier, Permissions, ServerPlatformContext, UserIdentifier};
 
 pub async fn get_channels(cmd: State<CommandHandler>) -> Result<Json<Vec<Channel>>> {
-    let base_channels = cmd.db.get_channels().expect("DB error");
+    let base_channels = cmd.db.run(|db| db.get_channels()).await?;
     let mut friendly_names =
         get_friendly_names(base_channels.iter().map(|ch| ch.id).collect(), &cmd).await?;
 
@@ -41,7 +56,8 @@ pub async fn get_channel_info(
 ) -> Result<Json<ChannelInfo>> {
     let channel = cmd
         .db
-        .get_channel_by_id(channel_id)?
+        .run(move |db| db.get_channel_by_id(channel_id))
+        .await?
         .ok_or(ApiError::NotFound)?;
 
     let display_name = match get_channel_display_name(&channel, &cmd).await {
@@ -87,7 +103,9 @@ pub async fn get_channel_commands(
     Path(channel_id): Path<u64>,
     cmd: State<CommandHandler>,
 ) -> Result<Json<Vec<Command>>> {
-    Ok(Json(cmd.db.get_commands(channel_id)?))
+    Ok(Json(
+        cmd.db.run(move |db| db.get_commands(channel_id)).await?,
+    ))
 }
 
 pub async fn get_channel_eventsub_triggers(
@@ -96,7 +114,8 @@ pub async fn get_channel_eventsub_triggers(
 ) -> Result<Json<Vec<Value>>> {
     let channel = cmd
         .db
-        .get_channel_by_id(channel_id)?
+        .run(move |db| db.get_channel_by_id(channel_id))
+        .await?
         .ok_or(ApiError::NotFound)?;
 
     match channel.get_identifier() {
@@ -106,13 +125,18 @@ pub async fn get_channel_eventsub_triggers(
                 .twitch_api
                 .as_ref()
                 .map(|twitch_api| &twitch_api.helix_api)
-                .expect("Twitch not configured");
+                .ok_or_else(|| ApiError::Unconfigured("Twitch".to_owned()))?;
 
-            let twitch_user = helix.get_user_by_id(&id).await?;
+            let twitch_user = helix
+                .get_user_by_id(&id)
+                .await
+                .map_err(|e| ApiError::upstream("Twitch", e))?;
 
+            let broadcaster_id = twitch_user.id;
             let triggers = cmd
                 .db
-                .get_eventsub_triggers_for_broadcaster(&twitch_user.id)?
+                .run(move |db| db.get_eventsub_triggers_for_broadcaster(&broadcaster_id))
+                .await?
                 .into_iter()
                 .map(|trigger| {
                     json!({
@@ -140,7 +164,11 @@ pub async fn get_filters(
         .await?
         >= Permissions::ChannelMod
     {
-        Ok(Json(cmd.db.get_filters_in_channel_id(channel_id)?))
+        Ok(Json(
+            cmd.db
+                .run(move |db| db.get_filters_in_channel_id(channel_id))
+                .await?,
+        ))
     } else {
         Err(ApiError::Unauthorized(
             "Not a moderator in this channel".to_owned(),
@@ -148,8 +176,647 @@ pub async fn get_filters(
     }
 }
 
+pub async fn get_custom_hooks(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Vec<CustomHook>>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    Ok(Json(
+        cmd.db
+            .run(move |db| db.get_custom_hooks(channel_id))
+            .await?,
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct CustomHookPayload {
+    pub action: String,
+    pub mode: String,
+}
+
+/// Creates or replaces the hook. The token is regenerated and only returned here
+pub async fn set_custom_hook(
+    session: WebSession,
+    Path((channel_id, hook_id)): Path<(u64, String)>,
+    cmd: State<CommandHandler>,
+    Json(payload): Json<CustomHookPayload>,
+) -> Result<Json<Value>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    let mode = CommandMode::from_str(&payload.mode)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid command mode {}", payload.mode)))?;
+
+    let token = {
+        let hook_id = hook_id.clone();
+        cmd.db
+            .run(move |db| db.set_custom_hook(channel_id, &hook_id, &payload.action, mode))
+            .await?
+    };
+
+    Ok(Json(json!({
+        "url": format!("{}/api/hooks/custom/{channel_id}/{hook_id}", cmd.config().base_url),
+        "token": token,
+    })))
+}
+
+pub async fn delete_custom_hook(
+    session: WebSession,
+    Path((channel_id, hook_id)): Path<(u64, String)>,
+    cmd: State<CommandHandler>,
+) -> Result<()> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    cmd.db
+        .run(move |db| db.delete_custom_hook(channel_id, &hook_id))
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_github_hook(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<GithubHookInfo>> {
+    check_channel_owner(&session, channel_id, &cmd).await?;
+
+    let hook = cmd
+        .db
+        .run(move |db| db.get_github_hook(channel_id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(GithubHookInfo::new(hook, &cmd.config().base_url)))
+}
+
+pub async fn create_github_hook(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<GithubHookInfo>> {
+    check_channel_owner(&session, channel_id, &cmd).await?;
+
+    let hook = cmd
+        .db
+        .run(move |db| db.get_or_create_github_hook(channel_id))
+        .await?;
+
+    Ok(Json(GithubHookInfo::new(hook, &cmd.config().base_url)))
+}
+
+pub async fn delete_github_hook(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<()> {
+    check_channel_owner(&session, channel_id, &cmd).await?;
+
+    cmd.db
+        .run(move |db| db.delete_github_hook(channel_id))
+        .await?;
+
+    Ok(())
+}
+
+/// An empty body resets the template to the default one
+pub async fn set_github_template(
+    session: WebSession,
+    Path((channel_id, event)): Path<(u64, String)>,
+    cmd: State<CommandHandler>,
+    template: String,
+) -> Result<Json<GithubHookInfo>> {
+    check_channel_owner(&session, channel_id, &cmd).await?;
+
+    let event = GithubEvent::from_str(&event)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid event {event}")))?;
+
+    let template = Some(template).filter(|template| !template.trim().is_empty());
+    let hook = cmd
+        .db
+        .run(move |db| {
+            let mut hook = db.get_github_hook(channel_id)?.ok_or(ApiError::NotFound)?;
+            hook.set_template(event, template);
+            db.save_github_hook(&hook)?;
+
+            Ok::<_, ApiError>(hook)
+        })
+        .await?;
+
+    Ok(Json(GithubHookInfo::new(hook, &cmd.config().base_url)))
+}
+
+pub async fn get_outgoing_webhooks(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Vec<OutgoingWebhook>>> {
+    check_channel_owner(&session, channel_id, &cmd).await?;
+
+    Ok(Json(
+        cmd.db
+            .run(move |db| db.get_outgoing_webhooks(channel_id))
+            .await?,
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct OutgoingWebhookPayload {
+    pub url: String,
+    pub events: Vec<String>,
+}
+
+/// The signing secret is only returned when the webhook is created
+pub async fn create_outgoing_webhook(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+    Json(payload): Json<OutgoingWebhookPayload>,
+) -> Result<Json<Value>> {
+    check_channel_owner(&session, channel_id, &cmd).await?;
+
+    let url = reqwest::Url::parse(&payload.url)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid URL {}", payload.url)))?;
+    check_public_url(&url).map_err(ApiError::BadRequest)?;
+
+    let events = payload
+        .events
+        .iter()
+        .map(|event| {
+            WebhookEvent::from_str(event)
+                .map_err(|_| ApiError::BadRequest(format!("Invalid event {event}")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if events.is_empty() {
+        return Err(ApiError::BadRequest("No events specified".to_owned()));
+    }
+
+    let webhook = cmd
+        .db
+        .run(move |db| db.add_outgoing_webhook(channel_id, &payload.url, &events))
+        .await?;
+
+    Ok(Json(json!({
+        "id": webhook.id,
+        "url": webhook.url,
+        "events": webhook.events,
+        "secret": webhook.secret,
+    })))
+}
+
+pub async fn delete_outgoing_webhook(
+    session: WebSession,
+    Path((channel_id, webhook_id)): Path<(u64, u64)>,
+    cmd: State<CommandHandler>,
+) -> Result<()> {
+    check_channel_owner(&session, channel_id, &cmd).await?;
+
+    cmd.db
+        .run(move |db| db.delete_outgoing_webhook(channel_id, webhook_id))
+        .await?;
+
+    Ok(())
+}
+
+const MODLOG_MAX_PER_PAGE: u32 = 100;
+
+#[derive(Deserialize)]
+pub struct ModerationLogParams {
+    /// User identifier of the target, for example `twitch:12345`
+    pub user: Option<String>,
+    pub action: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Starts from 1
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+pub async fn get_moderation_log(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    Query(params): Query<ModerationLogParams>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Vec<ModerationLogEntry>>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    let target = params
+        .user
+        .map(|user| {
+            UserIdentifier::from_string(&user)
+                .map(|identifier| identifier.to_string())
+                .map_err(|_| ApiError::BadRequest(format!("Invalid user identifier {user}")))
+        })
+        .transpose()?;
+
+    let action = params
+        .action
+        .map(|action| {
+            ModerationAction::from_str(&action)
+                .map_err(|_| ApiError::BadRequest(format!("Invalid action {action}")))
+        })
+        .transpose()?;
+
+    let filter = ModerationLogFilter {
+        target,
+        action,
+        from: params.from.map(|from| from.naive_utc()),
+        to: params.to.map(|to| to.naive_utc()),
+    };
+
+    let per_page = params.per_page.unwrap_or(50).clamp(1, MODLOG_MAX_PER_PAGE);
+    let page = params.page.unwrap_or(1).max(1);
+
+    let offset = i64::from(page - 1) * i64::from(per_page);
+    let entries = cmd
+        .db
+        .run(move |db| db.get_moderation_log(channel_id, filter, per_page.into(), offset))
+        .await?;
+
+    Ok(Json(entries))
+}
+
+const HIGHLIGHTS_MAX_PER_PAGE: u32 = 100;
+
+#[derive(Deserialize)]
+pub struct HighlightsParams {
+    /// Only the highlights of the given Twitch stream
+    pub stream_id: Option<String>,
+    /// Starts from 1
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+pub async fn get_stream_highlights(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    Query(params): Query<HighlightsParams>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Vec<StreamHighlight>>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    let per_page = params
+        .per_page
+        .unwrap_or(50)
+        .clamp(1, HIGHLIGHTS_MAX_PER_PAGE);
+    let page = params.page.unwrap_or(1).max(1);
+
+    let offset = i64::from(page - 1) * i64::from(per_page);
+    let highlights = cmd
+        .db
+        .run(move |db| {
+            db.get_stream_highlights(
+                channel_id,
+                params.stream_id.as_deref(),
+                per_page.into(),
+                offset,
+            )
+        })
+        .await?;
+
+    Ok(Json(highlights))
+}
+
+pub async fn delete_stream_highlight(
+    session: WebSession,
+    Path((channel_id, highlight_id)): Path<(u64, u64)>,
+    cmd: State<CommandHandler>,
+) -> Result<()> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    cmd.db
+        .run(move |db| db.delete_stream_highlight(channel_id, highlight_id))
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_protection_settings(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<ProtectionSettings>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    Ok(Json(cmd.spam_protection.get_settings(channel_id).await?))
+}
+
+pub async fn set_protection_settings(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+    Json(mut settings): Json<ProtectionSettings>,
+) -> Result<()> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    if ProtectionAction::from_str(&settings.action).is_err() {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid action {}",
+            settings.action
+        )));
+    }
+    if settings.max_caps_percent > Some(100) {
+        return Err(ApiError::BadRequest(
+            "Caps limit is a percentage".to_owned(),
+        ));
+    }
+    if settings.timeout_duration == 0 {
+        return Err(ApiError::BadRequest(
+            "Timeout duration must be positive".to_owned(),
+        ));
+    }
+
+    settings.channel_id = channel_id;
+    cmd.spam_protection.set_settings(settings).await?;
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct ShieldModePayload {
+    is_active: bool,
+}
+
+pub async fn get_shield_mode(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<ShieldModeStatus>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+    let (helix_api, broadcaster_id) = get_channel_broadcaster_api(&cmd, channel_id).await?;
+
+    let status = helix_api
+        .get_shield_mode_status(&broadcaster_id)
+        .await
+        .map_err(|e| ApiError::upstream("Twitch", e))?;
+
+    Ok(Json(status))
+}
+
+pub async fn set_shield_mode(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+    Json(payload): Json<ShieldModePayload>,
+) -> Result<Json<ShieldModeStatus>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+    let (helix_api, broadcaster_id) = get_channel_broadcaster_api(&cmd, channel_id).await?;
+
+    let status = helix_api
+        .update_shield_mode_status(&broadcaster_id, payload.is_active)
+        .await
+        .map_err(|e| ApiError::upstream("Twitch", e))?;
+
+    Ok(Json(status))
+}
+
+pub async fn get_automod_settings(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<AutoModSettings>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+    let (helix_api, broadcaster_id) = get_channel_broadcaster_api(&cmd, channel_id).await?;
+
+    let settings = helix_api
+        .get_automod_settings(&broadcaster_id)
+        .await
+        .map_err(|e| ApiError::upstream("Twitch", e))?;
+
+    Ok(Json(settings))
+}
+
+/// Individual category levels are ignored when `overall_level` is set
+pub async fn set_automod_settings(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+    Json(settings): Json<AutoModSettings>,
+) -> Result<Json<AutoModSettings>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    let levels = [
+        settings.overall_level.unwrap_or_default(),
+        settings.disability,
+        settings.aggression,
+        settings.sexuality_sex_or_gender,
+        settings.misogyny,
+        settings.bullying,
+        settings.swearing,
+        settings.race_ethnicity_or_religion,
+        settings.sex_based_terms,
+    ];
+    if levels
+        .iter()
+        .any(|level| *level > AutoModSettings::MAX_LEVEL)
+    {
+        return Err(ApiError::BadRequest(format!(
+            "AutoMod levels must be between 0 and {}",
+            AutoModSettings::MAX_LEVEL
+        )));
+    }
+
+    let (helix_api, broadcaster_id) = get_channel_broadcaster_api(&cmd, channel_id).await?;
+
+    let settings = helix_api
+        .update_automod_settings(&broadcaster_id, &settings)
+        .await
+        .map_err(|e| ApiError::upstream("Twitch", e))?;
+
+    Ok(Json(settings))
+}
+
+/// The Helix client acting with the streamer's manage token, along with their id
+async fn get_channel_broadcaster_api(
+    cmd: &CommandHandler,
+    channel_id: u64,
+) -> Result<(BroadcasterHelixApi, String)> {
+    let channel = cmd
+        .db
+        .run(move |db| db.get_channel_by_id(channel_id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    match channel.get_identifier() {
+        ChannelIdentifier::TwitchChannel((broadcaster_id, _)) => {
+            let helix_api = get_broadcaster_helix_api(&cmd.db, &broadcaster_id)
+                .await
+                .map_err(|_| {
+                    ApiError::BadRequest(
+                        "The streamer has not authenticated the bot to manage the channel"
+                            .to_owned(),
+                    )
+                })?;
+
+            Ok((helix_api, broadcaster_id))
+        }
+        _ => Err(ApiError::BadRequest("Not a Twitch channel".to_owned())),
+    }
+}
+
+pub async fn get_channel_settings(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<HashMap<String, String>>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    let values = cmd
+        .db
+        .run(move |db| db.get_channel_setting_values(channel_id))
+        .await?;
+    let settings = ChannelSetting::with_defaults(&values)
+        .into_iter()
+        .map(|(setting, value)| (setting.to_string(), value))
+        .collect();
+
+    Ok(Json(settings))
+}
+
+/// Only changes the given settings, settings set to `null` are reset to their defaults
+pub async fn update_channel_settings(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+    Json(changes): Json<HashMap<String, Option<String>>>,
+) -> Result<()> {
+    check_channel_owner(&session, channel_id, &cmd).await?;
+
+    let channel = cmd
+        .db
+        .run(move |db| db.get_channel_by_id(channel_id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let mut parsed_changes = Vec::with_capacity(changes.len());
+
+    for (name, value) in changes {
+        let setting = ChannelSetting::from_str(&name)
+            .map_err(|_| ApiError::BadRequest(format!("Unknown setting {name}")))?;
+
+        if let Some(value) = &value {
+            setting.validate(value).map_err(ApiError::BadRequest)?;
+        }
+
+        parsed_changes.push((setting, value));
+    }
+
+    cmd.db
+        .run(move |db| {
+            for (setting, value) in parsed_changes {
+                match value {
+                    Some(value) => db.set_channel_setting(channel_id, setting, value)?,
+                    None => db.remove_channel_setting(channel_id, setting)?,
+                }
+            }
+            Ok::<_, database::DatabaseError>(())
+        })
+        .await?;
+
+    cmd.platform_handler
+        .read()
+        .await
+        .reload_channel_settings(&cmd.db, channel.get_identifier(), channel_id)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_banphrase_api(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Option<BanphraseApiConfig>>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    Ok(Json(cmd.banphrase_api.get_config(channel_id).await?))
+}
+
+/// Only used for Twitch channels
+pub async fn set_banphrase_api(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+    Json(mut config): Json<BanphraseApiConfig>,
+) -> Result<()> {
+    check_channel_owner(&session, channel_id, &cmd).await?;
+
+    match reqwest::Url::parse(&config.url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => (),
+        _ => return Err(ApiError::BadRequest(format!("Invalid URL {}", config.url))),
+    }
+
+    config.channel_id = channel_id;
+    cmd.banphrase_api.set_config(config).await?;
+
+    Ok(())
+}
+
+pub async fn delete_banphrase_api(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<()> {
+    check_channel_owner(&session, channel_id, &cmd).await?;
+
+    cmd.banphrase_api.remove_config(channel_id).await?;
+
+    Ok(())
+}
+
+pub async fn get_debug_report(
+    session: WebSession,
+    Path((channel_id, report_id)): Path<(u64, String)>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<DebugReport>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    match cmd.debug_reports.get(&report_id) {
+        Some(report) if report.channel_id == channel_id => Ok(Json((*report).clone())),
+        _ => Err(ApiError::NotFound),
+    }
+}
+
+async fn check_channel_mod(
+    session: &WebSession,
+    channel_id: u64,
+    cmd: &CommandHandler,
+) -> Result<()> {
+    if cmd
+        .get_permissions_in_channel_by_id(session.user_id, channel_id)
+        .await?
+        >= Permissions::ChannelMod
+    {
+        Ok(())
+    } else {
+        Err(ApiError::Unauthorized(
+            "Not a moderator in this channel".to_owned(),
+        ))
+    }
+}
+
+async fn check_channel_owner(
+    session: &WebSession,
+    channel_id: u64,
+    cmd: &CommandHandler,
+) -> Result<()> {
+    if cmd
+        .get_permissions_in_channel_by_id(session.user_id, channel_id)
+        .await?
+        >= Permissions::ChannelOwner
+    {
+        Ok(())
+    } else {
+        Err(ApiError::Unauthorized(
+            "Not the owner of this channel".to_owned(),
+        ))
+    }
+}
+
 pub async fn get_channel_count(cmd: State<CommandHandler>) -> Result<Json<i64>> {
-    Ok(Json(cmd.db.get_channels_amount()?))
+    Ok(Json(cmd.db.run(|db| db.get_channels_amount()).await?))
 }
 
 #[derive(Serialize)]
@@ -167,8 +834,18 @@ async fn get_friendly_names(
     let mut twitch_channels = HashMap::new();
     let mut discord_channels = HashMap::new();
 
-    for id in channel_ids {
-        match cmd.db.get_channel_by_id(id)? {
+    let channels = cmd
+        .db
+        .run(move |db| {
+            channel_ids
+                .into_iter()
+                .map(|id| db.get_channel_by_id(id).map(|channel| (id, channel)))
+                .collect::<std::result::Result<Vec<_>, _>>()
+        })
+        .await?;
+
+    for (id, channel) in channels {
+        match channel {
             Some(channel) => match channel.get_identifier() {
                 ChannelIdentifier::TwitchChannel((twitch_id, _)) => {
                     twitch_channels.insert(twitch_id, id);
@@ -193,7 +870,7 @@ async fn get_friendly_names(
         let helix = platform_handler
             .twitch_api
             .as_ref()
-            .expect("Twitch API not initialized even though there are Twitch channels registered")
+            .ok_or_else(|| ApiError::Unconfigured("Twitch".to_owned()))?
             .helix_api
             .clone();
 
@@ -206,9 +883,9 @@ async fn get_friendly_names(
 
                     users.into_iter().for_each(|twitch_user| {
                         tracing::trace!("{:?}", twitch_user);
-                        let channel_id = *twitch_channels.get(&twitch_user.id).unwrap();
-
-                        results.insert(channel_id, twitch_user.display_name);
+                        if let Some(channel_id) = twitch_channels.get(&twitch_user.id) {
+                            results.insert(*channel_id, twitch_user.display_name);
+                        }
                     });
                 }
                 Err(e) => tracing::error!("Error getting Twitch name: {}", e),
@@ -217,15 +894,24 @@ async fn get_friendly_names(
     }
 
     if !discord_channels.is_empty() {
-        let discord_api = platform_handler.discord_api.clone().expect(
-            "Discord API not initialized even though there are Discord channels registered",
-        );
+        let discord_api = platform_handler
+            .discord_api
+            .clone()
+            .ok_or_else(|| ApiError::Unconfigured("Discord".to_owned()))?;
 
         for (guild_id, id) in discord_channels {
             let discord_api = discord_api.clone();
             let results = results.clone();
             handles.push(tokio::spawn(async move {
-                match discord_api.get_guild_name(guild_id.parse().unwrap()).await {
+                let guild_id = match guild_id.parse() {
+                    Ok(guild_id) => guild_id,
+                    Err(_) => {
+                        tracing::error!("Invalid guild id {guild_id}");
+                        return;
+                    }
+                };
+
+                match discord_api.get_guild_name(guild_id).await {
                     Ok(name) => {
                         results.lock().await.insert(id, name);
                     }
@@ -248,6 +934,36 @@ pub struct ChannelInfo {
     pub extra_sections: Vec<(&'static str, &'static str)>,
 }
 
+#[derive(Serialize)]
+pub struct GithubHookInfo {
+    pub url: String,
+    pub secret: String,
+    pub templates: HashMap<String, String>,
+}
+
+impl GithubHookInfo {
+    fn new(hook: GithubHook, base_url: &str) -> Self {
+        let templates = [
+            GithubEvent::Push,
+            GithubEvent::Release,
+            GithubEvent::Issues,
+            GithubEvent::PullRequest,
+        ]
+        .into_iter()
+        .map(|event| (event.to_string(), hook.get_template(event).to_owned()))
+        .collect();
+
+        Self {
+            url: format!(
+                "{}/api/hooks/github/{}/{}",
+                base_url, hook.channel_id, hook.secret
+            ),
+            secret: hook.secret,
+            templates,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct PermissionsInfo {
     pub name: Permissions,
@@ -265,12 +981,13 @@ async fn get_channel_display_name(
             let twitch_api = platform_handler_guard
                 .twitch_api
                 .as_ref()
-                .expect("Twitch channel found but Twitch is not configured");
+                .ok_or_else(|| ApiError::Unconfigured("Twitch".to_owned()))?;
 
             let twitch_user = twitch_api
                 .helix_api
                 .get_user_by_id(&channel.channel)
-                .await?;
+                .await
+                .map_err(|e| ApiError::upstream("Twitch", e))?;
 
             Ok(Some(twitch_user.display_name))
         }
@@ -278,11 +995,16 @@ async fn get_channel_display_name(
             let discord_api = platform_handler_guard
                 .discord_api
                 .as_ref()
-                .expect("Discord channel found but DIscord is not configured");
+                .ok_or_else(|| ApiError::Unconfigured("Discord".to_owned()))?;
+
+            let guild_id = channel.channel.parse().map_err(|_| {
+                ApiError::GenericError(format!("Invalid guild id {}", channel.channel))
+            })?;
 
             let guild_name = discord_api
-                .get_guild_name(channel.channel.parse().unwrap())
-                .await?;
+                .get_guild_name(guild_id)
+                .await
+                .map_err(|e| ApiError::upstream("Discord", e))?;
 
             Ok(Some(guild_name))
         }
@@ -290,20 +1012,69 @@ async fn get_channel_display_name(
     }
 }
 
+#[derive(Deserialize)]
+pub struct ValidateActionPayload {
+    pub action: String,
+    pub mode: String,
+    /// Checked against the names of the builtin commands when given
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Checks the command action without executing it
+pub async fn validate_command_action(
+    session: WebSession,
+    Path(channel_id): Path<u64>,
+    cmd: State<CommandHandler>,
+    Json(payload): Json<ValidateActionPayload>,
+) -> Result<Json<ActionValidation>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    let mode = CommandMode::from_str(&payload.mode)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid command mode {}", payload.mode)))?;
+
+    let mut validation = cmd.validate_action(&mode, &payload.action);
+
+    if let Some(name) = payload.name {
+        if cmd.db.is_builtin_command_name(&name) {
+            validation.errors.push(Diagnostic {
+                message: format!("{name} is the name of a builtin command"),
+                line: None,
+                column: None,
+            });
+        }
+    }
+
+    Ok(Json(validation))
+}
+
 #[derive(Deserialize)]
 pub struct EvalParams {
     pub mode: String,
     #[serde(default)]
     pub args: String,
+    /// Skips platform side effects such as sending messages, returning them instead
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize)]
+pub struct DryRunResult {
+    pub output: Option<String>,
+    pub side_effects: Vec<TraceEntry>,
 }
 
 pub async fn eval(
     Path(channel_id): Path<u64>,
     user: User,
-    Query(EvalParams { mode, args }): Query<EvalParams>,
+    Query(EvalParams {
+        mode,
+        args,
+        dry_run,
+    }): Query<EvalParams>,
     cmd: State<CommandHandler>,
     payload: String,
-) -> Result<String> {
+) -> Result<Response> {
     let args = args
         .split(',')
         .filter(|item| !item.is_empty())
@@ -312,7 +1083,8 @@ pub async fn eval(
 
     let channel = cmd
         .db
-        .get_channel_by_id(channel_id)?
+        .run(move |db| db.get_channel_by_id(channel_id))
+        .await?
         .ok_or(ApiError::NotFound)?;
 
     if cmd
@@ -320,20 +1092,18 @@ pub async fn eval(
         .await?
         >= Permissions::ChannelMod
     {
+        if cmd.is_safe_mode() {
+            return Err(ApiError::CommandError(CommandError::Localized(
+                Message::SafeModeCommandsDisabled,
+            )));
+        }
+
         let command_mode = CommandMode::from_str(&mode)
             .map_err(|_| ApiError::BadRequest(format!("Invalid command mode {mode}")))?;
 
-        let executing_user = if let Some(twitch_id) = user.twitch_id.clone() {
-            UserIdentifier::TwitchID(twitch_id)
-        } else if let Some(local_ip) = &user.local_addr {
-            UserIdentifier::IpAddr(local_ip.parse().unwrap())
-        } else {
-            todo!()
-        };
-
         let platform_ctx = ServerPlatformContext {
             target_channel: channel.get_identifier(),
-            executing_user,
+            executing_user: get_executing_user(&user)?,
             cmd: cmd.0.clone(),
             display_name: "Tester via API".to_owned(),
         };
@@ -348,6 +1118,7 @@ pub async fn eval(
             user: &user,
             processing_timestamp,
             blocked_users: &cmd.blocked_users,
+            payload: None,
         };
 
         let command = Command {
@@ -358,12 +1129,25 @@ pub async fn eval(
             triggers: None,
             cooldown: Some(0),
             mode: command_mode,
+            discord_channels: None,
+            args_spec: None,
         };
-        let response = cmd
-            .execute_command(command, &execution_ctx, args)
-            .await?
-            .unwrap_or_else(|| "<empty response>".to_owned());
-        Ok(response)
+        let execution = cmd.execute_command(command, &execution_ctx, args);
+
+        if dry_run {
+            let (output, trace, _) = debug_report::capture_dry_run(execution).await;
+
+            Ok(Json(DryRunResult {
+                output: output?,
+                side_effects: debug_report::get_side_effects(trace),
+            })
+            .into_response())
+        } else {
+            let response = execution
+                .await?
+                .unwrap_or_else(|| "<empty response>".to_owned());
+            Ok(response.into_response())
+        }
     } else {
         Err(ApiError::Unauthorized(
             "Not a moderator in this channel".to_owned(),
@@ -371,13 +1155,207 @@ pub async fn eval(
     }
 }
 
+/// Commands executed from the API run as the user's linked account
+fn get_executing_user(user: &User) -> Result<UserIdentifier> {
+    if let Some(twitch_id) = user.twitch_id.clone() {
+        Ok(UserIdentifier::TwitchID(twitch_id))
+    } else if let Some(discord_id) = user.discord_id.clone() {
+        Ok(UserIdentifier::DiscordID(discord_id))
+    } else if let Some(local_client) = user.local_addr.clone() {
+        Ok(UserIdentifier::LocalClient(local_client))
+    } else {
+        Err(ApiError::BadRequest(
+            "Evaluating requires a linked Twitch or Discord account".to_owned(),
+        ))
+    }
+}
+
+pub async fn get_command_tests(
+    session: WebSession,
+    Path((channel_id, command_name)): Path<(u64, String)>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Vec<CommandTest>>> {
+    check_channel_mod(&session, channel_id, &cmd).await?;
+
+    Ok(Json(
+        cmd.db
+            .run(move |db| db.get_command_tests(channel_id, &command_name))
+            .await?,
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct CommandTestPayload {
+    #[serde(default)]
+    pub args: String,
+    pub expected: Vec<String>,
+}
+
+pub async fn add_command_test(
+    session: WebSession,
+    Path((channel_id, command_name)): Path<(u64, String)>,
+    cmd: State<CommandHandler>,
+    Json(payload): Json<CommandTestPayload>,
+) -> Result<Json<CommandTest>> {
+    check_channel_owner(&session, channel_id, &cmd).await?;
+
+    // Substrings are stored one per line
+    if payload
+        .expected
+        .iter()
+        .any(|expected| expected.contains('\n'))
+    {
+        return Err(ApiError::BadRequest(
+            "Expected substrings cannot contain line breaks".to_owned(),
+        ));
+    }
+
+    let test = cmd
+        .db
+        .run(move |db| {
+            let commands = db.get_commands(channel_id)?;
+            if !commands.iter().any(|command| command.name == command_name) {
+                return Err(ApiError::NotFound);
+            }
+
+            Ok(db.add_command_test(channel_id, &command_name, &payload.args, &payload.expected)?)
+        })
+        .await?;
+
+    Ok(Json(test))
+}
+
+pub async fn delete_command_test(
+    session: WebSession,
+    Path((channel_id, command_name, test_id)): Path<(u64, String, u64)>,
+    cmd: State<CommandHandler>,
+) -> Result<()> {
+    check_channel_owner(&session, channel_id, &cmd).await?;
+
+    cmd.db
+        .run(move |db| db.delete_command_test(channel_id, &command_name, test_id))
+        .await?;
+
+    Ok(())
+}
+
+/// Runs the test cases of the command in dry run mode
+pub async fn run_command_tests(
+    Path((channel_id, command_name)): Path<(u64, String)>,
+    user: User,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Vec<CommandTestResult>>> {
+    let channel = cmd
+        .db
+        .run(move |db| db.get_channel_by_id(channel_id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if cmd
+        .get_permissions_in_channel(user.clone(), &channel.get_identifier())
+        .await?
+        < Permissions::ChannelMod
+    {
+        return Err(ApiError::Unauthorized(
+            "Not a moderator in this channel".to_owned(),
+        ));
+    }
+
+    let channel_identifier = channel.get_identifier();
+    let command = cmd
+        .db
+        .run(move |db| db.get_command(&channel_identifier, &command_name))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let platform_ctx = ServerPlatformContext {
+        target_channel: channel.get_identifier(),
+        executing_user: get_executing_user(&user)?,
+        cmd: cmd.0.clone(),
+        display_name: "Tester via API".to_owned(),
+    };
+
+    let platform_handler = cmd.platform_handler.read().await;
+    let execution_ctx = ExecutionContext {
+        db: &cmd.db,
+        channel_id: Some(channel.id),
+        platform_handler: &platform_handler,
+        platform_ctx,
+        user: &user,
+        processing_timestamp: Utc::now(),
+        blocked_users: &cmd.blocked_users,
+        payload: None,
+    };
+
+    let results = cmd.run_command_tests(&command, &execution_ctx).await?;
+
+    Ok(Json(results))
+}
+
 pub fn create_router() -> Router<AppState> {
     Router::new()
         .route("/", get(get_channels))
         .route("/count", get(get_channel_count))
         .route("/:id/info", get(get_channel_info))
         .route("/:id/filters", get(get_filters))
+        .route("/:id/modlog", get(get_moderation_log))
+        .route("/:id/highlights", get(get_stream_highlights))
+        .route(
+            "/:id/highlights/:highlight_id",
+            delete(delete_stream_highlight),
+        )
+        .route(
+            "/:id/banphrase",
+            get(get_banphrase_api)
+                .put(set_banphrase_api)
+                .delete(delete_banphrase_api),
+        )
+        .route(
+            "/:id/settings",
+            get(get_channel_settings).patch(update_channel_settings),
+        )
+        .route(
+            "/:id/protection",
+            get(get_protection_settings).put(set_protection_settings),
+        )
         .route("/:id/eventsub", get(get_channel_eventsub_triggers))
+        .route(
+            "/:id/twitch/shield-mode",
+            get(get_shield_mode).put(set_shield_mode),
+        )
+        .route(
+            "/:id/twitch/automod",
+            get(get_automod_settings).put(set_automod_settings),
+        )
         .route("/:id/commands", get(get_channel_commands))
+        .route("/:id/commands/validate", post(validate_command_action))
+        .route(
+            "/:id/commands/:name/tests",
+            get(get_command_tests).post(add_command_test),
+        )
+        .route("/:id/commands/:name/tests/run", post(run_command_tests))
+        .route(
+            "/:id/commands/:name/tests/:test_id",
+            delete(delete_command_test),
+        )
         .route("/:id/eval", post(eval))
+        .route("/:id/debug/:report_id", get(get_debug_report))
+        .route("/:id/events/ws", get(channel_events_ws))
+        .route(
+            "/:id/github",
+            get(get_github_hook)
+                .post(create_github_hook)
+                .delete(delete_github_hook),
+        )
+        .route("/:id/github/:event", put(set_github_template))
+        .route("/:id/hooks", get(get_custom_hooks))
+        .route(
+            "/:id/hooks/:hook_id",
+            put(set_custom_hook).delete(delete_custom_hook),
+        )
+        .route(
+            "/:id/webhooks",
+            get(get_outgoing_webhooks).post(create_outgoing_webhook),
+        )
+        .route("/:id/webhooks/:webhook_id", delete(delete_outgoing_webhook))
 }
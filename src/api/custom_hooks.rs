@@ -0,0 +1,72 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use http::HeaderMap;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{constant_time_eq, error::ApiError, Result};
+use crate::{
+    command_handler::CommandHandler,
+    platform::{ServerPlatformContext, UserIdentifier},
+};
+
+#[derive(Deserialize)]
+pub struct CustomHookParams {
+    pub token: Option<String>,
+}
+
+/// Runs the action of the hook with the posted JSON available as `payload`.
+/// The token can be passed either as a bearer token or as a `token` query parameter
+pub async fn custom_hook_callback(
+    Path((channel_id, hook_id)): Path<(u64, String)>,
+    Query(params): Query<CustomHookParams>,
+    cmd: State<CommandHandler>,
+    headers: HeaderMap,
+    Json(payload): Json<Value>,
+) -> Result<()> {
+    let hook = cmd
+        .db
+        .run(move |db| db.get_custom_hook(channel_id, &hook_id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let token = headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_owned)
+        .or(params.token)
+        .ok_or_else(|| ApiError::Unauthorized("Missing token".to_owned()))?;
+
+    if !constant_time_eq(token.as_bytes(), hook.token.as_bytes()) {
+        return Err(ApiError::Unauthorized("Invalid token".to_owned()));
+    }
+
+    let channel = cmd
+        .db
+        .run(move |db| db.get_channel_by_id(channel_id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let context = ServerPlatformContext {
+        target_channel: channel.get_identifier(),
+        executing_user: UserIdentifier::LocalClient(format!("hook:{}", hook.id)),
+        cmd: cmd.0.clone(),
+        display_name: hook.id,
+    };
+
+    cmd.handle_server_message(
+        hook.action,
+        hook.mode,
+        context,
+        Vec::new(),
+        Some(channel_id),
+        Some(payload),
+        None,
+    )
+    .await?;
+
+    Ok(())
+}
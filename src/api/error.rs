@@ -1,6 +1,7 @@
 use crate::{command_handler::error::CommandError, database::DatabaseError};
-use axum::response::IntoResponse;
+use axum::response::{Html, IntoResponse};
 use http::StatusCode;
+use std::fmt::Display;
 
 #[derive(Debug)]
 pub enum ApiError {
@@ -8,12 +9,26 @@ pub enum ApiError {
     BadRequest(String),
     InvalidUser,
     Unauthorized(String),
+    /// Shown to the user as a page when logging in or connecting an account fails
+    AuthenticationFailed(String),
+    /// A platform or service that the request needs is not set up on this instance
+    Unconfigured(String),
+    /// A request to an external API such as Twitch or Discord failed
+    UpstreamError(String),
     DatabaseError(DatabaseError),
     RequestError(reqwest::Error),
     CommandError(CommandError),
     GenericError(String),
 }
 
+impl ApiError {
+    /// The details of the failure are only logged
+    pub fn upstream(service: &str, error: impl Display) -> Self {
+        tracing::warn!("{service} API request failed: {error}");
+        Self::UpstreamError(format!("{service} API request failed"))
+    }
+}
+
 impl From<diesel::result::Error> for ApiError {
     fn from(e: diesel::result::Error) -> Self {
         Self::DatabaseError(DatabaseError::DieselError(e))
@@ -54,7 +69,27 @@ impl IntoResponse for ApiError {
                 (StatusCode::UNPROCESSABLE_ENTITY, err.to_string()).into_response()
             }
             ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg).into_response(),
-            _ => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            ApiError::AuthenticationFailed(msg) => (
+                StatusCode::UNAUTHORIZED,
+                Html(format!(
+                    "<h1>Authentication failed</h1><p>{msg}</p><a href=\"/\">Go back</a>"
+                )),
+            )
+                .into_response(),
+            ApiError::Unconfigured(name) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("{name} is not configured"),
+            )
+                .into_response(),
+            ApiError::UpstreamError(msg) => (StatusCode::BAD_GATEWAY, msg).into_response(),
+            ApiError::RequestError(_) => (
+                StatusCode::BAD_GATEWAY,
+                "Request to an external service failed",
+            )
+                .into_response(),
+            ApiError::DatabaseError(_) | ApiError::GenericError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
         }
     }
 }
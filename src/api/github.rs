@@ -0,0 +1,115 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+};
+use handlebars::Handlebars;
+use hmac::{Hmac, Mac};
+use http::HeaderMap;
+use serde_json::Value;
+use sha2::Sha256;
+use std::str::FromStr;
+
+use super::{constant_time_eq, error::ApiError, Result};
+use crate::{command_handler::CommandHandler, database::models::GithubEvent};
+
+pub async fn github_callback(
+    Path((channel_id, secret)): Path<(u64, String)>,
+    cmd: State<CommandHandler>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<()> {
+    let hook = cmd
+        .db
+        .run(move |db| db.get_github_hook(channel_id))
+        .await?
+        .filter(|hook| constant_time_eq(hook.secret.as_bytes(), secret.as_bytes()))
+        .ok_or(ApiError::NotFound)?;
+
+    let signature = headers
+        .get("X-Hub-Signature-256")
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing signature".to_owned()))?;
+
+    if !verify_github_signature(signature, &body, hook.secret.as_bytes()) {
+        tracing::warn!("Invalid GitHub webhook signature for channel {channel_id}");
+        return Err(ApiError::Unauthorized("Invalid signature".to_owned()));
+    }
+
+    let raw_event = headers
+        .get("X-GitHub-Event")
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| ApiError::BadRequest("Missing event type".to_owned()))?;
+
+    let event = match GithubEvent::from_str(raw_event) {
+        Ok(event) => event,
+        Err(_) => {
+            // Includes the `ping` event sent when the hook is created
+            tracing::debug!("Ignoring GitHub event {raw_event}");
+            return Ok(());
+        }
+    };
+
+    let payload: Value = serde_json::from_slice(&body)
+        .map_err(|err| ApiError::BadRequest(format!("Invalid payload: {err}")))?;
+
+    if !event.should_announce(payload["action"].as_str()) {
+        return Ok(());
+    }
+
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+
+    let message = handlebars
+        .render_template(hook.get_template(event), &payload)
+        .map_err(|err| ApiError::BadRequest(format!("Template error: {err}")))?;
+
+    let channel = cmd
+        .db
+        .run(move |db| db.get_channel_by_id(channel_id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    cmd.platform_handler
+        .read()
+        .await
+        .send_to_channel(channel.get_identifier(), message)
+        .await
+        .map_err(|err| ApiError::GenericError(format!("{err:?}")))?;
+
+    Ok(())
+}
+
+fn verify_github_signature(signature: &str, body: &[u8], secret: &[u8]) -> bool {
+    let signature = match signature.strip_prefix("sha256=").map(hex::decode) {
+        Some(Ok(signature)) => signature,
+        _ => return false,
+    };
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+    mac.update(body);
+
+    mac.verify_slice(&signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify_github_signature;
+
+    #[test]
+    fn verify_signature() {
+        // Example from the GitHub webhook documentation
+        let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+        assert!(verify_github_signature(
+            signature,
+            b"Hello, World!",
+            b"It's a Secret to Everybody"
+        ));
+        assert!(!verify_github_signature(
+            signature,
+            b"Hello, World?",
+            b"It's a Secret to Everybody"
+        ));
+        assert!(!verify_github_signature("invalid", b"", b""));
+    }
+}
@@ -0,0 +1,87 @@
+use super::state::AppState;
+use crate::{
+    command_handler::{outgoing_webhooks::ChannelEvent, CommandHandler},
+    platform::local::{LocalResponse, LocalSession},
+};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        ConnectInfo, State, WebSocketUpgrade,
+    },
+    response::Response,
+    routing::get,
+    Router,
+};
+use std::net::SocketAddr;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::{debug, warn};
+
+/// Same protocol as the TCP local platform, with each text message being a single request.
+/// Clients authenticate with the first message rather than when connecting.
+pub async fn local_ws(
+    ws: WebSocketUpgrade,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    cmd: State<CommandHandler>,
+) -> Response {
+    ws.on_upgrade(move |socket| handle_socket(socket, addr, cmd.0))
+}
+
+async fn handle_socket(mut socket: WebSocket, addr: SocketAddr, cmd: CommandHandler) {
+    debug!("Local websocket client connected from {addr}");
+
+    let mut session = LocalSession::new(cmd.clone());
+    // Subscribed once authenticated, so that nothing is buffered for unknown clients
+    let mut events = None;
+
+    loop {
+        let response = tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(text))) => session.handle_frame(&text).await,
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => {
+                    warn!("Websocket error: {err}");
+                    break;
+                }
+            },
+            event = next_event(&mut events) => match event {
+                Ok(event) => LocalResponse::Event { event },
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Local event stream for {addr} skipped {skipped} events");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        let text = serde_json::to_string(&response).expect("Failed to serialize response");
+        if let Err(err) = socket.send(Message::Text(text)).await {
+            warn!("Could not send websocket message: {err}");
+            break;
+        }
+
+        if session.auth_attempts_exceeded() {
+            warn!("Closing local websocket from {addr} after failed auth attempts");
+            break;
+        }
+        if events.is_none() && session.is_authenticated() {
+            events = Some(cmd.webhook_dispatcher.subscribe());
+        }
+    }
+
+    debug!("Local websocket client {addr} disconnected");
+}
+
+/// Never resolves before the client has subscribed
+async fn next_event(
+    events: &mut Option<Receiver<ChannelEvent>>,
+) -> Result<ChannelEvent, RecvError> {
+    match events {
+        Some(events) => events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+pub fn create_router() -> Router<AppState> {
+    Router::new().route("/ws", get(local_ws))
+}
@@ -1,15 +1,24 @@
+mod admin;
 mod authentication;
+mod channel_events;
 mod channels;
+mod custom_hooks;
 mod error;
+mod github;
+mod local;
+mod paste;
+mod rate_limit;
+mod shards;
 mod state;
+mod users;
 mod webhooks;
 
 use anyhow::anyhow;
-use axum::Router;
+use axum::{middleware, Router};
 use axum_extra::extract::cookie::Key;
 use dashmap::DashMap;
 use reqwest::{Client, Response};
-use std::{env, sync::Arc};
+use std::{net::SocketAddr, sync::Arc};
 use tower_http::{
     services::{ServeDir, ServeFile},
     trace::{self, DefaultOnResponse, TraceLayer},
@@ -17,36 +26,62 @@ use tower_http::{
 use tracing::{info, Level};
 
 use self::error::ApiError;
-use crate::{api::state::AppState, command_handler::CommandHandler};
+use self::rate_limit::{rate_limit, RateLimits};
+use self::webhooks::EVENTSUB_MESSAGE_ID_TTL;
+use crate::{api::state::AppState, command_handler::CommandHandler, database::cache::TtlCache};
 
 type Result<T> = std::result::Result<T, ApiError>;
 
 pub async fn run(command_handler: CommandHandler) {
+    let config = command_handler.config();
     let state_storage = Arc::new(DashMap::new());
-    let raw_secret_key = env::var("SECRET_KEY").expect("Could not read SECRET_KEY");
-    let secret_key = Key::from(raw_secret_key.as_bytes());
+    let secret_key = Key::from(config.secret_key.as_bytes());
+
+    shards::start_listener(command_handler.clone());
+    authentication::flow::start_state_cleanup(state_storage.clone());
 
     let state = AppState {
         cmd: command_handler,
         state_storage,
         http_client: Client::new(),
-        raw_secret_key,
+        eventsub_message_ids: Arc::new(TtlCache::new(EVENTSUB_MESSAGE_ID_TTL)),
         secret_key,
     };
 
     let authentication_routes = authentication::create_authentication_router();
 
-    let api_routes = Router::new()
+    let rate_limits = RateLimits::from_config(&config.rate_limits);
+
+    let mut api_routes = Router::new()
         .nest("/session", authentication::create_session_router())
         .nest("/channels", channels::create_router())
+        .nest("/admin", admin::create_router())
+        .nest("/users", users::create_router());
+
+    // Clients of the local platform can't authenticate without a token
+    if config.local_platform.token.is_some() {
+        api_routes = api_routes.nest("/local", local::create_router());
+    }
+
+    // Webhooks are not rate limited, as they come from a small set of services in bursts
+    let api_routes = api_routes
+        .layer(middleware::from_fn_with_state(
+            rate_limits.clone(),
+            rate_limit,
+        ))
         .nest("/hooks", webhooks::create_router());
 
+    // Paste links are posted in chat, so they are kept short
+    let paste_routes =
+        paste::create_router().layer(middleware::from_fn_with_state(rate_limits, rate_limit));
+
     let frontend_service =
         ServeDir::new("web/dist").fallback(ServeFile::new("web/dist/index.html"));
 
     let app = Router::new()
         .nest_service("/", frontend_service)
         .nest("/api", api_routes)
+        .nest("/p", paste_routes)
         .nest("/authenticate", authentication_routes)
         .with_state(state)
         .layer(
@@ -59,7 +94,7 @@ pub async fn run(command_handler: CommandHandler) {
     info!("Starting web server at {server_url}");
 
     axum::Server::bind(&server_url.parse().unwrap())
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .unwrap()
 
@@ -84,10 +119,6 @@ pub async fn run(command_handler: CommandHandler) {
     });*/
 }
 
-pub fn get_base_url() -> String {
-    env::var("BASE_URL").expect("BASE_URL missing!")
-}
-
 pub fn response_ok(r: &Response) -> anyhow::Result<()> {
     if r.status().is_success() {
         Ok(())
@@ -95,3 +126,22 @@ pub fn response_ok(r: &Response) -> anyhow::Result<()> {
         Err(anyhow!("Non-success response: {}", r.status()))
     }
 }
+
+/// Compares secrets without stopping at the first difference,
+/// so that the response time doesn't reveal how much of the secret was guessed
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::constant_time_eq;
+
+    #[test]
+    fn compares_secrets() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+}
@@ -0,0 +1,77 @@
+use super::{error::ApiError, state::AppState, Result};
+use crate::command_handler::CommandHandler;
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+/// Serves files that commands responded with on platforms without attachments
+pub async fn get_paste(Path(id): Path<String>, cmd: State<CommandHandler>) -> Result<Response> {
+    let paste = cmd
+        .db
+        .run(move |db| db.get_paste(&id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let filename = paste
+        .filename
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        .collect::<String>();
+    let content_disposition = HeaderValue::from_str(&format!("inline; filename=\"{filename}\""))
+        .expect("Sanitized filename is not a valid header");
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(get_content_type(&filename)),
+            ),
+            (header::CONTENT_DISPOSITION, content_disposition),
+            // Pastes are user content, so browsers should not guess that they are HTML
+            (
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ),
+        ],
+        paste.content,
+    )
+        .into_response())
+}
+
+/// Anything that could be rendered as a page is served as plain text
+fn get_content_type(filename: &str) -> &'static str {
+    let extension = filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("json") => "application/json",
+        Some("bin") => "application/octet-stream",
+        _ => "text/plain; charset=utf-8",
+    }
+}
+
+pub fn create_router() -> Router<AppState> {
+    Router::new().route("/:id", get(get_paste))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_content_type;
+
+    #[test]
+    fn guesses_content_types() {
+        assert_eq!(get_content_type("screenshot.PNG"), "image/png");
+        assert_eq!(get_content_type("commands.json"), "application/json");
+        assert_eq!(get_content_type("page.html"), "text/plain; charset=utf-8");
+        assert_eq!(get_content_type("output"), "text/plain; charset=utf-8");
+    }
+}
@@ -0,0 +1,201 @@
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::config::RateLimitsConfig;
+
+const WINDOW: Duration = Duration::from_secs(60);
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Fixed window limiter, counting requests per key in each minute
+pub struct RateLimiter {
+    limit: u32,
+    buckets: DashMap<String, (Instant, u32)>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            buckets: DashMap::new(),
+        }
+    }
+
+    /// Returns the time until the next request is allowed if the limit was reached
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut bucket = self.buckets.entry(key.to_owned()).or_insert((now, 0));
+        let (window_start, count) = bucket.value_mut();
+
+        if now.duration_since(*window_start) >= WINDOW {
+            *window_start = now;
+            *count = 0;
+        }
+
+        if *count >= self.limit {
+            Err(WINDOW - now.duration_since(*window_start))
+        } else {
+            *count += 1;
+            Ok(())
+        }
+    }
+
+    fn clear_expired(&self) {
+        let now = Instant::now();
+        self.buckets
+            .retain(|_, (window_start, _)| now.duration_since(*window_start) < WINDOW);
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimits {
+    ip: Arc<RateLimiter>,
+    session: Arc<RateLimiter>,
+    eval: Arc<RateLimiter>,
+    trusted_proxies: Arc<Vec<IpAddr>>,
+}
+
+impl RateLimits {
+    /// Limits are configured in requests per minute
+    pub fn from_config(config: &RateLimitsConfig) -> Self {
+        let limits = Self {
+            ip: Arc::new(RateLimiter::new(config.ip)),
+            session: Arc::new(RateLimiter::new(config.session)),
+            eval: Arc::new(RateLimiter::new(config.eval)),
+            trusted_proxies: Arc::new(config.trusted_proxies.clone()),
+        };
+
+        let cleanup_limits = limits.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(WINDOW).await;
+
+                cleanup_limits.ip.clear_expired();
+                cleanup_limits.session.clear_expired();
+                cleanup_limits.eval.clear_expired();
+            }
+        });
+
+        limits
+    }
+}
+
+pub async fn rate_limit<B>(
+    State(limits): State<RateLimits>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let ip = get_client_ip(&req, addr.ip(), &limits.trusted_proxies).to_string();
+    let session = get_session_key(&req);
+
+    let mut result = limits.ip.check(&ip);
+
+    if let Some(session) = &session {
+        result = result.and_then(|_| limits.session.check(session));
+    }
+
+    if req.uri().path().ends_with("/eval") {
+        let key = session.as_deref().unwrap_or(&ip);
+        result = result.and_then(|_| limits.eval.check(key));
+    }
+
+    match result {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => {
+            tracing::debug!("Rate limited request from {ip} to {}", req.uri());
+
+            let retry_after = retry_after.as_secs().max(1);
+
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, HeaderValue::from(retry_after))],
+                "Too many requests",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// The connecting address, unless it's a trusted proxy that forwarded the request.
+/// Proxies append to `X-Forwarded-For`, so the client is the last address that isn't a proxy
+fn get_client_ip<B>(req: &Request<B>, peer: IpAddr, trusted_proxies: &[IpAddr]) -> IpAddr {
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+
+    let forwarded: Vec<&str> = req
+        .headers()
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+
+    forwarded
+        .into_iter()
+        .rev()
+        .map_while(|address| address.trim().parse::<IpAddr>().ok())
+        .find(|address| !trusted_proxies.contains(address))
+        .unwrap_or(peer)
+}
+
+/// Identifies the session by the raw session cookie or API token, without looking it up
+fn get_session_key<B>(req: &Request<B>) -> Option<String> {
+    if let Some(token) = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        return Some(token.to_owned());
+    }
+
+    req.headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .find_map(|cookie| cookie.trim().strip_prefix("session_id="))
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_client_ip, RateLimiter};
+    use axum::http::Request;
+    use std::net::IpAddr;
+
+    #[test]
+    fn rate_limiter_blocks_after_limit() {
+        let limiter = RateLimiter::new(2);
+
+        assert!(limiter.check("a").is_ok());
+        assert!(limiter.check("a").is_ok());
+        assert!(limiter.check("a").is_err());
+        assert!(limiter.check("b").is_ok());
+    }
+
+    #[test]
+    fn gets_client_ip_from_trusted_proxies() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+        let req = Request::builder()
+            .header("X-Forwarded-For", "spoofed, 203.0.113.7, 10.0.0.1")
+            .body(())
+            .unwrap();
+
+        assert_eq!(get_client_ip(&req, proxy, &[proxy]), client);
+        assert_eq!(get_client_ip(&req, client, &[proxy]), client);
+        assert_eq!(get_client_ip(&req, proxy, &[]), proxy);
+    }
+}
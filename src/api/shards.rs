@@ -0,0 +1,88 @@
+//! Handles the messages that other shards forward to this one, see `command_handler::sharding`.
+//! Replies are `{"Ok": null}` or `{"Err": "<error>"}`, like in the NATS API
+use anyhow::Context;
+use futures::StreamExt;
+use tokio::task;
+use tracing::{debug, error, info};
+
+use super::webhooks::handle_eventsub_notification;
+use crate::command_handler::{
+    sharding::{ShardMessage, SUBJECT_PREFIX},
+    CommandHandler,
+};
+
+pub fn start_listener(cmd: CommandHandler) {
+    let shard_id = match cmd.sharding.get_shard_id() {
+        Some(shard_id) => shard_id.to_owned(),
+        None => return,
+    };
+
+    task::spawn(async move {
+        let nats_client = cmd.nats_client.wait().await;
+
+        let mut subscriber = match nats_client
+            .subscribe(format!("{SUBJECT_PREFIX}{shard_id}"))
+            .await
+        {
+            Ok(subscriber) => subscriber,
+            Err(err) => {
+                error!("Could not subscribe to the shard subject: {err}");
+                return;
+            }
+        };
+        info!("Listening to messages from other shards");
+
+        while let Some(msg) = subscriber.next().await {
+            let cmd = cmd.clone();
+            let nats_client = nats_client.clone();
+
+            task::spawn(async move {
+                let reply = match msg.reply.clone() {
+                    Some(reply) => reply,
+                    None => {
+                        debug!("Ignoring shard message without a reply subject");
+                        return;
+                    }
+                };
+
+                let result = handle_message(cmd, &msg.payload)
+                    .await
+                    .map_err(|err| format!("{err:#}"));
+                let payload = serde_json::to_vec(&result).expect("Failed to serialize reply");
+
+                if let Err(err) = nats_client.publish(reply, payload.into()).await {
+                    error!("Could not reply to shard message: {err}");
+                }
+            });
+        }
+    });
+}
+
+async fn handle_message(cmd: CommandHandler, payload: &[u8]) -> anyhow::Result<()> {
+    let message: ShardMessage =
+        serde_json::from_slice(payload).context("Malformed shard message")?;
+    debug!("Received shard message {message:?}");
+
+    match message {
+        // Actions can take a while, so the notification is acknowledged once it's accepted
+        ShardMessage::EventSubNotification { body } => {
+            let notification = serde_json::from_str(&body).context("Malformed notification")?;
+
+            task::spawn(async move {
+                if let Err(err) = handle_eventsub_notification(cmd, notification).await {
+                    error!("Could not handle forwarded EventSub notification: {err:#}");
+                }
+            });
+
+            Ok(())
+        }
+        ShardMessage::JoinTwitchChannel { login } => {
+            let platform_handler = cmd.platform_handler.read().await;
+            platform_handler.join_twitch_chat(login).await
+        }
+        ShardMessage::PartTwitchChannel { login } => {
+            let platform_handler = cmd.platform_handler.read().await;
+            platform_handler.part_twitch_chat(login).await
+        }
+    }
+}
@@ -1,4 +1,6 @@
+use super::authentication::flow::OAuthState;
 use crate::command_handler::CommandHandler;
+use crate::database::cache::TtlCache;
 use axum::extract::FromRef;
 use axum_extra::extract::cookie::Key;
 use dashmap::DashMap;
@@ -7,8 +9,9 @@ use std::sync::Arc;
 #[derive(Clone, FromRef)]
 pub struct AppState {
     pub cmd: CommandHandler,
-    pub state_storage: Arc<DashMap<String, String>>,
+    pub state_storage: Arc<DashMap<String, OAuthState>>,
     pub http_client: reqwest::Client,
     pub secret_key: Key,
-    pub raw_secret_key: String,
+    /// Ids of processed EventSub messages, to ignore replays and retries of them
+    pub eventsub_message_ids: Arc<TtlCache<String, ()>>,
 }
@@ -0,0 +1,100 @@
+use super::{error::ApiError, state::AppState, Result};
+use crate::{
+    command_handler::{
+        now_playing::{get_now_playing, NowPlaying},
+        CommandHandler,
+    },
+    database::models::WebSession,
+};
+use axum::{
+    extract::{Path, State},
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+    Json, Router,
+};
+use futures::{stream, Stream};
+use std::{convert::Infallible, time::Duration};
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::warn;
+
+const NOW_PLAYING_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+pub async fn get_user_now_playing(
+    session: Option<WebSession>,
+    Path(user_id): Path<u64>,
+    cmd: State<CommandHandler>,
+) -> Result<Json<Option<NowPlaying>>> {
+    check_now_playing_access(session, user_id, &cmd).await?;
+
+    let now_playing = get_now_playing(&cmd.db, cmd.lastfm_api.as_ref(), user_id).await?;
+
+    Ok(Json(now_playing))
+}
+
+/// Sends a `nowplaying` event with the current track (or `null`) whenever it changes,
+/// for use in stream overlays
+pub async fn user_now_playing_events(
+    session: Option<WebSession>,
+    Path(user_id): Path<u64>,
+    State(cmd): State<CommandHandler>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    check_now_playing_access(session, user_id, &cmd).await?;
+
+    let mut poll_interval = interval(NOW_PLAYING_POLL_INTERVAL);
+    poll_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let events = stream::unfold(
+        (cmd, poll_interval, None),
+        move |(cmd, mut poll_interval, mut last)| async move {
+            loop {
+                poll_interval.tick().await;
+
+                let now_playing =
+                    match get_now_playing(&cmd.db, cmd.lastfm_api.as_ref(), user_id).await {
+                        Ok(now_playing) => now_playing,
+                        Err(err) => {
+                            warn!("Could not get now playing track of user {user_id}: {err:#}");
+                            continue;
+                        }
+                    };
+
+                if last.as_ref() != Some(&now_playing) {
+                    let event = Event::default()
+                        .event("nowplaying")
+                        .json_data(&now_playing)
+                        .expect("Failed to serialize track");
+                    last = Some(now_playing);
+
+                    return Some((Ok(event), (cmd, poll_interval, last)));
+                }
+            }
+        },
+    );
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+async fn check_now_playing_access(
+    session: Option<WebSession>,
+    user_id: u64,
+    cmd: &CommandHandler,
+) -> Result<()> {
+    if session.map_or(false, |session| session.user_id == user_id)
+        || cmd
+            .db
+            .run(move |db| db.get_now_playing_public(user_id))
+            .await?
+    {
+        Ok(())
+    } else {
+        Err(ApiError::Unauthorized(
+            "The user's now playing track is not public".to_owned(),
+        ))
+    }
+}
+
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/:id/nowplaying", get(get_user_now_playing))
+        .route("/:id/nowplaying/events", get(user_now_playing_events))
+}
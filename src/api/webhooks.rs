@@ -1,3 +1,4 @@
+use anyhow::anyhow;
 use async_trait::async_trait;
 use axum::{
     body::Bytes,
@@ -5,165 +6,354 @@ use axum::{
     routing::post,
     Router,
 };
+use chrono::{DateTime, Utc};
 use hmac::{Hmac, Mac};
-use http::{request::Parts, StatusCode};
+use http::{request::Parts, HeaderMap};
 use sha2::Sha256;
-use std::str::FromStr;
+use std::{str::FromStr, time::Duration};
 use tokio::task;
-use tracing::error;
+use tracing::{error, warn};
 
 use crate::{
-    command_handler::twitch_api::eventsub::{events::*, *},
+    command_handler::{
+        outgoing_webhooks::BotEvent,
+        resubscribe_eventsub_trigger,
+        sharding::ShardMessage,
+        twitch_api::eventsub::{events::*, *},
+        CommandHandler,
+    },
     platform::{ChannelIdentifier, ServerPlatformContext, UserIdentifier},
 };
 
-use super::state::AppState;
+use super::{
+    custom_hooks::custom_hook_callback, error::ApiError, github::github_callback, state::AppState,
+};
+
+/// Twitch recommends rejecting messages older than 10 minutes
+const EVENTSUB_MAX_MESSAGE_AGE_MINUTES: i64 = 10;
+/// Older messages are rejected based on their timestamp, so their ids don't need to be kept
+pub const EVENTSUB_MESSAGE_ID_TTL: Duration =
+    Duration::from_secs(EVENTSUB_MAX_MESSAGE_AGE_MINUTES as u64 * 60);
 
 pub async fn eventsub_callback(
     properties: TwitchEventsubCallbackProperties,
     state: State<AppState>,
     body: Bytes,
-) -> Result<String, StatusCode> {
+) -> Result<String, ApiError> {
     tracing::info!("Handling eventsub callback {:?}", properties.message_type);
 
-    let message = serde_json::from_slice(&body).unwrap();
-
-    let secret_key = &state.raw_secret_key;
-
     if properties.message_retry > 1 {
         tracing::warn!("Received EventSub message retry");
     }
 
-    match verify_twitch_signature(&properties, &body, secret_key.as_bytes()).await {
-        true => Ok({
-            tracing::info!("Request signature verified");
+    let secrets = state
+        .cmd
+        .db
+        .run(|db| db.get_accepted_eventsub_secrets())
+        .await?;
+    if !verify_twitch_signature(&properties, &body, &secrets) {
+        tracing::warn!("REQUEST FORGERY DETECTED");
+        return Err(ApiError::Unauthorized("Invalid signature".to_owned()));
+    }
 
-            tracing::info!(
-                "Handling EventSub notification {}",
-                properties.subscription_type
-            );
+    tracing::info!("Request signature verified");
+
+    if !is_recent_timestamp(&properties.message_timestamp, Utc::now()) {
+        tracing::warn!("Rejecting EventSub message with an outdated timestamp");
+        return Err(ApiError::BadRequest(
+            "Outdated message timestamp".to_owned(),
+        ));
+    }
 
-            match properties.message_type {
-                EventSubNotificationType::Notification => {
-                    let notification: EventSubNotification =
-                        serde_json::from_value(message).expect("Invalid message format");
-
-                    let cmd = state.cmd.clone();
-
-                    task::spawn(async move {
-                        let platform_handler = cmd.platform_handler.read().await;
-                        let twitch_api = &platform_handler.twitch_api.as_ref().unwrap().helix_api;
-
-                        if let Some(redeem) = cmd
-                            .db
-                            .get_eventsub_redeem(&notification.subscription.id)
-                            .expect("DB error")
-                        {
-                            let event = notification
-                                .get_event()
-                                .expect("Failed to get notification event");
-
-                            tracing::info!("Received EventSub notification: {:?}", event);
-
-                            let broadcaster_id = event.get_broadcaster_id();
-
-                            let (user_id, arguments) = match event {
-                                EventSubEventType::ChannelUpdate(_)
-                                | EventSubEventType::StreamOnline(_) => {
-                                    (broadcaster_id.clone(), String::new())
-                                }
-                                EventSubEventType::ChannelPointsCustomRewardRedemptionAdd(
-                                    event,
-                                ) => (event.user_id, event.user_input),
-                            };
-
-                            let user = twitch_api
-                                .get_user_by_id(&user_id)
-                                .await
-                                .expect("Failed to get user");
-
-                            let context = ServerPlatformContext {
-                                target_channel: ChannelIdentifier::TwitchChannel((
-                                    broadcaster_id.to_string(),
-                                    None,
-                                )),
-                                executing_user: UserIdentifier::TwitchID(user_id),
-                                cmd: cmd.clone(),
-                                display_name: user.display_name,
-                            };
-
-                            let channel = cmd
-                                .db
-                                .get_channel(&context.target_channel)
-                                .expect("DB error");
-
-                            if let Err(err) = cmd
-                                .handle_server_message(
-                                    redeem.action,
-                                    redeem.mode,
-                                    context,
-                                    arguments
-                                        .split_whitespace()
-                                        .map(|s| s.to_string())
-                                        .collect(),
-                                    channel.map(|channel| channel.id),
-                                )
-                                .await
-                            {
-                                error!("Could not handle event: {err}");
-                            }
-                        } else {
-                            tracing::warn!("Unregistered EventSub notification (no cleanup?)");
-                        }
-                    });
-
-                    String::new()
+    state.eventsub_message_ids.remove_expired();
+    if !state
+        .eventsub_message_ids
+        .insert_if_absent(properties.message_id.clone(), ())
+    {
+        tracing::warn!(
+            "Ignoring duplicate EventSub message {}",
+            properties.message_id
+        );
+        return Ok(String::new());
+    }
+
+    tracing::info!(
+        "Handling EventSub notification {}",
+        properties.subscription_type
+    );
+
+    match properties.message_type {
+        EventSubNotificationType::Notification => {
+            let notification: EventSubNotification = serde_json::from_slice(&body)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid message format: {e}")))?;
+
+            let body = String::from_utf8(body.to_vec())
+                .map_err(|e| ApiError::BadRequest(format!("Invalid message format: {e}")))?;
+
+            let cmd = state.cmd.clone();
+            cmd.status.record_eventsub_notification();
+
+            task::spawn(async move {
+                if let Err(err) = route_eventsub_notification(cmd, notification, body).await {
+                    error!("Could not handle EventSub notification: {err:#}");
                 }
-                EventSubNotificationType::WebhookCallbackVerification => {
-                    let callback: EventSubVerficationCallback =
-                        serde_json::from_value(message).expect("Invalid message format");
+            });
 
-                    callback.challenge
+            Ok(String::new())
+        }
+        EventSubNotificationType::WebhookCallbackVerification => {
+            let callback: EventSubVerficationCallback = serde_json::from_slice(&body)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid message format: {e}")))?;
+
+            Ok(callback.challenge)
+        }
+        EventSubNotificationType::Revocation => {
+            let revocation: EventSubRevocation = serde_json::from_slice(&body)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid message format: {e}")))?;
+
+            tracing::warn!(
+                "EventSub subscription {} was revoked: {}",
+                properties.subscription_type,
+                revocation.subscription.status
+            );
+
+            let cmd = state.cmd.clone();
+            task::spawn(async move {
+                if let Err(err) = handle_eventsub_revocation(cmd, revocation).await {
+                    error!("Could not resubscribe revoked EventSub trigger: {err:#}");
                 }
-                EventSubNotificationType::Revocation => todo!(),
-            }
-        }),
-        false => {
-            tracing::warn!("REQUEST FORGERY DETECTED");
-            Err(StatusCode::UNAUTHORIZED)
+            });
+
+            Ok(String::new())
         }
     }
 }
 
-async fn verify_twitch_signature(
-    properties: &TwitchEventsubCallbackProperties,
-    body: &[u8],
-    secret_key: &[u8],
-) -> bool {
-    let mut hmac_message = Vec::new();
+/// Recreates the subscription of the revoked trigger, which fails if the authorization was revoked.
+/// The reconciliation tries again later in that case.
+async fn handle_eventsub_revocation(
+    cmd: CommandHandler,
+    revocation: EventSubRevocation,
+) -> anyhow::Result<()> {
+    let subscription_id = revocation.subscription.id;
+    let trigger = match cmd
+        .db
+        .run(move |db| db.get_eventsub_redeem(&subscription_id))
+        .await?
+    {
+        Some(trigger) => trigger,
+        None => return Ok(()),
+    };
+
+    let twitch_api = cmd
+        .platform_handler
+        .read()
+        .await
+        .twitch_api
+        .clone()
+        .ok_or_else(|| anyhow!("Twitch is not configured"))?;
+
+    let new_id = resubscribe_eventsub_trigger(&twitch_api, &cmd.db, &trigger).await?;
+    tracing::info!("Resubscribed EventSub trigger {} as {new_id}", trigger.id);
+
+    Ok(())
+}
 
-    hmac_message.extend_from_slice(properties.message_id.as_bytes());
-    hmac_message.extend_from_slice(properties.message_timestamp.as_bytes());
-    hmac_message.extend_from_slice(body);
+/// When sharding, the notification is handled by the shard that owns the broadcaster's channel.
+/// It's handled here if it can't be forwarded, so that it isn't lost
+async fn route_eventsub_notification(
+    cmd: CommandHandler,
+    notification: EventSubNotification,
+    body: String,
+) -> anyhow::Result<()> {
+    if cmd.sharding.is_enabled() {
+        let broadcaster_id = notification.clone().get_event()?.get_broadcaster_id();
+        let channel = cmd
+            .db
+            .run(move |db| {
+                db.get_channel(&ChannelIdentifier::TwitchChannel((broadcaster_id, None)))
+            })
+            .await?;
+
+        let message = ShardMessage::EventSubNotification { body };
+        match cmd
+            .sharding
+            .forward(channel.map(|channel| channel.id), &message)
+            .await
+        {
+            Ok(true) => return Ok(()),
+            Ok(false) => (),
+            Err(e) => warn!("Could not forward EventSub notification, handling it here: {e:#}"),
+        }
+    }
 
-    type HmacSha256 = Hmac<Sha256>;
+    handle_eventsub_notification(cmd, notification).await
+}
 
-    let mut mac = HmacSha256::new_from_slice(secret_key).unwrap();
+pub async fn handle_eventsub_notification(
+    cmd: CommandHandler,
+    notification: EventSubNotification,
+) -> anyhow::Result<()> {
+    let subscription_id = notification.subscription.id.clone();
+    let redeem = match cmd
+        .db
+        .run(move |db| db.get_eventsub_redeem(&subscription_id))
+        .await?
+    {
+        Some(redeem) => redeem,
+        None => {
+            tracing::warn!("Unregistered EventSub notification (no cleanup?)");
+            return Ok(());
+        }
+    };
 
-    mac.update(&hmac_message);
+    let subscription_type = notification.subscription.sub_type.clone();
 
-    let result = mac.finalize();
+    let event = notification.get_event()?;
 
-    let result_bytes = result.into_bytes();
+    tracing::info!("Received EventSub notification: {:?}", event);
 
-    let hmac_signature = hex::encode(result_bytes);
+    let broadcaster_id = event.get_broadcaster_id();
 
-    let expected_signature = properties
+    // The live state is tracked even when the trigger's action is paused
+    let live = match &event {
+        EventSubEventType::StreamOnline(_) => Some(true),
+        EventSubEventType::StreamOffline(_) => Some(false),
+        _ => None,
+    };
+    if let Some(live) = live {
+        if let Some(twitch_api) = &cmd.platform_handler.read().await.twitch_api {
+            twitch_api.set_live(&broadcaster_id, live);
+        }
+    }
+
+    if redeem.paused {
+        tracing::debug!("EventSub trigger {} is paused", redeem.id);
+        return Ok(());
+    }
+    let target_channel = ChannelIdentifier::TwitchChannel((broadcaster_id.clone(), None));
+    let channel = {
+        let target_channel = target_channel.clone();
+        cmd.db
+            .run(move |db| db.get_channel(&target_channel))
+            .await?
+    };
+
+    if let (EventSubEventType::ChannelRaid(event), Some(channel)) = (&event, &channel) {
+        let channel_id = channel.id;
+        let min_viewers = cmd
+            .db
+            .run(move |db| db.get_channel_settings(channel_id))
+            .await?
+            .raid_min_viewers;
+
+        if event.viewers < min_viewers {
+            tracing::debug!(
+                "Ignoring raid from {} with {} viewers",
+                event.from_broadcaster_user_login,
+                event.viewers
+            );
+            return Ok(());
+        }
+    }
+
+    let (user_id, arguments, redemption) = match event {
+        EventSubEventType::ChannelUpdate(_)
+        | EventSubEventType::StreamOnline(_)
+        | EventSubEventType::StreamOffline(_) => (broadcaster_id.clone(), String::new(), None),
+        EventSubEventType::ChannelPointsCustomRewardRedemptionAdd(event) => {
+            let redemption = event.pending_redemption();
+            (event.user_id, event.user_input, redemption)
+        }
+        // Commands are executed as the moderator who toggled shield mode
+        EventSubEventType::ShieldModeBegin(event) => (event.moderator_user_id, String::new(), None),
+        EventSubEventType::ShieldModeEnd(event) => (event.moderator_user_id, String::new(), None),
+        // Raid actions run as the broadcaster, so that they are allowed to shout out the raider
+        EventSubEventType::ChannelRaid(event) => {
+            let arguments = format!("{} {}", event.from_broadcaster_user_login, event.viewers);
+            (broadcaster_id.clone(), arguments, None)
+        }
+    };
+
+    cmd.webhook_dispatcher.dispatch(
+        &target_channel,
+        BotEvent::EventsubTriggered {
+            subscription_type,
+            user_id: user_id.clone(),
+            arguments: arguments.clone(),
+        },
+    );
+
+    let user = {
+        let platform_handler = cmd.platform_handler.read().await;
+        let twitch_api = platform_handler
+            .twitch_api
+            .as_ref()
+            .ok_or_else(|| anyhow!("Twitch is not configured"))?;
+
+        twitch_api.helix_api.get_user_by_id(&user_id).await?
+    };
+
+    let context = ServerPlatformContext {
+        target_channel,
+        executing_user: UserIdentifier::TwitchID(user_id),
+        cmd: cmd.clone(),
+        display_name: user.display_name,
+    };
+
+    cmd.handle_server_message(
+        redeem.action,
+        redeem.mode,
+        context,
+        arguments
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect(),
+        channel.map(|channel| channel.id),
+        None,
+        redemption,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Checks the signature against every accepted secret, so that notifications of subscriptions
+/// which were created with the previous secret are still accepted during a rotation
+fn verify_twitch_signature(
+    properties: &TwitchEventsubCallbackProperties,
+    body: &[u8],
+    secrets: &[String],
+) -> bool {
+    let signature = match properties
         .message_signature
         .strip_prefix("sha256=")
-        .unwrap();
+        .and_then(|signature| hex::decode(signature).ok())
+    {
+        Some(signature) => signature,
+        None => return false,
+    };
+
+    type HmacSha256 = Hmac<Sha256>;
+
+    secrets.iter().any(|secret| {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+
+        mac.update(properties.message_id.as_bytes());
+        mac.update(properties.message_timestamp.as_bytes());
+        mac.update(body);
+
+        // Compares in constant time
+        mac.verify_slice(&signature).is_ok()
+    })
+}
 
-    hmac_signature == expected_signature
+/// Messages that are too old could be replayed after their id was forgotten
+fn is_recent_timestamp(timestamp: &str, now: DateTime<Utc>) -> bool {
+    DateTime::parse_from_rfc3339(timestamp).map_or(false, |timestamp| {
+        let age = now - timestamp.with_timezone(&Utc);
+        age.num_minutes().abs() < EVENTSUB_MAX_MESSAGE_AGE_MINUTES
+    })
 }
 
 #[derive(Debug)]
@@ -178,55 +368,54 @@ pub struct TwitchEventsubCallbackProperties {
 
 #[async_trait]
 impl FromRequestParts<AppState> for TwitchEventsubCallbackProperties {
-    type Rejection = ();
+    type Rejection = ApiError;
 
     async fn from_request_parts(parts: &mut Parts, _: &AppState) -> Result<Self, Self::Rejection> {
         let headers = &parts.headers;
 
         Ok(Self {
-            message_id: headers
-                .get("Twitch-Eventsub-Message-Id")
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_owned(),
-            message_retry: headers
-                .get("Twitch-Eventsub-Message-Retry")
-                .unwrap()
-                .to_str()
-                .unwrap()
+            message_id: get_header(headers, "Twitch-Eventsub-Message-Id")?.to_owned(),
+            message_retry: get_header(headers, "Twitch-Eventsub-Message-Retry")?
                 .parse()
-                .unwrap(),
-            message_type: EventSubNotificationType::from_str(
-                headers
-                    .get("Twitch-Eventsub-Message-Type")
-                    .unwrap()
-                    .to_str()
-                    .unwrap(),
-            )
-            .expect("Invalid message type!"),
-            message_signature: headers
-                .get("Twitch-Eventsub-Message-Signature")
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_owned(),
-            message_timestamp: headers
-                .get("Twitch-Eventsub-Message-Timestamp")
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_owned(),
-            subscription_type: headers
-                .get("Twitch-Eventsub-Subscription-Type")
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_owned(),
+                .map_err(|_| ApiError::BadRequest("Invalid message retry".to_owned()))?,
+            message_type: EventSubNotificationType::from_str(get_header(
+                headers,
+                "Twitch-Eventsub-Message-Type",
+            )?)
+            .map_err(|_| ApiError::BadRequest("Invalid message type".to_owned()))?,
+            message_signature: get_header(headers, "Twitch-Eventsub-Message-Signature")?.to_owned(),
+            message_timestamp: get_header(headers, "Twitch-Eventsub-Message-Timestamp")?.to_owned(),
+            subscription_type: get_header(headers, "Twitch-Eventsub-Subscription-Type")?.to_owned(),
         })
     }
 }
 
+fn get_header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, ApiError> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| ApiError::BadRequest(format!("Missing header {name}")))
+}
+
 pub fn create_router() -> Router<AppState> {
-    Router::new().route("/twitch/eventsub", post(eventsub_callback))
+    Router::new()
+        .route("/twitch/eventsub", post(eventsub_callback))
+        .route("/github/:channel_id/:secret", post(github_callback))
+        .route("/custom/:channel_id/:hook_id", post(custom_hook_callback))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_recent_timestamp;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn rejects_outdated_timestamps() {
+        let now = Utc.with_ymd_and_hms(2023, 10, 22, 14, 0, 0).unwrap();
+
+        assert!(is_recent_timestamp("2023-10-22T13:55:00.123456789Z", now));
+        assert!(!is_recent_timestamp("2023-10-22T13:49:59Z", now));
+        assert!(!is_recent_timestamp("2023-10-22T14:11:00Z", now));
+        assert!(!is_recent_timestamp("not a timestamp", now));
+    }
 }
@@ -0,0 +1,234 @@
+//! Command line client for the GRPC server, for managing the bot without the web dashboard
+use clap::{Parser, Subcommand};
+use foobot::{
+    foobot_client::FoobotClient, DeleteCommandRequest, ExecuteCommandRequest, ListChannelsRequest,
+    ListCommandsRequest, SetCommandRequest, StreamEventsRequest,
+};
+use std::process;
+use tonic::{
+    codegen::InterceptedService,
+    metadata::{Ascii, MetadataValue},
+    service::Interceptor,
+    transport::{Channel, Endpoint},
+    Request, Status,
+};
+
+pub mod foobot {
+    tonic::include_proto!("foobot");
+}
+
+#[derive(Parser)]
+#[command(name = "foobot-cli")]
+struct Args {
+    /// Address of the GRPC server
+    #[arg(
+        long,
+        env = "FOOBOT_GRPC_ADDRESS",
+        default_value = "http://127.0.0.1:50051"
+    )]
+    address: String,
+    /// Same as `grpc.token` in the bot's config
+    #[arg(long, env = "FOOBOT_GRPC_TOKEN")]
+    token: Option<String>,
+    #[command(subcommand)]
+    command: CliCommand,
+}
+
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Runs a message in the channel as the given user and prints the response
+    Exec {
+        channel_id: u64,
+        /// Identifier of the executing user, e.g. `twitch:12345`
+        #[arg(long)]
+        user: String,
+        #[arg(long, default_value = "foobot-cli")]
+        display_name: String,
+        /// Also send the response to the channel
+        #[arg(long)]
+        send: bool,
+        #[arg(required = true)]
+        message: Vec<String>,
+    },
+    /// Prints channel events as they happen, from every channel if none is given
+    Events { channel_id: Option<u64> },
+    /// Lists the channels the bot knows about
+    Channels,
+    #[command(subcommand)]
+    Commands(CommandsCommand),
+}
+
+/// Manages the custom commands of a channel
+#[derive(Subcommand)]
+enum CommandsCommand {
+    List {
+        channel_id: u64,
+    },
+    /// Adds the command, or updates its action if it already exists
+    Set {
+        channel_id: u64,
+        name: String,
+        #[arg(required = true)]
+        action: Vec<String>,
+        /// `template` or `hebi`
+        #[arg(long)]
+        mode: Option<String>,
+    },
+    Delete {
+        channel_id: u64,
+        name: String,
+    },
+}
+
+type Client = FoobotClient<InterceptedService<Channel, AuthInterceptor>>;
+
+/// Sends the token the same way the server expects it
+#[derive(Clone)]
+struct AuthInterceptor {
+    token: Option<MetadataValue<Ascii>>,
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(token) = &self.token {
+            request
+                .metadata_mut()
+                .insert("authorization", token.clone());
+        }
+        Ok(request)
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+
+    let client = match connect(&args.address, args.token).await {
+        Ok(client) => client,
+        Err(err) => {
+            eprintln!("Could not connect to {}: {err}", args.address);
+            process::exit(1);
+        }
+    };
+
+    if let Err(status) = run(client, args.command).await {
+        eprintln!("Error: {}", status.message());
+        process::exit(1);
+    }
+}
+
+async fn connect(address: &str, token: Option<String>) -> anyhow::Result<Client> {
+    let channel = Endpoint::from_shared(address.to_owned())?.connect().await?;
+
+    let token = token
+        .map(|token| MetadataValue::try_from(format!("Bearer {token}")))
+        .transpose()?;
+
+    Ok(FoobotClient::with_interceptor(
+        channel,
+        AuthInterceptor { token },
+    ))
+}
+
+async fn run(mut client: Client, command: CliCommand) -> Result<(), Status> {
+    match command {
+        CliCommand::Exec {
+            channel_id,
+            user,
+            display_name,
+            send,
+            message,
+        } => {
+            let response = client
+                .execute_command(ExecuteCommandRequest {
+                    channel_id,
+                    user,
+                    display_name,
+                    message: message.join(" "),
+                    send_response: send,
+                })
+                .await?
+                .into_inner();
+
+            match response.response {
+                Some(response) => println!("{response}"),
+                None => eprintln!("No response"),
+            }
+        }
+        CliCommand::Events { channel_id } => {
+            let mut events = client
+                .stream_events(StreamEventsRequest {
+                    channel_id: channel_id.unwrap_or_default(),
+                })
+                .await?
+                .into_inner();
+
+            while let Some(event) = events.message().await? {
+                println!(
+                    "{} #{} {} {}",
+                    event.timestamp, event.channel_id, event.event, event.data
+                );
+            }
+        }
+        CliCommand::Channels => {
+            let channels = client
+                .list_channels(ListChannelsRequest {})
+                .await?
+                .into_inner()
+                .channels;
+
+            for channel in channels {
+                let status = if channel.joined { "" } else { " (not joined)" };
+                println!(
+                    "{}\t{}:{}{status}",
+                    channel.id, channel.platform, channel.channel
+                );
+            }
+        }
+        CliCommand::Commands(CommandsCommand::List { channel_id }) => {
+            let commands = client
+                .list_commands(ListCommandsRequest { channel_id })
+                .await?
+                .into_inner()
+                .commands;
+
+            for command in commands {
+                println!("{} ({}): {}", command.name, command.mode, command.action);
+            }
+        }
+        CliCommand::Commands(CommandsCommand::Set {
+            channel_id,
+            name,
+            action,
+            mode,
+        }) => {
+            let response = client
+                .set_command(SetCommandRequest {
+                    channel_id,
+                    name: name.clone(),
+                    action: action.join(" "),
+                    mode,
+                })
+                .await?
+                .into_inner();
+
+            if response.created {
+                println!("Added command {name}");
+            } else {
+                println!("Updated command {name}");
+            }
+        }
+        CliCommand::Commands(CommandsCommand::Delete { channel_id, name }) => {
+            client
+                .delete_command(DeleteCommandRequest {
+                    channel_id,
+                    name: name.clone(),
+                })
+                .await?;
+
+            println!("Deleted command {name}");
+        }
+    }
+
+    Ok(())
+}
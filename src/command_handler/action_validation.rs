@@ -0,0 +1,149 @@
+//! Checks command actions without executing them, for inline validation in the dashboard editor
+use super::eval::storage::ModuleStorage;
+use handlebars::template::{HelperTemplate, Parameter, Template, TemplateElement, TemplateMapping};
+use handlebars::Handlebars;
+use hebi::prelude::*;
+use serde::Serialize;
+
+/// Modules that are registered for every hebi execution
+const HEBI_NATIVE_MODULES: &[&str] = &["http", "utils", "context", "db", "ai", "chat"];
+
+/// Lines and columns start at 1
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ActionValidation {
+    pub errors: Vec<Diagnostic>,
+    pub warnings: Vec<Diagnostic>,
+}
+
+pub fn validate_template(registry: &Handlebars, source: &str) -> ActionValidation {
+    let mut validation = ActionValidation::default();
+
+    match Template::compile(source) {
+        Ok(template) => check_template_helpers(registry, &template, &mut validation.warnings),
+        Err(err) => validation.errors.push(Diagnostic {
+            message: err.reason().to_string(),
+            line: err.line_no,
+            column: err.column_no,
+        }),
+    }
+
+    validation
+}
+
+fn check_template_helpers(
+    registry: &Handlebars,
+    template: &Template,
+    warnings: &mut Vec<Diagnostic>,
+) {
+    for (i, element) in template.elements.iter().enumerate() {
+        match element {
+            TemplateElement::Expression(helper)
+            | TemplateElement::HtmlExpression(helper)
+            | TemplateElement::HelperBlock(helper) => {
+                check_helper(registry, helper, template.mapping.get(i), warnings)
+            }
+            _ => (),
+        }
+    }
+}
+
+fn check_helper(
+    registry: &Handlebars,
+    helper: &HelperTemplate,
+    position: Option<&TemplateMapping>,
+    warnings: &mut Vec<Diagnostic>,
+) {
+    // Expressions without any parameters are usually variables rather than helpers
+    let is_helper_call = helper.block || !helper.params.is_empty() || !helper.hash.is_empty();
+
+    if is_helper_call {
+        if let Some(name) = helper.name.as_name() {
+            if registry.get_helper(name).is_none() {
+                warnings.push(Diagnostic {
+                    message: format!("Unknown helper `{name}`"),
+                    line: position.map(|mapping| mapping.0),
+                    column: position.map(|mapping| mapping.1),
+                });
+            }
+        }
+    }
+
+    for param in helper.params.iter().chain(helper.hash.values()) {
+        if let Parameter::Subexpression(subexpression) = param {
+            if let TemplateElement::Expression(helper) = subexpression.element.as_ref() {
+                check_helper(registry, helper, position, warnings);
+            }
+        }
+    }
+
+    for template in helper.template.iter().chain(helper.inverse.iter()) {
+        check_template_helpers(registry, template, warnings);
+    }
+}
+
+pub fn validate_hebi(module_storage: &ModuleStorage, source: &str) -> ActionValidation {
+    let mut validation = ActionValidation::default();
+
+    let hebi = Hebi::builder().finish();
+    if let Err(err) = hebi.compile(source) {
+        // Hebi renders the position into the message along with a snippet of the source
+        validation.errors.push(Diagnostic {
+            message: err.to_string(),
+            line: None,
+            column: None,
+        });
+    }
+
+    let modules = module_storage.modules.load();
+    for (line, column, module) in get_hebi_imports(source) {
+        if !HEBI_NATIVE_MODULES.contains(&module) && !modules.contains_key(module) {
+            validation.warnings.push(Diagnostic {
+                message: format!("Unknown module `{module}`"),
+                line: Some(line),
+                column: Some(column),
+            });
+        }
+    }
+
+    validation
+}
+
+/// Modules imported with `import name` or `from name import ...`, with their positions
+fn get_hebi_imports(source: &str) -> Vec<(usize, usize, &str)> {
+    source
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let trimmed = line.trim_start();
+            let indent = line.len() - trimmed.len();
+
+            let mut words = trimmed.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some(keyword @ ("import" | "from")), Some(module)) => {
+                    let offset = trimmed[keyword.len()..].find(module)?;
+                    Some((i + 1, indent + keyword.len() + offset + 1, module))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_hebi_imports;
+
+    #[test]
+    fn finds_hebi_imports() {
+        let source = "import http\nvalue = 1\n  from utils import format\nimported = 2";
+
+        assert_eq!(get_hebi_imports(source), [(1, 8, "http"), (3, 8, "utils")]);
+    }
+}
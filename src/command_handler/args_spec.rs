@@ -0,0 +1,235 @@
+//! Declarative arguments of custom commands, such as `user:mention amount:int reason:rest?`.
+//!
+//! The arguments are checked before the action is executed, and their values are available
+//! as `params` in templates (`{{params.amount}}`) and Hebi (`params["amount"]`)
+use serde_json::{Map, Number, Value};
+use std::{fmt, str::FromStr};
+use strum::EnumString;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgsSpec {
+    params: Vec<Param>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Param {
+    name: String,
+    kind: ParamKind,
+    optional: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString)]
+#[strum(serialize_all = "lowercase")]
+enum ParamKind {
+    Word,
+    Int,
+    Number,
+    /// A user name with an optional `@`, or a Discord mention
+    Mention,
+    /// Every remaining argument joined with spaces, only allowed as the last one
+    Rest,
+}
+
+impl FromStr for ArgsSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut params: Vec<Param> = Vec::new();
+
+        for raw_param in s.split_whitespace() {
+            let (raw_param, optional) = match raw_param.strip_suffix('?') {
+                Some(raw_param) => (raw_param, true),
+                None => (raw_param, false),
+            };
+            let (name, kind) = match raw_param.split_once(':') {
+                Some((name, raw_kind)) => {
+                    let kind = ParamKind::from_str(raw_kind).map_err(|_| {
+                        format!(
+                            "unknown argument type {raw_kind}, \
+                            expected word, int, number, mention or rest"
+                        )
+                    })?;
+                    (name, kind)
+                }
+                None => (raw_param, ParamKind::Word),
+            };
+
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!(
+                    "invalid argument name {name}, only letters, digits and _ are allowed"
+                ));
+            }
+            if params.iter().any(|param| param.name == name) {
+                return Err(format!("argument {name} is specified twice"));
+            }
+            if let Some(previous) = params.last() {
+                if previous.kind == ParamKind::Rest {
+                    return Err(format!(
+                        "{} takes the rest, it has to be last",
+                        previous.name
+                    ));
+                }
+                if previous.optional && !optional {
+                    return Err(format!(
+                        "{name} is required, so it can't come after the optional {}",
+                        previous.name
+                    ));
+                }
+            }
+
+            params.push(Param {
+                name: name.to_owned(),
+                kind,
+                optional,
+            });
+        }
+
+        Ok(Self { params })
+    }
+}
+
+impl fmt::Display for ArgsSpec {
+    /// The usage of the command, such as `<user> <amount> [reason...]`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let usage = self
+            .params
+            .iter()
+            .map(|param| {
+                let name = match param.kind {
+                    ParamKind::Rest => format!("{}...", param.name),
+                    _ => param.name.clone(),
+                };
+
+                match param.optional {
+                    true => format!("[{name}]"),
+                    false => format!("<{name}>"),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        f.write_str(&usage)
+    }
+}
+
+impl ArgsSpec {
+    /// Missing optional arguments are `null`
+    pub fn parse(&self, args: &[String]) -> Result<Map<String, Value>, String> {
+        let mut values = Map::new();
+        let mut args = args.iter();
+
+        for param in &self.params {
+            let value = match param.kind {
+                ParamKind::Rest => {
+                    let rest = args.by_ref().map(String::as_str).collect::<Vec<_>>();
+                    match rest.is_empty() {
+                        true => None,
+                        false => Some(Value::String(rest.join(" "))),
+                    }
+                }
+                kind => match args.next() {
+                    Some(arg) => Some(parse_value(kind, &param.name, arg)?),
+                    None => None,
+                },
+            };
+
+            match value {
+                Some(value) => {
+                    values.insert(param.name.clone(), value);
+                }
+                None if param.optional => {
+                    values.insert(param.name.clone(), Value::Null);
+                }
+                None => return Err(format!("missing {}", param.name)),
+            }
+        }
+
+        if args.next().is_some() {
+            return Err("too many arguments".to_owned());
+        }
+
+        Ok(values)
+    }
+}
+
+fn parse_value(kind: ParamKind, name: &str, arg: &str) -> Result<Value, String> {
+    match kind {
+        ParamKind::Word | ParamKind::Rest => Ok(Value::String(arg.to_owned())),
+        ParamKind::Int => arg
+            .parse::<i64>()
+            .map(Value::from)
+            .map_err(|_| format!("{name} must be a whole number")),
+        ParamKind::Number => arg
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| format!("{name} must be a number")),
+        ParamKind::Mention => {
+            let user = arg
+                .trim_start_matches('@')
+                .trim_start_matches("<@")
+                .trim_start_matches('!')
+                .trim_end_matches('>');
+
+            match user.is_empty() {
+                true => Err(format!("{name} must be a user")),
+                false => Ok(Value::String(user.to_owned())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArgsSpec;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn parses_typed_arguments() {
+        let spec = ArgsSpec::from_str("user:mention amount:int reason:rest?").unwrap();
+        assert_eq!(spec.to_string(), "<user> <amount> [reason...]");
+
+        let values = spec.parse(&args("@someone 5 being nice")).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(values),
+            json!({ "user": "someone", "amount": 5, "reason": "being nice" })
+        );
+
+        let values = spec.parse(&args("<@!1234> 5")).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(values),
+            json!({ "user": "1234", "amount": 5, "reason": null })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        let spec = ArgsSpec::from_str("amount:int name").unwrap();
+
+        assert_eq!(
+            spec.parse(&args("five x")).unwrap_err(),
+            "amount must be a whole number"
+        );
+        assert_eq!(spec.parse(&args("5")).unwrap_err(), "missing name");
+        assert_eq!(
+            spec.parse(&args("5 x y")).unwrap_err(),
+            "too many arguments"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        assert!(ArgsSpec::from_str("reason:rest user").is_err());
+        assert!(ArgsSpec::from_str("amount:int? user").is_err());
+        assert!(ArgsSpec::from_str("amount:float").is_err());
+        assert!(ArgsSpec::from_str("user user").is_err());
+        assert!(ArgsSpec::from_str("a-b").is_err());
+    }
+}
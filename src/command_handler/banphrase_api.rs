@@ -0,0 +1,156 @@
+use crate::database::{models::BanphraseApiConfig, Database, DatabaseError};
+use anyhow::anyhow;
+use dashmap::DashMap;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+const CACHE_TTL: Duration = Duration::from_secs(600);
+const MAX_CACHED_RESULTS: usize = 1000;
+const MASKED_MESSAGE: &str = "[Banphrased]";
+
+#[derive(Deserialize)]
+struct BanphraseTestResponse {
+    banned: bool,
+}
+
+/// Checks outgoing messages against the pajbot-style banphrase APIs configured by channels.
+/// The results are cached, and messages are let through if the API can't be reached in time
+#[derive(Debug, Clone)]
+pub struct BanphraseApi {
+    db: Database,
+    client: Client,
+    configs: Arc<DashMap<u64, Option<BanphraseApiConfig>>>,
+    results: Arc<DashMap<(String, String), (Instant, bool)>>, // API url and message
+}
+
+impl BanphraseApi {
+    pub fn new(db: Database) -> Self {
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self {
+            db,
+            client,
+            configs: Arc::new(DashMap::new()),
+            results: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub async fn get_config(
+        &self,
+        channel_id: u64,
+    ) -> Result<Option<BanphraseApiConfig>, DatabaseError> {
+        if let Some(config) = self.configs.get(&channel_id) {
+            return Ok(config.clone());
+        }
+
+        let config = self
+            .db
+            .run(move |db| db.get_banphrase_api(channel_id))
+            .await?;
+        self.configs.insert(channel_id, config.clone());
+
+        Ok(config)
+    }
+
+    pub async fn set_config(&self, config: BanphraseApiConfig) -> Result<(), DatabaseError> {
+        let config = self
+            .db
+            .run(move |db| {
+                db.set_banphrase_api(&config)?;
+                Ok::<_, DatabaseError>(config)
+            })
+            .await?;
+        self.configs.insert(config.channel_id, Some(config));
+
+        Ok(())
+    }
+
+    pub async fn remove_config(&self, channel_id: u64) -> Result<(), DatabaseError> {
+        self.db
+            .run(move |db| db.delete_banphrase_api(channel_id))
+            .await?;
+        self.configs.insert(channel_id, None);
+
+        Ok(())
+    }
+
+    /// Masks or clears the message if the channel's banphrase API considers it banned
+    pub async fn check_message(&self, channel_id: u64, message: &mut String) {
+        if message.is_empty() {
+            return;
+        }
+
+        let config = match self.get_config(channel_id).await {
+            Ok(Some(config)) => config,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("Could not get banphrase API config: {e}");
+                return;
+            }
+        };
+
+        match self.is_banned(&config.url, message).await {
+            Ok(true) => {
+                tracing::info!("Message {message} is banphrased in channel {channel_id}");
+
+                if config.block_message {
+                    message.clear();
+                } else {
+                    *message = MASKED_MESSAGE.to_owned();
+                }
+            }
+            Ok(false) => (),
+            Err(e) => tracing::warn!("Banphrase check with {} failed: {e}", config.url),
+        }
+    }
+
+    pub fn clear_cache(&self) {
+        self.configs.clear();
+        self.results.clear();
+    }
+
+    async fn is_banned(&self, url: &str, message: &str) -> anyhow::Result<bool> {
+        let key = (url.to_owned(), message.to_owned());
+
+        if let Some(entry) = self.results.get(&key) {
+            let (checked_at, banned) = entry.value();
+
+            if checked_at.elapsed() < CACHE_TTL {
+                return Ok(*banned);
+            }
+        }
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/api/v1/banphrases/test",
+                url.trim_end_matches('/')
+            ))
+            .json(&json!({ "message": message }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Banphrase API error: {}", response.status()));
+        }
+
+        let banned = response.json::<BanphraseTestResponse>().await?.banned;
+
+        if self.results.len() >= MAX_CACHED_RESULTS {
+            self.results
+                .retain(|_, (checked_at, _)| checked_at.elapsed() < CACHE_TTL);
+        }
+        self.results.insert(key, (Instant::now(), banned));
+
+        Ok(banned)
+    }
+}
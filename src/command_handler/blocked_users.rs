@@ -0,0 +1,140 @@
+use crate::{
+    database::{models::BlockedUser, Database, DatabaseError},
+    platform::UserIdentifier,
+};
+use dashmap::DashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// Set once the legacy `blocked_users` setting has been imported,
+/// so that users unblocked afterwards aren't blocked again on the next start
+const LEGACY_IMPORT_MARKER: &str = "legacy_blocked_users_imported";
+
+/// Users that the bot ignores, either globally (no channel id) or in a specific channel
+#[derive(Debug, Clone)]
+pub struct BlockedUsers {
+    db: Database,
+    cache: Arc<DashMap<Option<u64>, HashSet<UserIdentifier>>>,
+}
+
+impl BlockedUsers {
+    /// Users from the legacy `blocked_users` setting are imported as global blocks, only once
+    pub fn load(db: Database, legacy_blocked_users: &[String]) -> Result<Self, DatabaseError> {
+        let blocked_users = Self {
+            db,
+            cache: Arc::new(DashMap::new()),
+        };
+        import_blocked_users(&blocked_users.db, legacy_blocked_users)?;
+        blocked_users.fill_cache(blocked_users.db.get_blocked_users()?)?;
+
+        Ok(blocked_users)
+    }
+
+    /// Adds global blocks to the DB, the cache has to be reloaded afterwards
+    pub async fn import(&self, raw_identifiers: &[String]) -> Result<(), DatabaseError> {
+        let raw_identifiers = raw_identifiers.to_vec();
+        self.db
+            .run(move |db| import_blocked_users(db, &raw_identifiers))
+            .await
+    }
+
+    pub async fn reload(&self) -> Result<(), DatabaseError> {
+        let blocked_users = self.db.run(|db| db.get_blocked_users()).await?;
+        self.fill_cache(blocked_users)
+    }
+
+    fn fill_cache(&self, entries: Vec<BlockedUser>) -> Result<(), DatabaseError> {
+        let mut blocked_users: HashMap<Option<u64>, HashSet<UserIdentifier>> = HashMap::new();
+
+        for blocked_user in entries {
+            let identifier = UserIdentifier::from_string(&blocked_user.identifier)?;
+
+            blocked_users
+                .entry(blocked_user.channel_id)
+                .or_default()
+                .insert(identifier);
+        }
+
+        self.cache.clear();
+        self.cache.extend(blocked_users);
+
+        Ok(())
+    }
+
+    pub fn is_blocked(&self, identifier: &UserIdentifier, channel_id: Option<u64>) -> bool {
+        let blocked_in = |channel_id| {
+            self.cache
+                .get(&channel_id)
+                .map(|users| users.contains(identifier))
+                .unwrap_or(false)
+        };
+
+        blocked_in(None) || (channel_id.is_some() && blocked_in(channel_id))
+    }
+
+    /// Returns the users blocked in the given channel, or the global blocks when no channel is given
+    pub fn get(&self, channel_id: Option<u64>) -> Vec<UserIdentifier> {
+        self.cache
+            .get(&channel_id)
+            .map(|users| users.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub async fn block(
+        &self,
+        identifier: UserIdentifier,
+        channel_id: Option<u64>,
+    ) -> Result<(), DatabaseError> {
+        let identifier = self
+            .db
+            .run(move |db| {
+                db.add_blocked_user(&identifier, channel_id)?;
+                Ok::<_, DatabaseError>(identifier)
+            })
+            .await?;
+        self.cache.entry(channel_id).or_default().insert(identifier);
+
+        Ok(())
+    }
+
+    pub async fn unblock(
+        &self,
+        identifier: &UserIdentifier,
+        channel_id: Option<u64>,
+    ) -> Result<(), DatabaseError> {
+        let owned_identifier = identifier.clone();
+        self.db
+            .run(move |db| db.remove_blocked_user(&owned_identifier, channel_id))
+            .await?;
+
+        if let Some(mut users) = self.cache.get_mut(&channel_id) {
+            users.remove(identifier);
+        }
+
+        Ok(())
+    }
+}
+
+fn import_blocked_users(db: &Database, raw_identifiers: &[String]) -> Result<(), DatabaseError> {
+    if raw_identifiers.is_empty() {
+        return Ok(());
+    }
+
+    if db.get_auth(LEGACY_IMPORT_MARKER)?.is_some() {
+        tracing::warn!(
+            "The blocked_users setting has already been imported and is ignored, \
+            use the block command or the admin API to manage blocked users"
+        );
+        return Ok(());
+    }
+
+    for raw_identifier in raw_identifiers {
+        let identifier = UserIdentifier::from_string(raw_identifier)?;
+        db.add_blocked_user(&identifier, None)?;
+    }
+    db.set_auth(LEGACY_IMPORT_MARKER, "true")?;
+
+    Ok(())
+}
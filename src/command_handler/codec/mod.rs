@@ -0,0 +1,264 @@
+//! Novelty encodings of text, available as template helpers and in the Hebi `codec` module
+mod forsencode;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use strum::{EnumString, IntoStaticStr};
+
+/// Longer inputs are rejected, as some of the encodings are many times larger than the text
+pub const MAX_INPUT_LENGTH: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum Codec {
+    Forsencode,
+    Base64,
+    Rot13,
+    Binary,
+    Morse,
+    /// Can't be decoded, as multiple letters are encoded the same way
+    Leet,
+}
+
+impl Codec {
+    pub const ALL: [Codec; 6] = [
+        Codec::Forsencode,
+        Codec::Base64,
+        Codec::Rot13,
+        Codec::Binary,
+        Codec::Morse,
+        Codec::Leet,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        self.into()
+    }
+
+    pub fn can_decode(&self) -> bool {
+        !matches!(self, Codec::Leet)
+    }
+
+    pub fn encode(&self, text: &str) -> Result<String, String> {
+        check_length(text)?;
+
+        match self {
+            Codec::Forsencode => Ok(forsencode::encode(text)),
+            Codec::Base64 => Ok(BASE64.encode(text)),
+            Codec::Rot13 => Ok(rot13(text)),
+            Codec::Binary => Ok(text
+                .bytes()
+                .map(|byte| format!("{byte:08b}"))
+                .collect::<Vec<_>>()
+                .join(" ")),
+            Codec::Morse => encode_morse(text),
+            Codec::Leet => Ok(text.chars().map(leet_char).collect()),
+        }
+    }
+
+    pub fn decode(&self, text: &str) -> Result<String, String> {
+        check_length(text)?;
+
+        match self {
+            Codec::Forsencode => forsencode::decode(text),
+            Codec::Base64 => {
+                let bytes = BASE64
+                    .decode(text.trim())
+                    .map_err(|err| format!("Invalid base64: {err}"))?;
+                String::from_utf8(bytes).map_err(|_| "The decoded data is not text".to_owned())
+            }
+            Codec::Rot13 => Ok(rot13(text)),
+            Codec::Binary => decode_binary(text),
+            Codec::Morse => decode_morse(text),
+            Codec::Leet => Err("leet can't be decoded".to_owned()),
+        }
+    }
+}
+
+fn check_length(text: &str) -> Result<(), String> {
+    if text.chars().count() > MAX_INPUT_LENGTH {
+        Err(format!(
+            "The text can be at most {MAX_INPUT_LENGTH} characters long"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn rot13(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'a'..='z' => ((c as u8 - b'a' + 13) % 26 + b'a') as char,
+            'A'..='Z' => ((c as u8 - b'A' + 13) % 26 + b'A') as char,
+            _ => c,
+        })
+        .collect()
+}
+
+/// Accepts bytes separated by whitespace, or written together
+fn decode_binary(text: &str) -> Result<String, String> {
+    let digits = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<char>>();
+
+    if digits.len() % 8 != 0 {
+        return Err("Binary text has to consist of 8 bit bytes".to_owned());
+    }
+
+    let bytes = digits
+        .chunks(8)
+        .map(|chunk| {
+            let byte = chunk.iter().collect::<String>();
+            u8::from_str_radix(&byte, 2).map_err(|_| format!("Invalid byte {byte}"))
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+
+    String::from_utf8(bytes).map_err(|_| "The decoded data is not text".to_owned())
+}
+
+const MORSE_TABLE: [(char, &str); 46] = [
+    ('a', ".-"),
+    ('b', "-..."),
+    ('c', "-.-."),
+    ('d', "-.."),
+    ('e', "."),
+    ('f', "..-."),
+    ('g', "--."),
+    ('h', "...."),
+    ('i', ".."),
+    ('j', ".---"),
+    ('k', "-.-"),
+    ('l', ".-.."),
+    ('m', "--"),
+    ('n', "-."),
+    ('o', "---"),
+    ('p', ".--."),
+    ('q', "--.-"),
+    ('r', ".-."),
+    ('s', "..."),
+    ('t', "-"),
+    ('u', "..-"),
+    ('v', "...-"),
+    ('w', ".--"),
+    ('x', "-..-"),
+    ('y', "-.--"),
+    ('z', "--.."),
+    ('0', "-----"),
+    ('1', ".----"),
+    ('2', "..---"),
+    ('3', "...--"),
+    ('4', "....-"),
+    ('5', "....."),
+    ('6', "-...."),
+    ('7', "--..."),
+    ('8', "---.."),
+    ('9', "----."),
+    ('.', ".-.-.-"),
+    (',', "--..--"),
+    ('?', "..--.."),
+    ('!', "-.-.--"),
+    ('\'', ".----."),
+    ('"', ".-..-."),
+    ('/', "-..-."),
+    (':', "---..."),
+    ('-', "-....-"),
+    ('@', ".--.-."),
+];
+
+/// Letters are separated by spaces and words by ` / `
+fn encode_morse(text: &str) -> Result<String, String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .map(|c| {
+                    let c = c.to_ascii_lowercase();
+                    MORSE_TABLE
+                        .iter()
+                        .find(|(letter, _)| *letter == c)
+                        .map(|(_, code)| *code)
+                        .ok_or_else(|| format!("{c} can't be written in Morse code"))
+                })
+                .collect::<Result<Vec<&str>, String>>()
+                .map(|codes| codes.join(" "))
+        })
+        .collect::<Result<Vec<String>, String>>()
+        .map(|words| words.join(" / "))
+}
+
+fn decode_morse(text: &str) -> Result<String, String> {
+    text.split('/')
+        .map(|word| {
+            word.split_whitespace()
+                .map(|code| {
+                    MORSE_TABLE
+                        .iter()
+                        .find(|(_, letter_code)| *letter_code == code)
+                        .map(|(letter, _)| *letter)
+                        .ok_or_else(|| format!("Unknown Morse code {code}"))
+                })
+                .collect::<Result<String, String>>()
+        })
+        .collect::<Result<Vec<String>, String>>()
+        .map(|words| words.join(" "))
+}
+
+fn leet_char(c: char) -> char {
+    match c.to_ascii_lowercase() {
+        'a' => '4',
+        'b' => '8',
+        'e' => '3',
+        'g' => '9',
+        'i' => '1',
+        'o' => '0',
+        's' => '5',
+        't' => '7',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Codec, MAX_INPUT_LENGTH};
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    #[test]
+    fn round_trips() {
+        let text = "Hello world! 123";
+
+        for codec in Codec::ALL.into_iter().filter(Codec::can_decode) {
+            let encoded = codec.encode(text).unwrap();
+            let decoded = codec.decode(&encoded).unwrap();
+
+            // Morse code has no case
+            if codec == Codec::Morse {
+                assert_eq!(decoded, text.to_lowercase());
+            } else {
+                assert_eq!(decoded, text, "{codec:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn encodings() {
+        assert_eq!(Codec::Rot13.encode("Hello").unwrap(), "Uryyb");
+        assert_eq!(Codec::Binary.encode("Hi").unwrap(), "01001000 01101001");
+        assert_eq!(Codec::Binary.decode("0100100001101001").unwrap(), "Hi");
+        assert_eq!(
+            Codec::Morse.encode("SOS sos").unwrap(),
+            "... --- ... / ... --- ..."
+        );
+        assert_eq!(Codec::Leet.encode("leet speak").unwrap(), "l337 5p34k");
+        assert_eq!(Codec::Base64.encode("forsen").unwrap(), "Zm9yc2Vu");
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(Codec::Morse.encode("привіт").is_err());
+        assert!(Codec::Binary.decode("0101").is_err());
+        assert!(Codec::Leet.decode("l337").is_err());
+        assert!(Codec::Base64
+            .encode(&"a".repeat(MAX_INPUT_LENGTH + 1))
+            .is_err());
+        assert_eq!(Codec::from_str("morse"), Ok(Codec::Morse));
+    }
+}
@@ -0,0 +1,101 @@
+//! Example invocations of custom commands, which are run in dry run mode to catch regressions
+use super::debug_report::{self, TraceEntry};
+use super::error::CommandError;
+use crate::database::models::CommandTest;
+use serde::Serialize;
+use std::future::Future;
+
+#[derive(Debug, Serialize)]
+pub struct CommandTestResult {
+    pub id: u64,
+    pub args: String,
+    pub passed: bool,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    /// Expected substrings that are not in the output
+    pub missing: Vec<String>,
+    pub side_effects: Vec<TraceEntry>,
+}
+
+/// Runs the tests one after another, `execute` runs the command with the given arguments
+pub async fn run_command_tests<F, Fut>(tests: &[CommandTest], execute: F) -> Vec<CommandTestResult>
+where
+    F: Fn(Vec<String>) -> Fut,
+    Fut: Future<Output = Result<Option<String>, CommandError>>,
+{
+    let mut results = Vec::with_capacity(tests.len());
+
+    for test in tests {
+        let (result, trace, _) = debug_report::capture_dry_run(execute(test.get_args())).await;
+
+        let (output, error) = match result {
+            Ok(output) => (output, None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let missing = get_missing_substrings(test, output.as_deref().unwrap_or_default());
+
+        results.push(CommandTestResult {
+            id: test.id,
+            args: test.args.clone(),
+            passed: error.is_none() && missing.is_empty(),
+            output,
+            error,
+            missing,
+            side_effects: debug_report::get_side_effects(trace),
+        });
+    }
+
+    results
+}
+
+fn get_missing_substrings(test: &CommandTest, output: &str) -> Vec<String> {
+    test.get_expected()
+        .filter(|expected| !output.contains(expected))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Summarizes the failed tests for chat, `None` if every test passed
+pub fn describe_failures(results: &[CommandTestResult]) -> Option<String> {
+    let failures = results
+        .iter()
+        .filter(|result| !result.passed)
+        .map(|result| match &result.error {
+            Some(error) => format!("#{} ({error})", result.id),
+            None => format!(
+                "#{} (missing \"{}\")",
+                result.id,
+                result.missing.join("\", \"")
+            ),
+        })
+        .collect::<Vec<_>>();
+
+    if failures.is_empty() {
+        None
+    } else {
+        Some(failures.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_missing_substrings;
+    use crate::database::models::CommandTest;
+
+    #[test]
+    fn finds_missing_substrings() {
+        let test = CommandTest {
+            id: 1,
+            channel_id: 1,
+            command_name: "weather".to_owned(),
+            args: "Kyiv".to_owned(),
+            expected: "Kyiv\n\n°C\nwind".to_owned(),
+        };
+
+        assert_eq!(
+            get_missing_substrings(&test, "Kyiv: 20°C, clear"),
+            ["wind".to_owned()]
+        );
+        assert!(get_missing_substrings(&test, "Kyiv: 20°C, wind 5m/s").is_empty());
+    }
+}
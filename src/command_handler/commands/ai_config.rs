@@ -0,0 +1,79 @@
+use std::str::FromStr;
+
+use super::*;
+use crate::command_handler::i18n::Message;
+use strum::EnumString;
+
+#[derive(Debug, Clone)]
+pub struct AiConfig;
+
+#[derive(EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum Subcommand {
+    Enable,
+    Disable,
+    Usage,
+}
+
+#[async_trait]
+impl ExecutableCommand for AiConfig {
+    fn get_names(&self) -> &[&str] {
+        &["aiconfig"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        0
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::ChannelOwner
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = ctx.channel_id.ok_or_else(|| {
+            CommandError::InvalidArgument(Message::OnlyInChannel.translate(language))
+        })?;
+
+        let raw_subcommand = args
+            .first()
+            .ok_or_else(|| CommandError::MissingArgument("enable/disable/usage".to_owned()))?;
+        let subcommand = Subcommand::from_str(raw_subcommand).map_err(|_| {
+            CommandError::InvalidArgument(
+                Message::InvalidSubcommand(raw_subcommand).translate(language),
+            )
+        })?;
+
+        let response = match subcommand {
+            Subcommand::Enable => {
+                ctx.db
+                    .run(move |db| db.set_ai_enabled(channel_id, true))
+                    .await?;
+                Message::AiEnabled.translate(language)
+            }
+            Subcommand::Disable => {
+                ctx.db
+                    .run(move |db| db.set_ai_enabled(channel_id, false))
+                    .await?;
+                Message::AiDisabled.translate(language)
+            }
+            Subcommand::Usage => {
+                let usage = ctx.db.run(move |db| db.get_ai_usage(channel_id)).await?;
+                Message::AiUsage {
+                    requests: usage.requests,
+                    prompt_tokens: usage.prompt_tokens,
+                    completion_tokens: usage.completion_tokens,
+                    cost: usage.cost,
+                }
+                .translate(language)
+            }
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
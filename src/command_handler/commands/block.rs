@@ -0,0 +1,77 @@
+use super::*;
+use crate::command_handler::i18n::Message;
+
+/// Blocks users from using the bot in the current channel, or globally with the `global` argument
+#[derive(Debug, Clone)]
+pub struct Block;
+
+#[async_trait]
+impl ExecutableCommand for Block {
+    fn get_names(&self) -> &[&str] {
+        &["block", "unblock"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        0
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::ChannelMod
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        trigger_name: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let mut args = args.into_iter();
+        let mut user = args
+            .next()
+            .ok_or_else(|| CommandError::MissingArgument("user".to_owned()))?;
+
+        let channel_id = if user == "global" {
+            if ctx.get_permissions().await? < Permissions::Admin {
+                return Err(CommandError::NoPermissions);
+            }
+
+            user = args
+                .next()
+                .ok_or_else(|| CommandError::MissingArgument("user".to_owned()))?;
+            None
+        } else {
+            Some(ctx.channel_id.ok_or_else(|| {
+                CommandError::GenericError(Message::ChannelCannotBlockUsers.translate(language))
+            })?)
+        };
+
+        let identifier = resolve_user(ctx, user).await?;
+
+        if identifier == ctx.platform_ctx.get_user_identifier() {
+            return Err(CommandError::InvalidArgument(
+                Message::CannotBlockYourself.translate(language),
+            ));
+        }
+
+        match trigger_name {
+            "block" => {
+                ctx.blocked_users.block(identifier, channel_id).await?;
+                let message = match channel_id {
+                    Some(_) => Message::UserBlocked(user),
+                    None => Message::UserBlockedGlobally(user),
+                };
+                Ok(CommandOutput::Text(message.translate(language)))
+            }
+            "unblock" => {
+                ctx.blocked_users.unblock(&identifier, channel_id).await?;
+                let message = match channel_id {
+                    Some(_) => Message::UserUnblocked(user),
+                    None => Message::UserUnblockedGlobally(user),
+                };
+                Ok(CommandOutput::Text(message.translate(language)))
+            }
+            _ => Err(CommandError::InvalidArgument(trigger_name.to_owned())),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::str::FromStr;
+
+use super::*;
+use crate::command_handler::{i18n::Message, ignored_users::IgnoredUsers};
+use strum::EnumString;
+
+#[derive(Debug, Clone)]
+pub struct BotSettings {
+    pub ignored_users: IgnoredUsers,
+}
+
+#[derive(EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum Subcommand {
+    Ignore,
+    Unignore,
+    Ignored,
+}
+
+#[async_trait]
+impl ExecutableCommand for BotSettings {
+    fn get_names(&self) -> &[&str] {
+        &["bot"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        0
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::ChannelMod
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = ctx.channel_id.ok_or_else(|| {
+            CommandError::InvalidArgument(Message::OnlyInChannel.translate(language))
+        })?;
+
+        let mut args = args.into_iter();
+
+        let raw_subcommand = args
+            .next()
+            .ok_or_else(|| CommandError::MissingArgument("ignore/unignore/ignored".to_owned()))?;
+        let subcommand = Subcommand::from_str(raw_subcommand).map_err(|_| {
+            CommandError::InvalidArgument(
+                Message::InvalidSubcommand(raw_subcommand).translate(language),
+            )
+        })?;
+
+        match subcommand {
+            Subcommand::Ignore | Subcommand::Unignore => {
+                let user = args
+                    .next()
+                    .ok_or_else(|| CommandError::MissingArgument("user".to_owned()))?;
+                let identifier = resolve_user(ctx, user).await?;
+
+                if let Subcommand::Ignore = subcommand {
+                    if identifier == ctx.platform_ctx.get_user_identifier() {
+                        return Err(CommandError::InvalidArgument(
+                            Message::CannotIgnoreYourself.translate(language),
+                        ));
+                    }
+
+                    self.ignored_users.ignore(channel_id, identifier).await?;
+                    Ok(CommandOutput::Text(
+                        Message::UserIgnored(user).translate(language),
+                    ))
+                } else {
+                    self.ignored_users.unignore(channel_id, &identifier).await?;
+                    Ok(CommandOutput::Text(
+                        Message::UserUnignored(user).translate(language),
+                    ))
+                }
+            }
+            Subcommand::Ignored => {
+                let ignored_users = self.ignored_users.get(channel_id).await?;
+
+                if ignored_users.is_empty() {
+                    Ok(CommandOutput::Text(
+                        Message::NoIgnoredUsers.translate(language),
+                    ))
+                } else {
+                    let users = ignored_users
+                        .iter()
+                        .map(|identifier| identifier.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    Ok(CommandOutput::Text(
+                        Message::IgnoredUsers(&users).translate(language),
+                    ))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use super::*;
+use crate::command_handler::{
+    i18n::{Language, Message},
+    twitch_api::{get_broadcaster_helix_api, model::AutoModSettings, BroadcasterHelixApi},
+};
+
+/// `shield [on|off]` and `automod [level | <category> <level>]`, which act on the channel
+/// through the streamer's manage token
+#[derive(Debug, Clone)]
+pub struct ChannelSafety;
+
+#[async_trait]
+impl ExecutableCommand for ChannelSafety {
+    fn get_names(&self) -> &[&str] {
+        &["shield", "automod"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::ChannelMod
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        trigger_name: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let broadcaster_id = match ctx.platform_ctx.get_channel() {
+            ChannelIdentifier::TwitchChannel((id, _)) => id,
+            _ => {
+                return Err(CommandError::GenericError(
+                    Message::OnlyOnTwitch(trigger_name).translate(language),
+                ))
+            }
+        };
+
+        let helix_api = get_broadcaster_helix_api(ctx.db, &broadcaster_id)
+            .await
+            .map_err(|_| {
+                CommandError::GenericError(Message::StreamerNotAuthenticated.translate(language))
+            })?;
+
+        let response = match trigger_name {
+            "shield" => shield_mode(&helix_api, &broadcaster_id, &args, language).await?,
+            "automod" => automod(&helix_api, &broadcaster_id, &args, language).await?,
+            _ => return Err(CommandError::InvalidArgument(trigger_name.to_owned())),
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+async fn shield_mode(
+    helix_api: &BroadcasterHelixApi,
+    broadcaster_id: &str,
+    args: &[&str],
+    language: Language,
+) -> Result<String, CommandError> {
+    let is_active = match args.first() {
+        None => {
+            let status = helix_api.get_shield_mode_status(broadcaster_id).await?;
+            let message = if status.is_active {
+                Message::ShieldModeActive(&status.moderator_name)
+            } else {
+                Message::ShieldModeInactive
+            };
+            return Ok(message.translate(language));
+        }
+        Some(&"on") => true,
+        Some(&"off") => false,
+        Some(arg) => return Err(CommandError::InvalidArgument(arg.to_string())),
+    };
+
+    helix_api
+        .update_shield_mode_status(broadcaster_id, is_active)
+        .await?;
+
+    if is_active {
+        Ok(Message::ShieldModeEnabled.translate(language))
+    } else {
+        Ok(Message::ShieldModeDisabled.translate(language))
+    }
+}
+
+async fn automod(
+    helix_api: &BroadcasterHelixApi,
+    broadcaster_id: &str,
+    args: &[&str],
+    language: Language,
+) -> Result<String, CommandError> {
+    let mut settings = helix_api.get_automod_settings(broadcaster_id).await?;
+
+    match args {
+        [] => return Ok(describe_automod_settings(&settings, language)),
+        [level] => settings.overall_level = Some(parse_automod_level(level, language)?),
+        [category, level] => {
+            let level = parse_automod_level(level, language)?;
+            if !settings.set_category_level(category, level) {
+                return Err(CommandError::InvalidArgument(
+                    Message::AutoModUnknownCategory {
+                        category,
+                        categories: &AutoModSettings::CATEGORIES.join(", "),
+                    }
+                    .translate(language),
+                ));
+            }
+        }
+        _ => {
+            return Err(CommandError::InvalidArgument(
+                Message::AutoModUsage.translate(language),
+            ))
+        }
+    }
+
+    let settings = helix_api
+        .update_automod_settings(broadcaster_id, &settings)
+        .await?;
+
+    Ok(
+        Message::AutoModUpdated(&describe_automod_settings(&settings, language))
+            .translate(language),
+    )
+}
+
+fn parse_automod_level(level: &str, language: Language) -> Result<u8, CommandError> {
+    level
+        .parse::<u8>()
+        .ok()
+        .filter(|level| *level <= AutoModSettings::MAX_LEVEL)
+        .ok_or_else(|| {
+            CommandError::InvalidArgument(
+                Message::AutoModInvalidLevel {
+                    level,
+                    max: AutoModSettings::MAX_LEVEL,
+                }
+                .translate(language),
+            )
+        })
+}
+
+fn describe_automod_settings(settings: &AutoModSettings, language: Language) -> String {
+    match settings.overall_level {
+        Some(level) => Message::AutoModLevel(level).translate(language),
+        None => {
+            let levels = [
+                settings.disability,
+                settings.aggression,
+                settings.sexuality_sex_or_gender,
+                settings.misogyny,
+                settings.bullying,
+                settings.swearing,
+                settings.race_ethnicity_or_religion,
+                settings.sex_based_terms,
+            ];
+
+            AutoModSettings::CATEGORIES
+                .iter()
+                .zip(levels)
+                .map(|(category, level)| format!("{category}: {level}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    }
+}
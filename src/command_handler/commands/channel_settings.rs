@@ -0,0 +1,118 @@
+use std::str::FromStr;
+
+use super::*;
+use crate::command_handler::i18n::{Language, Message};
+use crate::database::models::ChannelSetting;
+use strum::EnumString;
+
+/// `settings`, `settings set <name> <value>` and `settings reset <name>`
+#[derive(Debug, Clone)]
+pub struct Settings;
+
+#[derive(EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum Subcommand {
+    Show,
+    Set,
+    Reset,
+}
+
+#[async_trait]
+impl ExecutableCommand for Settings {
+    fn get_names(&self) -> &[&str] {
+        &["settings"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        0
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::ChannelOwner
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = ctx.channel_id.ok_or_else(|| {
+            CommandError::InvalidArgument(Message::OnlyInChannel.translate(language))
+        })?;
+
+        let mut args = args.into_iter();
+
+        let subcommand = match args.next() {
+            Some(raw_subcommand) => Subcommand::from_str(raw_subcommand).map_err(|_| {
+                CommandError::InvalidArgument(
+                    Message::InvalidSubcommand(raw_subcommand).translate(language),
+                )
+            })?,
+            None => Subcommand::Show,
+        };
+
+        let response = match subcommand {
+            Subcommand::Show => {
+                let values = ctx
+                    .db
+                    .run(move |db| db.get_channel_setting_values(channel_id))
+                    .await?;
+                let settings = ChannelSetting::with_defaults(&values)
+                    .into_iter()
+                    .map(|(setting, value)| format!("{setting}: {value}"))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+
+                return Ok(CommandOutput::Text(settings));
+            }
+            Subcommand::Set => {
+                let setting = parse_setting(args.next(), language)?;
+                let value = args.collect::<Vec<&str>>().join(" ");
+                setting
+                    .validate(&value)
+                    .map_err(CommandError::InvalidArgument)?;
+
+                let new_value = value.clone();
+                ctx.db
+                    .run(move |db| db.set_channel_setting(channel_id, setting, &new_value))
+                    .await?;
+                Message::SettingSet {
+                    setting: &setting.to_string(),
+                    value: &value,
+                }
+                .translate(language)
+            }
+            Subcommand::Reset => {
+                let setting = parse_setting(args.next(), language)?;
+
+                ctx.db
+                    .run(move |db| db.remove_channel_setting(channel_id, setting))
+                    .await?;
+                Message::SettingReset {
+                    setting: &setting.to_string(),
+                    value: &setting.default_value().to_string(),
+                }
+                .translate(language)
+            }
+        };
+
+        ctx.platform_handler
+            .reload_channel_settings(ctx.db, ctx.platform_ctx.get_channel(), channel_id)
+            .await?;
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+fn parse_setting(
+    setting: Option<&str>,
+    language: Language,
+) -> Result<ChannelSetting, CommandError> {
+    let setting = setting.ok_or_else(|| CommandError::MissingArgument("setting".to_owned()))?;
+
+    ChannelSetting::from_str(setting).map_err(|_| {
+        CommandError::InvalidArgument(Message::UnknownSetting(setting).translate(language))
+    })
+}
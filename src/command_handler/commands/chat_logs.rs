@@ -0,0 +1,196 @@
+use super::*;
+use crate::command_handler::{i18n::Message, inquiry_helper::format_duration};
+use chrono::Utc;
+
+/// `lines [user]`, the amount of logged messages of the user in the channel
+#[derive(Clone)]
+pub struct Lines;
+
+#[async_trait]
+impl ExecutableCommand for Lines {
+    fn get_names(&self) -> &[&str] {
+        &["lines"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let channel_id = get_logged_channel_id(ctx).await?;
+        let language = ctx.get_language().await?;
+
+        let response = match args.first() {
+            Some(user) => {
+                let user_id = resolve_user_id(ctx, user).await?;
+                let count = ctx
+                    .db
+                    .run(move |db| db.count_chat_logs(channel_id, user_id))
+                    .await?;
+                Message::UserSentMessages {
+                    user: user.trim_start_matches('@'),
+                    count,
+                }
+                .translate(language)
+            }
+            None => {
+                let user_id = ctx.user.id;
+                let count = ctx
+                    .db
+                    .run(move |db| db.count_chat_logs(channel_id, user_id))
+                    .await?;
+                Message::YouSentMessages(count).translate(language)
+            }
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+/// Longer messages are cut off in the responses of `lastseen` and `firstseen`
+const MAX_QUOTE_LENGTH: usize = 200;
+
+/// `lastseen <user>` and `firstseen <user>`, when the user sent their last or first logged
+/// message in the channel and what it was. `lastseen optout`/`optin` hides the user from both
+#[derive(Clone)]
+pub struct Seen;
+
+#[async_trait]
+impl ExecutableCommand for Seen {
+    fn get_names(&self) -> &[&str] {
+        &["lastseen", "firstseen"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        trigger_name: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let user = args
+            .first()
+            .ok_or_else(|| CommandError::MissingArgument("user".to_owned()))?;
+        let language = ctx.get_language().await?;
+        let own_user_id = ctx.user.id;
+
+        match *user {
+            "optout" => {
+                ctx.db
+                    .run(move |db| db.set_seen_opt_out(own_user_id, true))
+                    .await?;
+                return Ok(CommandOutput::Text(
+                    Message::SeenOptedOut(trigger_name).translate(language),
+                ));
+            }
+            "optin" => {
+                ctx.db
+                    .run(move |db| db.set_seen_opt_out(own_user_id, false))
+                    .await?;
+                return Ok(CommandOutput::Text(
+                    Message::SeenOptedIn(trigger_name).translate(language),
+                ));
+            }
+            _ => (),
+        }
+
+        let channel_id = get_logged_channel_id(ctx).await?;
+        let user_id = resolve_user_id(ctx, user).await?;
+        let name = user.trim_start_matches('@');
+
+        if user_id != own_user_id && ctx.db.run(move |db| db.get_seen_opt_out(user_id)).await? {
+            return Err(CommandError::GenericError(
+                Message::UserOptedOut {
+                    user: name,
+                    command: trigger_name,
+                }
+                .translate(language),
+            ));
+        }
+
+        let first = trigger_name == "firstseen";
+        let log = ctx
+            .db
+            .run(move |db| match first {
+                true => db.get_first_chat_log(channel_id, user_id),
+                false => db.get_last_chat_log(channel_id, user_id),
+            })
+            .await?;
+
+        let response = match log {
+            Some(log) => {
+                let ago = format_duration(Utc::now().naive_utc() - log.sent_at);
+                let text = quote(&log.text);
+                let message = match first {
+                    true => Message::FirstSeen {
+                        user: name,
+                        ago: &ago,
+                        text: &text,
+                    },
+                    false => Message::LastSeen {
+                        user: name,
+                        ago: &ago,
+                        text: &text,
+                    },
+                };
+                message.translate(language)
+            }
+            None => Message::NotSeen(name).translate(language),
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+fn quote(text: &str) -> String {
+    if text.chars().count() > MAX_QUOTE_LENGTH {
+        let text = text.chars().take(MAX_QUOTE_LENGTH).collect::<String>();
+        format!("{}…", text.trim_end())
+    } else {
+        text.to_owned()
+    }
+}
+
+/// Only channels that enabled the `chat_logs` setting have their messages logged
+async fn get_logged_channel_id<P: PlatformContext + Send + Sync>(
+    ctx: &ExecutionContext<'_, P>,
+) -> Result<u64, CommandError> {
+    let language = ctx.get_language().await?;
+    let channel_id = ctx
+        .channel_id
+        .ok_or_else(|| CommandError::InvalidArgument(Message::OnlyInChannel.translate(language)))?;
+
+    let settings = ctx
+        .db
+        .run(move |db| db.get_channel_settings(channel_id))
+        .await?;
+    if !settings.chat_logs {
+        return Err(CommandError::GenericError(
+            Message::ChatLogsDisabled.translate(language),
+        ));
+    }
+
+    Ok(channel_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quote, MAX_QUOTE_LENGTH};
+
+    #[test]
+    fn quotes_long_messages() {
+        assert_eq!(quote("hello"), "hello");
+
+        let quoted = quote(&"ї".repeat(MAX_QUOTE_LENGTH + 1));
+        assert_eq!(quoted.chars().count(), MAX_QUOTE_LENGTH + 1);
+        assert!(quoted.ends_with('…'));
+    }
+}
@@ -1,12 +1,31 @@
 use std::str::FromStr;
+use std::sync::atomic::Ordering;
 
 use super::*;
 use crate::{
-    api::get_base_url,
-    database::{models::CommandMode, DatabaseError},
+    command_handler::{
+        args_spec::ArgsSpec,
+        command_tests::{describe_failures, run_command_tests, CommandTestResult},
+        execute_action,
+        i18n::{Language, Message},
+        output::Attachment,
+        parse_command_params,
+        template_partials::{validate_partial_body, validate_partial_name, MAX_PARTIALS},
+    },
+    database::{
+        models::{parse_discord_channel_ids, Command, CommandMode},
+        Database, DatabaseError,
+    },
 };
 
-pub struct Cmd;
+pub struct Cmd {
+    pub template_registry: TemplateRegistry,
+    pub native_modules: Arc<Vec<NativeModule>>,
+    pub module_storage: ModuleStorage,
+    pub openai_api: Option<OpenAiApi>,
+    pub image_api: Option<ImageApi>,
+    pub safe_mode: Arc<AtomicBool>,
+}
 
 #[async_trait]
 impl ExecutableCommand for Cmd {
@@ -29,12 +48,15 @@ impl ExecutableCommand for Cmd {
         ctx: &ExecutionContext<'a, P>,
         mut trigger_name: &str,
         mut args: Vec<&str>,
-    ) -> Result<Option<String>, CommandError> {
+    ) -> Result<CommandOutput, CommandError> {
         let channel_identifier = ctx.platform_ctx.get_channel();
+        let identifier = channel_identifier.clone();
         let channel = ctx
             .db
-            .get_or_create_channel(&channel_identifier)?
+            .run(move |db| db.get_or_create_channel(&identifier))
+            .await?
             .ok_or(CommandError::NoPermissions)?; // Shouldn't happen anyway
+        let channel_id = channel.id;
 
         match trigger_name {
             "addcmd" => {
@@ -60,11 +82,12 @@ impl ExecutableCommand for Cmd {
         }
 
         let mut arguments = args.into_iter();
+        let language = ctx.get_language().await?;
 
         let response = if arguments.len() == 0 {
-            Ok(Some(format!(
+            Ok(CommandOutput::Text(format!(
                 "{}/channels/{}/commands",
-                get_base_url(),
+                ctx.db.config().base_url,
                 channel.id,
             )))
         } else if ctx.get_permissions().await? >= Permissions::ChannelMod {
@@ -88,16 +111,23 @@ impl ExecutableCommand for Cmd {
                         return Err(CommandError::MissingArgument("command action".to_string()));
                     }
 
-                    match ctx.db.add_command_to_channel(
-                        &channel_identifier,
-                        command_name,
-                        &command_action,
-                    ) {
-                        Ok(()) => Ok(Some("Command successfully added".to_string())),
+                    let name = command_name.to_owned();
+                    let result = ctx
+                        .db
+                        .run(move |db| {
+                            db.add_command_to_channel(&channel_identifier, &name, &command_action)
+                        })
+                        .await;
+                    match result {
+                        Ok(()) => Ok(CommandOutput::Text(
+                            Message::CommandAdded.translate(language),
+                        )),
                         Err(DatabaseError::DieselError(diesel::result::Error::DatabaseError(
                             diesel::result::DatabaseErrorKind::UniqueViolation,
                             _,
-                        ))) => Ok(Some("Command already exists".to_string())),
+                        ))) => Ok(CommandOutput::Text(
+                            Message::CommandExists.translate(language),
+                        )),
                         Err(e) => Err(CommandError::DatabaseError(e)),
                     }
                 }
@@ -110,11 +140,15 @@ impl ExecutableCommand for Cmd {
                         command_name = stripped_name;
                     }
 
+                    let name = command_name.to_owned();
                     match ctx
                         .db
-                        .delete_command_from_channel(&channel_identifier, command_name)
+                        .run(move |db| db.delete_command_from_channel(&channel_identifier, &name))
+                        .await
                     {
-                        Ok(()) => Ok(Some("Command succesfully removed".to_string())),
+                        Ok(()) => Ok(CommandOutput::Text(
+                            Message::CommandRemoved.translate(language),
+                        )),
                         Err(e) => Err(CommandError::DatabaseError(e)),
                     }
                 }
@@ -128,12 +162,17 @@ impl ExecutableCommand for Cmd {
                         return Err(CommandError::MissingArgument("command action".to_string()));
                     }
 
-                    match ctx.db.update_command_action(
-                        &channel_identifier,
-                        command_name,
-                        &command_action,
-                    ) {
-                        Ok(()) => Ok(Some(format!("Command {command_name} updated"))),
+                    let name = command_name.to_owned();
+                    let result = ctx
+                        .db
+                        .run(move |db| {
+                            db.update_command_action(&channel_identifier, &name, &command_action)
+                        })
+                        .await;
+                    match result {
+                        Ok(()) => Ok(CommandOutput::Text(
+                            Message::CommandUpdated(command_name).translate(language),
+                        )),
                         Err(e) => Err(CommandError::DatabaseError(e)),
                     }
                 }
@@ -146,9 +185,16 @@ impl ExecutableCommand for Cmd {
                         command_name = stripped_name;
                     }
 
-                    match ctx.db.get_command(&channel_identifier, command_name)? {
-                        Some(command) => Ok(Some(command.action)),
-                        None => Ok(Some(format!("command {} doesn't exist", command_name))),
+                    let name = command_name.to_owned();
+                    let command = ctx
+                        .db
+                        .run(move |db| db.get_command(&channel_identifier, &name))
+                        .await?;
+                    match command {
+                        Some(command) => Ok(CommandOutput::Text(command.action)),
+                        None => Ok(CommandOutput::Text(
+                            Message::CommandDoesNotExist(command_name).translate(language),
+                        )),
                     }
                 }
                 "set_triggers" => {
@@ -166,10 +212,14 @@ impl ExecutableCommand for Cmd {
                         return Err(CommandError::MissingArgument("triggers".to_string()));
                     }
 
+                    let name = command_name.to_owned();
                     ctx.db
-                        .set_command_triggers(channel.id, command_name, &triggers)?;
+                        .run(move |db| db.set_command_triggers(channel_id, &name, &triggers))
+                        .await?;
 
-                    Ok(Some(String::from("Succesfully updated command triggers")))
+                    Ok(CommandOutput::Text(
+                        Message::CommandTriggersUpdated.translate(language),
+                    ))
                 }
                 "get_triggers" => {
                     let mut command_name = arguments
@@ -180,17 +230,103 @@ impl ExecutableCommand for Cmd {
                         command_name = stripped_name;
                     }
 
-                    let commands = ctx.db.get_commands(channel.id)?;
+                    let commands = ctx.db.run(move |db| db.get_commands(channel_id)).await?;
 
                     for command in commands {
                         if command.name == command_name {
                             return Ok(match command.triggers {
-                                Some(triggers) => Some(format!("Command triggers: {}", triggers)),
-                                None => Some(String::from("Command has no triggers")),
+                                Some(triggers) => {
+                                    Some(Message::CommandTriggers(&triggers).translate(language))
+                                }
+                                None => Some(Message::CommandHasNoTriggers.translate(language)),
                             });
                         }
                     }
-                    Ok(Some(String::from("Command not found")))
+                    Ok(CommandOutput::Text(
+                        Message::CommandNotFound.translate(language),
+                    ))
+                }
+                "set_channels" => {
+                    let mut command_name = arguments
+                        .next()
+                        .ok_or_else(|| CommandError::MissingArgument("command name".to_string()))?;
+
+                    if let Some(stripped_name) = command_name.strip_prefix('!') {
+                        command_name = stripped_name;
+                    }
+
+                    // Without any channels the command is available everywhere again
+                    let channels = arguments.collect::<Vec<&str>>().join(" ");
+                    let channel_ids = parse_discord_channel_ids(&channels).ok_or_else(|| {
+                        CommandError::InvalidArgument(
+                            Message::InvalidDiscordChannels(&channels).translate(language),
+                        )
+                    })?;
+
+                    let name = command_name.to_owned();
+                    if channel_ids.is_empty() {
+                        ctx.db
+                            .run(move |db| db.set_command_discord_channels(channel_id, &name, None))
+                            .await?;
+                        Ok(CommandOutput::Text(
+                            Message::CommandChannelsReset.translate(language),
+                        ))
+                    } else {
+                        let channel_ids = channel_ids
+                            .iter()
+                            .map(u64::to_string)
+                            .collect::<Vec<String>>()
+                            .join(" ");
+                        ctx.db
+                            .run(move |db| {
+                                db.set_command_discord_channels(
+                                    channel_id,
+                                    &name,
+                                    Some(&channel_ids),
+                                )
+                            })
+                            .await?;
+                        Ok(CommandOutput::Text(
+                            Message::CommandChannelsUpdated.translate(language),
+                        ))
+                    }
+                }
+                "set_args" => {
+                    let mut command_name = arguments
+                        .next()
+                        .ok_or_else(|| CommandError::MissingArgument("command name".to_string()))?;
+
+                    if let Some(stripped_name) = command_name.strip_prefix('!') {
+                        command_name = stripped_name;
+                    }
+
+                    // Without a spec the arguments are no longer checked
+                    let raw_spec = arguments.collect::<Vec<&str>>().join(" ");
+
+                    let name = command_name.to_owned();
+                    if raw_spec.is_empty() {
+                        ctx.db
+                            .run(move |db| db.set_command_args_spec(channel_id, &name, None))
+                            .await?;
+                        Ok(CommandOutput::Text(
+                            Message::CommandArgsUnchecked(command_name).translate(language),
+                        ))
+                    } else {
+                        let spec =
+                            ArgsSpec::from_str(&raw_spec).map_err(CommandError::InvalidArgument)?;
+                        ctx.db
+                            .run(move |db| {
+                                db.set_command_args_spec(channel_id, &name, Some(&raw_spec))
+                            })
+                            .await?;
+                        Ok(CommandOutput::Text(
+                            Message::CommandUsage {
+                                name: command_name,
+                                spec: &spec.to_string(),
+                            }
+                            .translate(language),
+                        ))
+                    }
                 }
                 "set_mode" => {
                     let command_name = arguments
@@ -200,13 +336,80 @@ impl ExecutableCommand for Cmd {
                         .next()
                         .ok_or_else(|| CommandError::MissingArgument("command mode".to_string()))?;
                     let mode = CommandMode::from_str(raw_mode).map_err(|_| {
-                        CommandError::InvalidArgument(format!("invalid command mode {raw_mode}"))
+                        CommandError::InvalidArgument(
+                            Message::InvalidCommandMode(raw_mode).translate(language),
+                        )
                     })?;
 
+                    let name = command_name.to_owned();
                     ctx.db
-                        .set_command_mode(&channel_identifier, command_name, mode)?;
+                        .run(move |db| db.set_command_mode(&channel_identifier, &name, mode))
+                        .await?;
+
+                    Ok(CommandOutput::Text(
+                        Message::CommandModeUpdated.translate(language),
+                    ))
+                }
+                "test" => {
+                    let mut command_name = arguments
+                        .next()
+                        .ok_or_else(|| CommandError::MissingArgument("command name".to_string()))?;
+
+                    if let Some(stripped_name) = command_name.strip_prefix('!') {
+                        command_name = stripped_name;
+                    }
 
-                    Ok(Some("Updated command mode".to_owned()))
+                    let name = command_name.to_owned();
+                    let command = ctx
+                        .db
+                        .run(move |db| db.get_command(&channel_identifier, &name))
+                        .await?;
+                    match command {
+                        Some(command) => {
+                            let results = self.run_tests(ctx, &command, language).await?;
+
+                            if results.is_empty() {
+                                Ok(CommandOutput::Text(
+                                    Message::CommandHasNoTests.translate(language),
+                                ))
+                            } else if let Some(failures) = describe_failures(&results) {
+                                Ok(CommandOutput::Text(
+                                    Message::CommandTestsFailed(&failures).translate(language),
+                                ))
+                            } else {
+                                Ok(CommandOutput::Text(
+                                    Message::CommandTestsPassed(results.len()).translate(language),
+                                ))
+                            }
+                        }
+                        None => Ok(CommandOutput::Text(
+                            Message::CommandDoesNotExist(command_name).translate(language),
+                        )),
+                    }
+                }
+                "export" => {
+                    let commands = ctx.db.run(move |db| db.get_commands(channel_id)).await?;
+                    let content = serde_json::to_vec_pretty(&commands)
+                        .map_err(|e| CommandError::InternalError(e.to_string()))?;
+
+                    Ok(CommandOutput::Attachment(Attachment {
+                        filename: "commands.json".to_owned(),
+                        content,
+                    }))
+                }
+                "partial" => {
+                    let args = arguments.map(str::to_owned).collect::<Vec<String>>();
+                    ctx.db
+                        .run(move |db| {
+                            manage_partials(
+                                db,
+                                channel_id,
+                                args.iter().map(String::as_str),
+                                language,
+                            )
+                        })
+                        .await
+                        .map(CommandOutput::Text)
                 }
                 _ => Err(CommandError::InvalidArgument(trigger_name.to_owned())),
             }
@@ -220,3 +423,115 @@ impl ExecutableCommand for Cmd {
         Ok(response)
     }
 }
+
+impl Cmd {
+    async fn run_tests<P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'_, P>,
+        command: &Command,
+        language: Language,
+    ) -> Result<Vec<CommandTestResult>, CommandError> {
+        if self.safe_mode.load(Ordering::Relaxed) {
+            return Err(CommandError::GenericError(
+                Message::SafeModeCommandsDisabled.translate(language),
+            ));
+        }
+
+        let channel_id = command.channel_id;
+        let name = command.name.clone();
+        let tests = ctx
+            .db
+            .run(move |db| db.get_command_tests(channel_id, &name))
+            .await?;
+
+        Ok(run_command_tests(&tests, |args| async move {
+            let params = parse_command_params(command, &args)?;
+
+            execute_action(
+                self.template_registry.load_full(),
+                &self.native_modules,
+                self.module_storage.clone(),
+                self.openai_api.as_ref(),
+                self.image_api.as_ref(),
+                command.mode.clone(),
+                command.action.clone(),
+                Some(&command.name),
+                ctx,
+                args,
+                params,
+            )
+            .await
+        })
+        .await)
+    }
+}
+
+/// `partial add <name> <body>`, `partial remove <name>`, `partial show <name>` and `partial list`
+fn manage_partials<'a>(
+    db: &Database,
+    channel_id: u64,
+    mut args: impl Iterator<Item = &'a str>,
+    language: Language,
+) -> Result<String, CommandError> {
+    let subcommand = args.next().unwrap_or("list");
+    let mut get_name = || {
+        args.next()
+            .ok_or_else(|| CommandError::MissingArgument("partial name".to_owned()))
+    };
+
+    match subcommand {
+        "add" | "set" | "edit" => {
+            let name = get_name()?;
+            validate_partial_name(name).map_err(CommandError::InvalidArgument)?;
+
+            let body = args.collect::<Vec<&str>>().join(" ");
+            if body.is_empty() {
+                return Err(CommandError::MissingArgument("partial body".to_owned()));
+            }
+            validate_partial_body(&body).map_err(CommandError::InvalidArgument)?;
+
+            let partials = db.get_template_partials(channel_id)?;
+            let exists = partials.iter().any(|partial| partial.name == name);
+            if !exists && partials.len() >= MAX_PARTIALS {
+                return Err(CommandError::InvalidArgument(
+                    Message::PartialLimit(MAX_PARTIALS).translate(language),
+                ));
+            }
+
+            db.set_template_partial(channel_id, name, &body)?;
+
+            Ok(Message::PartialSaved(name).translate(language))
+        }
+        "del" | "delete" | "remove" => {
+            let name = get_name()?;
+
+            match db.delete_template_partial(channel_id, name)? {
+                true => Ok(Message::PartialRemoved(name).translate(language)),
+                false => Ok(Message::PartialDoesNotExist(name).translate(language)),
+            }
+        }
+        "show" => {
+            let name = get_name()?;
+
+            Ok(db
+                .get_template_partials(channel_id)?
+                .into_iter()
+                .find(|partial| partial.name == name)
+                .map(|partial| partial.body)
+                .unwrap_or_else(|| Message::PartialDoesNotExist(name).translate(language)))
+        }
+        "list" => {
+            let names = db
+                .get_template_partials(channel_id)?
+                .into_iter()
+                .map(|partial| partial.name)
+                .collect::<Vec<_>>();
+
+            match names.is_empty() {
+                true => Ok(Message::NoPartials.translate(language)),
+                false => Ok(names.join(", ")),
+            }
+        }
+        other => Err(CommandError::InvalidArgument(other.to_owned())),
+    }
+}
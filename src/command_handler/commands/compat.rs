@@ -0,0 +1,152 @@
+use super::*;
+use crate::command_handler::{
+    i18n::Message,
+    lastfm_api::{LastFMApi, Period, TopArtist},
+};
+use crate::database::DatabaseError;
+
+/// Artists of each user that are compared
+const COMPARED_ARTISTS: u32 = 50;
+const SHOWN_ARTISTS: usize = 3;
+
+/// `compat @user` compares the top artists of two users with linked Last.fm accounts
+#[derive(Clone)]
+pub struct Compat {
+    pub lastfm_api: Option<LastFMApi>,
+}
+
+#[async_trait]
+impl ExecutableCommand for Compat {
+    fn get_names(&self) -> &[&str] {
+        &["compat", "taste"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        10
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let lastfm_api = self.lastfm_api.as_ref().ok_or_else(|| {
+            CommandError::GenericError(Message::LastfmNotConfigured.translate(language))
+        })?;
+
+        let arg = args
+            .first()
+            .ok_or_else(|| CommandError::MissingArgument("user".to_owned()))?;
+        let other_name = arg.trim_start_matches('@');
+
+        let identifier = resolve_user(ctx, other_name).await?;
+        let other_user = ctx
+            .db
+            .run(move |db| db.get_user(&identifier))
+            .await?
+            .ok_or_else(|| {
+                CommandError::InvalidArgument(Message::UserNotKnown(other_name).translate(language))
+            })?;
+
+        let (own_user_id, other_user_id) = (ctx.user.id, other_user.id);
+        let (own_lastfm, other_lastfm) = ctx
+            .db
+            .run(move |db| {
+                let own_lastfm = db.get_lastfm_name(own_user_id)?;
+                let other_lastfm = db.get_lastfm_name(other_user_id)?;
+                Ok::<_, DatabaseError>((own_lastfm, other_lastfm))
+            })
+            .await?;
+
+        let own_lastfm = own_lastfm.ok_or_else(|| {
+            CommandError::GenericError(Message::LastfmNotLinked.translate(language))
+        })?;
+        let other_lastfm = other_lastfm.ok_or_else(|| {
+            CommandError::GenericError(Message::LastfmUserNotLinked(other_name).translate(language))
+        })?;
+
+        let (own_artists, other_artists) = tokio::try_join!(
+            lastfm_api.get_top_artists(&own_lastfm, Period::Overall, COMPARED_ARTISTS),
+            lastfm_api.get_top_artists(&other_lastfm, Period::Overall, COMPARED_ARTISTS),
+        )
+        .map_err(|e| CommandError::InternalError(e.to_string()))?;
+
+        let (score, shared) = compatibility(&own_artists, &other_artists);
+
+        let response = if shared.is_empty() {
+            Message::CompatNoSharedArtists {
+                user: other_name,
+                score,
+            }
+            .translate(language)
+        } else {
+            let shown = shared
+                .into_iter()
+                .take(SHOWN_ARTISTS)
+                .collect::<Vec<_>>()
+                .join(", ");
+            Message::CompatSharedArtists {
+                user: other_name,
+                score,
+                artists: &shown,
+            }
+            .translate(language)
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+/// The percentage of artists in common out of the shorter list, and the common artists,
+/// ordered by how high they are in both lists
+fn compatibility(own: &[TopArtist], other: &[TopArtist]) -> (u32, Vec<String>) {
+    let shortest = own.len().min(other.len());
+    if shortest == 0 {
+        return (0, Vec::new());
+    }
+
+    let mut shared = own
+        .iter()
+        .enumerate()
+        .filter_map(|(own_rank, artist)| {
+            other
+                .iter()
+                .position(|other_artist| other_artist.name.eq_ignore_ascii_case(&artist.name))
+                .map(|other_rank| (own_rank + other_rank, artist.name.clone()))
+        })
+        .collect::<Vec<_>>();
+    shared.sort_by_key(|(rank, _)| *rank);
+
+    let score = (shared.len() * 100 / shortest) as u32;
+    (score, shared.into_iter().map(|(_, name)| name).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compatibility;
+    use crate::command_handler::lastfm_api::TopArtist;
+
+    fn artists(names: &[&str]) -> Vec<TopArtist> {
+        names
+            .iter()
+            .map(|name| TopArtist {
+                name: name.to_string(),
+                playcount: "1".to_owned(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn compares_artists() {
+        let own = artists(&["Okean Elzy", "Radiohead", "Boards of Canada", "Muse"]);
+        let other = artists(&["Muse", "radiohead", "Daft Punk", "Okean Elzy"]);
+
+        let (score, shared) = compatibility(&own, &other);
+        assert_eq!(score, 75);
+        assert_eq!(shared, ["Radiohead", "Okean Elzy", "Muse"]);
+
+        assert_eq!(compatibility(&own, &[]), (0, vec![]));
+    }
+}
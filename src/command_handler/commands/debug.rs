@@ -1,10 +1,15 @@
 use super::*;
-use crate::command_handler::execute_template_command;
-use handlebars::Handlebars;
-use std::sync::Arc;
+use crate::command_handler::debug_report::{self, DebugReport, DebugReports};
+use crate::command_handler::i18n::Message;
+use crate::command_handler::inquiry_helper::format_duration;
+use crate::command_handler::{execute_template_command, TemplateRegistry};
+use crate::database::models::CommandMode;
+use chrono::Utc;
+use std::future::Future;
 
 pub struct Debug {
-    template_registry: Arc<Handlebars<'static>>,
+    template_registry: TemplateRegistry,
+    debug_reports: DebugReports,
 }
 
 #[async_trait]
@@ -26,14 +31,112 @@ impl ExecutableCommand for Debug {
         ctx: &ExecutionContext<'a, P>,
         _trigger_name: &str,
         args: Vec<&str>,
-    ) -> Result<Option<String>, CommandError> {
+    ) -> Result<CommandOutput, CommandError> {
+        if args.first() == Some(&"last-error") {
+            return self.show_last_error(ctx).await;
+        }
+
         let action = args.join(" ");
-        execute_template_command(self.template_registry.clone(), action, ctx, vec![]).await
+        let execution = execute_template_command(
+            self.template_registry.load_full(),
+            action.clone(),
+            None,
+            ctx,
+            vec![],
+            None,
+        );
+
+        execute_with_report(
+            ctx,
+            &self.debug_reports,
+            CommandMode::Template,
+            action,
+            execution,
+        )
+        .await
     }
 }
 
 impl Debug {
-    pub fn new(template_registry: Arc<Handlebars<'static>>) -> Self {
-        Self { template_registry }
+    pub fn new(template_registry: TemplateRegistry, debug_reports: DebugReports) -> Self {
+        Self {
+            template_registry,
+            debug_reports,
+        }
+    }
+
+    /// The full error, even when the channel's error responses don't show it
+    async fn show_last_error<P: PlatformContext>(
+        &self,
+        ctx: &ExecutionContext<'_, P>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = ctx.channel_id.ok_or_else(|| {
+            CommandError::InvalidArgument(Message::OnlyInChannel.translate(language))
+        })?;
+
+        let response = match self.debug_reports.get_last_error(channel_id) {
+            Some(last_error) => Message::LastError {
+                command: &last_error.command,
+                ago: &format_duration(Utc::now() - last_error.created_at),
+                error: &last_error.error,
+            }
+            .translate(language),
+            None => Message::NoRecentErrors.translate(language),
+        };
+
+        Ok(CommandOutput::Text(response))
     }
 }
+
+/// Traces the execution and links the stored report after the response.
+/// Reports are viewed in the channel's dashboard, so nothing is traced outside of channels.
+pub async fn execute_with_report<P, F>(
+    ctx: &ExecutionContext<'_, P>,
+    debug_reports: &DebugReports,
+    mode: CommandMode,
+    source: String,
+    execution: F,
+) -> Result<CommandOutput, CommandError>
+where
+    P: PlatformContext + Send + Sync,
+    F: Future<Output = Result<Option<String>, CommandError>>,
+{
+    let channel_id = match ctx.channel_id {
+        Some(channel_id) => channel_id,
+        None => return execution.await.map(CommandOutput::from),
+    };
+
+    let (result, trace, duration) = debug_report::capture(execution).await;
+
+    let report = debug_reports.insert(DebugReport {
+        id: String::new(),
+        channel_id,
+        mode: mode.to_string(),
+        source,
+        response: result.as_ref().ok().cloned().flatten(),
+        error: result.as_ref().err().map(ToString::to_string),
+        created_at: Utc::now(),
+        duration_ms: duration.as_millis() as u64,
+        trace,
+    });
+    let report_url = format!(
+        "{}/channels/{channel_id}/debug/{}",
+        ctx.db.config().base_url,
+        report.id
+    );
+
+    let language = ctx.get_language().await?;
+    let response = match result {
+        Ok(response) => response.unwrap_or_else(|| "<empty response>".to_owned()),
+        Err(e) => Message::DebugError(&e.to_string()).translate(language),
+    };
+
+    Ok(CommandOutput::Text(
+        Message::DebugReport {
+            response: &response,
+            url: &report_url,
+        }
+        .translate(language),
+    ))
+}
@@ -0,0 +1,109 @@
+use std::str::FromStr;
+
+use super::*;
+use crate::command_handler::i18n::{Language, Message};
+use crate::database::models::ChannelFeature;
+
+/// `feature list`, `feature enable/disable <feature>` and `feature reset <feature>`
+/// turn features of the current channel on or off
+#[derive(Debug, Clone)]
+pub struct Features;
+
+#[async_trait]
+impl ExecutableCommand for Features {
+    fn get_names(&self) -> &[&str] {
+        &["feature"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        0
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::Admin
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = ctx.channel_id.ok_or_else(|| {
+            CommandError::InvalidArgument(Message::OnlyInChannel.translate(language))
+        })?;
+        let mut args = args.into_iter();
+
+        let response = match args.next().unwrap_or("list") {
+            "list" => ctx
+                .db
+                .run(move |db| db.get_channel_features(channel_id))
+                .await?
+                .into_iter()
+                .map(|state| {
+                    Message::FeatureState {
+                        feature: &state.feature,
+                        enabled: state.enabled,
+                        is_default: state.is_default,
+                    }
+                    .translate(language)
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            "enable" => {
+                let feature = get_feature(args.next(), language)?;
+                ctx.db
+                    .run(move |db| db.set_channel_feature(channel_id, feature, true))
+                    .await?;
+                Message::FeatureEnabled(&feature.to_string()).translate(language)
+            }
+            "disable" => {
+                let feature = get_feature(args.next(), language)?;
+                ctx.db
+                    .run(move |db| db.set_channel_feature(channel_id, feature, false))
+                    .await?;
+                Message::FeatureDisabled(&feature.to_string()).translate(language)
+            }
+            "reset" => {
+                let feature = get_feature(args.next(), language)?;
+                ctx.db
+                    .run(move |db| db.reset_channel_feature(channel_id, feature))
+                    .await?;
+
+                let feature_name = feature.to_string();
+                let message = match feature.enabled_by_default() {
+                    true => Message::FeatureResetEnabled(&feature_name),
+                    false => Message::FeatureResetDisabled(&feature_name),
+                };
+                message.translate(language)
+            }
+            other => return Err(CommandError::InvalidArgument(other.to_owned())),
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+fn get_feature(
+    raw_feature: Option<&str>,
+    language: Language,
+) -> Result<ChannelFeature, CommandError> {
+    let raw_feature =
+        raw_feature.ok_or_else(|| CommandError::MissingArgument("feature".to_owned()))?;
+
+    ChannelFeature::from_str(raw_feature).map_err(|_| {
+        let features = ChannelFeature::ALL
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        CommandError::InvalidArgument(
+            Message::UnknownFeature {
+                feature: raw_feature,
+                features: &features,
+            }
+            .translate(language),
+        )
+    })
+}
@@ -0,0 +1,90 @@
+use super::*;
+use crate::{
+    command_handler::{i18n::Message, inquiry_helper::format_duration, twitch_api::get_followage},
+    platform::ChannelIdentifier,
+};
+
+#[derive(Debug, Clone)]
+pub struct Followage;
+
+#[async_trait]
+impl ExecutableCommand for Followage {
+    fn get_names(&self) -> &[&str] {
+        &["followage"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::Default
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let (broadcaster_id, channel_name) = match ctx.platform_ctx.get_channel() {
+            ChannelIdentifier::TwitchChannel((id, name)) => (id, name),
+            _ => {
+                return Err(CommandError::GenericError(
+                    Message::OnlyOnTwitch("followage").translate(language),
+                ))
+            }
+        };
+
+        let twitch_api = ctx.platform_handler.twitch_api.as_ref().ok_or_else(|| {
+            CommandError::GenericError(Message::TwitchNotConfigured.translate(language))
+        })?;
+
+        let (user_id, user_name) = match args.first() {
+            Some(user_name) => {
+                let user_name = user_name.trim_start_matches('@');
+                let user = twitch_api
+                    .helix_api
+                    .get_users(Some(&[user_name]), None)
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| {
+                        CommandError::InvalidArgument(
+                            Message::UserNotFound(user_name).translate(language),
+                        )
+                    })?;
+
+                (user.id, user.display_name)
+            }
+            None => {
+                let user_id = ctx
+                    .user
+                    .twitch_id
+                    .clone()
+                    .ok_or_else(|| CommandError::MissingArgument("user".to_owned()))?;
+
+                (user_id, ctx.platform_ctx.get_display_name().to_owned())
+            }
+        };
+
+        let channel_name = channel_name.unwrap_or_else(|| Message::TheChannel.translate(language));
+
+        let response = match get_followage(ctx.db, &broadcaster_id, &user_id).await? {
+            Some(duration) => Message::FollowingFor {
+                user: &user_name,
+                channel: &channel_name,
+                duration: &format_duration(duration),
+            }
+            .translate(language),
+            None => Message::NotFollowing {
+                user: &user_name,
+                channel: &channel_name,
+            }
+            .translate(language),
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
@@ -0,0 +1,41 @@
+use super::*;
+use crate::command_handler::i18n::Message;
+
+/// Clears the user's AI conversation in the channel
+#[derive(Clone)]
+pub struct Forget {
+    pub openai_api: Option<OpenAiApi>,
+}
+
+#[async_trait]
+impl ExecutableCommand for Forget {
+    fn get_names(&self) -> &[&str] {
+        &["forget"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        _: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = ctx.channel_id.ok_or_else(|| {
+            CommandError::InvalidArgument(Message::OnlyInChannel.translate(language))
+        })?;
+        let openai_api = self.openai_api.as_ref().ok_or_else(|| {
+            CommandError::GenericError(Message::AiNotConfigured.translate(language))
+        })?;
+
+        let message = match openai_api.forget(channel_id, ctx.user.id) {
+            true => Message::AiConversationForgotten,
+            false => Message::AiNoConversation,
+        };
+
+        Ok(CommandOutput::Text(message.translate(language)))
+    }
+}
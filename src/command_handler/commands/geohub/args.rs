@@ -15,6 +15,12 @@ pub enum Command {
     Link {
         username: String,
     },
+    /// Removes the link of the user, other users' links can only be removed by mods
+    Unlink {
+        user: Option<String>,
+    },
+    /// The links in the channel
+    List,
 }
 
 // #[derive(Parser)]
@@ -24,6 +30,10 @@ pub enum LeaderboardCommand {
         #[arg(long, default_value_t)]
         channel: bool,
     },
+    /// Scores of the channel's linked users since Monday
+    Weekly,
+    /// Scores of the channel's linked users since the start of the month
+    Monthly,
 }
 
 impl CommandArgs {
@@ -54,5 +64,11 @@ mod tests {
             args.cmd,
             Command::Leaderboard(LeaderboardCommand::Daily { channel: true })
         );
+
+        let args = CommandArgs::parse_from_args(&["leaderboard", "weekly"]).unwrap();
+        assert_eq!(args.cmd, Command::Leaderboard(LeaderboardCommand::Weekly));
+
+        let args = CommandArgs::parse_from_args(&["unlink"]).unwrap();
+        assert_eq!(args.cmd, Command::Unlink { user: None });
     }
 }
@@ -1,16 +1,23 @@
 mod args;
 
 use self::args::{Command, LeaderboardCommand};
-use super::ExecutableCommand;
+use super::{resolve_user, ExecutableCommand};
 use crate::{
     command_handler::{
-        commands::geohub::args::CommandArgs, error::CommandError, geohub::GeohubClient,
+        commands::geohub::args::CommandArgs,
+        error::CommandError,
+        geohub::{aggregate_results, GeohubClient, Period},
+        i18n::{Language, Message},
+        output::CommandOutput,
         ExecutionContext,
     },
     database::models::GeohubLink,
-    platform::PlatformContext,
+    platform::{Permissions, PlatformContext},
 };
 use async_trait::async_trait;
+use chrono::Utc;
+
+const PERIOD_LEADERBOARD_SIZE: usize = 10;
 
 #[derive(Default)]
 pub struct GeoHub {
@@ -32,11 +39,12 @@ impl ExecutableCommand for GeoHub {
         ctx: &ExecutionContext<'a, P>,
         _trigger_name: &str,
         args: Vec<&str>,
-    ) -> Result<Option<String>, CommandError> {
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
         let input = CommandArgs::parse_from_args(&args)?;
-        let channel_id = ctx
-            .channel_id
-            .ok_or_else(|| CommandError::InvalidArgument("not running in a channel".to_owned()))?;
+        let channel_id = ctx.channel_id.ok_or_else(|| {
+            CommandError::InvalidArgument(Message::OnlyInChannel.translate(language))
+        })?;
 
         match input.cmd {
             Command::Leaderboard(LeaderboardCommand::Daily { channel }) => {
@@ -44,7 +52,10 @@ impl ExecutableCommand for GeoHub {
                 let leaderboard = self.client.get_leaderboard(limit).await?;
 
                 let scores = if channel {
-                    let names = ctx.db.get_geohub_link_names(channel_id)?;
+                    let names = ctx
+                        .db
+                        .run(move |db| db.get_geohub_link_names(channel_id))
+                        .await?;
                     leaderboard
                         .today
                         .into_iter()
@@ -55,8 +66,8 @@ impl ExecutableCommand for GeoHub {
                 };
 
                 if scores.is_empty() {
-                    return Ok(Some(
-                        "Nobody has played the daily challenge yet!".to_owned(),
+                    return Ok(CommandOutput::Text(
+                        Message::GeohubNoScores.translate(language),
                     ));
                 }
 
@@ -65,7 +76,15 @@ impl ExecutableCommand for GeoHub {
                     .map(|entry| format!("{}: {}", entry.user_name, entry.total_points))
                     .collect::<Vec<String>>()
                     .join(", ");
-                Ok(Some(format!("Top daily challenge scores: {users_output}")))
+                Ok(CommandOutput::Text(
+                    Message::GeohubDailyScores(&users_output).translate(language),
+                ))
+            }
+            Command::Leaderboard(LeaderboardCommand::Weekly) => {
+                period_leaderboard(ctx, channel_id, Period::Week, language).await
+            }
+            Command::Leaderboard(LeaderboardCommand::Monthly) => {
+                period_leaderboard(ctx, channel_id, Period::Month, language).await
             }
             Command::Link { username } => {
                 let link = GeohubLink {
@@ -73,9 +92,118 @@ impl ExecutableCommand for GeoHub {
                     channel_id,
                     geohub_name: username,
                 };
-                ctx.db.create_geohub_link(link)?;
-                Ok(Some("Succesfully linked.".to_owned()))
+                ctx.db.run(move |db| db.create_geohub_link(link)).await?;
+                Ok(CommandOutput::Text(
+                    Message::GeohubLinked.translate(language),
+                ))
+            }
+            Command::Unlink { user: None } => {
+                let user_id = ctx.user.id;
+                match ctx
+                    .db
+                    .run(move |db| db.delete_geohub_link(user_id, channel_id))
+                    .await?
+                {
+                    true => Ok(CommandOutput::Text(
+                        Message::GeohubUnlinked.translate(language),
+                    )),
+                    false => Err(CommandError::InvalidArgument(
+                        Message::GeohubNotLinked.translate(language),
+                    )),
+                }
+            }
+            Command::Unlink { user: Some(user) } => {
+                if ctx.get_permissions().await? < Permissions::ChannelMod {
+                    return Err(CommandError::NoPermissions);
+                }
+
+                let identifier = resolve_user(ctx, &user).await?;
+                let user = ctx
+                    .db
+                    .run(move |db| db.get_user(&identifier))
+                    .await?
+                    .ok_or_else(|| {
+                        CommandError::InvalidArgument(
+                            Message::UserNotKnown(&user).translate(language),
+                        )
+                    })?;
+
+                match ctx
+                    .db
+                    .run(move |db| db.delete_geohub_link(user.id, channel_id))
+                    .await?
+                {
+                    true => Ok(CommandOutput::Text(
+                        Message::GeohubUnlinked.translate(language),
+                    )),
+                    false => Err(CommandError::InvalidArgument(
+                        Message::GeohubUserNotLinked.translate(language),
+                    )),
+                }
+            }
+            Command::List => {
+                let links = ctx
+                    .db
+                    .run(move |db| db.get_channel_geohub_links(channel_id))
+                    .await?;
+
+                if links.is_empty() {
+                    return Ok(CommandOutput::Text(
+                        Message::GeohubNoLinks.translate(language),
+                    ));
+                }
+
+                let names = links
+                    .into_iter()
+                    .map(|link| link.geohub_name)
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                Ok(CommandOutput::Text(
+                    Message::GeohubLinks(&names).translate(language),
+                ))
             }
         }
     }
 }
+
+/// Built from the results saved by the GeoHub listener
+async fn period_leaderboard<P: PlatformContext + Send + Sync>(
+    ctx: &ExecutionContext<'_, P>,
+    channel_id: u64,
+    period: Period,
+    language: Language,
+) -> Result<CommandOutput, CommandError> {
+    let since = period.start(Utc::now().date_naive());
+    let results = ctx
+        .db
+        .run(move |db| db.get_geohub_results(channel_id, since))
+        .await?;
+    let scores = aggregate_results(results);
+
+    if scores.is_empty() {
+        return Ok(CommandOutput::Text(
+            Message::GeohubNoScores.translate(language),
+        ));
+    }
+
+    let users_output = scores
+        .into_iter()
+        .take(PERIOD_LEADERBOARD_SIZE)
+        .map(|score| {
+            Message::GeohubPeriodScore {
+                name: &score.geohub_name,
+                points: score.total_points,
+                days: score.days,
+            }
+            .translate(language)
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let message = match period {
+        Period::Day => Message::GeohubDailyScores(&users_output),
+        Period::Week => Message::GeohubWeeklyScores(&users_output),
+        Period::Month => Message::GeohubMonthlyScores(&users_output),
+    };
+    Ok(CommandOutput::Text(message.translate(language)))
+}
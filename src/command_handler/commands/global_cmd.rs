@@ -0,0 +1,185 @@
+use std::str::FromStr;
+
+use super::*;
+use crate::{
+    command_handler::i18n::{Language, Message},
+    database::{
+        models::{CommandMode, NewGlobalCommand},
+        DatabaseError,
+    },
+};
+
+/// `globalcmd add/edit/remove/show/list/mode` manages the commands that work in every channel,
+/// unless the channel has its own command with the same name
+#[derive(Debug, Clone)]
+pub struct GlobalCmd;
+
+#[async_trait]
+impl ExecutableCommand for GlobalCmd {
+    fn get_names(&self) -> &[&str] {
+        &["globalcmd"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        0
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::Admin
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let mut args = args.into_iter();
+
+        let subcommand = args.next().ok_or_else(|| {
+            CommandError::MissingArgument("one of add, edit, remove, show, list, mode".to_owned())
+        })?;
+
+        let response = match subcommand {
+            "add" => {
+                let name = get_name(&mut args, ctx)?;
+                let action = get_action(args)?;
+
+                let owned_name = name.to_owned();
+                let result = ctx
+                    .db
+                    .run(move |db| {
+                        db.add_global_command(NewGlobalCommand {
+                            name: &owned_name,
+                            action: &action,
+                        })
+                    })
+                    .await;
+
+                match result {
+                    Ok(()) => Message::GlobalCommandAdded(name).translate(language),
+                    Err(DatabaseError::DieselError(diesel::result::Error::DatabaseError(
+                        diesel::result::DatabaseErrorKind::UniqueViolation,
+                        _,
+                    ))) => Message::GlobalCommandExists(name).translate(language),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            "edit" => {
+                let name = get_name(&mut args, ctx)?;
+                let action = get_action(args)?;
+
+                let owned_name = name.to_owned();
+                let updated = ctx
+                    .db
+                    .run(move |db| db.update_global_command_action(&owned_name, &action))
+                    .await?;
+
+                match updated {
+                    true => Message::GlobalCommandUpdated(name).translate(language),
+                    false => return Err(not_found(name, language)),
+                }
+            }
+            "remove" | "delete" => {
+                let name = get_name(&mut args, ctx)?;
+
+                let owned_name = name.to_owned();
+                let deleted = ctx
+                    .db
+                    .run(move |db| db.delete_global_command(&owned_name))
+                    .await?;
+
+                match deleted {
+                    true => Message::GlobalCommandRemoved(name).translate(language),
+                    false => return Err(not_found(name, language)),
+                }
+            }
+            "show" => {
+                let name = get_name(&mut args, ctx)?;
+
+                let owned_name = name.to_owned();
+                ctx.db
+                    .run(move |db| db.get_global_command(&owned_name))
+                    .await?
+                    .ok_or_else(|| not_found(name, language))?
+                    .action
+            }
+            "list" => {
+                let commands = ctx.db.run(|db| db.get_global_commands()).await?;
+
+                if commands.is_empty() {
+                    Message::NoGlobalCommands.translate(language)
+                } else {
+                    let names = commands
+                        .iter()
+                        .map(|command| command.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    Message::GlobalCommands(&names).translate(language)
+                }
+            }
+            "mode" => {
+                let name = get_name(&mut args, ctx)?;
+                let raw_mode = args
+                    .next()
+                    .ok_or_else(|| CommandError::MissingArgument("command mode".to_owned()))?;
+                let mode = CommandMode::from_str(raw_mode).map_err(|_| {
+                    CommandError::InvalidArgument(
+                        Message::InvalidCommandMode(raw_mode).translate(language),
+                    )
+                })?;
+
+                let owned_name = name.to_owned();
+                let updated = ctx
+                    .db
+                    .run(move |db| db.set_global_command_mode(&owned_name, mode))
+                    .await?;
+
+                match updated {
+                    true => Message::GlobalCommandModeUpdated {
+                        name,
+                        mode: raw_mode,
+                    }
+                    .translate(language),
+                    false => return Err(not_found(name, language)),
+                }
+            }
+            other => return Err(CommandError::InvalidArgument(other.to_owned())),
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+/// The name can be given with a prefix, such as `!hello`
+fn get_name<'a, P: PlatformContext + Send + Sync>(
+    args: &mut impl Iterator<Item = &'a str>,
+    ctx: &ExecutionContext<'_, P>,
+) -> Result<&'a str, CommandError> {
+    let mut name = args
+        .next()
+        .ok_or_else(|| CommandError::MissingArgument("command name".to_owned()))?;
+
+    for prefix in ctx.platform_ctx.get_prefixes() {
+        if let Some(stripped_name) = name.strip_prefix(prefix) {
+            name = stripped_name;
+        }
+    }
+
+    Ok(name)
+}
+
+fn get_action<'a>(args: impl Iterator<Item = &'a str>) -> Result<String, CommandError> {
+    let action = args.collect::<Vec<_>>().join(" ");
+
+    if action.is_empty() {
+        return Err(CommandError::MissingArgument("command action".to_owned()));
+    }
+
+    Ok(action)
+}
+
+fn not_found(name: &str, language: Language) -> CommandError {
+    CommandError::InvalidArgument(Message::GlobalCommandNotFound(name).translate(language))
+}
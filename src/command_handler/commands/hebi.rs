@@ -1,10 +1,20 @@
+use super::debug::execute_with_report;
 use super::*;
-use crate::command_handler::eval::{eval_hebi, storage::ModuleStorage};
+use crate::command_handler::{
+    debug_report::DebugReports,
+    eval::{context::HebiContext, eval_hebi, storage::ModuleStorage},
+    image_api::ImageApi,
+    openai_api::OpenAiApi,
+};
+use crate::database::models::CommandMode;
 use ::hebi::prelude::NativeModule;
 
 pub struct DebugHebi {
     native_modules: Arc<Vec<NativeModule>>,
     module_storage: ModuleStorage,
+    openai_api: Option<OpenAiApi>,
+    image_api: Option<ImageApi>,
+    debug_reports: DebugReports,
 }
 
 #[async_trait]
@@ -26,29 +36,49 @@ impl ExecutableCommand for DebugHebi {
         ctx: &ExecutionContext<'a, P>,
         _trigger_name: &str,
         args: Vec<&str>,
-    ) -> Result<Option<String>, CommandError> {
+    ) -> Result<CommandOutput, CommandError> {
         let action = args.join(" ");
 
         let db = ctx.db.clone();
-        let hebi_ctx = ctx.try_into()?;
+        let hebi_ctx = HebiContext::new(ctx).await?;
 
-        eval_hebi(
-            action,
+        let execution = eval_hebi(
+            action.clone(),
             &self.native_modules,
             self.module_storage.clone(),
             db,
+            self.openai_api.as_ref(),
+            self.image_api.as_ref(),
+            ctx.platform_handler,
             &[],
             hebi_ctx,
+        );
+
+        execute_with_report(
+            ctx,
+            &self.debug_reports,
+            CommandMode::Hebi,
+            action,
+            execution,
         )
         .await
     }
 }
 
 impl DebugHebi {
-    pub fn new(native_modules: Arc<Vec<NativeModule>>, module_storage: ModuleStorage) -> Self {
+    pub fn new(
+        native_modules: Arc<Vec<NativeModule>>,
+        module_storage: ModuleStorage,
+        openai_api: Option<OpenAiApi>,
+        image_api: Option<ImageApi>,
+        debug_reports: DebugReports,
+    ) -> Self {
         Self {
             native_modules,
             module_storage,
+            openai_api,
+            image_api,
+            debug_reports,
         }
     }
 }
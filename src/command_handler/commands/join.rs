@@ -0,0 +1,125 @@
+use super::*;
+use crate::command_handler::i18n::Message;
+
+/// `join` is used in the bot's own channel and joins the channel of the user who sent it,
+/// `part` leaves the current channel. The admin can specify any channel for both
+#[derive(Debug, Clone)]
+pub struct Join;
+
+#[async_trait]
+impl ExecutableCommand for Join {
+    fn get_names(&self) -> &[&str] {
+        &["join", "part"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        10
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::Default // Permissions are checked depending on the trigger
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        trigger_name: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = match ctx.platform_ctx.get_channel() {
+            ChannelIdentifier::TwitchChannel((id, _)) => id,
+            _ => {
+                return Err(CommandError::GenericError(
+                    Message::JoiningOnlyOnTwitch.translate(language),
+                ))
+            }
+        };
+
+        let twitch_api = ctx.platform_handler.twitch_api.as_ref().ok_or_else(|| {
+            CommandError::GenericError(Message::TwitchNotConfigured.translate(language))
+        })?;
+
+        let permissions = ctx.get_permissions().await?;
+
+        let target_login = match args.first() {
+            Some(login) => {
+                if permissions < Permissions::Admin {
+                    return Err(CommandError::NoPermissions);
+                }
+                Some(login.trim_start_matches('@').to_lowercase())
+            }
+            None => None,
+        };
+
+        match trigger_name {
+            "join" => {
+                let login = match target_login {
+                    Some(login) => login,
+                    None => {
+                        let bot_user = twitch_api.helix_api.get_self_user().await?;
+                        if bot_user.id != channel_id {
+                            return Err(CommandError::GenericError(
+                                Message::UseInChannelOf(&bot_user.login).translate(language),
+                            ));
+                        }
+
+                        let user_id = match ctx.platform_ctx.get_user_identifier() {
+                            UserIdentifier::TwitchID(user_id) => user_id,
+                            _ => return Err(CommandError::NoPermissions),
+                        };
+                        twitch_api.helix_api.get_user_by_id(&user_id).await?.login
+                    }
+                };
+
+                ctx.platform_handler
+                    .join_twitch_channel(ctx.db, &login)
+                    .await?;
+
+                Ok(CommandOutput::Text(
+                    Message::JoinedChannel(&login).translate(language),
+                ))
+            }
+            "part" => {
+                let channel = match target_login {
+                    Some(login) => {
+                        let user = twitch_api.helix_api.get_user_by_login(&login).await?;
+                        let identifier = ChannelIdentifier::TwitchChannel((user.id, None));
+                        ctx.db
+                            .run(move |db| db.get_channel(&identifier))
+                            .await?
+                            .ok_or_else(|| {
+                                CommandError::InvalidArgument(
+                                    Message::ChannelNotJoined(&login).translate(language),
+                                )
+                            })?
+                    }
+                    None => {
+                        if permissions < Permissions::ChannelOwner {
+                            return Err(CommandError::NoPermissions);
+                        }
+
+                        let channel_id = ctx.channel_id.ok_or_else(|| {
+                            CommandError::GenericError(Message::OnlyInChannel.translate(language))
+                        })?;
+                        ctx.db
+                            .run(move |db| db.get_channel_by_id(channel_id))
+                            .await?
+                            .ok_or_else(|| {
+                                CommandError::GenericError(
+                                    Message::UnknownChannel.translate(language),
+                                )
+                            })?
+                    }
+                };
+
+                ctx.platform_handler.part_channel(ctx.db, &channel).await?;
+
+                Ok(CommandOutput::Text(
+                    Message::LeavingChannel.translate(language),
+                ))
+            }
+            _ => Err(CommandError::InvalidArgument(trigger_name.to_owned())),
+        }
+    }
+}
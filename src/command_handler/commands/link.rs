@@ -0,0 +1,240 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::*;
+use crate::command_handler::i18n::{Language, Message};
+use crate::database::{
+    cache::TtlCache,
+    models::{NewUserMerge, User, UserIdentity, UserMergeMethod},
+    DatabaseError,
+};
+use passwords::PasswordGenerator;
+
+const CODE_TTL: Duration = Duration::from_secs(600);
+
+/// `link twitch` issues a one-time code, which is sent with `link <code>` from the Twitch account.
+/// The code is shown in the chat, so the merge only happens after `link confirm` from the
+/// account that requested it. If someone else used the code first, the user's own attempt fails
+#[derive(Clone)]
+pub struct Link {
+    /// Keyed by the code
+    requests: Arc<TtlCache<String, LinkRequest>>,
+    /// Keyed by the user who requested the link
+    confirmations: Arc<TtlCache<u64, LinkConfirmation>>,
+}
+
+#[derive(Clone)]
+struct LinkRequest {
+    user_id: u64,
+    identity: UserIdentity,
+}
+
+#[derive(Clone)]
+struct LinkConfirmation {
+    user_id: u64,
+    identifier: UserIdentifier,
+}
+
+impl Default for Link {
+    fn default() -> Self {
+        Self {
+            requests: Arc::new(TtlCache::new(CODE_TTL)),
+            confirmations: Arc::new(TtlCache::new(CODE_TTL)),
+        }
+    }
+}
+
+#[async_trait]
+impl ExecutableCommand for Link {
+    fn get_names(&self) -> &[&str] {
+        &["link"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let arg = args
+            .first()
+            .ok_or_else(|| CommandError::MissingArgument("platform, code or confirm".to_owned()))?;
+
+        let language = ctx.get_language().await?;
+        let response = match *arg {
+            "confirm" => self.confirm(ctx, language).await?,
+            arg => match UserIdentity::from_str(&arg.to_lowercase()) {
+                Ok(identity) => self.request(ctx, identity, language)?,
+                Err(_) => self.redeem(ctx, arg, language).await?,
+            },
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+impl Link {
+    fn request<P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'_, P>,
+        identity: UserIdentity,
+        language: Language,
+    ) -> Result<String, CommandError> {
+        let identity_name = identity.to_string();
+        if !matches!(identity, UserIdentity::Twitch | UserIdentity::Discord) {
+            return Err(CommandError::InvalidArgument(
+                Message::LinkOnlyOnWebsite(&identity_name).translate(language),
+            ));
+        }
+        if ctx.user.has_identity(identity) {
+            return Err(CommandError::GenericError(
+                Message::LinkAlreadyLinked(&identity_name).translate(language),
+            ));
+        }
+
+        let code = generate_code();
+        self.requests.insert(
+            code.clone(),
+            LinkRequest {
+                user_id: ctx.user.id,
+                identity,
+            },
+        );
+
+        Ok(Message::LinkCodeSent {
+            code: &code,
+            identity: &identity_name,
+        }
+        .translate(language))
+    }
+
+    async fn redeem<P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'_, P>,
+        code: &str,
+        language: Language,
+    ) -> Result<String, CommandError> {
+        let code = code.to_uppercase();
+        let request = self.requests.get(&code).ok_or_else(|| {
+            CommandError::InvalidArgument(Message::LinkInvalidCode.translate(language))
+        })?;
+
+        let identifier = ctx.platform_ctx.get_user_identifier();
+        if get_identity(&identifier) != Some(request.identity) {
+            return Err(CommandError::GenericError(
+                Message::LinkWrongAccount(&request.identity.to_string()).translate(language),
+            ));
+        }
+
+        let requesting_user_id = request.user_id;
+        let requesting_user = ctx
+            .db
+            .run(move |db| db.get_user_by_id(requesting_user_id))
+            .await?
+            .ok_or_else(|| {
+                CommandError::GenericError(Message::LinkUserMissing.translate(language))
+            })?;
+        check_mergeable(&requesting_user, ctx.user, language)?;
+
+        self.requests.remove(&code);
+        self.confirmations.insert(
+            request.user_id,
+            LinkConfirmation {
+                user_id: ctx.user.id,
+                identifier,
+            },
+        );
+
+        Ok(Message::LinkConfirmRequired.translate(language))
+    }
+
+    async fn confirm<P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'_, P>,
+        language: Language,
+    ) -> Result<String, CommandError> {
+        let confirmation = self.confirmations.get(&ctx.user.id).ok_or_else(|| {
+            CommandError::GenericError(Message::LinkNothingToConfirm.translate(language))
+        })?;
+        self.confirmations.remove(&ctx.user.id);
+
+        let other_user_id = confirmation.user_id;
+        let other_user = ctx
+            .db
+            .run(move |db| db.get_user_by_id(other_user_id))
+            .await?
+            .ok_or_else(|| {
+                CommandError::GenericError(Message::LinkUserMissing.translate(language))
+            })?;
+        check_mergeable(ctx.user, &other_user, language)?;
+
+        let own_user = ctx.user.clone();
+        let identity = confirmation.identifier.to_string();
+        let user = ctx
+            .db
+            .run(move |db| {
+                let user = db.merge_users(own_user, other_user)?;
+                db.add_user_merge(NewUserMerge {
+                    user_id: user.id,
+                    merged_user_id: other_user_id,
+                    identity: &identity,
+                    method: &UserMergeMethod::Chat.to_string(),
+                })?;
+                Ok::<_, DatabaseError>(user)
+            })
+            .await?;
+        tracing::info!(
+            "Merged user {} into {} with a chat verification code",
+            confirmation.user_id,
+            user.id
+        );
+
+        Ok(Message::LinkCompleted.translate(language))
+    }
+}
+
+fn get_identity(identifier: &UserIdentifier) -> Option<UserIdentity> {
+    match identifier {
+        UserIdentifier::TwitchID(_) => Some(UserIdentity::Twitch),
+        UserIdentifier::DiscordID(_) => Some(UserIdentity::Discord),
+        _ => None,
+    }
+}
+
+/// Identities that both users have would be lost when merging
+fn check_mergeable(user: &User, other: &User, language: Language) -> Result<(), CommandError> {
+    if user.id == other.id {
+        return Err(CommandError::GenericError(
+            Message::LinkSameUser.translate(language),
+        ));
+    }
+
+    match UserIdentity::ALL
+        .into_iter()
+        .find(|identity| user.has_identity(*identity) && other.has_identity(*identity))
+    {
+        Some(identity) => Err(CommandError::GenericError(
+            Message::LinkConflict(&identity.to_string()).translate(language),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn generate_code() -> String {
+    PasswordGenerator {
+        length: 6,
+        numbers: true,
+        lowercase_letters: false,
+        uppercase_letters: true,
+        symbols: false,
+        spaces: false,
+        exclude_similar_characters: true,
+        strict: true,
+    }
+    .generate_one()
+    .expect("Failed to generate link code")
+}
@@ -0,0 +1,134 @@
+use super::*;
+use crate::command_handler::{i18n::Message, markov::Markov};
+use crate::database::DatabaseError;
+use tokio::task;
+
+/// `markov [user]` generates a sentence from the recorded messages of the channel.
+/// `markov optout`/`markov optin` controls the recording of the user's own messages,
+/// `markov purge [user]` deletes the recorded messages of the channel (mods)
+#[derive(Clone)]
+pub struct MarkovCommand {
+    pub markov: Markov,
+}
+
+#[async_trait]
+impl ExecutableCommand for MarkovCommand {
+    fn get_names(&self) -> &[&str] {
+        &["markov"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        10
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let mut args = args.into_iter();
+
+        // Opting out is possible in any channel, even where markov is disabled
+        let response = match args.next() {
+            Some("optout") => {
+                let user_id = ctx.user.id;
+                self.run(move |markov| markov.set_opt_out(user_id, true))
+                    .await?;
+                Message::MarkovOptedOut.translate(language)
+            }
+            Some("optin") => {
+                let user_id = ctx.user.id;
+                self.run(move |markov| markov.set_opt_out(user_id, false))
+                    .await?;
+                Message::MarkovOptedIn.translate(language)
+            }
+            Some("purge") => {
+                let channel_id = self.get_channel_id(ctx).await?;
+                if ctx.get_permissions().await? < Permissions::ChannelMod {
+                    return Err(CommandError::NoPermissions);
+                }
+
+                let user_id = match args.next() {
+                    Some(user) => Some(resolve_user_id(ctx, user).await?),
+                    None => None,
+                };
+
+                let deleted = ctx
+                    .db
+                    .run(move |db| db.delete_markov_messages(channel_id, user_id))
+                    .await?;
+                Message::MarkovPurged(deleted).translate(language)
+            }
+            user => {
+                let channel_id = self.get_channel_id(ctx).await?;
+
+                let user_id = match user {
+                    Some(user) => {
+                        let user_id = resolve_user_id(ctx, user).await?;
+                        if self.run(move |markov| markov.is_opted_out(user_id)).await? {
+                            return Err(CommandError::GenericError(
+                                Message::UserOptedOut {
+                                    user,
+                                    command: "markov",
+                                }
+                                .translate(language),
+                            ));
+                        }
+                        Some(user_id)
+                    }
+                    None => None,
+                };
+
+                let sentence = self
+                    .run(move |markov| markov.generate(channel_id, user_id))
+                    .await?;
+
+                sentence.ok_or_else(|| {
+                    CommandError::GenericError(Message::MarkovNoMessages.translate(language))
+                })?
+            }
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+impl MarkovCommand {
+    /// Markov reads and writes the database synchronously, so it's used on the blocking pool
+    async fn run<F, T>(&self, f: F) -> Result<T, CommandError>
+    where
+        F: FnOnce(&Markov) -> Result<T, DatabaseError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let markov = self.markov.clone();
+
+        Ok(task::spawn_blocking(move || f(&markov))
+            .await
+            .map_err(|e| CommandError::InternalError(e.to_string()))??)
+    }
+
+    /// Only channels that enabled the `markov` setting have their messages recorded
+    async fn get_channel_id<P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'_, P>,
+    ) -> Result<u64, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = ctx.channel_id.ok_or_else(|| {
+            CommandError::InvalidArgument(Message::OnlyInChannel.translate(language))
+        })?;
+
+        let settings = ctx
+            .db
+            .run(move |db| db.get_channel_settings(channel_id))
+            .await?;
+        if !settings.markov {
+            return Err(CommandError::GenericError(
+                Message::MarkovDisabled.translate(language),
+            ));
+        }
+
+        Ok(channel_id)
+    }
+}
@@ -0,0 +1,353 @@
+use super::points::parse_points;
+use super::*;
+use crate::command_handler::i18n::{Language, Message};
+use crate::database::DatabaseError;
+use dashmap::DashMap;
+use rand::Rng;
+use std::time::{Duration, Instant};
+
+/// How long a challenged user has to accept a duel
+const DUEL_TIMEOUT: Duration = Duration::from_secs(60);
+
+const SLOTS_SYMBOLS: [&str; 5] = ["🍒", "🍋", "🍇", "🔔", "💎"];
+/// Three of a kind pays the cost of the spin multiplied by this, a pair returns the cost
+const SLOTS_JACKPOT_MULTIPLIER: u64 = 10;
+
+/// `gamble <amount|all>` doubles the points with the channel's `gamble_win_chance`, or loses them
+#[derive(Debug, Clone)]
+pub struct Gamble;
+
+#[async_trait]
+impl ExecutableCommand for Gamble {
+    fn get_names(&self) -> &[&str] {
+        &["gamble"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = get_channel_id(ctx, language)?;
+        let settings = ctx
+            .db
+            .run(move |db| db.get_channel_settings(channel_id))
+            .await?;
+        if !settings.gamble {
+            return Err(CommandError::GenericError(
+                Message::GambleDisabled.translate(language),
+            ));
+        }
+
+        let user_id = ctx.user.id;
+        let stake = match args.first() {
+            Some(&"all") => {
+                ctx.db
+                    .run(move |db| db.get_points(channel_id, user_id))
+                    .await?
+            }
+            amount => parse_points(amount.copied(), language)?,
+        };
+        if stake == 0 {
+            return Err(CommandError::InvalidArgument(
+                Message::NoPoints.translate(language),
+            ));
+        }
+
+        let won = rand::thread_rng().gen_range(0..100) < settings.gamble_win_chance;
+        let payout = if won { stake.saturating_mul(2) } else { 0 };
+
+        let balance = ctx
+            .db
+            .run(move |db| db.play_points(channel_id, user_id, stake, payout))
+            .await?;
+        let response = match balance {
+            Some(balance) if won => Message::GambleWon { stake, balance },
+            Some(balance) => Message::GambleLost { stake, balance },
+            None => {
+                return Err(CommandError::InvalidArgument(
+                    Message::NotEnoughPoints(stake).translate(language),
+                ))
+            }
+        };
+
+        Ok(CommandOutput::Text(response.translate(language)))
+    }
+}
+
+/// `duel <user> <amount>` challenges the user, who can `duel accept` or `duel decline` it.
+/// The winner is picked at random and takes the amount from the loser.
+#[derive(Debug, Clone, Default)]
+pub struct Duel {
+    /// Pending challenges by the channel and the challenged user
+    challenges: Arc<DashMap<(u64, u64), DuelChallenge>>,
+}
+
+#[derive(Debug, Clone)]
+struct DuelChallenge {
+    challenger_id: u64,
+    challenger_name: String,
+    amount: u64,
+    created_at: Instant,
+}
+
+#[async_trait]
+impl ExecutableCommand for Duel {
+    fn get_names(&self) -> &[&str] {
+        &["duel"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = get_channel_id(ctx, language)?;
+        let settings = ctx
+            .db
+            .run(move |db| db.get_channel_settings(channel_id))
+            .await?;
+        if !settings.duel {
+            return Err(CommandError::GenericError(
+                Message::DuelsDisabled.translate(language),
+            ));
+        }
+
+        self.challenges
+            .retain(|_, challenge| challenge.created_at.elapsed() < DUEL_TIMEOUT);
+
+        let mut args = args.into_iter();
+        let user = args
+            .next()
+            .ok_or_else(|| CommandError::MissingArgument("user".to_owned()))?;
+
+        match user {
+            "accept" => self.accept(ctx, channel_id, language).await,
+            "decline" => match self.challenges.remove(&(channel_id, ctx.user.id)) {
+                Some((_, challenge)) => Ok(CommandOutput::Text(
+                    Message::DuelDeclined(&challenge.challenger_name).translate(language),
+                )),
+                None => Err(CommandError::GenericError(
+                    Message::NoDuelChallenge.translate(language),
+                )),
+            },
+            user => {
+                let amount = parse_points(args.next(), language)?;
+
+                let identifier = resolve_user(ctx, user).await?;
+                if identifier == ctx.platform_ctx.get_user_identifier() {
+                    return Err(CommandError::InvalidArgument(
+                        Message::CannotDuelYourself.translate(language),
+                    ));
+                }
+                let user_id = ctx.user.id;
+                let (target, balance) = ctx
+                    .db
+                    .run(move |db| {
+                        let target = db.get_or_create_user(&identifier)?;
+                        let balance = db.get_points(channel_id, user_id)?;
+                        Ok::<_, DatabaseError>((target, balance))
+                    })
+                    .await?;
+
+                if balance < amount {
+                    return Err(CommandError::InvalidArgument(
+                        Message::NotEnoughPoints(amount).translate(language),
+                    ));
+                }
+
+                self.challenges.insert(
+                    (channel_id, target.id),
+                    DuelChallenge {
+                        challenger_id: ctx.user.id,
+                        challenger_name: ctx.platform_ctx.get_display_name().to_owned(),
+                        amount,
+                        created_at: Instant::now(),
+                    },
+                );
+
+                Ok(CommandOutput::Text(
+                    Message::DuelChallenge { user, amount }.translate(language),
+                ))
+            }
+        }
+    }
+}
+
+impl Duel {
+    async fn accept<P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'_, P>,
+        channel_id: u64,
+        language: Language,
+    ) -> Result<CommandOutput, CommandError> {
+        let (_, challenge) = self
+            .challenges
+            .remove(&(channel_id, ctx.user.id))
+            .ok_or_else(|| {
+                CommandError::GenericError(Message::NoDuelChallenge.translate(language))
+            })?;
+        let amount = challenge.amount;
+
+        let user_id = ctx.user.id;
+        let balance = ctx
+            .db
+            .run(move |db| db.get_points(channel_id, user_id))
+            .await?;
+        if balance < amount {
+            return Err(CommandError::InvalidArgument(
+                Message::NotEnoughPoints(amount).translate(language),
+            ));
+        }
+
+        let display_name = ctx.platform_ctx.get_display_name();
+        let accepted_won = rand::thread_rng().gen_bool(0.5);
+        let (winner_id, winner_name, loser_id) = if accepted_won {
+            (ctx.user.id, display_name, challenge.challenger_id)
+        } else {
+            (
+                challenge.challenger_id,
+                challenge.challenger_name.as_str(),
+                ctx.user.id,
+            )
+        };
+
+        let transferred = ctx
+            .db
+            .run(move |db| db.transfer_points(channel_id, loser_id, winner_id, amount))
+            .await?;
+        if transferred {
+            Ok(CommandOutput::Text(
+                Message::DuelWon {
+                    user: winner_name,
+                    amount,
+                }
+                .translate(language),
+            ))
+        } else {
+            Err(CommandError::GenericError(
+                Message::DuelCancelled {
+                    user: &challenge.challenger_name,
+                    amount,
+                }
+                .translate(language),
+            ))
+        }
+    }
+}
+
+/// `slots` spins three reels for the channel's `slots_cost`
+#[derive(Debug, Clone)]
+pub struct Slots;
+
+#[async_trait]
+impl ExecutableCommand for Slots {
+    fn get_names(&self) -> &[&str] {
+        &["slots"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        _: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = get_channel_id(ctx, language)?;
+        let settings = ctx
+            .db
+            .run(move |db| db.get_channel_settings(channel_id))
+            .await?;
+        if !settings.slots {
+            return Err(CommandError::GenericError(
+                Message::SlotsDisabled.translate(language),
+            ));
+        }
+        let cost = settings.slots_cost;
+
+        let reels: [usize; 3] = {
+            let mut rng = rand::thread_rng();
+            [(); 3].map(|_| rng.gen_range(0..SLOTS_SYMBOLS.len()))
+        };
+        let payout = slots_payout(reels, cost);
+
+        let user_id = ctx.user.id;
+        let balance = ctx
+            .db
+            .run(move |db| db.play_points(channel_id, user_id, cost, payout))
+            .await?
+            .ok_or_else(|| {
+                CommandError::InvalidArgument(Message::SlotsCost(cost).translate(language))
+            })?;
+
+        let symbols = reels.map(|reel| SLOTS_SYMBOLS[reel]).join(" ");
+        let response = if payout > cost {
+            Message::SlotsWon {
+                symbols: &symbols,
+                payout,
+                balance,
+            }
+        } else if payout == cost {
+            Message::SlotsRefunded {
+                symbols: &symbols,
+                balance,
+            }
+        } else {
+            Message::SlotsLost {
+                symbols: &symbols,
+                cost,
+                balance,
+            }
+        };
+
+        Ok(CommandOutput::Text(response.translate(language)))
+    }
+}
+
+fn slots_payout(reels: [usize; 3], cost: u64) -> u64 {
+    let [a, b, c] = reels;
+
+    if a == b && b == c {
+        cost.saturating_mul(SLOTS_JACKPOT_MULTIPLIER)
+    } else if a == b || b == c || a == c {
+        cost
+    } else {
+        0
+    }
+}
+
+fn get_channel_id<P: PlatformContext>(
+    ctx: &ExecutionContext<'_, P>,
+    language: Language,
+) -> Result<u64, CommandError> {
+    ctx.channel_id
+        .ok_or_else(|| CommandError::InvalidArgument(Message::OnlyInChannel.translate(language)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::slots_payout;
+
+    #[test]
+    fn pays_out_slots() {
+        assert_eq!(slots_payout([4, 4, 4], 10), 100);
+        assert_eq!(slots_payout([1, 3, 1], 10), 10);
+        assert_eq!(slots_payout([0, 1, 2], 10), 0);
+    }
+}
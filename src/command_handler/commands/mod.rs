@@ -1,24 +1,93 @@
+mod ai_config;
+mod block;
+mod bot;
+mod channel_safety;
+mod channel_settings;
+mod chat_logs;
 mod cmd;
+mod compat;
 mod debug;
+mod features;
+mod followage;
+mod forget;
 mod geohub;
+mod global_cmd;
 mod hebi;
+mod join;
+mod link;
+mod markov;
+mod minigames;
+mod moderation;
+mod name_history;
+mod personal;
 mod ping;
+mod points;
+mod portfolio;
+mod preferences;
+mod protection;
 mod reload;
+mod safe_mode;
 mod shell;
+mod status;
+mod stream_info;
+mod stream_markers;
+mod time;
+mod translate;
 mod twitch_eventsub;
+mod watch;
 mod whoami;
 
 use self::{
-    cmd::Cmd, debug::Debug, geohub::GeoHub, hebi::DebugHebi, ping::Ping, reload::Reload,
-    shell::Shell, twitch_eventsub::TwitchEventSub, whoami::WhoAmI,
+    ai_config::AiConfig,
+    block::Block,
+    bot::BotSettings,
+    channel_safety::ChannelSafety,
+    channel_settings::Settings,
+    chat_logs::{Lines, Seen},
+    cmd::Cmd,
+    compat::Compat,
+    debug::Debug,
+    features::Features,
+    followage::Followage,
+    forget::Forget,
+    geohub::GeoHub,
+    global_cmd::GlobalCmd,
+    hebi::DebugHebi,
+    join::Join,
+    link::Link,
+    markov::MarkovCommand,
+    minigames::{Duel, Gamble, Slots},
+    moderation::Moderation,
+    name_history::NameHistory,
+    personal::PersonalCommands,
+    ping::Ping,
+    points::Points,
+    portfolio::Portfolio,
+    preferences::Preferences,
+    reload::Reload,
+    safe_mode::SafeMode,
+    shell::Shell,
+    status::Status,
+    stream_info::SetStreamInfo,
+    stream_markers::StreamMarkers,
+    time::Time,
+    translate::Translate,
+    twitch_eventsub::TwitchEventSub,
+    watch::Watch,
+    whoami::WhoAmI,
 };
-use super::{eval::storage::ModuleStorage, CommandError, ExecutionContext};
-use crate::platform::{Permissions, PlatformContext};
+use super::{
+    config_reload::ConfigReloader, debug_report::DebugReports, eval::storage::ModuleStorage,
+    finnhub_api::FinnhubApi, i18n::Message, ignored_users::IgnoredUsers, image_api::ImageApi,
+    lastfm_api::LastFMApi, lingva_api::LingvaApi, markov::Markov, message_history::MessageHistory,
+    openai_api::OpenAiApi, output::CommandOutput, owm_api::OwmApi, spam_protection::SpamProtection,
+    status::StatusTracker, CommandError, ExecutionContext, TemplateRegistry,
+};
+use crate::platform::{ChannelIdentifier, Permissions, PlatformContext, UserIdentifier};
 use ::hebi::prelude::NativeModule;
 use async_trait::async_trait;
 use enum_dispatch::enum_dispatch;
-use handlebars::Handlebars;
-use std::sync::Arc;
+use std::sync::{atomic::AtomicBool, Arc};
 
 #[async_trait]
 #[enum_dispatch]
@@ -36,13 +105,14 @@ pub trait ExecutableCommand {
         ctx: &ExecutionContext<'a, P>,
         trigger_name: &str,
         args: Vec<&str>,
-    ) -> Result<Option<String>, CommandError>;
+    ) -> Result<CommandOutput, CommandError>;
 }
 
 #[enum_dispatch(ExecutableCommand)]
 #[derive(strum::Display)]
 pub enum BuiltinCommand {
     Ping(Ping),
+    Status(Status),
     Debug(Debug),
     Cmd(Cmd),
     WhoAmI(WhoAmI),
@@ -51,6 +121,37 @@ pub enum BuiltinCommand {
     DebugHebi(DebugHebi),
     Reload(Reload),
     GeoHub(GeoHub),
+    SafeMode(SafeMode),
+    Followage(Followage),
+    SetStreamInfo(SetStreamInfo),
+    ChannelSafety(ChannelSafety),
+    AiConfig(AiConfig),
+    Forget(Forget),
+    Block(Block),
+    BotSettings(BotSettings),
+    Join(Join),
+    Moderation(Moderation),
+    Protection(Protection),
+    Settings(Settings),
+    Preferences(Preferences),
+    Time(Time),
+    Watch(Watch),
+    Portfolio(Portfolio),
+    Compat(Compat),
+    Translate(Translate),
+    MarkovCommand(MarkovCommand),
+    Lines(Lines),
+    Seen(Seen),
+    StreamMarkers(StreamMarkers),
+    PersonalCommands(PersonalCommands),
+    GlobalCmd(GlobalCmd),
+    Link(Link),
+    NameHistory(NameHistory),
+    Features(Features),
+    Points(Points),
+    Gamble(Gamble),
+    Duel(Duel),
+    Slots(Slots),
 }
 
 impl std::fmt::Debug for BuiltinCommand {
@@ -59,20 +160,166 @@ impl std::fmt::Debug for BuiltinCommand {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_builtin_commands(
-    template_registry: Arc<Handlebars<'static>>,
+    template_registry: TemplateRegistry,
     native_modules: Arc<Vec<NativeModule>>,
     module_storage: ModuleStorage,
+    safe_mode: Arc<AtomicBool>,
+    openai_api: Option<OpenAiApi>,
+    image_api: Option<ImageApi>,
+    owm_api: Option<OwmApi>,
+    finnhub_api: Option<FinnhubApi>,
+    lastfm_api: Option<LastFMApi>,
+    lingva_api: LingvaApi,
+    ignored_users: IgnoredUsers,
+    message_history: MessageHistory,
+    markov: Markov,
+    spam_protection: SpamProtection,
+    config_reloader: ConfigReloader,
+    status: StatusTracker,
+    debug_reports: DebugReports,
 ) -> Vec<BuiltinCommand> {
     vec![
         Ping::default().into(),
-        Debug::new(template_registry).into(),
-        Cmd.into(),
+        Status { status }.into(),
+        Debug::new(template_registry.clone(), debug_reports.clone()).into(),
+        Cmd {
+            template_registry: template_registry.clone(),
+            native_modules: native_modules.clone(),
+            module_storage: module_storage.clone(),
+            openai_api: openai_api.clone(),
+            image_api: image_api.clone(),
+            safe_mode: safe_mode.clone(),
+        }
+        .into(),
+        PersonalCommands {
+            template_registry,
+            native_modules: native_modules.clone(),
+            module_storage: module_storage.clone(),
+            openai_api: openai_api.clone(),
+            image_api: image_api.clone(),
+            safe_mode: safe_mode.clone(),
+        }
+        .into(),
         WhoAmI.into(),
         Shell.into(),
         TwitchEventSub.into(),
-        DebugHebi::new(native_modules, module_storage.clone()).into(),
-        Reload { module_storage }.into(),
+        DebugHebi::new(
+            native_modules,
+            module_storage.clone(),
+            openai_api.clone(),
+            image_api,
+            debug_reports,
+        )
+        .into(),
+        Reload {
+            module_storage,
+            config_reloader,
+        }
+        .into(),
         GeoHub::default().into(),
+        SafeMode { safe_mode }.into(),
+        Followage.into(),
+        SetStreamInfo.into(),
+        ChannelSafety.into(),
+        AiConfig.into(),
+        Forget { openai_api }.into(),
+        Block.into(),
+        BotSettings { ignored_users }.into(),
+        Join.into(),
+        Moderation { message_history }.into(),
+        Protection { spam_protection }.into(),
+        Settings.into(),
+        Preferences.into(),
+        Time { owm_api }.into(),
+        Watch {
+            finnhub_api: finnhub_api.clone(),
+        }
+        .into(),
+        Portfolio { finnhub_api }.into(),
+        Compat { lastfm_api }.into(),
+        Translate { lingva_api }.into(),
+        MarkovCommand { markov }.into(),
+        Lines.into(),
+        Seen.into(),
+        StreamMarkers.into(),
+        GlobalCmd.into(),
+        Link::default().into(),
+        NameHistory.into(),
+        Features.into(),
+        Points.into(),
+        Gamble.into(),
+        Duel::default().into(),
+        Slots.into(),
     ]
 }
+
+/// Accepts either a full identifier such as `twitch:12345` or a user name on the current platform
+async fn resolve_user<P: PlatformContext + Send + Sync>(
+    ctx: &ExecutionContext<'_, P>,
+    user: &str,
+) -> Result<UserIdentifier, CommandError> {
+    if let Ok(identifier) = UserIdentifier::from_string(user) {
+        return Ok(identifier);
+    }
+
+    let language = ctx.get_language().await?;
+
+    match ctx.platform_ctx.get_channel() {
+        ChannelIdentifier::TwitchChannel(_) => {
+            let twitch_api = ctx.platform_handler.twitch_api.as_ref().ok_or_else(|| {
+                CommandError::GenericError(Message::TwitchNotConfigured.translate(language))
+            })?;
+
+            let user_name = user.trim_start_matches('@');
+            let user = twitch_api
+                .helix_api
+                .get_users(Some(&[user_name]), None)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    CommandError::InvalidArgument(
+                        Message::UserNotFound(user_name).translate(language),
+                    )
+                })?;
+
+            Ok(UserIdentifier::TwitchID(user.id))
+        }
+        ChannelIdentifier::DiscordChannel(_) => {
+            let user_id = user
+                .trim_start_matches("<@")
+                .trim_start_matches('!')
+                .trim_end_matches('>');
+
+            match user_id.parse::<u64>() {
+                Ok(_) => Ok(UserIdentifier::DiscordID(user_id.to_owned())),
+                Err(_) => Err(CommandError::InvalidArgument(
+                    Message::InvalidUserMention(user).translate(language),
+                )),
+            }
+        }
+        ChannelIdentifier::IrcChannel(_) => Ok(UserIdentifier::IrcName(user.to_owned())),
+        _ => Err(CommandError::InvalidArgument(
+            Message::InvalidUserIdentifier(user).translate(language),
+        )),
+    }
+}
+
+/// The database id of the user, who has to have been seen by the bot before
+async fn resolve_user_id<P: PlatformContext + Send + Sync>(
+    ctx: &ExecutionContext<'_, P>,
+    user: &str,
+) -> Result<u64, CommandError> {
+    let identifier = resolve_user(ctx, user.trim_start_matches('@')).await?;
+    let language = ctx.get_language().await?;
+
+    ctx.db
+        .run(move |db| db.get_user(&identifier))
+        .await?
+        .map(|user| user.id)
+        .ok_or_else(|| {
+            CommandError::InvalidArgument(Message::UserNotKnown(user).translate(language))
+        })
+}
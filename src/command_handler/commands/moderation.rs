@@ -0,0 +1,222 @@
+use super::*;
+use crate::{
+    command_handler::{i18n::Message, message_history::MessageHistory},
+    database::models::{ModerationAction, NewModerationLogEntry},
+};
+use std::{collections::HashSet, time::Duration};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(600);
+const PURGE_DURATION: Duration = Duration::from_secs(1);
+
+/// `timeout <user> [duration] [reason]`, `ban <user> [reason]`, `purge <user>`
+/// and `nuke <phrase> <window> <duration>`, which times out everyone who sent the phrase
+/// within the window. Every action is written to the moderation log
+#[derive(Debug, Clone)]
+pub struct Moderation {
+    pub message_history: MessageHistory,
+}
+
+#[async_trait]
+impl ExecutableCommand for Moderation {
+    fn get_names(&self) -> &[&str] {
+        &["timeout", "ban", "purge", "nuke"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        0
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::ChannelMod
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        trigger_name: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = ctx.channel_id.ok_or_else(|| {
+            CommandError::GenericError(Message::OnlyInChannel.translate(language))
+        })?;
+
+        let action = match trigger_name.parse::<ModerationAction>() {
+            Ok(action) => action,
+            Err(_) => return Err(CommandError::InvalidArgument(trigger_name.to_owned())),
+        };
+
+        if action == ModerationAction::Nuke {
+            return self.nuke(ctx, channel_id, args).await;
+        }
+
+        let mut args = args.into_iter();
+
+        let user = args
+            .next()
+            .ok_or_else(|| CommandError::MissingArgument("user".to_owned()))?;
+        let target = resolve_user(ctx, user).await?;
+
+        let duration = match action {
+            ModerationAction::Timeout => {
+                let mut rest = args.clone();
+                match rest.next().map(parse_duration) {
+                    Some(Some(duration)) => {
+                        args = rest;
+                        Some(duration)
+                    }
+                    _ => Some(DEFAULT_TIMEOUT),
+                }
+            }
+            ModerationAction::Purge => Some(PURGE_DURATION),
+            _ => None,
+        };
+
+        let reason = args.collect::<Vec<&str>>().join(" ");
+        let reason = Some(reason.as_str()).filter(|reason| !reason.is_empty());
+
+        apply_action(ctx, channel_id, &target, action, duration, reason).await?;
+
+        let response = match duration {
+            Some(duration) if action == ModerationAction::Timeout => Message::UserTimedOut {
+                user,
+                seconds: duration.as_secs(),
+            },
+            Some(_) => Message::UserPurged(user),
+            None => Message::UserBanned(user),
+        };
+
+        Ok(CommandOutput::Text(response.translate(language)))
+    }
+}
+
+impl Moderation {
+    async fn nuke<P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'_, P>,
+        channel_id: u64,
+        mut args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let (duration, window) = match (args.pop(), args.pop()) {
+            (Some(duration), Some(window)) => (duration, window),
+            _ => {
+                return Err(CommandError::MissingArgument(
+                    "phrase, window and duration".to_owned(),
+                ))
+            }
+        };
+
+        let duration = parse_duration(duration)
+            .ok_or_else(|| CommandError::InvalidArgument(format!("duration {duration}")))?;
+        let window = parse_duration(window)
+            .ok_or_else(|| CommandError::InvalidArgument(format!("window {window}")))?;
+
+        let phrase = args.join(" ").to_lowercase();
+        if phrase.is_empty() {
+            return Err(CommandError::MissingArgument("phrase".to_owned()));
+        }
+
+        let executor = ctx.platform_ctx.get_user_identifier();
+
+        let mut targets = HashSet::new();
+        for message in self.message_history.get_recent(channel_id, window) {
+            if message.user != executor && message.text.to_lowercase().contains(&phrase) {
+                targets.insert(message.user);
+            }
+        }
+
+        let mut failed = 0;
+        for target in &targets {
+            let reason = format!("Nuked phrase: {phrase}");
+
+            if let Err(e) = apply_action(
+                ctx,
+                channel_id,
+                target,
+                ModerationAction::Nuke,
+                Some(duration),
+                Some(&reason),
+            )
+            .await
+            {
+                tracing::warn!("Could not nuke {target}: {e}");
+                failed += 1;
+            }
+        }
+
+        let language = ctx.get_language().await?;
+        let mut response = Message::UsersNuked(targets.len() - failed).translate(language);
+        if failed > 0 {
+            response.push_str(&Message::NukeFailed(failed).translate(language));
+        }
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+async fn apply_action<P: PlatformContext + Send + Sync>(
+    ctx: &ExecutionContext<'_, P>,
+    channel_id: u64,
+    target: &UserIdentifier,
+    action: ModerationAction,
+    duration: Option<Duration>,
+    reason: Option<&str>,
+) -> Result<(), CommandError> {
+    ctx.platform_handler
+        .ban_user(&ctx.platform_ctx.get_channel(), target, duration, reason)
+        .await
+        .map_err(|e| CommandError::InternalError(e.to_string()))?;
+
+    let target = target.to_string();
+    let action = action.to_string();
+    let moderator_id = ctx.user.id;
+    let reason = reason.map(str::to_owned);
+
+    ctx.db
+        .run(move |db| {
+            db.add_moderation_log_entry(NewModerationLogEntry {
+                channel_id,
+                moderator_id: Some(moderator_id),
+                target: &target,
+                action: &action,
+                duration: duration.map(|duration| duration.as_secs() as u32),
+                reason: reason.as_deref(),
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Parses durations like `30`, `30s`, `10m`, `2h` or `1d`, a number without a unit is in seconds
+fn parse_duration(s: &str) -> Option<Duration> {
+    let (amount, multiplier) = match s.char_indices().last()? {
+        (i, 's') => (&s[..i], 1),
+        (i, 'm') => (&s[..i], 60),
+        (i, 'h') => (&s[..i], 60 * 60),
+        (i, 'd') => (&s[..i], 60 * 60 * 24),
+        _ => (s, 1),
+    };
+
+    let amount = amount.parse::<u64>().ok().filter(|amount| *amount > 0)?;
+
+    Some(Duration::from_secs(amount * multiplier))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_duration;
+    use std::time::Duration;
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse_duration("30"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("10m"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("1d"), Some(Duration::from_secs(86400)));
+        assert_eq!(parse_duration("0"), None);
+        assert_eq!(parse_duration("spam"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+}
@@ -0,0 +1,76 @@
+use super::*;
+use crate::command_handler::i18n::{Language, Message};
+use crate::database::models::UserName;
+
+/// Only the most recently seen names are listed in the chat
+const MAX_LISTED_NAMES: usize = 10;
+
+/// `namehistory [user]`, the names that the user has been seen with on every platform
+#[derive(Debug, Clone)]
+pub struct NameHistory;
+
+#[async_trait]
+impl ExecutableCommand for NameHistory {
+    fn get_names(&self) -> &[&str] {
+        &["namehistory"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let (user_id, user) = match args.first() {
+            Some(user) => (
+                resolve_user_id(ctx, user).await?,
+                user.trim_start_matches('@').to_owned(),
+            ),
+            None => (ctx.user.id, ctx.platform_ctx.get_display_name().to_owned()),
+        };
+
+        let names = ctx.db.run(move |db| db.get_user_names(user_id)).await?;
+        let language = ctx.get_language().await?;
+
+        let response = if names.is_empty() {
+            Message::NoNamesSeen(&user).translate(language)
+        } else {
+            Message::KnownNames {
+                user: &user,
+                names: &format_names(&names, language),
+            }
+            .translate(language)
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+/// Such as `NewName (twitch, 2023-10-01 - 2023-11-05), OldName (twitch, 2023-09-15 - 2023-10-01)`
+fn format_names(names: &[UserName], language: Language) -> String {
+    let mut formatted = names
+        .iter()
+        .take(MAX_LISTED_NAMES)
+        .map(|name| {
+            format!(
+                "{} ({}, {} - {})",
+                name.name,
+                name.platform,
+                name.first_seen_at.format("%Y-%m-%d"),
+                name.last_seen_at.format("%Y-%m-%d")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if names.len() > MAX_LISTED_NAMES {
+        let more = names.len() - MAX_LISTED_NAMES;
+        formatted.push_str(&Message::MoreNames(more).translate(language));
+    }
+
+    formatted
+}
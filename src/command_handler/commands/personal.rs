@@ -0,0 +1,239 @@
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
+
+use super::*;
+use crate::{
+    command_handler::{
+        execute_action,
+        i18n::{Language, Message},
+    },
+    database::{
+        models::{CommandMode, NewPersonalCommand},
+        DatabaseError,
+    },
+};
+
+/// The amount of personal commands a single user can have
+const MAX_PERSONAL_COMMANDS: i64 = 25;
+/// Personal commands can't be named after the subcommands
+const SUBCOMMANDS: &[&str] = &["add", "edit", "remove", "show", "list", "mode"];
+
+/// `my <name> [args]` runs a command that only the user who created it can trigger,
+/// in every channel where the bot is. `my add/edit/remove/show/list/mode` manages them
+pub struct PersonalCommands {
+    pub template_registry: TemplateRegistry,
+    pub native_modules: Arc<Vec<NativeModule>>,
+    pub module_storage: ModuleStorage,
+    pub openai_api: Option<OpenAiApi>,
+    pub image_api: Option<ImageApi>,
+    pub safe_mode: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl ExecutableCommand for PersonalCommands {
+    fn get_names(&self) -> &[&str] {
+        &["my"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        trigger_name: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let user_id = ctx.user.id;
+        let language = ctx.get_language().await?;
+        let mut args = args.into_iter();
+
+        let subcommand = args.next().ok_or_else(|| {
+            CommandError::MissingArgument(
+                "command name or one of add, edit, remove, show, list, mode".to_owned(),
+            )
+        })?;
+
+        let response = match subcommand {
+            "add" => {
+                let name = get_name(&mut args)?;
+                let action = get_action(args)?;
+
+                if SUBCOMMANDS.contains(&name) {
+                    return Err(CommandError::InvalidArgument(
+                        Message::PersonalCommandReserved(name).translate(language),
+                    ));
+                }
+
+                let count = ctx
+                    .db
+                    .run(move |db| db.count_personal_commands(user_id))
+                    .await?;
+                if count >= MAX_PERSONAL_COMMANDS {
+                    return Err(CommandError::GenericError(
+                        Message::PersonalCommandLimit(MAX_PERSONAL_COMMANDS).translate(language),
+                    ));
+                }
+
+                let command_name = name.to_owned();
+                let result = ctx
+                    .db
+                    .run(move |db| {
+                        db.add_personal_command(NewPersonalCommand {
+                            user_id,
+                            name: &command_name,
+                            action: &action,
+                        })
+                    })
+                    .await;
+                match result {
+                    Ok(()) => Message::PersonalCommandAdded(name).translate(language),
+                    Err(DatabaseError::DieselError(diesel::result::Error::DatabaseError(
+                        diesel::result::DatabaseErrorKind::UniqueViolation,
+                        _,
+                    ))) => Message::PersonalCommandExists(name).translate(language),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            "edit" => {
+                let name = get_name(&mut args)?;
+                let action = get_action(args)?;
+
+                let command_name = name.to_owned();
+                match ctx
+                    .db
+                    .run(move |db| {
+                        db.update_personal_command_action(user_id, &command_name, &action)
+                    })
+                    .await?
+                {
+                    true => Message::PersonalCommandUpdated(name).translate(language),
+                    false => return Err(not_found(name, language)),
+                }
+            }
+            "remove" => {
+                let name = get_name(&mut args)?;
+
+                let command_name = name.to_owned();
+                match ctx
+                    .db
+                    .run(move |db| db.delete_personal_command(user_id, &command_name))
+                    .await?
+                {
+                    true => Message::PersonalCommandRemoved(name).translate(language),
+                    false => return Err(not_found(name, language)),
+                }
+            }
+            "show" => {
+                let name = get_name(&mut args)?;
+
+                let command_name = name.to_owned();
+                ctx.db
+                    .run(move |db| db.get_personal_command(user_id, &command_name))
+                    .await?
+                    .ok_or_else(|| not_found(name, language))?
+                    .action
+            }
+            "list" => {
+                let commands = ctx
+                    .db
+                    .run(move |db| db.get_personal_commands(user_id))
+                    .await?;
+
+                if commands.is_empty() {
+                    Message::NoPersonalCommands.translate(language)
+                } else {
+                    let names = commands
+                        .iter()
+                        .map(|command| command.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    Message::PersonalCommands {
+                        names: &names,
+                        count: commands.len(),
+                        limit: MAX_PERSONAL_COMMANDS,
+                    }
+                    .translate(language)
+                }
+            }
+            "mode" => {
+                let name = get_name(&mut args)?;
+                let raw_mode = args
+                    .next()
+                    .ok_or_else(|| CommandError::MissingArgument("command mode".to_owned()))?;
+                let mode = CommandMode::from_str(raw_mode).map_err(|_| {
+                    CommandError::InvalidArgument(
+                        Message::InvalidCommandMode(raw_mode).translate(language),
+                    )
+                })?;
+
+                let command_name = name.to_owned();
+                match ctx
+                    .db
+                    .run(move |db| db.set_personal_command_mode(user_id, &command_name, mode))
+                    .await?
+                {
+                    true => Message::PersonalCommandModeUpdated {
+                        name,
+                        mode: raw_mode,
+                    }
+                    .translate(language),
+                    false => return Err(not_found(name, language)),
+                }
+            }
+            name => {
+                let command_name = name.to_owned();
+                let command = ctx
+                    .db
+                    .run(move |db| db.get_personal_command(user_id, &command_name))
+                    .await?
+                    .ok_or_else(|| not_found(name, language))?;
+
+                if self.safe_mode.load(Ordering::Relaxed) {
+                    return Err(CommandError::GenericError(
+                        Message::SafeModeCommandsDisabled.translate(language),
+                    ));
+                }
+
+                let output = execute_action(
+                    self.template_registry.load_full(),
+                    &self.native_modules,
+                    self.module_storage.clone(),
+                    self.openai_api.as_ref(),
+                    self.image_api.as_ref(),
+                    command.mode,
+                    command.action,
+                    Some(&format!("{trigger_name} {name}")),
+                    ctx,
+                    args.map(str::to_owned).collect(),
+                    None,
+                )
+                .await?;
+
+                return Ok(output.into());
+            }
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+fn get_name<'a>(args: &mut impl Iterator<Item = &'a str>) -> Result<&'a str, CommandError> {
+    args.next()
+        .ok_or_else(|| CommandError::MissingArgument("command name".to_owned()))
+}
+
+fn get_action<'a>(args: impl Iterator<Item = &'a str>) -> Result<String, CommandError> {
+    let action = args.collect::<Vec<_>>().join(" ");
+
+    if action.is_empty() {
+        return Err(CommandError::MissingArgument("command action".to_owned()));
+    }
+
+    Ok(action)
+}
+
+fn not_found(name: &str, language: Language) -> CommandError {
+    CommandError::InvalidArgument(Message::PersonalCommandNotFound(name).translate(language))
+}
@@ -1,8 +1,8 @@
 use super::*;
+use crate::command_handler::i18n::Message;
+use crate::command_handler::status::get_memory_usage;
 use crate::get_version;
-use std::fmt::Write;
 use std::{sync::Arc, time::Instant};
-use tokio::fs;
 
 #[derive(Debug, Clone)]
 pub struct Ping {
@@ -28,7 +28,8 @@ impl ExecutableCommand for Ping {
         ctx: &ExecutionContext<'a, P>,
         _: &str,
         _: Vec<&str>,
-    ) -> Result<Option<String>, CommandError> {
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
         let uptime = {
             let duration = self.startup_instant.elapsed();
 
@@ -52,36 +53,25 @@ impl ExecutableCommand for Ping {
             result
         };
 
-        let smaps = fs::read_to_string("/proc/self/smaps")
-            .await
-            .expect("Proc FS not found");
+        let mem_usage = get_memory_usage().await.map_err(|e| {
+            CommandError::GenericError(
+                Message::MemoryUsageFailed(&e.to_string()).translate(language),
+            )
+        })?;
 
-        let mut mem_usage = 0; // in KB
-
-        for line in smaps.lines() {
-            if line.starts_with("Pss:") || line.starts_with("SwapPss:") {
-                let mut split = line.split_whitespace();
-                split.next().unwrap();
-
-                let pss = split.next().unwrap();
-
-                mem_usage += pss.parse::<i32>().unwrap();
-            }
+        let mut output = Message::Pong {
+            version: &get_version(),
+            uptime: &uptime,
+            memory: mem_usage / 1024,
         }
-
-        let mut output = format!(
-            "Pong! Version: {}, Uptime {}, RAM usage: {} MiB",
-            get_version(),
-            uptime,
-            mem_usage / 1024,
-        );
+        .translate(language);
 
         if let Some(server_timestamp) = ctx.platform_ctx.get_server_timestamp() {
             let latency = ctx.processing_timestamp - server_timestamp;
-            write!(output, ", chat latency: {}ms", latency.num_milliseconds()).unwrap();
+            output.push_str(&Message::ChatLatency(latency.num_milliseconds()).translate(language));
         }
 
-        Ok(Some(output))
+        Ok(CommandOutput::Text(output))
     }
 }
 
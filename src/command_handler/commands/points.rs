@@ -0,0 +1,93 @@
+use super::*;
+use crate::command_handler::i18n::{Language, Message};
+
+/// `points [user]` shows the balance in the current channel, mods can `points give <user> <amount>`
+#[derive(Debug, Clone)]
+pub struct Points;
+
+#[async_trait]
+impl ExecutableCommand for Points {
+    fn get_names(&self) -> &[&str] {
+        &["points"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = ctx.channel_id.ok_or_else(|| {
+            CommandError::InvalidArgument(Message::OnlyInChannel.translate(language))
+        })?;
+        let mut args = args.into_iter();
+
+        let response = match args.next() {
+            Some("give") => {
+                if ctx.get_permissions().await? < Permissions::ChannelMod {
+                    return Err(CommandError::NoPermissions);
+                }
+
+                let user = args
+                    .next()
+                    .ok_or_else(|| CommandError::MissingArgument("user".to_owned()))?;
+                let amount = parse_points(args.next(), language)?;
+
+                let identifier = resolve_user(ctx, user).await?;
+                let balance = ctx
+                    .db
+                    .run(move |db| {
+                        let target = db.get_or_create_user(&identifier)?;
+                        db.add_points(channel_id, target.id, amount)
+                    })
+                    .await?;
+
+                Message::PointsGiven {
+                    user,
+                    amount,
+                    balance,
+                }
+                .translate(language)
+            }
+            Some(user) => {
+                let identifier = resolve_user(ctx, user).await?;
+                let balance = ctx
+                    .db
+                    .run(move |db| {
+                        let target = db.get_or_create_user(&identifier)?;
+                        db.get_points(channel_id, target.id)
+                    })
+                    .await?;
+
+                Message::UserPoints { user, balance }.translate(language)
+            }
+            None => {
+                let user_id = ctx.user.id;
+                let balance = ctx
+                    .db
+                    .run(move |db| db.get_points(channel_id, user_id))
+                    .await?;
+                Message::YourPoints(balance).translate(language)
+            }
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+/// Amounts of points have to be positive
+pub fn parse_points(amount: Option<&str>, language: Language) -> Result<u64, CommandError> {
+    let amount = amount.ok_or_else(|| CommandError::MissingArgument("amount".to_owned()))?;
+
+    match amount.parse::<u64>() {
+        Ok(amount) if amount > 0 => Ok(amount),
+        _ => Err(CommandError::InvalidArgument(
+            Message::InvalidPoints(amount).translate(language),
+        )),
+    }
+}
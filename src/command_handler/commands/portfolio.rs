@@ -0,0 +1,81 @@
+use super::*;
+use crate::command_handler::{
+    finnhub_api::{is_us_symbol, FinnhubApi},
+    i18n::Message,
+};
+
+/// Quotes of the symbols in the user's watchlist
+#[derive(Clone)]
+pub struct Portfolio {
+    pub finnhub_api: Option<FinnhubApi>,
+}
+
+#[async_trait]
+impl ExecutableCommand for Portfolio {
+    fn get_names(&self) -> &[&str] {
+        &["portfolio"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        10
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        _: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let finnhub_api = self.finnhub_api.as_ref().ok_or_else(|| {
+            CommandError::GenericError(Message::StocksNotConfigured.translate(language))
+        })?;
+
+        let user_id = ctx.user.id;
+        let watchlist = ctx.db.run(move |db| db.get_watchlist(user_id)).await?;
+        if watchlist.is_empty() {
+            return Ok(CommandOutput::Text(
+                Message::WatchlistEmptyHint.translate(language),
+            ));
+        }
+
+        let mut gainers = 0;
+        let mut losers = 0;
+
+        let quotes = finnhub_api
+            .quotes(&watchlist)
+            .await
+            .into_iter()
+            .map(|(symbol, quote)| match quote {
+                Ok(quote) => {
+                    match quote.percent_change.unwrap_or(0.0) {
+                        change if change > 0.0 => gainers += 1,
+                        change if change < 0.0 => losers += 1,
+                        _ => (),
+                    }
+                    format!("{symbol} {quote}")
+                }
+                Err(_) => Message::QuoteUnavailable(&symbol).translate(language),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut response = Message::PortfolioSummary {
+            quotes: &quotes,
+            gainers,
+            losers,
+        }
+        .translate(language);
+
+        // The market status is only an addition, so errors are ignored
+        if watchlist.iter().any(|symbol| is_us_symbol(symbol)) {
+            if let Ok(status) = finnhub_api.market_status().await {
+                if let Some(session) = status.extended_hours() {
+                    response.push_str(&format!(" ({session})"));
+                }
+            }
+        }
+
+        Ok(CommandOutput::Text(response))
+    }
+}
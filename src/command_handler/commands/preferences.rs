@@ -0,0 +1,129 @@
+use std::str::FromStr;
+
+use super::*;
+use crate::command_handler::i18n::{is_language_code, Language, Message};
+use crate::command_handler::owm_api::Units;
+use chrono_tz::Tz;
+use strum::EnumString;
+
+/// `set timezone <timezone>`, `set lang <language>`, `set units <units>` and `set supibot <id>`,
+/// shows the current value without one
+#[derive(Debug, Clone)]
+pub struct Preferences;
+
+#[derive(EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum Preference {
+    Timezone,
+    #[strum(serialize = "lang", serialize = "language")]
+    Language,
+    Units,
+    Supibot,
+}
+
+#[async_trait]
+impl ExecutableCommand for Preferences {
+    fn get_names(&self) -> &[&str] {
+        &["set"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let mut args = args.into_iter();
+
+        let language = ctx.get_language().await?;
+        let user_id = ctx.user.id;
+
+        let raw_preference = args.next().ok_or_else(|| {
+            CommandError::MissingArgument("timezone/lang/units/supibot".to_owned())
+        })?;
+        let preference = Preference::from_str(raw_preference).map_err(|_| {
+            CommandError::InvalidArgument(
+                Message::UnknownPreference(raw_preference).translate(language),
+            )
+        })?;
+
+        let response = match (preference, args.next()) {
+            (Preference::Timezone, Some(value)) => {
+                let timezone = Tz::from_str(value).map_err(|_| {
+                    CommandError::InvalidArgument(
+                        Message::InvalidTimezone(value).translate(language),
+                    )
+                })?;
+
+                ctx.db
+                    .run(move |db| db.set_timezone(user_id, timezone.name()))
+                    .await?;
+                Message::TimezoneSet(timezone.name()).translate(language)
+            }
+            (Preference::Timezone, None) => {
+                match ctx.db.run(move |db| db.get_timezone(user_id)).await? {
+                    Some(timezone) => Message::TimezoneIs(&timezone).translate(language),
+                    None => Message::TimezoneNotSet.translate(language),
+                }
+            }
+            (Preference::Language, Some(value)) => {
+                if !is_language_code(value) {
+                    return Err(CommandError::InvalidArgument(
+                        Message::InvalidLanguageCode(value).translate(language),
+                    ));
+                }
+
+                let code = value.to_lowercase();
+                let new_code = code.clone();
+                ctx.db
+                    .run(move |db| db.set_language(user_id, &new_code))
+                    .await?;
+
+                let language = Language::from_code(&code).unwrap_or(language);
+                Message::LanguageSet(&code).translate(language)
+            }
+            (Preference::Language, None) => {
+                match ctx.db.run(move |db| db.get_language(user_id)).await? {
+                    Some(code) => Message::LanguageIs(&code).translate(language),
+                    None => Message::LanguageNotSet.translate(language),
+                }
+            }
+            (Preference::Units, Some(value)) => {
+                let units = Units::from_str(&value.to_lowercase()).map_err(|_| {
+                    CommandError::InvalidArgument(Message::InvalidUnits(value).translate(language))
+                })?;
+
+                ctx.db
+                    .run(move |db| db.set_units(user_id, units.as_ref()))
+                    .await?;
+                Message::UnitsSet(units.as_ref()).translate(language)
+            }
+            (Preference::Units, None) => match ctx.db.run(move |db| db.get_units(user_id)).await? {
+                Some(units) => Message::UnitsAre(&units).translate(language),
+                None => Message::UnitsNotSet.translate(language),
+            },
+            (Preference::Supibot, Some(value)) => {
+                let id = value.parse::<u64>().map_err(|_| {
+                    CommandError::InvalidArgument(
+                        Message::InvalidSupibotId(value).translate(language),
+                    )
+                })?;
+
+                ctx.db.run(move |db| db.set_supibot_id(user_id, id)).await?;
+                Message::SupibotIdSet(&id.to_string()).translate(language)
+            }
+            (Preference::Supibot, None) => {
+                match ctx.db.run(move |db| db.get_supibot_id(user_id)).await? {
+                    Some(id) => Message::SupibotIdIs(&id.to_string()).translate(language),
+                    None => Message::SupibotIdNotSet.translate(language),
+                }
+            }
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
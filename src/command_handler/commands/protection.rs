@@ -0,0 +1,176 @@
+use std::str::FromStr;
+
+use super::*;
+use crate::{
+    command_handler::{
+        i18n::{Language, Message},
+        spam_protection::SpamProtection,
+    },
+    database::models::{ProtectionAction, ProtectionSettings},
+};
+use strum::EnumString;
+
+#[derive(Debug, Clone)]
+pub struct Protection {
+    pub spam_protection: SpamProtection,
+}
+
+#[derive(EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum Subcommand {
+    Enable,
+    Disable,
+    Show,
+    Set,
+}
+
+#[async_trait]
+impl ExecutableCommand for Protection {
+    fn get_names(&self) -> &[&str] {
+        &["protection"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        0
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::ChannelMod
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let channel_id = ctx.channel_id.ok_or_else(|| {
+            CommandError::InvalidArgument(Message::OnlyInChannel.translate(language))
+        })?;
+
+        let mut args = args.into_iter();
+
+        let subcommand = match args.next() {
+            Some(raw_subcommand) => Subcommand::from_str(raw_subcommand).map_err(|_| {
+                CommandError::InvalidArgument(
+                    Message::InvalidSubcommand(raw_subcommand).translate(language),
+                )
+            })?,
+            None => Subcommand::Show,
+        };
+
+        let mut settings = self.spam_protection.get_settings(channel_id).await?;
+
+        let response = match subcommand {
+            Subcommand::Enable | Subcommand::Disable => {
+                settings.enabled = matches!(subcommand, Subcommand::Enable);
+                self.spam_protection.set_settings(settings).await?;
+
+                if matches!(subcommand, Subcommand::Enable) {
+                    Message::SpamProtectionEnabled.translate(language)
+                } else {
+                    Message::SpamProtectionDisabled.translate(language)
+                }
+            }
+            Subcommand::Show => format_settings(&settings, language),
+            Subcommand::Set => {
+                let setting = args.next().ok_or_else(|| {
+                    CommandError::MissingArgument(
+                        "rate/repeats/emotes/caps/links/mentions/action/timeout".to_owned(),
+                    )
+                })?;
+                let value = args
+                    .next()
+                    .ok_or_else(|| CommandError::MissingArgument("value".to_owned()))?;
+
+                set_setting(&mut settings, setting, value, language)?;
+                self.spam_protection.set_settings(settings).await?;
+
+                Message::SettingSet { setting, value }.translate(language)
+            }
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+/// Limits can be disabled with `off`
+fn set_setting(
+    settings: &mut ProtectionSettings,
+    setting: &str,
+    value: &str,
+    language: Language,
+) -> Result<(), CommandError> {
+    let limit = || -> Result<Option<u32>, CommandError> {
+        match value {
+            "off" => Ok(None),
+            value => Ok(Some(value.parse()?)),
+        }
+    };
+
+    match setting {
+        "rate" => settings.max_message_rate = limit()?,
+        "repeats" => settings.max_repeats = limit()?,
+        "emotes" => settings.max_emotes = limit()?,
+        "caps" => {
+            let percent = limit()?;
+            if percent > Some(100) {
+                return Err(CommandError::InvalidArgument(
+                    Message::CapsNotPercentage.translate(language),
+                ));
+            }
+            settings.max_caps_percent = percent;
+        }
+        "links" => settings.max_links = limit()?,
+        "mentions" => settings.max_mentions = limit()?,
+        "action" => {
+            let action = ProtectionAction::from_str(value).map_err(|_| {
+                CommandError::InvalidArgument(Message::InvalidProtectionAction.translate(language))
+            })?;
+            settings.action = action.to_string();
+        }
+        "timeout" => match value.parse()? {
+            0 => {
+                return Err(CommandError::InvalidArgument(
+                    Message::TimeoutNotPositive.translate(language),
+                ))
+            }
+            duration => settings.timeout_duration = duration,
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("setting {setting}"))),
+    }
+
+    Ok(())
+}
+
+fn format_settings(settings: &ProtectionSettings, language: Language) -> String {
+    let format_limit = |limit: Option<u32>| match limit {
+        Some(limit) => limit.to_string(),
+        None => Message::LimitOff.translate(language),
+    };
+
+    let state = match settings.enabled {
+        true => Message::SpamProtectionIsOn,
+        false => Message::SpamProtectionIsOff,
+    };
+    let limits = Message::SpamProtectionLimits {
+        rate: &format_limit(settings.max_message_rate),
+        repeats: &format_limit(settings.max_repeats),
+        emotes: &format_limit(settings.max_emotes),
+        caps: &settings
+            .max_caps_percent
+            .map(|percent| format!("{percent}%"))
+            .unwrap_or_else(|| Message::LimitOff.translate(language)),
+        links: &format_limit(settings.max_links),
+        mentions: &format_limit(settings.max_mentions),
+        action: &settings.action,
+        timeout: settings.timeout_duration,
+    };
+
+    format!(
+        "{} {}",
+        state.translate(language),
+        limits.translate(language)
+    )
+}
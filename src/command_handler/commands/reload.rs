@@ -1,18 +1,22 @@
 use std::str::FromStr;
 
 use super::*;
+use crate::command_handler::config_reload::ConfigReloader;
 use crate::command_handler::eval::storage::ModuleStorage;
+use crate::command_handler::i18n::Message;
 use strum::EnumString;
 
 #[derive(Debug, Clone)]
 pub struct Reload {
     pub module_storage: ModuleStorage,
+    pub config_reloader: ConfigReloader,
 }
 
 #[derive(EnumString)]
 #[strum(serialize_all = "lowercase")]
 pub enum Subcommand {
     Hebi,
+    Config,
 }
 
 #[async_trait]
@@ -31,26 +35,42 @@ impl ExecutableCommand for Reload {
 
     async fn execute<'a, P: PlatformContext + Send + Sync>(
         &self,
-        _: &ExecutionContext<'a, P>,
+        ctx: &ExecutionContext<'a, P>,
         _: &str,
         args: Vec<&str>,
-    ) -> Result<Option<String>, CommandError> {
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
         let raw_subcommand = args
             .first()
             .ok_or_else(|| CommandError::MissingArgument("subcommand".to_owned()))?;
         let subcommand = Subcommand::from_str(raw_subcommand).map_err(|_| {
-            CommandError::InvalidArgument(format!("Invalid subcommand: {raw_subcommand}"))
+            CommandError::InvalidArgument(
+                Message::InvalidSubcommand(raw_subcommand).translate(language),
+            )
         })?;
 
         match subcommand {
             Subcommand::Hebi => match self.module_storage.update() {
-                Ok(Some(commit)) => Ok(Some(format!(
-                    "Hebi modules were updated to commit {commit}"
-                ))),
-                Ok(None) => Ok(Some("Hebi modules are already up to date".to_owned())),
-                Err(err) => Err(CommandError::GenericError(format!(
-                    "Could not reload hebi modules: {err:#}"
-                ))),
+                Ok(Some(commit)) => Ok(CommandOutput::Text(
+                    Message::HebiModulesUpdated(&commit).translate(language),
+                )),
+                Ok(None) => Ok(CommandOutput::Text(
+                    Message::HebiModulesUpToDate.translate(language),
+                )),
+                Err(err) => Err(CommandError::GenericError(
+                    Message::HebiModulesReloadFailed(&format!("{err:#}")).translate(language),
+                )),
+            },
+            Subcommand::Config => match self.config_reloader.reload().await {
+                Ok(false) => Ok(CommandOutput::Text(
+                    Message::ConfigReloaded.translate(language),
+                )),
+                Ok(true) => Ok(CommandOutput::Text(
+                    Message::ConfigReloadedRestartRequired.translate(language),
+                )),
+                Err(err) => Err(CommandError::GenericError(
+                    Message::ConfigReloadFailed(&format!("{err:#}")).translate(language),
+                )),
             },
         }
     }
@@ -0,0 +1,70 @@
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use super::*;
+use crate::command_handler::i18n::Message;
+use strum::EnumString;
+
+#[derive(Debug, Clone)]
+pub struct SafeMode {
+    pub safe_mode: Arc<AtomicBool>,
+}
+
+#[derive(EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum Subcommand {
+    On,
+    Off,
+    Status,
+}
+
+#[async_trait]
+impl ExecutableCommand for SafeMode {
+    fn get_names(&self) -> &[&str] {
+        &["safemode"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        0
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::Admin
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let raw_subcommand = args.first().copied().unwrap_or("status");
+        let subcommand = Subcommand::from_str(raw_subcommand).map_err(|_| {
+            CommandError::InvalidArgument(
+                Message::InvalidSubcommand(raw_subcommand).translate(language),
+            )
+        })?;
+
+        let response = match subcommand {
+            Subcommand::On => {
+                self.safe_mode.store(true, Ordering::Relaxed);
+                tracing::warn!("Entered safe mode");
+                Message::SafeModeEntered
+            }
+            Subcommand::Off => {
+                self.safe_mode.store(false, Ordering::Relaxed);
+                tracing::warn!("Exited safe mode");
+                Message::SafeModeExited
+            }
+            Subcommand::Status => match self.safe_mode.load(Ordering::Relaxed) {
+                true => Message::SafeModeEnabled,
+                false => Message::SafeModeDisabled,
+            },
+        };
+
+        Ok(CommandOutput::Text(response.translate(language)))
+    }
+}
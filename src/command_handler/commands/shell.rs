@@ -2,7 +2,10 @@ use tokio::process::Command;
 use tracing::warn;
 
 use super::*;
-use std::env;
+use crate::command_handler::{i18n::Message, output::Attachment};
+
+/// Longer outputs are sent as a file where possible
+const MAX_INLINE_LINES: usize = 5;
 
 #[derive(Debug, Clone)]
 pub struct Shell;
@@ -23,11 +26,11 @@ impl ExecutableCommand for Shell {
 
     async fn execute<'a, P: PlatformContext + Send + Sync>(
         &self,
-        _ctx: &ExecutionContext<'a, P>,
+        ctx: &ExecutionContext<'a, P>,
         _trigger_name: &str,
         args: Vec<&str>,
-    ) -> Result<Option<String>, CommandError> {
-        if let Ok("1") = env::var("ALLOW_SHELL").as_deref() {
+    ) -> Result<CommandOutput, CommandError> {
+        if ctx.db.config().allow_shell {
             match Command::new("sh").arg("-c").args(args).output().await {
                 Ok(output) => {
                     let stdout = String::from_utf8(output.stdout)
@@ -36,14 +39,21 @@ impl ExecutableCommand for Shell {
                         .unwrap_or_else(|_| "<invalid UTF-8>".to_owned());
                     let final_output = format!("{stdout}\n{stderr}").trim().to_owned();
 
-                    Ok(Some(final_output))
+                    if final_output.lines().count() > MAX_INLINE_LINES {
+                        Ok(CommandOutput::Attachment(Attachment {
+                            filename: "output.txt".to_owned(),
+                            content: final_output.into_bytes(),
+                        }))
+                    } else {
+                        Ok(CommandOutput::Text(final_output))
+                    }
                 }
-                Err(err) => Err(CommandError::GenericError(format!(
-                    "could not run command: {err}"
-                ))),
+                Err(err) => Err(CommandError::GenericError(
+                    Message::ShellFailed(&err.to_string()).translate(ctx.get_language().await?),
+                )),
             }
         } else {
-            warn!("Trying to use `shell` when ALLOW_SHELL isn't set to 1");
+            warn!("Trying to use `shell` when allow_shell isn't enabled");
             Err(CommandError::NoPermissions)
         }
     }
@@ -0,0 +1,140 @@
+use super::*;
+use crate::command_handler::i18n::Message;
+use crate::command_handler::inquiry_helper::format_duration;
+use crate::command_handler::status::{get_memory_usage, StatusTracker};
+use chrono::Utc;
+
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub status: StatusTracker,
+}
+
+#[async_trait]
+impl ExecutableCommand for Status {
+    fn get_names(&self) -> &[&str] {
+        &["status"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        0
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::Admin
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        _: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let now = Utc::now();
+        let language = ctx.get_language().await?;
+        let mut sections = Vec::new();
+
+        let connections = self
+            .status
+            .get_connections()
+            .into_iter()
+            .map(|(name, state, since)| {
+                Message::StatusConnection {
+                    name: &name,
+                    state: &state.to_string(),
+                    duration: &format_duration(now - since),
+                }
+                .translate(language)
+            })
+            .collect::<Vec<_>>();
+        if connections.is_empty() {
+            sections.push(Message::StatusNoPlatforms.translate(language));
+        } else {
+            sections.push(Message::StatusPlatforms(&connections.join(", ")).translate(language));
+        }
+
+        let degraded_services = self.status.get_degraded_services();
+        if !degraded_services.is_empty() {
+            let services = degraded_services
+                .into_iter()
+                .map(|(name, reason)| format!("{name} ({reason})"))
+                .collect::<Vec<_>>();
+            sections.push(Message::StatusDegraded(&services.join(", ")).translate(language));
+        }
+
+        let mut queues =
+            Message::StatusMessagesInFlight(self.status.messages_in_flight()).translate(language);
+        if let Some(twitch_api) = &ctx.platform_handler.twitch_api {
+            if let Some(chat_sender) = twitch_api.chat_sender.lock().await.as_ref() {
+                queues.push_str(
+                    &Message::StatusTwitchQueue(chat_sender.queued()).translate(language),
+                );
+            }
+        }
+        sections.push(queues);
+
+        let sharding = &ctx.platform_handler.sharding;
+        if let Some(shard_id) = sharding.get_shard_id() {
+            sections.push(
+                Message::StatusShards {
+                    shard: shard_id,
+                    shards: &sharding.get_shards().join(", "),
+                }
+                .translate(language),
+            );
+        }
+
+        let caches = ctx
+            .db
+            .get_cache_sizes()
+            .into_iter()
+            .map(|(name, size)| format!("{name} {size}"))
+            .collect::<Vec<_>>();
+        sections.push(
+            Message::StatusCaches {
+                caches: &caches.join(", "),
+                blocked_users: ctx.blocked_users.get(None).len(),
+            }
+            .translate(language),
+        );
+
+        let (used_connections, pool_size) = ctx.db.get_pool_usage();
+        let db_status = match ctx.db.run(|db| db.ping()).await {
+            Ok(latency) => format!("{}ms", latency.as_millis()),
+            Err(e) => Message::StatusUnavailable(&e.to_string()).translate(language),
+        };
+        sections.push(
+            Message::StatusDatabase {
+                status: &db_status,
+                used: used_connections,
+                size: pool_size,
+            }
+            .translate(language),
+        );
+
+        match self.status.last_eventsub_notification() {
+            Some(received_at) => sections.push(
+                Message::StatusLastEventSub(&format_duration(now - received_at))
+                    .translate(language),
+            ),
+            None => sections.push(Message::StatusNoEventSub.translate(language)),
+        }
+
+        let memory = match get_memory_usage().await {
+            Ok(memory) => format!("{} MiB", memory / 1024),
+            Err(e) => Message::StatusUnknown(&e.to_string()).translate(language),
+        };
+        let cpu = match self.status.get_cpu_usage().await {
+            Ok(cpu) => format!("{cpu:.1}%"),
+            Err(e) => Message::StatusUnknown(&e.to_string()).translate(language),
+        };
+        sections.push(
+            Message::StatusResources {
+                memory: &memory,
+                cpu: &cpu,
+            }
+            .translate(language),
+        );
+
+        Ok(CommandOutput::Text(sections.join(" | ")))
+    }
+}
@@ -0,0 +1,83 @@
+use super::*;
+use crate::{
+    command_handler::{i18n::Message, twitch_api::get_broadcaster_helix_api},
+    platform::ChannelIdentifier,
+};
+
+#[derive(Debug, Clone)]
+pub struct SetStreamInfo;
+
+#[async_trait]
+impl ExecutableCommand for SetStreamInfo {
+    fn get_names(&self) -> &[&str] {
+        &["settitle", "setgame"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::ChannelMod
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        trigger_name: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let broadcaster_id = match ctx.platform_ctx.get_channel() {
+            ChannelIdentifier::TwitchChannel((id, _)) => id,
+            _ => {
+                return Err(CommandError::GenericError(
+                    Message::OnlyOnTwitch(trigger_name).translate(language),
+                ))
+            }
+        };
+
+        let value = args.join(" ");
+        if value.is_empty() {
+            return Err(CommandError::MissingArgument(
+                trigger_name.trim_start_matches("set").to_owned(),
+            ));
+        }
+
+        let helix_api = get_broadcaster_helix_api(ctx.db, &broadcaster_id)
+            .await
+            .map_err(|_| {
+                CommandError::GenericError(
+                    Message::StreamerNotAuthenticatedChannel.translate(language),
+                )
+            })?;
+
+        let response = match trigger_name {
+            "settitle" => {
+                helix_api
+                    .modify_channel_information(&broadcaster_id, Some(&value), None)
+                    .await?;
+
+                Message::TitleSet(&value).translate(language)
+            }
+            "setgame" => {
+                let game = helix_api.get_game_by_name(&value).await?.ok_or_else(|| {
+                    CommandError::InvalidArgument(Message::GameNotFound(&value).translate(language))
+                })?;
+
+                helix_api
+                    .modify_channel_information(&broadcaster_id, None, Some(&game.id))
+                    .await?;
+
+                Message::GameSet(&game.name).translate(language)
+            }
+            _ => return Err(CommandError::InvalidArgument(trigger_name.to_owned())),
+        };
+
+        if let Some(twitch_api) = &ctx.platform_handler.twitch_api {
+            twitch_api.invalidate_stream_info(&broadcaster_id);
+        }
+
+        Ok(CommandOutput::Text(response))
+    }
+}
@@ -0,0 +1,154 @@
+use super::*;
+use crate::command_handler::{
+    i18n::{Language, Message},
+    twitch_api::get_broadcaster_helix_api,
+};
+use crate::database::models::NewStreamHighlight;
+
+/// Twitch rejects longer marker descriptions
+const MAX_MARKER_DESCRIPTION_LENGTH: usize = 140;
+
+/// `marker [description]` creates a Twitch stream marker through the streamer's manage token,
+/// `highlight [note]` saves a note with the position in the stream, which editors can
+/// look through with the API after the stream
+#[derive(Debug, Clone)]
+pub struct StreamMarkers;
+
+#[async_trait]
+impl ExecutableCommand for StreamMarkers {
+    fn get_names(&self) -> &[&str] {
+        &["marker", "highlight"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    fn get_permissions(&self) -> Permissions {
+        Permissions::ChannelMod
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        trigger_name: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let text = args.join(" ");
+        let language = ctx.get_language().await?;
+
+        let response = match trigger_name {
+            "marker" => create_marker(ctx, &text, language).await?,
+            "highlight" => save_highlight(ctx, &text, language).await?,
+            _ => return Err(CommandError::InvalidArgument(trigger_name.to_owned())),
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+async fn create_marker<P: PlatformContext + Send + Sync>(
+    ctx: &ExecutionContext<'_, P>,
+    description: &str,
+    language: Language,
+) -> Result<String, CommandError> {
+    let broadcaster_id = match ctx.platform_ctx.get_channel() {
+        ChannelIdentifier::TwitchChannel((id, _)) => id,
+        _ => {
+            return Err(CommandError::GenericError(
+                Message::OnlyOnTwitch("marker").translate(language),
+            ))
+        }
+    };
+
+    if description.chars().count() > MAX_MARKER_DESCRIPTION_LENGTH {
+        return Err(CommandError::InvalidArgument(
+            Message::MarkerDescriptionTooLong(MAX_MARKER_DESCRIPTION_LENGTH).translate(language),
+        ));
+    }
+
+    let helix_api = get_broadcaster_helix_api(ctx.db, &broadcaster_id)
+        .await
+        .map_err(|_| {
+            CommandError::GenericError(Message::StreamerNotAuthenticated.translate(language))
+        })?;
+
+    let description = Some(description).filter(|description| !description.is_empty());
+    let marker = helix_api
+        .create_stream_marker(&broadcaster_id, description)
+        .await
+        .map_err(|_| CommandError::GenericError(Message::MarkerFailed.translate(language)))?;
+
+    let position = u32::try_from(marker.position_seconds).unwrap_or_default();
+    Ok(Message::MarkerCreated(&format_position(position)).translate(language))
+}
+
+/// The position in the stream is only known on Twitch while the channel is live
+async fn save_highlight<P: PlatformContext + Send + Sync>(
+    ctx: &ExecutionContext<'_, P>,
+    note: &str,
+    language: Language,
+) -> Result<String, CommandError> {
+    let channel_id = ctx
+        .channel_id
+        .ok_or_else(|| CommandError::InvalidArgument(Message::OnlyInChannel.translate(language)))?;
+
+    let stream_info = match (
+        ctx.platform_ctx.get_channel(),
+        &ctx.platform_handler.twitch_api,
+    ) {
+        (ChannelIdentifier::TwitchChannel((broadcaster_id, _)), Some(twitch_api)) => {
+            twitch_api.get_stream_info(&broadcaster_id).await.ok()
+        }
+        _ => None,
+    };
+
+    let position = stream_info
+        .as_ref()
+        .and_then(|info| info.uptime())
+        .and_then(|uptime| u32::try_from(uptime.num_seconds()).ok());
+    let stream_id = stream_info
+        .as_ref()
+        .and_then(|info| info.stream.as_ref())
+        .map(|stream| stream.id.clone());
+    let user_id = ctx.user.id;
+    let note = note.to_owned();
+
+    ctx.db
+        .run(move |db| {
+            db.add_stream_highlight(NewStreamHighlight {
+                channel_id,
+                user_id: Some(user_id),
+                note: &note,
+                stream_id: stream_id.as_deref(),
+                position_seconds: position,
+            })
+        })
+        .await?;
+
+    Ok(match position {
+        Some(position) => Message::HighlightSavedAt(&format_position(position)).translate(language),
+        None => Message::HighlightSaved.translate(language),
+    })
+}
+
+/// Such as `1:02:03`
+fn format_position(seconds: u32) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_position;
+
+    #[test]
+    fn formats_stream_positions() {
+        assert_eq!(format_position(3723), "1:02:03");
+        assert_eq!(format_position(59), "0:00:59");
+    }
+}
@@ -0,0 +1,161 @@
+use std::str::FromStr;
+
+use super::*;
+use crate::command_handler::{
+    i18n::{Language, Message},
+    owm_api::{OwmApi, Units},
+};
+use crate::database::DatabaseError;
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::Tz;
+
+const TIME_FORMAT: &str = "%H:%M (%a, %b %-d)";
+
+/// `time [place | timezone | @user]`, uses the user's own timezone or location without arguments
+#[derive(Clone)]
+pub struct Time {
+    pub owm_api: Option<OwmApi>,
+}
+
+#[async_trait]
+impl ExecutableCommand for Time {
+    fn get_names(&self) -> &[&str] {
+        &["time"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let now = Utc::now();
+        let arg = args.join(" ");
+        let language = ctx.get_language().await?;
+
+        let response = if arg.is_empty() {
+            let time = self.get_user_time(ctx, ctx.user.id, now, language).await?;
+            Message::CurrentTime(&time).translate(language)
+        } else if is_user_argument(&arg) {
+            let identifier = resolve_user(ctx, arg.trim_start_matches('@')).await?;
+            let user = ctx
+                .db
+                .run(move |db| db.get_user(&identifier))
+                .await?
+                .ok_or_else(|| {
+                    CommandError::InvalidArgument(Message::UserNotKnown(&arg).translate(language))
+                })?;
+
+            // Only the time is shown, so that the location of the user isn't revealed
+            let time = self.get_user_time(ctx, user.id, now, language).await?;
+            Message::CurrentTimeFor {
+                time: &time,
+                user: arg.trim_start_matches('@'),
+            }
+            .translate(language)
+        } else if let Ok(timezone) = Tz::from_str(&arg) {
+            Message::CurrentTimeIn {
+                time: &now.with_timezone(&timezone).format(TIME_FORMAT).to_string(),
+                place: &arg,
+            }
+            .translate(language)
+        } else {
+            let (place, time) = self.get_place_time(&arg, now, language).await?;
+            Message::CurrentTimeIn {
+                time: &time,
+                place: &place,
+            }
+            .translate(language)
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+impl Time {
+    /// Prefers the timezone set with `set timezone` over the location of the weather helper
+    async fn get_user_time<P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'_, P>,
+        user_id: u64,
+        now: DateTime<Utc>,
+        language: Language,
+    ) -> Result<String, CommandError> {
+        let (timezone, location) = ctx
+            .db
+            .run(move |db| {
+                Ok::<_, DatabaseError>((db.get_timezone(user_id)?, db.get_location(user_id)?))
+            })
+            .await?;
+
+        if let Some(timezone) = timezone {
+            if let Ok(timezone) = Tz::from_str(&timezone) {
+                return Ok(now.with_timezone(&timezone).format(TIME_FORMAT).to_string());
+            }
+        }
+
+        match location {
+            Some(location) => Ok(self.get_place_time(&location, now, language).await?.1),
+            None => Err(CommandError::GenericError(
+                Message::NoTimezoneOrLocation.translate(language),
+            )),
+        }
+    }
+
+    /// Returns the resolved name of the place along with its time
+    async fn get_place_time(
+        &self,
+        place: &str,
+        now: DateTime<Utc>,
+        language: Language,
+    ) -> Result<(String, String), CommandError> {
+        let owm_api = self.owm_api.as_ref().ok_or_else(|| {
+            CommandError::GenericError(Message::PlacesNotConfigured.translate(language))
+        })?;
+
+        let weather = owm_api
+            .get_current(place, Units::default())
+            .await
+            .map_err(|e| CommandError::InternalError(e.to_string()))?;
+
+        let offset = i32::try_from(weather.timezone)
+            .ok()
+            .and_then(FixedOffset::east_opt)
+            .ok_or_else(|| {
+                CommandError::GenericError(Message::InvalidTimezoneOffset.translate(language))
+            })?;
+
+        let name = match weather.sys.country {
+            Some(country) => format!("{}, {country}", weather.name),
+            None => weather.name,
+        };
+
+        Ok((
+            name,
+            now.with_timezone(&offset).format(TIME_FORMAT).to_string(),
+        ))
+    }
+}
+
+/// Users have to be mentioned explicitly, as a lot of user names are also places
+fn is_user_argument(arg: &str) -> bool {
+    arg.starts_with('@') || arg.starts_with("<@") || UserIdentifier::from_string(arg).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_user_argument;
+
+    #[test]
+    fn detects_user_arguments() {
+        assert!(is_user_argument("@forsen"));
+        assert!(is_user_argument("<@123456>"));
+        assert!(is_user_argument("twitch:12345"));
+        assert!(!is_user_argument("Kyiv"));
+        assert!(!is_user_argument("Europe/Kyiv"));
+    }
+}
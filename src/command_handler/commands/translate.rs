@@ -0,0 +1,53 @@
+use super::*;
+use crate::command_handler::lingva_api::{LingvaApi, TranslationArgs};
+
+/// `translate [from:<language>] [to:<language>] <text>`, translates the message that
+/// is being replied to when there is no text.
+/// Translates to the user's language or English by default.
+#[derive(Clone)]
+pub struct Translate {
+    pub lingva_api: LingvaApi,
+}
+
+#[async_trait]
+impl ExecutableCommand for Translate {
+    fn get_names(&self) -> &[&str] {
+        &["translate"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let user_id = ctx.user.id;
+        let default_target = ctx.db.run(move |db| db.get_language(user_id)).await?;
+        let args = TranslationArgs::parse(&args, default_target.as_deref().unwrap_or("en"));
+
+        let text = if args.text.is_empty() {
+            ctx.platform_ctx
+                .get_reply_parent_text()
+                .ok_or_else(|| {
+                    CommandError::MissingArgument(
+                        "text, or reply to the message to translate".to_owned(),
+                    )
+                })?
+                .to_owned()
+        } else {
+            args.text
+        };
+
+        let translation = self
+            .lingva_api
+            .translate(&args.source, &args.target, &text)
+            .await
+            .map_err(|e| CommandError::InternalError(e.to_string()))?;
+
+        Ok(CommandOutput::Text(translation))
+    }
+}
@@ -2,18 +2,30 @@ use std::vec::IntoIter;
 
 use super::*;
 use crate::{
-    command_handler::twitch_api::{
-        eventsub::{
-            conditions::{ChannelPointsCustomRewardRedemptionAddCondition, ChannelUpdateCondition},
-            EventSubSubscriptionType,
+    command_handler::{
+        i18n::{Language, Message},
+        resubscribe_eventsub_trigger,
+        twitch_api::{
+            eventsub::{
+                conditions::{
+                    ChannelPointsCustomRewardRedemptionAddCondition, ChannelRaidCondition,
+                    ChannelUpdateCondition, ShieldModeCondition,
+                },
+                EventSubSubscriptionType,
+            },
+            get_broadcaster_helix_api,
         },
-        get_client_id, get_client_secret,
-        helix::HelixApi,
     },
-    database::{models::NewEventSubTrigger, Database},
+    database::{
+        models::{EventSubTrigger, NewEventSubTrigger},
+        Database,
+    },
     platform::ChannelIdentifier,
 };
-use twitch_irc::login::{LoginCredentials, RefreshingLoginCredentials};
+
+/// Shouts out the raider, whose name and viewer count are the arguments of raid actions
+const DEFAULT_RAID_ACTION: &str = "{{shoutout (args 0)}}Welcome raiders from {{args 0}}! \
+    They were just playing {{game (args 0)}}";
 
 pub struct TwitchEventSub;
 
@@ -36,7 +48,9 @@ impl ExecutableCommand for TwitchEventSub {
         ctx: &ExecutionContext<'a, P>,
         _: &str,
         args: Vec<&str>,
-    ) -> Result<Option<String>, CommandError> {
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+
         if let ChannelIdentifier::TwitchChannel((broadcaster_id, _)) =
             ctx.platform_ctx.get_channel()
         {
@@ -51,39 +65,76 @@ impl ExecutableCommand for TwitchEventSub {
                 match action {
                     "add" | "create" => {
                         let (subscription, action) = self
-                            .get_subscription(args, broadcaster_id.clone(), ctx.db)
+                            .get_subscription(args, broadcaster_id.clone(), ctx.db, language)
                             .await?;
 
                         if action.is_empty() {
                             return Err(CommandError::MissingArgument("action".to_owned()));
                         }
 
+                        let secret = ctx.db.run(|db| db.get_current_eventsub_secret()).await?;
                         let subscription_response = app_api
-                            .add_eventsub_subscription(subscription.clone())
+                            .add_eventsub_subscription(
+                                subscription.clone(),
+                                &ctx.db.config(),
+                                &secret.secret,
+                            )
                             .await
                             .map_err(|e| {
-                                CommandError::GenericError(format!(
-                                    "Failed to create subscription: {}",
-                                    e
-                                ))
+                                CommandError::GenericError(
+                                    Message::EventSubSubscriptionFailed(&e.to_string())
+                                        .translate(language),
+                                )
                             })?;
 
-                        let id = &subscription_response.data.first().unwrap().id;
+                        let id = subscription_response.data.first().unwrap().id.clone();
+                        let creation_payload =
+                            serde_json::to_string(&subscription).expect("failed to serialize");
+                        let broadcaster_id = broadcaster_id.clone();
+
+                        ctx.db
+                            .run(move |db| {
+                                db.add_eventsub_trigger(NewEventSubTrigger {
+                                    broadcaster_id: &broadcaster_id,
+                                    event_type: subscription.get_type(),
+                                    action: &action,
+                                    creation_payload: &creation_payload,
+                                    id: &id,
+                                    secret_id: Some(secret.id),
+                                })
+                            })
+                            .await?;
+
+                        Ok(CommandOutput::Text(
+                            Message::EventSubTriggerAdded.translate(language),
+                        ))
+                    }
+                    "remove" | "delete" if is_index(args.as_slice().first()) => {
+                        let broadcaster_id = broadcaster_id.clone();
+                        let triggers = ctx
+                            .db
+                            .run(move |db| {
+                                db.get_eventsub_triggers_for_broadcaster(&broadcaster_id)
+                            })
+                            .await?;
+                        let trigger = get_trigger_by_index(&triggers, args.next(), language)?;
 
-                        ctx.db.add_eventsub_trigger(NewEventSubTrigger {
-                            broadcaster_id: &broadcaster_id,
-                            event_type: subscription.get_type(),
-                            action: &action,
-                            creation_payload: &serde_json::to_string(&subscription)
-                                .expect("failed to serialize"),
-                            id,
-                        })?;
+                        if let Err(e) = app_api.delete_eventsub_subscription(&trigger.id).await {
+                            // The subscription may already be gone after being revoked
+                            tracing::warn!("Could not delete subscription {}: {e}", trigger.id);
+                        }
+                        let trigger_id = trigger.id.clone();
+                        ctx.db
+                            .run(move |db| db.delete_eventsub_trigger(&trigger_id))
+                            .await?;
 
-                        Ok(Some("Trigger successfully added".to_owned()))
+                        Ok(CommandOutput::Text(
+                            Message::EventSubTriggerRemoved.translate(language),
+                        ))
                     }
                     "remove" | "delete" => {
                         let (subscription_type, _) = self
-                            .get_subscription(args, broadcaster_id.clone(), ctx.db)
+                            .get_subscription(args, broadcaster_id.clone(), ctx.db, language)
                             .await?;
 
                         let subscriptions = app_api
@@ -97,40 +148,127 @@ impl ExecutableCommand for TwitchEventSub {
                             app_api
                                 .delete_eventsub_subscription(&subscription.id)
                                 .await?;
-                            ctx.db.delete_eventsub_trigger(&subscription.id)?;
+                            let subscription_id = subscription.id.clone();
+                            ctx.db
+                                .run(move |db| db.delete_eventsub_trigger(&subscription_id))
+                                .await?;
 
-                            Ok(Some("Trigger succesfully removed".to_owned()))
+                            Ok(CommandOutput::Text(
+                                Message::EventSubTriggerRemoved.translate(language),
+                            ))
                         } else {
                             Err(CommandError::InvalidArgument(
-                                "unable to find matching subscription".to_owned(),
+                                Message::EventSubNoMatchingSubscription.translate(language),
                             ))
                         }
                     }
                     "list" => {
+                        let broadcaster_id = broadcaster_id.clone();
                         let triggers = ctx
                             .db
-                            .get_eventsub_triggers_for_broadcaster(&broadcaster_id)?;
+                            .run(move |db| {
+                                db.get_eventsub_triggers_for_broadcaster(&broadcaster_id)
+                            })
+                            .await?;
 
                         if !triggers.is_empty() {
+                            let subscriptions = app_api.get_eventsub_subscriptions(None).await?;
+
                             let output = triggers
                                 .into_iter()
-                                .map(|trigger| trigger.event_type)
+                                .enumerate()
+                                .map(|(i, trigger)| {
+                                    let subscription =
+                                        subscriptions.iter().find(|sub| sub.id == trigger.id);
+                                    let mut status = match subscription {
+                                        Some(sub) if sub.is_active() => {
+                                            Message::EventSubSubscriptionActive.translate(language)
+                                        }
+                                        Some(sub) => {
+                                            Message::EventSubSubscriptionRevoked(&sub.status)
+                                                .translate(language)
+                                        }
+                                        None => {
+                                            Message::EventSubSubscriptionMissing.translate(language)
+                                        }
+                                    };
+                                    if trigger.paused {
+                                        status.push_str(
+                                            &Message::EventSubSubscriptionPaused
+                                                .translate(language),
+                                        );
+                                    }
+
+                                    format!("{}. {} ({status})", i + 1, trigger.event_type)
+                                })
                                 .collect::<Vec<String>>()
                                 .join(", ");
-                            Ok(Some(output))
+                            Ok(CommandOutput::Text(output))
                         } else {
-                            Ok(Some("No eventsub triggers registered".to_owned()))
+                            Ok(CommandOutput::Text(
+                                Message::EventSubNoTriggers.translate(language),
+                            ))
                         }
                     }
-                    _ => Err(CommandError::GenericError(format!(
-                        "invalid action {action}"
-                    ))),
+                    "pause" | "resume" => {
+                        let broadcaster_id = broadcaster_id.clone();
+                        let triggers = ctx
+                            .db
+                            .run(move |db| {
+                                db.get_eventsub_triggers_for_broadcaster(&broadcaster_id)
+                            })
+                            .await?;
+                        let trigger = get_trigger_by_index(&triggers, args.next(), language)?;
+
+                        let paused = action == "pause";
+                        let trigger_id = trigger.id.clone();
+                        ctx.db
+                            .run(move |db| db.set_eventsub_trigger_paused(&trigger_id, paused))
+                            .await?;
+
+                        let message = match paused {
+                            true => Message::EventSubTriggerPaused(&trigger.event_type),
+                            false => Message::EventSubTriggerResumed(&trigger.event_type),
+                        };
+                        Ok(CommandOutput::Text(message.translate(language)))
+                    }
+                    "resubscribe" => {
+                        let broadcaster_id = broadcaster_id.clone();
+                        let triggers = ctx
+                            .db
+                            .run(move |db| {
+                                db.get_eventsub_triggers_for_broadcaster(&broadcaster_id)
+                            })
+                            .await?;
+                        let trigger = get_trigger_by_index(&triggers, args.next(), language)?;
+
+                        resubscribe_eventsub_trigger(twitch_api, ctx.db, trigger)
+                            .await
+                            .map_err(|e| {
+                                CommandError::GenericError(
+                                    Message::EventSubResubscribeFailed(&format!("{e:#}"))
+                                        .translate(language),
+                                )
+                            })?;
+
+                        Ok(CommandOutput::Text(
+                            Message::EventSubTriggerResubscribed(&trigger.event_type)
+                                .translate(language),
+                        ))
+                    }
+                    _ => Err(CommandError::GenericError(
+                        Message::InvalidAction(action).translate(language),
+                    )),
                 }
             } else {
-                Err("Twitch is not configured".into())
+                Err(CommandError::GenericError(
+                    Message::TwitchNotConfigured.translate(language),
+                ))
             }
         } else {
-            Err("EventSub can only be used on Twitch".into())
+            Err(CommandError::GenericError(
+                Message::OnlyOnTwitch("EventSub").translate(language),
+            ))
         }
     }
 }
@@ -141,6 +279,7 @@ impl TwitchEventSub {
         mut args: IntoIter<&str>,
         broadcaster_id: String,
         db: &Database,
+        language: Language,
     ) -> Result<(EventSubSubscriptionType, String), CommandError> {
         let sub_type = args
             .next()
@@ -164,25 +303,14 @@ impl TwitchEventSub {
                 action = action_str.trim().to_string();
                 let reward_name = reward_name.trim();
 
-                let streamer_credentials = db.make_twitch_credentials(broadcaster_id.clone());
-                let refreshing_credentials = RefreshingLoginCredentials::init(
-                    get_client_id().unwrap(),
-                    get_client_secret().unwrap(),
-                    streamer_credentials,
-                );
-
-                refreshing_credentials
-                    .get_credentials()
+                let streamer_api = get_broadcaster_helix_api(db, &broadcaster_id)
                     .await
                     .map_err(|_| {
                         CommandError::GenericError(
-                            "streamer has not authenticated the bot to manage channel points"
-                                .to_owned(),
+                            Message::StreamerNotAuthenticatedRewards.translate(language),
                         )
                     })?;
 
-                let streamer_api = HelixApi::with_credentials(refreshing_credentials).await;
-
                 let rewards_response = streamer_api.get_custom_rewards().await?;
 
                 let reward = rewards_response
@@ -190,9 +318,9 @@ impl TwitchEventSub {
                     .iter()
                     .find(|reward| reward.title.trim() == reward_name)
                     .ok_or_else(|| {
-                        CommandError::InvalidArgument(format!(
-                            "could not find reward `{reward_name}`"
-                        ))
+                        CommandError::InvalidArgument(
+                            Message::EventSubRewardNotFound(reward_name).translate(language),
+                        )
                     })?;
 
                 EventSubSubscriptionType::ChannelPointsCustomRewardRedemptionAdd(
@@ -202,14 +330,60 @@ impl TwitchEventSub {
                     },
                 )
             }
+            "stream.online" => EventSubSubscriptionType::StreamOnline(StreamOnlineCondition {
+                broadcaster_user_id: broadcaster_id.clone(),
+            }),
+            "stream.offline" => EventSubSubscriptionType::StreamOffline(StreamOfflineCondition {
+                broadcaster_user_id: broadcaster_id.clone(),
+            }),
+            "channel.raid" | "raid" => {
+                if action.is_empty() {
+                    action = DEFAULT_RAID_ACTION.to_owned();
+                }
+
+                EventSubSubscriptionType::ChannelRaid(ChannelRaidCondition {
+                    to_broadcaster_user_id: broadcaster_id,
+                })
+            }
+            "channel.shield_mode.begin" | "shield.begin" => {
+                EventSubSubscriptionType::ShieldModeBegin(ShieldModeCondition {
+                    broadcaster_user_id: broadcaster_id.clone(),
+                    moderator_user_id: broadcaster_id,
+                })
+            }
+            "channel.shield_mode.end" | "shield.end" => {
+                EventSubSubscriptionType::ShieldModeEnd(ShieldModeCondition {
+                    broadcaster_user_id: broadcaster_id.clone(),
+                    moderator_user_id: broadcaster_id,
+                })
+            }
             _ => {
-                return Err(CommandError::InvalidArgument(format!(
-                    "Invalid subscription type {}",
-                    sub_type
-                )))
+                return Err(CommandError::InvalidArgument(
+                    Message::EventSubInvalidType(sub_type).translate(language),
+                ))
             }
         };
 
         Ok((subscription, action))
     }
 }
+
+fn is_index(arg: Option<&&str>) -> bool {
+    arg.map_or(false, |arg| arg.parse::<usize>().is_ok())
+}
+
+/// Triggers are referred to by their position in `eventsub list`, starting from 1
+fn get_trigger_by_index<'a>(
+    triggers: &'a [EventSubTrigger],
+    arg: Option<&str>,
+    language: Language,
+) -> Result<&'a EventSubTrigger, CommandError> {
+    let arg = arg.ok_or_else(|| CommandError::MissingArgument("trigger number".to_owned()))?;
+
+    arg.parse::<usize>()
+        .ok()
+        .and_then(|index| triggers.get(index.checked_sub(1)?))
+        .ok_or_else(|| {
+            CommandError::InvalidArgument(Message::EventSubNoTrigger(arg).translate(language))
+        })
+}
@@ -0,0 +1,152 @@
+use super::*;
+use crate::command_handler::{
+    finnhub_api::FinnhubApi,
+    i18n::{Language, Message},
+};
+
+/// Each symbol is a separate request in `portfolio`
+pub const MAX_WATCHLIST_SIZE: usize = 10;
+const MAX_SYMBOL_LENGTH: usize = 12;
+
+/// `watch add <symbols>`, `watch remove <symbols>`, `watch clear` and `watch list`
+#[derive(Clone)]
+pub struct Watch {
+    pub finnhub_api: Option<FinnhubApi>,
+}
+
+#[async_trait]
+impl ExecutableCommand for Watch {
+    fn get_names(&self) -> &[&str] {
+        &["watch", "watchlist"]
+    }
+
+    fn get_cooldown(&self) -> u64 {
+        5
+    }
+
+    async fn execute<'a, P: PlatformContext + Send + Sync>(
+        &self,
+        ctx: &ExecutionContext<'a, P>,
+        _: &str,
+        args: Vec<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let mut args = args.into_iter();
+        let user_id = ctx.user.id;
+        let mut watchlist = ctx.db.run(move |db| db.get_watchlist(user_id)).await?;
+
+        let response = match args.next().unwrap_or("list") {
+            "add" => {
+                let finnhub_api = self.finnhub_api.as_ref().ok_or_else(|| {
+                    CommandError::GenericError(Message::StocksNotConfigured.translate(language))
+                })?;
+
+                let symbols = parse_symbols(args, language)?;
+                for symbol in symbols {
+                    if watchlist.contains(&symbol) {
+                        continue;
+                    }
+                    if watchlist.len() >= MAX_WATCHLIST_SIZE {
+                        return Err(CommandError::InvalidArgument(
+                            Message::WatchlistFull(MAX_WATCHLIST_SIZE).translate(language),
+                        ));
+                    }
+
+                    let quote = finnhub_api
+                        .quote(&symbol)
+                        .await
+                        .map_err(|e| CommandError::InternalError(e.to_string()))?;
+                    if quote.is_empty() {
+                        return Err(CommandError::InvalidArgument(
+                            Message::UnknownSymbol(&symbol).translate(language),
+                        ));
+                    }
+
+                    watchlist.push(symbol);
+                }
+
+                let saved_watchlist = watchlist.clone();
+                ctx.db
+                    .run(move |db| db.set_watchlist(user_id, &saved_watchlist))
+                    .await?;
+                Message::Watching(&watchlist.join(", ")).translate(language)
+            }
+            "remove" | "delete" => {
+                let symbols = parse_symbols(args, language)?;
+                watchlist.retain(|symbol| !symbols.contains(symbol));
+
+                let saved_watchlist = watchlist.clone();
+                ctx.db
+                    .run(move |db| db.set_watchlist(user_id, &saved_watchlist))
+                    .await?;
+                match watchlist.is_empty() {
+                    true => Message::WatchlistEmpty.translate(language),
+                    false => Message::Watching(&watchlist.join(", ")).translate(language),
+                }
+            }
+            "clear" => {
+                ctx.db.run(move |db| db.set_watchlist(user_id, &[])).await?;
+                Message::WatchlistCleared.translate(language)
+            }
+            "list" => match watchlist.is_empty() {
+                true => Message::WatchlistEmptyHint.translate(language),
+                false => Message::Watching(&watchlist.join(", ")).translate(language),
+            },
+            other => {
+                return Err(CommandError::InvalidArgument(
+                    Message::WatchUnknownSubcommand(other).translate(language),
+                ))
+            }
+        };
+
+        Ok(CommandOutput::Text(response))
+    }
+}
+
+/// Symbols are stored in uppercase, separated by commas
+fn parse_symbols<'a>(
+    args: impl Iterator<Item = &'a str>,
+    language: Language,
+) -> Result<Vec<String>, CommandError> {
+    let symbols = args
+        .flat_map(|arg| arg.split(','))
+        .filter(|symbol| !symbol.is_empty())
+        .map(|symbol| {
+            if is_valid_symbol(symbol) {
+                Ok(symbol.to_uppercase())
+            } else {
+                Err(CommandError::InvalidArgument(
+                    Message::NotAStockSymbol(symbol).translate(language),
+                ))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if symbols.is_empty() {
+        return Err(CommandError::MissingArgument("symbol".to_owned()));
+    }
+
+    Ok(symbols)
+}
+
+fn is_valid_symbol(symbol: &str) -> bool {
+    symbol.len() <= MAX_SYMBOL_LENGTH
+        && symbol
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_symbols;
+    use crate::command_handler::i18n::Language;
+
+    #[test]
+    fn parses_symbols() {
+        let symbols = parse_symbols(["aapl,msft", "BMW.DE"].into_iter(), Language::En).unwrap();
+        assert_eq!(symbols, ["AAPL", "MSFT", "BMW.DE"]);
+
+        assert!(parse_symbols(["AAPL;DROP"].into_iter(), Language::En).is_err());
+        assert!(parse_symbols(std::iter::empty(), Language::En).is_err());
+    }
+}
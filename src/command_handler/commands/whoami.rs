@@ -1,4 +1,6 @@
 use super::*;
+use crate::command_handler::i18n::Message;
+use crate::command_handler::name_history::get_platform;
 
 #[derive(Debug, Clone)]
 pub struct WhoAmI;
@@ -22,13 +24,34 @@ impl ExecutableCommand for WhoAmI {
         ctx: &ExecutionContext<'a, P>,
         _trigger_name: &str,
         _args: Vec<&str>,
-    ) -> Result<Option<String>, CommandError> {
-        Ok(Some(format!(
-            "{:?}, identified as {}, channel: {}, permissions: {:?}",
-            ctx.user,
-            ctx.platform_ctx.get_user_identifier(),
-            ctx.platform_ctx.get_channel(),
-            ctx.get_permissions().await,
-        )))
+    ) -> Result<CommandOutput, CommandError> {
+        let language = ctx.get_language().await?;
+        let identifier = ctx.platform_ctx.get_user_identifier();
+        let mut response = Message::WhoAmI {
+            user: &format!("{:?}", ctx.user),
+            identifier: &identifier.to_string(),
+            channel: &ctx.platform_ctx.get_channel().to_string(),
+            permissions: &format!("{:?}", ctx.get_permissions().await),
+        }
+        .translate(language);
+
+        let platform = get_platform(&identifier);
+        let display_name = ctx.platform_ctx.get_display_name();
+        let user_id = ctx.user.id;
+        let previous_names = ctx
+            .db
+            .run(move |db| db.get_user_names(user_id))
+            .await?
+            .into_iter()
+            .filter(|name| Some(name.platform.as_str()) == platform)
+            .map(|name| name.name)
+            .filter(|name| !name.eq_ignore_ascii_case(display_name))
+            .collect::<Vec<_>>();
+        if !previous_names.is_empty() {
+            response
+                .push_str(&Message::PreviousNames(&previous_names.join(", ")).translate(language));
+        }
+
+        Ok(CommandOutput::Text(response))
     }
 }
@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+
+use super::blocked_users::BlockedUsers;
+use super::{register_api_helpers, TemplateRegistry};
+use crate::config::Config;
+use crate::database::Database;
+use crate::LogFilterHandle;
+
+/// Applies the settings that can be changed without reconnecting to the platforms:
+/// the log filter, blocked users, the admin user and the API keys of template helpers
+#[derive(Debug, Clone)]
+pub struct ConfigReloader {
+    pub db: Database,
+    pub config_path: Option<PathBuf>,
+    pub blocked_users: BlockedUsers,
+    pub template_registry: TemplateRegistry,
+    pub log_filter: LogFilterHandle,
+}
+
+impl ConfigReloader {
+    /// Returns whether some of the changed settings need a restart to take effect
+    pub async fn reload(&self) -> anyhow::Result<bool> {
+        let new_config = Config::load(self.config_path.clone())?;
+        let (config, restart_required) = self.db.config().merge_reloadable(&new_config);
+
+        let env_filter = config.env_filter()?;
+        self.log_filter
+            .reload(env_filter)
+            .context("Could not update the log filter")?;
+
+        self.blocked_users.import(&config.blocked_users).await?;
+        self.blocked_users.reload().await?;
+
+        let mut template_registry = (**self.template_registry.load()).clone();
+        register_api_helpers(&mut template_registry, &self.db, &config.api_keys);
+        self.template_registry.store(Arc::new(template_registry));
+
+        self.db.set_config(config);
+
+        tracing::info!("Reloaded the config, restart required: {restart_required}");
+
+        Ok(restart_required)
+    }
+}
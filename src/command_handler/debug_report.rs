@@ -0,0 +1,373 @@
+//! Traces of single command executions for the `debug` commands.
+//! Helper calls and HTTP requests are recorded while an execution is being captured,
+//! and the finished report is kept for a while so it can be viewed on the dashboard.
+//! Dry runs additionally skip platform side effects, which are recorded instead.
+//! The last error of each channel is kept too, for `debug last-error`.
+use super::render_budget;
+use crate::database::cache::TtlCache;
+use chrono::{DateTime, Utc};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    ScopedJson, StringOutput,
+};
+use passwords::PasswordGenerator;
+use serde::Serialize;
+use serde_json::Value as Json;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const REPORT_TTL: Duration = Duration::from_secs(3600);
+
+tokio::task_local! {
+    static RECORDER: TraceRecorder;
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceKind {
+    Helper,
+    Http,
+    /// A platform action that was skipped during a dry run
+    SideEffect,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceEntry {
+    pub kind: TraceKind,
+    pub name: String,
+    pub input: String,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    /// Since the start of the execution
+    pub started_ms: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugReport {
+    pub id: String,
+    pub channel_id: u64,
+    pub mode: String,
+    pub source: String,
+    pub response: Option<String>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub trace: Vec<TraceEntry>,
+}
+
+/// The most recent failure of a command in a channel, shown to mods with `debug last-error`
+/// regardless of how much of it the channel's error responses show
+#[derive(Debug, Clone)]
+pub struct LastError {
+    pub command: String,
+    pub error: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Collects the trace of the execution it is scoped to
+#[derive(Debug, Clone)]
+pub struct TraceRecorder {
+    started_at: Instant,
+    entries: Arc<Mutex<Vec<TraceEntry>>>,
+    dry_run: bool,
+}
+
+impl TraceRecorder {
+    fn new(dry_run: bool) -> Self {
+        Self {
+            started_at: Instant::now(),
+            entries: Arc::new(Mutex::new(Vec::new())),
+            dry_run,
+        }
+    }
+
+    /// The recorder of the execution that is currently being captured
+    pub fn current() -> Option<Self> {
+        RECORDER.try_with(Clone::clone).ok()
+    }
+
+    /// Task locals are not inherited by blocking tasks, so they have to be scoped again
+    pub fn sync_scope<R>(self, f: impl FnOnce() -> R) -> R {
+        RECORDER.sync_scope(self, f)
+    }
+
+    fn push(&self, entry: TraceEntry) {
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    fn into_entries(self) -> Vec<TraceEntry> {
+        let mut entries = std::mem::take(&mut *self.entries.lock().unwrap());
+        // Entries are pushed once they finish, so nested calls come before their parents
+        entries.sort_by_key(|entry| entry.started_ms);
+        entries
+    }
+}
+
+/// Records the entry if an execution is being captured, `started_at` is when the call began
+pub fn record(
+    kind: TraceKind,
+    name: impl Into<String>,
+    input: impl Into<String>,
+    result: Result<String, String>,
+    started_at: Instant,
+) {
+    if let Some(recorder) = TraceRecorder::current() {
+        let (output, error) = match result {
+            Ok(output) => (Some(output), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        recorder.push(TraceEntry {
+            kind,
+            name: name.into(),
+            input: input.into(),
+            output,
+            error,
+            started_ms: started_at
+                .saturating_duration_since(recorder.started_at)
+                .as_millis() as u64,
+            duration_ms: started_at.elapsed().as_millis() as u64,
+        });
+    }
+}
+
+/// Records the side effect instead of performing it if the current execution is a dry run.
+/// Returns whether the side effect should be skipped.
+pub fn skip_side_effect(name: &str, input: impl Into<String>) -> bool {
+    match TraceRecorder::current() {
+        Some(recorder) if recorder.dry_run => {
+            let started_at = Instant::now();
+            recorder.push(TraceEntry {
+                kind: TraceKind::SideEffect,
+                name: name.to_owned(),
+                input: input.into(),
+                output: None,
+                error: None,
+                started_ms: started_at
+                    .saturating_duration_since(recorder.started_at)
+                    .as_millis() as u64,
+                duration_ms: 0,
+            });
+            true
+        }
+        _ => false,
+    }
+}
+
+/// The side effects that were skipped during a dry run
+pub fn get_side_effects(trace: Vec<TraceEntry>) -> Vec<TraceEntry> {
+    trace
+        .into_iter()
+        .filter(|entry| matches!(entry.kind, TraceKind::SideEffect))
+        .collect()
+}
+
+/// Runs the future while recording its trace, returning the trace with the total duration
+pub async fn capture<F: Future>(future: F) -> (F::Output, Vec<TraceEntry>, Duration) {
+    capture_with(TraceRecorder::new(false), future).await
+}
+
+/// Like [`capture`], but platform side effects are skipped and recorded in the trace
+pub async fn capture_dry_run<F: Future>(future: F) -> (F::Output, Vec<TraceEntry>, Duration) {
+    capture_with(TraceRecorder::new(true), future).await
+}
+
+async fn capture_with<F: Future>(
+    recorder: TraceRecorder,
+    future: F,
+) -> (F::Output, Vec<TraceEntry>, Duration) {
+    let output = RECORDER.scope(recorder.clone(), future).await;
+    let duration = recorder.started_at.elapsed();
+
+    (output, recorder.into_entries(), duration)
+}
+
+/// Wraps a template helper to record its calls and keep them within the render budget
+pub struct TracedHelper {
+    name: String,
+    inner: Box<dyn HelperDef + Send + Sync>,
+    /// Every call counts as an HTTP call of the render
+    http: bool,
+}
+
+impl TracedHelper {
+    fn describe_params(h: &Helper) -> String {
+        let params: Vec<&Json> = h.params().iter().map(|param| param.value()).collect();
+        serde_json::to_string(&params).unwrap_or_default()
+    }
+}
+
+impl HelperDef for TracedHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        render_budget::check_duration()?;
+        if self.http {
+            render_budget::start_http_call()?;
+        }
+
+        let started_at = Instant::now();
+        let result = self.inner.call_inner(h, r, ctx, rc);
+
+        match &result {
+            // Handlebars falls back to `call` then, which is recorded and counted instead
+            Err(err) if err.is_unimplemented() => {
+                if self.http {
+                    render_budget::cancel_http_call();
+                }
+            }
+            Ok(value) => record(
+                TraceKind::Helper,
+                &self.name,
+                Self::describe_params(h),
+                Ok(value.render()),
+                started_at,
+            ),
+            Err(err) => record(
+                TraceKind::Helper,
+                &self.name,
+                Self::describe_params(h),
+                Err(err.desc.clone()),
+                started_at,
+            ),
+        }
+
+        result
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        render_budget::check_duration()?;
+        if self.http {
+            render_budget::start_http_call()?;
+        }
+
+        if TraceRecorder::current().is_none() {
+            return self.inner.call(h, r, ctx, rc, out);
+        }
+
+        let started_at = Instant::now();
+        let mut captured = StringOutput::new();
+        let result = self.inner.call(h, r, ctx, rc, &mut captured);
+        let output = captured.into_string().unwrap_or_default();
+
+        record(
+            TraceKind::Helper,
+            &self.name,
+            Self::describe_params(h),
+            match &result {
+                Ok(()) => Ok(output.clone()),
+                Err(err) => Err(err.desc.clone()),
+            },
+            started_at,
+        );
+
+        result?;
+        out.write(&output)?;
+
+        Ok(())
+    }
+}
+
+pub trait TracedHelperRegistry {
+    /// Registers the helper so that its calls show up in debug reports
+    fn register_traced_helper(&mut self, name: &str, helper: Box<dyn HelperDef + Send + Sync>);
+
+    /// Like [`register_traced_helper`](Self::register_traced_helper), for helpers that make
+    /// requests. Their calls count towards the HTTP calls of the render budget
+    fn register_http_helper(&mut self, name: &str, helper: Box<dyn HelperDef + Send + Sync>);
+}
+
+impl TracedHelperRegistry for Handlebars<'static> {
+    fn register_traced_helper(&mut self, name: &str, helper: Box<dyn HelperDef + Send + Sync>) {
+        self.register_helper(
+            name,
+            Box::new(TracedHelper {
+                name: name.to_owned(),
+                inner: helper,
+                http: false,
+            }),
+        );
+    }
+
+    fn register_http_helper(&mut self, name: &str, helper: Box<dyn HelperDef + Send + Sync>) {
+        self.register_helper(
+            name,
+            Box::new(TracedHelper {
+                name: name.to_owned(),
+                inner: helper,
+                http: true,
+            }),
+        );
+    }
+}
+
+/// Finished reports by id, which are kept for an hour
+#[derive(Debug, Clone)]
+pub struct DebugReports {
+    reports: Arc<TtlCache<String, Arc<DebugReport>>>,
+    /// By channel id
+    last_errors: Arc<TtlCache<u64, LastError>>,
+}
+
+impl Default for DebugReports {
+    fn default() -> Self {
+        Self {
+            reports: Arc::new(TtlCache::new(REPORT_TTL)),
+            last_errors: Arc::new(TtlCache::new(REPORT_TTL)),
+        }
+    }
+}
+
+impl DebugReports {
+    /// The id is set on the report, which is returned
+    pub fn insert(&self, mut report: DebugReport) -> Arc<DebugReport> {
+        self.reports.remove_expired();
+
+        report.id = generate_report_id();
+        let report = Arc::new(report);
+        self.reports.insert(report.id.clone(), report.clone());
+
+        report
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<DebugReport>> {
+        self.reports.get(&id.to_owned())
+    }
+
+    pub fn set_last_error(&self, channel_id: u64, error: LastError) {
+        self.last_errors.remove_expired();
+        self.last_errors.insert(channel_id, error);
+    }
+
+    pub fn get_last_error(&self, channel_id: u64) -> Option<LastError> {
+        self.last_errors.get(&channel_id)
+    }
+}
+
+fn generate_report_id() -> String {
+    PasswordGenerator {
+        length: 24,
+        numbers: true,
+        lowercase_letters: true,
+        uppercase_letters: false,
+        symbols: false,
+        spaces: false,
+        exclude_similar_characters: false,
+        strict: true,
+    }
+    .generate_one()
+    .expect("Failed to generate report id")
+}
@@ -0,0 +1,146 @@
+use anyhow::anyhow;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+
+const URBAN_DICTIONARY_URL: &str = "https://api.urbandictionary.com/v0/define";
+const DICTIONARY_URL: &str = "https://api.dictionaryapi.dev/api/v2/entries";
+
+#[derive(Clone, Default)]
+pub struct UrbanDictionaryApi {
+    client: Client,
+}
+
+impl UrbanDictionaryApi {
+    /// Returns the most upvoted definition of the term
+    pub async fn define(&self, term: &str) -> anyhow::Result<Option<UrbanDefinition>> {
+        let response = self
+            .client
+            .get(URBAN_DICTIONARY_URL)
+            .query(&[("term", term)])
+            .send()
+            .await?;
+
+        tracing::info!("GET {}: {}", response.url(), response.status());
+
+        if !response.status().is_success() {
+            return Err(anyhow!("response status {}", response.status()));
+        }
+
+        let definitions = response.json::<UrbanResponse>().await?.list;
+
+        Ok(definitions
+            .into_iter()
+            .max_by_key(|definition| definition.thumbs_up))
+    }
+}
+
+#[derive(Deserialize)]
+struct UrbanResponse {
+    list: Vec<UrbanDefinition>,
+}
+
+#[derive(Deserialize)]
+pub struct UrbanDefinition {
+    pub word: String,
+    pub definition: String,
+    pub example: String,
+    pub thumbs_up: i64,
+}
+
+impl UrbanDefinition {
+    /// Formats the definition without the link brackets around referenced terms
+    pub fn to_message(&self) -> String {
+        let strip = |text: &str| {
+            text.replace(['[', ']'], "")
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let mut message = format!("{}: {}", self.word, strip(&self.definition));
+
+        if !self.example.is_empty() {
+            message.push_str(&format!(" Example: {}", strip(&self.example)));
+        }
+
+        message
+    }
+}
+
+#[derive(Clone)]
+pub struct DictionaryApi {
+    client: Client,
+    language: String,
+}
+
+impl Default for DictionaryApi {
+    fn default() -> Self {
+        Self {
+            client: Client::new(),
+            language: String::from("en"),
+        }
+    }
+}
+
+impl DictionaryApi {
+    pub async fn define(&self, word: &str) -> anyhow::Result<Option<DictionaryEntry>> {
+        let response = self
+            .client
+            .get(format!(
+                "{DICTIONARY_URL}/{}/{}",
+                self.language,
+                urlencoding::encode(word)
+            ))
+            .send()
+            .await?;
+
+        tracing::info!("GET {}: {}", response.url(), response.status());
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let entries = response.json::<Vec<DictionaryEntry>>().await?;
+                Ok(entries.into_iter().next())
+            }
+            status => Err(anyhow!("response status {status}")),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DictionaryEntry {
+    pub word: String,
+    pub meanings: Vec<Meaning>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Meaning {
+    pub part_of_speech: String,
+    pub definitions: Vec<Definition>,
+}
+
+#[derive(Deserialize)]
+pub struct Definition {
+    pub definition: String,
+}
+
+impl DictionaryEntry {
+    /// Formats the first definition of every part of speech
+    pub fn to_message(&self) -> String {
+        let meanings = self
+            .meanings
+            .iter()
+            .filter_map(|meaning| {
+                let definition = meaning.definitions.first()?;
+                Some(format!(
+                    "({}) {}",
+                    meaning.part_of_speech, definition.definition
+                ))
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        format!("{}: {}", self.word, meanings)
+    }
+}
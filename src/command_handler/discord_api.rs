@@ -1,11 +1,14 @@
+use chrono::Utc;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
+use twilight_http::request::AuditLogReason;
 use twilight_http::Client;
 use twilight_model::guild::{Guild, Permissions};
 use twilight_model::id::Id;
 use twilight_model::user::{CurrentUser, User};
+use twilight_model::util::Timestamp;
 use twilight_util::permission_calculator::PermissionCalculator;
 
 #[derive(Clone, Debug)]
@@ -134,6 +137,54 @@ impl DiscordApi {
         }
     }
 
+    /// Discord timeouts can last up to 28 days
+    pub async fn timeout_member(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        duration: Duration,
+        reason: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let until = Timestamp::from_secs(Utc::now().timestamp() + duration.as_secs() as i64)?;
+
+        let mut request = self
+            .http
+            .update_guild_member(Id::new(guild_id), Id::new(user_id))
+            .communication_disabled_until(Some(until))?;
+        if let Some(reason) = reason {
+            request = request.reason(reason)?;
+        }
+
+        request.exec().await?;
+
+        Ok(())
+    }
+
+    pub async fn ban_member(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        reason: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let mut request = self.http.create_ban(Id::new(guild_id), Id::new(user_id));
+        if let Some(reason) = reason {
+            request = request.reason(reason)?;
+        }
+
+        request.exec().await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_message(&self, channel_id: u64, message_id: u64) -> anyhow::Result<()> {
+        self.http
+            .delete_message(Id::new(channel_id), Id::new(message_id))
+            .exec()
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn get_guild_name(&self, guild_id: u64) -> anyhow::Result<String> {
         let guild_names_cache_guard = self.guild_names_cache.read().await;
         Ok(match guild_names_cache_guard.get(&guild_id) {
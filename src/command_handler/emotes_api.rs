@@ -0,0 +1,200 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use reqwest::{Client, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use strum::{AsRefStr, EnumString};
+
+const SEVENTV_URL: &str = "https://7tv.io/v3";
+const BTTV_URL: &str = "https://api.betterttv.net/3";
+const FFZ_URL: &str = "https://api.frankerfacez.com/v1";
+
+const CACHE_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, EnumString, AsRefStr)]
+#[strum(ascii_case_insensitive)]
+#[serde(rename_all = "lowercase")]
+pub enum EmoteProvider {
+    #[strum(serialize = "7tv")]
+    #[serde(rename = "7tv")]
+    SevenTv,
+    #[strum(serialize = "bttv")]
+    Bttv,
+    #[strum(serialize = "ffz")]
+    Ffz,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Emote {
+    pub name: String,
+    pub provider: EmoteProvider,
+}
+
+/// Looks up third party emotes of Twitch channels
+#[derive(Debug, Clone, Default)]
+pub struct EmotesApi {
+    client: Client,
+    cache: Arc<DashMap<String, (Instant, Vec<Emote>)>>,
+}
+
+impl EmotesApi {
+    /// Returns the emotes from all providers for the given Twitch channel id,
+    /// providers that fail to respond are skipped
+    pub async fn get_channel_emotes(&self, twitch_id: &str) -> anyhow::Result<Vec<Emote>> {
+        if let Some(entry) = self.cache.get(twitch_id) {
+            let (fetched_at, emotes) = entry.value();
+
+            if fetched_at.elapsed() < CACHE_TTL {
+                return Ok(emotes.clone());
+            }
+        }
+
+        let (seventv, bttv, ffz) = tokio::join!(
+            self.get_seventv_emotes(twitch_id),
+            self.get_bttv_emotes(twitch_id),
+            self.get_ffz_emotes(twitch_id)
+        );
+
+        let mut emotes = Vec::new();
+        let mut complete = true;
+
+        for (provider, result) in [
+            (EmoteProvider::SevenTv, seventv),
+            (EmoteProvider::Bttv, bttv),
+            (EmoteProvider::Ffz, ffz),
+        ] {
+            match result {
+                Ok(provider_emotes) => emotes.extend(provider_emotes),
+                Err(e) => {
+                    tracing::warn!(
+                        "Could not get {} emotes for {twitch_id}: {e}",
+                        provider.as_ref()
+                    );
+                    complete = false;
+                }
+            }
+        }
+
+        // Partial results are not cached so that the failed providers are retried
+        if complete {
+            self.cache
+                .insert(twitch_id.to_owned(), (Instant::now(), emotes.clone()));
+        }
+
+        Ok(emotes)
+    }
+
+    /// Returns `None` if the channel is not registered with the provider
+    async fn get_json<T: DeserializeOwned>(&self, url: String) -> anyhow::Result<Option<T>> {
+        let response = self.client.get(url).send().await?;
+
+        tracing::info!("GET {}: {}", response.url(), response.status());
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            _ => Ok(Some(response.error_for_status()?.json().await?)),
+        }
+    }
+
+    async fn get_seventv_emotes(&self, twitch_id: &str) -> anyhow::Result<Vec<Emote>> {
+        let user: Option<SevenTvUser> = self
+            .get_json(format!("{SEVENTV_URL}/users/twitch/{twitch_id}"))
+            .await?;
+
+        Ok(user
+            .and_then(|user| user.emote_set)
+            .map(|set| set.emotes)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|emote| Emote {
+                name: emote.name,
+                provider: EmoteProvider::SevenTv,
+            })
+            .collect())
+    }
+
+    async fn get_bttv_emotes(&self, twitch_id: &str) -> anyhow::Result<Vec<Emote>> {
+        let user: Option<BttvUser> = self
+            .get_json(format!("{BTTV_URL}/cached/users/twitch/{twitch_id}"))
+            .await?;
+
+        Ok(user
+            .map(|user| {
+                user.channel_emotes
+                    .into_iter()
+                    .chain(user.shared_emotes)
+                    .map(|emote| Emote {
+                        name: emote.code,
+                        provider: EmoteProvider::Bttv,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn get_ffz_emotes(&self, twitch_id: &str) -> anyhow::Result<Vec<Emote>> {
+        let room: Option<FfzRoom> = self
+            .get_json(format!("{FFZ_URL}/room/id/{twitch_id}"))
+            .await?;
+
+        Ok(room
+            .map(|room| {
+                room.sets
+                    .into_values()
+                    .flat_map(|set| set.emoticons)
+                    .map(|emote| Emote {
+                        name: emote.name,
+                        provider: EmoteProvider::Ffz,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SevenTvUser {
+    emote_set: Option<SevenTvEmoteSet>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SevenTvEmoteSet {
+    #[serde(default)]
+    emotes: Vec<SevenTvEmote>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SevenTvEmote {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BttvUser {
+    channel_emotes: Vec<BttvEmote>,
+    shared_emotes: Vec<BttvEmote>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BttvEmote {
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfzRoom {
+    sets: HashMap<String, FfzEmoteSet>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfzEmoteSet {
+    emoticons: Vec<FfzEmote>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfzEmote {
+    name: String,
+}
@@ -1,6 +1,12 @@
+use super::i18n::{Language, Message};
+use crate::database::models::{ChannelSettings, ErrorResponseMode};
 use crate::{database::DatabaseError, platform::UserIdentifierError};
+use handlebars::Handlebars;
+use serde_json::json;
 use std::{env::VarError, fmt, num::ParseIntError};
 
+const SHORT_ERROR_RESPONSE: &str = "❗";
+
 #[derive(Debug, thiserror::Error)]
 pub enum CommandError {
     MissingArgument(String),
@@ -10,30 +16,71 @@ pub enum CommandError {
     TemplateError(#[from] handlebars::RenderError),
     ConfigurationError(#[from] VarError),
     GenericError(String),
+    /// A failure that isn't caused by the user's input, such as an unreachable external API
+    InternalError(String),
+    Localized(Message<'static>),
 }
 
-impl fmt::Display for CommandError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl CommandError {
+    /// Template errors are not translated, database failures are replaced with a generic message
+    pub fn localize(&self, language: Language) -> String {
         match self {
-            CommandError::MissingArgument(arg) => {
-                f.write_str(&format!("missing argument: {}", arg))
-            }
-            CommandError::InvalidArgument(arg) => {
-                f.write_str(&format!("invalid argument: {}", arg))
+            CommandError::MissingArgument(arg) => Message::MissingArgument(arg).translate(language),
+            CommandError::InvalidArgument(arg) => Message::InvalidArgument(arg).translate(language),
+            CommandError::NoPermissions => Message::NoPermissions.translate(language),
+            CommandError::DatabaseError(DatabaseError::DieselError(_)) => {
+                Message::DatabaseError.translate(language)
             }
-            CommandError::NoPermissions => {
-                f.write_str("you don't have the permissions to use this command")
-            }
-            CommandError::DatabaseError(e) => f.write_str(&e.to_string()),
-            CommandError::TemplateError(e) => f.write_str(&e.to_string()),
+            CommandError::DatabaseError(e) => e.to_string(),
+            CommandError::TemplateError(e) => e.to_string(),
             CommandError::ConfigurationError(e) => {
-                f.write_str(&format!("configuration error: {}", e))
+                Message::ConfigurationError(&e.to_string()).translate(language)
             }
-            CommandError::GenericError(s) => f.write_str(s),
+            CommandError::GenericError(s) | CommandError::InternalError(s) => s.clone(),
+            CommandError::Localized(message) => message.translate(language),
+        }
+    }
+
+    /// The full error for mods, including the details of database failures
+    pub fn details(&self) -> String {
+        match self {
+            CommandError::DatabaseError(e) => e.to_string(),
+            _ => self.to_string(),
         }
     }
 }
 
+/// The response to a failed command according to the channel's settings, `None` when silent.
+/// A template that fails to render falls back to the message itself
+pub fn format_error_response(
+    settings: &ChannelSettings,
+    command: &str,
+    message: &str,
+) -> Option<String> {
+    match settings.error_responses {
+        ErrorResponseMode::Silent => None,
+        ErrorResponseMode::Short => Some(SHORT_ERROR_RESPONSE.to_owned()),
+        ErrorResponseMode::Full => match &settings.error_template {
+            Some(template) => {
+                let mut handlebars = Handlebars::new();
+                handlebars.register_escape_fn(handlebars::no_escape);
+
+                let response = handlebars
+                    .render_template(template, &json!({ "error": message, "command": command }))
+                    .unwrap_or_else(|_| message.to_owned());
+                Some(response)
+            }
+            None => Some(message.to_owned()),
+        },
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Language::default()))
+    }
+}
+
 impl From<diesel::result::Error> for CommandError {
     fn from(e: diesel::result::Error) -> Self {
         Self::DatabaseError(DatabaseError::DieselError(e))
@@ -42,25 +89,25 @@ impl From<diesel::result::Error> for CommandError {
 
 impl From<ParseIntError> for CommandError {
     fn from(_: ParseIntError) -> Self {
-        Self::InvalidArgument("expected a number".to_string())
+        Self::Localized(Message::ExpectedNumber)
     }
 }
 
 impl From<UserIdentifierError> for CommandError {
     fn from(e: UserIdentifierError) -> Self {
         match e {
-            UserIdentifierError::MissingDelimiter => Self::MissingArgument(
-                "separator `:`! Must be in the form of `platform:user`".to_string(),
-            ),
-            UserIdentifierError::InvalidPlatform => Self::InvalidArgument("platform".to_string()),
-            UserIdentifierError::InvalidId => Self::InvalidArgument("invalid user id".to_owned()),
+            UserIdentifierError::MissingDelimiter => {
+                Self::Localized(Message::MissingPlatformSeparator)
+            }
+            UserIdentifierError::InvalidPlatform => Self::Localized(Message::InvalidPlatform),
+            UserIdentifierError::InvalidId => Self::Localized(Message::InvalidUserId),
         }
     }
 }
 
 impl From<anyhow::Error> for CommandError {
     fn from(e: anyhow::Error) -> Self {
-        Self::GenericError(e.to_string())
+        Self::InternalError(e.to_string())
     }
 }
 
@@ -69,3 +116,36 @@ impl From<&'static str> for CommandError {
         CommandError::GenericError(msg.to_owned())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::format_error_response;
+    use crate::database::models::{ChannelSettings, ErrorResponseMode};
+
+    #[test]
+    fn formats_error_responses() {
+        let mut settings = ChannelSettings::new(1);
+        assert_eq!(
+            format_error_response(&settings, "top", "missing argument"),
+            Some("missing argument".to_owned())
+        );
+
+        settings.error_template = Some("{{ command }} failed: {{ error }} <_<".to_owned());
+        assert_eq!(
+            format_error_response(&settings, "top", "missing argument"),
+            Some("top failed: missing argument <_<".to_owned())
+        );
+
+        settings.error_responses = ErrorResponseMode::Short;
+        assert_eq!(
+            format_error_response(&settings, "top", "missing argument"),
+            Some("❗".to_owned())
+        );
+
+        settings.error_responses = ErrorResponseMode::Silent;
+        assert_eq!(
+            format_error_response(&settings, "top", "missing argument"),
+            None
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use super::context::HebiContext;
+use crate::command_handler::openai_api::OpenAiApi;
+use hebi::prelude::*;
+use tracing::instrument;
+
+#[instrument(name = "hebi.ai.complete", skip_all)]
+pub async fn complete(
+    scope: Scope<'_>,
+    api: OpenAiApi,
+    ctx: HebiContext,
+) -> hebi::Result<Value<'_>> {
+    let prompt = scope.param::<Str>(0)?;
+
+    let response = api
+        .complete(ctx.channel_id, ctx.user_id, prompt.as_str())
+        .await
+        .map_err(|err| hebi::Error::User(format!("AI error: {err}").into()))?;
+
+    scope.new_string(response).into_value(scope.global())
+}
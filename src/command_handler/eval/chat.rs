@@ -0,0 +1,72 @@
+use super::context::HebiContext;
+use crate::{
+    command_handler::{platform_handler::PlatformHandler, twitch_api::model::AnnouncementColor},
+    platform::Permissions,
+};
+use hebi::prelude::*;
+use std::{str::FromStr, sync::Arc};
+use tracing::instrument;
+
+#[instrument(name = "hebi.chat.me", skip_all)]
+pub async fn me(
+    scope: Scope<'_>,
+    platform_handler: Arc<PlatformHandler>,
+    ctx: HebiContext,
+) -> hebi::Result<()> {
+    let message = scope.param::<Str>(0)?;
+
+    platform_handler
+        .send_action_to_channel(ctx.channel, message.to_string())
+        .await
+        .map_err(|err| hebi::Error::User(format!("Could not send message: {err}").into()))
+}
+
+#[instrument(name = "hebi.chat.announce", skip_all)]
+pub async fn announce(
+    scope: Scope<'_>,
+    platform_handler: Arc<PlatformHandler>,
+    ctx: HebiContext,
+) -> hebi::Result<()> {
+    check_moderator(&ctx, "announce")?;
+
+    let message = scope.param::<Str>(0)?;
+    let color = match scope.param::<Str>(1) {
+        Ok(color) => Some(
+            AnnouncementColor::from_str(color.as_str())
+                .map_err(|_| hebi::Error::User(format!("Invalid color `{color}`").into()))?,
+        ),
+        Err(_) => None,
+    };
+
+    platform_handler
+        .send_announcement(&ctx.channel, message.to_string(), color)
+        .await
+        .map_err(|err| hebi::Error::User(format!("Could not send announcement: {err}").into()))
+}
+
+#[instrument(name = "hebi.chat.shoutout", skip_all)]
+pub async fn shoutout(
+    scope: Scope<'_>,
+    platform_handler: Arc<PlatformHandler>,
+    ctx: HebiContext,
+) -> hebi::Result<()> {
+    check_moderator(&ctx, "shoutout")?;
+
+    let user_name = scope.param::<Str>(0)?;
+
+    platform_handler
+        .shoutout(&ctx.channel, user_name.as_str().trim_start_matches('@'))
+        .await
+        .map_err(|err| hebi::Error::User(format!("Could not send shoutout: {err}").into()))
+}
+
+/// These functions act with the bot's moderator privileges, so they are limited to moderators
+fn check_moderator(ctx: &HebiContext, name: &str) -> hebi::Result<()> {
+    if ctx.permissions < Some(Permissions::ChannelMod) {
+        return Err(hebi::Error::User(
+            format!("{name} can only be used by moderators on Twitch").into(),
+        ));
+    }
+
+    Ok(())
+}
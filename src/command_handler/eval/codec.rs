@@ -0,0 +1,32 @@
+use crate::command_handler::codec::Codec;
+use hebi::{prelude::*, Result};
+use std::str::FromStr;
+
+/// `codec.encode("morse", text)`
+pub fn encode(scope: Scope<'_>) -> Result<Str<'_>> {
+    let codec = get_codec(&scope)?;
+    let text = scope.param::<Str>(1)?;
+
+    let encoded = codec
+        .encode(text.as_str())
+        .map_err(|err| hebi::Error::User(err.into()))?;
+    Ok(scope.new_string(encoded))
+}
+
+/// `codec.decode("base64", text)`
+pub fn decode(scope: Scope<'_>) -> Result<Str<'_>> {
+    let codec = get_codec(&scope)?;
+    let text = scope.param::<Str>(1)?;
+
+    let decoded = codec
+        .decode(text.as_str())
+        .map_err(|err| hebi::Error::User(err.into()))?;
+    Ok(scope.new_string(decoded))
+}
+
+fn get_codec(scope: &Scope<'_>) -> Result<Codec> {
+    let name = scope.param::<Str>(0)?;
+
+    Codec::from_str(name.as_str())
+        .map_err(|_| hebi::Error::User(format!("Unknown codec {}", name.as_str()).into()))
+}
@@ -1,23 +1,48 @@
 use crate::{
     command_handler::{error::CommandError, ExecutionContext},
-    platform::PlatformContext,
+    database::DatabaseError,
+    platform::{ChannelIdentifier, Permissions, PlatformContext},
 };
 
 #[derive(Debug, Clone)]
 pub struct HebiContext {
     pub channel_id: u64,
+    pub user_id: u64,
+    pub payload: Option<serde_json::Value>,
+    /// The values of the arguments of commands with an args spec
+    pub params: Option<serde_json::Value>,
+    pub channel: ChannelIdentifier,
+    /// Only resolved on Twitch
+    pub permissions: Option<Permissions>,
+    pub timezone: Option<String>,
+    pub language: Option<String>,
 }
 
-impl<P: PlatformContext> TryFrom<&ExecutionContext<'_, P>> for HebiContext {
-    type Error = CommandError;
+impl HebiContext {
+    pub async fn new<P: PlatformContext>(
+        ctx: &ExecutionContext<'_, P>,
+    ) -> Result<Self, CommandError> {
+        let user_id = ctx.user.id;
+        let (timezone, language) = ctx
+            .db
+            .run(move |db| {
+                Ok::<_, DatabaseError>((db.get_timezone(user_id)?, db.get_language(user_id)?))
+            })
+            .await?;
 
-    fn try_from(ctx: &ExecutionContext<P>) -> Result<Self, Self::Error> {
         Ok(HebiContext {
             channel_id: ctx.channel_id.ok_or_else(|| {
                 CommandError::InvalidArgument(
                     "Hebi executing outside of a channel context".to_owned(),
                 )
             })?,
+            user_id: ctx.user.id,
+            payload: ctx.payload.clone(),
+            params: None,
+            channel: ctx.platform_ctx.get_channel(),
+            permissions: ctx.get_twitch_permissions().await?,
+            timezone,
+            language,
         })
     }
 }
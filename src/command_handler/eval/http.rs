@@ -1,12 +1,13 @@
+use crate::command_handler::http_fetch::HttpFetcher;
+use crate::command_handler::json_path;
 use ::serde::de::DeserializeSeed;
 use hebi::prelude::*;
 use http::Method;
-use reqwest::Client;
 use std::str::FromStr;
 use tracing::{debug, instrument, Span};
 
 #[instrument(name = "hebi.http.fetch", skip_all)]
-pub async fn request(scope: Scope<'_>, client: Client) -> hebi::Result<Value<'_>> {
+pub async fn request(scope: Scope<'_>, http_fetcher: HttpFetcher) -> hebi::Result<Value<'_>> {
     let span = Span::current();
 
     let url = scope.param::<Str>(0)?;
@@ -16,6 +17,8 @@ pub async fn request(scope: Scope<'_>, client: Client) -> hebi::Result<Value<'_>
 
     let raw_method = get_str_param(&request_params, &scope, "method", "GET");
     let format = get_str_param(&request_params, &scope, "format", "plain");
+    // Only used with the json format
+    let path = get_str_param(&request_params, &scope, "path", "$");
 
     let method =
         Method::from_str(raw_method.as_str()).map_err(|err| hebi::Error::User(Box::new(err)))?;
@@ -24,24 +27,23 @@ pub async fn request(scope: Scope<'_>, client: Client) -> hebi::Result<Value<'_>
     span.record("method", method.as_str());
     debug!("Sending {method} request to {url}");
 
-    let response = client
-        .request(method, url.as_str())
-        .send()
+    let text = http_fetcher
+        .fetch(method, url.as_str())
         .await
-        .map_err(|err| hebi::Error::User(Box::new(err)))?;
-
-    let text = response
-        .text()
-        .await
-        .map_err(|err| hebi::Error::User(Box::new(err)))?;
+        .map_err(|err| hebi::Error::User(format!("{err:#}").into()))?
+        .body;
 
     match format.as_str() {
         "plain" | "text" => scope.new_string(text).into_value(scope.global()),
         "json" => {
-            let mut json_deserializer = serde_json::Deserializer::from_str(&text);
+            let json: serde_json::Value = serde_json::from_str(&text)
+                .map_err(|err| hebi::Error::User(format!("Failed to parse json: {err}").into()))?;
+            let selected = json_path::select(&json, path.as_str())
+                .map_err(|err| hebi::Error::User(err.into()))?;
+
             let hebi_deserializer = ValueDeserializer::new(scope.global());
             let value = hebi_deserializer
-                .deserialize(&mut json_deserializer)
+                .deserialize(selected)
                 .map_err(|err| hebi::Error::User(format!("Deserialization error: {err}").into()))?;
             Ok(value)
         }
@@ -0,0 +1,25 @@
+use super::context::HebiContext;
+use crate::command_handler::image_api::ImageApi;
+use hebi::prelude::*;
+use tracing::instrument;
+
+#[instrument(name = "hebi.image.meme", skip_all)]
+pub async fn meme(scope: Scope<'_>, api: ImageApi, ctx: HebiContext) -> hebi::Result<Value<'_>> {
+    let url = scope.param::<Str>(0)?;
+    let top_text = scope.param::<Str>(1)?;
+    let bottom_text = scope
+        .param::<Str>(2)
+        .unwrap_or_else(|_| scope.new_string(""));
+
+    let link = api
+        .meme(
+            ctx.channel_id,
+            url.as_str(),
+            top_text.as_str(),
+            bottom_text.as_str(),
+        )
+        .await
+        .map_err(|err| hebi::Error::User(format!("Image error: {err}").into()))?;
+
+    scope.new_string(link).into_value(scope.global())
+}
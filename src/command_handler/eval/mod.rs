@@ -1,26 +1,43 @@
+mod ai;
+mod chat;
+mod codec;
 pub mod context;
 mod db;
 mod http;
+mod image;
 pub mod storage;
 mod utils;
 
 use self::{context::HebiContext, storage::ModuleStorage};
-use super::error::CommandError;
+use super::{
+    error::CommandError, http_fetch::HttpFetcher, i18n::Message, image_api::ImageApi,
+    openai_api::OpenAiApi, platform_handler::PlatformHandler,
+};
 use crate::database::Database;
+use ::serde::de::DeserializeSeed;
 use hebi::prelude::*;
-use reqwest::Client;
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 use tokio::time::timeout;
 use tracing::instrument;
 
 const TIMEOUT_SECS: u64 = 10;
 
-#[instrument(skip(native_modules, module_storage))]
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(
+    native_modules,
+    module_storage,
+    openai_api,
+    image_api,
+    platform_handler
+))]
 pub async fn eval_hebi(
     source: String,
     native_modules: &[NativeModule],
     module_storage: ModuleStorage,
     db: Database,
+    openai_api: Option<&OpenAiApi>,
+    image_api: Option<&ImageApi>,
+    platform_handler: &PlatformHandler,
     args: &[String],
     ctx: HebiContext,
 ) -> Result<Option<String>, CommandError> {
@@ -62,6 +79,67 @@ pub async fn eval_hebi(
 
     hebi.register(&db_module);
 
+    if let Some(openai_api) = openai_api {
+        let ai_module = NativeModule::builder("ai")
+            .async_function("complete", {
+                let openai_api = openai_api.clone();
+                let ctx = ctx.clone();
+                move |scope| ai::complete(scope, openai_api.clone(), ctx.clone())
+            })
+            .finish();
+
+        hebi.register(&ai_module);
+    }
+
+    if let Some(image_api) = image_api {
+        let image_module = NativeModule::builder("image")
+            .async_function("meme", {
+                let image_api = image_api.clone();
+                let ctx = ctx.clone();
+                move |scope| image::meme(scope, image_api.clone(), ctx.clone())
+            })
+            .finish();
+
+        hebi.register(&image_module);
+    }
+
+    let platform_handler = Arc::new(platform_handler.clone());
+    let chat_module = NativeModule::builder("chat")
+        .async_function("me", {
+            let platform_handler = platform_handler.clone();
+            let ctx = ctx.clone();
+            move |scope| chat::me(scope, platform_handler.clone(), ctx.clone())
+        })
+        .async_function("announce", {
+            let platform_handler = platform_handler.clone();
+            let ctx = ctx.clone();
+            move |scope| chat::announce(scope, platform_handler.clone(), ctx.clone())
+        })
+        .async_function("shoutout", {
+            let platform_handler = platform_handler.clone();
+            let ctx = ctx.clone();
+            move |scope| chat::shoutout(scope, platform_handler.clone(), ctx.clone())
+        })
+        .finish();
+
+    hebi.register(&chat_module);
+
+    if let Some(payload) = ctx.payload.clone() {
+        let payload_value = ValueDeserializer::new(hebi.global())
+            .deserialize(payload)
+            .map_err(|err| CommandError::GenericError(format!("Invalid payload: {err}")))?;
+
+        hebi.global().set(hebi.new_string("payload"), payload_value);
+    }
+
+    if let Some(params) = ctx.params.clone() {
+        let params_value = ValueDeserializer::new(hebi.global())
+            .deserialize(params)
+            .map_err(|err| CommandError::GenericError(format!("Invalid params: {err}")))?;
+
+        hebi.global().set(hebi.new_string("params"), params_value);
+    }
+
     hebi.global()
         .set(hebi.new_string("context"), hebi.new_instance(ctx).unwrap());
 
@@ -70,18 +148,16 @@ pub async fn eval_hebi(
     match timeout(Duration::from_secs(TIMEOUT_SECS), eval_future).await {
         Ok(Ok(value)) => Ok(Some(value.to_string())),
         Ok(Err(err)) => Err(CommandError::GenericError(err.to_string())),
-        Err(_) => Err(CommandError::GenericError("Execution timed out".to_owned())),
+        Err(_) => Err(CommandError::Localized(Message::ExecutionTimedOut)),
     }
 }
 
-pub fn create_native_modules(_: Database) -> Vec<NativeModule> {
+pub fn create_native_modules(_: Database, http_fetcher: HttpFetcher) -> Vec<NativeModule> {
     let mut modules = Vec::new();
 
-    let http_client = Client::new();
-
     let http = NativeModule::builder("http")
         .async_function("fetch", move |scope| {
-            http::request(scope, http_client.clone())
+            http::request(scope, http_fetcher.clone())
         })
         .finish();
     modules.push(http);
@@ -90,13 +166,22 @@ pub fn create_native_modules(_: Database) -> Vec<NativeModule> {
         .function("format", utils::format_string)
         .function("to_int", utils::to_int)
         .async_function("sleep", utils::sleep)
+        .function("json_path", utils::json_path)
         .finish();
     modules.push(utils);
 
+    let codec = NativeModule::builder("codec")
+        .function("encode", codec::encode)
+        .function("decode", codec::decode)
+        .finish();
+    modules.push(codec);
+
     let context_module = NativeModule::builder("context")
         .class::<HebiContext>("Context", |class| {
             class
                 .field("channel_id", |_, this| this.channel_id as i32)
+                .field("timezone", |_, this| this.timezone.clone())
+                .field("language", |_, this| this.language.clone())
                 .finish()
         })
         .finish();
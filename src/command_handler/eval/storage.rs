@@ -1,7 +1,7 @@
 use anyhow::anyhow;
 use arc_swap::ArcSwap;
 use hebi::prelude::*;
-use std::{collections::HashMap, env, fs, path::Path, process::Command, sync::Arc};
+use std::{collections::HashMap, fs, path::Path, process::Command, sync::Arc};
 use tempfile::{tempdir, TempDir};
 use tracing::info;
 
@@ -109,9 +109,9 @@ fn load_modules_from_path(path: &Path) -> anyhow::Result<Arc<HashMap<String, Str
     Ok(Arc::new(modules))
 }
 
-pub fn create_module_storage_from_env() -> anyhow::Result<ModuleStorage> {
-    match env::var("HEBI_MODULES_GIT_URL") {
-        Ok(git_url) => ModuleStorage::new(&git_url),
-        Err(_) => Ok(ModuleStorage::empty()),
+pub fn create_module_storage(git_url: Option<&str>) -> anyhow::Result<ModuleStorage> {
+    match git_url {
+        Some(git_url) => ModuleStorage::new(git_url),
+        None => Ok(ModuleStorage::empty()),
     }
 }
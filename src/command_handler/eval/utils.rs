@@ -1,3 +1,5 @@
+use crate::command_handler::json_path;
+use ::serde::de::DeserializeSeed;
 use hebi::{prelude::*, Result};
 use std::time::Duration;
 
@@ -23,3 +25,18 @@ pub async fn sleep(scope: Scope<'_>) -> Result<()> {
     tokio::time::sleep(Duration::from_millis(ms as u64)).await;
     Ok(())
 }
+
+/// `utils.json_path(text, "$.data[0].name")`, selects a value from JSON text
+pub fn json_path(scope: Scope<'_>) -> Result<Value<'_>> {
+    let text = scope.param::<Str>(0)?;
+    let path = scope.param::<Str>(1)?;
+
+    let json: serde_json::Value = serde_json::from_str(text.as_str())
+        .map_err(|err| hebi::Error::User(format!("Failed to parse json: {err}").into()))?;
+    let selected =
+        json_path::select(&json, path.as_str()).map_err(|err| hebi::Error::User(err.into()))?;
+
+    ValueDeserializer::new(scope.global())
+        .deserialize(selected)
+        .map_err(|err| hebi::Error::User(format!("Deserialization error: {err}").into()))
+}
@@ -0,0 +1,208 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context};
+use dashmap::DashMap;
+use reqwest::Client;
+use serde::Deserialize;
+
+const EXCHANGE_RATE_URL: &str = "https://v6.exchangerate-api.com/v6";
+const COINGECKO_URL: &str = "https://api.coingecko.com/api/v3";
+
+const RATES_CACHE_TTL: Duration = Duration::from_secs(3600);
+const PRICES_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Fiat currency conversion using ExchangeRate-API
+#[derive(Clone)]
+pub struct ExchangeRateApi {
+    client: Client,
+    api_key: Arc<String>,
+    rates_cache: Arc<DashMap<String, (Instant, HashMap<String, f64>)>>,
+}
+
+impl ExchangeRateApi {
+    pub fn init(api_key: String) -> Self {
+        Self {
+            client: Client::new(),
+            api_key: Arc::new(api_key),
+            rates_cache: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub async fn convert(&self, amount: f64, from: &str, to: &str) -> anyhow::Result<f64> {
+        let from = from.to_uppercase();
+        let to = to.to_uppercase();
+
+        let rates = self.get_rates(&from).await?;
+        let rate = rates
+            .get(&to)
+            .ok_or_else(|| anyhow!("unknown currency {to}"))?;
+
+        Ok(amount * rate)
+    }
+
+    async fn get_rates(&self, base: &str) -> anyhow::Result<HashMap<String, f64>> {
+        if let Some(entry) = self.rates_cache.get(base) {
+            let (fetched_at, rates) = entry.value();
+
+            if fetched_at.elapsed() < RATES_CACHE_TTL {
+                return Ok(rates.clone());
+            }
+        }
+
+        let response = self
+            .client
+            .get(format!("{EXCHANGE_RATE_URL}/{}/latest/{base}", self.api_key))
+            .send()
+            .await?;
+
+        tracing::info!("GET /latest/{}: {}", base, response.status());
+
+        let response = response.json::<ExchangeRateResponse>().await?;
+
+        match response.result.as_str() {
+            "success" => {
+                let rates = response.conversion_rates.unwrap_or_default();
+
+                self.rates_cache
+                    .insert(base.to_owned(), (Instant::now(), rates.clone()));
+
+                Ok(rates)
+            }
+            _ => Err(anyhow!(
+                "exchange rate error: {}",
+                response.error_type.unwrap_or_default()
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ExchangeRateResponse {
+    result: String,
+    error_type: Option<String>,
+    #[serde(rename = "conversion_rates")]
+    conversion_rates: Option<HashMap<String, f64>>,
+}
+
+/// Cryptocurrency prices using CoinGecko. The API key is optional
+#[derive(Clone)]
+pub struct CoinGeckoApi {
+    client: Client,
+    api_key: Option<Arc<String>>,
+    coin_ids_cache: Arc<DashMap<String, String>>, // Symbol to coin id
+    prices_cache: Arc<DashMap<(String, String), (Instant, CoinPrice)>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CoinPrice {
+    pub price: f64,
+    pub change_24h: Option<f64>,
+}
+
+impl CoinGeckoApi {
+    pub fn init(api_key: Option<String>) -> Self {
+        Self {
+            client: Client::new(),
+            api_key: api_key.map(Arc::new),
+            coin_ids_cache: Arc::new(DashMap::new()),
+            prices_cache: Arc::new(DashMap::new()),
+        }
+    }
+
+    async fn get<T: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> anyhow::Result<T> {
+        let mut request = self
+            .client
+            .get(format!("{COINGECKO_URL}{path}"))
+            .query(query);
+
+        if let Some(api_key) = &self.api_key {
+            request = request.header("x-cg-demo-api-key", api_key.as_str());
+        }
+
+        let response = request.send().await?;
+
+        tracing::info!("GET {}: {}", response.url().path(), response.status());
+
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    /// Resolves a coin symbol such as `btc` to the id of the coin with the highest market cap
+    async fn get_coin_id(&self, symbol: &str) -> anyhow::Result<String> {
+        let symbol = symbol.to_lowercase();
+
+        if let Some(id) = self.coin_ids_cache.get(&symbol) {
+            return Ok(id.clone());
+        }
+
+        let response: CoinSearchResponse = self.get("/search", &[("query", &symbol)]).await?;
+
+        let coin = response
+            .coins
+            .into_iter()
+            .find(|coin| coin.symbol.to_lowercase() == symbol || coin.id == symbol)
+            .ok_or_else(|| anyhow!("unknown coin {symbol}"))?;
+
+        self.coin_ids_cache.insert(symbol, coin.id.clone());
+
+        Ok(coin.id)
+    }
+
+    pub async fn get_price(&self, symbol: &str, vs_currency: &str) -> anyhow::Result<CoinPrice> {
+        let id = self.get_coin_id(symbol).await?;
+        let vs_currency = vs_currency.to_lowercase();
+
+        let key = (id.clone(), vs_currency.clone());
+
+        if let Some(entry) = self.prices_cache.get(&key) {
+            let (fetched_at, price) = entry.value();
+
+            if fetched_at.elapsed() < PRICES_CACHE_TTL {
+                return Ok(price.clone());
+            }
+        }
+
+        let mut response: HashMap<String, HashMap<String, f64>> = self
+            .get(
+                "/simple/price",
+                &[
+                    ("ids", &id),
+                    ("vs_currencies", &vs_currency),
+                    ("include_24hr_change", "true"),
+                ],
+            )
+            .await?;
+
+        let prices = response.remove(&id).context("coin missing from response")?;
+
+        let price = CoinPrice {
+            price: *prices
+                .get(&vs_currency)
+                .ok_or_else(|| anyhow!("unknown currency {vs_currency}"))?,
+            change_24h: prices.get(&format!("{vs_currency}_24h_change")).copied(),
+        };
+
+        self.prices_cache.insert(key, (Instant::now(), price.clone()));
+
+        Ok(price)
+    }
+}
+
+#[derive(Deserialize)]
+struct CoinSearchResponse {
+    coins: Vec<CoinSearchResult>,
+}
+
+#[derive(Deserialize)]
+struct CoinSearchResult {
+    id: String,
+    symbol: String,
+}
@@ -1,12 +1,23 @@
 use anyhow::anyhow;
+use futures::future::join_all;
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
+
+use crate::database::cache::TtlCache;
+
+const BASE_URL: &str = "https://finnhub.io/api/v1";
+/// The free tier allows 60 requests per minute, which watchlists would use up quickly
+const QUOTE_CACHE_TTL: Duration = Duration::from_secs(30);
+const MARKET_STATUS_CACHE_TTL: Duration = Duration::from_secs(60);
 
 #[derive(Clone)]
 pub struct FinnhubApi {
     client: Client,
     api_key: Arc<String>,
+    quote_cache: Arc<TtlCache<String, QuoteResponse>>,
+    market_status_cache: Arc<TtlCache<(), MarketStatus>>,
 }
 
 impl FinnhubApi {
@@ -14,14 +25,55 @@ impl FinnhubApi {
         Self {
             client: Client::new(),
             api_key: Arc::new(api_key),
+            quote_cache: Arc::new(TtlCache::new(QUOTE_CACHE_TTL)),
+            market_status_cache: Arc::new(TtlCache::new(MARKET_STATUS_CACHE_TTL)),
         }
     }
 
     pub async fn quote(&self, symbol: &str) -> anyhow::Result<QuoteResponse> {
+        let symbol = symbol.to_uppercase();
+        if let Some(quote) = self.quote_cache.get(&symbol) {
+            return Ok(quote);
+        }
+
+        let quote: QuoteResponse = self.get("quote", &[("symbol", &symbol)]).await?;
+        self.quote_cache.insert(symbol, quote.clone());
+
+        Ok(quote)
+    }
+
+    /// Finnhub doesn't have a batch endpoint, so the quotes are requested concurrently
+    pub async fn quotes(&self, symbols: &[String]) -> Vec<(String, anyhow::Result<QuoteResponse>)> {
+        let requests = symbols
+            .iter()
+            .map(|symbol| async move { (symbol.clone(), self.quote(symbol).await) });
+
+        join_all(requests).await
+    }
+
+    /// The trading session of US exchanges
+    pub async fn market_status(&self) -> anyhow::Result<MarketStatus> {
+        if let Some(status) = self.market_status_cache.get(&()) {
+            return Ok(status);
+        }
+
+        let status: MarketStatus = self
+            .get("stock/market-status", &[("exchange", "US")])
+            .await?;
+        self.market_status_cache.insert((), status.clone());
+
+        Ok(status)
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> anyhow::Result<T> {
         let response = self
             .client
-            .get("https://finnhub.io/api/v1/quote")
-            .query(&[("symbol", symbol)])
+            .get(format!("{BASE_URL}/{path}"))
+            .query(query)
             .header("X-Finnhub-Token", &*self.api_key)
             .send()
             .await?;
@@ -36,7 +88,7 @@ impl FinnhubApi {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct QuoteResponse {
     #[serde(rename = "c")]
     pub current_price: f32,
@@ -45,3 +97,67 @@ pub struct QuoteResponse {
     #[serde(rename = "dp")]
     pub percent_change: Option<f32>,
 }
+
+impl QuoteResponse {
+    /// Finnhub responds with zeroes instead of an error for symbols it doesn't know
+    pub fn is_empty(&self) -> bool {
+        self.current_price == 0.0 && self.change.is_none()
+    }
+}
+
+impl std::fmt::Display for QuoteResponse {
+    /// Such as `170.25 (+1.23%)`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({:+.2}%)",
+            self.current_price,
+            self.percent_change.unwrap_or(0.0)
+        )
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MarketStatus {
+    /// `pre-market`, `regular` or `post-market`, missing when the market is closed
+    pub session: Option<String>,
+}
+
+impl MarketStatus {
+    /// Shown next to prices outside of regular trading hours, which only move on a low volume
+    pub fn extended_hours(&self) -> Option<&'static str> {
+        match self.session.as_deref() {
+            Some("pre-market") => Some("pre-market"),
+            Some("post-market") => Some("after hours"),
+            _ => None,
+        }
+    }
+}
+
+/// Symbols of other exchanges have a suffix, such as `BMW.DE`
+pub fn is_us_symbol(symbol: &str) -> bool {
+    !symbol.contains('.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MarketStatus, QuoteResponse};
+
+    #[test]
+    fn formats_quotes() {
+        let quote = QuoteResponse {
+            current_price: 170.25,
+            change: Some(2.07),
+            percent_change: Some(1.2345),
+        };
+        assert_eq!(quote.to_string(), "170.25 (+1.23%)");
+
+        let status: MarketStatus =
+            serde_json::from_str(r#"{"isOpen": true, "session": "pre-market"}"#).unwrap();
+        assert_eq!(status.extended_hours(), Some("pre-market"));
+
+        let status: MarketStatus =
+            serde_json::from_str(r#"{"isOpen": false, "session": null}"#).unwrap();
+        assert_eq!(status.extended_hours(), None);
+    }
+}
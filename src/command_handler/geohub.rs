@@ -1,14 +1,23 @@
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::anyhow;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use reqwest::Client;
 use serde::Deserialize;
 use tokio::sync::RwLock;
 use tracing::{error, info, instrument, warn};
 
-use crate::database::Database;
+use crate::database::{
+    models::{GeohubLink, GeohubResult},
+    Database,
+};
 
 use super::platform_handler::PlatformHandler;
+use super::status::StatusTracker;
 
 const BASE_URL: &str = "https://geohub.vercel.app";
 
@@ -56,72 +65,362 @@ pub struct LeaderboardEntry {
     pub user_name: String,
 }
 
-pub async fn start_listener(
+/// Calendar periods of the channel leaderboards, days start at midnight UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    /// The first day of the period that `today` is in, weeks start on Monday
+    pub fn start(self, today: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => today,
+            Period::Week => {
+                today - chrono::Duration::days(today.weekday().num_days_from_monday().into())
+            }
+            Period::Month => today.with_day(1).expect("Every month has a first day"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AggregatedScore {
+    pub geohub_name: String,
+    pub total_points: u64,
+    /// Seconds
+    pub total_time: u64,
+    /// Daily challenges played
+    pub days: u32,
+}
+
+/// Sums up the results of every user, from the highest score to the lowest.
+/// Equal scores are ordered by who was faster.
+pub fn aggregate_results(results: Vec<GeohubResult>) -> Vec<AggregatedScore> {
+    let mut scores: HashMap<String, AggregatedScore> = HashMap::new();
+
+    for result in results {
+        let score = scores
+            .entry(result.geohub_name.clone())
+            .or_insert_with(|| AggregatedScore {
+                geohub_name: result.geohub_name,
+                total_points: 0,
+                total_time: 0,
+                days: 0,
+            });
+        score.total_points += u64::from(result.total_points);
+        score.total_time += u64::from(result.total_time);
+        score.days += 1;
+    }
+
+    let mut scores = scores.into_values().collect::<Vec<_>>();
+    scores.sort_by(|a, b| {
+        b.total_points
+            .cmp(&a.total_points)
+            .then(a.total_time.cmp(&b.total_time))
+    });
+    scores
+}
+
+/// Announcements that were due before the bot started are skipped,
+/// so that restarts don't announce the same winner again
+fn is_announcement_due(
+    announce_time: NaiveTime,
+    now: NaiveDateTime,
+    started_at: NaiveDateTime,
+    last_announced: Option<NaiveDate>,
+) -> bool {
+    let announce_at = now.date().and_time(announce_time);
+
+    announce_at <= now && announce_at > started_at && last_announced != Some(now.date())
+}
+
+/// The first leaderboard that is fetched successfully is only used as a baseline for
+/// the following ones, so GeoHub being down at startup doesn't stop the bot
+pub fn start_listener(
     db: Database,
     platform_handler: Arc<RwLock<PlatformHandler>>,
     interval: Duration,
     client: GeohubClient,
-) -> anyhow::Result<()> {
-    let mut last_leaderboard = client.get_leaderboard(200).await?;
+    status: StatusTracker,
+) {
+    let mut last_leaderboard: Option<DailyLeaderboard> = None;
 
     let mut sent_notifications = HashSet::new();
 
+    let started_at = Utc::now().naive_utc();
+    let mut announced_days = HashMap::new();
+
     tokio::spawn(async move {
         loop {
-            tokio::time::sleep(interval).await;
             info!("Fetching new GeoHub leaderboard");
 
-            let links = db.get_geohub_links().expect("DB error");
+            let links = match db.run(|db| db.get_geohub_links()).await {
+                Ok(links) => links,
+                Err(err) => {
+                    error!("Could not get GeoHub links: {err}");
+                    tokio::time::sleep(interval).await;
+                    continue;
+                }
+            };
             match client.get_leaderboard(200).await {
                 Ok(new_leaderboard) => {
-                    for new_entry in &new_leaderboard.today {
-                        if sent_notifications.contains(&new_entry.id) {
-                            warn!("Skipipng duplicate notification {new_entry:?}");
-                            continue;
-                        }
+                    status.clear_degraded("geohub");
 
-                        // A new entry
-                        if !last_leaderboard
-                            .today
-                            .iter()
-                            .any(|entry| entry.user_id == new_entry.user_id)
-                        {
-                            let entry_name = new_entry.user_name.to_lowercase();
+                    save_results(&db, &links, &new_leaderboard).await;
+
+                    if let Some(last_leaderboard) = &last_leaderboard {
+                        for new_entry in &new_leaderboard.today {
+                            if sent_notifications.contains(&new_entry.id) {
+                                warn!("Skipipng duplicate notification {new_entry:?}");
+                                continue;
+                            }
 
-                            if let Some(link) = links
+                            // A new entry
+                            if !last_leaderboard
+                                .today
                                 .iter()
-                                .find(|link| link.geohub_name.to_lowercase() == entry_name)
+                                .any(|entry| entry.user_id == new_entry.user_id)
                             {
-                                let channel = db
-                                    .get_channel_by_id(link.channel_id)
-                                    .expect("DB error")
-                                    .expect("Linked to an invalid channel");
-
-                                let message = format!("{} has completed the GeoHub daily challenge with the score of {} points!", new_entry.user_name, new_entry.total_points);
-                                match platform_handler
-                                    .read()
-                                    .await
-                                    .send_to_channel(channel.get_identifier(), message)
-                                    .await
+                                let entry_name = new_entry.user_name.to_lowercase();
+
+                                if let Some(link) = links
+                                    .iter()
+                                    .find(|link| link.geohub_name.to_lowercase() == entry_name)
                                 {
-                                    Ok(()) => {
-                                        sent_notifications.insert(new_entry.id.clone());
-                                    }
-                                    Err(err) => {
-                                        error!("Could not send notification: {err}");
+                                    let channel_id = link.channel_id;
+                                    let channel = match db
+                                        .run(move |db| db.get_channel_by_id(channel_id))
+                                        .await
+                                    {
+                                        Ok(Some(channel)) => channel,
+                                        Ok(None) => {
+                                            warn!(
+                                                "GeoHub link to unknown channel {}",
+                                                link.channel_id
+                                            );
+                                            continue;
+                                        }
+                                        Err(err) => {
+                                            error!("Could not get channel: {err}");
+                                            continue;
+                                        }
+                                    };
+
+                                    let message = format!("{} has completed the GeoHub daily challenge with the score of {} points!", new_entry.user_name, new_entry.total_points);
+                                    match platform_handler
+                                        .read()
+                                        .await
+                                        .send_to_channel(channel.get_identifier(), message)
+                                        .await
+                                    {
+                                        Ok(()) => {
+                                            sent_notifications.insert(new_entry.id.clone());
+                                        }
+                                        Err(err) => {
+                                            error!("Could not send notification: {err}");
+                                        }
                                     }
                                 }
                             }
                         }
                     }
-                    last_leaderboard = new_leaderboard;
+                    last_leaderboard = Some(new_leaderboard);
                 }
                 Err(err) => {
                     error!("Could not fetch leaderboard: {err}");
+                    status.set_degraded("geohub", err);
                 }
             }
+
+            announce_winners(
+                &db,
+                &platform_handler,
+                &links,
+                started_at,
+                &mut announced_days,
+            )
+            .await;
+
+            tokio::time::sleep(interval).await;
         }
     });
+}
+
+/// Keeps today's scores of the linked users for the weekly and monthly leaderboards
+async fn save_results(db: &Database, links: &[GeohubLink], leaderboard: &DailyLeaderboard) {
+    let today = Utc::now().date_naive();
 
-    Ok(())
+    let results = leaderboard
+        .today
+        .iter()
+        .filter(|entry| {
+            let entry_name = entry.user_name.to_lowercase();
+            links
+                .iter()
+                .any(|link| link.geohub_name.to_lowercase() == entry_name)
+        })
+        .map(|entry| GeohubResult {
+            geohub_name: entry.user_name.clone(),
+            date: today,
+            total_points: entry.total_points,
+            total_time: entry.total_time,
+        })
+        .collect::<Vec<_>>();
+
+    if !results.is_empty() {
+        if let Err(err) = db.run(move |db| db.save_geohub_results(&results)).await {
+            error!("Could not save GeoHub results: {err}");
+        }
+    }
+}
+
+/// Sends the best score of the day to the channels that have `geohub_announce_time` set
+async fn announce_winners(
+    db: &Database,
+    platform_handler: &RwLock<PlatformHandler>,
+    links: &[GeohubLink],
+    started_at: NaiveDateTime,
+    announced_days: &mut HashMap<u64, NaiveDate>,
+) {
+    let now = Utc::now().naive_utc();
+    let channel_ids = links
+        .iter()
+        .map(|link| link.channel_id)
+        .collect::<HashSet<_>>();
+
+    for channel_id in channel_ids {
+        let announce_time = match db.run(move |db| db.get_channel_settings(channel_id)).await {
+            Ok(settings) => settings.geohub_announce_time,
+            Err(err) => {
+                error!("Could not get the settings of channel {channel_id}: {err}");
+                continue;
+            }
+        };
+
+        match announce_time {
+            Some(announce_time)
+                if is_announcement_due(
+                    announce_time,
+                    now,
+                    started_at,
+                    announced_days.get(&channel_id).copied(),
+                ) => {}
+            _ => continue,
+        }
+        announced_days.insert(channel_id, now.date());
+
+        let date = now.date();
+        let results = match db
+            .run(move |db| db.get_geohub_results(channel_id, date))
+            .await
+        {
+            Ok(results) => results,
+            Err(err) => {
+                error!("Could not get GeoHub results: {err}");
+                continue;
+            }
+        };
+
+        // Nobody has played today
+        let winner = match aggregate_results(results).into_iter().next() {
+            Some(winner) => winner,
+            None => continue,
+        };
+
+        let channel = match db.run(move |db| db.get_channel_by_id(channel_id)).await {
+            Ok(Some(channel)) => channel,
+            Ok(None) => continue,
+            Err(err) => {
+                error!("Could not get channel {channel_id}: {err}");
+                continue;
+            }
+        };
+
+        let message = format!(
+            "{} has won today's GeoHub daily challenge with {} points!",
+            winner.geohub_name, winner.total_points
+        );
+        if let Err(err) = platform_handler
+            .read()
+            .await
+            .send_to_channel(channel.get_identifier(), message)
+            .await
+        {
+            error!("Could not announce the GeoHub winner: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{aggregate_results, is_announcement_due, Period};
+    use crate::database::models::GeohubResult;
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn result(name: &str, day: u32, total_points: u32, total_time: u32) -> GeohubResult {
+        GeohubResult {
+            geohub_name: name.to_owned(),
+            date: NaiveDate::from_ymd_opt(2023, 10, day).unwrap(),
+            total_points,
+            total_time,
+        }
+    }
+
+    #[test]
+    fn period_start() {
+        // A Thursday
+        let today = NaiveDate::from_ymd_opt(2023, 10, 26).unwrap();
+
+        assert_eq!(Period::Day.start(today), today);
+        assert_eq!(
+            Period::Week.start(today),
+            NaiveDate::from_ymd_opt(2023, 10, 23).unwrap()
+        );
+        assert_eq!(
+            Period::Month.start(today),
+            NaiveDate::from_ymd_opt(2023, 10, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn aggregates_results() {
+        let scores = aggregate_results(vec![
+            result("alice", 23, 20000, 300),
+            result("bob", 23, 15000, 200),
+            result("bob", 24, 10000, 200),
+            result("carol", 24, 25000, 500),
+        ]);
+
+        let names = scores
+            .iter()
+            .map(|score| score.geohub_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["bob", "carol", "alice"]);
+        assert_eq!(scores[0].total_points, 25000);
+        assert_eq!(scores[0].days, 2);
+    }
+
+    #[test]
+    fn announcement_schedule() {
+        let day = NaiveDate::from_ymd_opt(2023, 10, 26).unwrap();
+        let time = NaiveTime::from_hms_opt(20, 0, 0).unwrap();
+        let started_at = day.and_hms_opt(12, 0, 0).unwrap();
+
+        let before = day.and_hms_opt(19, 59, 0).unwrap();
+        let after = day.and_hms_opt(20, 1, 0).unwrap();
+
+        assert!(!is_announcement_due(time, before, started_at, None));
+        assert!(is_announcement_due(time, after, started_at, None));
+        assert!(!is_announcement_due(time, after, started_at, Some(day)));
+        // Started after the announcement was due
+        assert!(!is_announcement_due(
+            time,
+            after,
+            day.and_hms_opt(20, 0, 30).unwrap(),
+            None
+        ));
+    }
 }
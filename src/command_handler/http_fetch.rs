@@ -0,0 +1,394 @@
+//! Requests to arbitrary URLs made by commands, with the `get` helper and `http.fetch` in Hebi.
+//!
+//! The hosts are checked against the configured allow and deny lists, and private addresses
+//! are blocked by resolving hosts with [`PublicResolver`], which also applies to redirects.
+//! Responses are limited in size and can be cached for a short time.
+//! Outgoing webhooks use the same address checks with [`public_client`].
+use super::debug_report::{self, TraceKind};
+use crate::config::HttpFetchConfig;
+use crate::database::cache::TtlCache;
+use anyhow::{anyhow, Context};
+use http::{Method, StatusCode};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{redirect, Client, Response, Url};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const MAX_REDIRECTS: usize = 5;
+
+#[derive(Debug, Clone)]
+pub struct FetchedResponse {
+    pub status: StatusCode,
+    pub content_type: Option<String>,
+    pub body: String,
+}
+
+impl FetchedResponse {
+    /// Plain text or JSON, including types such as `application/ld+json`
+    pub fn is_text(&self) -> bool {
+        self.content_type
+            .as_deref()
+            .map_or(false, is_text_content_type)
+    }
+}
+
+#[derive(Clone)]
+pub struct HttpFetcher {
+    client: Client,
+    config: Arc<HttpFetchConfig>,
+    /// Only set when caching is enabled
+    cache: Option<Arc<TtlCache<String, FetchedResponse>>>,
+}
+
+impl HttpFetcher {
+    pub fn new(config: &HttpFetchConfig) -> anyhow::Result<Self> {
+        let config = Arc::new(config.clone());
+
+        let redirect_config = config.clone();
+        let redirect_policy = redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if let Err(err) = check_url(&redirect_config, attempt.url()) {
+                attempt.error(err)
+            } else {
+                attempt.follow()
+            }
+        });
+
+        let mut builder = Client::builder()
+            .timeout(Duration::from_secs(config.timeout))
+            .redirect(redirect_policy);
+
+        if !config.allow_private_addresses {
+            // A proxy would resolve the hosts instead
+            builder = builder.dns_resolver(Arc::new(PublicResolver)).no_proxy();
+        }
+
+        let cache = match config.cache_seconds {
+            0 => None,
+            seconds => Some(Arc::new(TtlCache::new(Duration::from_secs(seconds)))),
+        };
+
+        Ok(Self {
+            client: builder.build().context("Could not build HTTP client")?,
+            config,
+            cache,
+        })
+    }
+
+    /// Responses with an error status are returned as well
+    pub async fn fetch(&self, method: Method, url: &str) -> anyhow::Result<FetchedResponse> {
+        let url = Url::parse(url).with_context(|| format!("invalid URL {url}"))?;
+        check_url(&self.config, &url).map_err(|err| anyhow!(err))?;
+
+        let cache = self.cache.as_ref().filter(|_| method == Method::GET);
+        if let Some(response) = cache.and_then(|cache| cache.get(&url.to_string())) {
+            return Ok(response);
+        }
+
+        let started_at = Instant::now();
+        let result = self.send(method.clone(), url.clone()).await;
+        debug_report::record(
+            TraceKind::Http,
+            method.as_str(),
+            url.as_str(),
+            match &result {
+                Ok(response) => Ok(response.status.to_string()),
+                Err(err) => Err(format!("{err:#}")),
+            },
+            started_at,
+        );
+        let response = result?;
+
+        if let Some(cache) = cache {
+            if response.status.is_success() {
+                cache.insert(url.to_string(), response.clone());
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Binary GET requests such as images, with their own size limit. Responses are not cached
+    /// and an error status is an error
+    pub async fn download(&self, url: &str, max_size: u64) -> anyhow::Result<Vec<u8>> {
+        let url = Url::parse(url).with_context(|| format!("invalid URL {url}"))?;
+        check_url(&self.config, &url).map_err(|err| anyhow!(err))?;
+
+        let started_at = Instant::now();
+        let result = async {
+            let response = self.client.get(url.clone()).send().await?;
+            let response = response.error_for_status()?;
+            read_body(response, max_size).await
+        }
+        .await;
+        debug_report::record(
+            TraceKind::Http,
+            Method::GET.as_str(),
+            url.as_str(),
+            match &result {
+                Ok(body) => Ok(format!("{} bytes", body.len())),
+                Err(err) => Err(format!("{err:#}")),
+            },
+            started_at,
+        );
+
+        result
+    }
+
+    async fn send(&self, method: Method, url: Url) -> anyhow::Result<FetchedResponse> {
+        let response = self.client.request(method, url).send().await?;
+
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+
+        let body = read_body(response, self.config.max_response_size).await?;
+
+        Ok(FetchedResponse {
+            status,
+            content_type,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        })
+    }
+}
+
+/// Stops reading the response once it's over the size limit
+async fn read_body(mut response: Response, max_size: u64) -> anyhow::Result<Vec<u8>> {
+    let too_large = || anyhow!("the response is larger than {max_size} bytes");
+
+    if response
+        .content_length()
+        .map_or(false, |length| length > max_size)
+    {
+        return Err(too_large());
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if (body.len() + chunk.len()) as u64 > max_size {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body)
+}
+
+/// A client for requests that always have to go to public addresses, such as outgoing webhooks.
+/// Redirects are not followed, so only the URL itself has to be checked with [`check_public_url`]
+pub fn public_client(timeout: Duration) -> anyhow::Result<Client> {
+    Client::builder()
+        .timeout(timeout)
+        .redirect(redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        .no_proxy()
+        .build()
+        .context("Could not build HTTP client")
+}
+
+/// Only allows http(s) URLs whose host isn't a private address. Hosts that resolve to one
+/// are rejected by [`PublicResolver`] when the request is made
+pub fn check_public_url(url: &Url) -> Result<(), String> {
+    check_url(&HttpFetchConfig::default(), url)
+}
+
+/// Resolves hosts to their public addresses only, so that hosts which point to
+/// internal services can't be requested
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_address(addr.ip()))
+                .collect();
+
+            if addrs.is_empty() {
+                return Err(
+                    format!("{} does not resolve to a public address", name.as_str()).into(),
+                );
+            }
+
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+fn check_url(config: &HttpFetchConfig, url: &Url) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("unsupported URL scheme {}", url.scheme()));
+    }
+
+    let host = url
+        .host_str()
+        .ok_or_else(|| "the URL has no host".to_owned())?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_lowercase();
+    let ip = host.parse::<IpAddr>().ok();
+
+    let is_denied = config
+        .denied_hosts
+        .iter()
+        .any(|pattern| matches_host(pattern, &host));
+    let is_allowed = config.allowed_hosts.is_empty()
+        || config
+            .allowed_hosts
+            .iter()
+            .any(|pattern| matches_host(pattern, &host));
+
+    if is_denied || !is_allowed {
+        return Err(format!("requests to {host} are not allowed"));
+    }
+
+    // Addresses in the URL are not resolved, so they have to be checked here
+    if let Some(ip) = ip {
+        if !config.allow_private_addresses && !is_public_address(ip) {
+            return Err(format!("requests to {host} are not allowed"));
+        }
+    }
+
+    Ok(())
+}
+
+/// The host itself or any of its subdomains
+fn matches_host(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim().trim_start_matches("*.").to_lowercase();
+
+    host == pattern
+        || host
+            .strip_suffix(&pattern)
+            .map_or(false, |prefix| prefix.ends_with('.'))
+}
+
+fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "This network", shared address space (CGNAT), benchmarking and reserved ranges
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 198 && (18..20).contains(&b))
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first_segment = ip.segments()[0];
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local, link-local and documentation ranges
+        || (first_segment & 0xfe00) == 0xfc00
+        || (first_segment & 0xffc0) == 0xfe80
+        || (first_segment == 0x2001 && ip.segments()[1] == 0xdb8))
+}
+
+fn is_text_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+
+    matches!(
+        mime.as_str(),
+        "text/plain" | "application/json" | "text/json"
+    ) || mime.ends_with("+json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_public_url, check_url, is_public_address, is_text_content_type, matches_host,
+    };
+    use crate::config::HttpFetchConfig;
+    use reqwest::Url;
+
+    fn check(config: &HttpFetchConfig, url: &str) -> bool {
+        check_url(config, &Url::parse(url).unwrap()).is_ok()
+    }
+
+    #[test]
+    fn blocks_private_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_address(ip.parse().unwrap()), "{ip}");
+        }
+
+        assert!(is_public_address("1.1.1.1".parse().unwrap()));
+        assert!(is_public_address("2606:4700::1111".parse().unwrap()));
+    }
+
+    #[test]
+    fn checks_urls() {
+        let config = HttpFetchConfig::default();
+        assert!(check(&config, "https://example.com/api"));
+        assert!(!check(&config, "http://127.0.0.1:8080"));
+        assert!(!check(&config, "http://[::1]/"));
+        assert!(!check(&config, "file:///etc/passwd"));
+        assert!(check_public_url(&Url::parse("https://example.com/hook").unwrap()).is_ok());
+        assert!(check_public_url(&Url::parse("http://10.0.0.1/hook").unwrap()).is_err());
+
+        let config = HttpFetchConfig {
+            allowed_hosts: vec!["example.com".to_owned()],
+            denied_hosts: vec!["internal.example.com".to_owned()],
+            allow_private_addresses: true,
+            ..Default::default()
+        };
+        assert!(check(&config, "https://api.example.com"));
+        assert!(!check(&config, "https://notexample.com"));
+        assert!(!check(&config, "https://a.internal.example.com"));
+        assert!(!check(&config, "http://127.0.0.1"));
+    }
+
+    #[test]
+    fn matches_subdomains() {
+        assert!(matches_host("example.com", "example.com"));
+        assert!(matches_host("*.Example.com", "api.example.com"));
+        assert!(!matches_host("example.com", "badexample.com"));
+    }
+
+    #[test]
+    fn accepts_text_and_json() {
+        assert!(is_text_content_type("application/json; charset=utf-8"));
+        assert!(is_text_content_type("application/vnd.api+json"));
+        assert!(is_text_content_type("text/plain"));
+        assert!(!is_text_content_type("text/html"));
+    }
+}
@@ -0,0 +1,1665 @@
+use std::str::FromStr;
+use strum::EnumString;
+
+/// Languages that builtin responses are translated to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumString, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Uk,
+}
+
+impl Language {
+    /// Only the primary subtag of codes such as `uk-UA` is used
+    pub fn from_code(code: &str) -> Option<Self> {
+        let primary = code.split('-').next()?.to_lowercase();
+        Language::from_str(&primary).ok()
+    }
+
+    /// The user's language takes priority over the channel's, unsupported languages are skipped
+    pub fn resolve(user_language: Option<&str>, channel_language: Option<&str>) -> Self {
+        user_language
+            .and_then(Language::from_code)
+            .or_else(|| channel_language.and_then(Language::from_code))
+            .unwrap_or_default()
+    }
+}
+
+/// Accepts ISO 639 codes with an optional region, such as `uk` or `pt-BR`
+pub fn is_language_code(code: &str) -> bool {
+    let mut parts = code.split('-');
+
+    let language_valid = parts.next().map_or(false, |language| {
+        (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_alphabetic())
+    });
+    let region_valid = parts.next().map_or(true, |region| {
+        region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic())
+    });
+
+    language_valid && region_valid && parts.next().is_none()
+}
+
+/// User-facing messages of builtin commands and command errors
+#[derive(Debug, Clone, Copy)]
+pub enum Message<'a> {
+    MissingArgument(&'a str),
+    InvalidArgument(&'a str),
+    NoPermissions,
+    ConfigurationError(&'a str),
+    DatabaseError,
+    CommandAdded,
+    CommandExists,
+    CommandRemoved,
+    CommandUpdated(&'a str),
+    CommandDoesNotExist(&'a str),
+    CommandNotFound,
+    CommandTriggers(&'a str),
+    CommandHasNoTriggers,
+    CommandTriggersUpdated,
+    CommandModeUpdated,
+    CommandChannelsUpdated,
+    CommandChannelsReset,
+    CommandHasNoTests,
+    CommandTestsPassed(usize),
+    CommandTestsFailed(&'a str),
+    TimezoneSet(&'a str),
+    TimezoneIs(&'a str),
+    TimezoneNotSet,
+    LanguageSet(&'a str),
+    LanguageIs(&'a str),
+    LanguageNotSet,
+    UnitsSet(&'a str),
+    UnitsAre(&'a str),
+    UnitsNotSet,
+    SupibotIdSet(&'a str),
+    SupibotIdIs(&'a str),
+    SupibotIdNotSet,
+    AiEnabled,
+    AiDisabled,
+    AiConversationForgotten,
+    AiNoConversation,
+    UserIgnored(&'a str),
+    UserUnignored(&'a str),
+    IgnoredUsers(&'a str),
+    NoIgnoredUsers,
+    JoinedChannel(&'a str),
+    LeavingChannel,
+    InvalidSubcommand(&'a str),
+    ChannelCannotBlockUsers,
+    CannotBlockYourself,
+    UserBlocked(&'a str),
+    UserBlockedGlobally(&'a str),
+    UserUnblocked(&'a str),
+    UserUnblockedGlobally(&'a str),
+    HebiModulesUpdated(&'a str),
+    HebiModulesUpToDate,
+    HebiModulesReloadFailed(&'a str),
+    ConfigReloaded,
+    ConfigReloadedRestartRequired,
+    ConfigReloadFailed(&'a str),
+    SafeModeEntered,
+    SafeModeExited,
+    SafeModeEnabled,
+    SafeModeDisabled,
+    WhoAmI {
+        user: &'a str,
+        identifier: &'a str,
+        channel: &'a str,
+        permissions: &'a str,
+    },
+    PreviousNames(&'a str),
+    OnlyInChannel,
+    UserNotKnown(&'a str),
+    GeohubNoScores,
+    GeohubDailyScores(&'a str),
+    GeohubWeeklyScores(&'a str),
+    GeohubMonthlyScores(&'a str),
+    GeohubPeriodScore {
+        name: &'a str,
+        points: u64,
+        days: u32,
+    },
+    GeohubLinked,
+    GeohubUnlinked,
+    GeohubNotLinked,
+    GeohubUserNotLinked,
+    GeohubNoLinks,
+    GeohubLinks(&'a str),
+    TwitchNotConfigured,
+    OnlyOnTwitch(&'a str),
+    StreamerNotAuthenticated,
+    StreamerNotAuthenticatedRewards,
+    InvalidAction(&'a str),
+    EventSubTriggerAdded,
+    EventSubTriggerRemoved,
+    EventSubTriggerPaused(&'a str),
+    EventSubTriggerResumed(&'a str),
+    EventSubTriggerResubscribed(&'a str),
+    EventSubNoTriggers,
+    EventSubNoTrigger(&'a str),
+    EventSubSubscriptionActive,
+    EventSubSubscriptionRevoked(&'a str),
+    EventSubSubscriptionMissing,
+    EventSubSubscriptionPaused,
+    EventSubSubscriptionFailed(&'a str),
+    EventSubResubscribeFailed(&'a str),
+    EventSubNoMatchingSubscription,
+    EventSubInvalidType(&'a str),
+    EventSubRewardNotFound(&'a str),
+    FeatureState {
+        feature: &'a str,
+        enabled: bool,
+        is_default: bool,
+    },
+    FeatureEnabled(&'a str),
+    FeatureDisabled(&'a str),
+    FeatureResetEnabled(&'a str),
+    FeatureResetDisabled(&'a str),
+    UnknownFeature {
+        feature: &'a str,
+        features: &'a str,
+    },
+    NoNamesSeen(&'a str),
+    KnownNames {
+        user: &'a str,
+        names: &'a str,
+    },
+    MoreNames(usize),
+    UserSentMessages {
+        user: &'a str,
+        count: i64,
+    },
+    YouSentMessages(i64),
+    SeenOptedOut(&'a str),
+    SeenOptedIn(&'a str),
+    UserOptedOut {
+        user: &'a str,
+        command: &'a str,
+    },
+    FirstSeen {
+        user: &'a str,
+        ago: &'a str,
+        text: &'a str,
+    },
+    LastSeen {
+        user: &'a str,
+        ago: &'a str,
+        text: &'a str,
+    },
+    NotSeen(&'a str),
+    ChatLogsDisabled,
+    MarkovOptedOut,
+    MarkovOptedIn,
+    MarkovPurged(usize),
+    MarkovNoMessages,
+    MarkovDisabled,
+    LinkOnlyOnWebsite(&'a str),
+    LinkAlreadyLinked(&'a str),
+    LinkCodeSent {
+        code: &'a str,
+        identity: &'a str,
+    },
+    LinkInvalidCode,
+    LinkWrongAccount(&'a str),
+    LinkUserMissing,
+    LinkConfirmRequired,
+    LinkNothingToConfirm,
+    LinkCompleted,
+    LinkSameUser,
+    LinkConflict(&'a str),
+    InvalidCommandMode(&'a str),
+    GlobalCommandAdded(&'a str),
+    GlobalCommandExists(&'a str),
+    GlobalCommandUpdated(&'a str),
+    GlobalCommandRemoved(&'a str),
+    GlobalCommandModeUpdated {
+        name: &'a str,
+        mode: &'a str,
+    },
+    GlobalCommandNotFound(&'a str),
+    GlobalCommands(&'a str),
+    NoGlobalCommands,
+    PersonalCommandReserved(&'a str),
+    PersonalCommandLimit(i64),
+    PersonalCommandAdded(&'a str),
+    PersonalCommandExists(&'a str),
+    PersonalCommandUpdated(&'a str),
+    PersonalCommandRemoved(&'a str),
+    PersonalCommandModeUpdated {
+        name: &'a str,
+        mode: &'a str,
+    },
+    PersonalCommandNotFound(&'a str),
+    PersonalCommands {
+        names: &'a str,
+        count: usize,
+        limit: i64,
+    },
+    NoPersonalCommands,
+    SafeModeCommandsDisabled,
+    MarkerDescriptionTooLong(usize),
+    MarkerFailed,
+    MarkerCreated(&'a str),
+    HighlightSavedAt(&'a str),
+    HighlightSaved,
+    LastfmNotConfigured,
+    LastfmNotLinked,
+    LastfmUserNotLinked(&'a str),
+    CompatNoSharedArtists {
+        user: &'a str,
+        score: u32,
+    },
+    CompatSharedArtists {
+        user: &'a str,
+        score: u32,
+        artists: &'a str,
+    },
+    StocksNotConfigured,
+    QuoteUnavailable(&'a str),
+    PortfolioSummary {
+        quotes: &'a str,
+        gainers: u32,
+        losers: u32,
+    },
+    Watching(&'a str),
+    WatchlistEmpty,
+    WatchlistEmptyHint,
+    WatchlistCleared,
+    WatchlistFull(usize),
+    UnknownSymbol(&'a str),
+    NotAStockSymbol(&'a str),
+    WatchUnknownSubcommand(&'a str),
+    CurrentTime(&'a str),
+    CurrentTimeFor {
+        time: &'a str,
+        user: &'a str,
+    },
+    CurrentTimeIn {
+        time: &'a str,
+        place: &'a str,
+    },
+    NoTimezoneOrLocation,
+    PlacesNotConfigured,
+    InvalidTimezoneOffset,
+    ShieldModeActive(&'a str),
+    ShieldModeInactive,
+    ShieldModeEnabled,
+    ShieldModeDisabled,
+    AutoModUsage,
+    AutoModUnknownCategory {
+        category: &'a str,
+        categories: &'a str,
+    },
+    AutoModInvalidLevel {
+        level: &'a str,
+        max: u8,
+    },
+    AutoModLevel(u8),
+    AutoModUpdated(&'a str),
+    StatusConnection {
+        name: &'a str,
+        state: &'a str,
+        duration: &'a str,
+    },
+    StatusPlatforms(&'a str),
+    StatusNoPlatforms,
+    StatusDegraded(&'a str),
+    StatusMessagesInFlight(usize),
+    StatusTwitchQueue(usize),
+    StatusShards {
+        shard: &'a str,
+        shards: &'a str,
+    },
+    StatusCaches {
+        caches: &'a str,
+        blocked_users: usize,
+    },
+    StatusDatabase {
+        status: &'a str,
+        used: u32,
+        size: u32,
+    },
+    StatusUnavailable(&'a str),
+    StatusUnknown(&'a str),
+    StatusLastEventSub(&'a str),
+    StatusNoEventSub,
+    StatusResources {
+        memory: &'a str,
+        cpu: &'a str,
+    },
+    InvalidDiscordChannels(&'a str),
+    CommandArgsUnchecked(&'a str),
+    CommandUsage {
+        name: &'a str,
+        spec: &'a str,
+    },
+    PartialLimit(usize),
+    PartialSaved(&'a str),
+    PartialRemoved(&'a str),
+    PartialDoesNotExist(&'a str),
+    NoPartials,
+    UserTimedOut {
+        user: &'a str,
+        seconds: u64,
+    },
+    UserPurged(&'a str),
+    UserBanned(&'a str),
+    UsersNuked(usize),
+    NukeFailed(usize),
+    SpamProtectionEnabled,
+    SpamProtectionDisabled,
+    SpamProtectionIsOn,
+    SpamProtectionIsOff,
+    SpamProtectionLimits {
+        rate: &'a str,
+        repeats: &'a str,
+        emotes: &'a str,
+        caps: &'a str,
+        links: &'a str,
+        mentions: &'a str,
+        action: &'a str,
+        timeout: u32,
+    },
+    LimitOff,
+    CapsNotPercentage,
+    InvalidProtectionAction,
+    TimeoutNotPositive,
+    SettingSet {
+        setting: &'a str,
+        value: &'a str,
+    },
+    SettingReset {
+        setting: &'a str,
+        value: &'a str,
+    },
+    UnknownSetting(&'a str),
+    LastError {
+        command: &'a str,
+        ago: &'a str,
+        error: &'a str,
+    },
+    NoRecentErrors,
+    DebugError(&'a str),
+    DebugReport {
+        response: &'a str,
+        url: &'a str,
+    },
+    CannotIgnoreYourself,
+    UserNotFound(&'a str),
+    InvalidUserMention(&'a str),
+    InvalidUserIdentifier(&'a str),
+    TheChannel,
+    FollowingFor {
+        user: &'a str,
+        channel: &'a str,
+        duration: &'a str,
+    },
+    NotFollowing {
+        user: &'a str,
+        channel: &'a str,
+    },
+    StreamerNotAuthenticatedChannel,
+    TitleSet(&'a str),
+    GameSet(&'a str),
+    GameNotFound(&'a str),
+    JoiningOnlyOnTwitch,
+    UseInChannelOf(&'a str),
+    ChannelNotJoined(&'a str),
+    UnknownChannel,
+    MemoryUsageFailed(&'a str),
+    Pong {
+        version: &'a str,
+        uptime: &'a str,
+        memory: u64,
+    },
+    ChatLatency(i64),
+    ShellFailed(&'a str),
+    UnknownPreference(&'a str),
+    InvalidTimezone(&'a str),
+    InvalidLanguageCode(&'a str),
+    InvalidUnits(&'a str),
+    InvalidSupibotId(&'a str),
+    AiNotConfigured,
+    AiUsage {
+        requests: usize,
+        prompt_tokens: u64,
+        completion_tokens: u64,
+        cost: f64,
+    },
+    ExpectedNumber,
+    MissingPlatformSeparator,
+    InvalidPlatform,
+    InvalidUserId,
+    InvalidCommandArgs {
+        error: &'a str,
+        name: &'a str,
+        spec: &'a str,
+    },
+    ExecutionTimedOut,
+    PointsGiven {
+        user: &'a str,
+        amount: u64,
+        balance: u64,
+    },
+    UserPoints {
+        user: &'a str,
+        balance: u64,
+    },
+    YourPoints(u64),
+    InvalidPoints(&'a str),
+    NoPoints,
+    NotEnoughPoints(u64),
+    GambleDisabled,
+    GambleWon {
+        stake: u64,
+        balance: u64,
+    },
+    GambleLost {
+        stake: u64,
+        balance: u64,
+    },
+    DuelsDisabled,
+    NoDuelChallenge,
+    DuelDeclined(&'a str),
+    CannotDuelYourself,
+    DuelChallenge {
+        user: &'a str,
+        amount: u64,
+    },
+    DuelWon {
+        user: &'a str,
+        amount: u64,
+    },
+    DuelCancelled {
+        user: &'a str,
+        amount: u64,
+    },
+    SlotsDisabled,
+    SlotsCost(u64),
+    SlotsWon {
+        symbols: &'a str,
+        payout: u64,
+        balance: u64,
+    },
+    SlotsRefunded {
+        symbols: &'a str,
+        balance: u64,
+    },
+    SlotsLost {
+        symbols: &'a str,
+        cost: u64,
+        balance: u64,
+    },
+}
+
+impl Message<'_> {
+    pub fn translate(&self, language: Language) -> String {
+        match language {
+            Language::En => self.en(),
+            Language::Uk => self.uk(),
+        }
+    }
+
+    fn en(&self) -> String {
+        match self {
+            Message::MissingArgument(arg) => format!("missing argument: {arg}"),
+            Message::InvalidArgument(arg) => format!("invalid argument: {arg}"),
+            Message::NoPermissions => {
+                "you don't have the permissions to use this command".to_owned()
+            }
+            Message::ConfigurationError(e) => format!("configuration error: {e}"),
+            Message::DatabaseError => "something went wrong, please try again later".to_owned(),
+            Message::CommandAdded => "Command successfully added".to_owned(),
+            Message::CommandExists => "Command already exists".to_owned(),
+            Message::CommandRemoved => "Command successfully removed".to_owned(),
+            Message::CommandUpdated(name) => format!("Command {name} updated"),
+            Message::CommandDoesNotExist(name) => format!("command {name} doesn't exist"),
+            Message::CommandNotFound => "Command not found".to_owned(),
+            Message::CommandTriggers(triggers) => format!("Command triggers: {triggers}"),
+            Message::CommandHasNoTriggers => "Command has no triggers".to_owned(),
+            Message::CommandTriggersUpdated => "Successfully updated command triggers".to_owned(),
+            Message::CommandModeUpdated => "Updated command mode".to_owned(),
+            Message::CommandChannelsUpdated => "Command limited to the given channels".to_owned(),
+            Message::CommandChannelsReset => "Command is available in every channel".to_owned(),
+            Message::CommandHasNoTests => "Command has no test cases".to_owned(),
+            Message::CommandTestsPassed(count) => format!("All {count} test cases passed"),
+            Message::CommandTestsFailed(failures) => format!("Failed test cases: {failures}"),
+            Message::TimezoneSet(timezone) => format!("Your timezone is now {timezone}"),
+            Message::TimezoneIs(timezone) => format!("Your timezone is {timezone}"),
+            Message::TimezoneNotSet => "Your timezone is not set".to_owned(),
+            Message::LanguageSet(language) => format!("Your language is now {language}"),
+            Message::LanguageIs(language) => format!("Your language is {language}"),
+            Message::LanguageNotSet => "Your language is not set".to_owned(),
+            Message::UnitsSet(units) => format!("Your units are now {units}"),
+            Message::UnitsAre(units) => format!("Your units are {units}"),
+            Message::UnitsNotSet => "Your units are not set, metric is used".to_owned(),
+            Message::SupibotIdSet(id) => format!("Your Supibot ID is now {id}"),
+            Message::SupibotIdIs(id) => format!("Your Supibot ID is {id}"),
+            Message::SupibotIdNotSet => {
+                "Your Supibot ID is not set, Supibot shows it with its id command".to_owned()
+            }
+            Message::AiEnabled => "AI completions enabled in this channel".to_owned(),
+            Message::AiDisabled => "AI completions disabled in this channel".to_owned(),
+            Message::AiConversationForgotten => "Forgot our conversation".to_owned(),
+            Message::AiNoConversation => "There is no conversation to forget".to_owned(),
+            Message::UserIgnored(user) => format!("{user} will now be ignored in this channel"),
+            Message::UserUnignored(user) => format!("{user} is no longer ignored in this channel"),
+            Message::IgnoredUsers(users) => format!("Ignored users: {users}"),
+            Message::NoIgnoredUsers => "No users are ignored in this channel".to_owned(),
+            Message::JoinedChannel(login) => format!("Joined channel {login}"),
+            Message::LeavingChannel => "MrDestructoid 👋 Leaving the channel".to_owned(),
+            Message::InvalidSubcommand(subcommand) => format!("Invalid subcommand: {subcommand}"),
+            Message::ChannelCannotBlockUsers => "This channel cannot have blocked users".to_owned(),
+            Message::CannotBlockYourself => "you cannot block yourself".to_owned(),
+            Message::UserBlocked(user) => format!("{user} is now blocked in this channel"),
+            Message::UserBlockedGlobally(user) => format!("{user} is now blocked globally"),
+            Message::UserUnblocked(user) => format!("{user} is no longer blocked in this channel"),
+            Message::UserUnblockedGlobally(user) => format!("{user} is no longer blocked globally"),
+            Message::HebiModulesUpdated(commit) => {
+                format!("Hebi modules were updated to commit {commit}")
+            }
+            Message::HebiModulesUpToDate => "Hebi modules are already up to date".to_owned(),
+            Message::HebiModulesReloadFailed(e) => format!("Could not reload hebi modules: {e}"),
+            Message::ConfigReloaded => "Config reloaded".to_owned(),
+            Message::ConfigReloadedRestartRequired => {
+                "Config reloaded, some of the changed settings need a restart to take effect"
+                    .to_owned()
+            }
+            Message::ConfigReloadFailed(e) => format!("Could not reload config: {e}"),
+            Message::SafeModeEntered => {
+                "Entered safe mode, custom commands are disabled".to_owned()
+            }
+            Message::SafeModeExited => "Exited safe mode".to_owned(),
+            Message::SafeModeEnabled => "Safe mode is enabled".to_owned(),
+            Message::SafeModeDisabled => "Safe mode is disabled".to_owned(),
+            Message::WhoAmI {
+                user,
+                identifier,
+                channel,
+                permissions,
+            } => {
+                format!(
+                    "{user}, identified as {identifier}, channel: {channel}, \
+                    permissions: {permissions}"
+                )
+            }
+            Message::PreviousNames(names) => format!(", previous names: {names}"),
+            Message::OnlyInChannel => "can only be used in a channel".to_owned(),
+            Message::UserNotKnown(user) => format!("{user} is not known"),
+            Message::GeohubNoScores => "Nobody has played the daily challenge yet!".to_owned(),
+            Message::GeohubDailyScores(scores) => format!("Top daily challenge scores: {scores}"),
+            Message::GeohubWeeklyScores(scores) => format!("Top weekly challenge scores: {scores}"),
+            Message::GeohubMonthlyScores(scores) => {
+                format!("Top monthly challenge scores: {scores}")
+            }
+            Message::GeohubPeriodScore { name, points, days } => {
+                format!("{name}: {points} ({days} days)")
+            }
+            Message::GeohubLinked => "Successfully linked.".to_owned(),
+            Message::GeohubUnlinked => "Successfully unlinked.".to_owned(),
+            Message::GeohubNotLinked => "you are not linked in this channel".to_owned(),
+            Message::GeohubUserNotLinked => "the user is not linked in this channel".to_owned(),
+            Message::GeohubNoLinks => "Nobody is linked in this channel".to_owned(),
+            Message::GeohubLinks(names) => format!("Linked GeoHub users: {names}"),
+            Message::TwitchNotConfigured => "Twitch is not configured".to_owned(),
+            Message::OnlyOnTwitch(name) => format!("{name} can only be used on Twitch"),
+            Message::StreamerNotAuthenticated => {
+                "streamer has not authenticated the bot to manage the channel".to_owned()
+            }
+            Message::StreamerNotAuthenticatedRewards => {
+                "streamer has not authenticated the bot to manage channel points".to_owned()
+            }
+            Message::InvalidAction(action) => format!("invalid action {action}"),
+            Message::EventSubTriggerAdded => "Trigger successfully added".to_owned(),
+            Message::EventSubTriggerRemoved => "Trigger successfully removed".to_owned(),
+            Message::EventSubTriggerPaused(event_type) => format!("Paused trigger {event_type}"),
+            Message::EventSubTriggerResumed(event_type) => format!("Resumed trigger {event_type}"),
+            Message::EventSubTriggerResubscribed(event_type) => {
+                format!("Resubscribed trigger {event_type}")
+            }
+            Message::EventSubNoTriggers => "No eventsub triggers registered".to_owned(),
+            Message::EventSubNoTrigger(number) => format!("no trigger with number {number}"),
+            Message::EventSubSubscriptionActive => "active".to_owned(),
+            Message::EventSubSubscriptionRevoked(status) => format!("revoked: {status}"),
+            Message::EventSubSubscriptionMissing => "missing".to_owned(),
+            Message::EventSubSubscriptionPaused => ", paused".to_owned(),
+            Message::EventSubSubscriptionFailed(e) => format!("Failed to create subscription: {e}"),
+            Message::EventSubResubscribeFailed(e) => format!("Failed to resubscribe: {e}"),
+            Message::EventSubNoMatchingSubscription => {
+                "unable to find matching subscription".to_owned()
+            }
+            Message::EventSubInvalidType(sub_type) => {
+                format!("Invalid subscription type {sub_type}")
+            }
+            Message::EventSubRewardNotFound(reward) => format!("could not find reward `{reward}`"),
+            Message::FeatureState {
+                feature,
+                enabled,
+                is_default,
+            } => match (*enabled, *is_default) {
+                (true, true) => format!("{feature}: on (default)"),
+                (true, false) => format!("{feature}: on"),
+                (false, true) => format!("{feature}: off (default)"),
+                (false, false) => format!("{feature}: off"),
+            },
+            Message::FeatureEnabled(feature) => format!("{feature} enabled in this channel"),
+            Message::FeatureDisabled(feature) => format!("{feature} disabled in this channel"),
+            Message::FeatureResetEnabled(feature) => {
+                format!("{feature} is now enabled by default in this channel")
+            }
+            Message::FeatureResetDisabled(feature) => {
+                format!("{feature} is now disabled by default in this channel")
+            }
+            Message::UnknownFeature { feature, features } => {
+                format!("unknown feature {feature}, the features are {features}")
+            }
+            Message::NoNamesSeen(user) => format!("{user} hasn't been seen with any name yet"),
+            Message::KnownNames { user, names } => format!("Known names of {user}: {names}"),
+            Message::MoreNames(count) => format!(" and {count} more"),
+            Message::UserSentMessages { user, count } => {
+                format!("{user} has sent {count} messages")
+            }
+            Message::YouSentMessages(count) => format!("You have sent {count} messages"),
+            Message::SeenOptedOut(command) => {
+                format!("You can no longer be looked up with {command}")
+            }
+            Message::SeenOptedIn(command) => format!("You can be looked up with {command} again"),
+            Message::UserOptedOut { user, command } => format!("{user} has opted out of {command}"),
+            Message::FirstSeen { user, ago, text } => {
+                format!("{user} was first seen {ago} ago: {text}")
+            }
+            Message::LastSeen { user, ago, text } => {
+                format!("{user} was last seen {ago} ago: {text}")
+            }
+            Message::NotSeen(user) => format!("{user} has not been seen in this channel"),
+            Message::ChatLogsDisabled => {
+                "chat logs are not enabled in this channel, use `settings set chat_logs true`"
+                    .to_owned()
+            }
+            Message::MarkovOptedOut => {
+                "Your messages are no longer recorded and the recorded ones have been deleted"
+                    .to_owned()
+            }
+            Message::MarkovOptedIn => {
+                "Your messages are recorded again in channels with markov enabled".to_owned()
+            }
+            Message::MarkovPurged(count) => format!("Deleted {count} recorded messages"),
+            Message::MarkovNoMessages => "there are no recorded messages yet".to_owned(),
+            Message::MarkovDisabled => {
+                "markov is not enabled in this channel, use `settings set markov true`".to_owned()
+            }
+            Message::LinkOnlyOnWebsite(identity) => {
+                format!("{identity} accounts can only be linked on the website")
+            }
+            Message::LinkAlreadyLinked(identity) => {
+                format!("you already have a {identity} account linked")
+            }
+            Message::LinkCodeSent { code, identity } => {
+                format!(
+                    "Send `link {code}` from your {identity} account in the next 10 minutes, \
+                    then use `link confirm` here"
+                )
+            }
+            Message::LinkInvalidCode => {
+                "the code is invalid, expired or has already been used".to_owned()
+            }
+            Message::LinkWrongAccount(identity) => {
+                format!("the code has to be sent from a {identity} account")
+            }
+            Message::LinkUserMissing => "the user no longer exists".to_owned(),
+            Message::LinkConfirmRequired => {
+                "Use `link confirm` from your other account to finish linking".to_owned()
+            }
+            Message::LinkNothingToConfirm => {
+                "there is nothing to confirm, send the code from your other account first"
+                    .to_owned()
+            }
+            Message::LinkCompleted => "Your accounts are now linked".to_owned(),
+            Message::LinkSameUser => "the accounts are already linked".to_owned(),
+            Message::LinkConflict(identity) => {
+                format!("both accounts have a {identity} account linked")
+            }
+            Message::InvalidCommandMode(mode) => format!("invalid command mode {mode}"),
+            Message::GlobalCommandAdded(name) => format!("Global command {name} added"),
+            Message::GlobalCommandExists(name) => format!("Global command {name} already exists"),
+            Message::GlobalCommandUpdated(name) => format!("Global command {name} updated"),
+            Message::GlobalCommandRemoved(name) => format!("Global command {name} removed"),
+            Message::GlobalCommandModeUpdated { name, mode } => {
+                format!("Global command {name} now uses the {mode} mode")
+            }
+            Message::GlobalCommandNotFound(name) => {
+                format!("there is no global command named {name}")
+            }
+            Message::GlobalCommands(names) => format!("Global commands: {names}"),
+            Message::NoGlobalCommands => "There are no global commands".to_owned(),
+            Message::PersonalCommandReserved(name) => {
+                format!("{name} is reserved for managing personal commands")
+            }
+            Message::PersonalCommandLimit(limit) => {
+                format!("you can have at most {limit} personal commands")
+            }
+            Message::PersonalCommandAdded(name) => format!("Personal command {name} added"),
+            Message::PersonalCommandExists(name) => {
+                format!("You already have a command named {name}")
+            }
+            Message::PersonalCommandUpdated(name) => format!("Personal command {name} updated"),
+            Message::PersonalCommandRemoved(name) => format!("Personal command {name} removed"),
+            Message::PersonalCommandModeUpdated { name, mode } => {
+                format!("Personal command {name} now uses the {mode} mode")
+            }
+            Message::PersonalCommandNotFound(name) => {
+                format!("you have no personal command named {name}")
+            }
+            Message::PersonalCommands {
+                names,
+                count,
+                limit,
+            } => {
+                format!("Your personal commands ({count}/{limit}): {names}")
+            }
+            Message::NoPersonalCommands => "You have no personal commands".to_owned(),
+            Message::SafeModeCommandsDisabled => {
+                "Commands are not executed in safe mode".to_owned()
+            }
+            Message::MarkerDescriptionTooLong(max) => {
+                format!("the description can be at most {max} characters long")
+            }
+            Message::MarkerFailed => "could not create a marker, is the stream live?".to_owned(),
+            Message::MarkerCreated(position) => format!("Marker created at {position}"),
+            Message::HighlightSavedAt(position) => format!("Highlight saved at {position}"),
+            Message::HighlightSaved => "Highlight saved".to_owned(),
+            Message::LastfmNotConfigured => "Last.fm is not configured".to_owned(),
+            Message::LastfmNotLinked => "you haven't linked your Last.fm account".to_owned(),
+            Message::LastfmUserNotLinked(user) => {
+                format!("{user} hasn't linked their Last.fm account")
+            }
+            Message::CompatNoSharedArtists { user, score } => {
+                format!("You and {user} are {score}% compatible, with no artists in common")
+            }
+            Message::CompatSharedArtists {
+                user,
+                score,
+                artists,
+            } => {
+                format!("You and {user} are {score}% compatible, you both like {artists}")
+            }
+            Message::StocksNotConfigured => "stocks are not configured".to_owned(),
+            Message::QuoteUnavailable(symbol) => format!("{symbol} unavailable"),
+            Message::PortfolioSummary {
+                quotes,
+                gainers,
+                losers,
+            } => {
+                format!("{quotes} | {gainers} up, {losers} down")
+            }
+            Message::Watching(symbols) => format!("Watching {symbols}"),
+            Message::WatchlistEmpty => "Your watchlist is empty".to_owned(),
+            Message::WatchlistEmptyHint => {
+                "Your watchlist is empty, add symbols with `watch add`".to_owned()
+            }
+            Message::WatchlistCleared => "Your watchlist has been cleared".to_owned(),
+            Message::WatchlistFull(max) => format!("the watchlist can have at most {max} symbols"),
+            Message::UnknownSymbol(symbol) => format!("unknown symbol {symbol}"),
+            Message::NotAStockSymbol(symbol) => format!("{symbol} is not a stock symbol"),
+            Message::WatchUnknownSubcommand(subcommand) => {
+                format!("unknown subcommand {subcommand}, use add, remove, clear or list")
+            }
+            Message::CurrentTime(time) => format!("It's {time}"),
+            Message::CurrentTimeFor { time, user } => format!("It's {time} for {user}"),
+            Message::CurrentTimeIn { time, place } => format!("It's {time} in {place}"),
+            Message::NoTimezoneOrLocation => {
+                "no timezone or location set, use `set timezone` to set one".to_owned()
+            }
+            Message::PlacesNotConfigured => "looking up places is not configured".to_owned(),
+            Message::InvalidTimezoneOffset => "invalid timezone offset".to_owned(),
+            Message::ShieldModeActive(moderator) => {
+                format!("Shield mode is on, enabled by {moderator}")
+            }
+            Message::ShieldModeInactive => "Shield mode is off".to_owned(),
+            Message::ShieldModeEnabled => "Shield mode enabled".to_owned(),
+            Message::ShieldModeDisabled => "Shield mode disabled".to_owned(),
+            Message::AutoModUsage => "usage: automod [level | <category> <level>]".to_owned(),
+            Message::AutoModUnknownCategory {
+                category,
+                categories,
+            } => {
+                format!("unknown category {category}, available categories: {categories}")
+            }
+            Message::AutoModInvalidLevel { level, max } => {
+                format!("level {level}, must be between 0 and {max}")
+            }
+            Message::AutoModLevel(level) => format!("level {level}"),
+            Message::AutoModUpdated(settings) => format!("AutoMod updated: {settings}"),
+            Message::StatusConnection {
+                name,
+                state,
+                duration,
+            } => {
+                format!("{name} {state} for {duration}")
+            }
+            Message::StatusPlatforms(platforms) => format!("Platforms: {platforms}"),
+            Message::StatusNoPlatforms => "Platforms: none".to_owned(),
+            Message::StatusDegraded(services) => format!("Degraded: {services}"),
+            Message::StatusMessagesInFlight(count) => format!("Handling {count} messages"),
+            Message::StatusTwitchQueue(count) => format!(", {count} Twitch messages queued"),
+            Message::StatusShards { shard, shards } => {
+                format!("Shard {shard}, active shards: {shards}")
+            }
+            Message::StatusCaches {
+                caches,
+                blocked_users,
+            } => {
+                format!("Caches: {caches}, blocked users {blocked_users}")
+            }
+            Message::StatusDatabase { status, used, size } => {
+                format!("DB: {status}, {used}/{size} connections in use")
+            }
+            Message::StatusUnavailable(e) => format!("unavailable ({e})"),
+            Message::StatusUnknown(e) => format!("unknown ({e})"),
+            Message::StatusLastEventSub(ago) => format!("Last EventSub notification {ago} ago"),
+            Message::StatusNoEventSub => "No EventSub notifications since startup".to_owned(),
+            Message::StatusResources { memory, cpu } => {
+                format!("RAM: {memory}, average CPU: {cpu}")
+            }
+            Message::InvalidDiscordChannels(channels) => {
+                format!("invalid Discord channels {channels}")
+            }
+            Message::CommandArgsUnchecked(name) => {
+                format!("Arguments of {name} are no longer checked")
+            }
+            Message::CommandUsage { name, spec } => format!("Usage of {name}: {name} {spec}"),
+            Message::PartialLimit(max) => format!("a channel can have up to {max} partials"),
+            Message::PartialSaved(name) => {
+                format!("Partial {name} saved, include it with {{{{> {name}}}}}")
+            }
+            Message::PartialRemoved(name) => format!("Partial {name} removed"),
+            Message::PartialDoesNotExist(name) => format!("Partial {name} does not exist"),
+            Message::NoPartials => "The channel has no partials".to_owned(),
+            Message::UserTimedOut { user, seconds } => format!("Timed out {user} for {seconds}s"),
+            Message::UserPurged(user) => format!("Purged messages from {user}"),
+            Message::UserBanned(user) => format!("Banned {user}"),
+            Message::UsersNuked(count) => format!("Nuked {count} users"),
+            Message::NukeFailed(count) => format!(", failed to time out {count}"),
+            Message::SpamProtectionEnabled => "Spam protection enabled in this channel".to_owned(),
+            Message::SpamProtectionDisabled => {
+                "Spam protection disabled in this channel".to_owned()
+            }
+            Message::SpamProtectionIsOn => "Spam protection is on.".to_owned(),
+            Message::SpamProtectionIsOff => "Spam protection is off.".to_owned(),
+            Message::SpamProtectionLimits {
+                rate,
+                repeats,
+                emotes,
+                caps,
+                links,
+                mentions,
+                action,
+                timeout,
+            } => {
+                format!(
+                    "Rate: {rate}, repeats: {repeats}, emotes: {emotes}, caps: {caps}, \
+                    links: {links}, mentions: {mentions}, action: {action}, timeout: {timeout}s"
+                )
+            }
+            Message::LimitOff => "off".to_owned(),
+            Message::CapsNotPercentage => "caps is a percentage".to_owned(),
+            Message::InvalidProtectionAction => "action must be delete or timeout".to_owned(),
+            Message::TimeoutNotPositive => "timeout must be positive".to_owned(),
+            Message::SettingSet { setting, value } => format!("Set {setting} to {value}"),
+            Message::SettingReset { setting, value } => format!("Reset {setting} to {value}"),
+            Message::UnknownSetting(setting) => format!("Unknown setting {setting}"),
+            Message::LastError {
+                command,
+                ago,
+                error,
+            } => {
+                format!("{command} failed {ago} ago: {error}")
+            }
+            Message::NoRecentErrors => "No commands have failed in the last hour".to_owned(),
+            Message::DebugError(e) => format!("Error: {e}"),
+            Message::DebugReport { response, url } => format!("{response} | Debug report: {url}"),
+            Message::CannotIgnoreYourself => "you cannot ignore yourself".to_owned(),
+            Message::UserNotFound(user) => format!("user {user} not found"),
+            Message::InvalidUserMention(user) => format!("{user} is not a valid user mention"),
+            Message::InvalidUserIdentifier(user) => {
+                format!("{user} is not a valid user identifier")
+            }
+            Message::TheChannel => "the channel".to_owned(),
+            Message::FollowingFor {
+                user,
+                channel,
+                duration,
+            } => {
+                format!("{user} has been following {channel} for {duration}")
+            }
+            Message::NotFollowing { user, channel } => format!("{user} is not following {channel}"),
+            Message::StreamerNotAuthenticatedChannel => {
+                "streamer has not authenticated the bot to manage the channel".to_owned()
+            }
+            Message::TitleSet(title) => format!("Title set to {title}"),
+            Message::GameSet(game) => format!("Game set to {game}"),
+            Message::GameNotFound(game) => format!("could not find game `{game}`"),
+            Message::JoiningOnlyOnTwitch => {
+                "joining channels is only supported on Twitch".to_owned()
+            }
+            Message::UseInChannelOf(login) => format!("use this command in the channel of {login}"),
+            Message::ChannelNotJoined(login) => format!("{login} is not joined"),
+            Message::UnknownChannel => "Unknown channel".to_owned(),
+            Message::MemoryUsageFailed(e) => format!("Could not get memory usage: {e}"),
+            Message::Pong {
+                version,
+                uptime,
+                memory,
+            } => {
+                format!("Pong! Version: {version}, Uptime {uptime}, RAM usage: {memory} MiB")
+            }
+            Message::ChatLatency(latency) => format!(", chat latency: {latency}ms"),
+            Message::ShellFailed(err) => format!("could not run command: {err}"),
+            Message::UnknownPreference(preference) => format!("Unknown preference {preference}"),
+            Message::InvalidTimezone(value) => {
+                format!("{value} is not a timezone, use a name such as Europe/Kyiv")
+            }
+            Message::InvalidLanguageCode(value) => {
+                format!("{value} is not a language code, use a code such as uk or en")
+            }
+            Message::InvalidUnits(value) => {
+                format!("{value} are not units, use metric or imperial")
+            }
+            Message::InvalidSupibotId(value) => {
+                format!("{value} is not a Supibot ID, Supibot shows yours with its id command")
+            }
+            Message::AiNotConfigured => "AI is not configured".to_owned(),
+            Message::AiUsage {
+                requests,
+                prompt_tokens,
+                completion_tokens,
+                cost,
+            } => {
+                format!(
+                    "{requests} requests, {prompt_tokens} prompt tokens, \
+                     {completion_tokens} completion tokens, ${cost:.4} total"
+                )
+            }
+            Message::ExpectedNumber => "invalid argument: expected a number".to_owned(),
+            Message::MissingPlatformSeparator => {
+                "missing argument: separator `:`! Must be in the form of `platform:user`".to_owned()
+            }
+            Message::InvalidPlatform => "invalid argument: platform".to_owned(),
+            Message::InvalidUserId => "invalid argument: invalid user id".to_owned(),
+            Message::InvalidCommandArgs { error, name, spec } => {
+                format!("{error}, usage: {name} {spec}")
+            }
+            Message::ExecutionTimedOut => "Execution timed out".to_owned(),
+            Message::PointsGiven {
+                user,
+                amount,
+                balance,
+            } => {
+                format!("Gave {amount} points to {user}, who now has {balance}")
+            }
+            Message::UserPoints { user, balance } => format!("{user} has {balance} points"),
+            Message::YourPoints(balance) => format!("You have {balance} points"),
+            Message::InvalidPoints(amount) => {
+                format!("{amount} is not a positive number of points")
+            }
+            Message::NoPoints => "you don't have any points".to_owned(),
+            Message::NotEnoughPoints(amount) => format!("you don't have {amount} points"),
+            Message::GambleDisabled => "Gambling is not enabled in this channel".to_owned(),
+            Message::GambleWon { stake, balance } => {
+                format!("You won {stake} points and now have {balance}")
+            }
+            Message::GambleLost { stake, balance } => {
+                format!("You lost {stake} points and now have {balance}")
+            }
+            Message::DuelsDisabled => "Duels are not enabled in this channel".to_owned(),
+            Message::NoDuelChallenge => "Nobody has challenged you to a duel".to_owned(),
+            Message::DuelDeclined(user) => format!("You declined the duel with {user}"),
+            Message::CannotDuelYourself => "you cannot duel yourself".to_owned(),
+            Message::DuelChallenge { user, amount } => {
+                format!(
+                    "{user}, you have been challenged to a duel for {amount} points! \
+                     Reply with `duel accept` or `duel decline` within a minute"
+                )
+            }
+            Message::DuelWon { user, amount } => {
+                format!("{user} won the duel and {amount} points!")
+            }
+            Message::DuelCancelled { user, amount } => {
+                format!("{user} no longer has {amount} points, the duel is off")
+            }
+            Message::SlotsDisabled => "Slots are not enabled in this channel".to_owned(),
+            Message::SlotsCost(cost) => format!("a spin costs {cost} points"),
+            Message::SlotsWon {
+                symbols,
+                payout,
+                balance,
+            } => {
+                format!("{symbols} | you won {payout} points, now you have {balance}")
+            }
+            Message::SlotsRefunded { symbols, balance } => {
+                format!("{symbols} | you get your points back, now you have {balance}")
+            }
+            Message::SlotsLost {
+                symbols,
+                cost,
+                balance,
+            } => {
+                format!("{symbols} | you lost {cost} points, now you have {balance}")
+            }
+        }
+    }
+
+    fn uk(&self) -> String {
+        match self {
+            Message::MissingArgument(arg) => format!("відсутній аргумент: {arg}"),
+            Message::InvalidArgument(arg) => format!("неправильний аргумент: {arg}"),
+            Message::NoPermissions => "у вас немає дозволу на використання цієї команди".to_owned(),
+            Message::ConfigurationError(e) => format!("помилка конфігурації: {e}"),
+            Message::DatabaseError => "щось пішло не так, спробуйте пізніше".to_owned(),
+            Message::CommandAdded => "Команду успішно додано".to_owned(),
+            Message::CommandExists => "Команда вже існує".to_owned(),
+            Message::CommandRemoved => "Команду успішно видалено".to_owned(),
+            Message::CommandUpdated(name) => format!("Команду {name} оновлено"),
+            Message::CommandDoesNotExist(name) => format!("команди {name} не існує"),
+            Message::CommandNotFound => "Команду не знайдено".to_owned(),
+            Message::CommandTriggers(triggers) => format!("Тригери команди: {triggers}"),
+            Message::CommandHasNoTriggers => "Команда не має тригерів".to_owned(),
+            Message::CommandTriggersUpdated => "Тригери команди успішно оновлено".to_owned(),
+            Message::CommandModeUpdated => "Режим команди оновлено".to_owned(),
+            Message::CommandChannelsUpdated => {
+                "Команда доступна лише у вказаних каналах".to_owned()
+            }
+            Message::CommandChannelsReset => "Команда доступна в усіх каналах".to_owned(),
+            Message::CommandHasNoTests => "Команда не має тестів".to_owned(),
+            Message::CommandTestsPassed(count) => format!("Усі тести пройдено ({count})"),
+            Message::CommandTestsFailed(failures) => format!("Тести не пройдено: {failures}"),
+            Message::TimezoneSet(timezone) => format!("Ваш часовий пояс тепер {timezone}"),
+            Message::TimezoneIs(timezone) => format!("Ваш часовий пояс: {timezone}"),
+            Message::TimezoneNotSet => "Ваш часовий пояс не встановлено".to_owned(),
+            Message::LanguageSet(language) => format!("Ваша мова тепер {language}"),
+            Message::LanguageIs(language) => format!("Ваша мова: {language}"),
+            Message::LanguageNotSet => "Вашу мову не встановлено".to_owned(),
+            Message::UnitsSet(units) => format!("Ваші одиниці тепер {units}"),
+            Message::UnitsAre(units) => format!("Ваші одиниці: {units}"),
+            Message::UnitsNotSet => {
+                "Ваші одиниці не встановлено, використовується metric".to_owned()
+            }
+            Message::SupibotIdSet(id) => format!("Ваш ID у Supibot тепер {id}"),
+            Message::SupibotIdIs(id) => format!("Ваш ID у Supibot: {id}"),
+            Message::SupibotIdNotSet => {
+                "Ваш ID у Supibot не встановлено, Supibot показує його командою id".to_owned()
+            }
+            Message::AiEnabled => "AI-відповіді увімкнено в цьому каналі".to_owned(),
+            Message::AiDisabled => "AI-відповіді вимкнено в цьому каналі".to_owned(),
+            Message::AiConversationForgotten => "Нашу розмову забуто".to_owned(),
+            Message::AiNoConversation => "Немає розмови, яку можна забути".to_owned(),
+            Message::UserIgnored(user) => format!("{user} тепер ігнорується в цьому каналі"),
+            Message::UserUnignored(user) => format!("{user} більше не ігнорується в цьому каналі"),
+            Message::IgnoredUsers(users) => format!("Ігноровані користувачі: {users}"),
+            Message::NoIgnoredUsers => "У цьому каналі немає ігнорованих користувачів".to_owned(),
+            Message::JoinedChannel(login) => format!("Приєднався до каналу {login}"),
+            Message::LeavingChannel => "MrDestructoid 👋 Виходжу з каналу".to_owned(),
+            Message::InvalidSubcommand(subcommand) => {
+                format!("Неправильна підкоманда: {subcommand}")
+            }
+            Message::ChannelCannotBlockUsers => {
+                "У цьому каналі не можна блокувати користувачів".to_owned()
+            }
+            Message::CannotBlockYourself => "ви не можете заблокувати себе".to_owned(),
+            Message::UserBlocked(user) => format!("{user} тепер заблоковано в цьому каналі"),
+            Message::UserBlockedGlobally(user) => format!("{user} тепер заблоковано глобально"),
+            Message::UserUnblocked(user) => format!("{user} більше не заблоковано в цьому каналі"),
+            Message::UserUnblockedGlobally(user) => {
+                format!("{user} більше не заблоковано глобально")
+            }
+            Message::HebiModulesUpdated(commit) => {
+                format!("Модулі hebi оновлено до коміту {commit}")
+            }
+            Message::HebiModulesUpToDate => "Модулі hebi вже оновлені".to_owned(),
+            Message::HebiModulesReloadFailed(e) => {
+                format!("Не вдалося перезавантажити модулі hebi: {e}")
+            }
+            Message::ConfigReloaded => "Конфігурацію перезавантажено".to_owned(),
+            Message::ConfigReloadedRestartRequired => {
+                "Конфігурацію перезавантажено, деякі змінені налаштування запрацюють \
+                після перезапуску"
+                    .to_owned()
+            }
+            Message::ConfigReloadFailed(e) => {
+                format!("Не вдалося перезавантажити конфігурацію: {e}")
+            }
+            Message::SafeModeEntered => {
+                "Увімкнено безпечний режим, власні команди вимкнено".to_owned()
+            }
+            Message::SafeModeExited => "Безпечний режим вимкнено".to_owned(),
+            Message::SafeModeEnabled => "Безпечний режим увімкнений".to_owned(),
+            Message::SafeModeDisabled => "Безпечний режим вимкнений".to_owned(),
+            Message::WhoAmI {
+                user,
+                identifier,
+                channel,
+                permissions,
+            } => {
+                format!(
+                    "{user}, ідентифіковано як {identifier}, канал: {channel}, \
+                    дозволи: {permissions}"
+                )
+            }
+            Message::PreviousNames(names) => format!(", попередні імена: {names}"),
+            Message::OnlyInChannel => "можна використовувати лише в каналі".to_owned(),
+            Message::UserNotKnown(user) => format!("{user} невідомий"),
+            Message::GeohubNoScores => "Ще ніхто не зіграв щоденний челендж!".to_owned(),
+            Message::GeohubDailyScores(scores) => {
+                format!("Найкращі результати щоденного челенджу: {scores}")
+            }
+            Message::GeohubWeeklyScores(scores) => {
+                format!("Найкращі результати челенджів за тиждень: {scores}")
+            }
+            Message::GeohubMonthlyScores(scores) => {
+                format!("Найкращі результати челенджів за місяць: {scores}")
+            }
+            Message::GeohubPeriodScore { name, points, days } => {
+                format!("{name}: {points} (днів: {days})")
+            }
+            Message::GeohubLinked => "Успішно прив'язано.".to_owned(),
+            Message::GeohubUnlinked => "Успішно відв'язано.".to_owned(),
+            Message::GeohubNotLinked => "ви не прив'язані в цьому каналі".to_owned(),
+            Message::GeohubUserNotLinked => "користувач не прив'язаний у цьому каналі".to_owned(),
+            Message::GeohubNoLinks => "У цьому каналі ніхто не прив'язаний".to_owned(),
+            Message::GeohubLinks(names) => format!("Прив'язані користувачі GeoHub: {names}"),
+            Message::TwitchNotConfigured => "Twitch не налаштовано".to_owned(),
+            Message::OnlyOnTwitch(name) => format!("{name} можна використовувати лише на Twitch"),
+            Message::StreamerNotAuthenticated => {
+                "стример не дозволив боту керувати каналом".to_owned()
+            }
+            Message::StreamerNotAuthenticatedRewards => {
+                "стример не дозволив боту керувати балами каналу".to_owned()
+            }
+            Message::InvalidAction(action) => format!("неправильна дія {action}"),
+            Message::EventSubTriggerAdded => "Тригер успішно додано".to_owned(),
+            Message::EventSubTriggerRemoved => "Тригер успішно видалено".to_owned(),
+            Message::EventSubTriggerPaused(event_type) => {
+                format!("Тригер {event_type} призупинено")
+            }
+            Message::EventSubTriggerResumed(event_type) => {
+                format!("Тригер {event_type} відновлено")
+            }
+            Message::EventSubTriggerResubscribed(event_type) => {
+                format!("Тригер {event_type} підписано повторно")
+            }
+            Message::EventSubNoTriggers => "Немає зареєстрованих тригерів eventsub".to_owned(),
+            Message::EventSubNoTrigger(number) => format!("немає тригера з номером {number}"),
+            Message::EventSubSubscriptionActive => "активний".to_owned(),
+            Message::EventSubSubscriptionRevoked(status) => format!("відкликаний: {status}"),
+            Message::EventSubSubscriptionMissing => "відсутній".to_owned(),
+            Message::EventSubSubscriptionPaused => ", призупинений".to_owned(),
+            Message::EventSubSubscriptionFailed(e) => format!("Не вдалося створити підписку: {e}"),
+            Message::EventSubResubscribeFailed(e) => {
+                format!("Не вдалося підписатися повторно: {e}")
+            }
+            Message::EventSubNoMatchingSubscription => {
+                "не вдалося знайти відповідну підписку".to_owned()
+            }
+            Message::EventSubInvalidType(sub_type) => {
+                format!("Неправильний тип підписки {sub_type}")
+            }
+            Message::EventSubRewardNotFound(reward) => {
+                format!("не вдалося знайти нагороду `{reward}`")
+            }
+            Message::FeatureState {
+                feature,
+                enabled,
+                is_default,
+            } => match (*enabled, *is_default) {
+                (true, true) => format!("{feature}: увімкнено (типово)"),
+                (true, false) => format!("{feature}: увімкнено"),
+                (false, true) => format!("{feature}: вимкнено (типово)"),
+                (false, false) => format!("{feature}: вимкнено"),
+            },
+            Message::FeatureEnabled(feature) => format!("{feature} увімкнено в цьому каналі"),
+            Message::FeatureDisabled(feature) => format!("{feature} вимкнено в цьому каналі"),
+            Message::FeatureResetEnabled(feature) => {
+                format!("{feature} тепер типово увімкнено в цьому каналі")
+            }
+            Message::FeatureResetDisabled(feature) => {
+                format!("{feature} тепер типово вимкнено в цьому каналі")
+            }
+            Message::UnknownFeature { feature, features } => {
+                format!("невідома функція {feature}, доступні функції: {features}")
+            }
+            Message::NoNamesSeen(user) => format!("Імен {user} ще не помічено"),
+            Message::KnownNames { user, names } => format!("Відомі імена {user}: {names}"),
+            Message::MoreNames(count) => format!(" і ще {count}"),
+            Message::UserSentMessages { user, count } => format!("Повідомлень від {user}: {count}"),
+            Message::YouSentMessages(count) => format!("Ваших повідомлень: {count}"),
+            Message::SeenOptedOut(command) => format!("Вас більше не можна знайти через {command}"),
+            Message::SeenOptedIn(command) => format!("Вас знову можна знайти через {command}"),
+            Message::UserOptedOut { user, command } => {
+                format!("{user} відмовляється від {command}")
+            }
+            Message::FirstSeen { user, ago, text } => {
+                format!("Перше повідомлення {user} {ago} тому: {text}")
+            }
+            Message::LastSeen { user, ago, text } => {
+                format!("Останнє повідомлення {user} {ago} тому: {text}")
+            }
+            Message::NotSeen(user) => format!("Повідомлень від {user} у цьому каналі немає"),
+            Message::ChatLogsDisabled => "журнал чату не увімкнено в цьому каналі, \
+                використайте `settings set chat_logs true`"
+                .to_owned(),
+            Message::MarkovOptedOut => {
+                "Ваші повідомлення більше не записуються, а записані видалено".to_owned()
+            }
+            Message::MarkovOptedIn => {
+                "Ваші повідомлення знову записуються в каналах з увімкненим markov".to_owned()
+            }
+            Message::MarkovPurged(count) => format!("Видалено записаних повідомлень: {count}"),
+            Message::MarkovNoMessages => "записаних повідомлень ще немає".to_owned(),
+            Message::MarkovDisabled => {
+                "markov не увімкнено в цьому каналі, використайте `settings set markov true`"
+                    .to_owned()
+            }
+            Message::LinkOnlyOnWebsite(identity) => {
+                format!("Акаунти {identity} можна прив'язати лише на сайті")
+            }
+            Message::LinkAlreadyLinked(identity) => {
+                format!("у вас уже прив'язано акаунт {identity}")
+            }
+            Message::LinkCodeSent { code, identity } => {
+                format!(
+                    "Надішліть `link {code}` зі свого акаунта {identity} протягом 10 хвилин, \
+                    а потім використайте `link confirm` тут"
+                )
+            }
+            Message::LinkInvalidCode => {
+                "код неправильний, прострочений або вже використаний".to_owned()
+            }
+            Message::LinkWrongAccount(identity) => {
+                format!("код потрібно надіслати з акаунта {identity}")
+            }
+            Message::LinkUserMissing => "користувача більше не існує".to_owned(),
+            Message::LinkConfirmRequired => {
+                "Використайте `link confirm` зі свого іншого акаунта, щоб завершити прив'язку"
+                    .to_owned()
+            }
+            Message::LinkNothingToConfirm => {
+                "немає чого підтверджувати, спочатку надішліть код зі свого іншого акаунта"
+                    .to_owned()
+            }
+            Message::LinkCompleted => "Ваші акаунти тепер прив'язані".to_owned(),
+            Message::LinkSameUser => "акаунти вже прив'язані".to_owned(),
+            Message::LinkConflict(identity) => {
+                format!("обидва акаунти мають прив'язаний акаунт {identity}")
+            }
+            Message::InvalidCommandMode(mode) => format!("неправильний режим команди {mode}"),
+            Message::GlobalCommandAdded(name) => format!("Глобальну команду {name} додано"),
+            Message::GlobalCommandExists(name) => format!("Глобальна команда {name} вже існує"),
+            Message::GlobalCommandUpdated(name) => format!("Глобальну команду {name} оновлено"),
+            Message::GlobalCommandRemoved(name) => format!("Глобальну команду {name} видалено"),
+            Message::GlobalCommandModeUpdated { name, mode } => {
+                format!("Глобальна команда {name} тепер використовує режим {mode}")
+            }
+            Message::GlobalCommandNotFound(name) => format!("глобальної команди {name} не існує"),
+            Message::GlobalCommands(names) => format!("Глобальні команди: {names}"),
+            Message::NoGlobalCommands => "Глобальних команд немає".to_owned(),
+            Message::PersonalCommandReserved(name) => {
+                format!("{name} зарезервовано для керування особистими командами")
+            }
+            Message::PersonalCommandLimit(limit) => {
+                format!("у вас може бути не більше {limit} особистих команд")
+            }
+            Message::PersonalCommandAdded(name) => format!("Особисту команду {name} додано"),
+            Message::PersonalCommandExists(name) => format!("У вас уже є команда {name}"),
+            Message::PersonalCommandUpdated(name) => format!("Особисту команду {name} оновлено"),
+            Message::PersonalCommandRemoved(name) => format!("Особисту команду {name} видалено"),
+            Message::PersonalCommandModeUpdated { name, mode } => {
+                format!("Особиста команда {name} тепер використовує режим {mode}")
+            }
+            Message::PersonalCommandNotFound(name) => {
+                format!("у вас немає особистої команди {name}")
+            }
+            Message::PersonalCommands {
+                names,
+                count,
+                limit,
+            } => {
+                format!("Ваші особисті команди ({count}/{limit}): {names}")
+            }
+            Message::NoPersonalCommands => "У вас немає особистих команд".to_owned(),
+            Message::SafeModeCommandsDisabled => {
+                "У безпечному режимі команди не виконуються".to_owned()
+            }
+            Message::MarkerDescriptionTooLong(max) => {
+                format!("опис може містити не більше {max} символів")
+            }
+            Message::MarkerFailed => "не вдалося створити маркер, чи йде трансляція?".to_owned(),
+            Message::MarkerCreated(position) => format!("Маркер створено на {position}"),
+            Message::HighlightSavedAt(position) => format!("Момент збережено на {position}"),
+            Message::HighlightSaved => "Момент збережено".to_owned(),
+            Message::LastfmNotConfigured => "Last.fm не налаштовано".to_owned(),
+            Message::LastfmNotLinked => "ви не прив'язали свій акаунт Last.fm".to_owned(),
+            Message::LastfmUserNotLinked(user) => {
+                format!("{user} не має прив'язаного акаунта Last.fm")
+            }
+            Message::CompatNoSharedArtists { user, score } => {
+                format!("Ви та {user} сумісні на {score}%, спільних виконавців немає")
+            }
+            Message::CompatSharedArtists {
+                user,
+                score,
+                artists,
+            } => {
+                format!("Ви та {user} сумісні на {score}%, вам обом подобаються {artists}")
+            }
+            Message::StocksNotConfigured => "акції не налаштовано".to_owned(),
+            Message::QuoteUnavailable(symbol) => format!("{symbol} недоступний"),
+            Message::PortfolioSummary {
+                quotes,
+                gainers,
+                losers,
+            } => {
+                format!("{quotes} | зросли: {gainers}, впали: {losers}")
+            }
+            Message::Watching(symbols) => format!("Ви стежите за {symbols}"),
+            Message::WatchlistEmpty => "Ваш список спостереження порожній".to_owned(),
+            Message::WatchlistEmptyHint => {
+                "Ваш список спостереження порожній, додайте тикери командою `watch add`".to_owned()
+            }
+            Message::WatchlistCleared => "Ваш список спостереження очищено".to_owned(),
+            Message::WatchlistFull(max) => {
+                format!("список спостереження може містити не більше {max} тикерів")
+            }
+            Message::UnknownSymbol(symbol) => format!("невідомий тикер {symbol}"),
+            Message::NotAStockSymbol(symbol) => format!("{symbol} не є тикером акції"),
+            Message::WatchUnknownSubcommand(subcommand) => {
+                format!(
+                    "невідома підкоманда {subcommand}, використайте add, remove, clear або list"
+                )
+            }
+            Message::CurrentTime(time) => format!("Зараз {time}"),
+            Message::CurrentTimeFor { time, user } => format!("У {user} зараз {time}"),
+            Message::CurrentTimeIn { time, place } => format!("{place}: зараз {time}"),
+            Message::NoTimezoneOrLocation => {
+                "часовий пояс або місцезнаходження не встановлено, використайте `set timezone`"
+                    .to_owned()
+            }
+            Message::PlacesNotConfigured => "пошук місць не налаштовано".to_owned(),
+            Message::InvalidTimezoneOffset => "неправильний зсув часового поясу".to_owned(),
+            Message::ShieldModeActive(moderator) => {
+                format!("Режим щита увімкнений, модератор: {moderator}")
+            }
+            Message::ShieldModeInactive => "Режим щита вимкнений".to_owned(),
+            Message::ShieldModeEnabled => "Режим щита увімкнено".to_owned(),
+            Message::ShieldModeDisabled => "Режим щита вимкнено".to_owned(),
+            Message::AutoModUsage => {
+                "використання: automod [рівень | <категорія> <рівень>]".to_owned()
+            }
+            Message::AutoModUnknownCategory {
+                category,
+                categories,
+            } => {
+                format!("невідома категорія {category}, доступні категорії: {categories}")
+            }
+            Message::AutoModInvalidLevel { level, max } => {
+                format!("рівень {level}, має бути від 0 до {max}")
+            }
+            Message::AutoModLevel(level) => format!("рівень {level}"),
+            Message::AutoModUpdated(settings) => format!("AutoMod оновлено: {settings}"),
+            Message::StatusConnection {
+                name,
+                state,
+                duration,
+            } => {
+                format!("{name} {state} протягом {duration}")
+            }
+            Message::StatusPlatforms(platforms) => format!("Платформи: {platforms}"),
+            Message::StatusNoPlatforms => "Платформи: немає".to_owned(),
+            Message::StatusDegraded(services) => format!("Працюють з обмеженнями: {services}"),
+            Message::StatusMessagesInFlight(count) => format!("Обробляється повідомлень: {count}"),
+            Message::StatusTwitchQueue(count) => format!(", у черзі Twitch: {count}"),
+            Message::StatusShards { shard, shards } => {
+                format!("Шард {shard}, активні шарди: {shards}")
+            }
+            Message::StatusCaches {
+                caches,
+                blocked_users,
+            } => {
+                format!("Кеші: {caches}, заблоковані користувачі {blocked_users}")
+            }
+            Message::StatusDatabase { status, used, size } => {
+                format!("БД: {status}, використано з'єднань: {used}/{size}")
+            }
+            Message::StatusUnavailable(e) => format!("недоступна ({e})"),
+            Message::StatusUnknown(e) => format!("невідомо ({e})"),
+            Message::StatusLastEventSub(ago) => format!("Останнє сповіщення EventSub {ago} тому"),
+            Message::StatusNoEventSub => "Сповіщень EventSub від запуску не було".to_owned(),
+            Message::StatusResources { memory, cpu } => {
+                format!("RAM: {memory}, середнє навантаження CPU: {cpu}")
+            }
+            Message::InvalidDiscordChannels(channels) => {
+                format!("неправильні канали Discord {channels}")
+            }
+            Message::CommandArgsUnchecked(name) => {
+                format!("Аргументи {name} більше не перевіряються")
+            }
+            Message::CommandUsage { name, spec } => format!("Використання {name}: {name} {spec}"),
+            Message::PartialLimit(max) => format!("канал може мати не більше {max} фрагментів"),
+            Message::PartialSaved(name) => {
+                format!("Фрагмент {name} збережено, додайте його через {{{{> {name}}}}}")
+            }
+            Message::PartialRemoved(name) => format!("Фрагмент {name} видалено"),
+            Message::PartialDoesNotExist(name) => format!("Фрагмента {name} не існує"),
+            Message::NoPartials => "Канал не має фрагментів".to_owned(),
+            Message::UserTimedOut { user, seconds } => {
+                format!("{user} отримує тайм-аут на {seconds} с")
+            }
+            Message::UserPurged(user) => format!("Повідомлення {user} очищено"),
+            Message::UserBanned(user) => format!("{user} заблоковано на платформі"),
+            Message::UsersNuked(count) => format!("Покарано користувачів: {count}"),
+            Message::NukeFailed(count) => format!(", не вдалося дати тайм-аут: {count}"),
+            Message::SpamProtectionEnabled => {
+                "Захист від спаму увімкнено в цьому каналі".to_owned()
+            }
+            Message::SpamProtectionDisabled => {
+                "Захист від спаму вимкнено в цьому каналі".to_owned()
+            }
+            Message::SpamProtectionIsOn => "Захист від спаму увімкнено.".to_owned(),
+            Message::SpamProtectionIsOff => "Захист від спаму вимкнено.".to_owned(),
+            Message::SpamProtectionLimits {
+                rate,
+                repeats,
+                emotes,
+                caps,
+                links,
+                mentions,
+                action,
+                timeout,
+            } => {
+                format!(
+                    "Частота: {rate}, повтори: {repeats}, емоції: {emotes}, капс: {caps}, \
+                    посилання: {links}, згадки: {mentions}, дія: {action}, тайм-аут: {timeout} с"
+                )
+            }
+            Message::LimitOff => "вимк.".to_owned(),
+            Message::CapsNotPercentage => "капс задається у відсотках".to_owned(),
+            Message::InvalidProtectionAction => "дія має бути delete або timeout".to_owned(),
+            Message::TimeoutNotPositive => "тайм-аут має бути додатним".to_owned(),
+            Message::SettingSet { setting, value } => format!("{setting} тепер {value}"),
+            Message::SettingReset { setting, value } => format!("{setting} скинуто до {value}"),
+            Message::UnknownSetting(setting) => format!("Невідоме налаштування {setting}"),
+            Message::LastError {
+                command,
+                ago,
+                error,
+            } => {
+                format!("{command} завершилась помилкою {ago} тому: {error}")
+            }
+            Message::NoRecentErrors => "За останню годину помилок у командах не було".to_owned(),
+            Message::DebugError(e) => format!("Помилка: {e}"),
+            Message::DebugReport { response, url } => {
+                format!("{response} | Звіт налагодження: {url}")
+            }
+            Message::CannotIgnoreYourself => "ви не можете ігнорувати себе".to_owned(),
+            Message::UserNotFound(user) => format!("користувача {user} не знайдено"),
+            Message::InvalidUserMention(user) => format!("{user} не є дійсною згадкою користувача"),
+            Message::InvalidUserIdentifier(user) => {
+                format!("{user} не є дійсним ідентифікатором користувача")
+            }
+            Message::TheChannel => "каналу".to_owned(),
+            Message::FollowingFor {
+                user,
+                channel,
+                duration,
+            } => {
+                format!("{user} відстежує {channel} вже {duration}")
+            }
+            Message::NotFollowing { user, channel } => format!("{user} не відстежує {channel}"),
+            Message::StreamerNotAuthenticatedChannel => {
+                "стример не авторизував бота для керування каналом".to_owned()
+            }
+            Message::TitleSet(title) => format!("Назву змінено на {title}"),
+            Message::GameSet(game) => format!("Гру змінено на {game}"),
+            Message::GameNotFound(game) => format!("не вдалося знайти гру `{game}`"),
+            Message::JoiningOnlyOnTwitch => {
+                "приєднання до каналів підтримується лише на Twitch".to_owned()
+            }
+            Message::UseInChannelOf(login) => format!("використайте цю команду в каналі {login}"),
+            Message::ChannelNotJoined(login) => format!("бот не приєднаний до {login}"),
+            Message::UnknownChannel => "Невідомий канал".to_owned(),
+            Message::MemoryUsageFailed(e) => {
+                format!("Не вдалося отримати використання пам'яті: {e}")
+            }
+            Message::Pong {
+                version,
+                uptime,
+                memory,
+            } => {
+                format!(
+                    "Понг! Версія: {version}, час роботи {uptime}, використання RAM: {memory} MiB"
+                )
+            }
+            Message::ChatLatency(latency) => format!(", затримка чату: {latency}мс"),
+            Message::ShellFailed(err) => format!("не вдалося виконати команду: {err}"),
+            Message::UnknownPreference(preference) => format!("Невідоме налаштування {preference}"),
+            Message::InvalidTimezone(value) => {
+                format!("{value} не є часовим поясом, використайте назву на зразок Europe/Kyiv")
+            }
+            Message::InvalidLanguageCode(value) => {
+                format!("{value} не є кодом мови, використайте код на зразок uk або en")
+            }
+            Message::InvalidUnits(value) => {
+                format!("{value} не є одиницями, використайте metric або imperial")
+            }
+            Message::InvalidSupibotId(value) => {
+                format!("{value} не є ID Supibot, Supibot показує ваш командою id")
+            }
+            Message::AiNotConfigured => "AI не налаштовано".to_owned(),
+            Message::AiUsage {
+                requests,
+                prompt_tokens,
+                completion_tokens,
+                cost,
+            } => {
+                format!(
+                    "{requests} запитів, {prompt_tokens} токенів запиту, \
+                     {completion_tokens} токенів відповіді, ${cost:.4} загалом"
+                )
+            }
+            Message::ExpectedNumber => "неправильний аргумент: очікувалося число".to_owned(),
+            Message::MissingPlatformSeparator => {
+                "відсутній аргумент: роздільник `:`! Має бути у формі `platform:user`".to_owned()
+            }
+            Message::InvalidPlatform => "неправильний аргумент: платформа".to_owned(),
+            Message::InvalidUserId => "неправильний аргумент: недійсний ID користувача".to_owned(),
+            Message::InvalidCommandArgs { error, name, spec } => {
+                format!("{error}, використання: {name} {spec}")
+            }
+            Message::ExecutionTimedOut => "Час виконання вичерпано".to_owned(),
+            Message::PointsGiven {
+                user,
+                amount,
+                balance,
+            } => {
+                format!("Передано {amount} балів користувачу {user}, тепер у нього {balance}")
+            }
+            Message::UserPoints { user, balance } => format!("У {user} {balance} балів"),
+            Message::YourPoints(balance) => format!("У вас {balance} балів"),
+            Message::InvalidPoints(amount) => format!("{amount} не є додатною кількістю балів"),
+            Message::NoPoints => "у вас немає балів".to_owned(),
+            Message::NotEnoughPoints(amount) => format!("у вас немає {amount} балів"),
+            Message::GambleDisabled => "Азартні ігри не ввімкнено в цьому каналі".to_owned(),
+            Message::GambleWon { stake, balance } => {
+                format!("Ви виграли {stake} балів, тепер у вас {balance}")
+            }
+            Message::GambleLost { stake, balance } => {
+                format!("Ви програли {stake} балів, тепер у вас {balance}")
+            }
+            Message::DuelsDisabled => "Дуелі не ввімкнено в цьому каналі".to_owned(),
+            Message::NoDuelChallenge => "Ніхто не викликав вас на дуель".to_owned(),
+            Message::DuelDeclined(user) => format!("Ви відхилили дуель з {user}"),
+            Message::CannotDuelYourself => "ви не можете викликати на дуель себе".to_owned(),
+            Message::DuelChallenge { user, amount } => {
+                format!(
+                    "{user}, вас викликали на дуель за {amount} балів! \
+                     Відповідайте `duel accept` або `duel decline` протягом хвилини"
+                )
+            }
+            Message::DuelWon { user, amount } => {
+                format!("{user} перемагає в дуелі та отримує {amount} балів!")
+            }
+            Message::DuelCancelled { user, amount } => {
+                format!("У {user} більше немає {amount} балів, дуель скасовано")
+            }
+            Message::SlotsDisabled => "Слоти не ввімкнено в цьому каналі".to_owned(),
+            Message::SlotsCost(cost) => format!("оберт коштує {cost} балів"),
+            Message::SlotsWon {
+                symbols,
+                payout,
+                balance,
+            } => {
+                format!("{symbols} | ви виграли {payout} балів, тепер у вас {balance}")
+            }
+            Message::SlotsRefunded { symbols, balance } => {
+                format!("{symbols} | ви отримуєте свої бали назад, тепер у вас {balance}")
+            }
+            Message::SlotsLost {
+                symbols,
+                cost,
+                balance,
+            } => {
+                format!("{symbols} | ви програли {cost} балів, тепер у вас {balance}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_language_code, Language, Message};
+
+    #[test]
+    fn language_codes() {
+        assert!(is_language_code("uk"));
+        assert!(is_language_code("pt-BR"));
+        assert!(is_language_code("fil"));
+        assert!(!is_language_code("ukrainian"));
+        assert!(!is_language_code("en-"));
+        assert!(!is_language_code("en-US-x"));
+        assert!(!is_language_code(""));
+    }
+
+    #[test]
+    fn resolve_language() {
+        assert_eq!(Language::resolve(Some("uk-UA"), Some("en")), Language::Uk);
+        assert_eq!(Language::resolve(Some("de"), Some("uk")), Language::Uk);
+        assert_eq!(Language::resolve(None, None), Language::En);
+    }
+
+    #[test]
+    fn translate() {
+        let message = Message::CommandUpdated("ping");
+        assert_eq!(message.translate(Language::En), "Command ping updated");
+        assert_eq!(message.translate(Language::Uk), "Команду ping оновлено");
+    }
+}
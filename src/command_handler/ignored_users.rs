@@ -0,0 +1,101 @@
+use crate::{
+    database::{Database, DatabaseError},
+    platform::UserIdentifier,
+};
+use dashmap::DashMap;
+use std::{collections::HashSet, sync::Arc};
+
+/// Per-channel list of users that the bot never responds to, such as other bots.
+/// Channels are loaded into the cache on first use
+#[derive(Debug, Clone)]
+pub struct IgnoredUsers {
+    db: Database,
+    cache: Arc<DashMap<u64, HashSet<UserIdentifier>>>,
+}
+
+impl IgnoredUsers {
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            cache: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub async fn is_ignored(
+        &self,
+        channel_id: u64,
+        identifier: &UserIdentifier,
+    ) -> Result<bool, DatabaseError> {
+        self.load_channel(channel_id).await?;
+
+        Ok(self
+            .cache
+            .get(&channel_id)
+            .map(|users| users.contains(identifier))
+            .unwrap_or(false))
+    }
+
+    pub async fn get(&self, channel_id: u64) -> Result<Vec<UserIdentifier>, DatabaseError> {
+        self.load_channel(channel_id).await?;
+
+        Ok(self
+            .cache
+            .get(&channel_id)
+            .map(|users| users.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    pub async fn ignore(
+        &self,
+        channel_id: u64,
+        identifier: UserIdentifier,
+    ) -> Result<(), DatabaseError> {
+        let identifier = self
+            .db
+            .run(move |db| {
+                db.add_ignored_user(channel_id, &identifier)?;
+                Ok::<_, DatabaseError>(identifier)
+            })
+            .await?;
+        self.load_channel(channel_id).await?;
+
+        if let Some(mut users) = self.cache.get_mut(&channel_id) {
+            users.insert(identifier);
+        }
+
+        Ok(())
+    }
+
+    pub async fn unignore(
+        &self,
+        channel_id: u64,
+        identifier: &UserIdentifier,
+    ) -> Result<(), DatabaseError> {
+        let owned_identifier = identifier.clone();
+        self.db
+            .run(move |db| db.remove_ignored_user(channel_id, &owned_identifier))
+            .await?;
+
+        if let Some(mut users) = self.cache.get_mut(&channel_id) {
+            users.remove(identifier);
+        }
+
+        Ok(())
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.clear();
+    }
+
+    async fn load_channel(&self, channel_id: u64) -> Result<(), DatabaseError> {
+        if !self.cache.contains_key(&channel_id) {
+            let users = self
+                .db
+                .run(move |db| db.get_ignored_users(channel_id))
+                .await?;
+            self.cache.insert(channel_id, users.into_iter().collect());
+        }
+
+        Ok(())
+    }
+}
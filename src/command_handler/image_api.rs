@@ -0,0 +1,261 @@
+//! Meme-style captions on images. The results are stored as pastes, so they can be linked
+//! on every platform.
+use std::{
+    fs,
+    io::Cursor,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context};
+use dashmap::DashMap;
+use image::{
+    imageops::FilterType,
+    io::{Limits, Reader},
+    DynamicImage, ImageOutputFormat, Rgba, RgbaImage,
+};
+use imageproc::drawing::{draw_text_mut, text_size};
+use rusttype::{Font, Scale};
+use tokio::task;
+
+use super::http_fetch::HttpFetcher;
+use crate::config::ImagesConfig;
+use crate::database::Database;
+
+/// Images are rejected before decoding them if they are larger than this,
+/// anything that passes is scaled down to the configured dimension
+const MAX_SOURCE_DIMENSION: u32 = 8192;
+const MAX_CAPTION_LENGTH: usize = 200;
+const MIN_FONT_SIZE: f32 = 16.0;
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+pub struct ImageApi {
+    http_fetcher: HttpFetcher,
+    pub db: Database,
+    font: Arc<Font<'static>>,
+    max_download_size: u64,
+    max_dimension: u32,
+    rate_limiter: RateLimiter,
+}
+
+impl ImageApi {
+    /// Returns `None` if no font is configured
+    pub fn from_config(
+        config: &ImagesConfig,
+        db: Database,
+        http_fetcher: HttpFetcher,
+    ) -> anyhow::Result<Option<Self>> {
+        let font_path = match &config.font_path {
+            Some(font_path) => font_path,
+            None => return Ok(None),
+        };
+
+        let font_data =
+            fs::read(font_path).with_context(|| format!("could not read font {font_path}"))?;
+        let font = Font::try_from_vec(font_data).context("invalid font")?;
+
+        Ok(Some(Self {
+            http_fetcher,
+            db,
+            font: Arc::new(font),
+            max_download_size: config.max_download_size,
+            max_dimension: config.max_dimension,
+            rate_limiter: RateLimiter::new(config.channel_limit as usize, RATE_LIMIT_WINDOW),
+        }))
+    }
+
+    /// Captions the image at the url on behalf of the channel and returns the link to the result
+    pub async fn meme(
+        &self,
+        channel_id: u64,
+        url: &str,
+        top_text: &str,
+        bottom_text: &str,
+    ) -> anyhow::Result<String> {
+        if top_text.chars().count() + bottom_text.chars().count() > MAX_CAPTION_LENGTH {
+            return Err(anyhow!(
+                "captions can be at most {MAX_CAPTION_LENGTH} characters"
+            ));
+        }
+
+        if !self.rate_limiter.try_acquire(channel_id, Instant::now()) {
+            return Err(anyhow!("too many images in this channel, try again later"));
+        }
+
+        let data = self
+            .http_fetcher
+            .download(url, self.max_download_size)
+            .await?;
+
+        let font = self.font.clone();
+        let max_dimension = self.max_dimension;
+        let top_text = top_text.to_uppercase();
+        let bottom_text = bottom_text.to_uppercase();
+
+        // Decoding and drawing are CPU-bound
+        let png = task::spawn_blocking(move || {
+            render_meme(&data, max_dimension, &font, &top_text, &bottom_text)
+        })
+        .await??;
+
+        let paste = self
+            .db
+            .run(move |db| db.add_paste("meme.png".to_owned(), png))
+            .await?;
+
+        Ok(paste.get_url(&self.db.config().base_url))
+    }
+}
+
+fn render_meme(
+    data: &[u8],
+    max_dimension: u32,
+    font: &Font,
+    top_text: &str,
+    bottom_text: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+
+    let mut reader = Reader::new(Cursor::new(data)).with_guessed_format()?;
+    reader.limits(limits);
+    let image = reader.decode().context("could not decode image")?;
+
+    let image = if image.width() > max_dimension || image.height() > max_dimension {
+        image.resize(max_dimension, max_dimension, FilterType::Triangle)
+    } else {
+        image
+    };
+    let mut image = image.to_rgba8();
+
+    let (width, height) = image.dimensions();
+    let margin = (height / 40) as i32;
+
+    let (scale, lines) = fit_text(font, top_text, width, height);
+    draw_caption(&mut image, font, scale, &lines, margin);
+
+    let (scale, lines) = fit_text(font, bottom_text, width, height);
+    let bottom_y = height as i32 - margin - lines.len() as i32 * scale.y as i32;
+    draw_caption(&mut image, font, scale, &lines, bottom_y);
+
+    let mut png = Vec::new();
+    DynamicImage::ImageRgba8(image).write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+
+    Ok(png)
+}
+
+/// Shrinks the text until it takes up at most a third of the image
+fn fit_text(font: &Font, text: &str, width: u32, height: u32) -> (Scale, Vec<String>) {
+    let max_width = (width * 9 / 10) as i32;
+    let mut size = (height as f32 / 8.0).max(MIN_FONT_SIZE);
+
+    loop {
+        let scale = Scale::uniform(size);
+        let lines = wrap_text(font, scale, text, max_width);
+
+        let fits = lines.len() as f32 * size <= height as f32 / 3.0
+            && lines
+                .iter()
+                .all(|line| text_size(scale, font, line).0 <= max_width);
+
+        if fits || size <= MIN_FONT_SIZE {
+            return (scale, lines);
+        }
+        size = (size * 0.8).max(MIN_FONT_SIZE);
+    }
+}
+
+fn wrap_text(font: &Font, scale: Scale, text: &str, max_width: i32) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if text_size(scale, font, &format!("{line} {word}")).0 <= max_width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_owned()),
+        }
+    }
+
+    lines
+}
+
+/// White centered text with a black outline
+fn draw_caption(image: &mut RgbaImage, font: &Font, scale: Scale, lines: &[String], y: i32) {
+    let width = image.width() as i32;
+    let outline = (scale.y / 16.0).max(1.0) as i32;
+
+    for (i, line) in lines.iter().enumerate() {
+        let line_width = text_size(scale, font, line).0;
+        let x = (width - line_width) / 2;
+        let y = y + i as i32 * scale.y as i32;
+
+        for dx in -outline..=outline {
+            for dy in -outline..=outline {
+                draw_text_mut(
+                    image,
+                    Rgba([0, 0, 0, 255]),
+                    x + dx,
+                    y + dy,
+                    scale,
+                    font,
+                    line,
+                );
+            }
+        }
+        draw_text_mut(image, Rgba([255, 255, 255, 255]), x, y, scale, font, line);
+    }
+}
+
+/// Allows at most `limit` requests per key within the window
+#[derive(Clone)]
+struct RateLimiter {
+    limit: usize,
+    window: Duration,
+    requests: Arc<DashMap<u64, Vec<Instant>>>,
+}
+
+impl RateLimiter {
+    fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            requests: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Records the request if it's allowed
+    fn try_acquire(&self, key: u64, now: Instant) -> bool {
+        let mut requests = self.requests.entry(key).or_default();
+        requests.retain(|time| now.duration_since(*time) < self.window);
+
+        if requests.len() >= self.limit {
+            false
+        } else {
+            requests.push(now);
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimiter;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn rate_limits_channels() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire(1, now));
+        assert!(limiter.try_acquire(1, now));
+        assert!(!limiter.try_acquire(1, now));
+        assert!(limiter.try_acquire(2, now));
+
+        assert!(limiter.try_acquire(1, now + Duration::from_secs(61)));
+    }
+}
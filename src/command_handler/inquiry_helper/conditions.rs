@@ -0,0 +1,218 @@
+//! Helpers for simple branching in templates, for example
+//! `{{#if (gt (args 0) 10)}}big{{else}}small{{/if}}` or `{{ default (args 0) "world" }}`
+use std::cmp::Ordering;
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, JsonRender, RenderContext, RenderError, ScopedJson,
+};
+use serde_json::Value;
+
+/// `{{ default value "fallback" }}`, the first value that is not null
+pub struct DefaultHelper;
+
+impl HelperDef for DefaultHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        if h.params().len() < 2 {
+            return Err(RenderError::new("default needs a value and a fallback"));
+        }
+
+        let value = h
+            .params()
+            .iter()
+            .map(|param| param.value())
+            .find(|value| !value.is_null())
+            .cloned()
+            .unwrap_or(Value::Null);
+
+        Ok(ScopedJson::Derived(value))
+    }
+}
+
+/// `{{ if_empty value "fallback" }}`, the fallback when the value is null, blank or an empty
+/// list or object
+pub struct IfEmptyHelper;
+
+impl HelperDef for IfEmptyHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        match (h.param(0), h.param(1)) {
+            (Some(value), Some(fallback)) => {
+                let value = match is_empty(value.value()) {
+                    true => fallback.value(),
+                    false => value.value(),
+                };
+                Ok(ScopedJson::Derived(value.clone()))
+            }
+            _ => Err(RenderError::new("if_empty needs a value and a fallback")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl Comparison {
+    pub const ALL: [Comparison; 6] = [
+        Comparison::Eq,
+        Comparison::Ne,
+        Comparison::Gt,
+        Comparison::Gte,
+        Comparison::Lt,
+        Comparison::Lte,
+    ];
+
+    /// Also the name of the helper
+    pub fn name(self) -> &'static str {
+        match self {
+            Comparison::Eq => "eq",
+            Comparison::Ne => "ne",
+            Comparison::Gt => "gt",
+            Comparison::Gte => "gte",
+            Comparison::Lt => "lt",
+            Comparison::Lte => "lte",
+        }
+    }
+
+    fn matches(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Eq => ordering == Ordering::Equal,
+            Comparison::Ne => ordering != Ordering::Equal,
+            Comparison::Gt => ordering == Ordering::Greater,
+            Comparison::Gte => ordering != Ordering::Less,
+            Comparison::Lt => ordering == Ordering::Less,
+            Comparison::Lte => ordering != Ordering::Greater,
+        }
+    }
+}
+
+/// `{{#if (gt (args 0) 10)}}`, returns a boolean. Replaces the builtin comparison helpers,
+/// so that numbers from arguments and http responses can be compared even though they are strings
+pub struct CompareHelper {
+    pub comparison: Comparison,
+}
+
+impl HelperDef for CompareHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let name = self.comparison.name();
+
+        let (left, right) = match (h.param(0), h.param(1)) {
+            (Some(left), Some(right)) => (left.value(), right.value()),
+            _ => return Err(RenderError::new(format!("{name} needs two values"))),
+        };
+
+        let result = compare(self.comparison, left, right).map_err(RenderError::new)?;
+
+        Ok(ScopedJson::Derived(Value::Bool(result)))
+    }
+}
+
+/// Values are compared as numbers when both of them are numbers, equality of other values
+/// is checked as text
+fn compare(comparison: Comparison, left: &Value, right: &Value) -> Result<bool, String> {
+    match (as_number(left), as_number(right)) {
+        (Some(left), Some(right)) => {
+            let ordering = left
+                .partial_cmp(&right)
+                .ok_or_else(|| "numbers can't be compared".to_owned())?;
+            Ok(comparison.matches(ordering))
+        }
+        _ => match comparison {
+            Comparison::Eq => Ok(left.render() == right.render()),
+            Comparison::Ne => Ok(left.render() != right.render()),
+            _ => Err(format!("{} can only compare numbers", comparison.name())),
+        },
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text
+            .trim()
+            .parse()
+            .ok()
+            .filter(|number: &f64| number.is_finite()),
+        _ => None,
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(text) => text.trim().is_empty(),
+        Value::Array(items) => items.is_empty(),
+        Value::Object(fields) => fields.is_empty(),
+        Value::Bool(_) | Value::Number(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, is_empty, Comparison};
+    use serde_json::json;
+
+    #[test]
+    fn compares_numbers_given_as_text() {
+        assert_eq!(compare(Comparison::Gt, &json!("11"), &json!(10)), Ok(true));
+        assert_eq!(
+            compare(Comparison::Lt, &json!(" 2.5 "), &json!("10")),
+            Ok(true)
+        );
+        assert_eq!(compare(Comparison::Eq, &json!("5"), &json!(5.0)), Ok(true));
+        assert_eq!(compare(Comparison::Gte, &json!(5), &json!("5")), Ok(true));
+        assert_eq!(compare(Comparison::Lte, &json!(6), &json!(5)), Ok(false));
+    }
+
+    #[test]
+    fn compares_text() {
+        assert_eq!(
+            compare(Comparison::Eq, &json!("abc"), &json!("abc")),
+            Ok(true)
+        );
+        assert_eq!(
+            compare(Comparison::Ne, &json!("abc"), &json!("abd")),
+            Ok(true)
+        );
+        assert_eq!(
+            compare(Comparison::Eq, &json!(true), &json!("true")),
+            Ok(true)
+        );
+        assert!(compare(Comparison::Gt, &json!("abc"), &json!(1)).is_err());
+        assert!(compare(Comparison::Gt, &json!("inf"), &json!(1)).is_err());
+    }
+
+    #[test]
+    fn detects_empty_values() {
+        assert!(is_empty(&json!(null)));
+        assert!(is_empty(&json!("  ")));
+        assert!(is_empty(&json!([])));
+        assert!(is_empty(&json!({})));
+        assert!(!is_empty(&json!(0)));
+        assert!(!is_empty(&json!(false)));
+        assert!(!is_empty(&json!("text")));
+    }
+}
@@ -0,0 +1,42 @@
+const MARKDOWN_CHARS: &[char] = &['\\', '*', '_', '~', '`', '|', '>', '#', '[', ']'];
+const ZERO_WIDTH_SPACE: char = '\u{200B}';
+
+/// Escapes markdown formatting and breaks up mentions so that user input can't be used to format
+/// the message or ping people (mainly relevant for Discord)
+pub fn escape_markdown(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if MARKDOWN_CHARS.contains(&c) {
+            output.push('\\');
+            output.push(c);
+        } else if c == '@' {
+            output.push(c);
+            output.push(ZERO_WIDTH_SPACE);
+        } else {
+            output.push(c);
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape_markdown;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn escape_formatting() {
+        assert_eq!(
+            escape_markdown("**bold** _it_"),
+            "\\*\\*bold\\*\\* \\_it\\_"
+        );
+    }
+
+    #[test]
+    fn escape_mentions() {
+        assert_eq!(escape_markdown("@everyone"), "@\u{200B}everyone");
+        assert_eq!(escape_markdown("<@123>"), "<@\u{200B}123\\>");
+    }
+}
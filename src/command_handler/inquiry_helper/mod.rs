@@ -1,44 +1,88 @@
-mod forsencode;
+mod conditions;
+mod escape;
+mod paginate;
+mod time;
+mod twitch_chat;
+mod twitch_followage;
+mod twitch_stream;
 mod twitch_timeout;
 
-use std::env;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
-use dashmap::DashMap;
 use handlebars::{
     Context, Decorator, Handlebars, Helper, HelperDef, HelperResult, JsonRender, Output,
     RenderContext, RenderError, ScopedJson,
 };
+use http::Method;
 use rand::{thread_rng, Rng};
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as Json;
 use tokio::runtime::Handle;
 use tokio::sync::RwLock;
 use tokio::time::sleep;
-use twitch_irc::login::{LoginCredentials, RefreshingLoginCredentials};
+use twitch_irc::login::RefreshingLoginCredentials;
 
-use crate::database::{models::User, Database};
-use crate::platform::{ChannelIdentifier, UserIdentifier};
-
-use super::finnhub_api::FinnhubApi;
-use super::lastfm_api::LastFMApi;
-use super::lingva_api::LingvaApi;
+use crate::database::{
+    models::{ChannelFeature, User},
+    Database,
+};
+use crate::platform::{ChannelIdentifier, Permissions, UserIdentifier};
+
+use super::codec::Codec;
+use super::debug_report;
+use super::dictionary_api::{DictionaryApi, UrbanDictionaryApi};
+use super::emotes_api::{EmoteProvider, EmotesApi};
+use super::exchange_api::{CoinGeckoApi, ExchangeRateApi};
+use super::finnhub_api::{is_us_symbol, FinnhubApi};
+use super::http_fetch::HttpFetcher;
+use super::image_api::ImageApi;
+use super::json_path;
+use super::lastfm_api::{LastFMApi, Period, Track};
+use super::lingva_api::{LingvaApi, TranslationArgs};
+use super::openai_api::OpenAiApi;
 use super::platform_handler::PlatformHandler;
-use super::twitch_api::helix::HelixApi;
-use super::twitch_api::{get_client_id, get_client_secret, TwitchApi};
+use super::render_budget;
+use super::supinic_api::SupinicApi;
+use super::twitch_api::{get_broadcaster_helix_api, TwitchApi};
 use super::ukraine_alert::UkraineAlertClient;
-use super::{owm_api::OwmApi, spotify_api::SpotifyApi};
+use super::{
+    owm_api::{OwmApi, Units, MAX_FORECAST_DAYS},
+    spotify_api::SpotifyApi,
+};
 
+pub use conditions::{CompareHelper, Comparison, DefaultHelper, IfEmptyHelper};
+pub use paginate::PaginateHelper;
+pub use time::{countdown_helper, format_duration, TimestampHelper};
+pub use twitch_chat::{TwitchAnnounceHelper, TwitchShoutoutHelper};
+pub use twitch_followage::TwitchFollowageHelper;
+pub use twitch_stream::{StreamInfoKind, TwitchLiveHelper, TwitchStreamHelper};
 pub use twitch_timeout::TwitchTimeoutHelper;
 
+const MAX_LASTFM_TOP_LIMIT: u64 = 20;
+
 #[derive(Serialize, Deserialize)]
 pub struct InquiryContext {
     pub user: User,
     pub arguments: Vec<String>,
     pub display_name: String,
     pub channel: ChannelIdentifier,
+    #[serde(default)]
+    pub payload: Option<Json>,
+    /// The values of the arguments of commands with an args spec
+    #[serde(default)]
+    pub params: Option<Json>,
+    /// Only resolved on Twitch
+    #[serde(default)]
+    pub permissions: Option<Permissions>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    /// The command with its prefix, such as `!top`
+    #[serde(default)]
+    pub command: Option<String>,
 }
 
 pub struct TwitchUserHelper {
@@ -133,49 +177,58 @@ pub fn args_helper(
     Ok(())
 }
 
-pub fn song_helper(
-    h: &Helper,
-    hb: &Handlebars,
-    ctx: &Context,
-    _: &mut RenderContext,
-    out: &mut dyn Output,
-) -> HelperResult {
-    let mut params = h
-        .params()
-        .iter()
-        .map(|param| param.render())
-        .collect::<Vec<String>>()
-        .join(" ");
+/// Tries Last.FM first and falls back to Spotify
+pub struct SongHelper {
+    pub base_url: String,
+}
 
-    tracing::info!("Params: {}", params);
+impl HelperDef for SongHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        hb: &Handlebars,
+        ctx: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let mut params = h
+            .params()
+            .iter()
+            .map(|param| param.render())
+            .collect::<Vec<String>>()
+            .join(" ");
 
-    if !params.is_empty() {
-        params = format!("\"{}\"", params);
-    }
+        tracing::info!("Params: {}", params);
 
-    // Curly braces are doubled for escaping
-    match hb.render_template_with_context(&format!("{{{{ lastfm {} }}}}", params), ctx) {
-        Ok(lastfm_result) => {
-            out.write(&lastfm_result)?;
+        if !params.is_empty() {
+            params = format!("\"{}\"", params);
         }
-        Err(e) => {
-            tracing::info!("Last.FM Error: {}", e);
-
-            match hb.render_template_with_context(&format!("{{{{ spotify {} }}}}", params), ctx) {
-                Ok(spotify_result) => out.write(&spotify_result)?,
-                Err(e) => {
-                    tracing::info!("Spotify Error: {}", e);
-
-                    return Err(RenderError::new(format!(
-                        "No music source configured! Go to {}/profile to connect an account.",
-                        env::var("BASE_URL").expect("BASE_URL missing")
-                    )));
+
+        // Curly braces are doubled for escaping
+        match hb.render_template_with_context(&format!("{{{{ lastfm {} }}}}", params), ctx) {
+            Ok(lastfm_result) => {
+                out.write(&lastfm_result)?;
+            }
+            Err(e) => {
+                tracing::info!("Last.FM Error: {}", e);
+
+                match hb.render_template_with_context(&format!("{{{{ spotify {} }}}}", params), ctx)
+                {
+                    Ok(spotify_result) => out.write(&spotify_result)?,
+                    Err(e) => {
+                        tracing::info!("Spotify Error: {}", e);
+
+                        return Err(RenderError::new(format!(
+                            "No music source configured! Go to {}/profile to connect an account.",
+                            self.base_url
+                        )));
+                    }
                 }
             }
         }
-    }
 
-    Ok(())
+        Ok(())
+    }
 }
 
 pub struct WeatherHelper {
@@ -214,21 +267,90 @@ impl HelperDef for WeatherHelper {
             }
         };
 
+        let units = match h.hash_get("units") {
+            Some(units) => Units::from_str(&units.render())
+                .map_err(|_| RenderError::new("units must be metric or imperial"))?,
+            None => self
+                .db
+                .get_units(context.user.id)
+                .map_err(|e| RenderError::new(format!("DB Error: {}", e)))?
+                .and_then(|units| Units::from_str(&units).ok())
+                .unwrap_or_default(),
+        };
+        let days = match h.hash_get("days") {
+            Some(days) => days
+                .value()
+                .as_u64()
+                .filter(|days| (1..=MAX_FORECAST_DAYS as u64).contains(days))
+                .ok_or_else(|| {
+                    RenderError::new(format!("days must be between 1 and {MAX_FORECAST_DAYS}"))
+                })? as usize,
+            None => 0,
+        };
+        let air_quality = matches!(
+            h.hash_get("air").map(|air| air.value()),
+            Some(Json::Bool(true))
+        );
+
         tracing::info!("Querying weather for {}", place);
 
         let api = self.api.clone();
 
         // All of this is needed to call async apis from a blocking function
-        let weather = runtime
-            .block_on(api.get_current(&place))
-            .map_err(|e| RenderError::new(e.to_string()))?;
+        let (mut response, coord) = if days > 0 {
+            let forecast = runtime
+                .block_on(api.get_forecast(&place, units))
+                .map_err(|e| RenderError::new(e.to_string()))?;
+
+            let daily = forecast
+                .daily(days)
+                .into_iter()
+                .map(|day| {
+                    format!(
+                        "{} {:.0}..{:.0}{} {}",
+                        day.date.format("%a"),
+                        day.temp_min,
+                        day.temp_max,
+                        units.temperature(),
+                        day.description
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+
+            let city = forecast.city;
+            let response = format!(
+                "{}, {}: {daily}",
+                city.name,
+                city.country.unwrap_or_default()
+            );
+            (response, city.coord)
+        } else {
+            let weather = runtime
+                .block_on(api.get_current(&place, units))
+                .map_err(|e| RenderError::new(e.to_string()))?;
+
+            let response = format!(
+                "{}, {}: {}{}",
+                weather.name,
+                weather.sys.country.unwrap_or_default(),
+                weather.main.temp,
+                units.temperature()
+            );
+            (response, weather.coord)
+        };
 
-        out.write(&format!(
-            "{}, {}: {}°C",
-            weather.name,
-            weather.sys.country.unwrap_or_default(),
-            weather.main.temp
-        ))?;
+        if air_quality {
+            let air_pollution = runtime
+                .block_on(api.get_air_quality(&coord))
+                .map_err(|e| RenderError::new(e.to_string()))?;
+
+            if let Some(air_pollution) = air_pollution.list.first() {
+                response.push_str(&format!(", {}", air_pollution.describe()));
+            }
+        }
+
+        out.write(&response)?;
 
         Ok(())
     }
@@ -245,7 +367,7 @@ fn get_spotify_api(db: Database, ctx: &Context, h: &Helper) -> Result<SpotifyApi
                 .map_err(|_| RenderError::new("invalid user"))?;
 
             db.get_user(&user_identifier)
-                .expect("DB Error")
+                .map_err(|e| RenderError::new(format!("DB Error: {}", e)))?
                 .ok_or_else(|| RenderError::new("invalid user"))?
                 .id
         }
@@ -260,7 +382,7 @@ fn get_spotify_api(db: Database, ctx: &Context, h: &Helper) -> Result<SpotifyApi
         .ok_or_else(|| {
             RenderError::new(format!(
                 "Not configured for user! You can set up Spotify by going to {}/profile",
-                env::var("BASE_URL").unwrap()
+                db.config().base_url
             ))
         })?;
 
@@ -410,7 +532,7 @@ impl HelperDef for LastFMHelper {
 
                 self.db
                     .get_user(&user_identifier)
-                    .expect("DB Error")
+                    .map_err(|e| RenderError::new(format!("DB Error: {}", e)))?
                     .ok_or_else(|| RenderError::new("invalid user"))?
                     .id
             }
@@ -420,7 +542,7 @@ impl HelperDef for LastFMHelper {
         let username = self
             .db
             .get_lastfm_name(user_id)
-            .expect("DB Error")
+            .map_err(|e| RenderError::new(format!("DB Error: {}", e)))?
             .ok_or_else(|| RenderError::new("last.fm username not set!"))?;
 
         let lastfm_api = self.lastfm_api.clone();
@@ -446,6 +568,175 @@ impl HelperDef for LastFMHelper {
     }
 }
 
+/// The Last.fm statistics that have their own helpers
+#[derive(Clone, Copy, Debug)]
+pub enum LastFMStat {
+    /// `{{lastfm_top "week" limit=5}}`
+    TopArtists,
+    /// `{{lastfm_count}}`
+    Playcount,
+    /// `{{lastfm_album}}` for the current track or `{{lastfm_album "artist" "album"}}`
+    Album,
+    /// `{{lastfm_artist}}` for the current track or `{{lastfm_artist "artist"}}`
+    Artist,
+}
+
+/// The user is the one running the command, unless another one is given with `user=`
+#[derive(Clone)]
+pub struct LastFMStatsHelper {
+    pub db: Database,
+    pub lastfm_api: LastFMApi,
+    pub stat: LastFMStat,
+}
+
+impl LastFMStatsHelper {
+    fn get_username(&self, h: &Helper, ctx: &Context) -> Result<String, RenderError> {
+        let user_id = match h.hash_get("user") {
+            Some(user) => {
+                let user_identifier = UserIdentifier::from_string(&user.render())
+                    .map_err(|_| RenderError::new("invalid user"))?;
+
+                self.db
+                    .get_user(&user_identifier)
+                    .map_err(|e| RenderError::new(format!("DB Error: {}", e)))?
+                    .ok_or_else(|| RenderError::new("invalid user"))?
+                    .id
+            }
+            None => {
+                serde_json::from_value::<InquiryContext>(ctx.data().clone())
+                    .expect("Failed to get command context")
+                    .user
+                    .id
+            }
+        };
+
+        self.db
+            .get_lastfm_name(user_id)
+            .map_err(|e| RenderError::new(format!("DB Error: {}", e)))?
+            .ok_or_else(|| RenderError::new("last.fm username not set!"))
+    }
+
+    /// The track that is playing, or the last one that was played
+    async fn get_last_track(&self, username: &str) -> anyhow::Result<Track> {
+        self.lastfm_api
+            .get_recent_tracks(username)
+            .await?
+            .recenttracks
+            .track
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("no tracks have been played"))
+    }
+
+    async fn render(&self, h: &Helper<'_, '_>, ctx: &Context) -> anyhow::Result<String> {
+        match self.stat {
+            LastFMStat::TopArtists => {
+                let period = match h.param(0) {
+                    Some(period) => Period::parse(&period.render()).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "period must be week, month, 3month, 6month, year or overall"
+                        )
+                    })?,
+                    None => Period::default(),
+                };
+                let limit = match h.hash_get("limit") {
+                    Some(limit) => limit
+                        .value()
+                        .as_u64()
+                        .filter(|limit| (1..=MAX_LASTFM_TOP_LIMIT).contains(limit))
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("limit must be between 1 and {MAX_LASTFM_TOP_LIMIT}")
+                        })? as u32,
+                    None => 5,
+                };
+
+                let username = self.get_username(h, ctx)?;
+                let artists = self
+                    .lastfm_api
+                    .get_top_artists(&username, period, limit)
+                    .await?;
+
+                if artists.is_empty() {
+                    return Ok("No artists have been played".to_owned());
+                }
+
+                Ok(artists
+                    .into_iter()
+                    .map(|artist| format!("{} ({} plays)", artist.name, artist.playcount))
+                    .collect::<Vec<_>>()
+                    .join(", "))
+            }
+            LastFMStat::Playcount => {
+                let username = self.get_username(h, ctx)?;
+                Ok(self.lastfm_api.get_playcount(&username).await?.to_string())
+            }
+            LastFMStat::Album => {
+                let (artist, album) = match (h.param(0), h.param(1)) {
+                    (Some(artist), Some(album)) => (artist.render(), album.render()),
+                    _ => {
+                        let username = self.get_username(h, ctx)?;
+                        let track = self.get_last_track(&username).await?;
+                        (track.artist.text, track.album.text)
+                    }
+                };
+
+                let album = self.lastfm_api.get_album_info(&artist, &album).await?;
+                Ok(match album.cover_url() {
+                    Some(cover_url) => format!("{} - {}: {cover_url}", album.artist, album.name),
+                    None => format!("{} - {}", album.artist, album.name),
+                })
+            }
+            LastFMStat::Artist => {
+                let artist = match h.param(0) {
+                    Some(artist) => artist.render(),
+                    None => {
+                        let username = self.get_username(h, ctx)?;
+                        self.get_last_track(&username).await?.artist.text
+                    }
+                };
+
+                let artist = self.lastfm_api.get_artist_info(&artist).await?;
+                let mut response = format!(
+                    "{}: {} listeners, {} scrobbles",
+                    artist.name, artist.stats.listeners, artist.stats.playcount
+                );
+
+                let tags = artist
+                    .tags
+                    .tag
+                    .into_iter()
+                    .map(|tag| tag.name)
+                    .collect::<Vec<_>>();
+                if !tags.is_empty() {
+                    response.push_str(&format!(", tags: {}", tags.join(", ")));
+                }
+
+                Ok(response)
+            }
+        }
+    }
+}
+
+impl HelperDef for LastFMStatsHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let runtime = tokio::runtime::Handle::current();
+
+        let response = runtime
+            .block_on(self.render(h, ctx))
+            .map_err(|e| RenderError::new(format!("Last.FM Error: {}", e)))?;
+        out.write(&response)?;
+
+        Ok(())
+    }
+}
+
 pub fn random_helper(
     h: &Helper,
     _: &Handlebars,
@@ -476,11 +767,19 @@ pub fn sleep_helper(
 ) -> HelperResult {
     match h.params().get(0) {
         Some(duration) => {
-            let runtime = tokio::runtime::Handle::current();
+            let duration = duration
+                .value()
+                .as_u64()
+                .map(Duration::from_secs)
+                .ok_or_else(|| RenderError::new("sleep error: invalid duration"))?;
+
+            // Sleeping past the render budget would only hold on to the thread
+            if render_budget::remaining_duration().map_or(false, |remaining| duration > remaining) {
+                return Err(render_budget::duration_exceeded());
+            }
 
-            runtime.block_on(sleep(Duration::from_secs(
-                duration.value().as_u64().expect("Invalid duration"),
-            )));
+            let runtime = tokio::runtime::Handle::current();
+            runtime.block_on(sleep(duration));
 
             Ok(())
         }
@@ -507,29 +806,15 @@ impl HelperDef for LingvaApi {
             .collect::<Vec<String>>()
             .join(" ");
 
-        let mut params = raw_params.split_whitespace().collect::<Vec<&str>>();
-
         // The join into split is needed to fix inconsistencies when calling the command with
         // `(args)` as opposed to passing the arguments to the helper directly
+        let params = raw_params.split_whitespace().collect::<Vec<&str>>();
 
-        let mut source = String::from("auto");
-        let mut target = String::from("en");
-
-        params.retain(|param| {
-            if let Some(source_lang) = param.strip_prefix("from:") {
-                source = source_lang.to_string();
-
-                false
-            } else if let Some(target_lang) = param.strip_prefix("to:") {
-                target = target_lang.to_string();
-
-                false
-            } else {
-                true
-            }
-        });
-
-        let text = params.join(" ");
+        let TranslationArgs {
+            source,
+            target,
+            text,
+        } = TranslationArgs::parse(&params, "en");
 
         tracing::info!("Translating text {}", text);
 
@@ -572,6 +857,99 @@ impl HelperDef for FinnhubApi {
                     quote.percent_change.unwrap_or(0.0)
                 ))?;
 
+                // The market status is only an addition, so errors are ignored
+                if is_us_symbol(&symbol) {
+                    if let Ok(status) = rt.block_on(self.market_status()) {
+                        if let Some(session) = status.extended_hours() {
+                            out.write(&format!(", {session}"))?;
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+            Err(e) => Err(RenderError::new(e.to_string())),
+        }
+    }
+}
+
+impl HelperDef for ExchangeRateApi {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        _: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let mut params = collect_text_params(h)
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        let amount: f64 = params
+            .next()
+            .ok_or_else(|| RenderError::new("amount not specified!"))?
+            .parse()
+            .map_err(|_| RenderError::new("amount is not a number"))?;
+        let from = params
+            .next()
+            .ok_or_else(|| RenderError::new("source currency not specified!"))?;
+        let to = params.next().unwrap_or_else(|| String::from("USD"));
+
+        let rt = Handle::current();
+
+        match rt.block_on(self.convert(amount, &from, &to)) {
+            Ok(converted) => {
+                out.write(&format!(
+                    "{amount} {} = {converted:.2} {}",
+                    from.to_uppercase(),
+                    to.to_uppercase()
+                ))?;
+
+                Ok(())
+            }
+            Err(e) => Err(RenderError::new(e.to_string())),
+        }
+    }
+}
+
+impl HelperDef for CoinGeckoApi {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        _: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let symbol = h
+            .param(0)
+            .map(|param| param.value().render())
+            .ok_or_else(|| RenderError::new("coin not specified!"))?;
+        let vs_currency = h
+            .param(1)
+            .map(|param| param.value().render())
+            .unwrap_or_else(|| String::from("usd"));
+
+        let rt = Handle::current();
+
+        match rt.block_on(self.get_price(&symbol, &vs_currency)) {
+            Ok(price) => {
+                let mut text = format!(
+                    "{}: {} {}",
+                    symbol.to_uppercase(),
+                    price.price,
+                    vs_currency.to_uppercase()
+                );
+
+                if let Some(change) = price.change_24h {
+                    text.push_str(&format!(" ({change:+.2}% 24h)"));
+                }
+
+                out.write(&text)?;
+
                 Ok(())
             }
             Err(e) => Err(RenderError::new(e.to_string())),
@@ -616,18 +994,285 @@ impl HelperDef for UkraineAlertClient {
     }
 }
 
-pub struct HttpHelper {
-    client: Client,
+impl HelperDef for UrbanDictionaryApi {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        ctx: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let term = collect_text_params(h);
+        if term.is_empty() {
+            return Err(RenderError::new("term not specified"));
+        }
+
+        let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
+            .expect("Failed to get command context");
+
+        let message = match Handle::current().block_on(self.define(&term)) {
+            Ok(Some(definition)) => definition.to_message(),
+            Ok(None) => format!("No definition found for {term}"),
+            Err(e) => return Err(RenderError::new(e.to_string())),
+        };
+
+        out.write(&context.channel.truncate_message(&message))?;
+
+        Ok(())
+    }
+}
+
+impl HelperDef for DictionaryApi {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        ctx: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let word = collect_text_params(h);
+        if word.is_empty() {
+            return Err(RenderError::new("word not specified"));
+        }
+
+        let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
+            .expect("Failed to get command context");
+
+        let message = match Handle::current().block_on(self.define(&word)) {
+            Ok(Some(entry)) => entry.to_message(),
+            Ok(None) => format!("No definition found for {word}"),
+            Err(e) => return Err(RenderError::new(e.to_string())),
+        };
+
+        out.write(&context.channel.truncate_message(&message))?;
+
+        Ok(())
+    }
+}
+
+/// Joins all params into a single string, treating unknown paths as plain text
+fn collect_text_params(h: &Helper) -> String {
+    h.params()
+        .iter()
+        .map(|param| match param.relative_path() {
+            Some(path) => path.to_owned(),
+            None => param.render(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim()
+        .to_owned()
+}
+
+impl HelperDef for OpenAiApi {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        ctx: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let prompt = collect_text_params(h);
+        if prompt.is_empty() {
+            return Err(RenderError::new("prompt not specified"));
+        }
+
+        let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
+            .expect("Failed to get command context");
+
+        let channel = self
+            .db
+            .get_channel(&context.channel)
+            .map_err(|e| RenderError::new(e.to_string()))?
+            .ok_or_else(|| RenderError::new("ai can only be used in a channel"))?;
+
+        let completion = Handle::current()
+            .block_on(self.complete(channel.id, context.user.id, &prompt))
+            .map_err(|e| RenderError::new(e.to_string()))?;
+
+        out.write(&context.channel.truncate_message(&completion))?;
+
+        Ok(())
+    }
+}
+
+/// `{{ meme <url> "top text" "bottom text" }}`
+impl HelperDef for ImageApi {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        ctx: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let url = h
+            .param(0)
+            .map(|param| param.render())
+            .ok_or_else(|| RenderError::new("image url not specified"))?;
+        let top_text = h.param(1).map(|param| param.render()).unwrap_or_default();
+        let bottom_text = h.param(2).map(|param| param.render()).unwrap_or_default();
+
+        let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
+            .expect("Failed to get command context");
+
+        let channel = self
+            .db
+            .get_channel(&context.channel)
+            .map_err(|e| RenderError::new(e.to_string()))?
+            .ok_or_else(|| RenderError::new("meme can only be used in a channel"))?;
+
+        let link = Handle::current()
+            .block_on(self.meme(channel.id, &url, &top_text, &bottom_text))
+            .map_err(|e| RenderError::new(e.to_string()))?;
+
+        out.write(&link)?;
+
+        Ok(())
+    }
 }
 
-impl HttpHelper {
-    pub fn init() -> Self {
-        Self {
-            client: Client::new(),
+impl HelperDef for EmotesApi {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let mut params = h.params().iter().map(|param| param.value().render());
+
+        let action = params
+            .next()
+            .ok_or_else(|| RenderError::new("action not specified"))?;
+
+        let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
+            .expect("Failed to get command context");
+
+        let twitch_id = match context.channel {
+            ChannelIdentifier::TwitchChannel((id, _)) => id,
+            _ => return Err(RenderError::new("emotes cannot be used outside of Twitch!")),
+        };
+
+        let emotes = Handle::current()
+            .block_on(self.get_channel_emotes(&twitch_id))
+            .map_err(|e| {
+                tracing::warn!("{:?}", e);
+                RenderError::new("Failed to get emotes")
+            })?;
+
+        let result = match action.as_str() {
+            "exists" => {
+                let name = params
+                    .next()
+                    .ok_or_else(|| RenderError::new("emote name not specified"))?;
+
+                Json::from(emotes.iter().any(|emote| emote.name == name))
+            }
+            "random" | "count" => {
+                let emotes = match params.next() {
+                    Some(provider) => {
+                        let provider = EmoteProvider::from_str(&provider).map_err(|_| {
+                            RenderError::new(format!("unknown emote provider {provider}"))
+                        })?;
+
+                        emotes
+                            .into_iter()
+                            .filter(|emote| emote.provider == provider)
+                            .collect()
+                    }
+                    None => emotes,
+                };
+
+                match action.as_str() {
+                    "count" => Json::from(emotes.len()),
+                    _ => {
+                        let emote = emotes
+                            .get(thread_rng().gen_range(0..emotes.len().max(1)))
+                            .ok_or_else(|| RenderError::new("channel has no emotes"))?;
+
+                        Json::from(emote.name.clone())
+                    }
+                }
+            }
+            _ => return Err(RenderError::new(format!("unknown emotes action {action}"))),
+        };
+
+        Ok(ScopedJson::Derived(result))
+    }
+}
+
+/// `{{supi}}` is the user's Supibot ID that was set with `set supibot`.
+/// `{{supi "data/origin/list" name="x"}}` requests the Supinic API with the hash as the query,
+/// with `{user}` in the path replaced by the ID, and returns the `data` of the response
+pub struct SupinicHelper {
+    pub db: Database,
+    pub supinic_api: SupinicApi,
+}
+
+impl HelperDef for SupinicHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
+            .expect("Failed to get command context");
+
+        let channel = self
+            .db
+            .get_channel(&context.channel)
+            .map_err(|e| RenderError::new(e.to_string()))?
+            .ok_or_else(|| RenderError::new("supi can only be used in a channel"))?;
+        let enabled = self
+            .db
+            .is_feature_enabled(channel.id, ChannelFeature::BetaHelpers)
+            .map_err(|e| RenderError::new(e.to_string()))?;
+        if !enabled {
+            return Err(RenderError::new(
+                "supi is a beta helper that is not enabled in this channel",
+            ));
         }
+
+        let get_supibot_id = || {
+            self.db
+                .get_supibot_id(context.user.id)
+                .map_err(|e| RenderError::new(format!("DB Error: {e}")))?
+                .ok_or_else(|| RenderError::new("Supibot ID not set, use `set supibot <id>`"))
+        };
+
+        let path = match h.param(0) {
+            Some(param) => param.value().render(),
+            None => return Ok(ScopedJson::Derived(Json::from(get_supibot_id()?))),
+        };
+        let path = match path.contains("{user}") {
+            true => path.replace("{user}", &get_supibot_id()?.to_string()),
+            false => path,
+        };
+
+        let query: Vec<(String, String)> = h
+            .hash()
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.value().render()))
+            .collect();
+
+        let data = Handle::current()
+            .block_on(self.supinic_api.get(&path, &query))
+            .map_err(|e| RenderError::new(e.to_string()))?;
+
+        Ok(ScopedJson::Derived(data))
     }
 }
 
+pub struct HttpHelper {
+    pub http_fetcher: HttpFetcher,
+}
+
 impl HelperDef for HttpHelper {
     fn call<'reg: 'rc, 'rc>(
         &self,
@@ -649,44 +1294,22 @@ impl HelperDef for HttpHelper {
 
         tracing::info!("Making a request to: {}", url);
 
-        let rt = Handle::current();
-
-        let response = rt.block_on(self.client.get(url).send());
-
-        match response {
-            Ok(response) => {
-                if response.status().is_success() {
-                    match response.headers().get(http::header::CONTENT_TYPE) {
-                        Some(content_type) => {
-                            let content_type = content_type.to_str().unwrap();
-
-                            const ALLOWED_CONTENT_TYPES: &[&str] =
-                                &["text/plain", "application/json"];
-
-                            if ALLOWED_CONTENT_TYPES
-                                .iter()
-                                .any(|t| content_type.starts_with(t))
-                            {
-                                let text = rt
-                                    .block_on(response.text())
-                                    .unwrap_or_else(|_| "<empty text>".to_owned());
+        let response = Handle::current()
+            .block_on(self.http_fetcher.fetch(Method::GET, &url))
+            .map_err(|e| RenderError::new(format!("{e:#}")))?;
 
-                                out.write(&text)?;
-
-                                Ok(())
-                            } else {
-                                Err(RenderError::new("Disallowed content type!"))
-                            }
-                        }
-                        None => Err(RenderError::new("server did not return content type!")),
-                    }
-                } else {
-                    out.write(&format!("HTTP status: {}", response.status()))?;
+        if !response.status.is_success() {
+            out.write(&format!("HTTP status: {}", response.status))?;
+            return Ok(());
+        }
 
-                    Ok(())
-                }
+        match response.content_type {
+            Some(_) if response.is_text() => {
+                out.write(&response.body)?;
+                Ok(())
             }
-            Err(e) => Err(RenderError::new(e.to_string())),
+            Some(_) => Err(RenderError::new("Disallowed content type!")),
+            None => Err(RenderError::new("server did not return content type!")),
         }
     }
 }
@@ -755,8 +1378,15 @@ pub fn trim_matches_helper(
     Ok(())
 }
 
+/// Keys that a single channel can store with `data_set`
+const MAX_TEMP_DATA_KEYS: u32 = 100;
+/// TTLs are limited to a year, values without a TTL are kept until they are replaced
+const MAX_TEMP_DATA_TTL: u64 = 365 * 24 * 3600;
+
+/// `{{data_set "key" "value" ttl=3600}}` stores a value in the channel, optionally expiring after
+/// `ttl` seconds
 pub struct SetTempData {
-    pub data: Arc<DashMap<String, String>>,
+    pub db: Database,
 }
 
 impl HelperDef for SetTempData {
@@ -768,44 +1398,54 @@ impl HelperDef for SetTempData {
         _: &mut RenderContext,
         _: &mut dyn Output,
     ) -> HelperResult {
-        let raw_params = h
-            .params()
-            .iter()
-            .map(|param| param.value().render())
-            .collect::<Vec<String>>()
-            .join(" ");
-
         let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
             .expect("Failed to get command context");
 
-        let mut params = raw_params.split_whitespace();
+        let mut params = h.params().iter().map(|param| param.value().render());
 
-        let key = format!(
-            "{}-{}-{}",
-            context.channel.get_platform_name().unwrap_or_default(),
-            context
-                .channel
-                .get_channel()
-                .unwrap_or(&context.display_name),
-            params
-                .next()
-                .ok_or_else(|| RenderError::new("key missing"))?
-        );
-
-        let value = params
+        let key = params
             .next()
-            .ok_or_else(|| RenderError::new("value missing"))?;
+            .filter(|key| !key.trim().is_empty())
+            .ok_or_else(|| RenderError::new("key missing"))?;
+        let value = params.collect::<Vec<String>>().join(" ");
+        if value.is_empty() {
+            return Err(RenderError::new("value missing"));
+        }
+
+        let ttl = match h.hash_get("ttl") {
+            Some(ttl) => Some(
+                ttl.value()
+                    .as_u64()
+                    .filter(|ttl| (1..=MAX_TEMP_DATA_TTL).contains(ttl))
+                    .map(|ttl| chrono::Duration::seconds(ttl as i64))
+                    .ok_or_else(|| {
+                        RenderError::new(format!(
+                            "ttl must be between 1 and {MAX_TEMP_DATA_TTL} seconds"
+                        ))
+                    })?,
+            ),
+            None => None,
+        };
+
+        let channel = self
+            .db
+            .get_channel(&context.channel)
+            .map_err(|e| RenderError::new(e.to_string()))?
+            .ok_or_else(|| RenderError::new("data can only be stored in a channel"))?;
 
-        tracing::info!("Set custom data {}: {}", key, value);
+        tracing::info!("Set custom data {key} in channel {}: {value}", channel.id);
 
-        self.data.insert(key, value.to_string());
+        self.db
+            .set_template_data(channel.id, &key, &value, ttl, MAX_TEMP_DATA_KEYS)
+            .map_err(|e| RenderError::new(e.to_string()))?;
 
         Ok(())
     }
 }
 
+/// `{{data_get "key"}}`, the keys of the channel are listed without a key
 pub struct GetTempData {
-    pub data: Arc<DashMap<String, String>>,
+    pub db: Database,
 }
 
 impl HelperDef for GetTempData {
@@ -820,38 +1460,23 @@ impl HelperDef for GetTempData {
         let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
             .expect("Failed to get command context");
 
-        let raw_params = h
-            .params()
-            .iter()
-            .map(|param| param.value().render())
-            .collect::<Vec<String>>()
-            .join(" ");
-
-        let mut params = raw_params.split_whitespace();
-
-        let response = match params.next() {
-            Some(key) => {
-                let key = format!(
-                    "{}-{}-{}",
-                    context.channel.get_platform_name().unwrap_or_default(),
-                    context
-                        .channel
-                        .get_channel()
-                        .unwrap_or(&context.display_name),
-                    key,
-                );
+        let channel = self
+            .db
+            .get_channel(&context.channel)
+            .map_err(|e| RenderError::new(e.to_string()))?
+            .ok_or_else(|| RenderError::new("data can only be stored in a channel"))?;
 
-                match self.data.get(&key) {
-                    Some(value) => value.value().to_string(),
-                    None => String::new(),
-                }
-            }
+        let response = match h.param(0).map(|param| param.value().render()) {
+            Some(key) => self
+                .db
+                .get_template_data(channel.id, &key)
+                .map_err(|e| RenderError::new(e.to_string()))?
+                .unwrap_or_default(),
             None => {
                 let keys = self
-                    .data
-                    .iter()
-                    .map(|e| e.key().as_str().to_string())
-                    .collect::<Vec<String>>();
+                    .db
+                    .get_template_data_keys(channel.id)
+                    .map_err(|e| RenderError::new(e.to_string()))?;
 
                 if keys.is_empty() {
                     return Err(RenderError::new("No data"));
@@ -868,6 +1493,8 @@ impl HelperDef for GetTempData {
 
 pub struct SayHelper {
     pub platform_handler: Arc<RwLock<PlatformHandler>>,
+    /// Sends the message as a `/me` action
+    pub action: bool,
 }
 
 impl HelperDef for SayHelper {
@@ -889,16 +1516,34 @@ impl HelperDef for SayHelper {
         let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
             .expect("Failed to get command context");
 
+        // The message is sent from a separate task, which is outside of the dry run scope
+        let name = if self.action {
+            "send_action"
+        } else {
+            "send_message"
+        };
+        if debug_report::skip_side_effect(name, params.clone()) {
+            return Ok(());
+        }
+
         let runtime = tokio::runtime::Handle::current();
 
         let platform_handler = self.platform_handler.clone();
+        let action = self.action;
         runtime.spawn(async move {
             let platform_handler_guard = platform_handler.read().await;
 
-            if let Err(e) = platform_handler_guard
-                .send_to_channel(context.channel, params)
-                .await
-            {
+            let result = if action {
+                platform_handler_guard
+                    .send_action_to_channel(context.channel, params)
+                    .await
+            } else {
+                platform_handler_guard
+                    .send_to_channel(context.channel, params)
+                    .await
+            };
+
+            if let Err(e) = result {
                 tracing::warn!("Failed sending message from inqury context: {}", e);
             }
         });
@@ -968,18 +1613,13 @@ impl HelperDef for CommercialHelper {
 
         let runtime = tokio::runtime::Handle::current();
 
-        let credentials = self.db.make_twitch_credentials(broadcaster_id);
-        let refreshing_credentials = RefreshingLoginCredentials::init(
-            get_client_id().unwrap(),
-            get_client_secret().unwrap(),
-            credentials,
-        );
-
-        runtime
-            .block_on(refreshing_credentials.get_credentials())
+        let helix_api = runtime
+            .block_on(get_broadcaster_helix_api(&self.db, &broadcaster_id))
             .map_err(|_| RenderError::new("streamer is not authorized"))?;
 
-        let helix_api = runtime.block_on(HelixApi::with_credentials(refreshing_credentials));
+        if debug_report::skip_side_effect("commercial", duration) {
+            return Ok(());
+        }
 
         runtime
             .block_on(helix_api.start_commercial(length))
@@ -1018,7 +1658,90 @@ impl HelperDef for JsonHelper {
     }
 }
 
-pub fn forsencode_encode_helper(
+/// `{{jsonpath (get url) "$.data[0].name"}}`, the value can be JSON text or an object
+pub struct JsonPathHelper;
+
+impl HelperDef for JsonPathHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<handlebars::ScopedJson<'reg, 'rc>, RenderError> {
+        let (value, path) = match (h.param(0), h.param(1)) {
+            (Some(value), Some(path)) => (value.value(), path.value().render()),
+            _ => return Err(RenderError::new("jsonpath needs a value and a path")),
+        };
+
+        let parsed;
+        let value = match value {
+            Json::String(text) => {
+                parsed = serde_json::from_str(text)
+                    .map_err(|e| RenderError::new(format!("Failed to parse json: {e}")))?;
+                &parsed
+            }
+            value => value,
+        };
+
+        json_path::select(value, &path)
+            .map(ScopedJson::Derived)
+            .map_err(RenderError::new)
+    }
+}
+
+/// Registered in place of helpers that need an API key which is not configured
+pub struct UnconfiguredHelper;
+
+impl HelperDef for UnconfiguredHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<handlebars::ScopedJson<'reg, 'rc>, RenderError> {
+        Err(RenderError::new(format!(
+            "{} is not configured on this instance",
+            h.name()
+        )))
+    }
+}
+
+/// `{{base64_encode text}}`, `{{morse_decode text}}` and so on for every codec
+pub struct CodecHelper {
+    pub codec: Codec,
+    pub decode: bool,
+}
+
+impl HelperDef for CodecHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        _: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let params = h
+            .params()
+            .iter()
+            .map(|param| param.value().render())
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let text = match self.decode {
+            true => self.codec.decode(&params),
+            false => self.codec.encode(&params),
+        }
+        .map_err(RenderError::new)?;
+
+        out.write(&text)?;
+        Ok(())
+    }
+}
+
+pub fn urlencode_helper(
     h: &Helper,
     _: &Handlebars,
     _: &Context,
@@ -1032,11 +1755,11 @@ pub fn forsencode_encode_helper(
         .collect::<Vec<String>>()
         .join(" ");
 
-    out.write(&forsencode::encode(&params))?;
+    out.write(&urlencoding::encode(&params))?;
     Ok(())
 }
 
-pub fn forsencode_decode_helper(
+pub fn escape_helper(
     h: &Helper,
     _: &Handlebars,
     _: &Context,
@@ -1050,8 +1773,6 @@ pub fn forsencode_decode_helper(
         .collect::<Vec<String>>()
         .join(" ");
 
-    let text = forsencode::decode(&params).map_err(RenderError::new)?;
-
-    out.write(&text)?;
+    out.write(&escape::escape_markdown(&params))?;
     Ok(())
 }
@@ -0,0 +1,150 @@
+//! `{{ paginate list 10 }}` splits long lists into pages that fit into a message.
+//! The page is picked with the last argument of the command, such as `!top 3`
+use super::InquiryContext;
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, JsonRender, RenderContext, RenderError, ScopedJson,
+};
+use serde_json::Value;
+
+const DEFAULT_PAGE_SIZE: u64 = 10;
+const DEFAULT_SEPARATOR: &str = ", ";
+
+pub struct PaginateHelper;
+
+impl HelperDef for PaginateHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let list = h
+            .param(0)
+            .ok_or_else(|| RenderError::new("paginate needs a list"))?
+            .value();
+
+        // Lists from `get` are JSON text
+        let items = match list {
+            Value::Array(items) => items.clone(),
+            Value::String(text) => {
+                serde_json::from_str(text).map_err(|_| RenderError::new("paginate needs a list"))?
+            }
+            _ => return Err(RenderError::new("paginate needs a list")),
+        };
+        let items: Vec<String> = items.iter().map(|item| item.render()).collect();
+
+        let page_size = match h.param(1) {
+            Some(size) => size
+                .value()
+                .as_u64()
+                .filter(|size| *size > 0)
+                .ok_or_else(|| RenderError::new("the page size has to be a positive number"))?,
+            None => DEFAULT_PAGE_SIZE,
+        };
+        let separator = h
+            .hash_get("separator")
+            .map(|separator| separator.value().render())
+            .unwrap_or_else(|| DEFAULT_SEPARATOR.to_owned());
+
+        let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
+            .map_err(|_| RenderError::new("paginate can only be used in commands"))?;
+        let page = get_requested_page(&context.arguments);
+
+        let output = paginate(
+            &items,
+            page_size as usize,
+            page,
+            context.command.as_deref(),
+            &separator,
+        )
+        .map_err(RenderError::new)?;
+
+        Ok(ScopedJson::Derived(Value::String(output)))
+    }
+}
+
+/// The last argument when it's a page number, the first page otherwise
+fn get_requested_page(arguments: &[String]) -> usize {
+    arguments
+        .last()
+        .and_then(|argument| argument.parse().ok())
+        .filter(|page| *page > 0)
+        .unwrap_or(1)
+}
+
+/// Pages start at 1. The hint with the next page is only shown when the command is known
+fn paginate(
+    items: &[String],
+    page_size: usize,
+    page: usize,
+    command: Option<&str>,
+    separator: &str,
+) -> Result<String, String> {
+    let pages = items.len().div_ceil(page_size);
+
+    if pages <= 1 {
+        return Ok(items.join(separator));
+    }
+    if page > pages {
+        return Err(format!("there are only {pages} pages"));
+    }
+
+    let start = (page - 1) * page_size;
+    let end = (start + page_size).min(items.len());
+    let text = items[start..end].join(separator);
+
+    match command {
+        Some(command) if page < pages => Ok(format!(
+            "{text} (page {page}/{pages} — use {command} {})",
+            page + 1
+        )),
+        _ => Ok(format!("{text} (page {page}/{pages})")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_requested_page, paginate};
+    use pretty_assertions::assert_eq;
+
+    fn items(count: usize) -> Vec<String> {
+        (1..=count).map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn paginates_lists() {
+        let items = items(12);
+
+        assert_eq!(
+            paginate(&items, 5, 1, Some("!top"), ", "),
+            Ok("1, 2, 3, 4, 5 (page 1/3 — use !top 2)".to_owned())
+        );
+        assert_eq!(
+            paginate(&items, 5, 3, Some("!top"), ", "),
+            Ok("11, 12 (page 3/3)".to_owned())
+        );
+        assert_eq!(
+            paginate(&items, 5, 2, None, " | "),
+            Ok("6 | 7 | 8 | 9 | 10 (page 2/3)".to_owned())
+        );
+        assert!(paginate(&items, 5, 4, Some("!top"), ", ").is_err());
+    }
+
+    #[test]
+    fn skips_pages_for_short_lists() {
+        assert_eq!(
+            paginate(&items(3), 5, 1, Some("!top"), ", "),
+            Ok("1, 2, 3".to_owned())
+        );
+        assert_eq!(paginate(&[], 5, 1, None, ", "), Ok(String::new()));
+    }
+
+    #[test]
+    fn gets_page_from_arguments() {
+        assert_eq!(get_requested_page(&[]), 1);
+        assert_eq!(get_requested_page(&["user".to_owned(), "3".to_owned()]), 3);
+        assert_eq!(get_requested_page(&["3".to_owned(), "user".to_owned()]), 1);
+        assert_eq!(get_requested_page(&["0".to_owned()]), 1);
+    }
+}
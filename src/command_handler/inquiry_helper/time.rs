@@ -0,0 +1,167 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use chrono_tz::Tz;
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+};
+use std::fmt::{Display, Write};
+
+use super::InquiryContext;
+
+const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M %Z";
+
+pub fn countdown_helper(
+    h: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let date = parse_date_param(h)?;
+    let duration = date - Utc::now();
+
+    let text = if duration < Duration::zero() {
+        format!("{} ago", format_duration(-duration))
+    } else {
+        format!("in {}", format_duration(duration))
+    };
+
+    out.write(&text)?;
+    Ok(())
+}
+
+/// Formats a date in the given timezone, falling back to the user's timezone and then UTC.
+///
+/// Usage: `{{timestamp date format timezone}}`, where `date` is an RFC 3339 date or `now`
+pub struct TimestampHelper;
+
+impl HelperDef for TimestampHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        ctx: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
+            .expect("Failed to get command context");
+
+        let date = parse_date_param(h)?;
+
+        let format = h
+            .param(1)
+            .map(|param| param.value().render())
+            .unwrap_or_else(|| DEFAULT_TIMESTAMP_FORMAT.to_owned());
+
+        let raw_timezone = match h.param(2) {
+            Some(param) => Some(param.value().render()),
+            None => context.timezone,
+        };
+
+        let timezone = match raw_timezone {
+            Some(raw_timezone) => raw_timezone
+                .parse::<Tz>()
+                .map_err(|_| RenderError::new(format!("invalid timezone {raw_timezone}")))?,
+            None => Tz::UTC,
+        };
+
+        out.write(&format_timestamp(&date.with_timezone(&timezone), &format)?)?;
+        Ok(())
+    }
+}
+
+/// Unlike `format().to_string()`, fails instead of panicking on invalid specifiers such as `%`
+fn format_timestamp<T: TimeZone>(date: &DateTime<T>, format: &str) -> Result<String, RenderError>
+where
+    T::Offset: Display,
+{
+    let mut text = String::new();
+    write!(text, "{}", date.format(format))
+        .map_err(|_| RenderError::new(format!("invalid timestamp format {format}")))?;
+
+    Ok(text)
+}
+
+fn parse_date_param(h: &Helper) -> Result<DateTime<Utc>, RenderError> {
+    let raw_date = h
+        .param(0)
+        .map(|param| param.value().render())
+        .ok_or_else(|| RenderError::new("date not specified"))?;
+
+    if raw_date == "now" {
+        return Ok(Utc::now());
+    }
+
+    DateTime::parse_from_rfc3339(&raw_date)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|_| RenderError::new(format!("invalid date {raw_date}, expected RFC 3339")))
+}
+
+/// Formats the duration using its two most significant units, e.g. "3 days 4 hours"
+pub fn format_duration(duration: Duration) -> String {
+    let units = [
+        (duration.num_days() / 365, "year"),
+        (duration.num_days() % 365, "day"),
+        (duration.num_hours() % 24, "hour"),
+        (duration.num_minutes() % 60, "minute"),
+        (duration.num_seconds() % 60, "second"),
+    ];
+
+    let parts = units
+        .into_iter()
+        .skip_while(|(amount, _)| *amount == 0)
+        .take(2)
+        .filter(|(amount, _)| *amount != 0)
+        .map(|(amount, unit)| match amount {
+            1 => format!("{amount} {unit}"),
+            _ => format!("{amount} {unit}s"),
+        })
+        .collect::<Vec<_>>();
+
+    if parts.is_empty() {
+        "0 seconds".to_owned()
+    } else {
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_duration, format_timestamp};
+    use chrono::{Duration, TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn format_days_hours() {
+        let duration = Duration::days(3) + Duration::hours(4) + Duration::minutes(5);
+        assert_eq!(format_duration(duration), "3 days 4 hours");
+    }
+
+    #[test]
+    fn format_skips_zero_units() {
+        let duration = Duration::hours(1) + Duration::seconds(30);
+        assert_eq!(format_duration(duration), "1 hour");
+    }
+
+    #[test]
+    fn format_years() {
+        let duration = Duration::days(400) + Duration::hours(2);
+        assert_eq!(format_duration(duration), "1 year 35 days");
+    }
+
+    #[test]
+    fn format_zero() {
+        assert_eq!(format_duration(Duration::zero()), "0 seconds");
+    }
+
+    #[test]
+    fn rejects_invalid_timestamp_format() {
+        let date = Utc.with_ymd_and_hms(2023, 11, 1, 12, 30, 0).unwrap();
+
+        assert_eq!(
+            format_timestamp(&date, "%Y-%m-%d %H:%M").unwrap(),
+            "2023-11-01 12:30"
+        );
+        assert!(format_timestamp(&date, "%").is_err());
+    }
+}
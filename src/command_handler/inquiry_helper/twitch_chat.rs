@@ -0,0 +1,114 @@
+use std::{str::FromStr, sync::Arc};
+
+use crate::{
+    command_handler::{platform_handler::PlatformHandler, twitch_api::model::AnnouncementColor},
+    platform::Permissions,
+};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, JsonRender, Output, RenderContext,
+    RenderError,
+};
+use tokio::sync::RwLock;
+
+use super::InquiryContext;
+
+/// Sends a Helix announcement, for example `{{announce "Stream starting!" color="purple"}}`
+pub struct TwitchAnnounceHelper {
+    pub platform_handler: Arc<RwLock<PlatformHandler>>,
+}
+
+impl HelperDef for TwitchAnnounceHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        ctx: &Context,
+        _: &mut RenderContext,
+        _: &mut dyn Output,
+    ) -> HelperResult {
+        let context = get_moderator_context(ctx, "announce")?;
+
+        let message = h
+            .params()
+            .iter()
+            .map(|param| param.value().render())
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let color = h
+            .hash_get("color")
+            .map(|color| {
+                AnnouncementColor::from_str(&color.value().render())
+                    .map_err(|_| RenderError::new("invalid announcement color"))
+            })
+            .transpose()?;
+
+        let runtime = tokio::runtime::Handle::current();
+
+        runtime
+            .block_on(async {
+                self.platform_handler
+                    .read()
+                    .await
+                    .send_announcement(&context.channel, message, color)
+                    .await
+            })
+            .map_err(|e| {
+                tracing::warn!("{:?}", e);
+                RenderError::new("Failed to send announcement")
+            })
+    }
+}
+
+pub struct TwitchShoutoutHelper {
+    pub platform_handler: Arc<RwLock<PlatformHandler>>,
+}
+
+impl HelperDef for TwitchShoutoutHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        ctx: &Context,
+        _: &mut RenderContext,
+        _: &mut dyn Output,
+    ) -> HelperResult {
+        let context = get_moderator_context(ctx, "shoutout")?;
+
+        let user_name = h
+            .param(0)
+            .map(|param| param.value().render())
+            .map(|name| name.trim_start_matches('@').to_owned())
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| RenderError::new("user name not specified"))?;
+
+        let runtime = tokio::runtime::Handle::current();
+
+        runtime
+            .block_on(async {
+                self.platform_handler
+                    .read()
+                    .await
+                    .shoutout(&context.channel, &user_name)
+                    .await
+            })
+            .map_err(|e| {
+                tracing::warn!("{:?}", e);
+                RenderError::new("Failed to send shoutout")
+            })
+    }
+}
+
+/// These helpers act with the bot's moderator privileges, so they are limited to moderators
+fn get_moderator_context(ctx: &Context, name: &str) -> Result<InquiryContext, RenderError> {
+    let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
+        .expect("Failed to get command context");
+
+    if context.permissions < Some(Permissions::ChannelMod) {
+        return Err(RenderError::new(format!(
+            "{name} can only be used by moderators on Twitch!"
+        )));
+    }
+
+    Ok(context)
+}
@@ -0,0 +1,84 @@
+use crate::{
+    command_handler::{platform_handler::TwitchApi, twitch_api::get_followage},
+    database::Database,
+    platform::ChannelIdentifier,
+};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+};
+
+use super::{time::format_duration, InquiryContext};
+
+pub struct TwitchFollowageHelper {
+    pub db: Database,
+    pub twitch_api: TwitchApi,
+}
+
+impl HelperDef for TwitchFollowageHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        ctx: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
+            .expect("Failed to get command context");
+
+        let broadcaster_id = match context.channel {
+            ChannelIdentifier::TwitchChannel((id, _)) => id,
+            _ => {
+                return Err(RenderError::new(
+                    "followage cannot be used outside of Twitch!",
+                ));
+            }
+        };
+
+        let user_name = h
+            .param(0)
+            .map(|param| match param.relative_path() {
+                Some(path) => path.to_owned(),
+                None => param.render(),
+            })
+            .filter(|name| !name.is_empty());
+
+        let runtime = tokio::runtime::Handle::current();
+
+        let user_id = match user_name {
+            Some(user_name) => {
+                let users = runtime
+                    .block_on(
+                        self.twitch_api
+                            .helix_api
+                            .get_users(Some(&[user_name.as_str()]), None),
+                    )
+                    .map_err(|e| RenderError::new(e.to_string()))?;
+
+                users
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| RenderError::new("user not found"))?
+                    .id
+            }
+            None => context
+                .user
+                .twitch_id
+                .ok_or_else(|| RenderError::new("user not specified"))?,
+        };
+
+        let followage = runtime
+            .block_on(get_followage(&self.db, &broadcaster_id, &user_id))
+            .map_err(|e| {
+                tracing::warn!("{:?}", e);
+                RenderError::new("Failed to get followage")
+            })?;
+
+        match followage {
+            Some(duration) => out.write(&format_duration(duration))?,
+            None => out.write("not following")?,
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{command_handler::platform_handler::TwitchApi, platform::ChannelIdentifier};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    ScopedJson,
+};
+use serde_json::Value;
+use tokio::runtime::Handle;
+
+use super::{time::format_duration, InquiryContext};
+
+#[derive(Clone, Copy)]
+pub enum StreamInfoKind {
+    Uptime,
+    Title,
+    Game,
+}
+
+pub struct TwitchStreamHelper {
+    pub twitch_api: TwitchApi,
+    pub kind: StreamInfoKind,
+}
+
+impl HelperDef for TwitchStreamHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        ctx: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let runtime = Handle::current();
+        let broadcaster_id = get_broadcaster_id(&self.twitch_api, &runtime, h, ctx)?;
+
+        let info = runtime
+            .block_on(self.twitch_api.get_stream_info(&broadcaster_id))
+            .map_err(|e| {
+                tracing::warn!("{:?}", e);
+                RenderError::new("Failed to get stream info")
+            })?;
+
+        let output = match self.kind {
+            StreamInfoKind::Uptime => match info.uptime() {
+                Some(uptime) => format_duration(uptime),
+                None => "offline".to_owned(),
+            },
+            StreamInfoKind::Title => info.channel.title,
+            StreamInfoKind::Game => info.channel.game_name,
+        };
+
+        out.write(&output)?;
+
+        Ok(())
+    }
+}
+
+/// Returns a boolean, so that it can be used in conditions like `{{#if (is_live)}}`
+pub struct TwitchLiveHelper {
+    pub twitch_api: TwitchApi,
+}
+
+impl HelperDef for TwitchLiveHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let runtime = Handle::current();
+        let broadcaster_id = get_broadcaster_id(&self.twitch_api, &runtime, h, ctx)?;
+
+        let live = runtime
+            .block_on(self.twitch_api.is_live(&broadcaster_id))
+            .map_err(|e| {
+                tracing::warn!("{:?}", e);
+                RenderError::new("Failed to get stream info")
+            })?;
+
+        Ok(ScopedJson::Derived(Value::Bool(live)))
+    }
+}
+
+/// The channel given as the first parameter, or the current one
+fn get_broadcaster_id(
+    twitch_api: &TwitchApi,
+    runtime: &Handle,
+    h: &Helper,
+    ctx: &Context,
+) -> Result<String, RenderError> {
+    let channel_name = h
+        .param(0)
+        .map(|param| match param.relative_path() {
+            Some(path) => path.to_owned(),
+            None => param.render(),
+        })
+        .filter(|name| !name.is_empty());
+
+    match channel_name {
+        Some(channel_name) => {
+            let users = runtime
+                .block_on(
+                    twitch_api
+                        .helix_api
+                        .get_users(Some(&[channel_name.as_str()]), None),
+                )
+                .map_err(|e| RenderError::new(e.to_string()))?;
+
+            Ok(users
+                .into_iter()
+                .next()
+                .ok_or_else(|| RenderError::new("channel not found"))?
+                .id)
+        }
+        None => {
+            let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
+                .expect("Failed to get command context");
+
+            match context.channel {
+                ChannelIdentifier::TwitchChannel((id, _)) => Ok(id),
+                _ => Err(RenderError::new("channel not specified")),
+            }
+        }
+    }
+}
@@ -1,4 +1,15 @@
-use crate::{command_handler::platform_handler::TwitchApi, platform::ChannelIdentifier};
+use crate::{
+    command_handler::{
+        debug_report::skip_side_effect,
+        outgoing_webhooks::{BotEvent, WebhookDispatcher},
+        platform_handler::TwitchApi,
+    },
+    database::{
+        models::{ModerationAction, NewModerationLogEntry},
+        Database,
+    },
+    platform::{ChannelIdentifier, UserIdentifier},
+};
 use handlebars::{
     Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
 };
@@ -7,7 +18,9 @@ use tracing::debug;
 use super::InquiryContext;
 
 pub struct TwitchTimeoutHelper {
+    pub db: Database,
     pub twitch_api: TwitchApi,
+    pub webhook_dispatcher: WebhookDispatcher,
 }
 
 impl HelperDef for TwitchTimeoutHelper {
@@ -39,8 +52,8 @@ impl HelperDef for TwitchTimeoutHelper {
         let context = serde_json::from_value::<InquiryContext>(ctx.data().clone())
             .expect("Failed to get command context");
 
-        let broadcaster_id = match context.channel {
-            ChannelIdentifier::TwitchChannel((id, _)) => id,
+        let broadcaster_id = match &context.channel {
+            ChannelIdentifier::TwitchChannel((id, _)) => id.clone(),
             _ => {
                 return Err(RenderError::new(
                     "timeout cannot be used outside of Twitch!",
@@ -48,25 +61,75 @@ impl HelperDef for TwitchTimeoutHelper {
             }
         };
 
+        let input = serde_json::json!({ "user": name, "duration": length }).to_string();
+        if skip_side_effect("timeout", input) {
+            return Ok(());
+        }
+
         let api = self.twitch_api.clone();
 
         let runtime = tokio::runtime::Handle::current();
 
-        runtime
-            .block_on(async move {
+        let user_id = runtime
+            .block_on(async {
+                let user = api.helix_api.get_user_by_login(&name).await?;
                 api.helix_api
-                    .ban_user_by_name(&broadcaster_id, &name, Some(length))
-                    .await
+                    .ban_user(&broadcaster_id, &user.id, Some(length), None)
+                    .await?;
+                anyhow::Ok(user.id)
             })
             .map_err(|e| {
                 tracing::warn!("{:?}", e);
                 RenderError::new("Failed to timeout user")
             })?;
 
+        self.log_timeout(&context.channel, context.user.id, user_id, length);
+
+        self.webhook_dispatcher.dispatch(
+            &context.channel,
+            BotEvent::UserTimedOut {
+                user: name,
+                duration: length,
+            },
+        );
+
         Ok(())
     }
 }
 
+impl TwitchTimeoutHelper {
+    fn log_timeout(
+        &self,
+        channel: &ChannelIdentifier,
+        moderator_id: u64,
+        user_id: String,
+        length: i32,
+    ) {
+        let channel_id = match self.db.get_channel(channel) {
+            Ok(Some(channel)) => channel.id,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("Could not get channel: {e}");
+                return;
+            }
+        };
+
+        let target = UserIdentifier::TwitchID(user_id).to_string();
+        let action = ModerationAction::Timeout.to_string();
+
+        if let Err(e) = self.db.add_moderation_log_entry(NewModerationLogEntry {
+            channel_id,
+            moderator_id: Some(moderator_id),
+            target: &target,
+            action: &action,
+            duration: u32::try_from(length).ok(),
+            reason: None,
+        }) {
+            tracing::warn!("Could not log timeout: {e}");
+        }
+    }
+}
+
 fn collect_params(h: &Helper) -> Vec<String> {
     h.params()
         .iter()
@@ -0,0 +1,169 @@
+//! A subset of JSONPath for pulling fields out of API responses, available as the `jsonpath`
+//! template helper and `utils.json_path` in Hebi.
+//!
+//! Supported are the root `$`, fields (`.name` or `['name']`), array indices (`[0]`, `[-1]` for
+//! the last element) and wildcards (`.*` or `[*]`). Paths without a wildcard select a single
+//! value or `null`, paths with one select an array of every match
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Field(String),
+    Index(i64),
+    Wildcard,
+}
+
+pub fn select(value: &Value, path: &str) -> Result<Value, String> {
+    let segments = parse_path(path)?;
+
+    let mut matches = vec![value];
+    for segment in &segments {
+        matches = matches
+            .into_iter()
+            .flat_map(|value| select_segment(value, segment))
+            .collect();
+    }
+
+    if segments.contains(&Segment::Wildcard) {
+        Ok(Value::Array(matches.into_iter().cloned().collect()))
+    } else {
+        Ok(matches.first().copied().cloned().unwrap_or(Value::Null))
+    }
+}
+
+fn select_segment<'a>(value: &'a Value, segment: &Segment) -> Vec<&'a Value> {
+    match (segment, value) {
+        (Segment::Field(name), Value::Object(fields)) => fields.get(name).into_iter().collect(),
+        (Segment::Index(index), Value::Array(items)) => {
+            let index = match *index < 0 {
+                true => items.len() as i64 + index,
+                false => *index,
+            };
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| items.get(index))
+                .into_iter()
+                .collect()
+        }
+        (Segment::Wildcard, Value::Array(items)) => items.iter().collect(),
+        (Segment::Wildcard, Value::Object(fields)) => fields.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn parse_path(path: &str) -> Result<Vec<Segment>, String> {
+    let path = path.trim();
+    let mut rest = path.strip_prefix('$').unwrap_or(path);
+
+    // `data.name` is the same as `$.data.name`
+    let mut segments = Vec::new();
+    if !rest.is_empty() && !rest.starts_with(['.', '[']) {
+        let (segment, remaining) = parse_field(rest)?;
+        segments.push(segment);
+        rest = remaining;
+    }
+
+    while let Some(c) = rest.chars().next() {
+        let (segment, remaining) = match c {
+            '.' if rest.starts_with("..") => {
+                return Err("recursive descent (..) is not supported".to_owned())
+            }
+            '.' => parse_field(&rest[1..])?,
+            '[' => parse_bracket(&rest[1..])?,
+            _ => return Err(format!("unexpected {c} in path {path}")),
+        };
+
+        segments.push(segment);
+        rest = remaining;
+    }
+
+    Ok(segments)
+}
+
+/// `name` or `*` after a dot
+fn parse_field(input: &str) -> Result<(Segment, &str), String> {
+    let end = input.find(['.', '[']).unwrap_or(input.len());
+    let (name, rest) = input.split_at(end);
+
+    match name {
+        "" => Err("missing field name in path".to_owned()),
+        "*" => Ok((Segment::Wildcard, rest)),
+        name => Ok((Segment::Field(name.to_owned()), rest)),
+    }
+}
+
+/// `0]`, `*]` or `'name']` after an opening bracket
+fn parse_bracket(input: &str) -> Result<(Segment, &str), String> {
+    if let Some(quote) = input.chars().next().filter(|c| *c == '\'' || *c == '"') {
+        let end = input[1..]
+            .find(quote)
+            .ok_or_else(|| "unterminated quote in path".to_owned())?;
+        let name = &input[1..end + 1];
+        let rest = input[end + 2..]
+            .strip_prefix(']')
+            .ok_or_else(|| "missing ] in path".to_owned())?;
+
+        return Ok((Segment::Field(name.to_owned()), rest));
+    }
+
+    let end = input
+        .find(']')
+        .ok_or_else(|| "missing ] in path".to_owned())?;
+    let (content, rest) = (input[..end].trim(), &input[end + 1..]);
+
+    match content {
+        "*" => Ok((Segment::Wildcard, rest)),
+        content => content
+            .parse()
+            .map(|index| (Segment::Index(index), rest))
+            .map_err(|_| format!("invalid index {content} in path")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::select;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn selects_values() {
+        let value = json!({
+            "data": [
+                { "name": "first", "tags": ["a", "b"] },
+                { "name": "second", "tags": [] }
+            ],
+            "total count": 2
+        });
+
+        assert_eq!(select(&value, "$.data[0].name"), Ok(json!("first")));
+        assert_eq!(select(&value, "data[-1].name"), Ok(json!("second")));
+        assert_eq!(select(&value, "$['total count']"), Ok(json!(2)));
+        assert_eq!(select(&value, "$.data[0].tags[1]"), Ok(json!("b")));
+        assert_eq!(select(&value, "$"), Ok(value.clone()));
+        assert_eq!(select(&value, "$.data[5].name"), Ok(json!(null)));
+        assert_eq!(select(&value, "$.missing.field"), Ok(json!(null)));
+    }
+
+    #[test]
+    fn selects_wildcards() {
+        let value = json!({ "data": [{ "name": "first" }, { "name": "second" }] });
+
+        assert_eq!(
+            select(&value, "$.data[*].name"),
+            Ok(json!(["first", "second"]))
+        );
+        assert_eq!(select(&value, "$.missing.*"), Ok(json!([])));
+    }
+
+    #[test]
+    fn rejects_invalid_paths() {
+        let value = json!({});
+
+        assert!(select(&value, "$..name").is_err());
+        assert!(select(&value, "$.data[x]").is_err());
+        assert!(select(&value, "$.data[0").is_err());
+        assert!(select(&value, "$['name").is_err());
+        assert!(select(&value, "$.").is_err());
+    }
+}
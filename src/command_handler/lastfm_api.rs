@@ -1,7 +1,7 @@
 use anyhow::anyhow;
 use http::StatusCode;
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -19,15 +19,68 @@ impl LastFMApi {
     }
 
     pub async fn get_recent_tracks(&self, user: &str) -> anyhow::Result<RecentTracksResponse> {
+        self.get(&[("method", "user.getrecenttracks"), ("user", user)])
+            .await
+    }
+
+    pub async fn get_top_artists(
+        &self,
+        user: &str,
+        period: Period,
+        limit: u32,
+    ) -> anyhow::Result<Vec<TopArtist>> {
+        let response: TopArtistsResponse = self
+            .get(&[
+                ("method", "user.gettopartists"),
+                ("user", user),
+                ("period", period.api_name()),
+                ("limit", &limit.to_string()),
+            ])
+            .await?;
+
+        Ok(response.topartists.artist)
+    }
+
+    /// The total amount of scrobbles of the user
+    pub async fn get_playcount(&self, user: &str) -> anyhow::Result<u64> {
+        let response: UserInfoResponse = self
+            .get(&[("method", "user.getinfo"), ("user", user)])
+            .await?;
+
+        Ok(response.user.playcount.parse()?)
+    }
+
+    pub async fn get_album_info(&self, artist: &str, album: &str) -> anyhow::Result<AlbumInfo> {
+        let response: AlbumInfoResponse = self
+            .get(&[
+                ("method", "album.getinfo"),
+                ("artist", artist),
+                ("album", album),
+                ("autocorrect", "1"),
+            ])
+            .await?;
+
+        Ok(response.album)
+    }
+
+    pub async fn get_artist_info(&self, artist: &str) -> anyhow::Result<ArtistInfo> {
+        let response: ArtistInfoResponse = self
+            .get(&[
+                ("method", "artist.getinfo"),
+                ("artist", artist),
+                ("autocorrect", "1"),
+            ])
+            .await?;
+
+        Ok(response.artist)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, params: &[(&str, &str)]) -> anyhow::Result<T> {
         let response = self
             .client
             .get("https://ws.audioscrobbler.com/2.0/?")
-            .query(&[
-                ("method", "user.getrecenttracks"),
-                ("user", user),
-                ("api_key", &*self.api_key),
-                ("format", "json"),
-            ])
+            .query(params)
+            .query(&[("api_key", &*self.api_key), ("format", "json")])
             .send()
             .await?;
 
@@ -40,6 +93,44 @@ impl LastFMApi {
     }
 }
 
+/// The time ranges that Last.fm has charts for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Period {
+    #[default]
+    Overall,
+    Week,
+    Month,
+    ThreeMonths,
+    SixMonths,
+    Year,
+}
+
+impl Period {
+    /// Accepts the Last.fm names such as `7day` and plain ones such as `week`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "overall" | "all" => Some(Period::Overall),
+            "7day" | "week" => Some(Period::Week),
+            "1month" | "month" => Some(Period::Month),
+            "3month" => Some(Period::ThreeMonths),
+            "6month" => Some(Period::SixMonths),
+            "12month" | "year" => Some(Period::Year),
+            _ => None,
+        }
+    }
+
+    fn api_name(&self) -> &'static str {
+        match self {
+            Period::Overall => "overall",
+            Period::Week => "7day",
+            Period::Month => "1month",
+            Period::ThreeMonths => "3month",
+            Period::SixMonths => "6month",
+            Period::Year => "12month",
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RecentTracksResponse {
@@ -86,3 +177,126 @@ pub struct Album {
     #[serde(rename = "#text")]
     pub text: String,
 }
+
+// Last.fm sends all numbers as strings
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TopArtistsResponse {
+    pub topartists: TopArtists,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TopArtists {
+    pub artist: Vec<TopArtist>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TopArtist {
+    pub name: String,
+    pub playcount: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserInfoResponse {
+    pub user: UserInfo,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserInfo {
+    pub playcount: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlbumInfoResponse {
+    pub album: AlbumInfo,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlbumInfo {
+    pub name: String,
+    pub artist: String,
+    #[serde(default)]
+    pub image: Vec<Image>,
+}
+
+impl AlbumInfo {
+    /// The URL of the largest cover, albums without one have empty URLs
+    pub fn cover_url(&self) -> Option<&str> {
+        self.image
+            .iter()
+            .rev()
+            .map(|image| image.url.as_str())
+            .find(|url| !url.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Image {
+    #[serde(rename = "#text")]
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArtistInfoResponse {
+    pub artist: ArtistInfo,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArtistInfo {
+    pub name: String,
+    pub stats: ArtistStats,
+    #[serde(default)]
+    pub tags: Tags,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArtistStats {
+    pub listeners: String,
+    pub playcount: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Tags {
+    #[serde(default)]
+    pub tag: Vec<Tag>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Tag {
+    pub name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AlbumInfoResponse, Period};
+
+    #[test]
+    fn parses_periods() {
+        assert_eq!(Period::parse("week"), Some(Period::Week));
+        assert_eq!(Period::parse("12month"), Some(Period::Year));
+        assert_eq!(Period::parse("decade"), None);
+    }
+
+    #[test]
+    fn finds_album_cover() {
+        let response: AlbumInfoResponse = serde_json::from_str(
+            r##"{"album": {
+                "name": "Believe",
+                "artist": "Cher",
+                "url": "https://www.last.fm/music/Cher/Believe",
+                "image": [
+                    {"#text": "https://example.com/34s.png", "size": "small"},
+                    {"#text": "https://example.com/300x300.png", "size": "extralarge"},
+                    {"#text": "", "size": "mega"}
+                ],
+                "playcount": "1234"
+            }}"##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.album.cover_url(),
+            Some("https://example.com/300x300.png")
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use anyhow::anyhow;
-use reqwest::Client;
+use reqwest::{Client, Url};
 use serde_json::Value;
 use std::sync::Arc;
 
+#[derive(Clone)]
 pub struct LingvaApi {
     client: Client,
     url: Arc<String>,
@@ -22,7 +23,13 @@ impl LingvaApi {
         target: &str,
         query: &str,
     ) -> anyhow::Result<String> {
-        let request_url = format!("{}/api/v1/{}/{}/{}", self.url, source, target, query);
+        // The text is a part of the path, so it has to be encoded
+        let mut request_url = Url::parse(&self.url)?;
+        request_url
+            .path_segments_mut()
+            .map_err(|_| anyhow!("Invalid Lingva instance URL"))?
+            .pop_if_empty()
+            .extend(["api", "v1", source, target, query]);
 
         let response = self.client.get(request_url).send().await?;
 
@@ -45,9 +52,65 @@ impl LingvaApi {
     }
 }
 
+/// Arguments of translations, where the languages can be set with `from:` and `to:`
+#[derive(Debug, PartialEq, Eq)]
+pub struct TranslationArgs {
+    pub source: String,
+    pub target: String,
+    pub text: String,
+}
+
+impl TranslationArgs {
+    /// Translates from any language to `default_target`, unless specified otherwise
+    pub fn parse(args: &[&str], default_target: &str) -> Self {
+        let mut source = String::from("auto");
+        let mut target = default_target.to_owned();
+
+        let text = args
+            .iter()
+            .filter(|arg| {
+                if let Some(source_lang) = arg.strip_prefix("from:") {
+                    source = source_lang.to_string();
+                    false
+                } else if let Some(target_lang) = arg.strip_prefix("to:") {
+                    target = target_lang.to_string();
+                    false
+                } else {
+                    true
+                }
+            })
+            .copied()
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        Self {
+            source,
+            target,
+            text,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::LingvaApi;
+    use super::{LingvaApi, TranslationArgs};
+
+    #[test]
+    fn parses_translation_args() {
+        let args = TranslationArgs::parse(&["to:uk", "good", "morning"], "en");
+        assert_eq!(
+            args,
+            TranslationArgs {
+                source: "auto".to_owned(),
+                target: "uk".to_owned(),
+                text: "good morning".to_owned(),
+            }
+        );
+
+        let args = TranslationArgs::parse(&["from:de"], "en");
+        assert_eq!(args.target, "en");
+        assert!(args.text.is_empty());
+    }
 
     const TEST_URL: &str = "https://lingva.ml/";
 
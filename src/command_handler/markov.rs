@@ -0,0 +1,198 @@
+//! Imitation sentences generated from the chat messages of channels that enabled the `markov` setting
+use crate::database::{cache::TtlCache, models::NewMarkovMessage, Database, DatabaseError};
+use crate::platform::UserIdentifier;
+use rand::{seq::SliceRandom, thread_rng};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// The model of a channel is bounded to its newest messages
+pub const MAX_MESSAGES_PER_CHANNEL: i64 = 20_000;
+/// Messages that are this long are usually copypastas, which would dominate the sentences
+const MAX_MESSAGE_LENGTH: usize = 300;
+const MAX_SENTENCE_WORDS: usize = 30;
+const GENERATION_ATTEMPTS: usize = 10;
+/// Old messages are deleted every this many recorded messages
+const PRUNE_INTERVAL: u64 = 500;
+const OPT_OUT_CACHE_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone)]
+pub struct Markov {
+    db: Database,
+    opt_out_cache: Arc<TtlCache<u64, bool>>,
+    recorded: Arc<AtomicU64>,
+}
+
+impl Markov {
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            opt_out_cache: Arc::new(TtlCache::new(OPT_OUT_CACHE_TTL)),
+            recorded: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Messages of users that opted out are not recorded
+    pub fn record(
+        &self,
+        channel_id: u64,
+        user: &UserIdentifier,
+        text: &str,
+    ) -> Result<(), DatabaseError> {
+        let text = text.trim();
+        if text.is_empty() || text.chars().count() > MAX_MESSAGE_LENGTH {
+            return Ok(());
+        }
+
+        let user = self.db.get_or_create_user(user)?;
+        if self.is_opted_out(user.id)? {
+            return Ok(());
+        }
+
+        self.db.add_markov_message(NewMarkovMessage {
+            channel_id,
+            user_id: user.id,
+            text,
+        })?;
+
+        if self.recorded.fetch_add(1, Ordering::Relaxed) % PRUNE_INTERVAL == 0 {
+            self.db
+                .prune_markov_messages(channel_id, MAX_MESSAGES_PER_CHANNEL)?;
+        }
+
+        Ok(())
+    }
+
+    /// A sentence from the messages of the channel, only of the given user if there is one
+    pub fn generate(
+        &self,
+        channel_id: u64,
+        user_id: Option<u64>,
+    ) -> Result<Option<String>, DatabaseError> {
+        let messages =
+            self.db
+                .get_markov_messages(channel_id, user_id, MAX_MESSAGES_PER_CHANNEL)?;
+
+        Ok(MarkovChain::build(&messages).generate())
+    }
+
+    pub fn is_opted_out(&self, user_id: u64) -> Result<bool, DatabaseError> {
+        if let Some(opted_out) = self.opt_out_cache.get(&user_id) {
+            return Ok(opted_out);
+        }
+
+        let opted_out = self.db.get_markov_opt_out(user_id)?;
+        self.opt_out_cache.insert(user_id, opted_out);
+
+        Ok(opted_out)
+    }
+
+    /// Opting out deletes the messages of the user in every channel
+    pub fn set_opt_out(&self, user_id: u64, opt_out: bool) -> Result<(), DatabaseError> {
+        self.db.set_markov_opt_out(user_id, opt_out)?;
+        self.opt_out_cache.insert(user_id, opt_out);
+
+        if opt_out {
+            self.db.delete_user_markov_messages(user_id)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Word transitions, where `None` marks the start or the end of a message
+#[derive(Debug, Default)]
+struct MarkovChain<'a> {
+    transitions: HashMap<Option<&'a str>, Vec<Option<&'a str>>>,
+}
+
+impl<'a> MarkovChain<'a> {
+    fn build(messages: &'a [String]) -> Self {
+        let mut chain = Self::default();
+
+        for message in messages {
+            let mut previous = None;
+
+            for word in message.split_whitespace() {
+                chain
+                    .transitions
+                    .entry(previous)
+                    .or_default()
+                    .push(Some(word));
+                previous = Some(word);
+            }
+
+            if previous.is_some() {
+                chain.transitions.entry(previous).or_default().push(None);
+            }
+        }
+
+        chain
+    }
+
+    /// Prefers sentences with more than a couple of words, `None` if there are no messages
+    fn generate(&self) -> Option<String> {
+        let mut best: Option<Vec<&str>> = None;
+
+        for _ in 0..GENERATION_ATTEMPTS {
+            let words = self.walk()?;
+
+            if words.len() >= 3 {
+                return Some(words.join(" "));
+            }
+            if best.as_ref().map_or(true, |best| words.len() > best.len()) {
+                best = Some(words);
+            }
+        }
+
+        best.map(|words| words.join(" "))
+    }
+
+    fn walk(&self) -> Option<Vec<&'a str>> {
+        let mut rng = thread_rng();
+        let mut words = Vec::new();
+        let mut current = None;
+
+        while words.len() < MAX_SENTENCE_WORDS {
+            match self.transitions.get(&current)?.choose(&mut rng)? {
+                Some(word) => {
+                    words.push(*word);
+                    current = Some(*word);
+                }
+                None => break,
+            }
+        }
+
+        Some(words)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MarkovChain, MAX_SENTENCE_WORDS};
+
+    #[test]
+    fn generates_from_messages() {
+        let messages = vec!["hello there".to_owned(), "hello world".to_owned()];
+        let chain = MarkovChain::build(&messages);
+
+        let sentence = chain.generate().unwrap();
+        assert!(sentence == "hello there" || sentence == "hello world");
+
+        assert_eq!(MarkovChain::build(&[]).generate(), None);
+    }
+
+    #[test]
+    fn limits_sentence_length() {
+        let messages = vec!["a a".to_owned()];
+        let chain = MarkovChain::build(&messages);
+
+        let sentence = chain.generate().unwrap();
+        assert!(sentence.split(' ').count() <= MAX_SENTENCE_WORDS);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::platform::UserIdentifier;
+use dashmap::DashMap;
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const MAX_AGE: Duration = Duration::from_secs(1800);
+const MAX_MESSAGES_PER_CHANNEL: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct HistoryMessage {
+    pub received_at: Instant,
+    pub user: UserIdentifier,
+    pub text: String,
+}
+
+/// Recent chat messages by channel id, used for moderation actions such as nukes
+#[derive(Debug, Clone, Default)]
+pub struct MessageHistory {
+    channels: Arc<DashMap<u64, VecDeque<HistoryMessage>>>,
+}
+
+impl MessageHistory {
+    pub fn record(&self, channel_id: u64, user: UserIdentifier, text: &str) {
+        let mut messages = self.channels.entry(channel_id).or_default();
+
+        while messages.len() >= MAX_MESSAGES_PER_CHANNEL
+            || messages
+                .front()
+                .map_or(false, |message| message.received_at.elapsed() > MAX_AGE)
+        {
+            messages.pop_front();
+        }
+
+        messages.push_back(HistoryMessage {
+            received_at: Instant::now(),
+            user,
+            text: text.to_owned(),
+        });
+    }
+
+    /// Returns the messages in the channel that were received within the given duration
+    pub fn get_recent(&self, channel_id: u64, within: Duration) -> Vec<HistoryMessage> {
+        self.channels
+            .get(&channel_id)
+            .map(|messages| {
+                messages
+                    .iter()
+                    .filter(|message| message.received_at.elapsed() <= within)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -1,114 +1,191 @@
+pub mod action_validation;
+pub mod args_spec;
+pub mod banphrase_api;
+pub mod blocked_users;
+pub mod codec;
+pub mod command_tests;
 mod commands;
+mod config_reload;
+pub mod debug_report;
+pub mod dictionary_api;
 pub mod discord_api;
+pub mod emotes_api;
 pub mod error;
 mod eval;
 pub mod finnhub_api;
 pub mod geohub;
+pub mod http_fetch;
+pub mod i18n;
+pub mod ignored_users;
+pub mod image_api;
 pub mod inquiry_helper;
+pub mod json_path;
 pub mod lastfm_api;
 pub mod lingva_api;
+pub mod markov;
+pub mod message_history;
+pub mod name_history;
+pub mod nats_api;
+pub mod nats_connection;
+pub mod now_playing;
+pub mod openai_api;
+pub mod outgoing_webhooks;
+pub mod output;
 pub mod owm_api;
 pub mod platform_handler;
+pub mod render_budget;
+pub mod sharding;
+pub mod spam_protection;
 pub mod spotify_api;
+pub mod status;
+pub mod supinic_api;
+pub mod template_partials;
 pub mod twitch_api;
 mod ukraine_alert;
 
+use action_validation::{validate_hebi, validate_template, ActionValidation};
 use anyhow::{anyhow, Context};
+use arc_swap::ArcSwap;
+use banphrase_api::BanphraseApi;
+use blocked_users::BlockedUsers;
 use chrono::{DateTime, Utc};
+use codec::Codec;
+use command_tests::CommandTestResult;
+use config_reload::ConfigReloader;
 use dashmap::DashMap;
+use debug_report::{DebugReports, LastError, TraceRecorder, TracedHelperRegistry};
+use dictionary_api::{DictionaryApi, UrbanDictionaryApi};
 use discord_api::DiscordApi;
+use emotes_api::EmotesApi;
+use exchange_api::{CoinGeckoApi, ExchangeRateApi};
 use handlebars::Handlebars;
 use hebi::prelude::NativeModule;
+use http_fetch::HttpFetcher;
+use i18n::{Language, Message};
+use ignored_users::IgnoredUsers;
+use image_api::ImageApi;
 use inquiry_helper::*;
 use lastfm_api::LastFMApi;
 use lingva_api::LingvaApi;
+use markov::Markov;
+use message_history::MessageHistory;
+use name_history::{start_name_history_refresh, NameHistory};
+use nats_connection::NatsConnection;
+use openai_api::OpenAiApi;
 use opentelemetry::trace::TraceContextExt;
+use outgoing_webhooks::{BotEvent, WebhookDispatcher};
+use output::{Attachment, CommandOutput};
 use owm_api::OwmApi;
-use reqwest::Client;
+use render_budget::{render_with_budget, MAX_RENDER_DURATION};
+use sharding::Sharding;
+use spam_protection::SpamProtection;
+use status::StatusTracker;
 use std::collections::HashMap;
-use std::env;
 use std::fmt::Debug;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
+use supinic_api::SupinicApi;
+use template_partials::with_channel_partials;
 use tokio::sync::{Mutex, RwLock};
 use tokio::task;
 use tracing::{info, instrument, Span};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
-use twitch_api::TwitchApi;
-
+use twitch_api::{
+    eventsub::{events::PendingRedemption, EventSubSubscriptionType},
+    get_broadcaster_helix_api,
+    model::RedemptionStatus,
+    TwitchApi,
+};
+
+use self::args_spec::ArgsSpec;
 use self::commands::BuiltinCommand;
-use self::error::CommandError;
+use self::error::{format_error_response, CommandError};
 use self::eval::context::HebiContext;
 use self::eval::storage::ModuleStorage;
 use self::eval::{create_native_modules, eval_hebi};
 use self::finnhub_api::FinnhubApi;
 use self::platform_handler::PlatformHandler;
 use crate::command_handler::commands::{create_builtin_commands, ExecutableCommand};
-use crate::command_handler::eval::storage::create_module_storage_from_env;
+use crate::command_handler::eval::storage::create_module_storage;
 use crate::command_handler::ukraine_alert::UkraineAlertClient;
-use crate::database::models::{Command, CommandMode, Filter};
-use crate::database::{models::User, Database};
-use crate::platform::connector::get_connector_permissions;
-use crate::platform::{minecraft, UserIdentifier};
+use crate::config::{ApiKeysConfig, Config};
+use crate::database::models::{
+    Channel, ChannelFeature, Command, CommandMode, EventSubTrigger, Filter, GlobalCommand,
+    LongMessageMode, ModerationAction, NewModerationLogEntry, ProtectionAction,
+};
+use crate::database::{models::User, Database, DatabaseError};
+use crate::error_tracking;
+use crate::platform::connector::{ConnectorPermissions, ConnectorRegistry};
+use crate::platform::supervisor::Backoff;
+use crate::platform::{irc, minecraft, UserIdentifier};
 use crate::platform::{ChannelIdentifier, Permissions, PlatformContext, ServerPlatformContext};
+use crate::LogFilterHandle;
 
 const DEFAULT_COOLDOWN: u64 = 5;
+const EVENTSUB_RETRY_DELAY: Duration = Duration::from_secs(5);
+const EVENTSUB_MAX_RETRY_DELAY: Duration = Duration::from_secs(600);
+/// Revoked subscriptions are recreated on the next reconciliation
+const EVENTSUB_RECONCILE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Swapped out when helpers are registered again after a config reload
+pub type TemplateRegistry = Arc<ArcSwap<Handlebars<'static>>>;
 
 #[derive(Clone)]
 pub struct CommandHandler {
     pub db: Database,
     pub platform_handler: Arc<RwLock<PlatformHandler>>,
-    pub nats_client: async_nats::Client,
-    template_registry: Arc<Handlebars<'static>>,
+    pub nats_client: NatsConnection,
+    pub connector_permissions: ConnectorPermissions,
+    pub connectors: ConnectorRegistry,
+    pub sharding: Sharding,
+    template_registry: TemplateRegistry,
     builtin_commands: Arc<Vec<BuiltinCommand>>,
     cooldowns: Arc<RwLock<Vec<(u64, String)>>>, // User id and command
     command_triggers: Arc<DashMap<u64, Arc<DashMap<String, String>>>>, // Channel id, trigger phrase and command name
-    mirror_connections: Arc<HashMap<String, ChannelIdentifier>>,       // from and to channel
-    pub blocked_users: Arc<Vec<UserIdentifier>>,
+    mirror_connections: Arc<std::sync::RwLock<HashMap<String, ChannelIdentifier>>>, // from and to channel
+    pub blocked_users: BlockedUsers,
+    ignored_users: IgnoredUsers,
+    message_history: MessageHistory,
+    name_history: NameHistory,
+    markov: Markov,
+    pub spam_protection: SpamProtection,
+    pub banphrase_api: BanphraseApi,
     hebi_native_modules: Arc<Vec<NativeModule>>,
     hebi_module_storage: ModuleStorage,
+    safe_mode: Arc<AtomicBool>, // Only builtin commands are executed when enabled
+    openai_api: Option<OpenAiApi>,
+    image_api: Option<ImageApi>,
+    pub lastfm_api: Option<LastFMApi>,
+    pub webhook_dispatcher: WebhookDispatcher,
+    config_reloader: ConfigReloader,
+    pub status: StatusTracker,
+    pub debug_reports: DebugReports,
 }
 
 impl CommandHandler {
-    pub async fn init(db: Database) -> Self {
-        let nats_addr = env::var("NATS_ADDRESS").expect("NATS_ADDRESS not specified");
-        let nats_client = async_nats::connect(nats_addr)
-            .await
-            .expect("Could not connect to nats");
+    pub async fn init(
+        db: Database,
+        config_path: Option<PathBuf>,
+        log_filter: LogFilterHandle,
+    ) -> Self {
+        let config = db.config();
+
+        let status = StatusTracker::default();
+        let nats_client = NatsConnection::connect(config.nats_address.clone(), status.clone());
+        let sharding = Sharding::start(&config.sharding, db.clone(), nats_client.clone());
 
         let twitch_api = match TwitchApi::init_refreshing(db.clone()).await {
             Ok(api) => {
-                let active_triggers = api
-                    .helix_api_app
-                    .get_eventsub_subscriptions(None)
-                    .await
-                    .expect("Failed to get EventSub triggers");
-
-                for trigger in db.get_eventsub_triggers().expect("DB Error") {
-                    if !active_triggers
-                        .iter()
-                        .any(|active_trigger| active_trigger.id == trigger.id)
-                    {
-                        let subscription_type = serde_json::from_str(&trigger.creation_payload)
-                            .expect("Deserialization error");
-
-                        match api
-                            .helix_api_app
-                            .add_eventsub_subscription(subscription_type)
-                            .await
-                        {
-                            Ok(response) => {
-                                let new_id = &response.data.first().unwrap().id;
-
-                                db.update_eventsub_trigger_id(&trigger.id, new_id)
-                                    .expect("DB error");
-                            }
-                            Err(e) => tracing::error!("Failed to add EventSub subscription! {}", e),
-                        }
-                    }
-                }
-
+                start_eventsub_reconciliation(
+                    api.clone(),
+                    db.clone(),
+                    status.clone(),
+                    sharding.clone(),
+                );
+                start_name_history_refresh(api.clone(), db.clone());
                 Some(api)
             }
             Err(e) => {
@@ -117,15 +194,7 @@ impl CommandHandler {
             }
         };
 
-        let discord_api = match env::var("DISCORD_TOKEN") {
-            Ok(token) => Some(DiscordApi::new(token)),
-            Err(_) => None,
-        };
-
-        let lingva_url = match env::var("LINGVA_INSTANCE_URL") {
-            Ok(url) => url,
-            Err(_) => "https://lingva.ml".to_owned(),
-        };
+        let discord_api = config.discord.token.clone().map(DiscordApi::new);
 
         let mut filters: HashMap<ChannelIdentifier, Vec<Filter>> = HashMap::new();
 
@@ -145,12 +214,23 @@ impl CommandHandler {
 
         tracing::trace!("Loaded filters: {:?}", filters);
 
-        let minecraft = match minecraft::init() {
+        let mut channel_settings = HashMap::new();
+
+        for settings in db.get_all_channel_settings().expect("DB Error") {
+            let channel = db
+                .get_channel_by_id(settings.channel_id)
+                .expect("DB error")
+                .unwrap(); // channel_id is a foreign key
+
+            channel_settings.insert(channel.get_identifier(), settings);
+        }
+
+        let minecraft = match minecraft::init(&config.minecraft) {
             Ok(mut minecraft) => {
                 db.get_or_create_channel(&ChannelIdentifier::Minecraft)
                     .expect("DB error")
                     .expect("Failed to initialize Minecraft channel in the DB!");
-                if env::var("PROFILE") == Ok("debug".to_string()) {
+                if env!("PROFILE") == "debug" {
                     minecraft
                         .send_command("say Foobot2 connected".to_string())
                         .unwrap();
@@ -166,172 +246,346 @@ impl CommandHandler {
         let platform_handler = PlatformHandler {
             twitch_api,
             discord_api,
-            irc_sender: None,
+            irc_networks: HashMap::new(),
             minecraft_client: minecraft.map(|m| Arc::new(Mutex::new(m))),
             filters: Arc::new(std::sync::RwLock::new(filters)),
+            channel_settings: Arc::new(std::sync::RwLock::new(channel_settings)),
+            sharding: sharding.clone(),
         };
 
-        let hebi_module_storage =
-            create_module_storage_from_env().expect("Could not create hebi module storage");
+        let webhook_dispatcher = WebhookDispatcher::new(db.clone());
 
-        let lingva_api = LingvaApi::init(lingva_url);
+        let hebi_module_storage = create_module_storage(config.hebi_modules_git_url.as_deref())
+            .expect("Could not create hebi module storage");
+
+        let lingva_api = LingvaApi::init(config.lingva_instance_url.clone());
         let ukraine_alert_client = UkraineAlertClient::default();
 
         let mut template_registry = Handlebars::new();
 
-        template_registry.register_helper("translate", Box::new(lingva_api));
-        template_registry.register_helper("ukraine_alerts", Box::new(ukraine_alert_client));
-        template_registry.register_helper("args", Box::new(inquiry_helper::args_helper));
-        template_registry.register_helper("spotify", Box::new(SpotifyHelper { db: db.clone() }));
-        template_registry.register_helper(
+        template_registry.register_http_helper("translate", Box::new(lingva_api.clone()));
+        template_registry.register_http_helper("ukraine_alerts", Box::new(ukraine_alert_client));
+        template_registry.register_traced_helper("args", Box::new(inquiry_helper::args_helper));
+        template_registry
+            .register_http_helper("spotify", Box::new(SpotifyHelper { db: db.clone() }));
+        template_registry.register_http_helper(
             "spotify_last_song",
             Box::new(SpotifyLastHelper { db: db.clone() }),
         );
-        template_registry.register_helper(
+        template_registry.register_http_helper(
             "spotify_playlist",
             Box::new(SpotifyPlaylistHelper { db: db.clone() }),
         );
-        template_registry.register_helper("choose", Box::new(random_helper));
-        template_registry.register_helper("sleep", Box::new(sleep_helper));
-        template_registry.register_helper("username", Box::new(username_helper));
-        template_registry.register_helper("concat", Box::new(concat_helper));
-        template_registry.register_helper("trim_matches", Box::new(trim_matches_helper));
-        template_registry.register_helper(
-            "forsencode_encode",
-            Box::new(inquiry_helper::forsencode_encode_helper),
-        );
-        template_registry.register_helper(
-            "forsencode_decode",
-            Box::new(inquiry_helper::forsencode_decode_helper),
-        );
-
-        if let Ok(api_key) = env::var("FINNHUB_API_KEY") {
-            template_registry.register_helper("stock", Box::new(FinnhubApi::init(api_key)));
+        template_registry.register_traced_helper("choose", Box::new(random_helper));
+        template_registry.register_traced_helper("sleep", Box::new(sleep_helper));
+        template_registry.register_traced_helper("username", Box::new(username_helper));
+        template_registry.register_traced_helper("concat", Box::new(concat_helper));
+        template_registry.register_traced_helper("trim_matches", Box::new(trim_matches_helper));
+        template_registry.register_traced_helper("urlencode", Box::new(urlencode_helper));
+        template_registry.register_traced_helper("escape", Box::new(escape_helper));
+        template_registry.register_http_helper("urban", Box::new(UrbanDictionaryApi::default()));
+        template_registry.register_http_helper("define", Box::new(DictionaryApi::default()));
+        template_registry.register_traced_helper("countdown", Box::new(countdown_helper));
+        template_registry.register_traced_helper("timestamp", Box::new(TimestampHelper));
+        template_registry.register_traced_helper("default", Box::new(DefaultHelper));
+        template_registry.register_traced_helper("if_empty", Box::new(IfEmptyHelper));
+        for comparison in Comparison::ALL {
+            template_registry
+                .register_traced_helper(comparison.name(), Box::new(CompareHelper { comparison }));
         }
-
-        if let Ok(owm_api_key) = env::var("OWM_API_KEY") {
-            template_registry.register_helper(
-                "weather",
-                Box::new(WeatherHelper {
-                    db: db.clone(),
-                    api: OwmApi::init(owm_api_key),
+        for codec in Codec::ALL {
+            template_registry.register_traced_helper(
+                &format!("{}_encode", codec.name()),
+                Box::new(CodecHelper {
+                    codec,
+                    decode: false,
                 }),
             );
+            if codec.can_decode() {
+                template_registry.register_traced_helper(
+                    &format!("{}_decode", codec.name()),
+                    Box::new(CodecHelper {
+                        codec,
+                        decode: true,
+                    }),
+                );
+            }
         }
 
-        if let Ok(lastfm_api_key) = env::var("LASTFM_API_KEY") {
-            template_registry.register_helper(
-                "lastfm",
-                Box::new(LastFMHelper {
-                    db: db.clone(),
-                    lastfm_api: LastFMApi::init(lastfm_api_key),
-                }),
-            )
+        let openai_api = OpenAiApi::from_config(&config.openai, db.clone());
+
+        if let Some(openai_api) = &openai_api {
+            template_registry.register_http_helper("ai", Box::new(openai_api.clone()));
+            template_registry.register_http_helper("gpt", Box::new(openai_api.clone()));
+        }
+
+        let http_fetcher =
+            HttpFetcher::new(&config.http_fetch).expect("Failed to create HTTP fetcher");
+
+        let image_api = ImageApi::from_config(&config.images, db.clone(), http_fetcher.clone())
+            .unwrap_or_else(|e| {
+                tracing::warn!("Image generation is not available: {e:#}");
+                None
+            });
+
+        if let Some(image_api) = &image_api {
+            template_registry.register_http_helper("meme", Box::new(image_api.clone()));
         }
 
+        register_api_helpers(&mut template_registry, &db, &config.api_keys);
+
+        let supinic_api = SupinicApi::new(&config.supinic);
+        template_registry.register_http_helper(
+            "supi",
+            Box::new(SupinicHelper {
+                db: db.clone(),
+                supinic_api: supinic_api.clone(),
+            }),
+        );
+
+        let lastfm_api = config.api_keys.lastfm.clone().map(LastFMApi::init);
+
         if let Some(twitch_api) = &platform_handler.twitch_api {
-            template_registry.register_helper(
+            template_registry.register_http_helper(
                 "twitchuser",
                 Box::new(TwitchUserHelper {
                     twitch_api: twitch_api.clone(),
                 }),
             );
-            template_registry.register_helper(
+            template_registry.register_http_helper(
                 "twitch_commercial",
                 Box::new(CommercialHelper { db: db.clone() }),
             );
-            template_registry.register_helper(
+            template_registry.register_http_helper(
                 "twitch_timeout",
                 Box::new(TwitchTimeoutHelper {
+                    db: db.clone(),
+                    twitch_api: twitch_api.clone(),
+                    webhook_dispatcher: webhook_dispatcher.clone(),
+                }),
+            );
+            template_registry.register_http_helper(
+                "followage",
+                Box::new(TwitchFollowageHelper {
+                    db: db.clone(),
                     twitch_api: twitch_api.clone(),
                 }),
             );
-        }
 
-        template_registry.register_helper("get", Box::new(HttpHelper::init()));
-        template_registry.register_helper("json", Box::new(JsonHelper));
-        template_registry.register_helper("song", Box::new(inquiry_helper::song_helper));
+            for (name, kind) in [
+                ("uptime", StreamInfoKind::Uptime),
+                ("title", StreamInfoKind::Title),
+                ("game", StreamInfoKind::Game),
+            ] {
+                template_registry.register_http_helper(
+                    name,
+                    Box::new(TwitchStreamHelper {
+                        twitch_api: twitch_api.clone(),
+                        kind,
+                    }),
+                );
+            }
 
-        let temp_data = Arc::new(DashMap::new());
+            template_registry.register_http_helper(
+                "is_live",
+                Box::new(TwitchLiveHelper {
+                    twitch_api: twitch_api.clone(),
+                }),
+            );
+
+            template_registry.register_http_helper("emotes", Box::new(EmotesApi::default()));
+        }
 
-        template_registry.register_helper(
-            "data_get",
-            Box::new(GetTempData {
-                data: temp_data.clone(),
+        template_registry.register_http_helper(
+            "get",
+            Box::new(HttpHelper {
+                http_fetcher: http_fetcher.clone(),
             }),
         );
+        template_registry.register_traced_helper("json", Box::new(JsonHelper));
+        template_registry.register_traced_helper("jsonpath", Box::new(JsonPathHelper));
+        template_registry.register_traced_helper("paginate", Box::new(PaginateHelper));
+        template_registry.register_traced_helper(
+            "song",
+            Box::new(SongHelper {
+                base_url: config.base_url.clone(),
+            }),
+        );
+
+        template_registry
+            .register_traced_helper("data_get", Box::new(GetTempData { db: db.clone() }));
+        template_registry
+            .register_traced_helper("data_set", Box::new(SetTempData { db: db.clone() }));
 
         let platform_handler = Arc::new(RwLock::new(platform_handler));
 
-        template_registry.register_helper(
+        template_registry.register_traced_helper(
             "say",
             Box::new(inquiry_helper::SayHelper {
                 platform_handler: platform_handler.clone(),
+                action: false,
+            }),
+        );
+        template_registry.register_traced_helper(
+            "me",
+            Box::new(inquiry_helper::SayHelper {
+                platform_handler: platform_handler.clone(),
+                action: true,
+            }),
+        );
+        template_registry.register_http_helper(
+            "announce",
+            Box::new(TwitchAnnounceHelper {
+                platform_handler: platform_handler.clone(),
+            }),
+        );
+        template_registry.register_http_helper(
+            "shoutout",
+            Box::new(TwitchShoutoutHelper {
+                platform_handler: platform_handler.clone(),
             }),
         );
 
-        template_registry.register_helper("data_set", Box::new(SetTempData { data: temp_data }));
         template_registry.register_decorator("set", Box::new(set_decorator));
 
         template_registry.set_strict_mode(true);
 
-        let template_registry = Arc::new(template_registry);
+        let template_registry = Arc::new(ArcSwap::from_pointee(template_registry));
+
+        let hebi_native_modules = Arc::new(create_native_modules(db.clone(), http_fetcher));
+
+        let safe_mode = Arc::new(AtomicBool::new(config.safe_mode));
+
+        let ignored_users = IgnoredUsers::new(db.clone());
+        let message_history = MessageHistory::default();
+        let name_history = NameHistory::new(db.clone());
+        let markov = Markov::new(db.clone());
+        let spam_protection = SpamProtection::new(db.clone());
+        let banphrase_api = BanphraseApi::new(db.clone());
 
-        let hebi_native_modules = Arc::new(create_native_modules(db.clone()));
+        let connectors = ConnectorRegistry::default();
+        let debug_reports = DebugReports::default();
+
+        let blocked_users = BlockedUsers::load(db.clone(), &config.blocked_users)
+            .expect("Failed to load blocked users");
+
+        let config_reloader = ConfigReloader {
+            db: db.clone(),
+            config_path,
+            blocked_users: blocked_users.clone(),
+            template_registry: template_registry.clone(),
+            log_filter,
+        };
 
         let builtin_commands = create_builtin_commands(
             template_registry.clone(),
             hebi_native_modules.clone(),
             hebi_module_storage.clone(),
+            safe_mode.clone(),
+            openai_api.clone(),
+            image_api.clone(),
+            config.api_keys.owm.clone().map(OwmApi::init),
+            config.api_keys.finnhub.clone().map(FinnhubApi::init),
+            lastfm_api.clone(),
+            lingva_api,
+            ignored_users.clone(),
+            message_history.clone(),
+            markov.clone(),
+            spam_protection.clone(),
+            config_reloader.clone(),
+            status.clone(),
+            debug_reports.clone(),
         );
         info!("Loaded builtin commands: {builtin_commands:?}");
 
+        db.set_builtin_command_names(
+            builtin_commands
+                .iter()
+                .flat_map(|command| command.get_names())
+                .map(|name| name.to_string())
+                .collect(),
+        );
+        warn_builtin_command_conflicts(&db);
+
         let cooldowns = Arc::new(RwLock::new(Vec::new()));
 
-        let mut mirror_connections = HashMap::new();
-        for connection in db.get_mirror_connections().expect("DB error") {
-            let from_channel = db
-                .get_channel_by_id(connection.from_channel_id)
-                .unwrap()
-                .expect("Invalid channel connection");
-
-            let to_channel = db
-                .get_channel_by_id(connection.to_channel_id)
-                .unwrap()
-                .expect("Invalid channel connection");
-
-            if let Some(from_channel_str) = from_channel.get_identifier().get_channel() {
-                mirror_connections.insert(
-                    format!("{}-{}", from_channel.platform, from_channel_str),
-                    to_channel.get_identifier(),
-                );
-            }
-        }
+        let mirror_connections = load_mirror_connections(&db).expect("DB error");
         tracing::info!("Mirroring channels: {:?}", mirror_connections);
 
-        let blocked_users = env::var("BLOCKED_USERS")
-            .map(|blocked_users| {
-                blocked_users
-                    .split(',')
-                    .map(|raw_identifier| UserIdentifier::from_string(raw_identifier).unwrap())
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        start_supinic_heartbeat().await;
+        if supinic_api.is_authenticated() {
+            start_supinic_heartbeat(supinic_api);
+        }
 
         Self {
             db,
             platform_handler,
             template_registry,
             cooldowns,
-            mirror_connections: Arc::new(mirror_connections),
+            mirror_connections: Arc::new(std::sync::RwLock::new(mirror_connections)),
             command_triggers: Arc::new(DashMap::new()),
             builtin_commands: Arc::new(builtin_commands),
+            connector_permissions: ConnectorPermissions::new(
+                nats_client.clone(),
+                connectors.clone(),
+            ),
+            connectors,
+            sharding,
             nats_client,
-            blocked_users: Arc::new(blocked_users),
+            blocked_users,
+            ignored_users,
+            message_history,
+            name_history,
+            markov,
+            spam_protection,
+            banphrase_api,
             hebi_native_modules,
             hebi_module_storage,
+            safe_mode,
+            openai_api,
+            image_api,
+            lastfm_api,
+            webhook_dispatcher,
+            config_reloader,
+            status,
+            debug_reports,
+        }
+    }
+
+    pub fn config(&self) -> Arc<Config> {
+        self.db.config()
+    }
+
+    /// Re-reads the config file and applies the settings that don't need a restart.
+    /// Returns whether some of the changed settings were not applied
+    pub async fn reload_config(&self) -> anyhow::Result<bool> {
+        self.config_reloader.reload().await
+    }
+
+    pub fn is_safe_mode(&self) -> bool {
+        self.safe_mode.load(Ordering::Relaxed)
+    }
+
+    /// Returns the amount of loaded connections
+    pub fn reload_mirror_connections(&self) -> Result<usize, DatabaseError> {
+        let mirror_connections = load_mirror_connections(&self.db)?;
+        let count = mirror_connections.len();
+        tracing::info!("Reloaded mirror connections: {mirror_connections:?}");
+
+        *self.mirror_connections.write().expect("Failed to lock") = mirror_connections;
+
+        Ok(count)
+    }
+
+    pub async fn flush_caches(&self) {
+        self.db.clear_caches();
+        self.command_triggers.clear();
+        self.ignored_users.clear_cache();
+        self.spam_protection.clear_cache();
+        self.banphrase_api.clear_cache();
+        self.connector_permissions.clear();
+
+        if let Some(twitch_api) = &self.platform_handler.read().await.twitch_api {
+            twitch_api.clear_stream_info_cache();
         }
     }
 
@@ -339,39 +593,353 @@ impl CommandHandler {
         &self,
         message_text: &str,
         platform_ctx: P,
-    ) -> Option<String> {
+    ) -> CommandOutput {
+        let _message_guard = self.status.start_message();
+
         let channel = platform_ctx.get_channel();
+        let subchannel = platform_ctx.get_subchannel();
+        let user_identifier = platform_ctx.get_user_identifier();
+        let display_name = platform_ctx.get_display_name().to_owned();
+        let supports_attachments = platform_ctx.supports_attachments();
         let platform_handler = self.platform_handler.read().await;
 
-        self.handle_message_internal(message_text, platform_ctx)
+        // Database failures outside of commands are not reported in chat, as every message would
+        // get an error response
+        let mut output = match self
+            .handle_message_internal(message_text, platform_ctx)
             .await
-            .and_then(|mut response| {
-                platform_handler.filter_message(&mut response, &channel);
+        {
+            Ok(output) => output,
+            Err(e) => {
+                tracing::error!("Could not handle message in {channel}: {e}");
+                return CommandOutput::Nothing;
+            }
+        };
+        if output.is_nothing() {
+            return output;
+        }
 
-                if !response.is_empty() {
-                    Some(response)
-                } else {
-                    None
+        for text in output.texts_mut() {
+            for filter in platform_handler.filter_message(text, &channel, subchannel) {
+                self.log_filter_hit(&filter, &user_identifier).await;
+
+                self.webhook_dispatcher.dispatch(
+                    &channel,
+                    BotEvent::FilterMatched {
+                        regex: filter.regex,
+                        blocked: filter.block_message,
+                    },
+                );
+            }
+        }
+
+        if let ChannelIdentifier::TwitchChannel(_) = channel {
+            let channel_identifier = channel.clone();
+            match self
+                .db
+                .run(move |db| db.get_channel(&channel_identifier))
+                .await
+            {
+                Ok(Some(db_channel)) => {
+                    for text in output.texts_mut() {
+                        self.banphrase_api.check_message(db_channel.id, text).await;
+                    }
+                }
+                Ok(None) => (),
+                // Sending a response that could not be checked is not safe
+                Err(e) => {
+                    tracing::error!("Could not get channel for banphrase check: {e}");
+                    return CommandOutput::Nothing;
                 }
+            }
+        }
+
+        let output = match output.remove_empty() {
+            CommandOutput::Attachment(attachment) if !supports_attachments => {
+                self.paste_attachment(attachment).await
+            }
+            output => output,
+        };
+        let output = self
+            .paste_long_output(&platform_handler, &channel, output)
+            .await;
+
+        if let Some(response) = output.clone().into_text() {
+            self.webhook_dispatcher.dispatch(
+                &channel,
+                BotEvent::MessageHandled {
+                    user: display_name,
+                    message: message_text.to_owned(),
+                    response,
+                },
+            );
+        }
+
+        output
+    }
+
+    /// Links the attachment instead, it is sent inline if it can't be pasted
+    async fn paste_attachment(&self, attachment: Attachment) -> CommandOutput {
+        match self
+            .create_paste(&attachment.filename, attachment.content.clone())
+            .await
+        {
+            Some(url) => CommandOutput::Text(url),
+            None => CommandOutput::Attachment(attachment),
+        }
+    }
+
+    /// Links responses that don't fit into a single message if the channel has opted into it
+    async fn paste_long_output(
+        &self,
+        platform_handler: &PlatformHandler,
+        channel: &ChannelIdentifier,
+        output: CommandOutput,
+    ) -> CommandOutput {
+        let limit = match channel.get_message_limit() {
+            Some(limit) => limit,
+            None => return output,
+        };
+
+        let settings = platform_handler.get_channel_settings(channel);
+        if settings.long_messages != LongMessageMode::Paste
+            || !output.exceeds_limit(limit, channel.get_length_unit())
+        {
+            return output;
+        }
+
+        let text = output.clone().into_text().unwrap_or_default();
+        match self.create_paste("response.txt", text.into_bytes()).await {
+            Some(url) => CommandOutput::Text(url),
+            None => output,
+        }
+    }
+
+    /// Returns the link to the paste
+    async fn create_paste(&self, filename: &str, content: Vec<u8>) -> Option<String> {
+        let owned_filename = filename.to_owned();
+
+        match self
+            .db
+            .run(move |db| db.add_paste(owned_filename, content))
+            .await
+        {
+            Ok(paste) => Some(paste.get_url(&self.db.config().base_url)),
+            Err(e) => {
+                tracing::warn!("Could not paste {filename}: {e}");
+                None
+            }
+        }
+    }
+
+    async fn log_filter_hit(&self, filter: &Filter, user_identifier: &UserIdentifier) {
+        let channel_id = filter.channel_id;
+        let target = user_identifier.to_string();
+        let action = ModerationAction::Filter.to_string();
+        let regex = filter.regex.clone();
+
+        let result = self
+            .db
+            .run(move |db| {
+                db.add_moderation_log_entry(NewModerationLogEntry {
+                    channel_id,
+                    moderator_id: None,
+                    target: &target,
+                    action: &action,
+                    duration: None,
+                    reason: Some(&regex),
+                })
             })
+            .await;
+        if let Err(e) = result {
+            tracing::warn!("Could not log filter hit: {e}");
+        }
+    }
+
+    /// Returns true if the message was punished and should not be processed further.
+    /// Only messages that can be moderated on their platform are checked, moderators are exempt
+    async fn apply_spam_protection<P: PlatformContext + Send + Sync>(
+        &self,
+        channel_id: u64,
+        message_text: &str,
+        platform_ctx: &P,
+    ) -> bool {
+        let message = match platform_ctx.get_message_identifier() {
+            Some(message) => message,
+            None => return false,
+        };
+        match self
+            .db
+            .run(move |db| db.is_feature_enabled(channel_id, ChannelFeature::SpamProtection))
+            .await
+        {
+            Ok(true) => (),
+            Ok(false) => return false,
+            Err(e) => {
+                tracing::warn!("Could not check if spam protection is enabled: {e}");
+                return false;
+            }
+        }
+        let user = platform_ctx.get_user_identifier();
+
+        let violation = match self
+            .spam_protection
+            .check(
+                channel_id,
+                &user,
+                message_text,
+                platform_ctx.get_emote_count(),
+            )
+            .await
+        {
+            Ok(Some(violation)) => violation,
+            Ok(None) => return false,
+            Err(e) => {
+                tracing::warn!("Could not check spam protection: {e}");
+                return false;
+            }
+        };
+
+        if platform_ctx.get_permissions_internal().await >= Permissions::ChannelMod {
+            return false;
+        }
+
+        let punishment = match self.spam_protection.punish(channel_id, &user).await {
+            Ok(punishment) => punishment,
+            Err(e) => {
+                tracing::warn!("Could not get spam protection punishment: {e}");
+                return true;
+            }
+        };
+        tracing::info!("Punishing {user} for {violation}: {punishment:?}");
+
+        let reason = violation.to_string();
+        let platform_handler = self.platform_handler.read().await;
+
+        let (result, action) = match punishment.action {
+            ProtectionAction::Delete => (
+                platform_handler.delete_message(&message).await,
+                ModerationAction::Delete,
+            ),
+            ProtectionAction::Timeout => (
+                platform_handler
+                    .ban_user(
+                        &platform_ctx.get_channel(),
+                        &user,
+                        punishment.duration,
+                        Some(&reason),
+                    )
+                    .await,
+                ModerationAction::Timeout,
+            ),
+        };
+
+        if let Err(e) = result {
+            tracing::warn!("Could not punish {user}: {e}");
+            return true;
+        }
+
+        let target = user.to_string();
+        let action = action.to_string();
+        let duration = punishment
+            .duration
+            .map(|duration| duration.as_secs() as u32);
+
+        let result = self
+            .db
+            .run(move |db| {
+                db.add_moderation_log_entry(NewModerationLogEntry {
+                    channel_id,
+                    moderator_id: None,
+                    target: &target,
+                    action: &action,
+                    duration,
+                    reason: Some(&reason),
+                })
+            })
+            .await;
+        if let Err(e) = result {
+            tracing::warn!("Could not log spam protection action: {e}");
+        }
+
+        true
     }
 
     pub async fn handle_message_internal<P: PlatformContext + Send + Sync>(
         &self,
         message_text: &str,
         platform_ctx: P,
-    ) -> Option<String> {
+    ) -> Result<CommandOutput, DatabaseError> {
         tracing::trace!("Handling message in channel {}", platform_ctx.get_channel());
-        if let Some(mirror_channel) = self.mirror_connections.get(&format!(
-            "{}-{}",
-            platform_ctx
-                .get_channel()
-                .get_platform_name()
-                .unwrap_or_default(),
-            platform_ctx.get_channel().get_channel().unwrap_or_default()
-        )) {
+
+        let channel_identifier = platform_ctx.get_channel();
+        let db_channel = self
+            .db
+            .run(move |db| db.get_channel(&channel_identifier))
+            .await?;
+
+        if platform_ctx.is_received_by_every_shard()
+            && !self
+                .sharding
+                .owns_channel(db_channel.as_ref().map(|channel| channel.id))
+        {
+            return Ok(CommandOutput::Nothing);
+        }
+
+        self.name_history.record(
+            &platform_ctx.get_user_identifier(),
+            platform_ctx.get_display_name(),
+        );
+
+        if let Some(channel) = db_channel {
+            self.message_history.record(
+                channel.id,
+                platform_ctx.get_user_identifier(),
+                message_text,
+            );
+
+            if self
+                .ignored_users
+                .is_ignored(channel.id, &platform_ctx.get_user_identifier())
+                .await?
+            {
+                tracing::debug!(
+                    "Ignoring message from {}",
+                    platform_ctx.get_user_identifier()
+                );
+                return Ok(CommandOutput::Nothing);
+            }
+
+            if self
+                .apply_spam_protection(channel.id, message_text, &platform_ctx)
+                .await
+            {
+                return Ok(CommandOutput::Nothing);
+            }
+        }
+
+        let mirror_channel = self
+            .mirror_connections
+            .read()
+            .expect("Failed to lock")
+            .get(&format!(
+                "{}-{}",
+                platform_ctx
+                    .get_channel()
+                    .get_platform_name()
+                    .unwrap_or_default(),
+                platform_ctx.get_channel().get_channel().unwrap_or_default()
+            ))
+            .cloned();
+        let channel_settings = self
+            .platform_handler
+            .read()
+            .await
+            .get_channel_settings(&platform_ctx.get_channel());
+        let mirroring = channel_settings.mirroring;
+
+        if let Some(mirror_channel) = mirror_channel.filter(|_| mirroring) {
             let platform_handler = self.platform_handler.clone();
-            let mirror_channel = mirror_channel.clone();
             let mut channel = platform_ctx.get_channel().to_string();
             let mut display_name = platform_ctx.get_display_name().to_string();
 
@@ -396,41 +964,86 @@ impl CommandHandler {
             }
         }
 
-        if let Some(channel) = self
+        // Messages are still mirrored and moderated in the channels where the bot doesn't respond
+        if let Some(subchannel) = platform_ctx.get_subchannel() {
+            let allowed_channels = &channel_settings.discord_channels;
+
+            if !allowed_channels.is_empty() && !allowed_channels.contains(&subchannel) {
+                return Ok(CommandOutput::Nothing);
+            }
+        }
+
+        let channel_identifier = platform_ctx.get_channel();
+        let db_channel = self
             .db
-            .get_or_create_channel(&platform_ctx.get_channel())
-            .expect("DB error")
-        {
-            let triggers = self.get_command_triggers(channel.id).expect("DB error");
+            .run(move |db| db.get_or_create_channel(&channel_identifier))
+            .await?;
+
+        if let Some(channel) = db_channel {
+            if channel_settings.chat_logs {
+                let db = self.db.clone();
+                let user = platform_ctx.get_user_identifier();
+                let text = message_text.to_owned();
+
+                task::spawn_blocking(move || {
+                    let result = db
+                        .get_or_create_user(&user)
+                        .map_err(DatabaseError::from)
+                        .and_then(|user| db.add_chat_log(channel.id, user.id, &text));
+
+                    if let Err(e) = result {
+                        tracing::warn!("Could not log chat message: {e}");
+                    }
+                });
+            }
+
+            if channel_settings.markov
+                && !platform_ctx
+                    .get_prefixes()
+                    .iter()
+                    .any(|prefix| message_text.starts_with(prefix))
+            {
+                let markov = self.markov.clone();
+                let user = platform_ctx.get_user_identifier();
+                let text = message_text.to_owned();
+
+                task::spawn_blocking(move || {
+                    if let Err(e) = markov.record(channel.id, &user, &text) {
+                        tracing::warn!("Could not record markov message: {e}");
+                    }
+                });
+            }
+
+            let triggers = self.get_command_triggers(channel.id).await?;
 
             for trigger in triggers.iter() {
                 if let Some(command_args) = message_text.strip_prefix(trigger.key()) {
                     let command_msg = format!("{} {}", trigger.value(), command_args);
                     tracing::info!("Executing indirect command {}", command_msg);
 
-                    return self
+                    return Ok(self
                         .handle_command_message(&command_msg, platform_ctx)
-                        .await;
+                        .await);
                 }
             }
         }
 
         for prefix in platform_ctx.get_prefixes() {
             if let Some(command_msg) = message_text.strip_prefix(prefix) {
-                return self.handle_command_message(command_msg, platform_ctx).await;
+                return Ok(self.handle_command_message(command_msg, platform_ctx).await);
             }
         }
-        None
+        Ok(CommandOutput::Nothing)
     }
 
     /// This function expects a raw message that appears to be a command without the leading command prefix.
     #[instrument(skip(self))]
-    async fn handle_command_message<C>(&self, message_text: &str, context: C) -> Option<String>
+    async fn handle_command_message<C>(&self, message_text: &str, context: C) -> CommandOutput
     where
         C: PlatformContext + Send + Sync,
     {
         if message_text.trim().is_empty() {
-            Some("❗".to_string())
+            CommandOutput::Text("❗".to_string())
         } else {
             let mut split = message_text.split_whitespace();
 
@@ -438,15 +1051,92 @@ impl CommandHandler {
 
             let arguments: Vec<&str> = split.collect();
 
+            let user = context.get_user_identifier();
+            let channel = context.get_channel();
+
             let command_result = self.run_command(&command, arguments, context).await;
 
             match command_result {
                 Ok(result) => result,
-                Err(e) => Some(e.to_string()),
+                Err(e) => {
+                    match &e {
+                        CommandError::DatabaseError(db_error) => {
+                            tracing::error!("Database error while running {command}: {db_error}");
+                        }
+                        CommandError::InternalError(error) => {
+                            let trace_id =
+                                Span::current().context().span().span_context().trace_id();
+                            error_tracking::capture_command_error(
+                                &command,
+                                &channel,
+                                &trace_id.to_string(),
+                                error,
+                            );
+                        }
+                        _ => (),
+                    }
+
+                    let channel_identifier = channel.clone();
+                    match self
+                        .db
+                        .run(move |db| db.get_channel(&channel_identifier))
+                        .await
+                    {
+                        Ok(Some(channel)) => self.debug_reports.set_last_error(
+                            channel.id,
+                            LastError {
+                                command: command.clone(),
+                                error: e.details(),
+                                created_at: Utc::now(),
+                            },
+                        ),
+                        Ok(None) => (),
+                        Err(err) => tracing::warn!("Could not get channel: {err}"),
+                    }
+
+                    let language = self.get_language(&user, &channel).await;
+                    let settings = self
+                        .platform_handler
+                        .read()
+                        .await
+                        .get_channel_settings(&channel);
+
+                    match format_error_response(&settings, &command, &e.localize(language)) {
+                        Some(response) => CommandOutput::Text(response),
+                        None => CommandOutput::Nothing,
+                    }
+                }
             }
         }
     }
 
+    /// Falls back to the default language if it can't be looked up
+    async fn get_language(&self, user: &UserIdentifier, channel: &ChannelIdentifier) -> Language {
+        let user = user.clone();
+        let user_language = self
+            .db
+            .run(move |db| match db.get_user(&user) {
+                Ok(Some(user)) => db.get_language(user.id).unwrap_or_else(|e| {
+                    tracing::warn!("Could not get user language: {e}");
+                    None
+                }),
+                Ok(None) => None,
+                Err(e) => {
+                    tracing::warn!("Could not get user: {e}");
+                    None
+                }
+            })
+            .await;
+        let channel_language = self
+            .platform_handler
+            .read()
+            .await
+            .get_channel_settings(channel)
+            .language;
+
+        Language::resolve(user_language.as_deref(), channel_language.as_deref())
+    }
+
     // #[async_recursion]
     #[instrument(skip(self, platform_ctx))]
     async fn run_command<P: PlatformContext + Send + Sync>(
@@ -454,7 +1144,7 @@ impl CommandHandler {
         command: &str,
         args: Vec<&str>,
         platform_ctx: P,
-    ) -> Result<Option<String>, CommandError> {
+    ) -> Result<CommandOutput, CommandError> {
         let span = Span::current();
         let trace_id = span.context().span().span_context().trace_id();
 
@@ -462,7 +1152,10 @@ impl CommandHandler {
         let processing_timestamp = Utc::now();
 
         let user_identifier = platform_ctx.get_user_identifier();
-        let user = self.db.get_or_create_user(&user_identifier)?;
+        let user = self
+            .db
+            .run(move |db| db.get_or_create_user(&user_identifier))
+            .await?;
 
         if !self
             .cooldowns
@@ -471,7 +1164,11 @@ impl CommandHandler {
             .contains(&(user.id, command.to_string()))
         {
             let platform_handler = self.platform_handler.read().await;
-            let channel = self.db.get_or_create_channel(&platform_ctx.get_channel())?;
+            let channel_identifier = platform_ctx.get_channel();
+            let channel = self
+                .db
+                .run(move |db| db.get_or_create_channel(&channel_identifier))
+                .await?;
             let mut execution_ctx = ExecutionContext {
                 db: &self.db,
                 channel_id: channel.map(|channel| channel.id),
@@ -480,9 +1177,12 @@ impl CommandHandler {
                 user: &user,
                 processing_timestamp,
                 blocked_users: &self.blocked_users,
+                payload: None,
             };
 
-            let (output, cooldown) = if let Some(builtin_command) = self
+            let arguments: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+
+            let (output, cooldown, executed) = if let Some(builtin_command) = self
                 .builtin_commands
                 .iter()
                 .find(|cmd| cmd.get_names().contains(&command))
@@ -498,29 +1198,52 @@ impl CommandHandler {
                     .execute(&execution_ctx, command, args)
                     .await?;
 
-                (output, cooldown)
+                (output, cooldown, true)
             } else if let Some(command) = self
-                .db
-                .get_command(&execution_ctx.platform_ctx.get_channel(), command)?
+                .get_channel_command(execution_ctx.platform_ctx.get_channel(), command)
+                .await?
             {
-                // TODO custom permissions
+                // A channel command hides the global command of the same name even where it
+                // isn't available
+                if command.is_available_in(execution_ctx.platform_ctx.get_subchannel()) {
+                    // TODO custom permissions
 
-                execution_ctx.channel_id = Some(command.channel_id);
+                    execution_ctx.channel_id = Some(command.channel_id);
+                    let cooldown = command.cooldown.unwrap_or(DEFAULT_COOLDOWN);
+
+                    let output: CommandOutput = self
+                        .execute_command(command, &execution_ctx, arguments.clone())
+                        .await?
+                        .into();
+
+                    (output, cooldown, true)
+                } else {
+                    (CommandOutput::Nothing, 0, false)
+                }
+            } else if let Some(command) = self.get_global_command(command).await? {
                 let cooldown = command.cooldown.unwrap_or(DEFAULT_COOLDOWN);
 
-                let output = self
-                    .execute_command(
-                        command,
-                        &execution_ctx,
-                        args.into_iter().map(|a| a.to_owned()).collect(),
-                    )
-                    .await?;
+                let output: CommandOutput = self
+                    .execute_global_command(command, &execution_ctx, arguments.clone())
+                    .await?
+                    .into();
 
-                (output, cooldown)
+                (output, cooldown, true)
             } else {
-                (None, 0)
+                (CommandOutput::Nothing, 0, false)
             };
 
+            if executed {
+                self.webhook_dispatcher.dispatch(
+                    &execution_ctx.platform_ctx.get_channel(),
+                    BotEvent::CommandExecuted {
+                        command: command.to_owned(),
+                        user_id: user.id,
+                        arguments,
+                    },
+                );
+            }
+
             if cooldown != 0 {
                 self.start_cooldown(user.id, command.to_string(), cooldown)
                     .await;
@@ -529,10 +1252,31 @@ impl CommandHandler {
             Ok(output)
         } else {
             tracing::debug!("Ignoring command, on cooldown");
-            Ok(None)
+            Ok(CommandOutput::Nothing)
         }
     }
 
+    async fn get_channel_command(
+        &self,
+        channel_identifier: ChannelIdentifier,
+        command: &str,
+    ) -> Result<Option<Command>, DatabaseError> {
+        let command = command.to_owned();
+
+        self.db
+            .run(move |db| db.get_command(&channel_identifier, &command))
+            .await
+    }
+
+    async fn get_global_command(
+        &self,
+        command: &str,
+    ) -> Result<Option<GlobalCommand>, DatabaseError> {
+        let command = command.to_owned();
+
+        self.db.run(move |db| db.get_global_command(&command)).await
+    }
+
     #[instrument(skip(self))]
     pub async fn execute_command<P: PlatformContext>(
         &self,
@@ -540,24 +1284,91 @@ impl CommandHandler {
         ctx: &ExecutionContext<'_, P>,
         args: Vec<String>,
     ) -> Result<Option<String>, CommandError> {
-        match command.mode {
-            CommandMode::Template => {
-                execute_template_command(self.template_registry.clone(), command.action, ctx, args)
-                    .await
-            }
-            CommandMode::Hebi => {
-                let hebi_ctx = HebiContext::try_from(ctx)?;
+        if self.is_safe_mode() {
+            tracing::debug!(
+                "Not executing command {}, running in safe mode",
+                command.name
+            );
+            return Ok(None);
+        }
 
-                eval_hebi(
-                    command.action,
-                    &self.hebi_native_modules,
-                    self.hebi_module_storage.clone(),
-                    self.db.clone(),
-                    &args,
-                    hebi_ctx,
-                )
-                .await
-            }
+        let params = parse_command_params(&command, &args, ctx.get_language().await?)?;
+
+        execute_action(
+            self.template_registry.load_full(),
+            &self.hebi_native_modules,
+            self.hebi_module_storage.clone(),
+            self.openai_api.as_ref(),
+            self.image_api.as_ref(),
+            command.mode,
+            command.action,
+            Some(&command.name),
+            ctx,
+            args,
+            params,
+        )
+        .await
+    }
+
+    #[instrument(skip(self))]
+    async fn execute_global_command<P: PlatformContext>(
+        &self,
+        command: GlobalCommand,
+        ctx: &ExecutionContext<'_, P>,
+        args: Vec<String>,
+    ) -> Result<Option<String>, CommandError> {
+        if self.is_safe_mode() {
+            tracing::debug!(
+                "Not executing global command {}, running in safe mode",
+                command.name
+            );
+            return Ok(None);
+        }
+
+        execute_action(
+            self.template_registry.load_full(),
+            &self.hebi_native_modules,
+            self.hebi_module_storage.clone(),
+            self.openai_api.as_ref(),
+            self.image_api.as_ref(),
+            command.mode,
+            command.action,
+            Some(&command.name),
+            ctx,
+            args,
+            None,
+        )
+        .await
+    }
+
+    /// Runs the test cases of the command in dry run mode
+    pub async fn run_command_tests<P: PlatformContext>(
+        &self,
+        command: &Command,
+        ctx: &ExecutionContext<'_, P>,
+    ) -> Result<Vec<CommandTestResult>, CommandError> {
+        if self.is_safe_mode() {
+            return Err(CommandError::Localized(Message::SafeModeCommandsDisabled));
+        }
+
+        let channel_id = command.channel_id;
+        let name = command.name.clone();
+        let tests = self
+            .db
+            .run(move |db| db.get_command_tests(channel_id, &name))
+            .await?;
+
+        Ok(command_tests::run_command_tests(&tests, |args| {
+            self.execute_command(command.clone(), ctx, args)
+        })
+        .await)
+    }
+
+    /// Checks the action for syntax errors and unknown helpers or modules without executing it
+    pub fn validate_action(&self, mode: &CommandMode, action: &str) -> ActionValidation {
+        match mode {
+            CommandMode::Template => validate_template(&self.template_registry.load(), action),
+            CommandMode::Hebi => validate_hebi(&self.hebi_module_storage, action),
         }
     }
 
@@ -582,7 +1393,7 @@ impl CommandHandler {
         user: User,
         channel: &ChannelIdentifier,
     ) -> anyhow::Result<Permissions> {
-        if let Ok(Some(admin_user)) = self.db.get_admin_user() {
+        if let Ok(Some(admin_user)) = self.db.run(|db| db.get_admin_user()).await {
             if user.id == admin_user.id {
                 return Ok(Permissions::Admin);
             }
@@ -641,19 +1452,43 @@ impl CommandHandler {
                     false => Ok(Permissions::Default),
                 }
             }
-            ChannelIdentifier::IrcChannel(_) => Ok(Permissions::Default), // TODO
+            ChannelIdentifier::IrcChannel(channel) => {
+                let irc_name = user
+                    .irc_name
+                    .ok_or_else(|| anyhow!("Not registered on this platform"))?;
+
+                let (network_name, channel) = irc::parse_target(channel);
+                let (user_network_name, nickname) = irc::parse_target(&irc_name);
+
+                if network_name != user_network_name {
+                    return Ok(Permissions::Default);
+                }
+
+                let platform_handler = self.platform_handler.read().await;
+
+                let network = platform_handler
+                    .irc_networks
+                    .get(network_name)
+                    .ok_or_else(|| anyhow!("IRC network {network_name} not configured"))?;
+
+                Ok(network.get_permissions(channel, nickname))
+            }
             ChannelIdentifier::Anonymous => Ok(Permissions::Default),
-            ChannelIdentifier::LocalAddress(_) => Ok(Permissions::ChannelOwner), // on the local platform, each ip address is its own channel
+            ChannelIdentifier::LocalAddress(_) => Ok(Permissions::ChannelOwner), // on the local platform, each client is its own channel
             ChannelIdentifier::Minecraft => Ok(Permissions::Default),
             ChannelIdentifier::TelegramChat(_) => Ok(Permissions::Default),
-            ChannelIdentifier::MatrixChannel(channel_id) => {
-                get_connector_permissions(
-                    &self.nats_client,
-                    "matrix",
-                    channel_id.clone(),
-                    user.matrix_id.context("User has no matrix id")?,
-                )
-                .await
+            ChannelIdentifier::Connector(platform, channel_id) => {
+                let user_id = user.id;
+                let connector_platform = platform.clone();
+                let connector_user_id = self
+                    .db
+                    .run(move |db| db.get_connector_user_id(user_id, &connector_platform))
+                    .await?
+                    .with_context(|| format!("User has no {platform} id"))?;
+
+                self.connector_permissions
+                    .get(platform, channel_id.clone(), connector_user_id)
+                    .await
             }
         }
     }
@@ -663,12 +1498,17 @@ impl CommandHandler {
         user_id: u64,
         channel_id: u64,
     ) -> anyhow::Result<Permissions> {
-        let user = self
+        let (user, channel) = self
             .db
-            .get_user_by_id(user_id)?
-            .ok_or_else(|| anyhow!("Invalid user id"))?;
+            .run(move |db| {
+                let user = db.get_user_by_id(user_id)?;
+                let channel = db.get_channel_by_id(channel_id)?;
+                Ok::<_, DatabaseError>((user, channel))
+            })
+            .await?;
+        let user = user.ok_or_else(|| anyhow!("Invalid user id"))?;
 
-        match self.db.get_channel_by_id(channel_id)? {
+        match channel {
             Some(channel) => {
                 let channel_identifier =
                     ChannelIdentifier::new(&channel.platform, channel.channel)?;
@@ -688,9 +1528,19 @@ impl CommandHandler {
         platform_ctx: ServerPlatformContext,
         arguments: Vec<String>,
         channel_id: Option<u64>,
+        payload: Option<serde_json::Value>,
+        redemption: Option<PendingRedemption>,
     ) -> anyhow::Result<()> {
+        if self.is_safe_mode() {
+            return Err(anyhow!("Not executing server action, running in safe mode"));
+        }
+
         let processing_timestamp = Utc::now();
-        let user = self.db.get_or_create_user(&platform_ctx.executing_user)?;
+        let executing_user = platform_ctx.executing_user.clone();
+        let user = self
+            .db
+            .run(move |db| db.get_or_create_user(&executing_user))
+            .await?;
 
         let platform_handler = self.platform_handler.read().await;
         let execution_ctx = ExecutionContext {
@@ -701,32 +1551,53 @@ impl CommandHandler {
             user: &user,
             processing_timestamp,
             blocked_users: &self.blocked_users,
+            payload,
         };
 
-        let response = match mode {
+        let result = match mode {
             CommandMode::Template => {
                 execute_template_command(
-                    self.template_registry.clone(),
+                    self.template_registry.load_full(),
                     action,
+                    None,
                     &execution_ctx,
                     arguments,
+                    None,
                 ) // TODO
-                .await?
+                .await
             }
-            CommandMode::Hebi => {
-                let hebi_ctx = HebiContext::try_from(&execution_ctx)?;
-                eval_hebi(
-                    action,
-                    &self.hebi_native_modules,
-                    self.hebi_module_storage.clone(),
-                    self.db.clone(),
-                    &arguments,
-                    hebi_ctx,
-                )
-                .await?
+            CommandMode::Hebi => match HebiContext::new(&execution_ctx).await {
+                Ok(hebi_ctx) => {
+                    eval_hebi(
+                        action,
+                        &self.hebi_native_modules,
+                        self.hebi_module_storage.clone(),
+                        self.db.clone(),
+                        self.openai_api.as_ref(),
+                        self.image_api.as_ref(),
+                        &platform_handler,
+                        &arguments,
+                        hebi_ctx,
+                    )
+                    .await
+                }
+                Err(err) => Err(err),
+            },
+        };
+
+        // Failed actions refund the channel points
+        if let Some(redemption) = redemption {
+            let status = match result {
+                Ok(_) => RedemptionStatus::Fulfilled,
+                Err(_) => RedemptionStatus::Canceled,
+            };
+
+            if let Err(err) = self.update_redemption_status(&redemption, status).await {
+                tracing::warn!("Could not update redemption {}: {err:#}", redemption.id);
             }
         }
-        .unwrap_or_else(|| "Event triggered with no action".to_string());
+
+        let response = result?.unwrap_or_else(|| "Event triggered with no action".to_string());
 
         Ok(self
             .platform_handler
@@ -736,64 +1607,53 @@ impl CommandHandler {
             .await?)
     }
 
-    /*pub async fn join_channel(&self, channel: &ChannelIdentifier) -> anyhow::Result<()> {
-        match channel {
-            ChannelIdentifier::TwitchChannel((id, _)) => {
-                let platform_handler = self.platform_handler.read().await;
-                let twitch_api = platform_handler
-                    .twitch_api
-                    .as_ref()
-                    .context("Twitch not initialized")?;
-
-                let user = twitch_api.helix_api.get_user_by_id(id).await?;
-
-                let chat_sender_guard = twitch_api.chat_sender.lock().await;
-                let chat_sender = chat_sender_guard
-                    .as_ref()
-                    .context("Twitch chat not initialized")?;
-
-                chat_sender
-                    .send(twitch::SenderMessage::JoinChannel(user.login.clone()))
-                    .unwrap();
-                chat_sender
-                    .send(twitch::SenderMessage::Privmsg(twitch::Privmsg {
-                        channel_login: user.login,
-                        message: String::from("MrDestructoid 👍 Foobot2 joined"),
-                        reply_to_id: None,
-                    }))
-                    .unwrap();
+    /// Only works for rewards that were created with the same client id as the bot
+    async fn update_redemption_status(
+        &self,
+        redemption: &PendingRedemption,
+        status: RedemptionStatus,
+    ) -> anyhow::Result<()> {
+        let helix_api = get_broadcaster_helix_api(&self.db, &redemption.broadcaster_id).await?;
+
+        helix_api
+            .update_redemption_status(
+                &redemption.broadcaster_id,
+                &redemption.reward_id,
+                &redemption.id,
+                status,
+            )
+            .await
+    }
 
-                self.db
-                    .get_or_create_channel(channel)?
-                    .context("Failed to add channel")?;
+    pub async fn join_twitch_channel(&self, login: &str) -> anyhow::Result<Channel> {
+        let platform_handler = self.platform_handler.read().await;
+        platform_handler.join_twitch_channel(&self.db, login).await
+    }
 
-                Ok(())
-            }
-            ChannelIdentifier::DiscordChannel(_) => Ok(()), // Discord guilds don't need to be joined client side and get added to the DB on demand
-            ChannelIdentifier::TelegramChat(_) => todo!(),
-            ChannelIdentifier::IrcChannel(_) => Err(anyhow!("Not implemented yet")),
-            ChannelIdentifier::LocalAddress(_) => Err(anyhow!("This is not possible")),
-            ChannelIdentifier::Minecraft => panic!("This should never happen"),
-            ChannelIdentifier::Anonymous => Err(anyhow!("Invalid channel specified")),
-        }
-    }*/
+    /// The channel is kept in the DB with its commands, but is not joined on startup anymore
+    pub async fn part_channel(&self, channel: &Channel) -> anyhow::Result<()> {
+        let platform_handler = self.platform_handler.read().await;
+        platform_handler.part_channel(&self.db, channel).await
+    }
 
-    fn get_command_triggers(
+    async fn get_command_triggers(
         &self,
         channel_id: u64,
-    ) -> Result<Arc<DashMap<String, String>>, CommandError> {
-        match self.command_triggers.get(&channel_id) {
-            Some(triggers) => Ok(triggers.clone()),
-            None => {
-                self.refresh_command_triggers(channel_id)?;
-                self.get_command_triggers(channel_id)
-            }
+    ) -> Result<Arc<DashMap<String, String>>, DatabaseError> {
+        if let Some(triggers) = self.command_triggers.get(&channel_id) {
+            return Ok(triggers.clone());
         }
-    }
 
-    fn refresh_command_triggers(&self, channel_id: u64) -> Result<(), CommandError> {
-        let commands = self.db.get_commands(channel_id)?;
+        let commands = self.db.run(move |db| db.get_commands(channel_id)).await?;
 
+        Ok(self.set_command_triggers(channel_id, commands))
+    }
+
+    fn set_command_triggers(
+        &self,
+        channel_id: u64,
+        commands: Vec<Command>,
+    ) -> Arc<DashMap<String, String>> {
         let triggers = DashMap::new();
 
         for command in commands {
@@ -804,15 +1664,16 @@ impl CommandHandler {
             }
         }
 
+        let triggers = Arc::new(triggers);
         if self
             .command_triggers
-            .insert(channel_id, Arc::new(triggers))
+            .insert(channel_id, triggers.clone())
             .is_some()
         {
             tracing::info!("Reloaded command triggers in channel {}", channel_id);
         }
 
-        Ok(())
+        triggers
     }
 }
 
@@ -823,7 +1684,8 @@ pub struct ExecutionContext<'a, P: PlatformContext> {
     pub channel_id: Option<u64>,
     pub user: &'a User,
     pub processing_timestamp: DateTime<Utc>,
-    pub blocked_users: &'a [UserIdentifier],
+    pub blocked_users: &'a BlockedUsers,
+    pub payload: Option<serde_json::Value>, // Extra data from the source of a server-side execution
 }
 
 impl<P: PlatformContext> Debug for ExecutionContext<'_, P> {
@@ -840,44 +1702,52 @@ impl<P: PlatformContext> Debug for ExecutionContext<'_, P> {
 impl<P: PlatformContext> ExecutionContext<'_, P> {
     #[instrument]
     async fn get_permissions(&self) -> Result<Permissions, CommandError> {
-        if let Ok(Some(admin_user)) = self.db.get_admin_user() {
+        if let Ok(Some(admin_user)) = self.db.run(|db| db.get_admin_user()).await {
             if admin_user.id == self.user.id {
                 return Ok(Permissions::Admin);
             }
         }
 
         let identifier = self.platform_ctx.get_user_identifier();
-        if self.blocked_users.contains(&identifier) {
+        if self.blocked_users.is_blocked(&identifier, self.channel_id) {
             return Err(CommandError::NoPermissions);
         };
 
         Ok(self.platform_ctx.get_permissions_internal().await)
     }
-}
 
-async fn start_supinic_heartbeat() {
-    task::spawn(async move {
-        let client = Client::new();
+    /// The language that builtin responses are sent in
+    pub async fn get_language(&self) -> Result<Language, CommandError> {
+        let user_id = self.user.id;
+        let user_language = self.db.run(move |db| db.get_language(user_id)).await?;
+        let channel_language = self
+            .platform_handler
+            .get_channel_settings(&self.platform_ctx.get_channel())
+            .language;
 
-        let user_id = env::var("SUPINIC_USER_ID").unwrap_or_default();
-        let pass = env::var("SUPINIC_PASSWORD").unwrap_or_default();
+        Ok(Language::resolve(
+            user_language.as_deref(),
+            channel_language.as_deref(),
+        ))
+    }
+
+    /// Permissions for the Twitch moderation helpers. Other platforms don't support them,
+    /// and can need API requests to get the permissions
+    async fn get_twitch_permissions(&self) -> Result<Option<Permissions>, CommandError> {
+        match self.platform_ctx.get_channel() {
+            ChannelIdentifier::TwitchChannel(_) => Ok(Some(self.get_permissions().await?)),
+            _ => Ok(None),
+        }
+    }
+}
 
+fn start_supinic_heartbeat(supinic_api: SupinicApi) {
+    task::spawn(async move {
         loop {
             tracing::info!("Pinging Supinic API");
 
-            match client
-                .put("https://supinic.com/api/bot-program/bot/active")
-                .header("Authorization", format!("Basic {}:{}", user_id, pass))
-                .send()
-                .await
-            {
-                Ok(response) => {
-                    if !response.status().is_success() {
-                        let text = response.text().await;
-                        tracing::info!("Supinic API error: {:?}", text);
-                    }
-                }
-                Err(e) => tracing::warn!("Failed to ping Supinic API! {}", e),
+            if let Err(e) = supinic_api.ping_active().await {
+                tracing::warn!("Failed to ping Supinic API! {e:#}");
             }
 
             tokio::time::sleep(Duration::from_secs(3600)).await;
@@ -885,35 +1755,293 @@ async fn start_supinic_heartbeat() {
     });
 }
 
+/// Recreates EventSub subscriptions that are stored in the DB but missing or revoked on Twitch.
+/// Runs periodically, retrying in the background when it fails.
+/// When sharding, the triggers are reconciled by the shard that owns direct messages
+fn start_eventsub_reconciliation(
+    api: platform_handler::TwitchApi,
+    db: Database,
+    status: StatusTracker,
+    sharding: Sharding,
+) {
+    task::spawn(async move {
+        let mut backoff = Backoff::new(EVENTSUB_RETRY_DELAY, EVENTSUB_MAX_RETRY_DELAY);
+        let mut shard_changes = sharding.subscribe();
+
+        loop {
+            if !sharding.owns_channel(None) {
+                tokio::select! {
+                    _ = shard_changes.changed() => (),
+                    _ = tokio::time::sleep(EVENTSUB_RECONCILE_INTERVAL) => (),
+                }
+                continue;
+            }
+
+            match reconcile_eventsub_triggers(&api, &db).await {
+                Ok(()) => {
+                    status.clear_degraded("eventsub");
+                    backoff.reset();
+                    tokio::time::sleep(EVENTSUB_RECONCILE_INTERVAL).await;
+                }
+                Err(e) => {
+                    let delay = backoff.next_delay();
+                    tracing::warn!(
+                        "Failed to reconcile EventSub triggers, retrying in {delay:?}: {e:#}"
+                    );
+                    status.set_degraded("eventsub", format!("{e:#}"));
+
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    });
+}
+
+/// Subscriptions that were created with an old secret are recreated as well,
+/// after which the old secrets are no longer accepted
+pub async fn reconcile_eventsub_triggers(
+    api: &platform_handler::TwitchApi,
+    db: &Database,
+) -> anyhow::Result<()> {
+    let subscriptions = api
+        .helix_api_app
+        .get_eventsub_subscriptions(None)
+        .await
+        .context("Failed to get EventSub triggers")?;
+    let (current_secret, triggers) = db
+        .run(|db| {
+            let current_secret = db.get_current_eventsub_secret()?;
+            let triggers = db.get_eventsub_triggers()?;
+            Ok::<_, DatabaseError>((current_secret, triggers))
+        })
+        .await?;
+
+    for trigger in triggers {
+        let is_active = subscriptions
+            .iter()
+            .any(|subscription| subscription.id == trigger.id && subscription.is_active());
+
+        if !is_active || trigger.secret_id != Some(current_secret.id) {
+            // A single broken trigger shouldn't block the others from being recreated
+            if let Err(e) = resubscribe_eventsub_trigger(api, db, &trigger).await {
+                tracing::error!("Failed to recreate EventSub trigger {}: {e:#}", trigger.id);
+            }
+        }
+    }
+
+    db.run(|db| db.delete_unused_eventsub_secrets()).await?;
+
+    Ok(())
+}
+
+/// Replaces the trigger's subscription with a new one, returning the id of the new subscription
+pub async fn resubscribe_eventsub_trigger(
+    api: &platform_handler::TwitchApi,
+    db: &Database,
+    trigger: &EventSubTrigger,
+) -> anyhow::Result<String> {
+    let subscription_type: EventSubSubscriptionType =
+        serde_json::from_str(&trigger.creation_payload).context("Invalid trigger payload")?;
+
+    // Revoked subscriptions still count towards the subscription limit until they are deleted
+    if let Err(e) = api
+        .helix_api_app
+        .delete_eventsub_subscription(&trigger.id)
+        .await
+    {
+        tracing::debug!(
+            "Could not delete old EventSub subscription {}: {e}",
+            trigger.id
+        );
+    }
+
+    let secret = db.run(|db| db.get_current_eventsub_secret()).await?;
+    let response = api
+        .helix_api_app
+        .add_eventsub_subscription(subscription_type, &db.config(), &secret.secret)
+        .await?;
+    let new_id = response
+        .data
+        .into_iter()
+        .next()
+        .context("Twitch did not return the created subscription")?
+        .id;
+
+    let (trigger_id, subscription_id) = (trigger.id.clone(), new_id.clone());
+    db.run(move |db| {
+        db.update_eventsub_trigger_subscription(&trigger_id, &subscription_id, secret.id)
+    })
+    .await?;
+
+    Ok(new_id)
+}
+
+/// Commands that existed before a builtin command with the same name was added are never
+/// executed, so they are reported to be renamed
+fn warn_builtin_command_conflicts(db: &Database) {
+    match db.get_builtin_command_conflicts() {
+        Ok((channel_commands, global_commands)) => {
+            for command in channel_commands {
+                tracing::warn!(
+                    "Command {} of channel {} is shadowed by the builtin command of the same name",
+                    command.name,
+                    command.channel_id
+                );
+            }
+            for command in global_commands {
+                tracing::warn!(
+                    "Global command {} is shadowed by the builtin command of the same name",
+                    command.name
+                );
+            }
+        }
+        Err(e) => tracing::error!("Could not check commands for conflicts with builtins: {e}"),
+    }
+}
+
+/// Checks the arguments of a command that has an args spec, returning their values
+fn parse_command_params(
+    command: &Command,
+    args: &[String],
+    language: Language,
+) -> Result<Option<serde_json::Value>, CommandError> {
+    match &command.args_spec {
+        Some(raw_spec) => {
+            let spec = ArgsSpec::from_str(raw_spec).map_err(CommandError::GenericError)?;
+            let params = spec.parse(args).map_err(|e| {
+                CommandError::InvalidArgument(
+                    Message::InvalidCommandArgs {
+                        error: &e.to_string(),
+                        name: &command.name,
+                        spec: &spec.to_string(),
+                    }
+                    .translate(language),
+                )
+            })?;
+
+            Ok(Some(serde_json::Value::Object(params)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Executes the action of a custom or personal command according to its mode
+#[allow(clippy::too_many_arguments)]
+async fn execute_action<P: PlatformContext>(
+    template_registry: Arc<Handlebars<'static>>,
+    native_modules: &[NativeModule],
+    module_storage: ModuleStorage,
+    openai_api: Option<&OpenAiApi>,
+    image_api: Option<&ImageApi>,
+    mode: CommandMode,
+    action: String,
+    command_name: Option<&str>,
+    ctx: &ExecutionContext<'_, P>,
+    args: Vec<String>,
+    params: Option<serde_json::Value>,
+) -> Result<Option<String>, CommandError> {
+    match mode {
+        CommandMode::Template => {
+            execute_template_command(template_registry, action, command_name, ctx, args, params)
+                .await
+        }
+        CommandMode::Hebi => {
+            let mut hebi_ctx = HebiContext::new(ctx).await?;
+            hebi_ctx.params = params;
+
+            eval_hebi(
+                action,
+                native_modules,
+                module_storage,
+                ctx.db.clone(),
+                openai_api,
+                image_api,
+                ctx.platform_handler,
+                &args,
+                hebi_ctx,
+            )
+            .await
+        }
+    }
+}
+
 #[instrument(skip(template_registry))]
 async fn execute_template_command<P: PlatformContext>(
     template_registry: Arc<Handlebars<'static>>,
     action: String,
+    command_name: Option<&str>,
     ctx: &ExecutionContext<'_, P>,
     args: Vec<String>,
+    params: Option<serde_json::Value>,
 ) -> Result<Option<String>, CommandError> {
     tracing::debug!("Parsing action {}", action);
 
+    // The invocation that `paginate` suggests for the next page
+    let command = command_name.map(|name| {
+        let prefix = ctx.platform_ctx.get_prefixes().first().copied();
+        format!("{}{name}", prefix.unwrap_or_default())
+    });
+
     let display_name = ctx.platform_ctx.get_display_name().to_string();
     let channel = ctx.platform_ctx.get_channel();
     let user = ctx.user.clone();
+    let payload = ctx.payload.clone();
+    let permissions = ctx.get_twitch_permissions().await?;
+
+    let (user_id, channel_id) = (ctx.user.id, ctx.channel_id);
+    let (timezone, language, partials) = ctx
+        .db
+        .run(move |db| -> Result<_, DatabaseError> {
+            let partials = match channel_id {
+                Some(channel_id) => db.get_template_partials(channel_id)?,
+                None => Vec::new(),
+            };
+            Ok((
+                db.get_timezone(user_id)?,
+                db.get_language(user_id)?,
+                partials,
+            ))
+        })
+        .await?;
+
+    let recorder = TraceRecorder::current();
+
+    let render_task = task::spawn_blocking(move || {
+        let template_registry = with_channel_partials(template_registry, &partials);
+
+        let render = || {
+            render_with_budget(
+                &template_registry,
+                &action,
+                &(InquiryContext {
+                    user,
+                    arguments: args,
+                    display_name,
+                    channel,
+                    payload,
+                    params,
+                    permissions,
+                    timezone,
+                    language,
+                    command,
+                }),
+            )
+        };
 
-    let response = match task::spawn_blocking(move || {
-        template_registry.render_template(
-            &action,
-            &(InquiryContext {
-                user,
-                arguments: args,
-                display_name,
-                channel,
-            }),
-        )
-    })
-    .await
-    .expect("Failed to join")
-    {
-        Ok(result) => result,
-        Err(err) => err.desc,
+        match recorder {
+            Some(recorder) => recorder.sync_scope(render),
+            None => render(),
+        }
+    });
+
+    // A helper that is stuck in a request is not interrupted, but the render fails
+    // at its next helper call once the budget is exceeded
+    let response = match tokio::time::timeout(MAX_RENDER_DURATION, render_task).await {
+        Ok(result) => match result.expect("Failed to join") {
+            Ok(result) => result,
+            Err(err) => err.desc,
+        },
+        Err(_) => render_budget::duration_exceeded().desc,
     };
 
     if !response.is_empty() {
@@ -923,9 +2051,107 @@ async fn execute_template_command<P: PlatformContext>(
     }
 }
 
-pub fn get_admin_channel() -> Option<ChannelIdentifier> {
-    if let Ok(admin_str) = env::var("ADMIN_USER") {
-        match ChannelIdentifier::from_str(&admin_str) {
+/// Helpers that depend on API keys, registered again when the config is reloaded.
+/// Helpers without a key are replaced with one that reports that they're not configured
+fn register_api_helpers(
+    template_registry: &mut Handlebars<'static>,
+    db: &Database,
+    api_keys: &ApiKeysConfig,
+) {
+    match &api_keys.finnhub {
+        Some(api_key) => template_registry
+            .register_http_helper("stock", Box::new(FinnhubApi::init(api_key.clone()))),
+        None => template_registry.register_traced_helper("stock", Box::new(UnconfiguredHelper)),
+    }
+
+    match &api_keys.exchange_rate {
+        Some(api_key) => template_registry
+            .register_http_helper("currency", Box::new(ExchangeRateApi::init(api_key.clone()))),
+        None => template_registry.register_traced_helper("currency", Box::new(UnconfiguredHelper)),
+    }
+
+    template_registry.register_http_helper(
+        "crypto",
+        Box::new(CoinGeckoApi::init(api_keys.coingecko.clone())),
+    );
+
+    match &api_keys.owm {
+        Some(api_key) => template_registry.register_http_helper(
+            "weather",
+            Box::new(WeatherHelper {
+                db: db.clone(),
+                api: OwmApi::init(api_key.clone()),
+            }),
+        ),
+        None => template_registry.register_traced_helper("weather", Box::new(UnconfiguredHelper)),
+    }
+
+    match &api_keys.lastfm {
+        Some(api_key) => template_registry.register_http_helper(
+            "lastfm",
+            Box::new(LastFMHelper {
+                db: db.clone(),
+                lastfm_api: LastFMApi::init(api_key.clone()),
+            }),
+        ),
+        None => template_registry.register_traced_helper("lastfm", Box::new(UnconfiguredHelper)),
+    }
+
+    for (name, stat) in [
+        ("lastfm_top", LastFMStat::TopArtists),
+        ("lastfm_count", LastFMStat::Playcount),
+        ("lastfm_album", LastFMStat::Album),
+        ("lastfm_artist", LastFMStat::Artist),
+    ] {
+        match &api_keys.lastfm {
+            Some(api_key) => template_registry.register_http_helper(
+                name,
+                Box::new(LastFMStatsHelper {
+                    db: db.clone(),
+                    lastfm_api: LastFMApi::init(api_key.clone()),
+                    stat,
+                }),
+            ),
+            None => template_registry.register_traced_helper(name, Box::new(UnconfiguredHelper)),
+        }
+    }
+}
+
+fn load_mirror_connections(
+    db: &Database,
+) -> Result<HashMap<String, ChannelIdentifier>, DatabaseError> {
+    let mut mirror_connections = HashMap::new();
+
+    for connection in db.get_mirror_connections()? {
+        let from_channel = db.get_channel_by_id(connection.from_channel_id)?;
+        let to_channel = db.get_channel_by_id(connection.to_channel_id)?;
+
+        let (from_channel, to_channel) = match (from_channel, to_channel) {
+            (Some(from_channel), Some(to_channel)) => (from_channel, to_channel),
+            _ => {
+                tracing::warn!(
+                    "Skipping mirror connection {} -> {} to an unknown channel",
+                    connection.from_channel_id,
+                    connection.to_channel_id
+                );
+                continue;
+            }
+        };
+
+        if let Some(from_channel_str) = from_channel.get_identifier().get_channel() {
+            mirror_connections.insert(
+                format!("{}-{}", from_channel.platform, from_channel_str),
+                to_channel.get_identifier(),
+            );
+        }
+    }
+
+    Ok(mirror_connections)
+}
+
+pub fn get_admin_channel(config: &Config) -> Option<ChannelIdentifier> {
+    if let Some(admin_str) = &config.admin_user {
+        match ChannelIdentifier::from_str(admin_str) {
             Ok(admin_channel) => Some(admin_channel),
             Err(e) => {
                 tracing::warn!("Failed to get admin channel: {}", e);
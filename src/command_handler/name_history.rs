@@ -0,0 +1,124 @@
+use crate::{
+    command_handler::platform_handler::TwitchApi,
+    database::{cache::TtlCache, Database, DatabaseError},
+    platform::UserIdentifier,
+};
+use std::{sync::Arc, time::Duration};
+use tokio::task;
+
+/// How long a name is trusted to be recorded before it's written again to update when it was seen
+const SEEN_TTL: Duration = Duration::from_secs(3600);
+const REFRESH_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+/// Twitch names that haven't been seen for this long are looked up
+const REFRESH_MAX_AGE_DAYS: i64 = 7;
+const REFRESH_BATCH_SIZE: u32 = 1000;
+
+/// Records the names that users chat with, so that renames can be looked up later
+#[derive(Debug, Clone)]
+pub struct NameHistory {
+    db: Database,
+    seen: Arc<TtlCache<UserIdentifier, String>>,
+}
+
+impl NameHistory {
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            seen: Arc::new(TtlCache::new(SEEN_TTL)),
+        }
+    }
+
+    /// Only writes to the DB when the name is different from the one that was last seen
+    pub fn record(&self, identifier: &UserIdentifier, name: &str) {
+        let platform = match get_platform(identifier) {
+            Some(platform) => platform.to_owned(),
+            None => return,
+        };
+        if name.is_empty() || self.seen.get(identifier).as_deref() == Some(name) {
+            return;
+        }
+        self.seen.insert(identifier.clone(), name.to_owned());
+
+        let db = self.db.clone();
+        let identifier = identifier.clone();
+        let name = name.to_owned();
+
+        task::spawn_blocking(move || {
+            let result = db
+                .get_or_create_user(&identifier)
+                .map_err(DatabaseError::from)
+                .and_then(|user| db.record_user_name(user.id, &platform, &name));
+
+            if let Err(e) = result {
+                tracing::warn!("Could not record the name of {identifier}: {e}");
+            }
+        });
+    }
+}
+
+/// The platform names in the name history, IRC names are the identifiers themselves
+/// so they can't change
+pub fn get_platform(identifier: &UserIdentifier) -> Option<&str> {
+    match identifier {
+        UserIdentifier::TwitchID(_) => Some("twitch"),
+        UserIdentifier::DiscordID(_) => Some("discord"),
+        UserIdentifier::TelegramId(_) => Some("telegram"),
+        UserIdentifier::Connector(platform, _) => Some(platform),
+        _ => None,
+    }
+}
+
+/// Looks up the current names of Twitch users who haven't been seen in chat for a while
+pub fn start_name_history_refresh(api: TwitchApi, db: Database) {
+    task::spawn(async move {
+        loop {
+            if let Err(e) = refresh_twitch_names(&api, &db).await {
+                tracing::warn!("Failed to refresh Twitch name history: {e:#}");
+            }
+
+            tokio::time::sleep(REFRESH_INTERVAL).await;
+        }
+    });
+}
+
+async fn refresh_twitch_names(api: &TwitchApi, db: &Database) -> anyhow::Result<()> {
+    let stale_users = db
+        .run(|db| {
+            db.get_stale_twitch_name_users(
+                chrono::Duration::days(REFRESH_MAX_AGE_DAYS),
+                REFRESH_BATCH_SIZE,
+            )
+        })
+        .await?;
+    if stale_users.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<&str> = stale_users.iter().map(|(_, id)| id.as_str()).collect();
+    let twitch_users = api.helix_api.get_users_by_ids_uncached(&ids).await?;
+    tracing::debug!(
+        "Refreshing the names of {} Twitch users, {} were found",
+        stale_users.len(),
+        twitch_users.len()
+    );
+
+    let names: Vec<(u64, String)> = twitch_users
+        .into_iter()
+        .filter_map(|twitch_user| {
+            stale_users
+                .iter()
+                .find(|(_, id)| *id == twitch_user.id)
+                .map(|(user_id, _)| (*user_id, twitch_user.display_name))
+        })
+        .collect();
+
+    db.run(move |db| {
+        for (user_id, name) in names {
+            db.record_user_name(user_id, "twitch", &name)?;
+        }
+        Ok::<_, DatabaseError>(())
+    })
+    .await?;
+
+    Ok(())
+}
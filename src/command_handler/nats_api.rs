@@ -0,0 +1,196 @@
+//! Request/reply API for other services over NATS.
+//! Requests and replies are JSON, replies are either `{"Ok": <response>}` or `{"Err": "<error>"}`.
+//!
+//! - `foobot.command.execute`: runs a message as if it was sent in the channel by the given user.
+//!   Request: `{"channel_id": 1, "user": "twitch:12345", "display_name": "name",
+//!   "message": "%ping", "send_response": false}`, where `display_name` and `send_response`
+//!   are optional. Response: `{"response": "pong"}`
+//! - `foobot.channel.send`: sends a message to a channel.
+//!   Request: `{"channel_id": 1, "message": "hello"}`. Response: `null`
+//! - `foobot.user.lookup`: looks up a user by the id or a platform identifier.
+//!   Request: `{"id": 1}` or `{"identifier": "twitch:12345"}`. Response: the user
+use super::CommandHandler;
+use crate::{
+    database::models::{Channel, User},
+    platform::{ServerPlatformContext, UserIdentifier},
+};
+use anyhow::{anyhow, Context};
+use async_nats::Message;
+use futures::StreamExt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::{debug, error, info};
+
+pub const SUBJECT_PREFIX: &str = "foobot.";
+pub const EXECUTE_COMMAND_SUBJECT: &str = "foobot.command.execute";
+pub const SEND_MESSAGE_SUBJECT: &str = "foobot.channel.send";
+pub const USER_LOOKUP_SUBJECT: &str = "foobot.user.lookup";
+
+#[derive(Deserialize, Debug)]
+struct ExecuteCommandRequest {
+    channel_id: u64,
+    user: String,
+    #[serde(default)]
+    display_name: String,
+    message: String,
+    #[serde(default)]
+    send_response: bool,
+}
+
+#[derive(Serialize)]
+struct ExecuteCommandResponse {
+    response: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SendMessageRequest {
+    channel_id: u64,
+    message: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct UserLookupRequest {
+    id: Option<u64>,
+    identifier: Option<String>,
+}
+
+pub fn start(command_handler: CommandHandler) {
+    tokio::spawn(async move {
+        let nats_client = command_handler.nats_client.wait().await;
+
+        let subject = format!("{SUBJECT_PREFIX}>");
+        let mut subscriber = match nats_client
+            .queue_subscribe(subject, "foobot_core".into())
+            .await
+        {
+            Ok(subscriber) => subscriber,
+            Err(err) => {
+                error!("Could not subscribe to the NATS API subjects: {err}");
+                return;
+            }
+        };
+        info!("Listening to NATS API requests");
+
+        while let Some(msg) = subscriber.next().await {
+            let command_handler = command_handler.clone();
+            let nats_client = nats_client.clone();
+
+            tokio::spawn(async move {
+                let reply = match msg.reply.clone() {
+                    Some(reply) => reply,
+                    None => {
+                        debug!("Ignoring NATS API message without a reply subject");
+                        return;
+                    }
+                };
+
+                let response = match handle_request(&command_handler, &msg).await {
+                    Ok(response) => Ok(response),
+                    Err(err) => Err(format!("{err:#}")),
+                };
+                let payload = serde_json::to_vec(&response).expect("Failed to serialize reply");
+
+                if let Err(err) = nats_client.publish(reply, payload.into()).await {
+                    error!("Could not reply to NATS API request: {err}");
+                }
+            });
+        }
+    });
+}
+
+async fn handle_request(
+    command_handler: &CommandHandler,
+    msg: &Message,
+) -> anyhow::Result<serde_json::Value> {
+    match msg.subject.as_str() {
+        EXECUTE_COMMAND_SUBJECT => {
+            let request: ExecuteCommandRequest = parse_request(msg)?;
+            debug!("{request:?}");
+
+            let channel = get_channel(command_handler, request.channel_id).await?;
+            let executing_user = UserIdentifier::from_string(&request.user)
+                .map_err(|_| anyhow!("Invalid user identifier"))?;
+
+            let platform_ctx = ServerPlatformContext {
+                target_channel: channel.get_identifier(),
+                executing_user,
+                cmd: command_handler.clone(),
+                display_name: request.display_name,
+            };
+
+            let response = command_handler
+                .handle_message(&request.message, platform_ctx)
+                .await
+                .into_text();
+
+            if let (Some(response), true) = (&response, request.send_response) {
+                send_to_channel(command_handler, &channel, response.clone()).await?;
+            }
+
+            Ok(serde_json::to_value(ExecuteCommandResponse { response })?)
+        }
+        SEND_MESSAGE_SUBJECT => {
+            let request: SendMessageRequest = parse_request(msg)?;
+            debug!("{request:?}");
+
+            let channel = get_channel(command_handler, request.channel_id).await?;
+            send_to_channel(command_handler, &channel, request.message).await?;
+
+            Ok(serde_json::Value::Null)
+        }
+        USER_LOOKUP_SUBJECT => {
+            let request: UserLookupRequest = parse_request(msg)?;
+            let user = lookup_user(command_handler, request).await?;
+
+            Ok(serde_json::to_value(user)?)
+        }
+        subject => Err(anyhow!("Unknown subject {subject}")),
+    }
+}
+
+fn parse_request<T: DeserializeOwned>(msg: &Message) -> anyhow::Result<T> {
+    serde_json::from_slice(&msg.payload).context("Malformed request")
+}
+
+async fn get_channel(command_handler: &CommandHandler, channel_id: u64) -> anyhow::Result<Channel> {
+    command_handler
+        .db
+        .run(move |db| db.get_channel_by_id(channel_id))
+        .await?
+        .ok_or_else(|| anyhow!("Specified channel not found"))
+}
+
+async fn send_to_channel(
+    command_handler: &CommandHandler,
+    channel: &Channel,
+    message: String,
+) -> anyhow::Result<()> {
+    let platform_handler = command_handler.platform_handler.read().await;
+
+    platform_handler
+        .send_to_channel(channel.get_identifier(), message)
+        .await
+        .map_err(|err| anyhow!("Could not send message: {err}"))
+}
+
+async fn lookup_user(
+    command_handler: &CommandHandler,
+    request: UserLookupRequest,
+) -> anyhow::Result<User> {
+    let db = &command_handler.db;
+
+    let user = match (request.id, request.identifier) {
+        (Some(id), None) => db.run(move |db| db.get_user_by_id(id)).await?,
+        (None, Some(identifier)) => {
+            let identifier = UserIdentifier::from_string(&identifier)
+                .map_err(|_| anyhow!("Invalid user identifier"))?;
+            db.run(move |db| db.get_user(&identifier)).await?
+        }
+        _ => {
+            return Err(anyhow!(
+                "Either the id or the identifier has to be specified"
+            ))
+        }
+    };
+
+    user.ok_or_else(|| anyhow!("User not found"))
+}
@@ -0,0 +1,66 @@
+use super::status::StatusTracker;
+use crate::platform::supervisor::Backoff;
+use async_nats::Client;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// The NATS client, connected in the background so that chat keeps working while NATS is down.
+/// Once connected, the client reconnects on its own.
+#[derive(Clone)]
+pub struct NatsConnection {
+    client: watch::Receiver<Option<Client>>,
+}
+
+impl NatsConnection {
+    /// Retries with an exponential backoff until the initial connection succeeds
+    pub fn connect(address: String, status: StatusTracker) -> Self {
+        let (sender, client) = watch::channel(None);
+
+        tokio::spawn(async move {
+            let mut backoff = Backoff::new(INITIAL_RETRY_DELAY, MAX_RETRY_DELAY);
+
+            loop {
+                match async_nats::connect(&address).await {
+                    Ok(client) => {
+                        info!("Connected to NATS");
+                        status.clear_degraded("nats");
+                        let _ = sender.send(Some(client));
+                        break;
+                    }
+                    Err(err) => {
+                        let delay = backoff.next_delay();
+                        warn!("Could not connect to NATS, retrying in {delay:?}: {err}");
+                        status.set_degraded("nats", err);
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+            }
+        });
+
+        Self { client }
+    }
+
+    /// `None` until the initial connection is established
+    pub fn get(&self) -> Option<Client> {
+        self.client.borrow().clone()
+    }
+
+    pub async fn wait(&self) -> Client {
+        let mut receiver = self.client.clone();
+
+        loop {
+            if let Some(client) = receiver.borrow_and_update().clone() {
+                return client;
+            }
+
+            if receiver.changed().await.is_err() {
+                // The connection task only stops after sending a client
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+}
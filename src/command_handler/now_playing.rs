@@ -0,0 +1,89 @@
+use super::{lastfm_api::LastFMApi, spotify_api::SpotifyApi};
+use crate::database::{Database, DatabaseError};
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NowPlayingSource {
+    Spotify,
+    LastFm,
+}
+
+/// The track that a user is currently listening to, as used by the now playing API
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct NowPlaying {
+    pub source: NowPlayingSource,
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    pub url: Option<String>,
+    /// Only available on Spotify
+    pub progress_ms: Option<i64>,
+    pub duration_ms: Option<i64>,
+}
+
+/// Spotify is preferred if the user has connected it, otherwise Last.fm is used
+pub async fn get_now_playing(
+    db: &Database,
+    lastfm_api: Option<&LastFMApi>,
+    user_id: u64,
+) -> anyhow::Result<Option<NowPlaying>> {
+    let (spotify_access_token, lastfm_name) = db
+        .run(move |db| {
+            Ok::<_, DatabaseError>((
+                db.get_spotify_access_token(user_id)?,
+                db.get_lastfm_name(user_id)?,
+            ))
+        })
+        .await?;
+
+    if let Some(access_token) = spotify_access_token {
+        let spotify_api = SpotifyApi::new(&access_token);
+
+        return Ok(spotify_api
+            .get_current_song()
+            .await?
+            .filter(|playback| playback.is_playing)
+            .map(|playback| NowPlaying {
+                source: NowPlayingSource::Spotify,
+                artist: playback
+                    .item
+                    .artists
+                    .iter()
+                    .map(|artist| artist.name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", "),
+                title: playback.item.name,
+                album: Some(playback.item.album.name),
+                url: None,
+                progress_ms: Some(playback.progress_ms),
+                duration_ms: Some(playback.item.duration_ms),
+            }));
+    }
+
+    if let (Some(lastfm_api), Some(username)) = (lastfm_api, lastfm_name) {
+        let response = lastfm_api.get_recent_tracks(&username).await?;
+
+        return Ok(response
+            .recenttracks
+            .track
+            .into_iter()
+            .find(|track| {
+                track
+                    .attr
+                    .as_ref()
+                    .map_or(false, |attr| attr.nowplaying == "true")
+            })
+            .map(|track| NowPlaying {
+                source: NowPlayingSource::LastFm,
+                artist: track.artist.text,
+                title: track.name,
+                album: Some(track.album.text).filter(|album| !album.is_empty()),
+                url: Some(track.url),
+                progress_ms: None,
+                duration_ms: None,
+            }));
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,295 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context};
+use dashmap::DashMap;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::config::OpenAiConfig;
+use crate::database::{
+    cache::TtlCache,
+    models::{ChannelFeature, NewAiUsage},
+    Database, DatabaseError,
+};
+
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+/// Rough average for English text, which is good enough for budgeting the history
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Client for an OpenAI-compatible chat completions API
+#[derive(Clone)]
+pub struct OpenAiApi {
+    client: Client,
+    pub db: Database,
+    base_url: Arc<String>,
+    api_key: Option<Arc<String>>,
+    model: Arc<String>,
+    max_tokens: u32,
+    user_cooldown: Duration,
+    prompt_price: f64,                                // USD per 1000 tokens
+    completion_price: f64,                            // USD per 1000 tokens
+    last_requests: Arc<DashMap<u64, Instant>>,        // User id to the time of their last request
+    memory: Arc<TtlCache<(u64, u64), Vec<Exchange>>>, // Channel and user id
+    memory_exchanges: usize,
+    memory_max_tokens: usize,
+}
+
+/// A prompt and its completion, which are sent again as the history of the conversation
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Exchange {
+    prompt: String,
+    completion: String,
+}
+
+impl Exchange {
+    fn estimate_tokens(&self) -> usize {
+        (self.prompt.chars().count() + self.completion.chars().count()) / CHARS_PER_TOKEN + 1
+    }
+}
+
+impl OpenAiApi {
+    /// Returns `None` if neither an API key nor a custom base URL are configured
+    pub fn from_config(config: &OpenAiConfig, db: Database) -> Option<Self> {
+        if config.api_key.is_none() && config.base_url.is_none() {
+            return None;
+        }
+
+        Some(Self {
+            client: Client::new(),
+            db,
+            base_url: Arc::new(
+                config
+                    .base_url
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+            ),
+            api_key: config.api_key.clone().map(Arc::new),
+            model: Arc::new(config.model.clone()),
+            max_tokens: config.max_tokens,
+            user_cooldown: Duration::from_secs(config.user_cooldown),
+            prompt_price: config.prompt_price,
+            completion_price: config.completion_price,
+            last_requests: Arc::new(DashMap::new()),
+            memory: Arc::new(TtlCache::new(Duration::from_secs(config.memory_ttl))),
+            memory_exchanges: config.memory_exchanges,
+            memory_max_tokens: config.memory_max_tokens as usize,
+        })
+    }
+
+    /// Runs a completion on behalf of the user in the channel, enforcing the channel opt-in and user rate limit.
+    /// With the `ai_memory` setting, the user's recent conversation in the channel is continued
+    pub async fn complete(
+        &self,
+        channel_id: u64,
+        user_id: u64,
+        prompt: &str,
+    ) -> anyhow::Result<String> {
+        let (feature_enabled, ai_enabled, memory_enabled) = self
+            .db
+            .run(move |db| {
+                Ok::<_, DatabaseError>((
+                    db.is_feature_enabled(channel_id, ChannelFeature::Ai)?,
+                    db.is_ai_enabled(channel_id)?,
+                    db.get_channel_settings(channel_id)?.ai_memory,
+                ))
+            })
+            .await?;
+
+        if !feature_enabled {
+            return Err(anyhow!("AI is not available in this channel"));
+        }
+        if !ai_enabled {
+            return Err(anyhow!("AI is not enabled in this channel"));
+        }
+
+        if let Some(last_request) = self.last_requests.get(&user_id) {
+            let elapsed = last_request.elapsed();
+
+            if elapsed < self.user_cooldown {
+                return Err(anyhow!(
+                    "please wait {} seconds",
+                    (self.user_cooldown - elapsed).as_secs() + 1
+                ));
+            }
+        }
+        self.last_requests.insert(user_id, Instant::now());
+
+        let history = match memory_enabled {
+            true => self.memory.get(&(channel_id, user_id)).unwrap_or_default(),
+            false => Vec::new(),
+        };
+
+        let mut messages = Vec::with_capacity(history.len() * 2 + 1);
+        for exchange in &history {
+            messages.push(Message {
+                role: "user".to_owned(),
+                content: exchange.prompt.clone(),
+            });
+            messages.push(Message {
+                role: "assistant".to_owned(),
+                content: exchange.completion.clone(),
+            });
+        }
+        messages.push(Message {
+            role: "user".to_owned(),
+            content: prompt.to_owned(),
+        });
+
+        let request = CompletionRequest {
+            model: &self.model,
+            messages,
+            max_tokens: self.max_tokens,
+        };
+
+        let mut request_builder = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&request);
+
+        if let Some(api_key) = &self.api_key {
+            request_builder = request_builder.bearer_auth(api_key.as_str());
+        }
+
+        let response = request_builder.send().await?;
+
+        tracing::info!("POST {}: {}", response.url(), response.status());
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            tracing::warn!("Completion error: {text}");
+
+            return Err(anyhow!("completion API error: {status}"));
+        }
+
+        let completion = response.json::<CompletionResponse>().await?;
+
+        if let Some(usage) = &completion.usage {
+            let cost = (f64::from(usage.prompt_tokens) * self.prompt_price
+                + f64::from(usage.completion_tokens) * self.completion_price)
+                / 1000.0;
+
+            let model = self.model.clone();
+            let (prompt_tokens, completion_tokens) = (usage.prompt_tokens, usage.completion_tokens);
+            self.db
+                .run(move |db| {
+                    db.add_ai_usage(NewAiUsage {
+                        channel_id,
+                        user_id,
+                        model: &model,
+                        prompt_tokens,
+                        completion_tokens,
+                        cost,
+                    })
+                })
+                .await?;
+        }
+
+        let message = completion
+            .choices
+            .into_iter()
+            .next()
+            .context("empty completion response")?
+            .message;
+
+        let completion = message.content.trim().to_owned();
+
+        if memory_enabled {
+            let mut history = history;
+            history.push(Exchange {
+                prompt: prompt.to_owned(),
+                completion: completion.clone(),
+            });
+            trim_history(&mut history, self.memory_exchanges, self.memory_max_tokens);
+
+            self.memory.remove_expired();
+            self.memory.insert((channel_id, user_id), history);
+        }
+
+        Ok(completion)
+    }
+
+    /// Returns whether the user had a conversation in the channel
+    pub fn forget(&self, channel_id: u64, user_id: u64) -> bool {
+        let key = (channel_id, user_id);
+        let remembered = self.memory.get(&key).is_some();
+        self.memory.remove(&key);
+
+        remembered
+    }
+}
+
+/// Drops the oldest exchanges until the history fits into both limits
+fn trim_history(history: &mut Vec<Exchange>, max_exchanges: usize, max_tokens: usize) {
+    let mut tokens: usize = history.iter().map(Exchange::estimate_tokens).sum();
+    let mut excess = 0;
+
+    for exchange in history.iter() {
+        if history.len() - excess <= max_exchanges && tokens <= max_tokens {
+            break;
+        }
+        tokens -= exchange.estimate_tokens();
+        excess += 1;
+    }
+
+    history.drain(..excess);
+}
+
+#[derive(Serialize)]
+struct CompletionRequest<'a> {
+    model: &'a str,
+    messages: Vec<Message>,
+    max_tokens: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct CompletionResponse {
+    choices: Vec<Choice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{trim_history, Exchange};
+
+    fn exchange(prompt: &str) -> Exchange {
+        Exchange {
+            prompt: prompt.to_owned(),
+            completion: "x".repeat(39),
+        }
+    }
+
+    #[test]
+    fn trims_history() {
+        let mut history = vec![exchange("1"), exchange("2"), exchange("3")];
+        trim_history(&mut history, 2, 1000);
+        assert_eq!(history, [exchange("2"), exchange("3")]);
+
+        // Every exchange is estimated at 11 tokens
+        trim_history(&mut history, 2, 15);
+        assert_eq!(history, [exchange("3")]);
+
+        trim_history(&mut history, 0, 1000);
+        assert!(history.is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+use super::http_fetch::{check_public_url, public_client};
+use crate::{
+    database::{
+        models::{OutgoingWebhook, WebhookEvent},
+        Database,
+    },
+    platform::ChannelIdentifier,
+};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Url};
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const EVENT_STREAM_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum BotEvent {
+    MessageHandled {
+        user: String,
+        message: String,
+        response: String,
+    },
+    CommandExecuted {
+        command: String,
+        user_id: u64,
+        arguments: Vec<String>,
+    },
+    FilterMatched {
+        regex: String,
+        blocked: bool,
+    },
+    UserTimedOut {
+        user: String,
+        duration: i32,
+    },
+    EventsubTriggered {
+        subscription_type: String,
+        user_id: String,
+        arguments: String,
+    },
+}
+
+impl BotEvent {
+    pub fn kind(&self) -> WebhookEvent {
+        match self {
+            BotEvent::MessageHandled { .. } => WebhookEvent::MessageHandled,
+            BotEvent::CommandExecuted { .. } => WebhookEvent::CommandExecuted,
+            BotEvent::FilterMatched { .. } => WebhookEvent::FilterMatched,
+            BotEvent::UserTimedOut { .. } => WebhookEvent::UserTimedOut,
+            BotEvent::EventsubTriggered { .. } => WebhookEvent::EventsubTriggered,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelEvent {
+    pub event: String,
+    pub channel_id: u64,
+    pub timestamp: i64,
+    pub data: BotEvent,
+}
+
+/// Delivers bot events to the webhooks registered by channel owners and to event stream listeners.
+/// Deliveries run in the background and never block the caller
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    db: Database,
+    client: Client,
+    events: broadcast::Sender<ChannelEvent>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(db: Database) -> Self {
+        // Channel owners choose the URLs, so internal services can't be reached
+        let client = public_client(REQUEST_TIMEOUT).expect("Failed to build HTTP client");
+
+        let (events, _) = broadcast::channel(EVENT_STREAM_CAPACITY);
+
+        Self { db, client, events }
+    }
+
+    /// Receives events from all channels, subscribers are expected to filter by channel id
+    pub fn subscribe(&self) -> broadcast::Receiver<ChannelEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn dispatch(&self, channel: &ChannelIdentifier, event: BotEvent) {
+        let dispatcher = self.clone();
+        let channel = channel.clone();
+
+        tokio::spawn(async move {
+            if let Err(err) = dispatcher.dispatch_internal(&channel, event).await {
+                tracing::warn!("Failed to dispatch webhook event in {channel}: {err}");
+            }
+        });
+    }
+
+    async fn dispatch_internal(
+        &self,
+        channel: &ChannelIdentifier,
+        event: BotEvent,
+    ) -> anyhow::Result<()> {
+        let channel = channel.clone();
+        let channel = match self.db.run(move |db| db.get_channel(&channel)).await? {
+            Some(channel) => channel,
+            None => return Ok(()),
+        };
+
+        let kind = event.kind();
+        let payload = ChannelEvent {
+            event: kind.to_string(),
+            channel_id: channel.id,
+            timestamp: Utc::now().timestamp(),
+            data: event,
+        };
+
+        // Sending only fails when there are no subscribers
+        let _ = self.events.send(payload.clone());
+
+        let channel_id = channel.id;
+        let webhooks: Vec<OutgoingWebhook> = self
+            .db
+            .run(move |db| db.get_outgoing_webhooks(channel_id))
+            .await?
+            .into_iter()
+            .filter(|webhook| webhook.is_subscribed(kind))
+            .collect();
+
+        if webhooks.is_empty() {
+            return Ok(());
+        }
+
+        let body = serde_json::to_vec(&payload)?;
+
+        for webhook in webhooks {
+            let client = self.client.clone();
+            let body = body.clone();
+            let event = kind;
+
+            tokio::spawn(async move {
+                deliver(&client, &webhook, event, body).await;
+            });
+        }
+
+        Ok(())
+    }
+}
+
+async fn deliver(client: &Client, webhook: &OutgoingWebhook, event: WebhookEvent, body: Vec<u8>) {
+    let url = match Url::parse(&webhook.url)
+        .map_err(|err| err.to_string())
+        .and_then(|url| check_public_url(&url).map(|_| url))
+    {
+        Ok(url) => url,
+        Err(err) => {
+            tracing::warn!(
+                "Not delivering event {event} to webhook {}: {err}",
+                webhook.id
+            );
+            return;
+        }
+    };
+
+    let signature = sign_payload(&body, webhook.secret.as_bytes());
+    let mut backoff = INITIAL_BACKOFF;
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let response = client
+            .post(url.clone())
+            .header("Content-Type", "application/json")
+            .header("X-Foobot-Event", event.to_string())
+            .header("X-Foobot-Signature-256", &signature)
+            .header("X-Foobot-Delivery-Attempt", attempt)
+            .body(body.clone())
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => return,
+            // Client errors will not be fixed by retrying, except for rate limiting
+            Ok(response)
+                if response.status().is_client_error()
+                    && response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS =>
+            {
+                tracing::warn!(
+                    "Webhook {} rejected event {event}: {}",
+                    webhook.id,
+                    response.status()
+                );
+                return;
+            }
+            Ok(response) => tracing::debug!(
+                "Webhook {} responded with {} on attempt {attempt}",
+                webhook.id,
+                response.status()
+            ),
+            Err(err) => {
+                tracing::debug!(
+                    "Webhook {} delivery attempt {attempt} failed: {err}",
+                    webhook.id
+                )
+            }
+        }
+
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    tracing::warn!(
+        "Giving up on delivering event {event} to webhook {} after {MAX_ATTEMPTS} attempts",
+        webhook.id
+    );
+}
+
+/// Signs the body the same way as GitHub does, so existing verification code can be reused
+pub fn sign_payload(body: &[u8], secret: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC can take key of any size");
+    mac.update(body);
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sign_payload;
+
+    #[test]
+    fn sign_payload_matches_known_digest() {
+        // Reference value from GitHub's webhook validation docs
+        assert_eq!(
+            sign_payload(b"Hello, World!", b"It's a Secret to Everybody"),
+            "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Responses of commands, which platforms send in the richest form they support.
+//! Platforms that only have plain text messages use [`CommandOutput::into_messages`].
+
+use crate::platform::LengthUnit;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum CommandOutput {
+    /// Nothing is sent
+    #[default]
+    Nothing,
+    Text(String),
+    /// Sent as separate messages
+    Messages(Vec<String>),
+    Embed(Embed),
+    /// An emoji added to the triggering message
+    Reaction(String),
+    Attachment(Attachment),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Embed {
+    pub title: Option<String>,
+    pub description: String,
+    pub url: Option<String>,
+    pub fields: Vec<EmbedField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    pub filename: String,
+    pub content: Vec<u8>,
+}
+
+impl CommandOutput {
+    pub fn is_nothing(&self) -> bool {
+        matches!(self, Self::Nothing)
+    }
+
+    /// The text parts of the output, for filters and banphrase checks.
+    /// The content of attachments is not included.
+    pub fn texts_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Self::Nothing => vec![],
+            Self::Text(text) | Self::Reaction(text) => vec![text],
+            Self::Messages(messages) => messages.iter_mut().collect(),
+            Self::Embed(embed) => {
+                let mut texts: Vec<&mut String> = vec![&mut embed.description];
+                texts.extend(embed.title.as_mut());
+                for field in &mut embed.fields {
+                    texts.push(&mut field.name);
+                    texts.push(&mut field.value);
+                }
+                texts
+            }
+            Self::Attachment(attachment) => vec![&mut attachment.filename],
+        }
+    }
+
+    /// Turns outputs without any content into [`CommandOutput::Nothing`],
+    /// which includes outputs that were blocked by filters
+    pub fn remove_empty(self) -> Self {
+        match self {
+            Self::Text(text) | Self::Reaction(text) if text.trim().is_empty() => Self::Nothing,
+            Self::Messages(mut messages) => {
+                messages.retain(|message| !message.trim().is_empty());
+                if messages.is_empty() {
+                    Self::Nothing
+                } else {
+                    Self::Messages(messages)
+                }
+            }
+            Self::Embed(embed) if embed.description.trim().is_empty() => Self::Nothing,
+            Self::Attachment(attachment) if attachment.filename.is_empty() => Self::Nothing,
+            output => output,
+        }
+    }
+
+    /// Whether a text message of the output is longer than `limit`.
+    /// Other kinds of outputs are not sent as plain messages where it matters.
+    pub fn exceeds_limit(&self, limit: usize, unit: LengthUnit) -> bool {
+        match self {
+            Self::Text(text) => unit.measure(text) > limit,
+            Self::Messages(messages) => {
+                messages.iter().any(|message| unit.measure(message) > limit)
+            }
+            _ => false,
+        }
+    }
+
+    /// The output as plain text messages
+    pub fn into_messages(self) -> Vec<String> {
+        match self {
+            Self::Nothing => vec![],
+            Self::Text(text) | Self::Reaction(text) => vec![text],
+            Self::Messages(messages) => messages,
+            Self::Embed(embed) => vec![embed.to_text()],
+            // Text files are still useful when sent inline
+            Self::Attachment(attachment) => match String::from_utf8(attachment.content) {
+                Ok(content) => vec![content],
+                Err(_) => vec![attachment.filename],
+            },
+        }
+    }
+
+    /// The output as a single message, `None` if there is nothing to send
+    pub fn into_text(self) -> Option<String> {
+        let messages = self.into_messages();
+
+        if messages.is_empty() {
+            None
+        } else {
+            Some(messages.join("\n"))
+        }
+    }
+}
+
+impl Embed {
+    fn to_text(&self) -> String {
+        let mut lines = Vec::new();
+
+        match &self.title {
+            Some(title) => lines.push(format!("{title}: {}", self.description)),
+            None => lines.push(self.description.clone()),
+        }
+        for field in &self.fields {
+            lines.push(format!("{}: {}", field.name, field.value));
+        }
+        lines.extend(self.url.clone());
+
+        lines.join("\n")
+    }
+}
+
+impl From<String> for CommandOutput {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<Option<String>> for CommandOutput {
+    fn from(text: Option<String>) -> Self {
+        text.map(Self::Text).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Attachment, CommandOutput, Embed, EmbedField};
+    use crate::platform::LengthUnit;
+
+    #[test]
+    fn converts_to_messages() {
+        let embed = CommandOutput::Embed(Embed {
+            title: Some("Kyiv".to_owned()),
+            description: "20°C, clear".to_owned(),
+            url: Some("https://example.com".to_owned()),
+            fields: vec![EmbedField {
+                name: "Wind".to_owned(),
+                value: "5m/s".to_owned(),
+            }],
+        });
+        assert_eq!(
+            embed.into_text().unwrap(),
+            "Kyiv: 20°C, clear\nWind: 5m/s\nhttps://example.com"
+        );
+
+        let attachment = CommandOutput::Attachment(Attachment {
+            filename: "output.txt".to_owned(),
+            content: b"hello".to_vec(),
+        });
+        assert_eq!(attachment.into_messages(), ["hello"]);
+
+        assert_eq!(CommandOutput::Nothing.into_text(), None);
+    }
+
+    #[test]
+    fn removes_blocked_messages() {
+        let output = CommandOutput::Messages(vec!["first".to_owned(), String::new()]);
+        assert_eq!(
+            output.remove_empty(),
+            CommandOutput::Messages(vec!["first".to_owned()])
+        );
+        assert!(CommandOutput::Text(" ".to_owned())
+            .remove_empty()
+            .is_nothing());
+    }
+
+    #[test]
+    fn checks_message_limit() {
+        let output = CommandOutput::Messages(vec!["short".to_owned(), "ї".repeat(6)]);
+        assert!(output.exceeds_limit(5, LengthUnit::Chars));
+        assert!(!output.exceeds_limit(6, LengthUnit::Chars));
+        assert!(output.exceeds_limit(6, LengthUnit::Bytes));
+        assert!(!CommandOutput::Reaction("👍".to_owned()).exceeds_limit(0, LengthUnit::Chars));
+    }
+}
@@ -1,13 +1,27 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
+use chrono::{NaiveDate, TimeZone, Utc};
 use http::status::StatusCode;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use reqwest::{Client, Response};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use strum::EnumString;
+
+use crate::database::cache::TtlCache;
+
+/// Popular locations are requested often, while the weather doesn't change that fast
+const CACHE_TTL: Duration = Duration::from_secs(600);
+/// The free forecast API covers 5 days
+pub const MAX_FORECAST_DAYS: usize = 5;
 
 #[derive(Clone)]
 pub struct OwmApi {
     client: Client,
     api_key: Arc<String>,
+    current_cache: Arc<TtlCache<String, WeatherResponse>>,
+    forecast_cache: Arc<TtlCache<String, ForecastResponse>>,
+    air_quality_cache: Arc<TtlCache<String, AirPollutionResponse>>,
 }
 
 impl OwmApi {
@@ -15,23 +29,127 @@ impl OwmApi {
         Self {
             api_key: Arc::new(api_key),
             client: Client::new(),
+            current_cache: Arc::new(TtlCache::new(CACHE_TTL)),
+            forecast_cache: Arc::new(TtlCache::new(CACHE_TTL)),
+            air_quality_cache: Arc::new(TtlCache::new(CACHE_TTL)),
+        }
+    }
+
+    pub async fn get_current(
+        &self,
+        place: &str,
+        units: Units,
+    ) -> Result<WeatherResponse, OwmError> {
+        let key = cache_key(place, units);
+        if let Some(weather) = self.current_cache.get(&key) {
+            return Ok(weather);
+        }
+
+        let response = self
+            .client
+            .get("https://api.openweathermap.org/data/2.5/weather")
+            .query(&[
+                ("q", place),
+                ("appid", self.api_key.as_str()),
+                ("units", units.as_ref()),
+            ])
+            .send()
+            .await?;
+
+        let weather: WeatherResponse = parse_response(response).await?;
+        self.current_cache.insert(key, weather.clone());
+
+        Ok(weather)
+    }
+
+    /// Forecast in 3 hour steps
+    pub async fn get_forecast(
+        &self,
+        place: &str,
+        units: Units,
+    ) -> Result<ForecastResponse, OwmError> {
+        let key = cache_key(place, units);
+        if let Some(forecast) = self.forecast_cache.get(&key) {
+            return Ok(forecast);
         }
+
+        let response = self
+            .client
+            .get("https://api.openweathermap.org/data/2.5/forecast")
+            .query(&[
+                ("q", place),
+                ("appid", self.api_key.as_str()),
+                ("units", units.as_ref()),
+            ])
+            .send()
+            .await?;
+
+        let forecast: ForecastResponse = parse_response(response).await?;
+        self.forecast_cache.insert(key, forecast.clone());
+
+        Ok(forecast)
     }
 
-    pub async fn get_current(&self, place: &str) -> Result<WeatherResponse, OwmError> {
+    pub async fn get_air_quality(&self, coord: &Coord) -> Result<AirPollutionResponse, OwmError> {
+        // Roughly a kilometer, so that nearby requests share the result
+        let key = format!("{:.2},{:.2}", coord.lat, coord.lon);
+        if let Some(air_quality) = self.air_quality_cache.get(&key) {
+            return Ok(air_quality);
+        }
+
         let response = self
             .client
-            .get("https://api.openweathermap.org/data/2.5/weather?")
-            .query(&[("q", place), ("appid", &self.api_key), ("units", "metric")])
+            .get("https://api.openweathermap.org/data/2.5/air_pollution")
+            .query(&[
+                ("lat", coord.lat.to_string().as_str()),
+                ("lon", coord.lon.to_string().as_str()),
+                ("appid", self.api_key.as_str()),
+            ])
             .send()
             .await?;
 
-        tracing::info!("GET {}: {}", response.url(), response.status());
+        let air_quality: AirPollutionResponse = parse_response(response).await?;
+        self.air_quality_cache.insert(key, air_quality.clone());
+
+        Ok(air_quality)
+    }
+}
+
+fn cache_key(place: &str, units: Units) -> String {
+    format!("{}:{}", units.as_ref(), place.trim().to_lowercase())
+}
+
+async fn parse_response<T: DeserializeOwned>(response: Response) -> Result<T, OwmError> {
+    tracing::info!("GET {}: {}", response.url().path(), response.status());
+
+    match response.status() {
+        StatusCode::OK => Ok(response.json().await?),
+        StatusCode::NOT_FOUND => Err(OwmError::LocationNotFound),
+        _ => Err(OwmError::UnexpectedCode(response.status().to_string())),
+    }
+}
+
+/// Set by users with `set units`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumString, strum::AsRefStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum Units {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl Units {
+    pub fn temperature(&self) -> &'static str {
+        match self {
+            Units::Metric => "°C",
+            Units::Imperial => "°F",
+        }
+    }
 
-        match response.status() {
-            StatusCode::OK => Ok(response.json().await?),
-            StatusCode::NOT_FOUND => Err(OwmError::LocationNotFound),
-            _ => Err(OwmError::UnexpectedCode(response.status().to_string())),
+    pub fn speed(&self) -> &'static str {
+        match self {
+            Units::Metric => "m/s",
+            Units::Imperial => "mph",
         }
     }
 }
@@ -136,3 +254,177 @@ pub struct Wind {
     // pub gust: f64,
     pub speed: f64,
 }
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ForecastResponse {
+    pub list: Vec<ForecastEntry>,
+    pub city: City,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ForecastEntry {
+    pub dt: i64,
+    pub main: Main,
+    pub weather: Vec<Weather>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct City {
+    pub name: String,
+    pub country: Option<String>,
+    pub coord: Coord,
+    /// Offset from UTC in seconds
+    pub timezone: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyForecast {
+    pub date: NaiveDate,
+    pub temp_min: f64,
+    pub temp_max: f64,
+    /// The most common description of the day
+    pub description: String,
+}
+
+impl ForecastResponse {
+    /// Groups the forecast by the local date of the location, starting with today
+    pub fn daily(&self, days: usize) -> Vec<DailyForecast> {
+        let mut dates: Vec<NaiveDate> = Vec::new();
+        let mut daily: HashMap<NaiveDate, (f64, f64, HashMap<&str, usize>)> = HashMap::new();
+
+        for entry in &self.list {
+            let date = match Utc.timestamp_opt(entry.dt + self.city.timezone, 0).single() {
+                Some(time) => time.date_naive(),
+                None => continue,
+            };
+
+            if !dates.contains(&date) {
+                dates.push(date);
+            }
+
+            let (temp_min, temp_max, descriptions) =
+                daily
+                    .entry(date)
+                    .or_insert((f64::MAX, f64::MIN, HashMap::new()));
+            *temp_min = temp_min.min(entry.main.temp_min);
+            *temp_max = temp_max.max(entry.main.temp_max);
+
+            if let Some(weather) = entry.weather.first() {
+                *descriptions.entry(&weather.description).or_default() += 1;
+            }
+        }
+
+        dates
+            .into_iter()
+            .take(days)
+            .map(|date| {
+                let (temp_min, temp_max, descriptions) = &daily[&date];
+                let description = descriptions
+                    .iter()
+                    .max_by_key(|(description, count)| (**count, std::cmp::Reverse(**description)))
+                    .map(|(description, _)| description.to_string())
+                    .unwrap_or_default();
+
+                DailyForecast {
+                    date,
+                    temp_min: *temp_min,
+                    temp_max: *temp_max,
+                    description,
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AirPollutionResponse {
+    pub list: Vec<AirPollution>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AirPollution {
+    pub main: AirQualityIndex,
+    pub components: AirComponents,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AirQualityIndex {
+    /// From 1 (good) to 5 (very poor)
+    pub aqi: u8,
+}
+
+/// Concentrations in μg/m³
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AirComponents {
+    pub pm2_5: f64,
+    pub pm10: f64,
+}
+
+impl AirPollution {
+    pub fn describe(&self) -> String {
+        let quality = match self.main.aqi {
+            1 => "good",
+            2 => "fair",
+            3 => "moderate",
+            4 => "poor",
+            _ => "very poor",
+        };
+
+        format!(
+            "air quality {quality} (PM2.5 {:.1}, PM10 {:.1} μg/m³)",
+            self.components.pm2_5, self.components.pm10
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{City, Coord, ForecastEntry, ForecastResponse, Main, Weather};
+    use chrono::NaiveDate;
+
+    fn entry(dt: i64, temp: f64, description: &str) -> ForecastEntry {
+        ForecastEntry {
+            dt,
+            main: Main {
+                temp_min: temp,
+                temp_max: temp,
+                ..Default::default()
+            },
+            weather: vec![Weather {
+                description: description.to_owned(),
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn groups_forecast_by_local_date() {
+        // 2023-10-25 22:00 UTC is already the next day in Kyiv
+        let forecast = ForecastResponse {
+            list: vec![
+                entry(1698220800, 10.0, "rain"),
+                entry(1698231600, 14.0, "clear sky"),
+                entry(1698242400, 12.0, "rain"),
+                entry(1698271200, 5.0, "snow"),
+            ],
+            city: City {
+                name: "Kyiv".to_owned(),
+                country: Some("UA".to_owned()),
+                coord: Coord::default(),
+                timezone: 3 * 3600,
+            },
+        };
+
+        let daily = forecast.daily(5);
+        assert_eq!(daily.len(), 2);
+        assert_eq!(
+            daily[0].date,
+            NaiveDate::from_ymd_opt(2023, 10, 25).unwrap()
+        );
+        assert_eq!((daily[0].temp_min, daily[0].temp_max), (10.0, 14.0));
+        assert_eq!(daily[0].description, "rain");
+        assert_eq!(daily[1].description, "snow");
+
+        assert_eq!(forecast.daily(1).len(), 1);
+    }
+}
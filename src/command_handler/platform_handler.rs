@@ -1,25 +1,40 @@
-use super::discord_api::DiscordApi;
+use super::{
+    debug_report::skip_side_effect,
+    discord_api::DiscordApi,
+    sharding::{ShardMessage, Sharding},
+    twitch_api::model::AnnouncementColor,
+};
 use crate::{
-    database::{models::Filter, Database},
-    platform::{twitch, ChannelIdentifier},
+    database::{
+        models::{Channel, ChannelSettings, Filter, LongMessageMode},
+        Database, DatabaseError,
+    },
+    platform::{
+        irc::{self, IrcNetwork},
+        twitch, ChannelIdentifier, LengthUnit, MessageIdentifier, UserIdentifier,
+    },
 };
-use anyhow::anyhow;
 use anyhow::Error;
-use irc::client::Sender as IrcSender;
+use anyhow::{anyhow, Context};
 use regex::Regex;
+use serde_json::json;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use std::{collections::HashMap, fmt::Display};
 use tokio::sync::Mutex;
 use twitch_irc::login::RefreshingLoginCredentials;
 
 pub type TwitchApi = super::twitch_api::TwitchApi<RefreshingLoginCredentials<Database>>;
 
+#[derive(Clone)]
 pub struct PlatformHandler {
     pub twitch_api: Option<TwitchApi>,
     pub discord_api: Option<DiscordApi>,
-    pub irc_sender: Option<IrcSender>,
+    pub irc_networks: HashMap<String, IrcNetwork>,
     pub minecraft_client: Option<Arc<Mutex<minecraft_client_rs::Client>>>,
     pub filters: Arc<RwLock<HashMap<ChannelIdentifier, Vec<Filter>>>>,
+    pub channel_settings: Arc<RwLock<HashMap<ChannelIdentifier, ChannelSettings>>>,
+    pub sharding: Sharding,
 }
 
 impl PlatformHandler {
@@ -28,7 +43,97 @@ impl PlatformHandler {
         channel: ChannelIdentifier,
         mut msg: String,
     ) -> Result<(), PlatformHandlerError> {
-        self.filter_message(&mut msg, &channel);
+        self.filter_message(&mut msg, &channel, None);
+
+        if skip_side_effect("send_message", msg.clone()) {
+            return Ok(());
+        }
+
+        match channel {
+            ChannelIdentifier::TwitchChannel((channel_id, _)) => {
+                self.send_twitch_message(&channel_id, msg, false).await
+            }
+            ChannelIdentifier::IrcChannel(ref target) => {
+                let (network, target) = irc::parse_target(target);
+
+                let network = self
+                    .irc_networks
+                    .get(network)
+                    .ok_or(PlatformHandlerError::Unconfigured)?;
+
+                for message in self.split_message(&channel, &msg) {
+                    network.send_privmsg(target, &message).map_err(Error::new)?;
+                }
+
+                Ok(())
+            }
+            ChannelIdentifier::Minecraft => {
+                let mut minecraft = self
+                    .minecraft_client
+                    .as_ref()
+                    .ok_or(PlatformHandlerError::Unconfigured)?
+                    .lock()
+                    .await;
+
+                for message in self.split_message(&channel, &msg) {
+                    minecraft
+                        .send_command(format!("say {}", message))
+                        .map_err(|e| anyhow!("Failed to send Minecraft message: {}", e))?;
+                }
+
+                Ok(())
+            }
+            _ => Err(PlatformHandlerError::Unsupported),
+        }
+    }
+
+    /// Sends a `/me` message, only supported on Twitch and IRC
+    pub async fn send_action_to_channel(
+        &self,
+        channel: ChannelIdentifier,
+        mut msg: String,
+    ) -> Result<(), PlatformHandlerError> {
+        self.filter_message(&mut msg, &channel, None);
+
+        if skip_side_effect("send_action", msg.clone()) {
+            return Ok(());
+        }
+
+        match channel {
+            ChannelIdentifier::TwitchChannel((channel_id, _)) => {
+                self.send_twitch_message(&channel_id, msg, true).await
+            }
+            ChannelIdentifier::IrcChannel(ref target) => {
+                let (network, target) = irc::parse_target(target);
+
+                let network = self
+                    .irc_networks
+                    .get(network)
+                    .ok_or(PlatformHandlerError::Unconfigured)?;
+
+                for message in self.split_message(&channel, &msg) {
+                    network.send_action(target, &message).map_err(Error::new)?;
+                }
+
+                Ok(())
+            }
+            _ => Err(PlatformHandlerError::Unsupported),
+        }
+    }
+
+    /// Sends a highlighted Helix announcement, the bot has to be a moderator in the channel
+    pub async fn send_announcement(
+        &self,
+        channel: &ChannelIdentifier,
+        mut msg: String,
+        color: Option<AnnouncementColor>,
+    ) -> Result<(), PlatformHandlerError> {
+        self.filter_message(&mut msg, channel, None);
+
+        let input = json!({ "message": msg, "color": color }).to_string();
+        if skip_side_effect("send_announcement", input) {
+            return Ok(());
+        }
 
         match channel {
             ChannelIdentifier::TwitchChannel((channel_id, _)) => {
@@ -37,67 +142,370 @@ impl PlatformHandler {
                     .as_ref()
                     .ok_or(PlatformHandlerError::Unconfigured)?;
 
-                let broadcaster = twitch_api.helix_api.get_user_by_id(&channel_id).await?;
+                Ok(twitch_api
+                    .helix_api
+                    .send_chat_announcement(channel_id, &msg, color)
+                    .await?)
+            }
+            _ => Err(PlatformHandlerError::Unsupported),
+        }
+    }
 
-                let chat_sender_guard = twitch_api.chat_sender.lock().await;
-                let chat_sender = chat_sender_guard.as_ref().expect("Chat client missing");
+    /// Sends an official Twitch shoutout, the bot has to be a moderator in the channel
+    pub async fn shoutout(
+        &self,
+        channel: &ChannelIdentifier,
+        target_login: &str,
+    ) -> Result<(), PlatformHandlerError> {
+        if skip_side_effect("shoutout", target_login) {
+            return Ok(());
+        }
 
-                tracing::info!("Sending {} to {}", msg, broadcaster.login);
+        match channel {
+            ChannelIdentifier::TwitchChannel((channel_id, _)) => {
+                let twitch_api = self
+                    .twitch_api
+                    .as_ref()
+                    .ok_or(PlatformHandlerError::Unconfigured)?;
 
-                let message = msg.split_whitespace().collect::<Vec<&str>>().join(" ");
+                let target = twitch_api.helix_api.get_user_by_login(target_login).await?;
 
-                chat_sender
-                    .send(twitch::SenderMessage::Privmsg(twitch::Privmsg {
-                        channel_login: broadcaster.login,
-                        message,
-                        reply_to_id: None,
-                    }))
-                    .unwrap();
+                Ok(twitch_api
+                    .helix_api
+                    .send_shoutout(channel_id, &target.id)
+                    .await?)
+            }
+            _ => Err(PlatformHandlerError::Unsupported),
+        }
+    }
 
-                Ok(())
+    /// Times the user out for the given duration, or bans them permanently without one
+    pub async fn ban_user(
+        &self,
+        channel: &ChannelIdentifier,
+        user: &UserIdentifier,
+        duration: Option<Duration>,
+        reason: Option<&str>,
+    ) -> Result<(), PlatformHandlerError> {
+        let input = json!({
+            "user": user.to_string(),
+            "duration": duration.map(|duration| duration.as_secs()),
+            "reason": reason,
+        })
+        .to_string();
+        if skip_side_effect("ban_user", input) {
+            return Ok(());
+        }
+
+        match (channel, user) {
+            (
+                ChannelIdentifier::TwitchChannel((channel_id, _)),
+                UserIdentifier::TwitchID(user_id),
+            ) => {
+                let twitch_api = self
+                    .twitch_api
+                    .as_ref()
+                    .ok_or(PlatformHandlerError::Unconfigured)?;
+
+                let duration = duration.map(|duration| duration.as_secs() as i32);
+
+                Ok(twitch_api
+                    .helix_api
+                    .ban_user(channel_id, user_id, duration, reason)
+                    .await?)
             }
-            ChannelIdentifier::IrcChannel(channel) => {
-                let sender = self
-                    .irc_sender
+            (ChannelIdentifier::DiscordChannel(guild_id), UserIdentifier::DiscordID(user_id)) => {
+                let discord_api = self
+                    .discord_api
                     .as_ref()
                     .ok_or(PlatformHandlerError::Unconfigured)?;
 
-                sender.send_privmsg(channel, &msg).map_err(Error::new)?;
+                let guild_id = guild_id.parse().context("Invalid guild id")?;
+                let user_id = user_id.parse().context("Invalid user id")?;
+
+                match duration {
+                    Some(duration) => {
+                        discord_api
+                            .timeout_member(guild_id, user_id, duration, reason)
+                            .await?
+                    }
+                    None => discord_api.ban_member(guild_id, user_id, reason).await?,
+                }
 
                 Ok(())
             }
-            ChannelIdentifier::Minecraft => {
-                let mut minecraft = self
-                    .minecraft_client
+            _ => Err(PlatformHandlerError::Unsupported),
+        }
+    }
+
+    pub async fn delete_message(
+        &self,
+        message: &MessageIdentifier,
+    ) -> Result<(), PlatformHandlerError> {
+        if skip_side_effect("delete_message", format!("{message:?}")) {
+            return Ok(());
+        }
+
+        match message {
+            MessageIdentifier::Twitch {
+                channel_id,
+                message_id,
+            } => {
+                let twitch_api = self
+                    .twitch_api
                     .as_ref()
-                    .ok_or(PlatformHandlerError::Unconfigured)?
-                    .lock()
-                    .await;
+                    .ok_or(PlatformHandlerError::Unconfigured)?;
+
+                Ok(twitch_api
+                    .helix_api
+                    .delete_chat_message(channel_id, message_id)
+                    .await?)
+            }
+            MessageIdentifier::Discord {
+                channel_id,
+                message_id,
+            } => {
+                let discord_api = self
+                    .discord_api
+                    .as_ref()
+                    .ok_or(PlatformHandlerError::Unconfigured)?;
+
+                Ok(discord_api.delete_message(*channel_id, *message_id).await?)
+            }
+        }
+    }
+
+    async fn send_twitch_message(
+        &self,
+        channel_id: &str,
+        msg: String,
+        action: bool,
+    ) -> Result<(), PlatformHandlerError> {
+        let twitch_api = self
+            .twitch_api
+            .as_ref()
+            .ok_or(PlatformHandlerError::Unconfigured)?;
+
+        let broadcaster = twitch_api.helix_api.get_user_by_id(channel_id).await?;
+
+        let chat_sender_guard = twitch_api.chat_sender.lock().await;
+        let chat_sender = chat_sender_guard
+            .as_ref()
+            .context("Twitch chat not initialized")?;
+
+        tracing::info!("Sending {} to {}", msg, broadcaster.login);
+
+        let msg = msg.split_whitespace().collect::<Vec<&str>>().join(" ");
+        let channel = ChannelIdentifier::TwitchChannel((channel_id.to_owned(), None));
+
+        for message in self.split_message(&channel, &msg) {
+            chat_sender
+                .send(twitch::SenderMessage::Privmsg(twitch::Privmsg {
+                    channel_login: broadcaster.login.clone(),
+                    message,
+                    reply_to_id: None,
+                    action,
+                }))
+                .context("Twitch chat is not running")?;
+        }
+
+        Ok(())
+    }
+
+    /// Fits the message into the platform's length limit. Depending on the channel's settings,
+    /// it is either truncated or split into at most `max_lines` messages
+    pub fn split_message(&self, channel: &ChannelIdentifier, message: &str) -> Vec<String> {
+        let limit = match channel.get_message_limit() {
+            Some(limit) => limit,
+            None => return vec![message.to_owned()],
+        };
+
+        let settings = self.get_channel_settings(channel);
+
+        match settings.long_messages {
+            LongMessageMode::Truncate => vec![channel.truncate_message(message)],
+            // Only command responses are pasted, anything else that is too long is split
+            LongMessageMode::Split | LongMessageMode::Paste => {
+                let max_chunks = settings.max_lines.max(1) as usize;
+                split_text(message, limit, max_chunks, channel.get_length_unit())
+            }
+        }
+    }
+
+    /// Returns the default settings if the channel has none
+    pub fn get_channel_settings(&self, channel: &ChannelIdentifier) -> ChannelSettings {
+        self.channel_settings
+            .read()
+            .expect("Failed to lock")
+            .get(channel)
+            .cloned()
+            .unwrap_or_else(|| ChannelSettings::new(0))
+    }
+
+    /// Adds the channel's response prefix and the mention of the user if it's enabled
+    pub fn format_response(
+        &self,
+        channel: &ChannelIdentifier,
+        response: &str,
+        mention: &str,
+    ) -> String {
+        format_response(&self.get_channel_settings(channel), response, mention)
+    }
+
+    /// Has to be called after the channel's settings are changed in the DB
+    pub async fn reload_channel_settings(
+        &self,
+        db: &Database,
+        channel: ChannelIdentifier,
+        channel_id: u64,
+    ) -> Result<(), DatabaseError> {
+        let settings = db
+            .run(move |db| db.get_channel_settings(channel_id))
+            .await?;
+
+        self.channel_settings
+            .write()
+            .expect("Failed to lock")
+            .insert(channel, settings);
+
+        Ok(())
+    }
+
+    /// Adds the channel to the DB if needed and marks it as joined, so it is also joined on startup
+    /// by the shard that owns it
+    pub async fn join_twitch_channel(&self, db: &Database, login: &str) -> anyhow::Result<Channel> {
+        let twitch_api = self.twitch_api.as_ref().context("Twitch not initialized")?;
+
+        let user = twitch_api.helix_api.get_user_by_login(login).await?;
+
+        let identifier = ChannelIdentifier::TwitchChannel((user.id.clone(), None));
+        let channel = db
+            .run(move |db| {
+                let channel = db.get_or_create_channel(&identifier)?;
+                if let Some(channel) = &channel {
+                    db.set_channel_joined(channel.id, true)?;
+                }
+                Ok::<_, DatabaseError>(channel)
+            })
+            .await?
+            .context("Failed to add channel")?;
+
+        let message = ShardMessage::JoinTwitchChannel {
+            login: user.login.clone(),
+        };
+        match self.sharding.forward(Some(channel.id), &message).await {
+            Ok(true) => (),
+            Ok(false) => self.join_twitch_chat(user.login).await?,
+            // The owner joins the channel on its next reconciliation
+            Err(e) => tracing::warn!(
+                "Could not forward joining {} to its shard: {e:#}",
+                user.login
+            ),
+        }
+
+        Ok(Channel {
+            joined: true,
+            ..channel
+        })
+    }
+
+    /// Joins the channel on this shard and announces it in the chat
+    pub async fn join_twitch_chat(&self, login: String) -> anyhow::Result<()> {
+        let twitch_api = self.twitch_api.as_ref().context("Twitch not initialized")?;
+
+        let chat_sender_guard = twitch_api.chat_sender.lock().await;
+        let chat_sender = chat_sender_guard
+            .as_ref()
+            .context("Twitch chat not initialized")?;
 
-                minecraft
-                    .send_command(format!("say {}", msg))
-                    .map_err(|e| anyhow!("Failed to send Minecraft message: {}", e))?;
+        chat_sender
+            .send(twitch::SenderMessage::JoinChannel(login.clone()))
+            .context("Twitch chat is not running")?;
+        chat_sender
+            .send(twitch::SenderMessage::Privmsg(twitch::Privmsg {
+                channel_login: login,
+                message: String::from("MrDestructoid 👍 Foobot2 joined"),
+                reply_to_id: None,
+                action: false,
+            }))
+            .context("Twitch chat is not running")?;
+
+        Ok(())
+    }
+
+    /// Parts the channel on this shard
+    pub async fn part_twitch_chat(&self, login: String) -> anyhow::Result<()> {
+        let twitch_api = self.twitch_api.as_ref().context("Twitch not initialized")?;
+
+        if let Some(chat_sender) = twitch_api.chat_sender.lock().await.as_ref() {
+            chat_sender
+                .send(twitch::SenderMessage::PartChannel(login))
+                .context("Twitch chat is not running")?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn part_channel(&self, db: &Database, channel: &Channel) -> anyhow::Result<()> {
+        match channel.get_identifier() {
+            ChannelIdentifier::TwitchChannel((id, _)) => {
+                let twitch_api = self.twitch_api.as_ref().context("Twitch not initialized")?;
+
+                let user = twitch_api.helix_api.get_user_by_id(&id).await?;
+
+                let channel_id = channel.id;
+                db.run(move |db| db.set_channel_joined(channel_id, false))
+                    .await?;
+
+                let message = ShardMessage::PartTwitchChannel {
+                    login: user.login.clone(),
+                };
+                match self.sharding.forward(Some(channel.id), &message).await {
+                    Ok(true) => (),
+                    Ok(false) => self.part_twitch_chat(user.login).await?,
+                    // The owner parts the channel on its next reconciliation
+                    Err(e) => {
+                        tracing::warn!(
+                            "Could not forward parting {} to its shard: {e:#}",
+                            user.login
+                        )
+                    }
+                }
 
                 Ok(())
             }
-            _ => Err(PlatformHandlerError::Unsupported),
+            _ => Err(anyhow!("Leaving channels is only supported on Twitch")),
         }
     }
 
-    pub fn filter_message(&self, message: &mut String, channel: &ChannelIdentifier) {
+    /// Applies the channel's filters to the message and returns the ones that matched
+    /// Filters that are limited to other Discord channels than `subchannel` are skipped
+    pub fn filter_message(
+        &self,
+        message: &mut String,
+        channel: &ChannelIdentifier,
+        subchannel: Option<u64>,
+    ) -> Vec<Filter> {
         let filters = self.filters.read().expect("Failed to lock");
+        let mut matched = Vec::new();
 
         tracing::trace!("Checking filters for {}", message);
         if let Some(filters) = filters.get(channel) {
-            for filter in filters {
+            for filter in filters
+                .iter()
+                .filter(|filter| filter.applies_to(subchannel))
+            {
                 tracing::trace!("Matching {}", filter.regex);
                 match Regex::new(&filter.regex) {
                     Ok(re) => {
+                        if !re.is_match(message) {
+                            continue;
+                        }
+                        matched.push(filter.clone());
+
                         if filter.block_message {
-                            if re.is_match(message) {
-                                message.clear();
-                                break;
-                            }
+                            message.clear();
+                            break;
                         } else {
                             let replacement = match &filter.replacement {
                                 Some(replacement) => replacement,
@@ -113,7 +521,59 @@ impl PlatformHandler {
                 }
             }
         }
+
+        matched
+    }
+}
+
+fn format_response(settings: &ChannelSettings, response: &str, mention: &str) -> String {
+    let mut formatted = String::new();
+
+    if let Some(prefix) = &settings.response_prefix {
+        formatted.push_str(prefix);
+        formatted.push(' ');
+    }
+    if settings.mention_user && !mention.is_empty() {
+        formatted.push_str(mention);
+        formatted.push_str(", ");
+    }
+    formatted.push_str(response);
+
+    formatted
+}
+
+/// Splits the text into chunks of at most `limit` characters, preferably on whitespace.
+/// Text that doesn't fit into `max_chunks` is cut off
+fn split_text(text: &str, limit: usize, max_chunks: usize, unit: LengthUnit) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+
+    while unit.measure(rest) > limit {
+        if chunks.len() + 1 == max_chunks {
+            chunks.push(unit.truncate(rest, limit));
+            return chunks;
+        }
+
+        let split_index = unit.prefix_end(rest, limit);
+
+        let index = if rest[split_index..].starts_with(char::is_whitespace) {
+            split_index
+        } else {
+            rest[..split_index]
+                .rfind(char::is_whitespace)
+                .filter(|index| *index > 0)
+                .unwrap_or(split_index)
+        };
+
+        chunks.push(rest[..index].trim_end().to_owned());
+        rest = rest[index..].trim_start();
+    }
+
+    if !rest.is_empty() {
+        chunks.push(rest.to_owned());
     }
+
+    chunks
 }
 
 #[derive(Debug)]
@@ -149,3 +609,57 @@ impl Display for PlatformHandlerError {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{format_response, split_text};
+    use crate::database::models::ChannelSettings;
+    use crate::platform::LengthUnit;
+
+    #[test]
+    fn split_on_whitespace() {
+        assert_eq!(split_text("short", 10, 3, LengthUnit::Chars), vec!["short"]);
+        assert_eq!(
+            split_text("hello there world", 11, 3, LengthUnit::Chars),
+            vec!["hello there", "world"]
+        );
+        assert_eq!(
+            split_text("aaaaaaaaaaaa bb", 5, 5, LengthUnit::Chars),
+            vec!["aaaaa", "aaaaa", "aa bb"]
+        );
+    }
+
+    #[test]
+    fn split_multibyte() {
+        let chunks = split_text(&"ы".repeat(12), 5, 5, LengthUnit::Chars);
+        assert_eq!(chunks, vec!["ыыыыы", "ыыыыы", "ыы"]);
+    }
+
+    #[test]
+    fn split_bytes() {
+        let chunks = split_text(&"ы".repeat(6), 5, 5, LengthUnit::Bytes);
+        assert_eq!(chunks, vec!["ыы", "ыы", "ыы"]);
+        assert!(split_text("ыыы ыыы", 9, 1, LengthUnit::Bytes)[0].len() <= 9);
+    }
+
+    #[test]
+    fn split_max_chunks() {
+        let chunks = split_text("one two three four five six", 9, 2, LengthUnit::Chars);
+        assert_eq!(chunks, vec!["one two", "three fo…"]);
+    }
+
+    #[test]
+    fn response_style() {
+        let mut settings = ChannelSettings::new(0);
+        assert_eq!(format_response(&settings, "pong", "@user"), "pong");
+
+        settings.mention_user = true;
+        assert_eq!(format_response(&settings, "pong", "@user"), "@user, pong");
+
+        settings.response_prefix = Some("🤖".to_owned());
+        assert_eq!(
+            format_response(&settings, "pong", "@user"),
+            "🤖 @user, pong"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Limits of a single template render. Templates are rendered on a blocking thread, so a
+//! template that keeps sleeping or making requests would otherwise hold on to the thread.
+//!
+//! The duration is checked before every helper call and bounds how long `sleep` can wait,
+//! HTTP calls are counted by helpers registered with `register_http_helper`
+//! and the output stops being written once it's too long.
+use handlebars::{Handlebars, RenderError};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const MAX_RENDER_DURATION: Duration = Duration::from_secs(30);
+pub const MAX_HTTP_CALLS: u32 = 10;
+/// In bytes
+pub const MAX_OUTPUT_LENGTH: usize = 20_000;
+
+tokio::task_local! {
+    static BUDGET: RenderBudget;
+}
+
+#[derive(Debug, Clone)]
+struct RenderBudget {
+    started_at: Instant,
+    http_calls: Arc<AtomicU32>,
+}
+
+impl RenderBudget {
+    fn current() -> Option<Self> {
+        BUDGET.try_with(Clone::clone).ok()
+    }
+
+    fn remaining(&self) -> Duration {
+        MAX_RENDER_DURATION.saturating_sub(self.started_at.elapsed())
+    }
+}
+
+/// Renders the template within the budget. The error describes which limit was exceeded
+pub fn render_with_budget<T: Serialize>(
+    template_registry: &Handlebars,
+    template: &str,
+    data: &T,
+) -> Result<String, RenderError> {
+    let budget = RenderBudget {
+        started_at: Instant::now(),
+        http_calls: Arc::new(AtomicU32::new(0)),
+    };
+    let mut output = LimitedOutput::default();
+
+    let result = BUDGET.sync_scope(budget, || {
+        template_registry.render_template_to_write(template, data, &mut output)
+    });
+
+    match result {
+        Ok(()) => Ok(String::from_utf8_lossy(&output.buf).into_owned()),
+        Err(_) if output.exceeded => Err(RenderError::new(format!(
+            "the response is too long, the limit is {MAX_OUTPUT_LENGTH} bytes"
+        ))),
+        Err(err) => Err(err),
+    }
+}
+
+/// Called before every helper call
+pub fn check_duration() -> Result<(), RenderError> {
+    match RenderBudget::current() {
+        Some(budget) if budget.remaining().is_zero() => Err(duration_exceeded()),
+        _ => Ok(()),
+    }
+}
+
+/// Fails when the render has already made the maximum amount of HTTP calls
+pub fn start_http_call() -> Result<(), RenderError> {
+    if let Some(budget) = RenderBudget::current() {
+        let calls = budget.http_calls.fetch_add(1, Ordering::Relaxed) + 1;
+        if calls > MAX_HTTP_CALLS {
+            return Err(RenderError::new(format!(
+                "the command made too many requests, the limit is {MAX_HTTP_CALLS}"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// For calls that turned out to not make a request after all
+pub fn cancel_http_call() {
+    if let Some(budget) = RenderBudget::current() {
+        budget.http_calls.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// `None` outside of a render
+pub fn remaining_duration() -> Option<Duration> {
+    RenderBudget::current().map(|budget| budget.remaining())
+}
+
+pub fn duration_exceeded() -> RenderError {
+    RenderError::new(format!(
+        "the command took too long, the limit is {} seconds",
+        MAX_RENDER_DURATION.as_secs()
+    ))
+}
+
+#[derive(Default)]
+struct LimitedOutput {
+    buf: Vec<u8>,
+    exceeded: bool,
+}
+
+impl Write for LimitedOutput {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + data.len() > MAX_OUTPUT_LENGTH {
+            self.exceeded = true;
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "output limit exceeded",
+            ));
+        }
+
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_with_budget, start_http_call, MAX_HTTP_CALLS, MAX_OUTPUT_LENGTH};
+    use handlebars::{
+        Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
+    };
+    use serde_json::json;
+
+    fn request_helper(
+        _: &Helper,
+        _: &Handlebars,
+        _: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        start_http_call()?;
+        out.write("response")?;
+        Ok(())
+    }
+
+    #[test]
+    fn limits_output_length() {
+        let registry = Handlebars::new();
+        let data = json!({ "text": "a".repeat(MAX_OUTPUT_LENGTH / 2 + 1) });
+
+        assert!(render_with_budget(&registry, "{{ text }}", &data).is_ok());
+        let err = render_with_budget(&registry, "{{ text }}{{ text }}", &data).unwrap_err();
+        assert!(err.desc.contains("too long"));
+    }
+
+    #[test]
+    fn limits_http_calls() {
+        let mut registry = Handlebars::new();
+        registry.register_helper("request", Box::new(request_helper));
+
+        let allowed = "{{ request }}".repeat(MAX_HTTP_CALLS as usize);
+        assert!(render_with_budget(&registry, &allowed, &json!({})).is_ok());
+
+        let err: RenderError = render_with_budget(
+            &registry,
+            &format!("{allowed}{{{{ request }}}}"),
+            &json!({}),
+        )
+        .unwrap_err();
+        assert!(err.desc.contains("too many requests"));
+
+        // Each render has its own budget
+        assert!(render_with_budget(&registry, &allowed, &json!({})).is_ok());
+    }
+}
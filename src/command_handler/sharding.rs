@@ -0,0 +1,245 @@
+//! Splits the channels between multiple instances of the bot.
+//!
+//! Every shard holds a lease in the DB while it's running. The owner of a channel is picked
+//! from the shards with an active lease with rendezvous hashing, so when a shard joins or leaves
+//! only the channels of that shard move. Shards only join and answer the channels they own,
+//! EventSub notifications are forwarded to the owner over NATS on `foobot_shard.<shard id>`.
+use super::nats_connection::NatsConnection;
+use crate::{config::ShardingConfig, database::Database};
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// Not under `foobot.`, as those subjects are handled by any instance of the NATS API
+pub const SUBJECT_PREFIX: &str = "foobot_shard.";
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(10);
+/// Direct messages and channels that are not in the DB yet are handled by the owner of this key,
+/// channel ids start at 1
+const UNKNOWN_CHANNEL_KEY: u64 = 0;
+
+/// Messages that are forwarded to the shard that owns the channel
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShardMessage {
+    /// The body of a verified EventSub notification
+    EventSubNotification {
+        body: String,
+    },
+    JoinTwitchChannel {
+        login: String,
+    },
+    PartTwitchChannel {
+        login: String,
+    },
+}
+
+#[derive(Clone)]
+pub struct Sharding {
+    shard_id: Option<Arc<String>>,
+    /// Ids of the shards with an active lease, sorted
+    shards: Arc<watch::Sender<Vec<String>>>,
+    nats_client: NatsConnection,
+}
+
+impl Sharding {
+    /// Starts renewing the lease when a shard id is configured
+    pub fn start(config: &ShardingConfig, db: Database, nats_client: NatsConnection) -> Self {
+        let (shards, _) = watch::channel(Vec::new());
+        let shards = Arc::new(shards);
+
+        if let Some(shard_id) = &config.shard_id {
+            info!("Running as shard {shard_id}");
+            start_lease_renewal(
+                db,
+                shard_id.clone(),
+                Duration::from_secs(config.lease_seconds),
+                shards.clone(),
+            );
+        }
+
+        Self {
+            shard_id: config.shard_id.clone().map(Arc::new),
+            shards,
+            nats_client,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.shard_id.is_some()
+    }
+
+    pub fn get_shard_id(&self) -> Option<&str> {
+        self.shard_id.as_deref().map(String::as_str)
+    }
+
+    pub fn get_shards(&self) -> Vec<String> {
+        self.shards.borrow().clone()
+    }
+
+    /// Notified whenever shards join or leave
+    pub fn subscribe(&self) -> watch::Receiver<Vec<String>> {
+        self.shards.subscribe()
+    }
+
+    /// `None` when sharding is disabled or no shard has a lease.
+    /// Direct messages and channels that are not in the DB have no channel id
+    pub fn get_owner(&self, channel_id: Option<u64>) -> Option<String> {
+        self.shard_id.as_ref()?;
+
+        get_channel_shard(
+            &self.shards.borrow(),
+            channel_id.unwrap_or(UNKNOWN_CHANNEL_KEY),
+        )
+        .map(str::to_owned)
+    }
+
+    /// Every channel is owned when sharding is disabled. A shard doesn't own any channels
+    /// until it has acquired its lease, or after it couldn't renew it in time
+    pub fn owns_channel(&self, channel_id: Option<u64>) -> bool {
+        match &self.shard_id {
+            Some(shard_id) => self.get_owner(channel_id).as_deref() == Some(shard_id.as_str()),
+            None => true,
+        }
+    }
+
+    /// Sends the message to the shard that owns the channel and waits for it to be handled.
+    /// Returns false when the message should be handled by this shard instead
+    pub async fn forward(
+        &self,
+        channel_id: Option<u64>,
+        message: &ShardMessage,
+    ) -> anyhow::Result<bool> {
+        let owner = match self.get_owner(channel_id) {
+            Some(owner) if Some(owner.as_str()) != self.get_shard_id() => owner,
+            _ => return Ok(false),
+        };
+
+        let nats_client = self.nats_client.get().context("Not connected to NATS")?;
+        let payload = serde_json::to_vec(message)?;
+
+        let reply = tokio::time::timeout(
+            FORWARD_TIMEOUT,
+            nats_client.request(format!("{SUBJECT_PREFIX}{owner}"), payload.into()),
+        )
+        .await
+        .map_err(|_| anyhow!("Shard {owner} did not reply in time"))?
+        .map_err(|err| anyhow!("NATS request error: {err}"))?;
+
+        let result: Result<(), String> =
+            serde_json::from_slice(&reply.payload).context("Malformed shard reply")?;
+        result.map_err(|err| anyhow!("Shard {owner} could not handle the message: {err}"))?;
+
+        Ok(true)
+    }
+}
+
+/// Renews the lease three times per lease duration and updates the list of shards.
+/// The shard steps down when it couldn't renew its lease before it expired,
+/// as the other shards have taken over its channels by then
+fn start_lease_renewal(
+    db: Database,
+    shard_id: String,
+    lease_duration: Duration,
+    shards: Arc<watch::Sender<Vec<String>>>,
+) {
+    tokio::spawn(async move {
+        let mut renewed_at: Option<Instant> = None;
+
+        loop {
+            let result = {
+                let shard_id = shard_id.clone();
+                let lease_secs = lease_duration.as_secs();
+
+                db.run(move |db| {
+                    db.renew_shard_lease(&shard_id, lease_secs)?;
+                    db.get_active_shards()
+                })
+                .await
+            };
+
+            match result {
+                Ok(active_shards) => {
+                    renewed_at = Some(Instant::now());
+                    update_shards(&shards, active_shards);
+                }
+                Err(e) => {
+                    warn!("Could not renew the lease of shard {shard_id}: {e}");
+
+                    let expired = renewed_at.map_or(true, |at| at.elapsed() > lease_duration);
+                    if expired {
+                        update_shards(&shards, Vec::new());
+                    }
+                }
+            }
+
+            tokio::time::sleep(lease_duration / 3).await;
+        }
+    });
+}
+
+/// Subscribers are only notified when the shards actually changed
+fn update_shards(shards: &watch::Sender<Vec<String>>, active_shards: Vec<String>) {
+    shards.send_if_modified(|shards| {
+        if *shards == active_shards {
+            return false;
+        }
+
+        info!("Active shards changed: {active_shards:?}");
+        *shards = active_shards;
+        true
+    });
+}
+
+/// The shard with the highest hash of the shard id and the channel id owns the channel
+pub fn get_channel_shard(shards: &[String], channel_id: u64) -> Option<&str> {
+    shards
+        .iter()
+        .max_by_key(|shard| {
+            let hash = Sha256::new()
+                .chain_update(shard.as_bytes())
+                .chain_update(channel_id.to_be_bytes())
+                .finalize();
+            u64::from_be_bytes(hash[..8].try_into().expect("Hash is too short"))
+        })
+        .map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_channel_shard;
+
+    fn shards(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn assigns_every_channel_to_a_shard() {
+        assert_eq!(get_channel_shard(&[], 1), None);
+        assert_eq!(get_channel_shard(&shards(&["a"]), 1), Some("a"));
+
+        let shards = shards(&["a", "b", "c"]);
+        for shard in &shards {
+            assert!((1..1000)
+                .any(|channel_id| get_channel_shard(&shards, channel_id) == Some(shard.as_str())));
+        }
+    }
+
+    #[test]
+    fn only_moves_channels_of_changed_shards() {
+        let before = shards(&["a", "b", "c"]);
+        let after = shards(&["a", "c", "d"]);
+
+        for channel_id in 1..1000 {
+            let old_owner = get_channel_shard(&before, channel_id).unwrap();
+            let new_owner = get_channel_shard(&after, channel_id).unwrap();
+
+            if old_owner != "b" && new_owner != "d" {
+                assert_eq!(old_owner, new_owner);
+            }
+        }
+    }
+}
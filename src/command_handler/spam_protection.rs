@@ -0,0 +1,323 @@
+use crate::{
+    database::{
+        models::{ProtectionAction, ProtectionSettings},
+        Database, DatabaseError,
+    },
+    platform::UserIdentifier,
+};
+use dashmap::DashMap;
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+const REPEAT_WINDOW: Duration = Duration::from_secs(60);
+/// Offenses are forgotten after this long without a new one
+const OFFENSE_RESET: Duration = Duration::from_secs(3600);
+/// The longest timeout that Twitch allows
+const MAX_TIMEOUT: Duration = Duration::from_secs(1_209_600);
+const MIN_CAPS_LETTERS: usize = 10;
+
+/// Per-channel spam protection. Settings are loaded into the cache on first use,
+/// recent messages and offenses of users are only kept in memory
+#[derive(Debug, Clone)]
+pub struct SpamProtection {
+    db: Database,
+    settings_cache: Arc<DashMap<u64, ProtectionSettings>>,
+    recent_messages: Arc<DashMap<(u64, UserIdentifier), VecDeque<(Instant, String)>>>,
+    offenses: Arc<DashMap<(u64, UserIdentifier), (Instant, u32)>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    MessageRate,
+    Repeats,
+    Emotes,
+    Caps,
+    Links,
+    Mentions,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Violation::MessageRate => "sending messages too fast",
+            Violation::Repeats => "repeated messages",
+            Violation::Emotes => "too many emotes",
+            Violation::Caps => "excessive caps",
+            Violation::Links => "too many links",
+            Violation::Mentions => "too many mentions",
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Punishment {
+    pub action: ProtectionAction,
+    /// Only set for timeouts
+    pub duration: Option<Duration>,
+}
+
+impl SpamProtection {
+    pub fn new(db: Database) -> Self {
+        let protection = Self {
+            db,
+            settings_cache: Arc::new(DashMap::new()),
+            recent_messages: Arc::new(DashMap::new()),
+            offenses: Arc::new(DashMap::new()),
+        };
+
+        let cleanup_protection = protection.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(REPEAT_WINDOW).await;
+                cleanup_protection.clear_expired();
+            }
+        });
+
+        protection
+    }
+
+    /// Returns the default (disabled) settings if the channel has none
+    pub async fn get_settings(&self, channel_id: u64) -> Result<ProtectionSettings, DatabaseError> {
+        if let Some(settings) = self.settings_cache.get(&channel_id) {
+            return Ok(settings.clone());
+        }
+
+        let settings = self
+            .db
+            .run(move |db| db.get_protection_settings(channel_id))
+            .await?
+            .unwrap_or_else(|| ProtectionSettings::new(channel_id));
+        self.settings_cache.insert(channel_id, settings.clone());
+
+        Ok(settings)
+    }
+
+    pub async fn set_settings(&self, settings: ProtectionSettings) -> Result<(), DatabaseError> {
+        let settings = self
+            .db
+            .run(move |db| {
+                db.set_protection_settings(&settings)?;
+                Ok::<_, DatabaseError>(settings)
+            })
+            .await?;
+        self.settings_cache.insert(settings.channel_id, settings);
+
+        Ok(())
+    }
+
+    /// Records the message and returns the first check that it fails
+    pub async fn check(
+        &self,
+        channel_id: u64,
+        user: &UserIdentifier,
+        text: &str,
+        emote_count: Option<usize>,
+    ) -> Result<Option<Violation>, DatabaseError> {
+        let settings = self.get_settings(channel_id).await?;
+        if !settings.enabled {
+            return Ok(None);
+        }
+
+        let now = Instant::now();
+        let normalized_text = text.trim().to_lowercase();
+
+        let (rate, repeats) = {
+            let mut messages = self
+                .recent_messages
+                .entry((channel_id, user.clone()))
+                .or_default();
+
+            while messages
+                .front()
+                .map_or(false, |(received_at, _)| now - *received_at > REPEAT_WINDOW)
+            {
+                messages.pop_front();
+            }
+            messages.push_back((now, normalized_text.clone()));
+
+            let rate = messages
+                .iter()
+                .filter(|(received_at, _)| now - *received_at <= RATE_WINDOW)
+                .count();
+            let repeats = messages
+                .iter()
+                .filter(|(_, text)| *text == normalized_text)
+                .count();
+
+            (rate, repeats)
+        };
+
+        let emote_count = emote_count.unwrap_or_else(|| count_emotes(text));
+
+        let checks = [
+            (settings.max_message_rate, rate, Violation::MessageRate),
+            (settings.max_repeats, repeats, Violation::Repeats),
+            (settings.max_emotes, emote_count, Violation::Emotes),
+            (settings.max_links, count_links(text), Violation::Links),
+            (
+                settings.max_mentions,
+                count_mentions(text),
+                Violation::Mentions,
+            ),
+        ];
+
+        for (limit, value, violation) in checks {
+            if let Some(limit) = limit {
+                if value > limit as usize {
+                    return Ok(Some(violation));
+                }
+            }
+        }
+
+        if let (Some(limit), Some(percent)) = (settings.max_caps_percent, get_caps_percent(text)) {
+            if percent > limit as usize {
+                return Ok(Some(Violation::Caps));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Registers an offense of the user, timeouts are doubled with every repeated offense
+    pub async fn punish(
+        &self,
+        channel_id: u64,
+        user: &UserIdentifier,
+    ) -> Result<Punishment, DatabaseError> {
+        let settings = self.get_settings(channel_id).await?;
+        let action = ProtectionAction::from_str(&settings.action).unwrap_or_else(|_| {
+            tracing::warn!("Invalid protection action {}", settings.action);
+            ProtectionAction::Delete
+        });
+
+        let now = Instant::now();
+        let mut offense = self
+            .offenses
+            .entry((channel_id, user.clone()))
+            .or_insert((now, 0));
+
+        if now - offense.0 > OFFENSE_RESET {
+            offense.1 = 0;
+        }
+        offense.0 = now;
+        offense.1 += 1;
+
+        let duration = match action {
+            ProtectionAction::Delete => None,
+            ProtectionAction::Timeout => {
+                let multiplier = 2u64.saturating_pow(offense.1 - 1);
+                let secs = u64::from(settings.timeout_duration).saturating_mul(multiplier);
+                Some(Duration::from_secs(secs).min(MAX_TIMEOUT))
+            }
+        };
+
+        Ok(Punishment { action, duration })
+    }
+
+    pub fn clear_cache(&self) {
+        self.settings_cache.clear();
+    }
+
+    fn clear_expired(&self) {
+        self.recent_messages.retain(|_, messages| {
+            messages.back().map_or(false, |(received_at, _)| {
+                received_at.elapsed() <= REPEAT_WINDOW
+            })
+        });
+        self.offenses
+            .retain(|_, (last_offense, _)| last_offense.elapsed() <= OFFENSE_RESET);
+    }
+}
+
+/// Counts Discord custom emotes and emoji, platforms that report emotes are counted separately
+fn count_emotes(text: &str) -> usize {
+    let custom_emotes = text
+        .split('<')
+        .skip(1)
+        .filter(|part| {
+            let part = part.strip_prefix('a').unwrap_or(part);
+            part.starts_with(':') && part.contains('>')
+        })
+        .count();
+
+    let emoji = text
+        .chars()
+        .filter(|c| matches!(*c as u32, 0x1F300..=0x1FAFF | 0x2600..=0x27BF))
+        .count();
+
+    custom_emotes + emoji
+}
+
+fn count_links(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|word| {
+            let word = word.to_lowercase();
+            word.starts_with("http://") || word.starts_with("https://") || word.starts_with("www.")
+        })
+        .count()
+}
+
+/// Counts unique `@name` and Discord `<@id>` mentions
+fn count_mentions(text: &str) -> usize {
+    text.split_whitespace()
+        .filter_map(|word| {
+            let name = match word.strip_prefix("<@") {
+                Some(mention) => mention.trim_start_matches('!').trim_end_matches('>'),
+                None => word.strip_prefix('@')?,
+            };
+            let name = name.trim_end_matches(|c: char| c.is_ascii_punctuation());
+
+            Some(name.to_lowercase()).filter(|name| !name.is_empty())
+        })
+        .collect::<HashSet<String>>()
+        .len()
+}
+
+/// Not calculated for short messages
+fn get_caps_percent(text: &str) -> Option<usize> {
+    let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+    if letters < MIN_CAPS_LETTERS {
+        return None;
+    }
+
+    let caps = text.chars().filter(|c| c.is_uppercase()).count();
+
+    Some(caps * 100 / letters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_emotes, count_links, count_mentions, get_caps_percent};
+
+    #[test]
+    fn emotes() {
+        assert_eq!(count_emotes("hello <:pog:123> <a:wave:456> 😀"), 3);
+        assert_eq!(count_emotes("a < b"), 0);
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(
+            count_links("check https://example.com and www.example.org, not example.net"),
+            2
+        );
+    }
+
+    #[test]
+    fn mentions() {
+        assert_eq!(count_mentions("@a @b, @A <@123> <@!123> @"), 3);
+    }
+
+    #[test]
+    fn caps() {
+        assert_eq!(get_caps_percent("SHORT"), None);
+        assert_eq!(get_caps_percent("HELLO world"), Some(50));
+        assert_eq!(get_caps_percent("THIS IS ALL CAPS"), Some(100));
+    }
+}
@@ -82,6 +82,9 @@ impl SpotifyApi {
         payload.insert("redirect_uri", "http://localhost:5555/");
         payload.insert("client_id", client_id);
         payload.insert("client_secret", client_secret);
+        if let Some(code_verifier) = code_verifier {
+            payload.insert("code_verifier", code_verifier);
+        }
 
         let response = http_client
             .post("https://accounts.spotify.com/api/token")
@@ -116,6 +119,7 @@ impl SpotifyApi {
         client_id: &str,
         client_secret: &str,
         redirect_uri: &str,
+        code_verifier: Option<&str>,
     ) -> Result<SpotifyAuthentication, reqwest::Error> {
         let client = Client::new();
 
@@ -135,7 +139,7 @@ impl SpotifyApi {
 
         tracing::info!("POST {}: {}", response.url(), response.status());
 
-        response.json().await
+        response.error_for_status()?.json().await
     }
 }
 
@@ -159,6 +163,8 @@ pub struct CurrentPlayback {
     pub context: Option<Context>,
     #[serde(rename = "progress_ms")]
     pub progress_ms: i64,
+    #[serde(rename = "is_playing")]
+    pub is_playing: bool,
     pub item: Item,
 }
 
@@ -0,0 +1,184 @@
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use strum::Display;
+use tokio::fs;
+
+/// Linux reports CPU time in clock ticks, which are 100 per second on all common configurations
+const CLOCK_TICKS_PER_SECOND: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+/// Runtime state that is otherwise only visible in the logs, reported by the `status` command
+#[derive(Debug, Clone)]
+pub struct StatusTracker {
+    started_at: Instant,
+    /// Platforms or IRC networks by name with their state and when it last changed
+    connections: Arc<DashMap<String, (ConnectionState, DateTime<Utc>)>>,
+    messages_in_flight: Arc<AtomicUsize>,
+    last_eventsub_notification: Arc<Mutex<Option<DateTime<Utc>>>>,
+    /// Optional services that are currently unavailable by name, with the last error
+    degraded_services: Arc<DashMap<String, String>>,
+}
+
+impl StatusTracker {
+    pub fn set_connection_state(&self, name: &str, state: ConnectionState) {
+        let changed = self
+            .connections
+            .get(name)
+            .map(|entry| entry.0 != state)
+            .unwrap_or(true);
+
+        if changed {
+            self.connections
+                .insert(name.to_owned(), (state, Utc::now()));
+        }
+    }
+
+    /// Sorted by name
+    pub fn get_connections(&self) -> Vec<(String, ConnectionState, DateTime<Utc>)> {
+        let mut connections: Vec<_> = self
+            .connections
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.0, entry.1))
+            .collect();
+        connections.sort_by(|a, b| a.0.cmp(&b.0));
+
+        connections
+    }
+
+    pub fn set_degraded(&self, service: &str, reason: impl ToString) {
+        self.degraded_services
+            .insert(service.to_owned(), reason.to_string());
+    }
+
+    pub fn clear_degraded(&self, service: &str) {
+        self.degraded_services.remove(service);
+    }
+
+    /// Sorted by name
+    pub fn get_degraded_services(&self) -> Vec<(String, String)> {
+        let mut services: Vec<_> = self
+            .degraded_services
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        services.sort();
+
+        services
+    }
+
+    /// Counts the message as being handled until the returned guard is dropped
+    pub fn start_message(&self) -> MessageGuard {
+        self.messages_in_flight.fetch_add(1, Ordering::Relaxed);
+
+        MessageGuard {
+            messages_in_flight: self.messages_in_flight.clone(),
+        }
+    }
+
+    pub fn messages_in_flight(&self) -> usize {
+        self.messages_in_flight.load(Ordering::Relaxed)
+    }
+
+    pub fn record_eventsub_notification(&self) {
+        *self.last_eventsub_notification.lock().unwrap() = Some(Utc::now());
+    }
+
+    pub fn last_eventsub_notification(&self) -> Option<DateTime<Utc>> {
+        *self.last_eventsub_notification.lock().unwrap()
+    }
+
+    /// Average CPU usage of the process since startup in percent of a single core
+    pub async fn get_cpu_usage(&self) -> anyhow::Result<f64> {
+        let cpu_seconds = get_cpu_time().await?;
+        let elapsed = self.started_at.elapsed().as_secs_f64();
+
+        Ok(cpu_seconds / elapsed * 100.0)
+    }
+}
+
+impl Default for StatusTracker {
+    fn default() -> Self {
+        Self {
+            started_at: Instant::now(),
+            connections: Arc::new(DashMap::new()),
+            messages_in_flight: Arc::new(AtomicUsize::new(0)),
+            last_eventsub_notification: Arc::new(Mutex::new(None)),
+            degraded_services: Arc::new(DashMap::new()),
+        }
+    }
+}
+
+pub struct MessageGuard {
+    messages_in_flight: Arc<AtomicUsize>,
+}
+
+impl Drop for MessageGuard {
+    fn drop(&mut self) {
+        self.messages_in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Proportional set size of the process including swap, in KiB
+pub async fn get_memory_usage() -> anyhow::Result<u64> {
+    let smaps = fs::read_to_string("/proc/self/smaps").await?;
+
+    let mut mem_usage = 0;
+
+    for line in smaps.lines() {
+        if line.starts_with("Pss:") || line.starts_with("SwapPss:") {
+            if let Some(pss) = line.split_whitespace().nth(1) {
+                mem_usage += pss.parse::<u64>()?;
+            }
+        }
+    }
+
+    Ok(mem_usage)
+}
+
+/// User and system CPU time of the process in seconds
+async fn get_cpu_time() -> anyhow::Result<f64> {
+    let stat = fs::read_to_string("/proc/self/stat").await?;
+
+    parse_cpu_ticks(&stat)
+        .map(|ticks| ticks as f64 / CLOCK_TICKS_PER_SECOND)
+        .ok_or_else(|| anyhow::anyhow!("Unexpected /proc/self/stat format"))
+}
+
+fn parse_cpu_ticks(stat: &str) -> Option<u64> {
+    // The process name can contain spaces, so the fields are counted from its closing parenthesis.
+    // utime and stime are the 14th and 15th fields overall
+    let (_, fields) = stat.rsplit_once(')')?;
+    let mut fields = fields.split_whitespace().skip(11);
+
+    let utime: u64 = fields.next()?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+
+    Some(utime + stime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_cpu_ticks;
+
+    #[test]
+    fn parses_cpu_ticks() {
+        let stat = "1234 (foo bar) S 1 1234 1234 0 -1 4194560 2000 0 0 0 150 50 0 0 20 0 8 0";
+
+        assert_eq!(parse_cpu_ticks(stat), Some(200));
+    }
+
+    #[test]
+    fn rejects_truncated_stat() {
+        assert_eq!(parse_cpu_ticks("1234 (foo) S 1 2"), None);
+    }
+}
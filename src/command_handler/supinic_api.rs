@@ -0,0 +1,136 @@
+use anyhow::anyhow;
+use reqwest::{Client, Url};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::debug_report::{self, TraceKind};
+use crate::config::SupinicConfig;
+
+const BASE_URL: &str = "https://supinic.com/api/";
+
+/// The Supinic API, which is used by Supibot and has data such as origins of emotes and
+/// fishing stats. Requests are authenticated as the bot when it's configured
+#[derive(Clone)]
+pub struct SupinicApi {
+    client: Client,
+    authorization: Option<Arc<String>>,
+}
+
+impl SupinicApi {
+    pub fn new(config: &SupinicConfig) -> Self {
+        let authorization = match (&config.user_id, &config.password) {
+            (Some(user_id), Some(password)) => {
+                Some(Arc::new(format!("Basic {user_id}:{password}")))
+            }
+            _ => None,
+        };
+
+        Self {
+            client: Client::new(),
+            authorization,
+        }
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.authorization.is_some()
+    }
+
+    /// Returns the `data` of the response. The path is relative to `https://supinic.com/api/`,
+    /// such as `data/origin/list`
+    pub async fn get(&self, path: &str, query: &[(String, String)]) -> anyhow::Result<Value> {
+        let url = build_url(path)?;
+
+        let mut request = self.client.get(url.clone()).query(query);
+        if let Some(authorization) = &self.authorization {
+            request = request.header("Authorization", authorization.as_str());
+        }
+
+        let started_at = Instant::now();
+        let response = request.send().await;
+        debug_report::record(
+            TraceKind::Http,
+            "GET",
+            url.as_str(),
+            match &response {
+                Ok(response) => Ok(response.status().to_string()),
+                Err(err) => Err(err.to_string()),
+            },
+            started_at,
+        );
+        let response = response?;
+
+        tracing::info!("GET {}: {}", response.url(), response.status());
+
+        let status = response.status();
+        let mut body: Value = response.json().await?;
+
+        if status.is_success() {
+            Ok(body.get_mut("data").map(Value::take).unwrap_or(Value::Null))
+        } else {
+            let message = body
+                .pointer("/error/message")
+                .and_then(Value::as_str)
+                .map(str::to_owned)
+                .unwrap_or_else(|| status.to_string());
+            Err(anyhow!("Supinic API error: {message}"))
+        }
+    }
+
+    /// Marks the bot as active in the Supibot bot program
+    pub async fn ping_active(&self) -> anyhow::Result<()> {
+        let authorization = self
+            .authorization
+            .as_ref()
+            .ok_or_else(|| anyhow!("Supinic credentials are not configured"))?;
+
+        let response = self
+            .client
+            .put(format!("{BASE_URL}bot-program/bot/active"))
+            .header("Authorization", authorization.as_str())
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(anyhow!("Supinic API error: {:?}", response.text().await))
+        }
+    }
+}
+
+/// Only paths within the API are allowed, the query is given separately
+fn build_url(path: &str) -> anyhow::Result<Url> {
+    let path = path.trim_start_matches('/');
+
+    if path.is_empty() || path.contains(['?', '#', '\\']) {
+        return Err(anyhow!("invalid Supinic API path {path}"));
+    }
+
+    // Dot segments are resolved when joining, which could leave the API
+    let url = Url::parse(BASE_URL)?.join(path)?;
+    match url.as_str().starts_with(BASE_URL) {
+        true => Ok(url),
+        false => Err(anyhow!("invalid Supinic API path {path}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build_url;
+
+    #[test]
+    fn builds_api_urls() {
+        assert_eq!(
+            build_url("/data/origin/list").unwrap().as_str(),
+            "https://supinic.com/api/data/origin/list"
+        );
+        assert!(build_url("../../login").is_err());
+        assert!(build_url("bot/active?x=1").is_err());
+        assert!(build_url("data/%2e%2e/%2e%2e/login").is_err());
+        assert_eq!(
+            build_url("//example.com").unwrap().host_str(),
+            Some("supinic.com")
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Partials are named template snippets of a channel, managed with `cmd partial` and included
+//! in the channel's template commands with `{{> name}}`
+use crate::database::models::TemplatePartial;
+use handlebars::template::{Template, TemplateElement};
+use handlebars::Handlebars;
+use std::sync::Arc;
+use tracing::warn;
+
+pub const MAX_PARTIALS: usize = 50;
+const MAX_NAME_LENGTH: usize = 64;
+
+pub fn validate_partial_name(name: &str) -> Result<(), String> {
+    let is_valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    match is_valid {
+        true => Ok(()),
+        false => Err(format!(
+            "partial names can only contain letters, numbers, - and _ and be up to \
+            {MAX_NAME_LENGTH} characters long"
+        )),
+    }
+}
+
+/// Partials can't include other partials, so that a partial can't end up including itself
+pub fn validate_partial_body(body: &str) -> Result<(), String> {
+    let template = Template::compile(body).map_err(|err| err.reason().to_string())?;
+
+    match includes_partial(&template) {
+        true => Err("partials can't include other partials".to_owned()),
+        false => Ok(()),
+    }
+}
+
+fn includes_partial(template: &Template) -> bool {
+    template.elements.iter().any(|element| match element {
+        TemplateElement::PartialExpression(_) | TemplateElement::PartialBlock(_) => true,
+        TemplateElement::HelperBlock(helper) => helper
+            .template
+            .iter()
+            .chain(helper.inverse.iter())
+            .any(includes_partial),
+        TemplateElement::DecoratorBlock(decorator) => {
+            decorator.template.iter().any(includes_partial)
+        }
+        _ => false,
+    })
+}
+
+/// A copy of the registry with the partials of the channel registered in it.
+/// The registry is only copied when the channel has partials
+pub fn with_channel_partials(
+    template_registry: Arc<Handlebars<'static>>,
+    partials: &[TemplatePartial],
+) -> Arc<Handlebars<'static>> {
+    if partials.is_empty() {
+        return template_registry;
+    }
+
+    let mut channel_registry = (*template_registry).clone();
+
+    for partial in partials {
+        if let Err(err) = channel_registry.register_partial(&partial.name, &partial.body) {
+            warn!(
+                "Could not register partial {} of channel {}: {err}",
+                partial.name, partial.channel_id
+            );
+        }
+    }
+
+    Arc::new(channel_registry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_partial_body, validate_partial_name, with_channel_partials};
+    use crate::database::models::TemplatePartial;
+    use handlebars::Handlebars;
+    use std::sync::Arc;
+
+    #[test]
+    fn validates_partials() {
+        assert!(validate_partial_name("greeting_2").is_ok());
+        assert!(validate_partial_name("").is_err());
+        assert!(validate_partial_name("../greeting").is_err());
+
+        assert!(validate_partial_body("Hello {{ display_name }}").is_ok());
+        assert!(validate_partial_body("{{#if x}}").is_err());
+        assert!(validate_partial_body("{{> other}}").is_err());
+        assert!(validate_partial_body("{{#if x}}{{else}}{{> other}}{{/if}}").is_err());
+    }
+
+    #[test]
+    fn renders_channel_partials() {
+        let registry = Arc::new(Handlebars::new());
+        let partials = [TemplatePartial {
+            channel_id: 1,
+            name: "greeting".to_owned(),
+            body: "Hello {{ name }}".to_owned(),
+        }];
+
+        let channel_registry = with_channel_partials(registry.clone(), &partials);
+
+        assert_eq!(
+            channel_registry
+                .render_template("{{> greeting}}!", &serde_json::json!({ "name": "foo" }))
+                .unwrap(),
+            "Hello foo!"
+        );
+        assert!(registry.get_template("greeting").is_none());
+    }
+}
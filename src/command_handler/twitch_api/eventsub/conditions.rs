@@ -6,9 +6,25 @@ pub struct BroadcasterIdCondition {
 }
 
 pub type ChannelUpdateCondition = BroadcasterIdCondition;
+pub type StreamOnlineCondition = BroadcasterIdCondition;
+pub type StreamOfflineCondition = BroadcasterIdCondition;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelPointsCustomRewardRedemptionAddCondition {
     pub broadcaster_user_id: String,
     pub reward_id: Option<String>,
 }
+
+/// Raids into the broadcaster's channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelRaidCondition {
+    pub to_broadcaster_user_id: String,
+}
+
+/// The moderator has to have authorized the shield mode scopes, which is the broadcaster
+/// when the subscription is created with their manage token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShieldModeCondition {
+    pub broadcaster_user_id: String,
+    pub moderator_user_id: String,
+}
@@ -5,7 +5,11 @@ use serde::Deserialize;
 pub enum EventSubEventType {
     ChannelUpdate(ChannelUpdateEvent),
     StreamOnline(StreamOnlineEvent),
+    StreamOffline(StreamOfflineEvent),
     ChannelPointsCustomRewardRedemptionAdd(ChannelPointsCustomRewardRedemptionAddEvent),
+    ShieldModeBegin(ShieldModeBeginEvent),
+    ShieldModeEnd(ShieldModeEndEvent),
+    ChannelRaid(ChannelRaidEvent),
 }
 
 impl EventSubEventType {
@@ -13,9 +17,13 @@ impl EventSubEventType {
         match self {
             EventSubEventType::ChannelUpdate(event) => event.broadcaster_user_id.clone(),
             EventSubEventType::StreamOnline(event) => event.broadcaster_user_id.clone(),
+            EventSubEventType::StreamOffline(event) => event.broadcaster_user_id.clone(),
             EventSubEventType::ChannelPointsCustomRewardRedemptionAdd(event) => {
                 event.broadcaster_user_id.clone()
             }
+            EventSubEventType::ShieldModeBegin(event) => event.broadcaster_user_id.clone(),
+            EventSubEventType::ShieldModeEnd(event) => event.broadcaster_user_id.clone(),
+            EventSubEventType::ChannelRaid(event) => event.to_broadcaster_user_id.clone(),
         }
     }
 }
@@ -43,6 +51,46 @@ pub struct StreamOnlineEvent {
     pub started_at: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct StreamOfflineEvent {
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelRaidEvent {
+    pub from_broadcaster_user_id: String,
+    pub from_broadcaster_user_login: String,
+    pub from_broadcaster_user_name: String,
+    pub to_broadcaster_user_id: String,
+    pub to_broadcaster_user_login: String,
+    pub to_broadcaster_user_name: String,
+    pub viewers: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShieldModeBeginEvent {
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    pub moderator_user_id: String,
+    pub moderator_user_login: String,
+    pub moderator_user_name: String,
+    pub started_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShieldModeEndEvent {
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    pub moderator_user_id: String,
+    pub moderator_user_login: String,
+    pub moderator_user_name: String,
+    pub ended_at: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ChannelPointsCustomRewardRedemptionAddEvent {
     pub id: String,
@@ -58,6 +106,25 @@ pub struct ChannelPointsCustomRewardRedemptionAddEvent {
     pub redeemed_at: String,
 }
 
+impl ChannelPointsCustomRewardRedemptionAddEvent {
+    /// Redemptions of rewards that skip the request queue are already fulfilled
+    pub fn pending_redemption(&self) -> Option<PendingRedemption> {
+        (self.status == "unfulfilled").then(|| PendingRedemption {
+            id: self.id.clone(),
+            broadcaster_id: self.broadcaster_user_id.clone(),
+            reward_id: self.reward.id.clone(),
+        })
+    }
+}
+
+/// A redemption that is waiting to be fulfilled or canceled
+#[derive(Debug, Clone)]
+pub struct PendingRedemption {
+    pub id: String,
+    pub broadcaster_id: String,
+    pub reward_id: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Reward {
     pub id: String,
@@ -5,26 +5,35 @@ use anyhow::anyhow;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::{json, Value};
-use std::env;
 use std::str::FromStr;
 
 use self::conditions::*;
 use self::events::*;
-use crate::api;
+use crate::config::Config;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EventSubSubscriptionType {
     ChannelUpdate(ChannelUpdateCondition),
+    StreamOnline(StreamOnlineCondition),
+    StreamOffline(StreamOfflineCondition),
     ChannelPointsCustomRewardRedemptionAdd(ChannelPointsCustomRewardRedemptionAddCondition),
+    ShieldModeBegin(ShieldModeCondition),
+    ShieldModeEnd(ShieldModeCondition),
+    ChannelRaid(ChannelRaidCondition),
 }
 
 impl EventSubSubscriptionType {
     pub fn get_type(&self) -> &str {
         match self {
             Self::ChannelUpdate(_) => "channel.update",
+            Self::StreamOnline(_) => "stream.online",
+            Self::StreamOffline(_) => "stream.offline",
             Self::ChannelPointsCustomRewardRedemptionAdd(_) => {
                 "channel.channel_points_custom_reward_redemption.add"
             }
+            Self::ShieldModeBegin(_) => "channel.shield_mode.begin",
+            Self::ShieldModeEnd(_) => "channel.shield_mode.end",
+            Self::ChannelRaid(_) => "channel.raid",
         }
     }
 
@@ -34,30 +43,36 @@ impl EventSubSubscriptionType {
 
     pub fn get_condition(&self) -> Value {
         match self {
-            Self::ChannelUpdate(condition) => serde_json::to_value(condition).unwrap(),
+            Self::ChannelUpdate(condition)
+            | Self::StreamOnline(condition)
+            | Self::StreamOffline(condition) => serde_json::to_value(condition).unwrap(),
             Self::ChannelPointsCustomRewardRedemptionAdd(condition) => {
                 serde_json::to_value(condition).unwrap()
             }
+            Self::ShieldModeBegin(condition) | Self::ShieldModeEnd(condition) => {
+                serde_json::to_value(condition).unwrap()
+            }
+            Self::ChannelRaid(condition) => serde_json::to_value(condition).unwrap(),
         }
     }
 
-    fn get_transport() -> Value {
-        let key = env::var("SECRET_KEY").expect("Could not read SECRET_KEY");
-        let callback_url = format!("{}/api/hooks/twitch/eventsub", api::get_base_url());
+    fn get_transport(config: &Config, secret: &str) -> Value {
+        let callback_url = format!("{}/api/hooks/twitch/eventsub", config.base_url);
 
         json!({
            "method": "webhook",
            "callback": callback_url,
-           "secret": key,
+           "secret": secret,
         })
     }
 
-    pub fn build_body(&self) -> Value {
+    /// Notifications of the subscription are signed with the secret
+    pub fn build_body(&self, config: &Config, secret: &str) -> Value {
         json!({
             "type": self.get_type(),
             "version": self.get_version(),
             "condition": self.get_condition(),
-            "transport": Self::get_transport()
+            "transport": Self::get_transport(config, secret)
         })
     }
 }
@@ -100,6 +115,17 @@ impl EventSubNotification {
                     self.event,
                 )?)
             }
+            "channel.shield_mode.begin" => {
+                EventSubEventType::ShieldModeBegin(serde_json::from_value(self.event)?)
+            }
+            "channel.shield_mode.end" => {
+                EventSubEventType::ShieldModeEnd(serde_json::from_value(self.event)?)
+            }
+            "stream.online" => EventSubEventType::StreamOnline(serde_json::from_value(self.event)?),
+            "stream.offline" => {
+                EventSubEventType::StreamOffline(serde_json::from_value(self.event)?)
+            }
+            "channel.raid" => EventSubEventType::ChannelRaid(serde_json::from_value(self.event)?),
             _ => unimplemented!(),
         })
     }
@@ -117,12 +143,27 @@ pub struct EventSubSubscription {
     pub created_at: String,
 }
 
+impl EventSubSubscription {
+    /// Revoked subscriptions and ones with too many failed notifications don't receive events
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.status.as_str(),
+            "enabled" | "webhook_callback_verification_pending"
+        )
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct EventSubVerficationCallback {
     pub subscription: EventSubSubscription,
     pub challenge: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct EventSubRevocation {
+    pub subscription: EventSubSubscription,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct EventSubSubscriptionResponse {
     pub data: Vec<EventSubSubscription>,
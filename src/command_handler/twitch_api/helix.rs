@@ -11,11 +11,11 @@ use tokio::task;
 use tracing::debug;
 use twitch_irc::login::{LoginCredentials, StaticLoginCredentials};
 
+use crate::config::Config;
 use crate::{api::response_ok, command_handler::twitch_api::model::UsersResponse};
 
 use super::{
     eventsub::{EventSubSubscription, EventSubSubscriptionResponse, EventSubSubscriptionType},
-    get_client_id,
     model::*,
 };
 
@@ -30,13 +30,10 @@ pub struct HelixApi<C: LoginCredentials> {
 }
 
 impl<C: LoginCredentials> HelixApi<C> {
-    pub async fn with_credentials(credentials: C) -> Self {
+    pub async fn with_credentials(credentials: C, client_id: &str) -> Self {
         let mut headers = HeaderMap::new();
 
-        headers.insert(
-            "Client-Id",
-            get_client_id().expect("Client ID missing").parse().unwrap(),
-        );
+        headers.insert("Client-Id", client_id.parse().unwrap());
 
         let helix = Self {
             client: Client::new(),
@@ -92,6 +89,14 @@ impl<C: LoginCredentials> HelixApi<C> {
         self.request(Method::DELETE, path).await
     }
 
+    async fn put(&self, path: &str) -> anyhow::Result<RequestBuilder> {
+        self.request(Method::PUT, path).await
+    }
+
+    async fn patch(&self, path: &str) -> anyhow::Result<RequestBuilder> {
+        self.request(Method::PATCH, path).await
+    }
+
     pub async fn get_users(
         &self,
         logins: Option<&[&str]>,
@@ -147,6 +152,29 @@ impl<C: LoginCredentials> HelixApi<C> {
         Ok(results)
     }
 
+    /// Always queries the API and refreshes the cached users, so renamed users are picked up
+    pub async fn get_users_by_ids_uncached(&self, ids: &[&str]) -> anyhow::Result<Vec<User>> {
+        let mut results = Vec::with_capacity(ids.len());
+
+        for chunk in ids.chunks(100) {
+            let params: Vec<(&str, &str)> = chunk.iter().map(|id| ("id", *id)).collect();
+
+            let response = self.get("/users").await?.query(&params).send().await?;
+
+            tracing::info!("GET {}: {}", response.url(), response.status());
+
+            response_ok(&response)?;
+
+            results.extend(response.json::<UsersResponse>().await?.data);
+        }
+
+        let mut users_cache = self.users_cache.write().unwrap();
+        users_cache.retain(|user| !results.iter().any(|result| result.id == user.id));
+        users_cache.extend(results.clone());
+
+        Ok(results)
+    }
+
     pub async fn get_user_by_id(&self, id: &str) -> anyhow::Result<User> {
         let users = self.get_users(None, Some(&[id])).await?;
 
@@ -156,6 +184,15 @@ impl<C: LoginCredentials> HelixApi<C> {
             .ok_or_else(|| anyhow!("User not found"))
     }
 
+    pub async fn get_user_by_login(&self, login: &str) -> anyhow::Result<User> {
+        let users = self.get_users(Some(&[login]), None).await?;
+
+        users
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("User not found"))
+    }
+
     /*async fn get_token(&self) -> anyhow::Result<String> {
         Ok(self
             .credentials
@@ -194,11 +231,13 @@ impl<C: LoginCredentials> HelixApi<C> {
     pub async fn add_eventsub_subscription(
         &self,
         subscription: EventSubSubscriptionType,
+        config: &Config,
+        secret: &str,
     ) -> anyhow::Result<EventSubSubscriptionResponse> {
         let response = self
             .post("/eventsub/subscriptions")
             .await?
-            .json(&subscription.build_body())
+            .json(&subscription.build_body(config, secret))
             .send()
             .await?;
 
@@ -278,11 +317,219 @@ impl<C: LoginCredentials> HelixApi<C> {
         Ok(info)
     }
 
+    /// Returns the stream of the given broadcaster, or `None` if they are offline
+    pub async fn get_stream(&self, broadcaster_id: &str) -> anyhow::Result<Option<Stream>> {
+        let response = self
+            .get("/streams")
+            .await?
+            .query(&[("user_id", broadcaster_id)])
+            .send()
+            .await?;
+
+        response_ok(&response)?;
+
+        let data = response.json::<GenericHelixResponse<Stream>>().await?;
+
+        Ok(data.data.into_iter().next())
+    }
+
+    pub async fn get_channel_information(
+        &self,
+        broadcaster_id: &str,
+    ) -> anyhow::Result<ChannelInformation> {
+        let response = self
+            .get("/channels")
+            .await?
+            .query(&[("broadcaster_id", broadcaster_id)])
+            .send()
+            .await?;
+
+        response_ok(&response)?;
+
+        let data = response
+            .json::<GenericHelixResponse<ChannelInformation>>()
+            .await?;
+
+        data.data
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Channel not found"))
+    }
+
+    /// Requires the `channel:manage:broadcast` scope
+    pub async fn modify_channel_information(
+        &self,
+        broadcaster_id: &str,
+        title: Option<&str>,
+        game_id: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let mut payload = json!({});
+
+        if let Some(title) = title {
+            payload["title"] = json!(title);
+        }
+        if let Some(game_id) = game_id {
+            payload["game_id"] = json!(game_id);
+        }
+
+        let response = self
+            .patch("/channels")
+            .await?
+            .query(&[("broadcaster_id", broadcaster_id)])
+            .json(&payload)
+            .send()
+            .await?;
+
+        response_ok(&response)
+    }
+
+    /// Requires the `channel:manage:broadcast` scope, the broadcaster has to be live
+    pub async fn create_stream_marker(
+        &self,
+        broadcaster_id: &str,
+        description: Option<&str>,
+    ) -> anyhow::Result<StreamMarker> {
+        let mut payload = json!({ "user_id": broadcaster_id });
+        if let Some(description) = description {
+            payload["description"] = json!(description);
+        }
+
+        let response = self
+            .post("/streams/markers")
+            .await?
+            .json(&payload)
+            .send()
+            .await?;
+
+        response_ok(&response)?;
+
+        let data = response
+            .json::<GenericHelixResponse<StreamMarker>>()
+            .await?;
+
+        data.data
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Twitch did not return the created marker"))
+    }
+
+    pub async fn get_game_by_name(&self, name: &str) -> anyhow::Result<Option<Game>> {
+        let response = self
+            .get("/games")
+            .await?
+            .query(&[("name", name)])
+            .send()
+            .await?;
+
+        response_ok(&response)?;
+
+        let data = response.json::<GenericHelixResponse<Game>>().await?;
+
+        Ok(data.data.into_iter().next())
+    }
+
+    /// Requires the `moderator:read:followers` scope
+    pub async fn get_channel_follower(
+        &self,
+        broadcaster_id: &str,
+        user_id: &str,
+    ) -> anyhow::Result<Option<ChannelFollower>> {
+        let response = self
+            .get("/channels/followers")
+            .await?
+            .query(&[("broadcaster_id", broadcaster_id), ("user_id", user_id)])
+            .send()
+            .await?;
+
+        response_ok(&response)?;
+
+        let data = response
+            .json::<GenericHelixResponse<ChannelFollower>>()
+            .await?;
+
+        Ok(data.data.into_iter().next())
+    }
+
+    /// Requires the `channel:manage:redemptions` scope,
+    /// the reward has to be created by the same client id
+    pub async fn update_redemption_status(
+        &self,
+        broadcaster_id: &str,
+        reward_id: &str,
+        redemption_id: &str,
+        status: RedemptionStatus,
+    ) -> anyhow::Result<()> {
+        let response = self
+            .patch("/channel_points/custom_rewards/redemptions")
+            .await?
+            .query(&[
+                ("id", redemption_id),
+                ("broadcaster_id", broadcaster_id),
+                ("reward_id", reward_id),
+            ])
+            .json(&json!({ "status": status }))
+            .send()
+            .await?;
+
+        response_ok(&response)
+    }
+
+    /// Requires the `moderator:manage:announcements` scope
+    pub async fn send_chat_announcement(
+        &self,
+        broadcaster_id: &str,
+        message: &str,
+        color: Option<AnnouncementColor>,
+    ) -> anyhow::Result<()> {
+        let self_id = self.get_self_user().await?.id;
+
+        let mut payload = json!({ "message": message });
+        if let Some(color) = color {
+            payload["color"] = json!(color);
+        }
+
+        let response = self
+            .post("/chat/announcements")
+            .await?
+            .query(&[
+                ("broadcaster_id", broadcaster_id),
+                ("moderator_id", &self_id),
+            ])
+            .json(&payload)
+            .send()
+            .await?;
+
+        response_ok(&response)
+    }
+
+    /// Requires the `moderator:manage:shoutouts` scope
+    pub async fn send_shoutout(
+        &self,
+        from_broadcaster_id: &str,
+        to_broadcaster_id: &str,
+    ) -> anyhow::Result<()> {
+        let self_id = self.get_self_user().await?.id;
+
+        let response = self
+            .post("/chat/shoutouts")
+            .await?
+            .query(&[
+                ("from_broadcaster_id", from_broadcaster_id),
+                ("to_broadcaster_id", to_broadcaster_id),
+                ("moderator_id", &self_id),
+            ])
+            .send()
+            .await?;
+
+        response_ok(&response)
+    }
+
     pub async fn ban_user(
         &self,
         broadcaster_id: &str,
         user_id: &str,
         duration: Option<i32>,
+        reason: Option<&str>,
     ) -> anyhow::Result<()> {
         let self_id = self.get_self_user().await?.id;
         debug!("Self id: {self_id}");
@@ -291,6 +538,7 @@ impl<C: LoginCredentials> HelixApi<C> {
             "data": {
                 "user_id": user_id,
                 "duration": duration,
+                "reason": reason,
             }
         });
         debug!("Timeout payload: {payload}");
@@ -311,16 +559,147 @@ impl<C: LoginCredentials> HelixApi<C> {
         Ok(())
     }
 
-    pub async fn ban_user_by_name(
+    pub async fn delete_chat_message(
         &self,
         broadcaster_id: &str,
-        user_name: &str,
-        duration: Option<i32>,
+        message_id: &str,
     ) -> anyhow::Result<()> {
-        let users = self.get_users(Some(&[user_name]), None).await?;
-        debug!("Fetched user info");
-        let user = users.first().context("Empty users response")?;
-        self.ban_user(broadcaster_id, &user.id, duration).await
+        let self_id = self.get_self_user().await?.id;
+
+        let response = self
+            .delete("/moderation/chat")
+            .await?
+            .query(&[
+                ("broadcaster_id", broadcaster_id),
+                ("moderator_id", &self_id),
+                ("message_id", message_id),
+            ])
+            .send()
+            .await?;
+
+        response_ok(&response)?;
+
+        Ok(())
+    }
+
+    /// Requires the `moderator:read:shield_mode` or `moderator:manage:shield_mode` scope
+    pub async fn get_shield_mode_status(
+        &self,
+        broadcaster_id: &str,
+    ) -> anyhow::Result<ShieldModeStatus> {
+        let self_id = self.get_self_user().await?.id;
+
+        let response = self
+            .get("/moderation/shield_mode")
+            .await?
+            .query(&[
+                ("broadcaster_id", broadcaster_id),
+                ("moderator_id", &self_id),
+            ])
+            .send()
+            .await?;
+
+        response_ok(&response)?;
+
+        let data = response
+            .json::<GenericHelixResponse<ShieldModeStatus>>()
+            .await?;
+
+        data.data
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Twitch did not return the shield mode status"))
+    }
+
+    /// Requires the `moderator:manage:shield_mode` scope
+    pub async fn update_shield_mode_status(
+        &self,
+        broadcaster_id: &str,
+        is_active: bool,
+    ) -> anyhow::Result<ShieldModeStatus> {
+        let self_id = self.get_self_user().await?.id;
+
+        let response = self
+            .put("/moderation/shield_mode")
+            .await?
+            .query(&[
+                ("broadcaster_id", broadcaster_id),
+                ("moderator_id", &self_id),
+            ])
+            .json(&json!({ "is_active": is_active }))
+            .send()
+            .await?;
+
+        response_ok(&response)?;
+
+        let data = response
+            .json::<GenericHelixResponse<ShieldModeStatus>>()
+            .await?;
+
+        data.data
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Twitch did not return the shield mode status"))
+    }
+
+    /// Requires the `moderator:read:automod_settings` or `moderator:manage:automod_settings` scope
+    pub async fn get_automod_settings(
+        &self,
+        broadcaster_id: &str,
+    ) -> anyhow::Result<AutoModSettings> {
+        let self_id = self.get_self_user().await?.id;
+
+        let response = self
+            .get("/moderation/automod/settings")
+            .await?
+            .query(&[
+                ("broadcaster_id", broadcaster_id),
+                ("moderator_id", &self_id),
+            ])
+            .send()
+            .await?;
+
+        response_ok(&response)?;
+
+        let data = response
+            .json::<GenericHelixResponse<AutoModSettings>>()
+            .await?;
+
+        data.data
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Twitch did not return the AutoMod settings"))
+    }
+
+    /// Requires the `moderator:manage:automod_settings` scope
+    pub async fn update_automod_settings(
+        &self,
+        broadcaster_id: &str,
+        settings: &AutoModSettings,
+    ) -> anyhow::Result<AutoModSettings> {
+        let self_id = self.get_self_user().await?.id;
+
+        let response = self
+            .put("/moderation/automod/settings")
+            .await?
+            .query(&[
+                ("broadcaster_id", broadcaster_id),
+                ("moderator_id", &self_id),
+            ])
+            .json(&settings.to_update_payload())
+            .send()
+            .await?;
+
+        response_ok(&response)?;
+
+        let data = response
+            .json::<GenericHelixResponse<AutoModSettings>>()
+            .await?;
+
+        data.data
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Twitch did not return the AutoMod settings"))
     }
 }
 
@@ -3,65 +3,89 @@ pub mod helix;
 pub mod model;
 
 use std::collections::HashMap;
-use std::env;
 use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
 use reqwest::Client;
-use tokio::sync::mpsc::UnboundedSender;
 use tokio::task;
 
 use model::*;
 use twitch_irc::login::{LoginCredentials, RefreshingLoginCredentials, StaticLoginCredentials};
 
-use crate::database::Database;
+use crate::database::{credentials::Credentials, Database};
 use crate::platform::twitch;
 
 use self::helix::HelixApi;
 
 const APP_SCOPES: &[&str] = &["moderation:read", "channel:moderate", "chat:edit"];
 
+const STREAM_INFO_CACHE_TTL: Duration = Duration::from_secs(60);
+
+pub type BroadcasterHelixApi = HelixApi<RefreshingLoginCredentials<Credentials>>;
+
 #[derive(Clone, Debug)]
 pub struct TwitchApi<C: LoginCredentials + Clone> {
     pub helix_api: HelixApi<C>,
     pub helix_api_app: HelixApi<StaticLoginCredentials>,
-    pub chat_sender: Arc<Mutex<Option<UnboundedSender<twitch::SenderMessage>>>>,
+    pub chat_sender: Arc<Mutex<Option<twitch::ChatSender>>>,
     moderators_cache: Arc<RwLock<HashMap<String, Vec<String>>>>,
+    stream_info_cache: Arc<DashMap<String, (Instant, StreamInfo)>>,
+    /// Kept up to date by the `stream.online` and `stream.offline` EventSub triggers
+    live_state: Arc<DashMap<String, bool>>,
     client: Client,
 }
 
+#[derive(Clone, Debug)]
+pub struct StreamInfo {
+    pub channel: ChannelInformation,
+    pub stream: Option<Stream>,
+}
+
+impl StreamInfo {
+    /// Returns how long the stream has been live, or `None` if the channel is offline
+    pub fn uptime(&self) -> Option<chrono::Duration> {
+        let stream = self.stream.as_ref()?;
+        let started_at = DateTime::parse_from_rfc3339(&stream.started_at).ok()?;
+
+        Some(Utc::now().signed_duration_since(started_at))
+    }
+}
+
 impl TwitchApi<RefreshingLoginCredentials<Database>> {
     pub async fn init_refreshing(db: Database) -> anyhow::Result<Self> {
-        let client_id = env::var("TWITCH_CLIENT_ID")?;
-        let client_secret = env::var("TWITCH_CLIENT_SECRET")?;
+        let (client_id, client_secret) = get_app_credentials(&db)?;
 
-        let credentials = RefreshingLoginCredentials::init(client_id, client_secret, db);
+        let credentials =
+            RefreshingLoginCredentials::init(client_id.clone(), client_secret.clone(), db);
 
-        Self::init(credentials).await
+        Self::init(credentials, &client_id, &client_secret).await
     }
 }
 
 impl<C: LoginCredentials + Clone> TwitchApi<C> {
-    pub async fn init(credentials: C) -> anyhow::Result<Self> {
-        let client_id = get_client_id().expect("Client ID missing");
-
-        let app_access_token = Self::get_app_token(
-            &client_id,
-            &get_client_secret().expect("client secret missing"),
-        )
-        .await?;
+    pub async fn init(
+        credentials: C,
+        client_id: &str,
+        client_secret: &str,
+    ) -> anyhow::Result<Self> {
+        let app_access_token = Self::get_app_token(client_id, client_secret).await?;
 
         let twitch_api = TwitchApi {
-            helix_api: HelixApi::with_credentials(credentials).await,
-            helix_api_app: HelixApi::with_credentials(StaticLoginCredentials::new(
-                String::new(),
-                Some(app_access_token),
-            ))
+            helix_api: HelixApi::with_credentials(credentials, client_id).await,
+            helix_api_app: HelixApi::with_credentials(
+                StaticLoginCredentials::new(String::new(), Some(app_access_token)),
+                client_id,
+            )
             .await,
             client: Client::new(),
             chat_sender: Arc::new(Mutex::new(None)),
             moderators_cache: Arc::new(RwLock::new(HashMap::new())),
+            stream_info_cache: Arc::new(DashMap::new()),
+            live_state: Arc::new(DashMap::new()),
         };
 
         twitch_api.start_cron().await;
@@ -169,6 +193,55 @@ impl<C: LoginCredentials + Clone> TwitchApi<C> {
 
         Ok(mods)
     }
+
+    /// Returns the channel information and the current stream, cached for a short time
+    pub async fn get_stream_info(&self, broadcaster_id: &str) -> anyhow::Result<StreamInfo> {
+        if let Some(entry) = self.stream_info_cache.get(broadcaster_id) {
+            let (fetched_at, info) = entry.value();
+
+            if fetched_at.elapsed() < STREAM_INFO_CACHE_TTL {
+                return Ok(info.clone());
+            }
+        }
+
+        let channel = self
+            .helix_api_app
+            .get_channel_information(broadcaster_id)
+            .await?;
+        let stream = self.helix_api_app.get_stream(broadcaster_id).await?;
+
+        let info = StreamInfo { channel, stream };
+
+        self.stream_info_cache
+            .insert(broadcaster_id.to_owned(), (Instant::now(), info.clone()));
+
+        Ok(info)
+    }
+
+    pub fn invalidate_stream_info(&self, broadcaster_id: &str) {
+        self.stream_info_cache.remove(broadcaster_id);
+    }
+
+    pub fn clear_stream_info_cache(&self) {
+        self.stream_info_cache.clear();
+        self.live_state.clear();
+    }
+
+    pub fn set_live(&self, broadcaster_id: &str, live: bool) {
+        self.live_state.insert(broadcaster_id.to_owned(), live);
+        self.invalidate_stream_info(broadcaster_id);
+    }
+
+    /// Channels without stream EventSub triggers fall back to the cached stream info
+    pub async fn is_live(&self, broadcaster_id: &str) -> anyhow::Result<bool> {
+        if let Some(live) = self.live_state.get(broadcaster_id) {
+            return Ok(*live);
+        }
+
+        let info = self.get_stream_info(broadcaster_id).await?;
+        Ok(info.stream.is_some())
+    }
+
     // This terrible abomination has to exist because twitch doesn't provide an endpoint for this that doesn't require channel auth
     // /// Returns the list of logins of channel moderators. Don't expect this to be efficient
     /*async fn get_channel_mods_from_irc(
@@ -219,10 +292,49 @@ impl<C: LoginCredentials + Clone> TwitchApi<C> {
     }*/
 }
 
-pub fn get_client_id() -> Option<String> {
-    env::var("TWITCH_CLIENT_ID").ok()
+/// The client id and secret of the Twitch application
+pub fn get_app_credentials(db: &Database) -> anyhow::Result<(String, String)> {
+    db.config()
+        .twitch
+        .credentials()
+        .map(|(client_id, client_secret)| (client_id.to_owned(), client_secret.to_owned()))
+        .context("Twitch is not configured")
 }
 
-pub fn get_client_secret() -> Option<String> {
-    env::var("TWITCH_CLIENT_SECRET").ok()
+/// Creates a Helix client that acts on behalf of the broadcaster using their manage token
+pub async fn get_broadcaster_helix_api(
+    db: &Database,
+    broadcaster_id: &str,
+) -> anyhow::Result<BroadcasterHelixApi> {
+    let (client_id, client_secret) = get_app_credentials(db)?;
+    let credentials = db.make_twitch_credentials(broadcaster_id.to_owned());
+    let refreshing_credentials =
+        RefreshingLoginCredentials::init(client_id.clone(), client_secret, credentials);
+
+    refreshing_credentials
+        .get_credentials()
+        .await
+        .map_err(|_| anyhow!("streamer is not authorized"))?;
+
+    Ok(HelixApi::with_credentials(refreshing_credentials, &client_id).await)
+}
+
+/// Returns how long the user has been following the channel, or `None` if they are not following it
+pub async fn get_followage(
+    db: &Database,
+    broadcaster_id: &str,
+    user_id: &str,
+) -> anyhow::Result<Option<chrono::Duration>> {
+    let helix_api = get_broadcaster_helix_api(db, broadcaster_id).await?;
+
+    match helix_api
+        .get_channel_follower(broadcaster_id, user_id)
+        .await?
+    {
+        Some(follower) => {
+            let followed_at = DateTime::parse_from_rfc3339(&follower.followed_at)?;
+            Ok(Some(Utc::now().signed_duration_since(followed_at)))
+        }
+        None => Ok(None),
+    }
 }
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use strum::EnumString;
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -136,3 +138,159 @@ pub struct UserBanInfo {
     pub created_at: String,
     pub end_time: String,
 }
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelFollower {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub followed_at: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stream {
+    pub id: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub game_id: String,
+    pub game_name: String,
+    pub title: String,
+    pub viewer_count: i64,
+    pub started_at: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamMarker {
+    pub id: String,
+    pub created_at: String,
+    pub description: String,
+    /// Seconds since the start of the stream
+    pub position_seconds: i64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelInformation {
+    pub broadcaster_id: String,
+    pub broadcaster_login: String,
+    pub broadcaster_name: String,
+    pub game_id: String,
+    pub game_name: String,
+    pub title: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Game {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RedemptionStatus {
+    Fulfilled,
+    Canceled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum AnnouncementColor {
+    Primary,
+    Blue,
+    Green,
+    Orange,
+    Purple,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShieldModeStatus {
+    pub is_active: bool,
+    pub moderator_id: String,
+    pub moderator_login: String,
+    pub moderator_name: String,
+    pub last_activated_at: String,
+}
+
+/// Levels go from 0 (no filtering) to 4 (most aggressive filtering).
+/// `overall_level` is `None` when the categories were set individually.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoModSettings {
+    pub overall_level: Option<u8>,
+    pub disability: u8,
+    pub aggression: u8,
+    pub sexuality_sex_or_gender: u8,
+    pub misogyny: u8,
+    pub bullying: u8,
+    pub swearing: u8,
+    pub race_ethnicity_or_religion: u8,
+    pub sex_based_terms: u8,
+}
+
+impl AutoModSettings {
+    pub const MAX_LEVEL: u8 = 4;
+    pub const CATEGORIES: &'static [&'static str] = &[
+        "disability",
+        "aggression",
+        "sexuality_sex_or_gender",
+        "misogyny",
+        "bullying",
+        "swearing",
+        "race_ethnicity_or_religion",
+        "sex_based_terms",
+    ];
+
+    /// Setting a single category switches the settings to individual levels.
+    /// Returns false if there is no such category.
+    pub fn set_category_level(&mut self, category: &str, level: u8) -> bool {
+        let field = match category {
+            "disability" => &mut self.disability,
+            "aggression" => &mut self.aggression,
+            "sexuality_sex_or_gender" => &mut self.sexuality_sex_or_gender,
+            "misogyny" => &mut self.misogyny,
+            "bullying" => &mut self.bullying,
+            "swearing" => &mut self.swearing,
+            "race_ethnicity_or_religion" => &mut self.race_ethnicity_or_religion,
+            "sex_based_terms" => &mut self.sex_based_terms,
+            _ => return false,
+        };
+
+        *field = level;
+        self.overall_level = None;
+        true
+    }
+
+    /// Twitch rejects updates that set both the overall level and individual categories
+    pub fn to_update_payload(&self) -> Value {
+        match self.overall_level {
+            Some(level) => json!({ "overall_level": level }),
+            None => {
+                let mut payload = serde_json::to_value(self).unwrap();
+                payload.as_object_mut().unwrap().remove("overall_level");
+                payload
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AutoModSettings;
+    use serde_json::json;
+
+    #[test]
+    fn automod_category_switches_to_individual_levels() {
+        let mut settings = AutoModSettings {
+            overall_level: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(settings.to_update_payload(), json!({ "overall_level": 2 }));
+
+        assert!(settings.set_category_level("swearing", 3));
+        assert!(!settings.set_category_level("spam", 3));
+
+        let payload = settings.to_update_payload();
+        assert_eq!(payload["swearing"], 3);
+        assert!(payload.get("overall_level").is_none());
+    }
+}
@@ -0,0 +1,952 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use tracing::metadata::LevelFilter;
+use tracing_subscriber::filter::ParseError;
+use tracing_subscriber::EnvFilter;
+
+use crate::platform::irc::DEFAULT_NETWORK;
+use crate::platform::UserIdentifier;
+
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Settings of the whole bot.
+///
+/// Values are read from a TOML file, every setting can also be overridden with
+/// an environment variable of the same name as in `.env.dist`
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub base_url: String,
+    /// Used for signing cookies and EventSub messages, has to be at least 64 bytes long
+    pub secret_key: String,
+    /// The user identifier of the bot owner, such as `twitch:12345`
+    pub admin_user: Option<String>,
+    pub command_prefix: String,
+    /// Only run builtin commands, without executing custom commands or EventSub actions
+    pub safe_mode: bool,
+    pub allow_shell: bool,
+    pub otlp_host: String,
+    /// Directives in the `RUST_LOG` format, `info` is used when empty
+    pub log_filter: String,
+    pub nats_address: String,
+    pub lingva_instance_url: String,
+    pub hebi_modules_git_url: Option<String>,
+    /// Legacy list of blocked user identifiers, imported into the database once
+    pub blocked_users: Vec<String>,
+    pub database: DatabaseConfig,
+    pub twitch: OAuthClientConfig,
+    pub discord: DiscordConfig,
+    pub spotify: OAuthClientConfig,
+    pub github: OAuthClientConfig,
+    pub google: OAuthClientConfig,
+    pub api_keys: ApiKeysConfig,
+    pub openai: OpenAiConfig,
+    pub images: ImagesConfig,
+    pub chat_logs: ChatLogsConfig,
+    pub minecraft: MinecraftConfig,
+    pub local_platform: LocalPlatformConfig,
+    pub grpc: GrpcConfig,
+    pub rate_limits: RateLimitsConfig,
+    pub supinic: SupinicConfig,
+    pub sharding: ShardingConfig,
+    pub http_fetch: HttpFetchConfig,
+    pub sentry: SentryConfig,
+    /// IRC networks by name, the `default` network's channels are not prefixed with its name
+    pub irc: BTreeMap<String, IrcNetworkConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub pool_size: u32,
+    pub pool_min_idle: Option<u32>,
+    /// Seconds to wait for a free connection
+    pub pool_timeout: u64,
+    /// Connections held for longer than this many milliseconds are reported as slow
+    pub slow_query_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OAuthClientConfig {
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiscordConfig {
+    pub token: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// Overrides the global command prefix on Discord
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiKeysConfig {
+    pub owm: Option<String>,
+    pub lastfm: Option<String>,
+    pub finnhub: Option<String>,
+    pub exchange_rate: Option<String>,
+    pub coingecko: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpenAiConfig {
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub model: String,
+    pub max_tokens: u32,
+    /// Seconds between requests of a single user
+    pub user_cooldown: u64,
+    /// USD per 1000 tokens
+    pub prompt_price: f64,
+    /// USD per 1000 tokens
+    pub completion_price: f64,
+    /// Exchanges of a user that are remembered in channels with the `ai_memory` setting
+    pub memory_exchanges: usize,
+    /// Seconds since the last exchange after which the conversation is forgotten
+    pub memory_ttl: u64,
+    /// Estimated tokens of the remembered exchanges sent with each prompt
+    pub memory_max_tokens: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImagesConfig {
+    /// TTF or OTF font of captions, image generation is disabled without it
+    pub font_path: Option<String>,
+    /// Bytes
+    pub max_download_size: u64,
+    /// Larger images are scaled down to fit
+    pub max_dimension: u32,
+    /// Images per minute in a single channel
+    pub channel_limit: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChatLogsConfig {
+    /// Full months that are kept in addition to the current one, forever if 0
+    pub retention_months: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MinecraftConfig {
+    pub rcon_address: Option<String>,
+    pub rcon_password: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocalPlatformConfig {
+    pub address: Option<String>,
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GrpcConfig {
+    pub port: u16,
+    pub token: Option<String>,
+}
+
+/// Requests per minute
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitsConfig {
+    pub ip: u32,
+    pub session: u32,
+    pub eval: u32,
+    /// Reverse proxies whose `X-Forwarded-For` header is used as the client address
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SupinicConfig {
+    pub user_id: Option<String>,
+    pub password: Option<String>,
+}
+
+/// Multiple instances split the channels between each other when `shard_id` is set
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShardingConfig {
+    /// Has to be unique for every instance
+    pub shard_id: Option<String>,
+    /// Seconds after which the channels of a shard that stopped renewing its lease are taken over
+    pub lease_seconds: u64,
+}
+
+/// Requests made by commands with the `get` helper and `http.fetch` in Hebi
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpFetchConfig {
+    /// Only these hosts and their subdomains can be requested, every host if empty
+    pub allowed_hosts: Vec<String>,
+    /// Hosts and their subdomains that can't be requested
+    pub denied_hosts: Vec<String>,
+    /// Loopback, private and link-local addresses are blocked unless this is set
+    pub allow_private_addresses: bool,
+    /// In bytes
+    pub max_response_size: u64,
+    /// Seconds
+    pub timeout: u64,
+    /// Seconds that successful GET responses are reused for, not cached if 0
+    pub cache_seconds: u64,
+}
+
+/// Panics and unexpected command errors are reported to Sentry when `dsn` is set
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SentryConfig {
+    pub dsn: Option<String>,
+    /// Such as `production`, shown on the reported events
+    pub environment: Option<String>,
+}
+
+impl SentryConfig {
+    /// An empty DSN disables reporting too
+    pub fn get_dsn(&self) -> Option<&str> {
+        self.dsn.as_deref().filter(|dsn| !dsn.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IrcNetworkConfig {
+    pub server: Option<String>,
+    pub port: Option<u16>,
+    pub nickname: Option<String>,
+    pub password: Option<String>,
+    pub channels: Vec<String>,
+    pub sasl: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            base_url: String::new(),
+            secret_key: String::new(),
+            admin_user: None,
+            command_prefix: "!".to_owned(),
+            safe_mode: false,
+            allow_shell: false,
+            otlp_host: String::new(),
+            log_filter: String::new(),
+            nats_address: String::new(),
+            lingva_instance_url: "https://lingva.ml".to_owned(),
+            hebi_modules_git_url: None,
+            blocked_users: Vec::new(),
+            database: DatabaseConfig::default(),
+            twitch: OAuthClientConfig::default(),
+            discord: DiscordConfig::default(),
+            spotify: OAuthClientConfig::default(),
+            github: OAuthClientConfig::default(),
+            google: OAuthClientConfig::default(),
+            api_keys: ApiKeysConfig::default(),
+            openai: OpenAiConfig::default(),
+            images: ImagesConfig::default(),
+            chat_logs: ChatLogsConfig::default(),
+            minecraft: MinecraftConfig::default(),
+            local_platform: LocalPlatformConfig::default(),
+            grpc: GrpcConfig::default(),
+            rate_limits: RateLimitsConfig::default(),
+            supinic: SupinicConfig::default(),
+            sharding: ShardingConfig::default(),
+            http_fetch: HttpFetchConfig::default(),
+            sentry: SentryConfig::default(),
+            irc: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            pool_size: 10,
+            pool_min_idle: None,
+            pool_timeout: 5,
+            slow_query_ms: 500,
+        }
+    }
+}
+
+impl Default for OpenAiConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            base_url: None,
+            model: "gpt-3.5-turbo".to_owned(),
+            max_tokens: 200,
+            user_cooldown: 30,
+            prompt_price: 0.0,
+            completion_price: 0.0,
+            memory_exchanges: 5,
+            memory_ttl: 1800,
+            memory_max_tokens: 1000,
+        }
+    }
+}
+
+impl Default for ImagesConfig {
+    fn default() -> Self {
+        Self {
+            font_path: None,
+            max_download_size: 5_000_000,
+            max_dimension: 1024,
+            channel_limit: 5,
+        }
+    }
+}
+
+impl Default for ChatLogsConfig {
+    fn default() -> Self {
+        Self {
+            retention_months: 3,
+        }
+    }
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            port: 50051,
+            token: None,
+        }
+    }
+}
+
+impl Default for RateLimitsConfig {
+    fn default() -> Self {
+        Self {
+            ip: 120,
+            session: 300,
+            eval: 10,
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+impl Default for ShardingConfig {
+    fn default() -> Self {
+        Self {
+            shard_id: None,
+            lease_seconds: 30,
+        }
+    }
+}
+
+impl Default for HttpFetchConfig {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: Vec::new(),
+            denied_hosts: Vec::new(),
+            allow_private_addresses: false,
+            max_response_size: 1_000_000,
+            timeout: 10,
+            cache_seconds: 0,
+        }
+    }
+}
+
+// Secrets shouldn't end up in logs, the config is included in the debug output of the database
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config").finish_non_exhaustive()
+    }
+}
+
+impl OAuthClientConfig {
+    /// The client id and secret, if both are configured
+    pub fn credentials(&self) -> Option<(&str, &str)> {
+        self.client_id.as_deref().zip(self.client_secret.as_deref())
+    }
+}
+
+impl Config {
+    /// Loads the file at the given path, `CONFIG_FILE` or `config.toml` and applies the environment overrides.
+    /// The default path is allowed to not exist, in which case the configuration comes from the environment only
+    pub fn load(path: Option<PathBuf>) -> Result<Self, ConfigError> {
+        let (path, required) =
+            match path.or_else(|| env::var("CONFIG_FILE").ok().map(PathBuf::from)) {
+                Some(path) => (path, true),
+                None => (PathBuf::from(DEFAULT_CONFIG_PATH), false),
+            };
+
+        let mut config = match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).map_err(|e| ConfigError::Parse(path, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => Self::default(),
+            Err(e) => return Err(ConfigError::Read(path, e)),
+        };
+
+        let mut problems = config.apply_env_overrides();
+        problems.extend(config.validate());
+
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    /// The user identifier of the admin, validated on load
+    pub fn admin_user(&self) -> Option<UserIdentifier> {
+        self.admin_user
+            .as_deref()
+            .and_then(|admin_user| UserIdentifier::from_string(admin_user).ok())
+    }
+
+    pub fn env_filter(&self) -> Result<EnvFilter, ParseError> {
+        EnvFilter::builder()
+            .with_default_directive(LevelFilter::INFO.into())
+            .parse(&self.log_filter)
+    }
+
+    /// Takes the settings that can be changed at runtime from a newly loaded config,
+    /// everything else is only applied after a restart.
+    /// Returns whether the new config also has changes to other settings
+    pub fn merge_reloadable(&self, new: &Config) -> (Config, bool) {
+        let mut merged = self.clone();
+        merged.log_filter = new.log_filter.clone();
+        merged.blocked_users = new.blocked_users.clone();
+        merged.admin_user = new.admin_user.clone();
+        merged.api_keys = new.api_keys.clone();
+
+        // Safe mode is toggled at runtime and can be enabled from the command line
+        let restart_required = Config {
+            safe_mode: self.safe_mode,
+            ..new.clone()
+        } != merged;
+
+        (merged, restart_required)
+    }
+
+    fn apply_env_overrides(&mut self) -> Vec<String> {
+        let mut env = EnvOverrides::default();
+
+        env.set(&mut self.base_url, "BASE_URL");
+        env.set(&mut self.secret_key, "SECRET_KEY");
+        env.set_opt(&mut self.admin_user, "ADMIN_USER");
+        env.set(&mut self.command_prefix, "COMMAND_PREFIX");
+        env.set_flag(&mut self.safe_mode, "SAFE_MODE");
+        env.set_flag(&mut self.allow_shell, "ALLOW_SHELL");
+        env.set(&mut self.otlp_host, "OTLP_HOST");
+        env.set(&mut self.log_filter, "RUST_LOG");
+        env.set(&mut self.nats_address, "NATS_ADDRESS");
+        env.set(&mut self.lingva_instance_url, "LINGVA_INSTANCE_URL");
+        env.set_opt(&mut self.hebi_modules_git_url, "HEBI_MODULES_GIT_URL");
+        env.set_list(&mut self.blocked_users, "BLOCKED_USERS");
+
+        let database = &mut self.database;
+        env.set(&mut database.url, "DATABASE_URL");
+        env.set(&mut database.pool_size, "DB_POOL_SIZE");
+        env.set_opt(&mut database.pool_min_idle, "DB_POOL_MIN_IDLE");
+        env.set(&mut database.pool_timeout, "DB_POOL_TIMEOUT");
+        env.set(&mut database.slow_query_ms, "DB_SLOW_QUERY_MS");
+
+        for (client, prefix) in [
+            (&mut self.twitch, "TWITCH"),
+            (&mut self.spotify, "SPOTIFY"),
+            (&mut self.github, "GITHUB"),
+            (&mut self.google, "GOOGLE"),
+        ] {
+            env.set_opt(&mut client.client_id, &format!("{prefix}_CLIENT_ID"));
+            env.set_opt(
+                &mut client.client_secret,
+                &format!("{prefix}_CLIENT_SECRET"),
+            );
+        }
+
+        let discord = &mut self.discord;
+        env.set_opt(&mut discord.token, "DISCORD_TOKEN");
+        env.set_opt(&mut discord.client_id, "DISCORD_CLIENT_ID");
+        env.set_opt(&mut discord.client_secret, "DISCORD_CLIENT_SECRET");
+        env.set_opt(&mut discord.prefix, "DISCORD_PREFIX");
+
+        let api_keys = &mut self.api_keys;
+        env.set_opt(&mut api_keys.owm, "OWM_API_KEY");
+        env.set_opt(&mut api_keys.lastfm, "LASTFM_API_KEY");
+        env.set_opt(&mut api_keys.finnhub, "FINNHUB_API_KEY");
+        env.set_opt(&mut api_keys.exchange_rate, "EXCHANGE_RATE_API_KEY");
+        env.set_opt(&mut api_keys.coingecko, "COINGECKO_API_KEY");
+
+        let openai = &mut self.openai;
+        env.set_opt(&mut openai.api_key, "OPENAI_API_KEY");
+        env.set_opt(&mut openai.base_url, "OPENAI_BASE_URL");
+        env.set(&mut openai.model, "OPENAI_MODEL");
+        env.set(&mut openai.max_tokens, "AI_MAX_TOKENS");
+        env.set(&mut openai.user_cooldown, "AI_USER_COOLDOWN");
+        env.set(&mut openai.prompt_price, "AI_PROMPT_PRICE");
+        env.set(&mut openai.completion_price, "AI_COMPLETION_PRICE");
+        env.set(&mut openai.memory_exchanges, "AI_MEMORY_EXCHANGES");
+        env.set(&mut openai.memory_ttl, "AI_MEMORY_TTL");
+        env.set(&mut openai.memory_max_tokens, "AI_MEMORY_MAX_TOKENS");
+
+        let images = &mut self.images;
+        env.set_opt(&mut images.font_path, "IMAGES_FONT_PATH");
+        env.set(&mut images.max_download_size, "IMAGES_MAX_DOWNLOAD_SIZE");
+        env.set(&mut images.max_dimension, "IMAGES_MAX_DIMENSION");
+        env.set(&mut images.channel_limit, "IMAGES_CHANNEL_LIMIT");
+
+        env.set(
+            &mut self.chat_logs.retention_months,
+            "CHAT_LOGS_RETENTION_MONTHS",
+        );
+
+        env.set_opt(&mut self.minecraft.rcon_address, "MINECRAFT_RCON_ADDRESS");
+        env.set_opt(&mut self.minecraft.rcon_password, "MINECRAFT_RCON_PASSWORD");
+
+        env.set_opt(&mut self.local_platform.address, "LOCAL_PLATFORM_ADDRESS");
+        env.set_opt(&mut self.local_platform.token, "LOCAL_PLATFORM_TOKEN");
+
+        env.set(&mut self.grpc.port, "GRPC_PORT");
+        env.set_opt(&mut self.grpc.token, "GRPC_TOKEN");
+
+        env.set(&mut self.rate_limits.ip, "API_RATE_LIMIT_IP");
+        env.set(&mut self.rate_limits.session, "API_RATE_LIMIT_SESSION");
+        env.set(&mut self.rate_limits.eval, "API_RATE_LIMIT_EVAL");
+        env.set_list(&mut self.rate_limits.trusted_proxies, "API_TRUSTED_PROXIES");
+
+        env.set_opt(&mut self.supinic.user_id, "SUPINIC_USER_ID");
+        env.set_opt(&mut self.supinic.password, "SUPINIC_PASSWORD");
+
+        env.set_opt(&mut self.sharding.shard_id, "SHARD_ID");
+        env.set(&mut self.sharding.lease_seconds, "SHARD_LEASE_SECONDS");
+
+        let http_fetch = &mut self.http_fetch;
+        env.set_list(&mut http_fetch.allowed_hosts, "HTTP_FETCH_ALLOWED_HOSTS");
+        env.set_list(&mut http_fetch.denied_hosts, "HTTP_FETCH_DENIED_HOSTS");
+        env.set_flag(
+            &mut http_fetch.allow_private_addresses,
+            "HTTP_FETCH_ALLOW_PRIVATE_ADDRESSES",
+        );
+        env.set(
+            &mut http_fetch.max_response_size,
+            "HTTP_FETCH_MAX_RESPONSE_SIZE",
+        );
+        env.set(&mut http_fetch.timeout, "HTTP_FETCH_TIMEOUT");
+        env.set(&mut http_fetch.cache_seconds, "HTTP_FETCH_CACHE_SECONDS");
+
+        env.set_opt(&mut self.sentry.dsn, "SENTRY_DSN");
+        env.set_opt(&mut self.sentry.environment, "SENTRY_ENVIRONMENT");
+
+        // The default network is configured with `IRC_SERVER` etc, additional networks are listed in
+        // `IRC_NETWORKS` and use variables with the network name, such as `IRC_LIBERA_SERVER`
+        let mut irc_networks = Vec::new();
+        if env::var("IRC_SERVER").is_ok() {
+            irc_networks.push((DEFAULT_NETWORK.to_owned(), "IRC".to_owned()));
+        }
+        if let Ok(names) = env::var("IRC_NETWORKS") {
+            for name in names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+            {
+                irc_networks.push((name.to_lowercase(), format!("IRC_{}", name.to_uppercase())));
+            }
+        }
+
+        for (name, prefix) in irc_networks {
+            let network = self.irc.entry(name).or_default();
+
+            env.set_opt(&mut network.server, &format!("{prefix}_SERVER"));
+            env.set_opt(&mut network.port, &format!("{prefix}_PORT"));
+            env.set_opt(&mut network.nickname, &format!("{prefix}_NICKNAME"));
+            env.set_opt(&mut network.password, &format!("{prefix}_PASSWORD"));
+            env.set_list(&mut network.channels, &format!("{prefix}_CHANNELS"));
+            env.set_flag(&mut network.sasl, &format!("{prefix}_SASL"));
+        }
+
+        env.problems
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for (value, name) in [
+            (&self.base_url, "base_url (BASE_URL)"),
+            (&self.secret_key, "secret_key (SECRET_KEY)"),
+            (&self.database.url, "database.url (DATABASE_URL)"),
+            (&self.otlp_host, "otlp_host (OTLP_HOST)"),
+            (&self.nats_address, "nats_address (NATS_ADDRESS)"),
+        ] {
+            if value.is_empty() {
+                problems.push(format!("{name} is required"));
+            }
+        }
+
+        if !self.base_url.is_empty()
+            && !(self.base_url.starts_with("http://") || self.base_url.starts_with("https://"))
+        {
+            problems.push(format!(
+                "base_url has to start with http:// or https://, got {}",
+                self.base_url
+            ));
+        }
+
+        if !self.secret_key.is_empty() && self.secret_key.len() < 64 {
+            problems.push("secret_key has to be at least 64 bytes long".to_owned());
+        }
+
+        if let Err(e) = self.env_filter() {
+            problems.push(format!("log_filter is invalid: {e}"));
+        }
+
+        if let Some(admin_user) = &self.admin_user {
+            if let Err(e) = UserIdentifier::from_string(admin_user) {
+                problems.push(format!("admin_user {admin_user} is invalid: {e}"));
+            }
+        }
+
+        for blocked_user in &self.blocked_users {
+            if let Err(e) = UserIdentifier::from_string(blocked_user) {
+                problems.push(format!("blocked user {blocked_user} is invalid: {e}"));
+            }
+        }
+
+        for (name, client) in [
+            ("twitch", &self.twitch),
+            ("spotify", &self.spotify),
+            ("github", &self.github),
+            ("google", &self.google),
+        ] {
+            check_pair(
+                &mut problems,
+                name,
+                ("client_id", &client.client_id),
+                ("client_secret", &client.client_secret),
+            );
+        }
+
+        check_pair(
+            &mut problems,
+            "discord",
+            ("client_id", &self.discord.client_id),
+            ("client_secret", &self.discord.client_secret),
+        );
+        if self.discord.token.is_some() && self.discord.client_id.is_none() {
+            problems.push("discord.client_id is required when discord.token is set".to_owned());
+        }
+
+        check_pair(
+            &mut problems,
+            "minecraft",
+            ("rcon_address", &self.minecraft.rcon_address),
+            ("rcon_password", &self.minecraft.rcon_password),
+        );
+
+        for (name, network) in &self.irc {
+            if network.server.is_none() {
+                problems.push(format!("irc.{name}.server is required"));
+            }
+        }
+
+        // The shard id is part of the NATS subject that the shard listens on
+        if let Some(shard_id) = &self.sharding.shard_id {
+            if shard_id.is_empty()
+                || shard_id.len() > 64
+                || !shard_id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                problems.push(format!(
+                    "sharding.shard_id {shard_id} is invalid, it can only contain up to 64 \
+                    letters, numbers, dashes and underscores"
+                ));
+            }
+        }
+        if self.sharding.lease_seconds < 3 {
+            problems.push("sharding.lease_seconds has to be at least 3".to_owned());
+        }
+
+        if self.http_fetch.timeout == 0 {
+            problems.push("http_fetch.timeout has to be at least 1".to_owned());
+        }
+        if self.http_fetch.max_response_size == 0 {
+            problems.push("http_fetch.max_response_size has to be at least 1".to_owned());
+        }
+        if self.http_fetch.cache_seconds > 3600 {
+            problems.push("http_fetch.cache_seconds can be at most 3600".to_owned());
+        }
+
+        if let Some(dsn) = self.sentry.get_dsn() {
+            if let Err(err) = sentry::types::Dsn::from_str(dsn) {
+                problems.push(format!("sentry.dsn is invalid: {err}"));
+            }
+        }
+
+        problems
+    }
+}
+
+fn check_pair(
+    problems: &mut Vec<String>,
+    section: &str,
+    (first_name, first): (&str, &Option<String>),
+    (second_name, second): (&str, &Option<String>),
+) {
+    match (first, second) {
+        (Some(_), None) => problems.push(format!(
+            "{section}.{second_name} is required when {section}.{first_name} is set"
+        )),
+        (None, Some(_)) => problems.push(format!(
+            "{section}.{first_name} is required when {section}.{second_name} is set"
+        )),
+        _ => (),
+    }
+}
+
+/// Collects the values of environment variables that couldn't be parsed
+#[derive(Default)]
+struct EnvOverrides {
+    problems: Vec<String>,
+}
+
+impl EnvOverrides {
+    fn parse<T>(&mut self, name: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let value = env::var(name).ok()?;
+
+        match value.parse() {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.problems.push(format!("{name} is invalid: {e}"));
+                None
+            }
+        }
+    }
+
+    fn set<T>(&mut self, target: &mut T, name: &str)
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        if let Some(value) = self.parse(name) {
+            *target = value;
+        }
+    }
+
+    fn set_opt<T>(&mut self, target: &mut Option<T>, name: &str)
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        if let Some(value) = self.parse(name) {
+            *target = Some(value);
+        }
+    }
+
+    /// Accepts `1` and `0` in addition to `true` and `false`
+    fn set_flag(&mut self, target: &mut bool, name: &str) {
+        if let Ok(value) = env::var(name) {
+            match value.as_str() {
+                "1" | "true" => *target = true,
+                "0" | "false" => *target = false,
+                _ => self
+                    .problems
+                    .push(format!("{name} has to be true or false, got {value}")),
+            }
+        }
+    }
+
+    /// Comma separated values, the list is kept as is if any of them is invalid
+    fn set_list<T>(&mut self, target: &mut Vec<T>, name: &str)
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        if let Ok(value) = env::var(name) {
+            let items = value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::parse)
+                .collect::<Result<Vec<T>, _>>();
+
+            match items {
+                Ok(items) => *target = items,
+                Err(e) => self.problems.push(format!("{name} is invalid: {e}")),
+            }
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("could not read {}: {1}", .0.display())]
+    Read(PathBuf, io::Error),
+    #[error("could not parse {}: {1}", .0.display())]
+    Parse(PathBuf, toml::de::Error),
+    #[error("invalid configuration:\n{}", format_problems(.0))]
+    Invalid(Vec<String>),
+}
+
+fn format_problems(problems: &[String]) -> String {
+    problems
+        .iter()
+        .map(|problem| format!("  - {problem}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiKeysConfig, Config, DatabaseConfig};
+    use std::env;
+
+    fn valid_config() -> Config {
+        Config {
+            base_url: "https://example.com".to_owned(),
+            secret_key: "a".repeat(64),
+            otlp_host: "http://localhost:4317".to_owned(),
+            nats_address: "localhost:4222".to_owned(),
+            database: DatabaseConfig {
+                url: "mysql://localhost/foobot".to_owned(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merges_reloadable_settings() {
+        let current = Config::default();
+        let new = Config {
+            admin_user: Some("twitch:12345".to_owned()),
+            api_keys: ApiKeysConfig {
+                owm: Some("key".to_owned()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let (merged, restart_required) = current.merge_reloadable(&new);
+
+        assert_eq!(merged.admin_user, new.admin_user);
+        assert_eq!(merged.api_keys, new.api_keys);
+        assert!(!restart_required);
+    }
+
+    #[test]
+    fn keeps_settings_that_need_a_restart() {
+        let current = Config::default();
+        let new = Config {
+            base_url: "https://example.com".to_owned(),
+            safe_mode: true,
+            ..Default::default()
+        };
+
+        let (merged, restart_required) = current.merge_reloadable(&new);
+
+        assert_eq!(merged.base_url, current.base_url);
+        assert!(!merged.safe_mode);
+        assert!(restart_required);
+    }
+
+    #[test]
+    fn accepts_valid_config() {
+        assert!(valid_config().validate().is_empty());
+    }
+
+    #[test]
+    fn overrides_settings_from_env() {
+        let mut config: Config = toml::from_str(
+            r#"
+            base_url = "https://toml.example.com"
+            command_prefix = "?"
+
+            [database]
+            pool_size = 5
+            "#,
+        )
+        .unwrap();
+
+        env::set_var("BASE_URL", "https://env.example.com");
+        env::set_var("DB_POOL_SIZE", "20");
+        env::set_var("SHARD_LEASE_SECONDS", "soon");
+        let problems = config.apply_env_overrides();
+        env::remove_var("BASE_URL");
+        env::remove_var("DB_POOL_SIZE");
+        env::remove_var("SHARD_LEASE_SECONDS");
+
+        assert_eq!(config.base_url, "https://env.example.com");
+        assert_eq!(config.database.pool_size, 20);
+        assert_eq!(config.command_prefix, "?");
+        assert_eq!(config.sharding.lease_seconds, 30);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("SHARD_LEASE_SECONDS is invalid"));
+    }
+
+    #[test]
+    fn requires_settings() {
+        let config = Config {
+            secret_key: String::new(),
+            ..valid_config()
+        };
+
+        assert_eq!(
+            config.validate(),
+            vec!["secret_key (SECRET_KEY) is required".to_owned()]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_base_url() {
+        let config = Config {
+            base_url: "example.com".to_owned(),
+            ..valid_config()
+        };
+
+        assert_eq!(
+            config.validate(),
+            vec!["base_url has to start with http:// or https://, got example.com".to_owned()]
+        );
+    }
+
+    #[test]
+    fn rejects_short_secret_key() {
+        let config = Config {
+            secret_key: "a".repeat(63),
+            ..valid_config()
+        };
+
+        assert_eq!(
+            config.validate(),
+            vec!["secret_key has to be at least 64 bytes long".to_owned()]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_user_identifiers() {
+        let config = Config {
+            admin_user: Some("telegram:admin".to_owned()),
+            blocked_users: vec!["twitch:12345".to_owned(), "12345".to_owned()],
+            ..valid_config()
+        };
+
+        let problems = config.validate();
+
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("admin_user telegram:admin is invalid"));
+        assert!(problems[1].starts_with("blocked user 12345 is invalid"));
+    }
+}
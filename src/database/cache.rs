@@ -0,0 +1,135 @@
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+
+/// A concurrent map where every entry expires after a time to live
+#[derive(Debug)]
+pub struct TtlCache<K: Eq + Hash, V> {
+    entries: DashMap<K, (V, Instant)>,
+    ttl: Duration,
+}
+
+impl<K: Eq + Hash, V: Clone> TtlCache<K, V> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: DashMap::new(),
+            ttl,
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let now = Instant::now();
+
+        if let Some(entry) = self.entries.get(key) {
+            let (value, expires_at) = entry.value();
+            if *expires_at > now {
+                return Some(value.clone());
+            }
+        } else {
+            return None;
+        }
+
+        self.entries
+            .remove_if(key, |_, (_, expires_at)| *expires_at <= now);
+        None
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        self.insert_with_ttl(key, value, self.ttl);
+    }
+
+    /// Inserts an entry that expires sooner or later than the default
+    pub fn insert_with_ttl(&self, key: K, value: V, ttl: Duration) {
+        self.entries.insert(key, (value, Instant::now() + ttl));
+    }
+
+    /// Returns false without replacing the value if the key has an entry that hasn't expired
+    pub fn insert_if_absent(&self, key: K, value: V) -> bool {
+        let now = Instant::now();
+
+        match self.entries.entry(key) {
+            Entry::Occupied(entry) if entry.get().1 > now => false,
+            Entry::Occupied(mut entry) => {
+                entry.insert((value, now + self.ttl));
+                true
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((value, now + self.ttl));
+                true
+            }
+        }
+    }
+
+    pub fn remove(&self, key: &K) {
+        self.entries.remove(key);
+    }
+
+    pub fn retain(&self, mut f: impl FnMut(&K, &V) -> bool) {
+        self.entries.retain(|key, (value, _)| f(key, value));
+    }
+
+    /// Drops expired entries so that values which are never requested again don't pile up
+    pub fn remove_expired(&self) {
+        let now = Instant::now();
+        self.entries.retain(|_, (_, expires_at)| *expires_at > now);
+    }
+
+    pub fn clear(&self) {
+        self.entries.clear();
+    }
+
+    /// Includes expired entries that haven't been removed yet
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TtlCache;
+    use std::time::Duration;
+
+    #[test]
+    fn returns_fresh_entries() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        cache.insert(1, "value");
+
+        assert_eq!(cache.get(&1), Some("value"));
+        assert_eq!(cache.get(&2), None);
+    }
+
+    #[test]
+    fn expires_entries() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        cache.insert_with_ttl(1, "value", Duration::ZERO);
+
+        assert_eq!(cache.get(&1), None);
+    }
+
+    #[test]
+    fn removes_expired_entries() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        cache.insert_with_ttl(1, "old", Duration::ZERO);
+        cache.insert(2, "new");
+        cache.remove_expired();
+
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.get(&2), Some("new"));
+    }
+
+    #[test]
+    fn inserts_only_absent_entries() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        cache.insert_with_ttl(1, "expired", Duration::ZERO);
+
+        assert!(cache.insert_if_absent(1, "first"));
+        assert!(!cache.insert_if_absent(1, "second"));
+        assert_eq!(cache.get(&1), Some("first"));
+    }
+}
@@ -0,0 +1,117 @@
+//! Per-channel feature flags.
+//!
+//! Only the flags that were set for a channel are stored, other channels use the feature's
+//! default. The flags of a channel are cached, as they are checked for messages and commands
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use super::models::{ChannelFeature, ChannelFeatureState, ChannelFeatureValue};
+use super::schema::channel_features;
+use super::{Database, DatabaseError};
+
+impl Database {
+    pub fn is_feature_enabled(
+        &self,
+        channel_id: u64,
+        feature: ChannelFeature,
+    ) -> Result<bool, DatabaseError> {
+        Ok(self
+            .get_channel_feature_flags(channel_id)?
+            .get(&feature)
+            .copied()
+            .unwrap_or_else(|| feature.enabled_by_default()))
+    }
+
+    /// Every feature, including the ones that use the default
+    pub fn get_channel_features(
+        &self,
+        channel_id: u64,
+    ) -> Result<Vec<ChannelFeatureState>, DatabaseError> {
+        let flags = self.get_channel_feature_flags(channel_id)?;
+
+        Ok(ChannelFeature::ALL
+            .into_iter()
+            .map(|feature| ChannelFeatureState {
+                feature: feature.to_string(),
+                enabled: flags
+                    .get(&feature)
+                    .copied()
+                    .unwrap_or_else(|| feature.enabled_by_default()),
+                is_default: !flags.contains_key(&feature),
+            })
+            .collect())
+    }
+
+    pub fn set_channel_feature(
+        &self,
+        channel_id: u64,
+        feature: ChannelFeature,
+        enabled: bool,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::replace_into(channel_features::table)
+            .values(ChannelFeatureValue {
+                channel_id,
+                feature: feature.to_string(),
+                enabled,
+            })
+            .execute(&mut conn)?;
+
+        self.channel_features_cache.remove(&channel_id);
+
+        Ok(())
+    }
+
+    /// Makes the channel use the feature's default again
+    pub fn reset_channel_feature(
+        &self,
+        channel_id: u64,
+        feature: ChannelFeature,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::delete(
+            channel_features::table
+                .filter(channel_features::channel_id.eq(channel_id))
+                .filter(channel_features::feature.eq(feature.to_string())),
+        )
+        .execute(&mut conn)?;
+
+        self.channel_features_cache.remove(&channel_id);
+
+        Ok(())
+    }
+
+    fn get_channel_feature_flags(
+        &self,
+        channel_id: u64,
+    ) -> Result<HashMap<ChannelFeature, bool>, DatabaseError> {
+        if let Some(flags) = self.channel_features_cache.get(&channel_id) {
+            return Ok(flags);
+        }
+
+        let mut conn = self.get_conn()?;
+
+        let values: Vec<ChannelFeatureValue> = channel_features::table
+            .filter(channel_features::channel_id.eq(channel_id))
+            .load(&mut conn)?;
+
+        // Flags of features that no longer exist are ignored
+        let flags: HashMap<ChannelFeature, bool> = values
+            .into_iter()
+            .filter_map(|value| {
+                ChannelFeature::from_str(&value.feature)
+                    .ok()
+                    .map(|feature| (feature, value.enabled))
+            })
+            .collect();
+
+        self.channel_features_cache
+            .insert(channel_id, flags.clone());
+
+        Ok(flags)
+    }
+}
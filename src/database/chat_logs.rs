@@ -0,0 +1,248 @@
+//! Chat messages of the channels with the `chat_logs` setting.
+//!
+//! The `chat_logs` table is partitioned by month, so that expired months can be dropped
+//! without deleting every row. Messages of months without a partition go into `p_future`,
+//! which is split as new months are created.
+use chrono::{Datelike, Duration, Months, NaiveDate, Utc};
+use diesel::sql_types::{Nullable, Text};
+use diesel::{sql_query, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+use super::models::{ChannelSetting, ChatLog, NewChatLog};
+use super::schema::{channel_settings, chat_logs};
+use super::{Database, DatabaseError};
+
+const FUTURE_PARTITION: &str = "p_future";
+
+#[derive(QueryableByName)]
+struct Partition {
+    #[diesel(sql_type = Nullable<Text>)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PartitionChanges {
+    /// First days of the months
+    create: Vec<NaiveDate>,
+    drop: Vec<String>,
+}
+
+impl Database {
+    pub fn add_chat_log(
+        &self,
+        channel_id: u64,
+        user_id: u64,
+        text: &str,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::insert_into(chat_logs::table)
+            .values(NewChatLog {
+                channel_id,
+                user_id,
+                text,
+                sent_at: Utc::now().naive_utc(),
+            })
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn count_chat_logs(&self, channel_id: u64, user_id: u64) -> Result<i64, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(chat_logs::table
+            .filter(chat_logs::channel_id.eq(channel_id))
+            .filter(chat_logs::user_id.eq(user_id))
+            .count()
+            .get_result(&mut conn)?)
+    }
+
+    pub fn get_last_chat_log(
+        &self,
+        channel_id: u64,
+        user_id: u64,
+    ) -> Result<Option<ChatLog>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(chat_logs::table
+            .filter(chat_logs::channel_id.eq(channel_id))
+            .filter(chat_logs::user_id.eq(user_id))
+            .order_by(chat_logs::sent_at.desc())
+            .first(&mut conn)
+            .optional()?)
+    }
+
+    /// The oldest message that is still kept
+    pub fn get_first_chat_log(
+        &self,
+        channel_id: u64,
+        user_id: u64,
+    ) -> Result<Option<ChatLog>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(chat_logs::table
+            .filter(chat_logs::channel_id.eq(channel_id))
+            .filter(chat_logs::user_id.eq(user_id))
+            .order_by(chat_logs::sent_at.asc())
+            .first(&mut conn)
+            .optional()?)
+    }
+
+    /// Drops the partitions that are past the retention and creates the ones of the current
+    /// and the next month, then deletes the expired logs of channels with a shorter retention.
+    /// Returns the amount of dropped partitions and deleted messages
+    pub fn prune_chat_logs(&self, retention_months: u32) -> Result<(usize, usize), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let partitions: Vec<Partition> = sql_query(
+            "SELECT PARTITION_NAME AS name FROM information_schema.PARTITIONS \
+            WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = 'chat_logs'",
+        )
+        .load(&mut conn)?;
+        let partitions = partitions
+            .into_iter()
+            .filter_map(|partition| partition.name)
+            .collect::<Vec<_>>();
+
+        let now = Utc::now().naive_utc();
+        let changes = plan_partitions(&partitions, now.date(), retention_months);
+
+        if !changes.create.is_empty() {
+            let definitions = changes
+                .create
+                .iter()
+                .map(|month| {
+                    format!(
+                        "PARTITION {} VALUES LESS THAN ('{}')",
+                        partition_name(*month),
+                        next_month(*month)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            sql_query(format!(
+                "ALTER TABLE chat_logs REORGANIZE PARTITION {FUTURE_PARTITION} INTO \
+                ({definitions}, PARTITION {FUTURE_PARTITION} VALUES LESS THAN (MAXVALUE))"
+            ))
+            .execute(&mut conn)?;
+        }
+
+        if !changes.drop.is_empty() {
+            sql_query(format!(
+                "ALTER TABLE chat_logs DROP PARTITION {}",
+                changes.drop.join(", ")
+            ))
+            .execute(&mut conn)?;
+        }
+
+        let channel_days: Vec<(u64, String)> = channel_settings::table
+            .filter(channel_settings::name.eq(ChannelSetting::ChatLogDays.to_string()))
+            .select((channel_settings::channel_id, channel_settings::value))
+            .load(&mut conn)?;
+
+        let mut deleted = 0;
+        for (channel_id, days) in channel_days {
+            if let Ok(days) = days.parse::<i64>() {
+                deleted += diesel::delete(
+                    chat_logs::table
+                        .filter(chat_logs::channel_id.eq(channel_id))
+                        .filter(chat_logs::sent_at.lt(now - Duration::days(days))),
+                )
+                .execute(&mut conn)?;
+            }
+        }
+
+        Ok((changes.drop.len(), deleted))
+    }
+}
+
+/// Partitions are named after their month, such as `p202310`
+fn partition_name(month: NaiveDate) -> String {
+    month.format("p%Y%m").to_string()
+}
+
+fn parse_partition_month(name: &str) -> Option<NaiveDate> {
+    let month = name.strip_prefix('p')?;
+    NaiveDate::parse_from_str(&format!("{month}01"), "%Y%m%d").ok()
+}
+
+fn next_month(month: NaiveDate) -> NaiveDate {
+    month + Months::new(1)
+}
+
+/// Months can only be added after the newest existing partition. A retention of 0 keeps
+/// every partition
+fn plan_partitions(
+    existing: &[String],
+    today: NaiveDate,
+    retention_months: u32,
+) -> PartitionChanges {
+    let current_month = today.with_day(1).expect("Every month has a first day");
+    let months = existing
+        .iter()
+        .filter_map(|name| Some((name, parse_partition_month(name)?)))
+        .collect::<Vec<_>>();
+    let newest = months.iter().map(|(_, month)| *month).max();
+
+    let create = [current_month, next_month(current_month)]
+        .into_iter()
+        .filter(|month| newest.map_or(true, |newest| *month > newest))
+        .collect();
+
+    let drop = match retention_months {
+        0 => Vec::new(),
+        _ => {
+            let oldest_kept = current_month - Months::new(retention_months);
+            months
+                .into_iter()
+                .filter(|(_, month)| *month < oldest_kept)
+                .map(|(name, _)| name.clone())
+                .collect()
+        }
+    };
+
+    PartitionChanges { create, drop }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plan_partitions, PartitionChanges};
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+
+    fn date(year: i32, month: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, 1).unwrap()
+    }
+
+    #[test]
+    fn creates_missing_months() {
+        let changes = plan_partitions(&["p_future".to_owned()], date(2023, 12), 3);
+        assert_eq!(
+            changes,
+            PartitionChanges {
+                create: vec![date(2023, 12), date(2024, 1)],
+                drop: vec![],
+            }
+        );
+
+        let existing = ["p202312".to_owned(), "p_future".to_owned()];
+        let changes = plan_partitions(&existing, date(2023, 12), 3);
+        assert_eq!(changes.create, [date(2024, 1)]);
+    }
+
+    #[test]
+    fn drops_expired_months() {
+        let existing = [
+            "p202306", "p202307", "p202308", "p202309", "p202310", "p_future",
+        ]
+        .map(str::to_owned);
+
+        let changes = plan_partitions(&existing, date(2023, 10), 3);
+        assert_eq!(changes.drop, ["p202306"]);
+        assert_eq!(changes.create, [date(2023, 11)]);
+
+        let changes = plan_partitions(&existing, date(2023, 10), 0);
+        assert!(changes.drop.is_empty());
+    }
+}
@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use twitch_irc::login::{TokenStorage, UserAccessToken};
 
-use super::Database;
+use super::{Database, DatabaseError};
 
 #[derive(Debug)]
 pub struct Credentials {
@@ -22,30 +22,33 @@ impl TokenStorage for Credentials {
     type UpdateError = anyhow::Error;
 
     async fn load_token(&mut self) -> Result<UserAccessToken, Self::LoadError> {
-        let access_token = self
-            .db
-            .get_auth(&self.make_entry_name("twitch_access_token"))?
-            .unwrap_or_default();
-        let refresh_token = self
+        let entries = [
+            "twitch_access_token",
+            "twitch_refresh_token",
+            "twitch_created_at",
+            "twitch_expires_at",
+        ]
+        .map(|entry| self.make_entry_name(entry));
+
+        let [access_token, refresh_token, created_at, expires_at] = self
             .db
-            .get_auth(&self.make_entry_name("twitch_refresh_token"))?
-            .unwrap_or_default();
+            .run(move |db| {
+                let [access_token, refresh_token, created_at, expires_at] = &entries;
+                Ok::<_, DatabaseError>([
+                    db.get_auth(access_token)?,
+                    db.get_auth(refresh_token)?,
+                    db.get_auth(created_at)?,
+                    db.get_auth(expires_at)?,
+                ])
+            })
+            .await?;
 
         let created_at = DateTime::from_utc(
-            DateTime::parse_from_rfc3339(
-                &self
-                    .db
-                    .get_auth(&self.make_entry_name("twitch_created_at"))?
-                    .unwrap_or_default(),
-            )?
-            .naive_utc(),
+            DateTime::parse_from_rfc3339(&created_at.unwrap_or_default())?.naive_utc(),
             Utc,
         );
 
-        let expires_at = match self
-            .db
-            .get_auth(&self.make_entry_name("twitch_expires_at"))?
-        {
+        let expires_at = match expires_at {
             Some(date) => Some(DateTime::from_utc(
                 DateTime::parse_from_rfc3339(&date)?.naive_utc(),
                 Utc,
@@ -54,8 +57,8 @@ impl TokenStorage for Credentials {
         };
 
         Ok(UserAccessToken {
-            access_token,
-            refresh_token,
+            access_token: access_token.unwrap_or_default(),
+            refresh_token: refresh_token.unwrap_or_default(),
             created_at,
             expires_at,
         })
@@ -64,28 +67,37 @@ impl TokenStorage for Credentials {
     async fn update_token(&mut self, token: &UserAccessToken) -> Result<(), Self::UpdateError> {
         tracing::info!("Refreshed Twitch token for {}!", self.user_id);
 
-        self.db.set_auth(
-            &self.make_entry_name("twitch_access_token"),
-            &token.access_token,
-        )?;
-
-        self.db.set_auth(
-            &self.make_entry_name("twitch_refresh_token"),
-            &token.refresh_token,
-        )?;
-
-        self.db.set_auth(
-            &self.make_entry_name("twitch_created_at"),
-            &token.created_at.to_rfc3339(),
-        )?;
+        let mut entries = vec![
+            (
+                self.make_entry_name("twitch_access_token"),
+                token.access_token.clone(),
+            ),
+            (
+                self.make_entry_name("twitch_refresh_token"),
+                token.refresh_token.clone(),
+            ),
+            (
+                self.make_entry_name("twitch_created_at"),
+                token.created_at.to_rfc3339(),
+            ),
+        ];
 
         if let Some(expires_at) = token.expires_at {
-            self.db.set_auth(
-                &self.make_entry_name("twitch_expires_at"),
-                &expires_at.to_rfc3339(),
-            )?;
+            entries.push((
+                self.make_entry_name("twitch_expires_at"),
+                expires_at.to_rfc3339(),
+            ));
         }
 
+        self.db
+            .run(move |db| {
+                for (name, value) in entries {
+                    db.set_auth(&name, &value)?;
+                }
+                Ok::<_, DatabaseError>(())
+            })
+            .await?;
+
         Ok(())
     }
 }
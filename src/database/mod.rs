@@ -1,57 +1,94 @@
-use std::env;
+use std::collections::HashMap;
 use std::fmt::Display;
+use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use arc_swap::ArcSwap;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use dashmap::DashMap;
 use diesel::mysql::MysqlConnection;
-use diesel::r2d2::{self, ConnectionManager, Pool};
-use diesel::sql_types::{BigInt, Unsigned};
+use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+use diesel::result::DatabaseErrorKind;
+use diesel::sql_types::{BigInt, Nullable, Unsigned, Varchar};
 use diesel::{sql_query, EqAll, QueryDsl};
 use diesel::{ConnectionError, OptionalExtension};
 use diesel::{ExpressionMethods, RunQueryDsl};
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use passwords::PasswordGenerator;
 use reqwest::Client;
-use tokio::time;
+use sha2::{Digest, Sha256};
+use tokio::{task, time};
 use tracing::{error, instrument};
 use twitch_irc::login::{TokenStorage, UserAccessToken};
 
 use crate::command_handler::spotify_api::SpotifyApi;
+use crate::config::Config;
 use crate::database::schema::*;
 use crate::platform::{ChannelIdentifier, UserIdentifier, UserIdentifierError};
 
+use self::cache::TtlCache;
 use self::credentials::Credentials;
 use self::models::*;
+use self::pool::build_pool;
 
+pub mod cache;
+mod channel_features;
+mod chat_logs;
 pub mod credentials;
 pub mod models;
+mod points;
+mod pool;
 mod schema;
+mod shard_leases;
+mod template_data;
+mod template_partials;
+mod user_names;
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
 
-const BUILTIN_COMMANDS: &[&str] = &[
-    "ping", "commands", "cmd", "command", "addcmd", "debug", "delcmd", "merge", "showcmd",
-    "checkcmd",
-];
+diesel::sql_function!(fn last_insert_id() -> Unsigned<BigInt>);
+
+type DbConnection = PooledConnection<ConnectionManager<MysqlConnection>>;
+
+/// How often the connection pool utilization is reported
+const DB_POOL_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long cached users, channels and prefixes are kept before being reloaded
+const CACHE_TTL: Duration = Duration::from_secs(3600);
+/// Identifiers without a user are remembered for less time, as the user may register at any point
+const MISSING_USER_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Sessions are extended to this lifetime when they are used
+const WEB_SESSION_LIFETIME_DAYS: i64 = 30;
+
+const PASTE_LIFETIME_DAYS: i64 = 30;
 
 #[derive(Clone, Debug)]
 pub struct Database {
     conn_pool: Pool<ConnectionManager<MysqlConnection>>,
+    config: Arc<ArcSwap<Config>>,
     web_sessions_cache: Arc<DashMap<String, WebSession>>,
-    users_cache: Arc<DashMap<u64, User>>,
-    user_identifiers_cache: Arc<DashMap<UserIdentifier, u64>>, // Caches the user IDs
-    prefixes_cache: Arc<DashMap<u64, Option<String>>>,
+    users_cache: Arc<TtlCache<u64, User>>,
+    /// Caches the user IDs, `None` means that there is no user with this identifier
+    user_identifiers_cache: Arc<TtlCache<UserIdentifier, Option<u64>>>,
+    prefixes_cache: Arc<TtlCache<u64, Option<String>>>,
     // TODO: look into only caching channel IDs, not entire channels
-    channels_cache: Arc<DashMap<String, Channel>>,
+    channels_cache: Arc<TtlCache<String, Channel>>,
+    /// Feature flags that were set for the channel
+    channel_features_cache: Arc<TtlCache<u64, HashMap<ChannelFeature, bool>>>,
+    /// Partials of the channel, loaded whenever a template command is executed
+    template_partials_cache: Arc<TtlCache<u64, Vec<TemplatePartial>>>,
+    /// Set by the command handler once the builtin commands are created
+    builtin_command_names: Arc<ArcSwap<Vec<String>>>,
 }
 
 impl Database {
-    pub fn connect(database_url: String) -> Result<Self, ConnectionError> {
-        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
-        let conn_pool = r2d2::Pool::new(manager).expect("Failed to set up DB connection pool");
+    pub fn connect(config: Config) -> Result<Self, ConnectionError> {
+        let manager = ConnectionManager::<MysqlConnection>::new(&config.database.url);
+        let conn_pool =
+            build_pool(&config.database, manager).expect("Failed to set up DB connection pool");
 
         conn_pool
             .get()
@@ -60,110 +97,242 @@ impl Database {
             .expect("Failed to run migrations");
 
         let web_sessions_cache = Arc::new(DashMap::new());
-        let users_cache = Arc::new(DashMap::new());
-        let user_identifiers_cache = Arc::new(DashMap::new());
-        let prefixes_cache = Arc::new(DashMap::new());
-        let channels_cache = Arc::new(DashMap::new());
+        let users_cache = Arc::new(TtlCache::new(CACHE_TTL));
+        let user_identifiers_cache = Arc::new(TtlCache::new(CACHE_TTL));
+        let prefixes_cache = Arc::new(TtlCache::new(CACHE_TTL));
+        let channels_cache = Arc::new(TtlCache::new(CACHE_TTL));
+        let channel_features_cache = Arc::new(TtlCache::new(CACHE_TTL));
+        let template_partials_cache = Arc::new(TtlCache::new(CACHE_TTL));
 
         Ok(Self {
             conn_pool,
+            config: Arc::new(ArcSwap::from_pointee(config)),
             web_sessions_cache,
             users_cache,
             user_identifiers_cache,
             prefixes_cache,
             channels_cache,
+            channel_features_cache,
+            template_partials_cache,
+            builtin_command_names: Arc::new(ArcSwap::from_pointee(Vec::new())),
         })
     }
 
+    /// Runs blocking database work on tokio's blocking thread pool,
+    /// so that waiting for a connection or a query doesn't stall the async workers
+    pub async fn run<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&Database) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let db = self.clone();
+
+        task::spawn_blocking(move || f(&db))
+            .await
+            .expect("Database task panicked")
+    }
+
+    pub fn config(&self) -> Arc<Config> {
+        self.config.load_full()
+    }
+
+    /// Replaces the config that is returned by [`Database::config`]
+    pub fn set_config(&self, config: Config) {
+        self.config.store(Arc::new(config));
+    }
+
+    /// Time to check out a connection and run a trivial query
+    pub fn ping(&self) -> Result<Duration, DatabaseError> {
+        let started_at = Instant::now();
+
+        let mut conn = self.get_conn()?;
+        sql_query("SELECT 1").execute(&mut conn)?;
+
+        Ok(started_at.elapsed())
+    }
+
+    /// Connections in use and the pool size
+    pub fn get_pool_usage(&self) -> (u32, u32) {
+        let state = self.conn_pool.state();
+
+        (
+            state.connections - state.idle_connections,
+            self.conn_pool.max_size(),
+        )
+    }
+
+    pub fn get_cache_sizes(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("users", self.users_cache.len()),
+            ("user identifiers", self.user_identifiers_cache.len()),
+            ("channels", self.channels_cache.len()),
+            ("prefixes", self.prefixes_cache.len()),
+            ("channel features", self.channel_features_cache.len()),
+            ("template partials", self.template_partials_cache.len()),
+            ("web sessions", self.web_sessions_cache.len()),
+        ]
+    }
+
     pub fn start_cron(&self) {
-        let web_sessions_cache = self.web_sessions_cache.clone();
-        let users_cache = self.users_cache.clone();
-        let user_identifiers_cache = self.user_identifiers_cache.clone();
+        let conn_pool = self.conn_pool.clone();
+
+        tokio::spawn(async move {
+            loop {
+                time::sleep(DB_POOL_REPORT_INTERVAL).await;
+
+                let state = conn_pool.state();
+                tracing::info!(
+                    connections = state.connections,
+                    idle_connections = state.idle_connections,
+                    max_size = conn_pool.max_size(),
+                    "DB pool utilization"
+                );
+            }
+        });
+
+        let db = self.clone();
 
         tokio::spawn(async move {
             loop {
                 time::sleep(Duration::from_secs(3600)).await;
 
-                tracing::info!("Clearing caches");
+                tracing::info!("Removing expired cache entries");
+
+                db.web_sessions_cache.clear();
+                db.users_cache.remove_expired();
+                db.user_identifiers_cache.remove_expired();
+                db.prefixes_cache.remove_expired();
+                db.channels_cache.remove_expired();
+                db.channel_features_cache.remove_expired();
+                db.template_partials_cache.remove_expired();
+
+                match db.remove_expired_web_sessions() {
+                    Ok(count) => tracing::info!("Removed {count} expired web sessions"),
+                    Err(e) => error!("Failed to remove expired web sessions: {e}"),
+                }
+
+                match db.remove_expired_pastes() {
+                    Ok(count) => tracing::info!("Removed {count} expired pastes"),
+                    Err(e) => error!("Failed to remove expired pastes: {e}"),
+                }
+
+                match db.remove_expired_template_data() {
+                    Ok(count) => tracing::info!("Removed {count} expired template data values"),
+                    Err(e) => error!("Failed to remove expired template data: {e}"),
+                }
+
+                match db.remove_expired_shard_leases() {
+                    Ok(count) => tracing::info!("Removed {count} expired shard leases"),
+                    Err(e) => error!("Failed to remove expired shard leases: {e}"),
+                }
 
-                web_sessions_cache.clear();
-                users_cache.clear();
-                user_identifiers_cache.clear();
+                match db.prune_chat_logs(db.config().chat_logs.retention_months) {
+                    Ok((partitions, messages)) => tracing::info!(
+                        "Dropped {partitions} chat log partitions and {messages} expired messages"
+                    ),
+                    Err(e) => error!("Failed to prune chat logs: {e}"),
+                }
             }
         });
 
-        {
-            let conn_pool = self.conn_pool.clone();
-
-            if let Ok(client_id) = env::var("SPOTIFY_CLIENT_ID") {
-                if let Ok(client_secret) = env::var("SPOTIFY_CLIENT_SECRET") {
-                    tokio::spawn(async move {
-                        loop {
-                            let mut conn = conn_pool.get().unwrap();
-
-                            let refresh_tokens = user_data::table
-                                .select((user_data::user_id, user_data::value))
-                                .filter(user_data::name.eq_all("spotify_refresh_token"))
-                                .load::<(u64, String)>(&mut conn)
-                                .expect("DB Error");
-
-                            if refresh_tokens.is_empty() {
-                                tracing::info!("Updating Spotify tokens...");
-                            }
-
-                            let mut refresh_in = None;
-
-                            let client = Client::new();
-
-                            for (user_id, refresh_token) in refresh_tokens {
-                                match SpotifyApi::update_token(
-                                    &client,
-                                    &client_id,
-                                    &client_secret,
-                                    &refresh_token,
-                                )
-                                .await
-                                {
-                                    Ok((access_token, expiration_time)) => {
-                                        tracing::info!(
-                                            "Refreshed Spotify token for user {}",
-                                            user_id
-                                        );
-
-                                        diesel::update(
-                                            user_data::table
-                                                .filter(
-                                                    user_data::name.eq_all("spotify_access_token"),
-                                                )
-                                                .filter(user_data::user_id.eq_all(user_id)),
-                                        )
-                                        .set(user_data::value.eq_all(access_token))
-                                        .execute(&mut conn)
-                                        .expect("DB Error");
-
-                                        if refresh_in.is_none() {
-                                            refresh_in = Some(expiration_time);
-                                        }
-                                    }
-                                    Err(e) => {
-                                        tracing::warn!("Error refreshing Spotify token: {e:#}");
-                                    }
-                                }
-                            }
-
-                            time::sleep(Duration::from_secs(
-                                refresh_in.unwrap_or(3600).try_into().unwrap(),
-                            ))
-                            .await;
-                        }
-                    });
+        let config = self.config();
+
+        if let Some((client_id, client_secret)) = config.spotify.credentials() {
+            let db = self.clone();
+            let client_id = client_id.to_owned();
+            let client_secret = client_secret.to_owned();
+
+            tokio::spawn(async move {
+                let client = Client::new();
+
+                loop {
+                    let refresh_in = db
+                        .refresh_spotify_tokens(&client, &client_id, &client_secret)
+                        .await
+                        .unwrap_or_else(|e| {
+                            error!("Could not refresh Spotify tokens: {e}");
+                            None
+                        });
+
+                    time::sleep(Duration::from_secs(
+                        refresh_in.unwrap_or(3600).try_into().unwrap_or(3600),
+                    ))
+                    .await;
+                }
+            });
+        }
+    }
+
+    /// Returns the time in seconds until the earliest refreshed token expires
+    async fn refresh_spotify_tokens(
+        &self,
+        client: &Client,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Result<Option<i64>, DatabaseError> {
+        let refresh_tokens = {
+            let mut conn = self.get_conn()?;
+
+            user_data::table
+                .select((user_data::user_id, user_data::value))
+                .filter(user_data::name.eq_all("spotify_refresh_token"))
+                .load::<(u64, String)>(&mut conn)?
+        };
+
+        if !refresh_tokens.is_empty() {
+            tracing::info!("Updating Spotify tokens...");
+        }
+
+        let mut refresh_in = None;
+
+        for (user_id, refresh_token) in refresh_tokens {
+            match SpotifyApi::update_token(client, client_id, client_secret, &refresh_token).await {
+                Ok((access_token, expiration_time)) => {
+                    tracing::info!("Refreshed Spotify token for user {}", user_id);
+
+                    let mut conn = self.get_conn()?;
+
+                    diesel::update(
+                        user_data::table
+                            .filter(user_data::name.eq_all("spotify_access_token"))
+                            .filter(user_data::user_id.eq_all(user_id)),
+                    )
+                    .set(user_data::value.eq_all(access_token))
+                    .execute(&mut conn)?;
+
+                    if refresh_in.is_none() {
+                        refresh_in = Some(expiration_time);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Error refreshing Spotify token: {e:#}");
                 }
             }
         }
+
+        Ok(refresh_in)
+    }
+
+    /// Waits up to the pool timeout for a free connection, so that a spike in load
+    /// fails the query instead of the whole bot
+    fn get_conn(&self) -> Result<DbConnection, diesel::result::Error> {
+        self.conn_pool.get().map_err(|e| {
+            let state = self.conn_pool.state();
+            error!(
+                connections = state.connections,
+                idle_connections = state.idle_connections,
+                "Could not get a DB connection: {e}"
+            );
+
+            diesel::result::Error::DatabaseError(
+                DatabaseErrorKind::UnableToSendCommand,
+                Box::new(e.to_string()),
+            )
+        })
     }
 
     pub fn get_channels(&self) -> Result<Vec<Channel>, diesel::result::Error> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         channels::table.order(channels::id).load(&mut conn)
     }
@@ -172,12 +341,12 @@ impl Database {
         &self,
         channel_identifier: &ChannelIdentifier,
     ) -> Result<Option<Channel>, diesel::result::Error> {
-        let mut conn = self.conn_pool.get().unwrap();
-
         if let Some(channel) = channel_identifier.get_channel() {
             if let Some(channel) = self.channels_cache.get(&channel_identifier.to_string()) {
-                Ok(Some(channel.value().clone()))
+                Ok(Some(channel))
             } else {
+                let mut conn = self.get_conn()?;
+
                 let channel = channels::table
                     .filter(
                         channels::platform.eq_all(channel_identifier.get_platform_name().unwrap()),
@@ -202,31 +371,22 @@ impl Database {
         &self,
         channel_identifier: &ChannelIdentifier,
     ) -> Result<Option<Channel>, diesel::result::Error> {
-        let mut conn = self.conn_pool.get().unwrap();
-
-        let query = channels::table.into_boxed();
-
         if let Some(platform) = channel_identifier.get_platform_name() {
-            let channel = channel_identifier.get_channel().unwrap_or_default();
-            match query
-                .filter(channels::platform.eq_all(platform))
-                .filter(channels::channel.eq_all(channel))
-                .first(&mut conn)
-                .optional()?
-            {
+            match self.get_channel(channel_identifier)? {
                 Some(channel) => Ok(Some(channel)),
                 None => {
+                    let mut conn = self.get_conn()?;
+
                     let new_channel = NewChannel {
-                        platform: channel_identifier.get_platform_name().unwrap(),
-                        channel,
+                        platform,
+                        channel: channel_identifier.get_channel().unwrap_or_default(),
                     };
 
                     diesel::insert_into(channels::table)
                         .values(new_channel)
-                        .execute(&mut conn)
-                        .expect("Failed to create channel");
+                        .execute(&mut conn)?;
 
-                    self.get_or_create_channel(channel_identifier)
+                    self.get_channel(channel_identifier)
                 }
             }
         } else {
@@ -235,13 +395,9 @@ impl Database {
     }
 
     pub fn get_admin_user(&self) -> Result<Option<User>, DatabaseError> {
-        match env::var("ADMIN_USER") {
-            Ok(s) => {
-                let admin_identifier = UserIdentifier::from_string(&s)?;
-
-                Ok(self.get_user(&admin_identifier)?)
-            }
-            Err(_) => Ok(None),
+        match self.config().admin_user() {
+            Some(admin_identifier) => Ok(self.get_user(&admin_identifier)?),
+            None => Ok(None),
         }
     }
 
@@ -249,7 +405,7 @@ impl Database {
         &self,
         channel_id: u64,
     ) -> Result<Option<Channel>, diesel::result::Error> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         channels::table
             .filter(channels::id.eq_all(channel_id))
@@ -258,18 +414,153 @@ impl Database {
     }
 
     pub fn get_channels_amount(&self) -> Result<i64, diesel::result::Error> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         channels::table.count().get_result(&mut conn)
     }
 
+    pub fn get_users_amount(&self) -> Result<i64, diesel::result::Error> {
+        let mut conn = self.get_conn()?;
+
+        users::table.count().get_result(&mut conn)
+    }
+
+    pub fn get_commands_amount(&self) -> Result<i64, diesel::result::Error> {
+        let mut conn = self.get_conn()?;
+
+        commands::table.count().get_result(&mut conn)
+    }
+
+    pub fn set_channel_joined(&self, channel_id: u64, joined: bool) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::update(channels::table.filter(channels::id.eq(channel_id)))
+            .set(channels::joined.eq(joined))
+            .execute(&mut conn)?;
+
+        self.channels_cache
+            .retain(|_, channel| channel.id != channel_id);
+
+        Ok(())
+    }
+
+    pub fn get_blocked_users(&self) -> Result<Vec<BlockedUser>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(blocked_users::table.load(&mut conn)?)
+    }
+
+    /// A block without a channel applies to all channels
+    pub fn add_blocked_user(
+        &self,
+        identifier: &UserIdentifier,
+        channel_id: Option<u64>,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+        let identifier = identifier.to_string();
+
+        // A single statement, so that concurrent blocks of the same user don't both insert.
+        // `<=>` also matches the NULL channel of global blocks
+        sql_query(
+            "INSERT INTO blocked_users(identifier, channel_id) SELECT ?, ? FROM DUAL \
+            WHERE NOT EXISTS (SELECT 1 FROM blocked_users WHERE identifier = ? AND channel_id <=> ?)",
+        )
+        .bind::<Varchar, _>(&identifier)
+        .bind::<Nullable<Unsigned<BigInt>>, _>(channel_id)
+        .bind::<Varchar, _>(&identifier)
+        .bind::<Nullable<Unsigned<BigInt>>, _>(channel_id)
+        .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn remove_blocked_user(
+        &self,
+        identifier: &UserIdentifier,
+        channel_id: Option<u64>,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let query =
+            blocked_users::table.filter(blocked_users::identifier.eq(identifier.to_string()));
+
+        match channel_id {
+            Some(channel_id) => {
+                diesel::delete(query.filter(blocked_users::channel_id.eq(channel_id)))
+                    .execute(&mut conn)?
+            }
+            None => diesel::delete(query.filter(blocked_users::channel_id.is_null()))
+                .execute(&mut conn)?,
+        };
+
+        Ok(())
+    }
+
+    pub fn get_ignored_users(&self, channel_id: u64) -> Result<Vec<UserIdentifier>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let identifiers: Vec<String> = ignored_users::table
+            .filter(ignored_users::channel_id.eq(channel_id))
+            .select(ignored_users::identifier)
+            .load(&mut conn)?;
+
+        Ok(identifiers
+            .iter()
+            .map(|identifier| UserIdentifier::from_string(identifier))
+            .collect::<Result<_, _>>()?)
+    }
+
+    pub fn add_ignored_user(
+        &self,
+        channel_id: u64,
+        identifier: &UserIdentifier,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::replace_into(ignored_users::table)
+            .values((
+                ignored_users::channel_id.eq(channel_id),
+                ignored_users::identifier.eq(identifier.to_string()),
+            ))
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn remove_ignored_user(
+        &self,
+        channel_id: u64,
+        identifier: &UserIdentifier,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::delete(
+            ignored_users::table
+                .filter(ignored_users::channel_id.eq(channel_id))
+                .filter(ignored_users::identifier.eq(identifier.to_string())),
+        )
+        .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn clear_caches(&self) {
+        self.web_sessions_cache.clear();
+        self.users_cache.clear();
+        self.user_identifiers_cache.clear();
+        self.prefixes_cache.clear();
+        self.channels_cache.clear();
+        self.channel_features_cache.clear();
+        self.template_partials_cache.clear();
+    }
+
     #[instrument]
     pub fn get_command(
         &self,
         channel_identifier: &ChannelIdentifier,
         command: &str,
     ) -> Result<Option<Command>, DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         match channel_identifier.get_channel() {
             Some(channel) => Ok(commands::table
@@ -292,13 +583,78 @@ impl Database {
     }
 
     pub fn get_commands(&self, channel_id: u64) -> Result<Vec<Command>, diesel::result::Error> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         commands::table
             .filter(commands::channel_id.eq_all(channel_id))
             .load::<Command>(&mut conn)
     }
 
+    pub fn get_command_tests(
+        &self,
+        channel_id: u64,
+        command_name: &str,
+    ) -> Result<Vec<CommandTest>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let tests = command_tests::table
+            .filter(command_tests::channel_id.eq(channel_id))
+            .filter(command_tests::command_name.eq(command_name))
+            .order(command_tests::id)
+            .load(&mut conn)?;
+
+        Ok(tests)
+    }
+
+    pub fn add_command_test(
+        &self,
+        channel_id: u64,
+        command_name: &str,
+        args: &str,
+        expected: &[String],
+    ) -> Result<CommandTest, DatabaseError> {
+        let mut conn = self.get_conn()?;
+        let expected = expected.join("\n");
+
+        diesel::insert_into(command_tests::table)
+            .values(NewCommandTest {
+                channel_id,
+                command_name,
+                args,
+                expected: &expected,
+            })
+            .execute(&mut conn)?;
+
+        let id = diesel::select(last_insert_id()).get_result::<u64>(&mut conn)?;
+        let test = command_tests::table
+            .filter(command_tests::id.eq(id))
+            .first(&mut conn)?;
+
+        Ok(test)
+    }
+
+    pub fn delete_command_test(
+        &self,
+        channel_id: u64,
+        command_name: &str,
+        id: u64,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let affected = diesel::delete(
+            command_tests::table
+                .filter(command_tests::channel_id.eq(channel_id))
+                .filter(command_tests::command_name.eq(command_name))
+                .filter(command_tests::id.eq(id)),
+        )
+        .execute(&mut conn)?;
+
+        match affected {
+            0 => Err(DatabaseError::InvalidValue),
+            _ => Ok(()),
+        }
+    }
+
     pub fn add_command_to_channel(
         &self,
         channel_identifier: &ChannelIdentifier,
@@ -317,18 +673,48 @@ impl Database {
     }
 
     fn add_command(&self, command: NewCommand) -> Result<(), DatabaseError> {
-        match BUILTIN_COMMANDS.contains(&command.name) {
-            false => {
-                let mut conn = self.conn_pool.get().unwrap();
+        if self.is_builtin_command_name(command.name) {
+            return Err(DatabaseError::BuiltinCommandName(command.name.to_owned()));
+        }
 
-                diesel::insert_into(commands::table)
-                    .values(&command)
-                    .execute(&mut conn)?;
+        let mut conn = self.get_conn()?;
 
-                Ok(())
-            }
-            true => Err(DatabaseError::InvalidValue),
-        }
+        diesel::insert_into(commands::table)
+            .values(&command)
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// Custom and global commands can't be named like a builtin command, as the builtin one
+    /// would always be executed instead
+    pub fn set_builtin_command_names(&self, names: Vec<String>) {
+        self.builtin_command_names.store(Arc::new(names));
+    }
+
+    pub fn is_builtin_command_name(&self, name: &str) -> bool {
+        self.builtin_command_names
+            .load()
+            .iter()
+            .any(|builtin_name| builtin_name == name)
+    }
+
+    /// Custom and global commands that were created before a builtin command with the same name
+    /// was added, which are never executed
+    pub fn get_builtin_command_conflicts(
+        &self,
+    ) -> Result<(Vec<Command>, Vec<GlobalCommand>), DatabaseError> {
+        let names = self.builtin_command_names.load_full();
+        let mut conn = self.get_conn()?;
+
+        let channel_commands = commands::table
+            .filter(commands::name.eq_any(names.iter()))
+            .load(&mut conn)?;
+        let global_commands = global_commands::table
+            .filter(global_commands::name.eq_any(names.iter()))
+            .load(&mut conn)?;
+
+        Ok((channel_commands, global_commands))
     }
 
     pub fn update_command_action(
@@ -337,7 +723,7 @@ impl Database {
         command_name: &str,
         new_action: &str,
     ) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
         let channel = self
             .get_channel(channel_identifier)?
             .ok_or(DatabaseError::InvalidValue)?;
@@ -359,7 +745,7 @@ impl Database {
         command_name: &str,
         mode: CommandMode,
     ) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
         let channel = self
             .get_channel(channel_identifier)?
             .ok_or(DatabaseError::InvalidValue)?;
@@ -386,7 +772,7 @@ impl Database {
     }
 
     pub fn delete_command(&self, channel_id: u64, command_name: &str) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         let affected = diesel::delete(
             commands::table
@@ -406,9 +792,10 @@ impl Database {
         user_identifier: &UserIdentifier,
     ) -> Result<Option<User>, diesel::result::Error> {
         match self.user_identifiers_cache.get(user_identifier) {
-            Some(id) => self.get_user_by_id(*id),
+            Some(Some(id)) => self.get_user_by_id(id),
+            Some(None) => Ok(None),
             None => {
-                let mut conn = self.conn_pool.get().unwrap();
+                let mut conn = self.get_conn()?;
 
                 let query = users::table.into_boxed();
 
@@ -419,31 +806,68 @@ impl Database {
                     UserIdentifier::DiscordID(user_id) => {
                         query.filter(users::discord_id.eq(Some(user_id)))
                     }
+                    UserIdentifier::GithubId(user_id) => {
+                        query.filter(users::github_id.eq(Some(user_id)))
+                    }
+                    UserIdentifier::GoogleId(user_id) => {
+                        query.filter(users::google_id.eq(Some(user_id)))
+                    }
                     UserIdentifier::TelegramId(id) => {
                         query.filter(users::telegram_id.eq(Some(id.to_string())))
                     }
                     UserIdentifier::IrcName(name) => query.filter(users::irc_name.eq(Some(name))),
-                    UserIdentifier::IpAddr(addr) => {
-                        query.filter(users::local_addr.eq(Some(addr.to_string())))
+                    UserIdentifier::LocalClient(name) => {
+                        query.filter(users::local_addr.eq(Some(name)))
                     }
-                    UserIdentifier::MatrixId(mxid) => query.filter(users::matrix_id.eq(Some(mxid))),
+                    UserIdentifier::Connector(platform, connector_user_id) => query.filter(
+                        users::id.eq_any(
+                            connector_users::table
+                                .filter(connector_users::platform.eq(platform))
+                                .filter(connector_users::connector_user_id.eq(connector_user_id))
+                                .select(connector_users::user_id),
+                        ),
+                    ),
                 };
 
-                Ok(query.first::<User>(&mut conn).optional()?.map(|user| {
-                    self.user_identifiers_cache
-                        .insert(user_identifier.clone(), user.id);
+                let user = query.first::<User>(&mut conn).optional()?;
 
-                    user
-                }))
+                match &user {
+                    Some(user) => self
+                        .user_identifiers_cache
+                        .insert(user_identifier.clone(), Some(user.id)),
+                    None => self.user_identifiers_cache.insert_with_ttl(
+                        user_identifier.clone(),
+                        None,
+                        MISSING_USER_CACHE_TTL,
+                    ),
+                }
+
+                Ok(user)
             }
         }
     }
 
+    /// The id of the user on a platform provided by a connector
+    pub fn get_connector_user_id(
+        &self,
+        user_id: u64,
+        platform: &str,
+    ) -> Result<Option<String>, diesel::result::Error> {
+        let mut conn = self.get_conn()?;
+
+        connector_users::table
+            .filter(connector_users::user_id.eq(user_id))
+            .filter(connector_users::platform.eq(platform))
+            .select(connector_users::connector_user_id)
+            .first(&mut conn)
+            .optional()
+    }
+
     pub fn get_user_by_id(&self, user_id: u64) -> Result<Option<User>, diesel::result::Error> {
         match self.users_cache.get(&user_id) {
-            Some(user) => Ok(Some(user.clone())),
+            Some(user) => Ok(Some(user)),
             None => {
-                let mut conn = self.conn_pool.get().unwrap();
+                let mut conn = self.get_conn()?;
 
                 match users::table
                     .filter(users::id.eq_all(user_id))
@@ -467,11 +891,11 @@ impl Database {
         &self,
         user_identifier: &UserIdentifier,
     ) -> Result<User, diesel::result::Error> {
-        let mut conn = self.conn_pool.get().unwrap();
-
         match self.get_user(user_identifier)? {
             Some(user) => Ok(user),
             None => {
+                let mut conn = self.get_conn()?;
+
                 let new_user = match &user_identifier {
                     UserIdentifier::TwitchID(user_id) => NewUser {
                         twitch_id: Some(user_id),
@@ -481,61 +905,147 @@ impl Database {
                         discord_id: Some(user_id),
                         ..Default::default()
                     },
+                    UserIdentifier::GithubId(user_id) => NewUser {
+                        github_id: Some(user_id),
+                        ..Default::default()
+                    },
+                    UserIdentifier::GoogleId(user_id) => NewUser {
+                        google_id: Some(user_id),
+                        ..Default::default()
+                    },
                     UserIdentifier::IrcName(name) => NewUser {
                         irc_name: Some(name),
                         ..Default::default()
                     },
-                    UserIdentifier::IpAddr(addr) => NewUser {
-                        local_addr: Some(addr.to_string()),
+                    UserIdentifier::LocalClient(name) => NewUser {
+                        local_addr: Some(name),
                         ..Default::default()
                     },
                     UserIdentifier::TelegramId(id) => NewUser {
                         telegram_id: Some(id.to_string()),
                         ..Default::default()
                     },
-                    UserIdentifier::MatrixId(mxid) => NewUser {
-                        matrix_id: Some(mxid),
-                        ..Default::default()
-                    },
+                    // Connector users are linked in a separate table
+                    UserIdentifier::Connector(_, _) => NewUser::default(),
                 };
 
                 diesel::insert_into(users::table)
                     .values(new_user)
-                    .execute(&mut conn)
-                    .expect("Failed to save new user");
+                    .execute(&mut conn)?;
 
-                Ok(self.get_user(user_identifier)?.unwrap())
+                if let UserIdentifier::Connector(platform, connector_user_id) = user_identifier {
+                    let user_id = diesel::select(last_insert_id()).get_result::<u64>(&mut conn)?;
+
+                    diesel::insert_into(connector_users::table)
+                        .values((
+                            connector_users::platform.eq(platform),
+                            connector_users::connector_user_id.eq(connector_user_id),
+                            connector_users::user_id.eq(user_id),
+                        ))
+                        .execute(&mut conn)?;
+                }
+
+                self.user_identifiers_cache.remove(user_identifier);
+
+                self.get_user(user_identifier)?
+                    .ok_or(diesel::result::Error::NotFound)
             }
         }
     }
 
-    pub fn merge_users(&self, mut user: User, other: User) -> User {
-        let mut conn = self.conn_pool.get().unwrap();
+    pub fn merge_users(&self, mut user: User, other: User) -> Result<User, diesel::result::Error> {
+        let mut conn = self.get_conn()?;
 
-        self.users_cache.remove(&other.id);
+        let other_id = other.id;
+        self.users_cache.remove(&other_id);
 
-        sql_query("REPLACE INTO user_data(user_id, name, value) SELECT ?, name, value FROM user_data WHERE user_id = ?").bind::<Unsigned<BigInt>, _>(user.id).bind::<Unsigned<BigInt>, _>(other.id).execute(&mut conn).expect("Failed to run replace query");
+        sql_query("REPLACE INTO user_data(user_id, name, value) SELECT ?, name, value FROM user_data WHERE user_id = ?").bind::<Unsigned<BigInt>, _>(user.id).bind::<Unsigned<BigInt>, _>(other.id).execute(&mut conn)?;
+
+        diesel::update(connector_users::table.filter(connector_users::user_id.eq(other.id)))
+            .set(connector_users::user_id.eq(user.id))
+            .execute(&mut conn)?;
+
+        // Names that both users have been seen with are left to be deleted with the other user
+        sql_query("UPDATE IGNORE user_names SET user_id = ? WHERE user_id = ?")
+            .bind::<Unsigned<BigInt>, _>(user.id)
+            .bind::<Unsigned<BigInt>, _>(other.id)
+            .execute(&mut conn)?;
 
-        diesel::delete(&other)
-            .execute(&mut conn)
-            .expect("Failed to delete");
+        diesel::delete(&other).execute(&mut conn)?;
 
         user.merge(other);
 
         diesel::update(users::table.filter(users::id.eq_all(user.id)))
             .set(&user)
-            .execute(&mut conn)
-            .expect("Failed to update");
+            .execute(&mut conn)?;
 
         self.users_cache.remove(&user.id);
 
-        self.user_identifiers_cache.clear();
+        // Identifiers of the other user now belong to the merged user
+        self.user_identifiers_cache
+            .retain(|_, id| *id != Some(other_id));
+
+        Ok(user)
+    }
+
+    pub fn add_user_merge(&self, merge: NewUserMerge) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::insert_into(user_merges::table)
+            .values(merge)
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// Removes the platform id from the user row, the caller is responsible for checking
+    /// that the user is left with an identity to log in with
+    pub fn unlink_user_identity(
+        &self,
+        user: &User,
+        identity: UserIdentity,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let query = diesel::update(users::table.filter(users::id.eq(user.id)));
+
+        let user_identifier = match identity {
+            UserIdentity::Twitch => {
+                query
+                    .set(users::twitch_id.eq(None::<String>))
+                    .execute(&mut conn)?;
+                user.twitch_id.clone().map(UserIdentifier::TwitchID)
+            }
+            UserIdentity::Discord => {
+                query
+                    .set(users::discord_id.eq(None::<String>))
+                    .execute(&mut conn)?;
+                user.discord_id.clone().map(UserIdentifier::DiscordID)
+            }
+            UserIdentity::Github => {
+                query
+                    .set(users::github_id.eq(None::<String>))
+                    .execute(&mut conn)?;
+                user.github_id.clone().map(UserIdentifier::GithubId)
+            }
+            UserIdentity::Google => {
+                query
+                    .set(users::google_id.eq(None::<String>))
+                    .execute(&mut conn)?;
+                user.google_id.clone().map(UserIdentifier::GoogleId)
+            }
+        };
 
-        user
+        if let Some(user_identifier) = user_identifier {
+            self.user_identifiers_cache.remove(&user_identifier);
+        }
+        self.users_cache.remove(&user.id);
+
+        Ok(())
     }
 
     pub fn get_auth(&self, key: &str) -> Result<Option<String>, DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         Ok(auth::table
             .filter(auth::name.eq_all(key))
@@ -546,7 +1056,7 @@ impl Database {
     }
 
     pub fn set_auth(&self, key: &str, value: &str) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         tracing::debug!("Setting auth: {} - {}", key, value);
 
@@ -562,7 +1072,7 @@ impl Database {
         user_id: u64,
         key: &str,
     ) -> Result<Option<String>, diesel::result::Error> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         user_data::table
             .filter(user_data::user_id.eq_all(user_id))
@@ -576,7 +1086,7 @@ impl Database {
         &self,
         id: &str,
     ) -> Result<Option<EventSubTrigger>, diesel::result::Error> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         eventsub_triggers::table
             .filter(eventsub_triggers::id.eq_all(id))
@@ -585,7 +1095,7 @@ impl Database {
     }
 
     pub fn get_eventsub_triggers(&self) -> Result<Vec<EventSubTrigger>, DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         Ok(eventsub_triggers::table.load(&mut conn)?)
     }
@@ -594,10 +1104,11 @@ impl Database {
         &self,
         broadcaster_id: &str,
     ) -> Result<Vec<EventSubTrigger>, DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         Ok(eventsub_triggers::table
             .filter(eventsub_triggers::broadcaster_id.eq(broadcaster_id))
+            .order((eventsub_triggers::event_type, eventsub_triggers::id))
             .load(&mut conn)?)
     }
     pub fn set_user_data(
@@ -605,7 +1116,7 @@ impl Database {
         user_data: &UserData,
         overwrite: bool,
     ) -> Result<(), diesel::result::Error> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         match overwrite {
             true => diesel::replace_into(user_data::table)
@@ -620,7 +1131,7 @@ impl Database {
     }
 
     pub fn remove_user_data(&self, user_id: u64, data: &str) -> Result<(), diesel::result::Error> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         diesel::delete(
             user_data::table
@@ -643,15 +1154,15 @@ impl Database {
         self.get_user_data_value(user_id, "location")
     }
 
-    pub fn get_lastfm_name(&self, user_id: u64) -> Result<Option<String>, DatabaseError> {
-        Ok(self.get_user_data_value(user_id, "lastfm_name")?)
+    pub fn get_timezone(&self, user_id: u64) -> Result<Option<String>, DatabaseError> {
+        Ok(self.get_user_data_value(user_id, "timezone")?)
     }
 
-    pub fn set_lastfm_name(&self, user_id: u64, name: &str) -> Result<(), DatabaseError> {
+    pub fn set_timezone(&self, user_id: u64, timezone: &str) -> Result<(), DatabaseError> {
         Ok(self.set_user_data(
             &UserData {
-                name: "lastfm_name".to_string(),
-                value: name.to_string(),
+                name: "timezone".to_string(),
+                value: timezone.to_string(),
                 public: true,
                 user_id,
             },
@@ -659,16 +1170,183 @@ impl Database {
         )?)
     }
 
-    pub fn get_web_session(
-        &self,
-        session_id: &str,
-    ) -> Result<Option<WebSession>, diesel::result::Error> {
-        match self.web_sessions_cache.get(session_id) {
-            Some(session) => Ok(Some(session.clone())),
-            None => {
-                let mut conn = self.conn_pool.get().unwrap();
+    /// `metric` or `imperial`
+    pub fn get_units(&self, user_id: u64) -> Result<Option<String>, DatabaseError> {
+        Ok(self.get_user_data_value(user_id, "units")?)
+    }
 
-                match web_sessions::table
+    pub fn set_units(&self, user_id: u64, units: &str) -> Result<(), DatabaseError> {
+        Ok(self.set_user_data(
+            &UserData {
+                name: "units".to_string(),
+                value: units.to_string(),
+                public: true,
+                user_id,
+            },
+            true,
+        )?)
+    }
+
+    /// The user's ID in Supibot's database, which is used by the Supinic API
+    pub fn get_supibot_id(&self, user_id: u64) -> Result<Option<u64>, DatabaseError> {
+        Ok(self
+            .get_user_data_value(user_id, "supibot_id")?
+            .and_then(|id| id.parse().ok()))
+    }
+
+    pub fn set_supibot_id(&self, user_id: u64, supibot_id: u64) -> Result<(), DatabaseError> {
+        Ok(self.set_user_data(
+            &UserData {
+                name: "supibot_id".to_string(),
+                value: supibot_id.to_string(),
+                public: true,
+                user_id,
+            },
+            true,
+        )?)
+    }
+
+    /// Stock symbols that the user follows with the `portfolio` command
+    pub fn get_watchlist(&self, user_id: u64) -> Result<Vec<String>, DatabaseError> {
+        let value = self.get_user_data_value(user_id, "watchlist")?;
+
+        Ok(value
+            .map(|value| value.split(',').map(str::to_owned).collect())
+            .unwrap_or_default())
+    }
+
+    /// An empty watchlist is removed
+    pub fn set_watchlist(&self, user_id: u64, symbols: &[String]) -> Result<(), DatabaseError> {
+        if symbols.is_empty() {
+            return Ok(self.remove_user_data(user_id, "watchlist")?);
+        }
+
+        Ok(self.set_user_data(
+            &UserData {
+                name: "watchlist".to_string(),
+                value: symbols.join(","),
+                public: true,
+                user_id,
+            },
+            true,
+        )?)
+    }
+
+    /// Users that opted out of markov don't have their messages recorded in any channel
+    pub fn get_markov_opt_out(&self, user_id: u64) -> Result<bool, DatabaseError> {
+        Ok(self
+            .get_user_data_value(user_id, "markov_opt_out")?
+            .map_or(false, |value| value == "true"))
+    }
+
+    pub fn set_markov_opt_out(&self, user_id: u64, opt_out: bool) -> Result<(), DatabaseError> {
+        if !opt_out {
+            return Ok(self.remove_user_data(user_id, "markov_opt_out")?);
+        }
+
+        Ok(self.set_user_data(
+            &UserData {
+                name: "markov_opt_out".to_string(),
+                value: "true".to_string(),
+                public: false,
+                user_id,
+            },
+            true,
+        )?)
+    }
+
+    /// Users that opted out of `lastseen` and `firstseen` can't be looked up with them
+    pub fn get_seen_opt_out(&self, user_id: u64) -> Result<bool, DatabaseError> {
+        Ok(self
+            .get_user_data_value(user_id, "seen_opt_out")?
+            .map_or(false, |value| value == "true"))
+    }
+
+    pub fn set_seen_opt_out(&self, user_id: u64, opt_out: bool) -> Result<(), DatabaseError> {
+        if !opt_out {
+            return Ok(self.remove_user_data(user_id, "seen_opt_out")?);
+        }
+
+        Ok(self.set_user_data(
+            &UserData {
+                name: "seen_opt_out".to_string(),
+                value: "true".to_string(),
+                public: false,
+                user_id,
+            },
+            true,
+        )?)
+    }
+
+    /// The preferred language of the user as a language code, such as `uk`
+    pub fn get_language(&self, user_id: u64) -> Result<Option<String>, DatabaseError> {
+        Ok(self.get_user_data_value(user_id, "language")?)
+    }
+
+    pub fn set_language(&self, user_id: u64, language: &str) -> Result<(), DatabaseError> {
+        Ok(self.set_user_data(
+            &UserData {
+                name: "language".to_string(),
+                value: language.to_string(),
+                public: true,
+                user_id,
+            },
+            true,
+        )?)
+    }
+
+    pub fn get_lastfm_name(&self, user_id: u64) -> Result<Option<String>, DatabaseError> {
+        Ok(self.get_user_data_value(user_id, "lastfm_name")?)
+    }
+
+    pub fn set_lastfm_name(&self, user_id: u64, name: &str) -> Result<(), DatabaseError> {
+        Ok(self.set_user_data(
+            &UserData {
+                name: "lastfm_name".to_string(),
+                value: name.to_string(),
+                public: true,
+                user_id,
+            },
+            true,
+        )?)
+    }
+
+    /// Whether anyone can see the track the user is listening to through the now playing API
+    pub fn get_now_playing_public(&self, user_id: u64) -> Result<bool, DatabaseError> {
+        Ok(self
+            .get_user_data_value(user_id, "now_playing_public")?
+            .map_or(false, |value| value == "true"))
+    }
+
+    pub fn set_now_playing_public(&self, user_id: u64, public: bool) -> Result<(), DatabaseError> {
+        Ok(self.set_user_data(
+            &UserData {
+                name: "now_playing_public".to_string(),
+                value: public.to_string(),
+                public: true,
+                user_id,
+            },
+            true,
+        )?)
+    }
+
+    /// Expired sessions are removed, sessions that were not extended in the last day
+    /// get their expiry moved forward
+    pub fn get_web_session(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<WebSession>, diesel::result::Error> {
+        let cached_session = self
+            .web_sessions_cache
+            .get(session_id)
+            .map(|session| session.clone());
+
+        let mut session = match cached_session {
+            Some(session) => session,
+            None => {
+                let mut conn = self.get_conn()?;
+
+                match web_sessions::table
                     .filter(web_sessions::session_id.eq_all(session_id))
                     .first::<WebSession>(&mut conn)
                     .optional()?
@@ -679,12 +1357,51 @@ impl Database {
 
                         tracing::debug!("Inserted session {} into cache", session_id);
 
-                        Ok(Some(session))
+                        session
                     }
-                    None => Ok(None),
+                    None => return Ok(None),
                 }
             }
+        };
+
+        let now = Utc::now().naive_utc();
+        let lifetime = chrono::Duration::days(WEB_SESSION_LIFETIME_DAYS);
+
+        if session.expires_at <= now {
+            let mut conn = self.get_conn()?;
+
+            diesel::delete(web_sessions::table)
+                .filter(web_sessions::session_id.eq(session_id))
+                .execute(&mut conn)?;
+            self.web_sessions_cache.remove(session_id);
+
+            return Ok(None);
+        }
+
+        if session.expires_at - now < lifetime - chrono::Duration::days(1) {
+            let mut conn = self.get_conn()?;
+
+            session.expires_at = now + lifetime;
+
+            diesel::update(web_sessions::table)
+                .filter(web_sessions::session_id.eq(session_id))
+                .set(web_sessions::expires_at.eq(session.expires_at))
+                .execute(&mut conn)?;
+            self.web_sessions_cache
+                .insert(session_id.to_owned(), session.clone());
         }
+
+        Ok(Some(session))
+    }
+
+    pub fn get_user_web_sessions(&self, user_id: u64) -> Result<Vec<WebSession>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(web_sessions::table
+            .filter(web_sessions::user_id.eq(user_id))
+            .filter(web_sessions::expires_at.gt(Utc::now().naive_utc()))
+            .order(web_sessions::created_at.desc())
+            .load(&mut conn)?)
     }
 
     /// Returns the session id
@@ -692,8 +1409,11 @@ impl Database {
         &self,
         user_id: u64,
         username: String,
+        user_agent: Option<String>,
     ) -> Result<String, diesel::result::Error> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
+
+        let now = Utc::now().naive_utc();
 
         let session = WebSession {
             session_id: PasswordGenerator {
@@ -710,6 +1430,9 @@ impl Database {
             .unwrap(),
             user_id,
             username,
+            created_at: now,
+            expires_at: now + chrono::Duration::days(WEB_SESSION_LIFETIME_DAYS),
+            user_agent,
         };
 
         diesel::insert_into(web_sessions::table)
@@ -720,7 +1443,7 @@ impl Database {
     }
 
     pub fn remove_web_session(&self, session_id: &str) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         diesel::delete(web_sessions::table)
             .filter(web_sessions::session_id.eq(session_id))
@@ -730,8 +1453,148 @@ impl Database {
         Ok(())
     }
 
+    pub fn remove_expired_web_sessions(&self) -> Result<usize, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(diesel::delete(web_sessions::table)
+            .filter(web_sessions::expires_at.lt(Utc::now().naive_utc()))
+            .execute(&mut conn)?)
+    }
+
+    pub fn add_paste(&self, filename: String, content: Vec<u8>) -> Result<Paste, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let now = Utc::now().naive_utc();
+
+        let paste = Paste {
+            id: PasswordGenerator {
+                length: 24,
+                numbers: true,
+                lowercase_letters: true,
+                uppercase_letters: true,
+                symbols: false,
+                spaces: false,
+                exclude_similar_characters: false,
+                strict: true,
+            }
+            .generate_one()
+            .unwrap(),
+            filename,
+            content,
+            created_at: now,
+            expires_at: now + chrono::Duration::days(PASTE_LIFETIME_DAYS),
+        };
+
+        diesel::insert_into(pastes::table)
+            .values(&paste)
+            .execute(&mut conn)?;
+
+        Ok(paste)
+    }
+
+    pub fn get_paste(&self, id: &str) -> Result<Option<Paste>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(pastes::table
+            .find(id)
+            .filter(pastes::expires_at.gt(Utc::now().naive_utc()))
+            .first(&mut conn)
+            .optional()?)
+    }
+
+    pub fn remove_expired_pastes(&self) -> Result<usize, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(diesel::delete(pastes::table)
+            .filter(pastes::expires_at.lt(Utc::now().naive_utc()))
+            .execute(&mut conn)?)
+    }
+
+    /// Logs the user out everywhere
+    pub fn remove_user_web_sessions(&self, user_id: u64) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::delete(web_sessions::table)
+            .filter(web_sessions::user_id.eq(user_id))
+            .execute(&mut conn)?;
+        self.web_sessions_cache
+            .retain(|_, session| session.user_id != user_id);
+
+        Ok(())
+    }
+
+    /// Creates a token and returns it in plain text, only its hash is stored
+    pub fn create_api_token(
+        &self,
+        user_id: u64,
+        username: &str,
+        name: &str,
+        scope: ApiTokenScope,
+    ) -> Result<String, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let token = PasswordGenerator {
+            length: 48,
+            numbers: true,
+            lowercase_letters: true,
+            uppercase_letters: true,
+            symbols: false,
+            spaces: false,
+            exclude_similar_characters: false,
+            strict: true,
+        }
+        .generate_one()
+        .unwrap();
+
+        diesel::insert_into(api_tokens::table)
+            .values(NewApiToken {
+                user_id,
+                name,
+                username,
+                token_hash: &hash_api_token(&token),
+                scope: &scope.to_string(),
+            })
+            .execute(&mut conn)?;
+
+        Ok(token)
+    }
+
+    pub fn get_api_tokens(&self, user_id: u64) -> Result<Vec<ApiToken>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let tokens = api_tokens::table
+            .filter(api_tokens::user_id.eq(user_id))
+            .load(&mut conn)?;
+
+        Ok(tokens)
+    }
+
+    pub fn get_api_token(&self, token: &str) -> Result<Option<ApiToken>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let token = api_tokens::table
+            .filter(api_tokens::token_hash.eq(hash_api_token(token)))
+            .first(&mut conn)
+            .optional()?;
+
+        Ok(token)
+    }
+
+    pub fn delete_api_token(&self, user_id: u64, id: u64) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::delete(
+            api_tokens::table
+                .filter(api_tokens::user_id.eq(user_id))
+                .filter(api_tokens::id.eq(id)),
+        )
+        .execute(&mut conn)?;
+
+        Ok(())
+    }
+
     pub fn add_eventsub_trigger(&self, trigger: NewEventSubTrigger) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         diesel::insert_into(eventsub_triggers::table)
             .values(trigger)
@@ -741,7 +1604,7 @@ impl Database {
     }
 
     pub fn delete_eventsub_trigger(&self, id: &str) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         diesel::delete(eventsub_triggers::table)
             .filter(eventsub_triggers::id.eq(id))
@@ -750,16 +1613,128 @@ impl Database {
         Ok(())
     }
 
-    pub fn update_eventsub_trigger_id(
+    /// Replaces the trigger's subscription with one created using the given secret
+    pub fn update_eventsub_trigger_subscription(
         &self,
         old_id: &str,
         new_id: &str,
+        secret_id: u64,
     ) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         diesel::update(eventsub_triggers::table)
             .filter(eventsub_triggers::id.eq(old_id))
-            .set(eventsub_triggers::id.eq(new_id))
+            .set((
+                eventsub_triggers::id.eq(new_id),
+                eventsub_triggers::secret_id.eq(secret_id),
+            ))
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// The secret that new EventSub subscriptions are created with, generated on first use
+    pub fn get_current_eventsub_secret(&self) -> Result<EventSubSecret, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let secret = eventsub_secrets::table
+            .order(eventsub_secrets::id.desc())
+            .first(&mut conn)
+            .optional()?;
+
+        match secret {
+            Some(secret) => Ok(secret),
+            None => self.add_eventsub_secret(),
+        }
+    }
+
+    /// Generates a new current secret. Existing subscriptions keep using their secret until
+    /// they are recreated
+    pub fn add_eventsub_secret(&self) -> Result<EventSubSecret, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let secret = PasswordGenerator {
+            length: 64,
+            numbers: true,
+            lowercase_letters: true,
+            uppercase_letters: true,
+            symbols: false,
+            spaces: false,
+            exclude_similar_characters: false,
+            strict: true,
+        }
+        .generate_one()
+        .unwrap();
+
+        diesel::insert_into(eventsub_secrets::table)
+            .values(eventsub_secrets::secret.eq(&secret))
+            .execute(&mut conn)?;
+
+        let id = diesel::select(last_insert_id()).get_result::<u64>(&mut conn)?;
+        let secret = eventsub_secrets::table
+            .filter(eventsub_secrets::id.eq(id))
+            .first(&mut conn)?;
+
+        Ok(secret)
+    }
+
+    /// Secrets that notifications may be signed with: the current one, older ones that
+    /// subscriptions still use, and the legacy secret from the config
+    pub fn get_accepted_eventsub_secrets(&self) -> Result<Vec<String>, DatabaseError> {
+        let current = self.get_current_eventsub_secret()?;
+        let used_ids = self.get_used_eventsub_secret_ids()?;
+        let mut conn = self.get_conn()?;
+
+        let mut ids: Vec<u64> = used_ids.iter().flatten().copied().collect();
+        ids.push(current.id);
+
+        let mut secrets: Vec<String> = eventsub_secrets::table
+            .filter(eventsub_secrets::id.eq_any(ids))
+            .select(eventsub_secrets::secret)
+            .load(&mut conn)?;
+
+        let legacy_secret = &self.config().secret_key;
+        if used_ids.contains(&None) && !legacy_secret.is_empty() {
+            secrets.push(legacy_secret.clone());
+        }
+
+        Ok(secrets)
+    }
+
+    /// Removes old secrets once none of the subscriptions use them
+    pub fn delete_unused_eventsub_secrets(&self) -> Result<(), DatabaseError> {
+        let current = self.get_current_eventsub_secret()?;
+        let mut ids: Vec<u64> = self
+            .get_used_eventsub_secret_ids()?
+            .into_iter()
+            .flatten()
+            .collect();
+        ids.push(current.id);
+
+        let mut conn = self.get_conn()?;
+
+        diesel::delete(eventsub_secrets::table)
+            .filter(eventsub_secrets::id.ne_all(ids))
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    fn get_used_eventsub_secret_ids(&self) -> Result<Vec<Option<u64>>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(eventsub_triggers::table
+            .select(eventsub_triggers::secret_id)
+            .distinct()
+            .load(&mut conn)?)
+    }
+
+    pub fn set_eventsub_trigger_paused(&self, id: &str, paused: bool) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::update(eventsub_triggers::table)
+            .filter(eventsub_triggers::id.eq(id))
+            .set(eventsub_triggers::paused.eq(paused))
             .execute(&mut conn)?;
 
         Ok(())
@@ -773,11 +1748,11 @@ impl Database {
     }
 
     pub fn get_prefix(&self, channel_id: u64) -> Result<Option<String>, DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
-
         match self.prefixes_cache.get(&channel_id) {
-            Some(prefix_entry) => Ok(prefix_entry.value().clone()),
+            Some(prefix) => Ok(prefix),
             None => {
+                let mut conn = self.get_conn()?;
+
                 let prefix = prefixes::table
                     .filter(prefixes::channel_id.eq_all(channel_id))
                     .first::<Prefix>(&mut conn)
@@ -803,7 +1778,7 @@ impl Database {
     }
 
     pub fn get_mirror_connections(&self) -> Result<Vec<MirrorConnection>, DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         Ok(mirror_connections::table.load(&mut conn)?)
     }
@@ -812,7 +1787,7 @@ impl Database {
         &self,
         connection: MirrorConnection,
     ) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         diesel::insert_into(mirror_connections::table)
             .values(&connection)
@@ -826,7 +1801,7 @@ impl Database {
         command_name: &str,
         triggers: &str,
     ) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         if diesel::update(commands::table)
             .filter(commands::channel_id.eq(channel_id))
@@ -841,26 +1816,69 @@ impl Database {
         }
     }
 
-    pub fn get_all_filters(&self) -> Result<Vec<Filter>, DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
-
-        Ok(filters::table.load(&mut conn)?)
-    }
-
-    pub fn get_filters_in_channel_id(&self, channel_id: u64) -> Result<Vec<Filter>, DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+    /// `None` makes the command available in every channel
+    pub fn set_command_discord_channels(
+        &self,
+        channel_id: u64,
+        command_name: &str,
+        discord_channels: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
 
-        Ok(filters::table
-            .filter(filters::channel_id.eq(channel_id))
-            .load(&mut conn)?)
+        if diesel::update(commands::table)
+            .filter(commands::channel_id.eq(channel_id))
+            .filter(commands::name.eq(command_name))
+            .set(commands::discord_channels.eq(discord_channels))
+            .execute(&mut conn)?
+            > 0
+        {
+            Ok(())
+        } else {
+            Err(DatabaseError::InvalidValue)
+        }
     }
 
-    pub fn get_hebi_data(
+    pub fn set_command_args_spec(
         &self,
         channel_id: u64,
-        key: &str,
-    ) -> Result<Option<String>, DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        command_name: &str,
+        args_spec: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        if diesel::update(commands::table)
+            .filter(commands::channel_id.eq(channel_id))
+            .filter(commands::name.eq(command_name))
+            .set(commands::args_spec.eq(args_spec))
+            .execute(&mut conn)?
+            > 0
+        {
+            Ok(())
+        } else {
+            Err(DatabaseError::InvalidValue)
+        }
+    }
+
+    pub fn get_all_filters(&self) -> Result<Vec<Filter>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(filters::table.load(&mut conn)?)
+    }
+
+    pub fn get_filters_in_channel_id(&self, channel_id: u64) -> Result<Vec<Filter>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(filters::table
+            .filter(filters::channel_id.eq(channel_id))
+            .load(&mut conn)?)
+    }
+
+    pub fn get_hebi_data(
+        &self,
+        channel_id: u64,
+        key: &str,
+    ) -> Result<Option<String>, DatabaseError> {
+        let mut conn = self.get_conn()?;
 
         let value = hebi_data::table
             .select(hebi_data::value)
@@ -879,7 +1897,7 @@ impl Database {
         key: &str,
         value: &str,
     ) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         diesel::replace_into(hebi_data::table)
             .values((
@@ -893,7 +1911,7 @@ impl Database {
     }
 
     pub fn remove_hebi_data(&self, channel_id: u64, key: &str) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
 
         diesel::delete(
             hebi_data::table
@@ -906,7 +1924,7 @@ impl Database {
     }
 
     pub fn create_geohub_link(&self, link: GeohubLink) -> Result<(), DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
         diesel::insert_into(geohub_link::table)
             .values(link)
             .execute(&mut conn)?;
@@ -914,7 +1932,7 @@ impl Database {
     }
 
     pub fn get_geohub_link_names(&self, channel_id: u64) -> Result<Vec<String>, DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
         let values = geohub_link::table
             .select(geohub_link::geohub_name)
             .filter(geohub_link::channel_id.eq(channel_id))
@@ -922,12 +1940,798 @@ impl Database {
         Ok(values)
     }
 
+    /// Returns whether the user had a link in the channel
+    pub fn delete_geohub_link(&self, user_id: u64, channel_id: u64) -> Result<bool, DatabaseError> {
+        let mut conn = self.get_conn()?;
+        let affected = diesel::delete(
+            geohub_link::table
+                .filter(geohub_link::user_id.eq(user_id))
+                .filter(geohub_link::channel_id.eq(channel_id)),
+        )
+        .execute(&mut conn)?;
+        Ok(affected > 0)
+    }
+
+    pub fn get_channel_geohub_links(
+        &self,
+        channel_id: u64,
+    ) -> Result<Vec<GeohubLink>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+        let values = geohub_link::table
+            .filter(geohub_link::channel_id.eq(channel_id))
+            .order(geohub_link::geohub_name)
+            .load(&mut conn)?;
+        Ok(values)
+    }
+
     pub fn get_geohub_links(&self) -> Result<Vec<GeohubLink>, DatabaseError> {
-        let mut conn = self.conn_pool.get().unwrap();
+        let mut conn = self.get_conn()?;
         let values = geohub_link::table.load(&mut conn)?;
         Ok(values)
     }
 
+    /// Overwrites the results of the same users on the same days
+    pub fn save_geohub_results(&self, results: &[GeohubResult]) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+        diesel::replace_into(geohub_results::table)
+            .values(results)
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Results of the users linked in the channel since the given day, inclusive
+    pub fn get_geohub_results(
+        &self,
+        channel_id: u64,
+        since: NaiveDate,
+    ) -> Result<Vec<GeohubResult>, DatabaseError> {
+        let names = self.get_geohub_link_names(channel_id)?;
+
+        let mut conn = self.get_conn()?;
+        let values = geohub_results::table
+            .filter(geohub_results::geohub_name.eq_any(names))
+            .filter(geohub_results::date.ge(since))
+            .load(&mut conn)?;
+        Ok(values)
+    }
+
+    pub fn add_markov_message(&self, message: NewMarkovMessage) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+        diesel::insert_into(markov_messages::table)
+            .values(message)
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// The newest messages of the channel, only of the given user if there is one
+    pub fn get_markov_messages(
+        &self,
+        channel_id: u64,
+        user_id: Option<u64>,
+        limit: i64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let mut query = markov_messages::table
+            .filter(markov_messages::channel_id.eq(channel_id))
+            .select(markov_messages::text)
+            .order_by(markov_messages::id.desc())
+            .limit(limit)
+            .into_boxed();
+
+        if let Some(user_id) = user_id {
+            query = query.filter(markov_messages::user_id.eq(user_id));
+        }
+
+        Ok(query.load(&mut conn)?)
+    }
+
+    /// Deletes everything but the newest `keep` messages of the channel
+    pub fn prune_markov_messages(&self, channel_id: u64, keep: i64) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let oldest_kept: Option<u64> = markov_messages::table
+            .filter(markov_messages::channel_id.eq(channel_id))
+            .select(markov_messages::id)
+            .order_by(markov_messages::id.desc())
+            .offset(keep - 1)
+            .first(&mut conn)
+            .optional()?;
+
+        if let Some(oldest_kept) = oldest_kept {
+            diesel::delete(
+                markov_messages::table
+                    .filter(markov_messages::channel_id.eq(channel_id))
+                    .filter(markov_messages::id.lt(oldest_kept)),
+            )
+            .execute(&mut conn)?;
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the messages of the channel, only of the given user if there is one.
+    /// Returns the amount of deleted messages
+    pub fn delete_markov_messages(
+        &self,
+        channel_id: u64,
+        user_id: Option<u64>,
+    ) -> Result<usize, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let query = markov_messages::table.filter(markov_messages::channel_id.eq(channel_id));
+
+        let deleted = match user_id {
+            Some(user_id) => diesel::delete(query.filter(markov_messages::user_id.eq(user_id)))
+                .execute(&mut conn)?,
+            None => diesel::delete(query).execute(&mut conn)?,
+        };
+
+        Ok(deleted)
+    }
+
+    /// Deletes the messages of the user in every channel
+    pub fn delete_user_markov_messages(&self, user_id: u64) -> Result<usize, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(
+            diesel::delete(markov_messages::table.filter(markov_messages::user_id.eq(user_id)))
+                .execute(&mut conn)?,
+        )
+    }
+
+    pub fn get_custom_hook(
+        &self,
+        channel_id: u64,
+        id: &str,
+    ) -> Result<Option<CustomHook>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let hook = custom_hooks::table
+            .filter(custom_hooks::channel_id.eq(channel_id))
+            .filter(custom_hooks::id.eq(id))
+            .first(&mut conn)
+            .optional()?;
+
+        Ok(hook)
+    }
+
+    pub fn get_custom_hooks(&self, channel_id: u64) -> Result<Vec<CustomHook>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(custom_hooks::table
+            .filter(custom_hooks::channel_id.eq(channel_id))
+            .load(&mut conn)?)
+    }
+
+    /// Creates or replaces the hook, returning its newly generated token
+    pub fn set_custom_hook(
+        &self,
+        channel_id: u64,
+        id: &str,
+        action: &str,
+        mode: CommandMode,
+    ) -> Result<String, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let token = PasswordGenerator {
+            length: 32,
+            numbers: true,
+            lowercase_letters: true,
+            uppercase_letters: true,
+            symbols: false,
+            spaces: false,
+            exclude_similar_characters: false,
+            strict: true,
+        }
+        .generate_one()
+        .unwrap();
+
+        diesel::replace_into(custom_hooks::table)
+            .values(NewCustomHook {
+                channel_id,
+                id,
+                token: &token,
+                action,
+                execution_mode: &mode.to_string(),
+            })
+            .execute(&mut conn)?;
+
+        Ok(token)
+    }
+
+    pub fn delete_custom_hook(&self, channel_id: u64, id: &str) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::delete(
+            custom_hooks::table
+                .filter(custom_hooks::channel_id.eq(channel_id))
+                .filter(custom_hooks::id.eq(id)),
+        )
+        .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn get_outgoing_webhooks(
+        &self,
+        channel_id: u64,
+    ) -> Result<Vec<OutgoingWebhook>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let webhooks = outgoing_webhooks::table
+            .filter(outgoing_webhooks::channel_id.eq(channel_id))
+            .load(&mut conn)?;
+
+        Ok(webhooks)
+    }
+
+    /// Registers a webhook with a newly generated signing secret
+    pub fn add_outgoing_webhook(
+        &self,
+        channel_id: u64,
+        url: &str,
+        events: &[WebhookEvent],
+    ) -> Result<OutgoingWebhook, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let secret = PasswordGenerator {
+            length: 32,
+            numbers: true,
+            lowercase_letters: true,
+            uppercase_letters: true,
+            symbols: false,
+            spaces: false,
+            exclude_similar_characters: false,
+            strict: true,
+        }
+        .generate_one()
+        .unwrap();
+
+        let events = events
+            .iter()
+            .map(|event| event.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        diesel::insert_into(outgoing_webhooks::table)
+            .values(NewOutgoingWebhook {
+                channel_id,
+                url,
+                secret: &secret,
+                events: &events,
+            })
+            .execute(&mut conn)?;
+
+        let webhook = outgoing_webhooks::table
+            .filter(outgoing_webhooks::channel_id.eq(channel_id))
+            .filter(outgoing_webhooks::secret.eq(&secret))
+            .first(&mut conn)?;
+
+        Ok(webhook)
+    }
+
+    pub fn delete_outgoing_webhook(&self, channel_id: u64, id: u64) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::delete(
+            outgoing_webhooks::table
+                .filter(outgoing_webhooks::channel_id.eq(channel_id))
+                .filter(outgoing_webhooks::id.eq(id)),
+        )
+        .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn get_github_hook(&self, channel_id: u64) -> Result<Option<GithubHook>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let hook = github_hooks::table
+            .filter(github_hooks::channel_id.eq(channel_id))
+            .first(&mut conn)
+            .optional()?;
+
+        Ok(hook)
+    }
+
+    /// Returns the existing hook of the channel or creates one with a new secret
+    pub fn get_or_create_github_hook(&self, channel_id: u64) -> Result<GithubHook, DatabaseError> {
+        if let Some(hook) = self.get_github_hook(channel_id)? {
+            return Ok(hook);
+        }
+
+        let hook = GithubHook {
+            channel_id,
+            secret: PasswordGenerator {
+                length: 32,
+                numbers: true,
+                lowercase_letters: true,
+                uppercase_letters: true,
+                symbols: false,
+                spaces: false,
+                exclude_similar_characters: false,
+                strict: true,
+            }
+            .generate_one()
+            .unwrap(),
+            push_template: None,
+            release_template: None,
+            issues_template: None,
+            pull_request_template: None,
+        };
+
+        self.save_github_hook(&hook)?;
+
+        Ok(hook)
+    }
+
+    pub fn save_github_hook(&self, hook: &GithubHook) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::replace_into(github_hooks::table)
+            .values(hook.clone())
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn delete_github_hook(&self, channel_id: u64) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::delete(github_hooks::table.filter(github_hooks::channel_id.eq(channel_id)))
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn is_ai_enabled(&self, channel_id: u64) -> Result<bool, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let count: i64 = ai_channels::table
+            .filter(ai_channels::channel_id.eq(channel_id))
+            .count()
+            .get_result(&mut conn)?;
+
+        Ok(count > 0)
+    }
+
+    pub fn set_ai_enabled(&self, channel_id: u64, enabled: bool) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        match enabled {
+            true => diesel::replace_into(ai_channels::table)
+                .values(ai_channels::channel_id.eq(channel_id))
+                .execute(&mut conn)?,
+            false => {
+                diesel::delete(ai_channels::table.filter(ai_channels::channel_id.eq(channel_id)))
+                    .execute(&mut conn)?
+            }
+        };
+
+        Ok(())
+    }
+
+    pub fn add_ai_usage(&self, usage: NewAiUsage) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::insert_into(ai_usage::table)
+            .values(usage)
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn get_ai_usage(&self, channel_id: u64) -> Result<AiUsageSummary, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let rows = ai_usage::table
+            .select((
+                ai_usage::prompt_tokens,
+                ai_usage::completion_tokens,
+                ai_usage::cost,
+            ))
+            .filter(ai_usage::channel_id.eq(channel_id))
+            .load::<(u32, u32, f64)>(&mut conn)?;
+
+        Ok(rows.into_iter().fold(
+            AiUsageSummary::default(),
+            |mut summary, (prompt_tokens, completion_tokens, cost)| {
+                summary.requests += 1;
+                summary.prompt_tokens += u64::from(prompt_tokens);
+                summary.completion_tokens += u64::from(completion_tokens);
+                summary.cost += cost;
+                summary
+            },
+        ))
+    }
+
+    pub fn add_moderation_log_entry(
+        &self,
+        entry: NewModerationLogEntry,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::insert_into(moderation_log::table)
+            .values(entry)
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// Newest entries first
+    pub fn get_moderation_log(
+        &self,
+        channel_id: u64,
+        filter: ModerationLogFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ModerationLogEntry>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let mut query = moderation_log::table
+            .filter(moderation_log::channel_id.eq(channel_id))
+            .into_boxed();
+
+        if let Some(target) = filter.target {
+            query = query.filter(moderation_log::target.eq(target));
+        }
+        if let Some(action) = filter.action {
+            query = query.filter(moderation_log::action.eq(action.to_string()));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(moderation_log::created_at.ge(from));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(moderation_log::created_at.le(to));
+        }
+
+        Ok(query
+            .order(moderation_log::id.desc())
+            .limit(limit)
+            .offset(offset)
+            .load(&mut conn)?)
+    }
+
+    pub fn add_stream_highlight(&self, highlight: NewStreamHighlight) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+        diesel::insert_into(stream_highlights::table)
+            .values(highlight)
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Newest first, only of the given stream if there is one
+    pub fn get_stream_highlights(
+        &self,
+        channel_id: u64,
+        stream_id: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<StreamHighlight>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let mut query = stream_highlights::table
+            .filter(stream_highlights::channel_id.eq(channel_id))
+            .into_boxed();
+
+        if let Some(stream_id) = stream_id {
+            query = query.filter(stream_highlights::stream_id.eq(stream_id));
+        }
+
+        Ok(query
+            .order(stream_highlights::id.desc())
+            .limit(limit)
+            .offset(offset)
+            .load(&mut conn)?)
+    }
+
+    pub fn delete_stream_highlight(&self, channel_id: u64, id: u64) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::delete(
+            stream_highlights::table
+                .filter(stream_highlights::channel_id.eq(channel_id))
+                .filter(stream_highlights::id.eq(id)),
+        )
+        .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn get_global_command(&self, name: &str) -> Result<Option<GlobalCommand>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(global_commands::table
+            .find(name)
+            .first(&mut conn)
+            .optional()?)
+    }
+
+    pub fn get_global_commands(&self) -> Result<Vec<GlobalCommand>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(global_commands::table
+            .order(global_commands::name.asc())
+            .load(&mut conn)?)
+    }
+
+    pub fn add_global_command(&self, command: NewGlobalCommand) -> Result<(), DatabaseError> {
+        if self.is_builtin_command_name(command.name) {
+            return Err(DatabaseError::BuiltinCommandName(command.name.to_owned()));
+        }
+
+        let mut conn = self.get_conn()?;
+
+        diesel::insert_into(global_commands::table)
+            .values(command)
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// Returns `false` if there is no such command
+    pub fn update_global_command_action(
+        &self,
+        name: &str,
+        action: &str,
+    ) -> Result<bool, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let updated = diesel::update(global_commands::table.find(name))
+            .set(global_commands::action.eq(action))
+            .execute(&mut conn)?;
+
+        Ok(updated > 0)
+    }
+
+    pub fn set_global_command_mode(
+        &self,
+        name: &str,
+        mode: CommandMode,
+    ) -> Result<bool, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let updated = diesel::update(global_commands::table.find(name))
+            .set(global_commands::mode.eq(mode.to_string()))
+            .execute(&mut conn)?;
+
+        Ok(updated > 0)
+    }
+
+    pub fn delete_global_command(&self, name: &str) -> Result<bool, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let deleted = diesel::delete(global_commands::table.find(name)).execute(&mut conn)?;
+
+        Ok(deleted > 0)
+    }
+
+    pub fn get_personal_command(
+        &self,
+        user_id: u64,
+        name: &str,
+    ) -> Result<Option<PersonalCommand>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(personal_commands::table
+            .find((user_id, name))
+            .first(&mut conn)
+            .optional()?)
+    }
+
+    pub fn get_personal_commands(
+        &self,
+        user_id: u64,
+    ) -> Result<Vec<PersonalCommand>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(personal_commands::table
+            .filter(personal_commands::user_id.eq(user_id))
+            .order(personal_commands::name.asc())
+            .load(&mut conn)?)
+    }
+
+    pub fn count_personal_commands(&self, user_id: u64) -> Result<i64, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(personal_commands::table
+            .filter(personal_commands::user_id.eq(user_id))
+            .count()
+            .get_result(&mut conn)?)
+    }
+
+    pub fn add_personal_command(&self, command: NewPersonalCommand) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::insert_into(personal_commands::table)
+            .values(command)
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// Returns `false` if the user has no such command
+    pub fn update_personal_command_action(
+        &self,
+        user_id: u64,
+        name: &str,
+        action: &str,
+    ) -> Result<bool, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let updated = diesel::update(personal_commands::table.find((user_id, name)))
+            .set(personal_commands::action.eq(action))
+            .execute(&mut conn)?;
+
+        Ok(updated > 0)
+    }
+
+    pub fn set_personal_command_mode(
+        &self,
+        user_id: u64,
+        name: &str,
+        mode: CommandMode,
+    ) -> Result<bool, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let updated = diesel::update(personal_commands::table.find((user_id, name)))
+            .set(personal_commands::mode.eq(mode.to_string()))
+            .execute(&mut conn)?;
+
+        Ok(updated > 0)
+    }
+
+    pub fn delete_personal_command(&self, user_id: u64, name: &str) -> Result<bool, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let deleted =
+            diesel::delete(personal_commands::table.find((user_id, name))).execute(&mut conn)?;
+
+        Ok(deleted > 0)
+    }
+
+    pub fn get_protection_settings(
+        &self,
+        channel_id: u64,
+    ) -> Result<Option<ProtectionSettings>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(protection_settings::table
+            .find(channel_id)
+            .first(&mut conn)
+            .optional()?)
+    }
+
+    pub fn set_protection_settings(
+        &self,
+        settings: &ProtectionSettings,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::replace_into(protection_settings::table)
+            .values(settings)
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn get_all_channel_settings(&self) -> Result<Vec<ChannelSettings>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let mut values_by_channel: HashMap<u64, Vec<ChannelSettingValue>> = HashMap::new();
+
+        for value in channel_settings::table.load::<ChannelSettingValue>(&mut conn)? {
+            values_by_channel
+                .entry(value.channel_id)
+                .or_default()
+                .push(value);
+        }
+
+        Ok(values_by_channel
+            .into_iter()
+            .map(|(channel_id, values)| ChannelSettings::from_values(channel_id, &values))
+            .collect())
+    }
+
+    pub fn get_channel_settings(&self, channel_id: u64) -> Result<ChannelSettings, DatabaseError> {
+        let values = self.get_channel_setting_values(channel_id)?;
+
+        Ok(ChannelSettings::from_values(channel_id, &values))
+    }
+
+    /// Only returns the settings that have been set in the channel
+    pub fn get_channel_setting_values(
+        &self,
+        channel_id: u64,
+    ) -> Result<Vec<ChannelSettingValue>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(channel_settings::table
+            .filter(channel_settings::channel_id.eq(channel_id))
+            .load(&mut conn)?)
+    }
+
+    /// Returns `None` if the setting is not set or its value can't be parsed
+    pub fn get_channel_setting<T: FromStr>(
+        &self,
+        channel_id: u64,
+        setting: ChannelSetting,
+    ) -> Result<Option<T>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let value: Option<String> = channel_settings::table
+            .find((channel_id, setting.to_string()))
+            .select(channel_settings::value)
+            .first(&mut conn)
+            .optional()?;
+
+        Ok(value.and_then(|value| value.parse().ok()))
+    }
+
+    pub fn set_channel_setting<T: Display>(
+        &self,
+        channel_id: u64,
+        setting: ChannelSetting,
+        value: T,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::replace_into(channel_settings::table)
+            .values(ChannelSettingValue {
+                channel_id,
+                name: setting.to_string(),
+                value: value.to_string(),
+            })
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// Resets the setting to its default value
+    pub fn remove_channel_setting(
+        &self,
+        channel_id: u64,
+        setting: ChannelSetting,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::delete(channel_settings::table.find((channel_id, setting.to_string())))
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn get_banphrase_api(
+        &self,
+        channel_id: u64,
+    ) -> Result<Option<BanphraseApiConfig>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(banphrase_apis::table
+            .find(channel_id)
+            .first(&mut conn)
+            .optional()?)
+    }
+
+    pub fn set_banphrase_api(&self, config: &BanphraseApiConfig) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::replace_into(banphrase_apis::table)
+            .values(config)
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    pub fn delete_banphrase_api(&self, channel_id: u64) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::delete(banphrase_apis::table.find(channel_id)).execute(&mut conn)?;
+
+        Ok(())
+    }
+
     /*pub fn get_filters_in_channel(
         &self,
         channel_identifier: &ChannelIdentifier,
@@ -939,10 +2743,18 @@ impl Database {
     }*/
 }
 
+fn hash_api_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
 #[derive(Debug)]
 pub enum DatabaseError {
     DieselError(diesel::result::Error),
     InvalidValue,
+    /// A custom command can't be created with the name of a builtin command
+    BuiltinCommandName(String),
+    /// The channel already has the maximum amount of template data keys
+    TemplateDataQuota(u32),
 }
 
 impl From<diesel::result::Error> for DatabaseError {
@@ -966,6 +2778,12 @@ impl Display for DatabaseError {
             match self {
                 DatabaseError::DieselError(e) => format!("Database error: {}", e),
                 DatabaseError::InvalidValue => "Invalid value".to_string(),
+                DatabaseError::BuiltinCommandName(name) => {
+                    format!("{name} is the name of a builtin command")
+                }
+                DatabaseError::TemplateDataQuota(max_keys) => {
+                    format!("the channel can't store more than {max_keys} data keys")
+                }
             }
         )
     }
@@ -1,8 +1,9 @@
 use std::str::FromStr;
 
-use crate::platform::ChannelIdentifier;
+use crate::{command_handler::i18n::is_language_code, platform::ChannelIdentifier};
 
 use super::schema::*;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use diesel::Queryable;
 use serde::{Deserialize, Serialize};
 use strum::EnumString;
@@ -15,10 +16,47 @@ pub struct User {
     pub irc_name: Option<String>,
     pub local_addr: Option<String>,
     pub telegram_id: Option<String>,
-    pub matrix_id: Option<String>,
+    pub github_id: Option<String>,
+    pub google_id: Option<String>,
+}
+
+/// Platform identities that can be used to log in to the web interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum UserIdentity {
+    Twitch,
+    Discord,
+    Github,
+    Google,
+}
+
+impl UserIdentity {
+    pub const ALL: [UserIdentity; 4] = [
+        UserIdentity::Twitch,
+        UserIdentity::Discord,
+        UserIdentity::Github,
+        UserIdentity::Google,
+    ];
 }
 
 impl User {
+    pub fn has_identity(&self, identity: UserIdentity) -> bool {
+        match identity {
+            UserIdentity::Twitch => self.twitch_id.is_some(),
+            UserIdentity::Discord => self.discord_id.is_some(),
+            UserIdentity::Github => self.github_id.is_some(),
+            UserIdentity::Google => self.google_id.is_some(),
+        }
+    }
+
+    /// An identity can only be unlinked if the user can still log in with another one
+    pub fn can_unlink(&self, identity: UserIdentity) -> bool {
+        self.has_identity(identity)
+            && UserIdentity::ALL
+                .iter()
+                .any(|other| *other != identity && self.has_identity(*other))
+    }
+
     pub fn merge(&mut self, other: User) {
         if self.twitch_id.is_none() && other.twitch_id.is_some() {
             self.twitch_id = other.twitch_id;
@@ -27,9 +65,50 @@ impl User {
         if self.discord_id.is_none() && other.discord_id.is_some() {
             self.discord_id = other.discord_id;
         }
+
+        if self.github_id.is_none() && other.github_id.is_some() {
+            self.github_id = other.github_id;
+        }
+
+        if self.google_id.is_none() && other.google_id.is_some() {
+            self.google_id = other.google_id;
+        }
     }
 }
 
+/// An audit record of another user being merged into the user
+#[derive(Insertable)]
+#[diesel(table_name = user_merges)]
+pub struct NewUserMerge<'a> {
+    pub user_id: u64,
+    pub merged_user_id: u64,
+    /// The identifier that the merged user was found by, such as `twitch:12345`
+    pub identity: &'a str,
+    pub method: &'a str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum UserMergeMethod {
+    /// Logging in with another platform on the web interface
+    Oauth,
+    /// A verification code sent from the other platform with the `link` command
+    Chat,
+}
+
+/// A name that the user has been seen with on a platform
+#[derive(Queryable, Serialize, Debug, Clone)]
+pub struct UserName {
+    #[serde(skip)]
+    pub user_id: u64,
+    pub platform: String,
+    pub name: String,
+    /// UTC
+    pub first_seen_at: NaiveDateTime,
+    /// UTC
+    pub last_seen_at: NaiveDateTime,
+}
+
 #[derive(Insertable, Default)]
 #[diesel(table_name = users)]
 pub struct NewUser<'a> {
@@ -38,7 +117,8 @@ pub struct NewUser<'a> {
     pub irc_name: Option<&'a str>,
     pub local_addr: Option<String>,
     pub telegram_id: Option<String>,
-    pub matrix_id: Option<&'a str>,
+    pub github_id: Option<&'a str>,
+    pub google_id: Option<&'a str>,
 }
 
 #[derive(Queryable, Debug, PartialEq, Eq, Serialize, Clone)]
@@ -46,6 +126,7 @@ pub struct Channel {
     pub id: u64,
     pub platform: String,
     pub channel: String,
+    pub joined: bool,
 }
 
 impl Channel {
@@ -61,7 +142,7 @@ pub struct NewChannel<'a> {
     pub channel: &'a str,
 }
 
-#[derive(Queryable, Debug, PartialEq, Eq, Serialize)]
+#[derive(Queryable, Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Command {
     pub name: String,
     pub action: String,
@@ -71,9 +152,19 @@ pub struct Command {
     pub triggers: Option<String>,
     #[diesel(deserialize_as = String)]
     pub mode: CommandMode,
+    /// Discord channel ids separated by spaces, the command works in every channel if unset
+    pub discord_channels: Option<String>,
+    /// Such as `user:mention amount:int`, the arguments are checked before the action is executed
+    pub args_spec: Option<String>,
+}
+
+impl Command {
+    pub fn is_available_in(&self, subchannel: Option<u64>) -> bool {
+        is_in_discord_channels(self.discord_channels.as_deref(), subchannel)
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Serialize, EnumString, strum::Display)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, EnumString, strum::Display)]
 #[strum(serialize_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum CommandMode {
@@ -89,6 +180,37 @@ impl TryFrom<String> for CommandMode {
     }
 }
 
+/// Example invocation of a command, passing if the response contains every expected substring
+#[derive(Queryable, Debug, Clone, Serialize)]
+pub struct CommandTest {
+    pub id: u64,
+    #[serde(skip)]
+    pub channel_id: u64,
+    pub command_name: String,
+    pub args: String,
+    /// One substring per line
+    pub expected: String,
+}
+
+impl CommandTest {
+    pub fn get_args(&self) -> Vec<String> {
+        self.args.split_whitespace().map(str::to_owned).collect()
+    }
+
+    pub fn get_expected(&self) -> impl Iterator<Item = &str> {
+        self.expected.lines().filter(|line| !line.is_empty())
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = command_tests)]
+pub struct NewCommandTest<'a> {
+    pub channel_id: u64,
+    pub command_name: &'a str,
+    pub args: &'a str,
+    pub expected: &'a str,
+}
+
 #[derive(Insertable, Debug, PartialEq, Eq)]
 #[diesel(table_name = commands)]
 pub struct NewCommand<'a> {
@@ -99,6 +221,41 @@ pub struct NewCommand<'a> {
     pub cooldown: u64,
 }
 
+/// A command of the bot's admin that works in every channel without a command of the same name
+#[derive(Queryable, Serialize, Debug, Clone)]
+pub struct GlobalCommand {
+    pub name: String,
+    pub action: String,
+    #[diesel(deserialize_as = String)]
+    pub mode: CommandMode,
+    pub cooldown: Option<u64>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = global_commands)]
+pub struct NewGlobalCommand<'a> {
+    pub name: &'a str,
+    pub action: &'a str,
+}
+
+/// A command of a single user, triggered with `my <name>` in any channel
+#[derive(Queryable, Debug, Clone)]
+pub struct PersonalCommand {
+    pub user_id: u64,
+    pub name: String,
+    pub action: String,
+    #[diesel(deserialize_as = String)]
+    pub mode: CommandMode,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = personal_commands)]
+pub struct NewPersonalCommand<'a> {
+    pub user_id: u64,
+    pub name: &'a str,
+    pub action: &'a str,
+}
+
 #[derive(Queryable, Insertable, Debug, PartialEq, Eq)]
 #[diesel(table_name = user_data)]
 pub struct UserData {
@@ -121,6 +278,60 @@ pub struct WebSession {
     pub session_id: String,
     pub user_id: u64,
     pub username: String,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Queryable, Debug)]
+pub struct BlockedUser {
+    pub id: u64,
+    pub identifier: String,
+    pub channel_id: Option<u64>,
+}
+
+#[derive(Queryable, Serialize)]
+pub struct ApiToken {
+    pub id: u64,
+    #[serde(skip)]
+    pub user_id: u64,
+    pub name: String,
+    #[serde(skip)]
+    pub username: String,
+    #[serde(skip)]
+    pub token_hash: String,
+    #[diesel(deserialize_as = String)]
+    pub scope: ApiTokenScope,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = api_tokens)]
+pub struct NewApiToken<'a> {
+    pub user_id: u64,
+    pub name: &'a str,
+    pub username: &'a str,
+    pub token_hash: &'a str,
+    pub scope: &'a str,
+}
+
+/// Read tokens can only make `GET` requests, admin tokens can additionally use admin routes
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, EnumString, strum::Display,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ApiTokenScope {
+    Read,
+    Write,
+    Admin,
+}
+
+impl TryFrom<String> for ApiTokenScope {
+    type Error = strum::ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::from_str(&value)
+    }
 }
 
 #[derive(Insertable)]
@@ -131,6 +342,7 @@ pub struct NewEventSubTrigger<'a> {
     pub action: &'a str,
     pub creation_payload: &'a str,
     pub id: &'a str,
+    pub secret_id: Option<u64>,
 }
 
 #[derive(Queryable, Serialize)]
@@ -142,6 +354,35 @@ pub struct EventSubTrigger {
     pub id: String,
     #[diesel(deserialize_as = String)]
     pub mode: CommandMode,
+    /// Paused triggers stay subscribed, but their actions are not executed
+    pub paused: bool,
+    /// The secret the subscription was created with, `None` for the legacy secret from the config
+    #[serde(skip)]
+    pub secret_id: Option<u64>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct EventSubSecret {
+    pub id: u64,
+    pub secret: String,
+    pub created_at: NaiveDateTime,
+}
+
+/// Files that are linked instead of uploaded, such as long responses and generated images
+#[derive(Queryable, Insertable, Debug, Clone)]
+#[diesel(table_name = pastes)]
+pub struct Paste {
+    pub id: String,
+    pub filename: String,
+    pub content: Vec<u8>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl Paste {
+    pub fn get_url(&self, base_url: &str) -> String {
+        format!("{base_url}/p/{}", self.id)
+    }
 }
 
 #[derive(Queryable)]
@@ -157,7 +398,7 @@ pub struct MirrorConnection {
     pub to_channel_id: u64,
 }
 
-#[derive(Queryable, Insertable, Debug, Serialize)]
+#[derive(Queryable, Insertable, Debug, Serialize, Clone)]
 #[diesel(table_name = filters)]
 pub struct Filter {
     #[serde(skip)]
@@ -165,6 +406,504 @@ pub struct Filter {
     pub regex: String,
     pub block_message: bool,
     pub replacement: Option<String>,
+    /// Discord channel ids separated by spaces, the filter applies everywhere if unset
+    pub discord_channels: Option<String>,
+}
+
+impl Filter {
+    /// Filters are applied when the channel is not known, as it's safer to filter too much
+    pub fn applies_to(&self, subchannel: Option<u64>) -> bool {
+        subchannel.is_none() || is_in_discord_channels(self.discord_channels.as_deref(), subchannel)
+    }
+}
+
+/// Channels outside of Discord are never restricted
+fn is_in_discord_channels(channels: Option<&str>, subchannel: Option<u64>) -> bool {
+    match (channels.and_then(parse_discord_channel_ids), subchannel) {
+        (Some(channels), Some(subchannel)) if !channels.is_empty() => {
+            channels.contains(&subchannel)
+        }
+        _ => true,
+    }
+}
+
+/// Accepts channel ids and channel mentions such as `<#123>`, separated by spaces
+pub fn parse_discord_channel_ids(value: &str) -> Option<Vec<u64>> {
+    value
+        .split_whitespace()
+        .map(|channel| {
+            channel
+                .trim_start_matches("<#")
+                .trim_end_matches('>')
+                .parse()
+                .ok()
+        })
+        .collect()
+}
+
+/// `Some(None)` for an empty value, which turns the setting off
+fn parse_time_of_day(value: &str) -> Option<Option<NaiveTime>> {
+    match value.trim() {
+        "" => Some(None),
+        value => NaiveTime::parse_from_str(value, "%H:%M").ok().map(Some),
+    }
+}
+
+/// `Some(None)` for an empty value
+fn parse_optional_days(value: &str) -> Option<Option<u32>> {
+    match value.trim() {
+        "" => Some(None),
+        value => match value.parse::<u32>() {
+            Ok(days) if days > 0 => Some(Some(days)),
+            _ => None,
+        },
+    }
+}
+
+#[derive(Queryable, Insertable, Debug, Clone)]
+#[diesel(table_name = channel_settings)]
+pub struct ChannelSettingValue {
+    pub channel_id: u64,
+    pub name: String,
+    pub value: String,
+}
+
+const MAX_ERROR_TEMPLATE_LENGTH: usize = 200;
+
+/// The settings that can be stored in the `channel_settings` table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ChannelSetting {
+    /// How responses that don't fit into a single message are handled
+    LongMessages,
+    /// The most messages that a split response is sent as
+    MaxLines,
+    /// Send responses as replies to the command message on platforms that support it
+    ReplyToMessages,
+    MentionUser,
+    /// Text or emoji that every response starts with
+    ResponsePrefix,
+    /// Whether messages from the channel are mirrored to its mirror connections
+    Mirroring,
+    /// Whether the `gamble` mini-game can be played in the channel
+    Gamble,
+    /// The percentage of gambles that are won
+    GambleWinChance,
+    /// Whether users can challenge each other with `duel`
+    Duel,
+    /// Whether the `slots` mini-game can be played in the channel
+    Slots,
+    /// The points that a spin of `slots` costs
+    SlotsCost,
+    /// The language of builtin responses for users that haven't set their own
+    Language,
+    /// Raids with fewer viewers don't run the channel's raid triggers
+    RaidMinViewers,
+    /// The Discord channels of the guild where the bot responds, all channels if empty
+    DiscordChannels,
+    /// AI completions continue the user's recent conversation
+    AiMemory,
+    /// UTC time of day when the winner of the GeoHub daily challenge is announced, never if empty
+    GeohubAnnounceTime,
+    /// Chat messages are recorded for generating sentences with the `markov` command
+    Markov,
+    /// Chat messages are logged for commands such as `lines` and `lastseen`
+    ChatLogs,
+    /// Days that the chat logs are kept for, as long as the bot's retention allows if empty
+    ChatLogDays,
+    /// How much of a failed command's error is shown in chat
+    ErrorResponses,
+    /// Template for the text of error responses, the error itself if empty
+    ErrorTemplate,
+}
+
+impl ChannelSetting {
+    pub const ALL: [ChannelSetting; 21] = [
+        ChannelSetting::LongMessages,
+        ChannelSetting::MaxLines,
+        ChannelSetting::ReplyToMessages,
+        ChannelSetting::MentionUser,
+        ChannelSetting::ResponsePrefix,
+        ChannelSetting::Mirroring,
+        ChannelSetting::Gamble,
+        ChannelSetting::GambleWinChance,
+        ChannelSetting::Duel,
+        ChannelSetting::Slots,
+        ChannelSetting::SlotsCost,
+        ChannelSetting::Language,
+        ChannelSetting::RaidMinViewers,
+        ChannelSetting::DiscordChannels,
+        ChannelSetting::AiMemory,
+        ChannelSetting::GeohubAnnounceTime,
+        ChannelSetting::Markov,
+        ChannelSetting::ChatLogs,
+        ChannelSetting::ChatLogDays,
+        ChannelSetting::ErrorResponses,
+        ChannelSetting::ErrorTemplate,
+    ];
+
+    pub fn default_value(&self) -> &'static str {
+        match self {
+            ChannelSetting::LongMessages => "split",
+            ChannelSetting::ErrorResponses => "full",
+            ChannelSetting::MaxLines => "3",
+            ChannelSetting::GambleWinChance => "45",
+            ChannelSetting::SlotsCost => "10",
+            ChannelSetting::RaidMinViewers => "0",
+            ChannelSetting::ReplyToMessages | ChannelSetting::Mirroring => "true",
+            ChannelSetting::MentionUser
+            | ChannelSetting::Gamble
+            | ChannelSetting::Duel
+            | ChannelSetting::Slots
+            | ChannelSetting::AiMemory
+            | ChannelSetting::Markov
+            | ChannelSetting::ChatLogs => "false",
+            ChannelSetting::ResponsePrefix
+            | ChannelSetting::Language
+            | ChannelSetting::DiscordChannels
+            | ChannelSetting::GeohubAnnounceTime
+            | ChannelSetting::ChatLogDays
+            | ChannelSetting::ErrorTemplate => "",
+        }
+    }
+
+    /// Returns the values of all settings, using the defaults for the ones that are not set
+    pub fn with_defaults(values: &[ChannelSettingValue]) -> Vec<(ChannelSetting, String)> {
+        Self::ALL
+            .into_iter()
+            .map(|setting| {
+                let value = values
+                    .iter()
+                    .find(|value| value.name == setting.to_string())
+                    .map(|value| value.value.clone())
+                    .unwrap_or_else(|| setting.default_value().to_owned());
+
+                (setting, value)
+            })
+            .collect()
+    }
+
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        match self {
+            ChannelSetting::LongMessages => LongMessageMode::from_str(value)
+                .map(|_| ())
+                .map_err(|_| format!("{self} must be split, truncate or paste")),
+            ChannelSetting::MaxLines => match value.parse::<u32>() {
+                Ok(lines) if lines > 0 => Ok(()),
+                _ => Err(format!("{self} must be a positive number")),
+            },
+            ChannelSetting::ReplyToMessages
+            | ChannelSetting::MentionUser
+            | ChannelSetting::Mirroring
+            | ChannelSetting::Gamble
+            | ChannelSetting::Duel
+            | ChannelSetting::Slots
+            | ChannelSetting::AiMemory
+            | ChannelSetting::Markov
+            | ChannelSetting::ChatLogs => value
+                .parse::<bool>()
+                .map(|_| ())
+                .map_err(|_| format!("{self} must be true or false")),
+            ChannelSetting::RaidMinViewers => value
+                .parse::<u32>()
+                .map(|_| ())
+                .map_err(|_| format!("{self} must be a number")),
+            ChannelSetting::DiscordChannels => match parse_discord_channel_ids(value) {
+                Some(_) => Ok(()),
+                None => Err(format!("{self} must be Discord channel ids or mentions")),
+            },
+            ChannelSetting::GambleWinChance => match value.parse::<u32>() {
+                Ok(0..=100) => Ok(()),
+                _ => Err(format!("{self} must be a percentage from 0 to 100")),
+            },
+            ChannelSetting::SlotsCost => match value.parse::<u64>() {
+                Ok(cost) if cost > 0 => Ok(()),
+                _ => Err(format!("{self} must be a positive number")),
+            },
+            ChannelSetting::ResponsePrefix => match value.chars().count() {
+                0..=64 => Ok(()),
+                _ => Err(format!("{self} can be at most 64 characters")),
+            },
+            ChannelSetting::Language => match is_language_code(value) {
+                true => Ok(()),
+                false => Err(format!("{self} must be a language code such as en or uk")),
+            },
+            ChannelSetting::GeohubAnnounceTime => match parse_time_of_day(value) {
+                Some(_) => Ok(()),
+                None => Err(format!("{self} must be a time such as 20:00, or empty")),
+            },
+            ChannelSetting::ChatLogDays => match parse_optional_days(value) {
+                Some(_) => Ok(()),
+                None => Err(format!(
+                    "{self} must be a positive number of days, or empty"
+                )),
+            },
+            ChannelSetting::ErrorResponses => ErrorResponseMode::from_str(value)
+                .map(|_| ())
+                .map_err(|_| format!("{self} must be full, short or silent")),
+            ChannelSetting::ErrorTemplate => {
+                if value.chars().count() > MAX_ERROR_TEMPLATE_LENGTH {
+                    return Err(format!(
+                        "{self} can be at most {MAX_ERROR_TEMPLATE_LENGTH} characters"
+                    ));
+                }
+                handlebars::template::Template::compile(value)
+                    .map(|_| ())
+                    .map_err(|err| format!("{self} is not a valid template: {}", err.reason()))
+            }
+        }
+    }
+}
+
+#[derive(Queryable, Insertable, Debug, Clone)]
+#[diesel(table_name = channel_features)]
+pub struct ChannelFeatureValue {
+    pub channel_id: u64,
+    pub feature: String,
+    pub enabled: bool,
+}
+
+/// Features that the admin can turn on or off per channel, stored in the `channel_features` table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ChannelFeature {
+    /// Spam protection of chat messages, which the channel's mods configure with `protection`
+    SpamProtection,
+    /// AI completions, the channel owner still has to opt in with `aiconfig enable`
+    Ai,
+    /// Template helpers that are still being tried out, currently `supi`
+    BetaHelpers,
+}
+
+impl ChannelFeature {
+    pub const ALL: [ChannelFeature; 3] = [
+        ChannelFeature::SpamProtection,
+        ChannelFeature::Ai,
+        ChannelFeature::BetaHelpers,
+    ];
+
+    /// Experimental features are off unless enabled for the channel
+    pub fn enabled_by_default(&self) -> bool {
+        match self {
+            ChannelFeature::SpamProtection | ChannelFeature::Ai => true,
+            ChannelFeature::BetaHelpers => false,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ChannelFeatureState {
+    pub feature: String,
+    pub enabled: bool,
+    /// The flag isn't set for the channel, so the feature's default is used
+    pub is_default: bool,
+}
+
+/// A named snippet that the channel's template commands can include with `{{> name}}`
+#[derive(Queryable, Insertable, Serialize, Debug, Clone)]
+#[diesel(table_name = template_partials)]
+pub struct TemplatePartial {
+    pub channel_id: u64,
+    pub name: String,
+    pub body: String,
+}
+
+/// Typed view of the channel's settings, with defaults for the ones that are not set
+#[derive(Debug, Clone)]
+pub struct ChannelSettings {
+    pub channel_id: u64,
+    pub long_messages: LongMessageMode,
+    pub max_lines: u32,
+    pub reply_to_messages: bool,
+    pub mention_user: bool,
+    pub response_prefix: Option<String>,
+    pub mirroring: bool,
+    pub gamble: bool,
+    pub gamble_win_chance: u32,
+    pub duel: bool,
+    pub slots: bool,
+    pub slots_cost: u64,
+    pub language: Option<String>,
+    pub raid_min_viewers: u32,
+    pub discord_channels: Vec<u64>,
+    pub ai_memory: bool,
+    pub geohub_announce_time: Option<NaiveTime>,
+    pub markov: bool,
+    pub chat_logs: bool,
+    pub chat_log_days: Option<u32>,
+    pub error_responses: ErrorResponseMode,
+    pub error_template: Option<String>,
+}
+
+impl ChannelSettings {
+    pub fn new(channel_id: u64) -> Self {
+        Self {
+            channel_id,
+            long_messages: LongMessageMode::Split,
+            max_lines: 3,
+            reply_to_messages: true,
+            mention_user: false,
+            response_prefix: None,
+            mirroring: true,
+            gamble: false,
+            gamble_win_chance: 45,
+            duel: false,
+            slots: false,
+            slots_cost: 10,
+            language: None,
+            raid_min_viewers: 0,
+            discord_channels: Vec::new(),
+            ai_memory: false,
+            geohub_announce_time: None,
+            markov: false,
+            chat_logs: false,
+            chat_log_days: None,
+            error_responses: ErrorResponseMode::Full,
+            error_template: None,
+        }
+    }
+
+    /// Values that are unknown or invalid are ignored
+    pub fn from_values(channel_id: u64, values: &[ChannelSettingValue]) -> Self {
+        let mut settings = Self::new(channel_id);
+
+        for value in values {
+            let setting = match ChannelSetting::from_str(&value.name) {
+                Ok(setting) => setting,
+                Err(_) => continue,
+            };
+            let value = value.value.as_str();
+
+            match setting {
+                ChannelSetting::LongMessages => {
+                    if let Ok(mode) = value.parse() {
+                        settings.long_messages = mode;
+                    }
+                }
+                ChannelSetting::MaxLines => {
+                    if let Ok(lines) = value.parse() {
+                        settings.max_lines = lines;
+                    }
+                }
+                ChannelSetting::ReplyToMessages => {
+                    if let Ok(reply) = value.parse() {
+                        settings.reply_to_messages = reply;
+                    }
+                }
+                ChannelSetting::MentionUser => {
+                    if let Ok(mention) = value.parse() {
+                        settings.mention_user = mention;
+                    }
+                }
+                ChannelSetting::ResponsePrefix => {
+                    settings.response_prefix = Some(value.to_owned()).filter(|s| !s.is_empty());
+                }
+                ChannelSetting::Mirroring => {
+                    if let Ok(mirroring) = value.parse() {
+                        settings.mirroring = mirroring;
+                    }
+                }
+                ChannelSetting::Gamble => {
+                    if let Ok(gamble) = value.parse() {
+                        settings.gamble = gamble;
+                    }
+                }
+                ChannelSetting::GambleWinChance => {
+                    if let Ok(chance) = value.parse() {
+                        settings.gamble_win_chance = chance;
+                    }
+                }
+                ChannelSetting::Duel => {
+                    if let Ok(duel) = value.parse() {
+                        settings.duel = duel;
+                    }
+                }
+                ChannelSetting::Slots => {
+                    if let Ok(slots) = value.parse() {
+                        settings.slots = slots;
+                    }
+                }
+                ChannelSetting::SlotsCost => {
+                    if let Ok(cost) = value.parse() {
+                        settings.slots_cost = cost;
+                    }
+                }
+                ChannelSetting::Language => settings.language = Some(value.to_owned()),
+                ChannelSetting::RaidMinViewers => {
+                    if let Ok(viewers) = value.parse() {
+                        settings.raid_min_viewers = viewers;
+                    }
+                }
+                ChannelSetting::DiscordChannels => {
+                    if let Some(channels) = parse_discord_channel_ids(value) {
+                        settings.discord_channels = channels;
+                    }
+                }
+                ChannelSetting::AiMemory => {
+                    if let Ok(ai_memory) = value.parse() {
+                        settings.ai_memory = ai_memory;
+                    }
+                }
+                ChannelSetting::GeohubAnnounceTime => {
+                    settings.geohub_announce_time = parse_time_of_day(value).flatten();
+                }
+                ChannelSetting::Markov => {
+                    if let Ok(markov) = value.parse() {
+                        settings.markov = markov;
+                    }
+                }
+                ChannelSetting::ChatLogs => {
+                    if let Ok(chat_logs) = value.parse() {
+                        settings.chat_logs = chat_logs;
+                    }
+                }
+                ChannelSetting::ChatLogDays => {
+                    settings.chat_log_days = parse_optional_days(value).flatten();
+                }
+                ChannelSetting::ErrorResponses => {
+                    if let Ok(mode) = value.parse() {
+                        settings.error_responses = mode;
+                    }
+                }
+                ChannelSetting::ErrorTemplate => {
+                    settings.error_template = Some(value.to_owned()).filter(|s| !s.is_empty());
+                }
+            }
+        }
+
+        settings
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum LongMessageMode {
+    Split,
+    Truncate,
+    /// Command responses are uploaded as a paste and linked
+    Paste,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ErrorResponseMode {
+    /// The error message, formatted with the channel's error template if it has one
+    Full,
+    /// Only an emoji
+    Short,
+    /// Nothing is sent
+    Silent,
+}
+
+/// A pajbot-compatible API that outgoing messages are checked against
+#[derive(Queryable, Insertable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = banphrase_apis)]
+pub struct BanphraseApiConfig {
+    #[serde(skip)]
+    pub channel_id: u64,
+    pub url: String,
+    /// Banned messages are masked otherwise
+    pub block_message: bool,
 }
 
 #[derive(Queryable, Insertable)]
@@ -175,6 +914,14 @@ pub struct HebiData {
     pub value: Option<String>,
 }
 
+#[derive(Queryable, Insertable, Debug, Clone)]
+#[diesel(table_name = points)]
+pub struct PointsBalance {
+    pub channel_id: u64,
+    pub user_id: u64,
+    pub balance: u64,
+}
+
 #[derive(Queryable, Insertable)]
 #[diesel(table_name = geohub_link)]
 pub struct GeohubLink {
@@ -183,11 +930,327 @@ pub struct GeohubLink {
     pub geohub_name: String,
 }
 
+/// The score of a GeoHub user in the daily challenge of a day (UTC)
+#[derive(Queryable, Insertable, Debug, Clone, PartialEq, Eq)]
+#[diesel(table_name = geohub_results)]
+pub struct GeohubResult {
+    pub geohub_name: String,
+    pub date: NaiveDate,
+    pub total_points: u32,
+    /// Seconds
+    pub total_time: u32,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct ChatLog {
+    pub id: u64,
+    pub channel_id: u64,
+    pub user_id: u64,
+    pub text: String,
+    /// UTC
+    pub sent_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = chat_logs)]
+pub struct NewChatLog<'a> {
+    pub channel_id: u64,
+    pub user_id: u64,
+    pub text: &'a str,
+    pub sent_at: NaiveDateTime,
+}
+
+/// A chat message that markov sentences of the channel are generated from
+#[derive(Insertable, Debug)]
+#[diesel(table_name = markov_messages)]
+pub struct NewMarkovMessage<'a> {
+    pub channel_id: u64,
+    pub user_id: u64,
+    pub text: &'a str,
+}
+
+#[derive(Queryable, Serialize)]
+pub struct CustomHook {
+    pub channel_id: u64,
+    pub id: String,
+    #[serde(skip)]
+    pub token: String,
+    pub action: String,
+    #[diesel(deserialize_as = String)]
+    pub mode: CommandMode,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = custom_hooks)]
+pub struct NewCustomHook<'a> {
+    pub channel_id: u64,
+    pub id: &'a str,
+    pub token: &'a str,
+    pub action: &'a str,
+    pub execution_mode: &'a str,
+}
+
+#[derive(Queryable, Insertable, Debug, Clone)]
+#[diesel(table_name = github_hooks)]
+pub struct GithubHook {
+    pub channel_id: u64,
+    pub secret: String,
+    pub push_template: Option<String>,
+    pub release_template: Option<String>,
+    pub issues_template: Option<String>,
+    pub pull_request_template: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum GithubEvent {
+    Push,
+    Release,
+    Issues,
+    PullRequest,
+}
+
+impl GithubEvent {
+    pub fn default_template(&self) -> &'static str {
+        match self {
+            GithubEvent::Push => {
+                "[{{repository.full_name}}] {{pusher.name}} pushed to {{ref}}: \
+                 {{head_commit.message}} {{compare}}"
+            }
+            GithubEvent::Release => {
+                "[{{repository.full_name}}] Release {{release.name}} {{action}}: \
+                 {{release.html_url}}"
+            }
+            GithubEvent::Issues => {
+                "[{{repository.full_name}}] {{sender.login}} {{action}} issue \
+                 #{{issue.number}}: {{issue.title}} {{issue.html_url}}"
+            }
+            GithubEvent::PullRequest => {
+                "[{{repository.full_name}}] {{sender.login}} {{action}} pull request \
+                 #{{pull_request.number}}: {{pull_request.title}} {{pull_request.html_url}}"
+            }
+        }
+    }
+
+    /// Whether an event with the given action should be announced, to avoid spamming on every label change
+    pub fn should_announce(&self, action: Option<&str>) -> bool {
+        match self {
+            GithubEvent::Push => true,
+            GithubEvent::Release => action == Some("published"),
+            GithubEvent::Issues | GithubEvent::PullRequest => {
+                matches!(action, Some("opened" | "closed" | "reopened"))
+            }
+        }
+    }
+}
+
+impl GithubHook {
+    pub fn get_template(&self, event: GithubEvent) -> &str {
+        let template = match event {
+            GithubEvent::Push => &self.push_template,
+            GithubEvent::Release => &self.release_template,
+            GithubEvent::Issues => &self.issues_template,
+            GithubEvent::PullRequest => &self.pull_request_template,
+        };
+
+        template
+            .as_deref()
+            .unwrap_or_else(|| event.default_template())
+    }
+
+    pub fn set_template(&mut self, event: GithubEvent, template: Option<String>) {
+        let field = match event {
+            GithubEvent::Push => &mut self.push_template,
+            GithubEvent::Release => &mut self.release_template,
+            GithubEvent::Issues => &mut self.issues_template,
+            GithubEvent::PullRequest => &mut self.pull_request_template,
+        };
+
+        *field = template;
+    }
+}
+
+#[derive(Queryable, Serialize)]
+pub struct OutgoingWebhook {
+    pub id: u64,
+    #[serde(skip)]
+    pub channel_id: u64,
+    pub url: String,
+    #[serde(skip)]
+    pub secret: String,
+    pub events: String,
+}
+
+impl OutgoingWebhook {
+    pub fn is_subscribed(&self, event: WebhookEvent) -> bool {
+        let event = event.to_string();
+        self.events.split(',').any(|name| name.trim() == event)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = outgoing_webhooks)]
+pub struct NewOutgoingWebhook<'a> {
+    pub channel_id: u64,
+    pub url: &'a str,
+    pub secret: &'a str,
+    pub events: &'a str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum WebhookEvent {
+    MessageHandled,
+    CommandExecuted,
+    FilterMatched,
+    UserTimedOut,
+    EventsubTriggered,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = ai_usage)]
+pub struct NewAiUsage<'a> {
+    pub channel_id: u64,
+    pub user_id: u64,
+    pub model: &'a str,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub cost: f64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = moderation_log)]
+pub struct NewModerationLogEntry<'a> {
+    pub channel_id: u64,
+    /// Not set for actions that the bot takes automatically
+    pub moderator_id: Option<u64>,
+    pub target: &'a str,
+    pub action: &'a str,
+    /// Seconds, not set for permanent bans
+    pub duration: Option<u32>,
+    pub reason: Option<&'a str>,
+}
+
+#[derive(Queryable, Serialize, Debug)]
+pub struct ModerationLogEntry {
+    pub id: u64,
+    #[serde(skip)]
+    pub channel_id: u64,
+    pub moderator_id: Option<u64>,
+    pub target: String,
+    pub action: String,
+    pub duration: Option<u32>,
+    pub reason: Option<String>,
+    /// UTC
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = stream_highlights)]
+pub struct NewStreamHighlight<'a> {
+    pub channel_id: u64,
+    pub user_id: Option<u64>,
+    pub note: &'a str,
+    /// Not set if the channel wasn't live
+    pub stream_id: Option<&'a str>,
+    pub position_seconds: Option<u32>,
+}
+
+/// A moment of the stream noted with the `highlight` command, for editors to find after the stream
+#[derive(Queryable, Serialize, Debug)]
+pub struct StreamHighlight {
+    pub id: u64,
+    #[serde(skip)]
+    pub channel_id: u64,
+    pub user_id: Option<u64>,
+    pub note: String,
+    pub stream_id: Option<String>,
+    /// Seconds since the start of the stream
+    pub position_seconds: Option<u32>,
+    /// UTC
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ModerationAction {
+    Timeout,
+    Ban,
+    Purge,
+    Nuke,
+    /// A message filter matched a response
+    Filter,
+    Delete,
+}
+
+#[derive(Debug, Default)]
+pub struct ModerationLogFilter {
+    pub target: Option<String>,
+    pub action: Option<ModerationAction>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+/// Spam protection configuration, the checks that are not set are disabled
+#[derive(Queryable, Insertable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = protection_settings)]
+pub struct ProtectionSettings {
+    #[serde(skip)]
+    pub channel_id: u64,
+    pub enabled: bool,
+    /// Messages from a single user in 10 seconds
+    pub max_message_rate: Option<u32>,
+    /// Identical messages from a single user in a minute
+    pub max_repeats: Option<u32>,
+    pub max_emotes: Option<u32>,
+    /// Only applied to messages with at least 10 letters
+    pub max_caps_percent: Option<u32>,
+    pub max_links: Option<u32>,
+    /// Unique users mentioned in a message
+    pub max_mentions: Option<u32>,
+    pub action: String,
+    /// Seconds, doubled with every repeated offense
+    pub timeout_duration: u32,
+}
+
+impl ProtectionSettings {
+    pub fn new(channel_id: u64) -> Self {
+        Self {
+            channel_id,
+            enabled: false,
+            max_message_rate: None,
+            max_repeats: None,
+            max_emotes: None,
+            max_caps_percent: None,
+            max_links: None,
+            max_mentions: None,
+            action: ProtectionAction::Delete.to_string(),
+            timeout_duration: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ProtectionAction {
+    Delete,
+    Timeout,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AiUsageSummary {
+    pub requests: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+}
+
 #[cfg(test)]
 mod tests {
     use crate::platform::ChannelIdentifier;
 
-    use super::Channel;
+    use super::{is_in_discord_channels, parse_discord_channel_ids, parse_time_of_day, Channel};
+    use chrono::NaiveTime;
 
     #[test]
     fn channel_to_identifier() {
@@ -195,6 +1258,7 @@ mod tests {
             id: 1,
             platform: String::from("twitch"),
             channel: String::from("123"),
+            joined: true,
         };
 
         assert_eq!(
@@ -202,4 +1266,29 @@ mod tests {
             ChannelIdentifier::TwitchChannel((String::from("123"), None))
         )
     }
+
+    #[test]
+    fn discord_channel_scoping() {
+        assert_eq!(
+            parse_discord_channel_ids("123 <#456>"),
+            Some(vec![123, 456])
+        );
+        assert_eq!(parse_discord_channel_ids("general"), None);
+
+        assert!(is_in_discord_channels(Some("123 456"), Some(456)));
+        assert!(!is_in_discord_channels(Some("123 456"), Some(789)));
+        assert!(is_in_discord_channels(None, Some(789)));
+        assert!(is_in_discord_channels(Some(""), Some(789)));
+        assert!(is_in_discord_channels(Some("123"), None));
+    }
+
+    #[test]
+    fn parses_time_of_day() {
+        assert_eq!(
+            parse_time_of_day("20:30"),
+            Some(NaiveTime::from_hms_opt(20, 30, 0))
+        );
+        assert_eq!(parse_time_of_day(""), Some(None));
+        assert_eq!(parse_time_of_day("8pm"), None);
+    }
 }
@@ -0,0 +1,122 @@
+//! Per-channel points that the mini-games are played with.
+//!
+//! Users that don't have a balance in the channel yet start with [`STARTING_POINTS`].
+//! Balances are read with `FOR UPDATE` and written in the same transaction, so that
+//! concurrent games of the same user can't spend the same points twice.
+use diesel::mysql::MysqlConnection;
+use diesel::{Connection, OptionalExtension, QueryDsl, QueryResult, RunQueryDsl};
+
+use super::models::PointsBalance;
+use super::schema::points;
+use super::{Database, DatabaseError};
+
+const STARTING_POINTS: u64 = 100;
+
+impl Database {
+    pub fn get_points(&self, channel_id: u64, user_id: u64) -> Result<u64, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let balance = points::table
+            .find((channel_id, user_id))
+            .select(points::balance)
+            .first(&mut conn)
+            .optional()?;
+
+        Ok(balance.unwrap_or(STARTING_POINTS))
+    }
+
+    /// Returns the new balance
+    pub fn add_points(
+        &self,
+        channel_id: u64,
+        user_id: u64,
+        amount: u64,
+    ) -> Result<u64, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(conn.transaction(|conn| {
+            let balance = lock_balance(conn, channel_id, user_id)?.saturating_add(amount);
+            set_balance(conn, channel_id, user_id, balance)?;
+            Ok::<_, diesel::result::Error>(balance)
+        })?)
+    }
+
+    /// Takes the stake from the user's balance and adds the payout to it.
+    /// Returns the new balance, or `None` if the balance is lower than the stake.
+    pub fn play_points(
+        &self,
+        channel_id: u64,
+        user_id: u64,
+        stake: u64,
+        payout: u64,
+    ) -> Result<Option<u64>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(conn.transaction(|conn| {
+            let balance = lock_balance(conn, channel_id, user_id)?;
+            if balance < stake {
+                return Ok(None);
+            }
+
+            let balance = (balance - stake).saturating_add(payout);
+            set_balance(conn, channel_id, user_id, balance)?;
+            Ok::<_, diesel::result::Error>(Some(balance))
+        })?)
+    }
+
+    /// Returns `false` without moving any points if the sender's balance is lower than the amount
+    pub fn transfer_points(
+        &self,
+        channel_id: u64,
+        from_user_id: u64,
+        to_user_id: u64,
+        amount: u64,
+    ) -> Result<bool, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(conn.transaction(|conn| {
+            let from_balance = lock_balance(conn, channel_id, from_user_id)?;
+            let to_balance = lock_balance(conn, channel_id, to_user_id)?;
+            if from_balance < amount {
+                return Ok(false);
+            }
+
+            set_balance(conn, channel_id, from_user_id, from_balance - amount)?;
+            set_balance(
+                conn,
+                channel_id,
+                to_user_id,
+                to_balance.saturating_add(amount),
+            )?;
+            Ok::<_, diesel::result::Error>(true)
+        })?)
+    }
+}
+
+fn lock_balance(conn: &mut MysqlConnection, channel_id: u64, user_id: u64) -> QueryResult<u64> {
+    let balance = points::table
+        .find((channel_id, user_id))
+        .select(points::balance)
+        .for_update()
+        .first(conn)
+        .optional()?;
+
+    Ok(balance.unwrap_or(STARTING_POINTS))
+}
+
+fn set_balance(
+    conn: &mut MysqlConnection,
+    channel_id: u64,
+    user_id: u64,
+    balance: u64,
+) -> QueryResult<()> {
+    diesel::replace_into(points::table)
+        .values(PointsBalance {
+            channel_id,
+            user_id,
+            balance,
+        })
+        .execute(conn)?;
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use diesel::mysql::MysqlConnection;
+use diesel::r2d2::event::{CheckinEvent, CheckoutEvent, HandleEvent, TimeoutEvent};
+use diesel::r2d2::{ConnectionManager, Pool, PoolError};
+
+use crate::config::DatabaseConfig;
+
+pub fn build_pool(
+    config: &DatabaseConfig,
+    manager: ConnectionManager<MysqlConnection>,
+) -> Result<Pool<ConnectionManager<MysqlConnection>>, PoolError> {
+    let connection_timeout = Duration::from_secs(config.pool_timeout);
+
+    tracing::info!(
+        "Creating DB pool with max size {}, min idle {:?}, timeout {:?}",
+        config.pool_size,
+        config.pool_min_idle,
+        connection_timeout
+    );
+
+    Pool::builder()
+        .max_size(config.pool_size)
+        .min_idle(config.pool_min_idle)
+        .connection_timeout(connection_timeout)
+        .event_handler(Box::new(PoolMetrics {
+            slow_query_threshold: Duration::from_millis(config.slow_query_ms),
+        }))
+        .build(manager)
+}
+
+/// Reports connection checkout and usage timings to the tracing layers
+#[derive(Debug)]
+struct PoolMetrics {
+    slow_query_threshold: Duration,
+}
+
+impl HandleEvent for PoolMetrics {
+    fn handle_checkout(&self, event: CheckoutEvent) {
+        tracing::debug!(
+            connection_id = event.connection_id(),
+            wait_ms = event.duration().as_millis() as u64,
+            "DB connection checked out"
+        );
+    }
+
+    fn handle_timeout(&self, event: TimeoutEvent) {
+        tracing::warn!(
+            timeout_ms = event.timeout().as_millis() as u64,
+            "Timed out waiting for a DB connection"
+        );
+    }
+
+    fn handle_checkin(&self, event: CheckinEvent) {
+        let duration = event.duration();
+
+        if duration > self.slow_query_threshold {
+            tracing::warn!(
+                connection_id = event.connection_id(),
+                held_ms = duration.as_millis() as u64,
+                "DB connection was held for longer than {:?}",
+                self.slow_query_threshold
+            );
+        } else {
+            tracing::debug!(
+                connection_id = event.connection_id(),
+                held_ms = duration.as_millis() as u64,
+                "DB connection returned"
+            );
+        }
+    }
+}
@@ -1,5 +1,39 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    ai_channels (channel_id) {
+        channel_id -> Unsigned<Bigint>,
+    }
+}
+
+diesel::table! {
+    ai_usage (id) {
+        id -> Unsigned<Bigint>,
+        channel_id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        #[max_length = 255]
+        model -> Varchar,
+        prompt_tokens -> Unsigned<Integer>,
+        completion_tokens -> Unsigned<Integer>,
+        cost -> Double,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    api_tokens (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        #[max_length = 255]
+        name -> Varchar,
+        username -> Text,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        #[max_length = 16]
+        scope -> Varchar,
+    }
+}
+
 diesel::table! {
     auth (name) {
         #[max_length = 255]
@@ -9,6 +43,43 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    banphrase_apis (channel_id) {
+        channel_id -> Unsigned<Bigint>,
+        #[max_length = 255]
+        url -> Varchar,
+        block_message -> Bool,
+    }
+}
+
+diesel::table! {
+    blocked_users (id) {
+        id -> Unsigned<Bigint>,
+        #[max_length = 255]
+        identifier -> Varchar,
+        channel_id -> Nullable<Unsigned<Bigint>>,
+    }
+}
+
+diesel::table! {
+    channel_features (channel_id, feature) {
+        channel_id -> Unsigned<Bigint>,
+        #[max_length = 64]
+        feature -> Varchar,
+        enabled -> Bool,
+    }
+}
+
+diesel::table! {
+    channel_settings (channel_id, name) {
+        channel_id -> Unsigned<Bigint>,
+        #[max_length = 64]
+        name -> Varchar,
+        #[max_length = 255]
+        value -> Varchar,
+    }
+}
+
 diesel::table! {
     channels (id) {
         id -> Unsigned<Bigint>,
@@ -16,6 +87,28 @@ diesel::table! {
         platform -> Varchar,
         #[max_length = 255]
         channel -> Varchar,
+        joined -> Bool,
+    }
+}
+
+diesel::table! {
+    chat_logs (id, sent_at) {
+        id -> Unsigned<Bigint>,
+        channel_id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        text -> Text,
+        sent_at -> Datetime,
+    }
+}
+
+diesel::table! {
+    command_tests (id) {
+        id -> Unsigned<Bigint>,
+        channel_id -> Unsigned<Bigint>,
+        #[max_length = 255]
+        command_name -> Varchar,
+        args -> Text,
+        expected -> Text,
     }
 }
 
@@ -30,6 +123,42 @@ diesel::table! {
         triggers -> Nullable<Text>,
         #[max_length = 127]
         mode -> Varchar,
+        discord_channels -> Nullable<Text>,
+        args_spec -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    connector_users (platform, connector_user_id) {
+        #[max_length = 127]
+        platform -> Varchar,
+        #[max_length = 255]
+        connector_user_id -> Varchar,
+        user_id -> Unsigned<Bigint>,
+    }
+}
+
+diesel::table! {
+    custom_hooks (channel_id, id) {
+        channel_id -> Unsigned<Bigint>,
+        #[max_length = 127]
+        id -> Varchar,
+        #[max_length = 255]
+        token -> Varchar,
+        action -> Mediumtext,
+        #[max_length = 127]
+        execution_mode -> Varchar,
+        paused -> Bool,
+        secret_id -> Nullable<Unsigned<Bigint>>,
+    }
+}
+
+diesel::table! {
+    eventsub_secrets (id) {
+        id -> Unsigned<Bigint>,
+        #[max_length = 100]
+        secret -> Varchar,
+        created_at -> Timestamp,
     }
 }
 
@@ -45,6 +174,8 @@ diesel::table! {
         id -> Varchar,
         #[max_length = 127]
         execution_mode -> Varchar,
+        paused -> Bool,
+        secret_id -> Nullable<Unsigned<Bigint>>,
     }
 }
 
@@ -56,6 +187,7 @@ diesel::table! {
         block_message -> Bool,
         #[max_length = 255]
         replacement -> Nullable<Varchar>,
+        discord_channels -> Nullable<Text>,
     }
 }
 
@@ -68,6 +200,39 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    geohub_results (geohub_name, date) {
+        #[max_length = 255]
+        geohub_name -> Varchar,
+        date -> Date,
+        total_points -> Unsigned<Integer>,
+        total_time -> Unsigned<Integer>,
+    }
+}
+
+diesel::table! {
+    github_hooks (channel_id) {
+        channel_id -> Unsigned<Bigint>,
+        #[max_length = 255]
+        secret -> Varchar,
+        push_template -> Nullable<Text>,
+        release_template -> Nullable<Text>,
+        issues_template -> Nullable<Text>,
+        pull_request_template -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    global_commands (name) {
+        #[max_length = 255]
+        name -> Varchar,
+        action -> Text,
+        #[max_length = 127]
+        mode -> Varchar,
+        cooldown -> Nullable<Unsigned<Bigint>>,
+    }
+}
+
 diesel::table! {
     hebi_data (channel_id, name) {
         channel_id -> Unsigned<Bigint>,
@@ -77,6 +242,23 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    ignored_users (channel_id, identifier) {
+        channel_id -> Unsigned<Bigint>,
+        #[max_length = 255]
+        identifier -> Varchar,
+    }
+}
+
+diesel::table! {
+    markov_messages (id) {
+        id -> Unsigned<Bigint>,
+        channel_id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        text -> Text,
+    }
+}
+
 diesel::table! {
     mirror_connections (from_channel_id, to_channel_id) {
         from_channel_id -> Unsigned<Bigint>,
@@ -84,6 +266,64 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    moderation_log (id) {
+        id -> Unsigned<Bigint>,
+        channel_id -> Unsigned<Bigint>,
+        moderator_id -> Nullable<Unsigned<Bigint>>,
+        #[max_length = 255]
+        target -> Varchar,
+        #[max_length = 16]
+        action -> Varchar,
+        duration -> Nullable<Unsigned<Integer>>,
+        reason -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    outgoing_webhooks (id) {
+        id -> Unsigned<Bigint>,
+        channel_id -> Unsigned<Bigint>,
+        url -> Text,
+        #[max_length = 255]
+        secret -> Varchar,
+        #[max_length = 255]
+        events -> Varchar,
+    }
+}
+
+diesel::table! {
+    pastes (id) {
+        #[max_length = 32]
+        id -> Varchar,
+        #[max_length = 255]
+        filename -> Varchar,
+        content -> Mediumblob,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    personal_commands (user_id, name) {
+        user_id -> Unsigned<Bigint>,
+        #[max_length = 255]
+        name -> Varchar,
+        action -> Text,
+        #[max_length = 127]
+        mode -> Varchar,
+    }
+}
+
+diesel::table! {
+    points (channel_id, user_id) {
+        channel_id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        balance -> Unsigned<Bigint>,
+    }
+}
+
 diesel::table! {
     prefixes (channel_id) {
         channel_id -> Unsigned<Bigint>,
@@ -91,6 +331,62 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    protection_settings (channel_id) {
+        channel_id -> Unsigned<Bigint>,
+        enabled -> Bool,
+        max_message_rate -> Nullable<Unsigned<Integer>>,
+        max_repeats -> Nullable<Unsigned<Integer>>,
+        max_emotes -> Nullable<Unsigned<Integer>>,
+        max_caps_percent -> Nullable<Unsigned<Integer>>,
+        max_links -> Nullable<Unsigned<Integer>>,
+        max_mentions -> Nullable<Unsigned<Integer>>,
+        #[max_length = 16]
+        action -> Varchar,
+        timeout_duration -> Unsigned<Integer>,
+    }
+}
+
+diesel::table! {
+    shard_leases (shard_id) {
+        #[max_length = 64]
+        shard_id -> Varchar,
+        expires_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    stream_highlights (id) {
+        id -> Unsigned<Bigint>,
+        channel_id -> Unsigned<Bigint>,
+        user_id -> Nullable<Unsigned<Bigint>>,
+        note -> Text,
+        #[max_length = 64]
+        stream_id -> Nullable<Varchar>,
+        position_seconds -> Nullable<Unsigned<Integer>>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    template_data (channel_id, name) {
+        channel_id -> Unsigned<Bigint>,
+        #[max_length = 255]
+        name -> Varchar,
+        value -> Text,
+        expires_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    template_partials (channel_id, name) {
+        channel_id -> Unsigned<Bigint>,
+        #[max_length = 64]
+        name -> Varchar,
+        body -> Text,
+    }
+}
+
 diesel::table! {
     user_data (user_id, name) {
         #[max_length = 255]
@@ -102,6 +398,31 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    user_merges (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        merged_user_id -> Unsigned<Bigint>,
+        #[max_length = 255]
+        identity -> Varchar,
+        #[max_length = 16]
+        method -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    user_names (user_id, platform, name) {
+        user_id -> Unsigned<Bigint>,
+        #[max_length = 16]
+        platform -> Varchar,
+        #[max_length = 255]
+        name -> Varchar,
+        first_seen_at -> Timestamp,
+        last_seen_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Unsigned<Bigint>,
@@ -110,7 +431,8 @@ diesel::table! {
         irc_name -> Nullable<Text>,
         local_addr -> Nullable<Text>,
         telegram_id -> Nullable<Text>,
-        matrix_id -> Nullable<Text>,
+        github_id -> Nullable<Text>,
+        google_id -> Nullable<Text>,
     }
 }
 
@@ -120,29 +442,90 @@ diesel::table! {
         session_id -> Varchar,
         user_id -> Unsigned<Bigint>,
         username -> Text,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+        user_agent -> Nullable<Text>,
     }
 }
 
+diesel::joinable!(ai_channels -> channels (channel_id));
+diesel::joinable!(ai_usage -> channels (channel_id));
+diesel::joinable!(ai_usage -> users (user_id));
+diesel::joinable!(api_tokens -> users (user_id));
+diesel::joinable!(banphrase_apis -> channels (channel_id));
+diesel::joinable!(blocked_users -> channels (channel_id));
+diesel::joinable!(channel_features -> channels (channel_id));
+diesel::joinable!(channel_settings -> channels (channel_id));
+diesel::joinable!(command_tests -> channels (channel_id));
 diesel::joinable!(commands -> channels (channel_id));
+diesel::joinable!(connector_users -> users (user_id));
+diesel::joinable!(custom_hooks -> channels (channel_id));
+diesel::joinable!(eventsub_triggers -> eventsub_secrets (secret_id));
 diesel::joinable!(filters -> channels (channel_id));
 diesel::joinable!(geohub_link -> channels (channel_id));
 diesel::joinable!(geohub_link -> users (user_id));
+diesel::joinable!(github_hooks -> channels (channel_id));
 diesel::joinable!(hebi_data -> channels (channel_id));
+diesel::joinable!(ignored_users -> channels (channel_id));
+diesel::joinable!(markov_messages -> channels (channel_id));
+diesel::joinable!(markov_messages -> users (user_id));
+diesel::joinable!(moderation_log -> channels (channel_id));
+diesel::joinable!(moderation_log -> users (moderator_id));
+diesel::joinable!(outgoing_webhooks -> channels (channel_id));
+diesel::joinable!(personal_commands -> users (user_id));
+diesel::joinable!(points -> channels (channel_id));
+diesel::joinable!(points -> users (user_id));
 diesel::joinable!(prefixes -> channels (channel_id));
+diesel::joinable!(protection_settings -> channels (channel_id));
+diesel::joinable!(stream_highlights -> channels (channel_id));
+diesel::joinable!(stream_highlights -> users (user_id));
+diesel::joinable!(template_data -> channels (channel_id));
+diesel::joinable!(template_partials -> channels (channel_id));
 diesel::joinable!(user_data -> users (user_id));
+diesel::joinable!(user_merges -> users (user_id));
+diesel::joinable!(user_names -> users (user_id));
 diesel::joinable!(web_sessions -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    ai_channels,
+    ai_usage,
+    api_tokens,
     auth,
+    banphrase_apis,
+    blocked_users,
+    channel_features,
+    channel_settings,
     channels,
+    chat_logs,
+    command_tests,
     commands,
+    connector_users,
+    custom_hooks,
+    eventsub_secrets,
     eventsub_triggers,
     filters,
     geohub_link,
+    geohub_results,
+    github_hooks,
+    global_commands,
     hebi_data,
+    ignored_users,
+    markov_messages,
     mirror_connections,
+    moderation_log,
+    outgoing_webhooks,
+    pastes,
+    personal_commands,
+    points,
     prefixes,
+    protection_settings,
+    shard_leases,
+    stream_highlights,
+    template_data,
+    template_partials,
     user_data,
+    user_merges,
+    user_names,
     users,
     web_sessions,
 );
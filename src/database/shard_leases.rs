@@ -0,0 +1,56 @@
+//! Leases of the bot instances that run in sharding mode.
+//!
+//! Every shard renews its lease periodically, the shards with an unexpired lease split
+//! the channels between each other. The DB clock is used for the expiry, so that the clocks
+//! of the shards don't have to agree.
+use diesel::sql_types::{BigInt, Unsigned, Varchar};
+use diesel::{sql_query, RunQueryDsl};
+
+use super::{Database, DatabaseError};
+
+#[derive(QueryableByName)]
+struct ActiveShard {
+    #[diesel(sql_type = Varchar)]
+    shard_id: String,
+}
+
+impl Database {
+    /// Creates the lease of the shard or extends it to expire in `ttl_secs`
+    pub fn renew_shard_lease(&self, shard_id: &str, ttl_secs: u64) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        sql_query(
+            "INSERT INTO shard_leases(shard_id, expires_at) \
+            VALUES (?, UTC_TIMESTAMP() + INTERVAL ? SECOND) \
+            ON DUPLICATE KEY UPDATE expires_at = VALUES(expires_at)",
+        )
+        .bind::<Varchar, _>(shard_id)
+        .bind::<Unsigned<BigInt>, _>(ttl_secs)
+        .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// Ids of the shards with an unexpired lease, sorted
+    pub fn get_active_shards(&self) -> Result<Vec<String>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let shards: Vec<ActiveShard> = sql_query(
+            "SELECT shard_id FROM shard_leases WHERE expires_at > UTC_TIMESTAMP() \
+            ORDER BY shard_id",
+        )
+        .load(&mut conn)?;
+
+        Ok(shards.into_iter().map(|shard| shard.shard_id).collect())
+    }
+
+    /// Returns the amount of removed leases
+    pub fn remove_expired_shard_leases(&self) -> Result<usize, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(
+            sql_query("DELETE FROM shard_leases WHERE expires_at <= UTC_TIMESTAMP()")
+                .execute(&mut conn)?,
+        )
+    }
+}
@@ -0,0 +1,103 @@
+//! Values stored by templates with `data_set` and read with `data_get`.
+//!
+//! Values are stored per channel and can expire. Expired values are ignored when reading
+//! and removed periodically.
+use chrono::{Duration, Utc};
+use diesel::{
+    dsl::count_star, BoolExpressionMethods, Connection, ExpressionMethods, OptionalExtension,
+    QueryDsl, RunQueryDsl,
+};
+
+use super::schema::template_data;
+use super::{Database, DatabaseError};
+
+impl Database {
+    pub fn get_template_data(
+        &self,
+        channel_id: u64,
+        key: &str,
+    ) -> Result<Option<String>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+        let now = Utc::now().naive_utc();
+
+        Ok(template_data::table
+            .select(template_data::value)
+            .filter(template_data::channel_id.eq(channel_id))
+            .filter(template_data::name.eq(key))
+            .filter(
+                template_data::expires_at
+                    .is_null()
+                    .or(template_data::expires_at.gt(now)),
+            )
+            .first(&mut conn)
+            .optional()?)
+    }
+
+    pub fn get_template_data_keys(&self, channel_id: u64) -> Result<Vec<String>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+        let now = Utc::now().naive_utc();
+
+        Ok(template_data::table
+            .select(template_data::name)
+            .filter(template_data::channel_id.eq(channel_id))
+            .filter(
+                template_data::expires_at
+                    .is_null()
+                    .or(template_data::expires_at.gt(now)),
+            )
+            .order(template_data::name)
+            .load(&mut conn)?)
+    }
+
+    /// The value is kept until it's replaced when there's no `ttl`.
+    /// New keys can't be added once the channel has `max_keys` keys
+    pub fn set_template_data(
+        &self,
+        channel_id: u64,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+        max_keys: u32,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+        let now = Utc::now().naive_utc();
+        let expires_at = ttl.map(|ttl| now + ttl);
+
+        conn.transaction(|conn| {
+            let other_keys: i64 = template_data::table
+                .select(count_star())
+                .filter(template_data::channel_id.eq(channel_id))
+                .filter(template_data::name.ne(key))
+                .filter(
+                    template_data::expires_at
+                        .is_null()
+                        .or(template_data::expires_at.gt(now)),
+                )
+                .first(conn)?;
+
+            if other_keys >= max_keys.into() {
+                return Err(DatabaseError::TemplateDataQuota(max_keys));
+            }
+
+            diesel::replace_into(template_data::table)
+                .values((
+                    template_data::channel_id.eq(channel_id),
+                    template_data::name.eq(key),
+                    template_data::value.eq(value),
+                    template_data::expires_at.eq(expires_at),
+                ))
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+
+    /// Returns the amount of removed values
+    pub fn remove_expired_template_data(&self) -> Result<usize, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(diesel::delete(template_data::table)
+            .filter(template_data::expires_at.le(Utc::now().naive_utc()))
+            .execute(&mut conn)?)
+    }
+}
@@ -0,0 +1,75 @@
+//! Named template snippets of a channel, included in its template commands with `{{> name}}`.
+//!
+//! The partials of a channel are cached, as they are needed whenever a template command is
+//! executed in it
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use super::models::TemplatePartial;
+use super::schema::template_partials;
+use super::{Database, DatabaseError};
+
+impl Database {
+    /// Sorted by name
+    pub fn get_template_partials(
+        &self,
+        channel_id: u64,
+    ) -> Result<Vec<TemplatePartial>, DatabaseError> {
+        if let Some(partials) = self.template_partials_cache.get(&channel_id) {
+            return Ok(partials);
+        }
+
+        let mut conn = self.get_conn()?;
+
+        let partials: Vec<TemplatePartial> = template_partials::table
+            .filter(template_partials::channel_id.eq(channel_id))
+            .order(template_partials::name)
+            .load(&mut conn)?;
+
+        self.template_partials_cache
+            .insert(channel_id, partials.clone());
+
+        Ok(partials)
+    }
+
+    /// Replaces the partial if the channel already has one with this name
+    pub fn set_template_partial(
+        &self,
+        channel_id: u64,
+        name: &str,
+        body: &str,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        diesel::replace_into(template_partials::table)
+            .values(TemplatePartial {
+                channel_id,
+                name: name.to_owned(),
+                body: body.to_owned(),
+            })
+            .execute(&mut conn)?;
+
+        self.template_partials_cache.remove(&channel_id);
+
+        Ok(())
+    }
+
+    /// Returns false when the channel has no partial with this name
+    pub fn delete_template_partial(
+        &self,
+        channel_id: u64,
+        name: &str,
+    ) -> Result<bool, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        let deleted = diesel::delete(
+            template_partials::table
+                .filter(template_partials::channel_id.eq(channel_id))
+                .filter(template_partials::name.eq(name)),
+        )
+        .execute(&mut conn)?;
+
+        self.template_partials_cache.remove(&channel_id);
+
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,85 @@
+//! Names that users have been seen with on each platform.
+//!
+//! Names are recorded from the metadata of chat messages, and the names of Twitch users
+//! who haven't been seen in a while are looked up periodically, so renames are noticed
+//! even when the user doesn't chat.
+use chrono::{Duration, Utc};
+use diesel::sql_types::{BigInt, Integer, Timestamp, Unsigned, Varchar};
+use diesel::{sql_query, ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use super::models::UserName;
+use super::schema::user_names;
+use super::{Database, DatabaseError};
+
+#[derive(QueryableByName)]
+struct StaleTwitchUser {
+    #[diesel(sql_type = Unsigned<BigInt>)]
+    user_id: u64,
+    #[diesel(sql_type = Varchar)]
+    twitch_id: String,
+}
+
+impl Database {
+    /// Adds the name to the history, or updates when it was last seen if it's already there
+    pub fn record_user_name(
+        &self,
+        user_id: u64,
+        platform: &str,
+        name: &str,
+    ) -> Result<(), DatabaseError> {
+        let mut conn = self.get_conn()?;
+        let now = Utc::now().naive_utc();
+
+        sql_query(
+            "INSERT INTO user_names(user_id, platform, name, first_seen_at, last_seen_at) \
+            VALUES (?, ?, ?, ?, ?) ON DUPLICATE KEY UPDATE last_seen_at = VALUES(last_seen_at)",
+        )
+        .bind::<Unsigned<BigInt>, _>(user_id)
+        .bind::<Varchar, _>(platform)
+        .bind::<Varchar, _>(name)
+        .bind::<Timestamp, _>(now)
+        .bind::<Timestamp, _>(now)
+        .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// The most recently seen names come first
+    pub fn get_user_names(&self, user_id: u64) -> Result<Vec<UserName>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+
+        Ok(user_names::table
+            .filter(user_names::user_id.eq(user_id))
+            .order(user_names::last_seen_at.desc())
+            .load(&mut conn)?)
+    }
+
+    /// Users with a Twitch id whose Twitch name hasn't been seen within `max_age`,
+    /// as pairs of the user id and the Twitch id. Users without any recorded name come first
+    pub fn get_stale_twitch_name_users(
+        &self,
+        max_age: Duration,
+        limit: u32,
+    ) -> Result<Vec<(u64, String)>, DatabaseError> {
+        let mut conn = self.get_conn()?;
+        let seen_after = (Utc::now() - max_age).naive_utc();
+
+        let users: Vec<StaleTwitchUser> = sql_query(
+            "SELECT users.id AS user_id, users.twitch_id FROM users \
+            LEFT JOIN user_names ON user_names.user_id = users.id \
+            AND user_names.platform = 'twitch' \
+            WHERE users.twitch_id IS NOT NULL \
+            GROUP BY users.id, users.twitch_id \
+            HAVING MAX(user_names.last_seen_at) IS NULL OR MAX(user_names.last_seen_at) < ? \
+            ORDER BY MAX(user_names.last_seen_at) LIMIT ?",
+        )
+        .bind::<Timestamp, _>(seen_after)
+        .bind::<Unsigned<Integer>, _>(limit)
+        .load(&mut conn)?;
+
+        Ok(users
+            .into_iter()
+            .map(|user| (user.user_id, user.twitch_id))
+            .collect())
+    }
+}
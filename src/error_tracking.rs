@@ -0,0 +1,41 @@
+//! Optional error reporting to Sentry, complementing the OTLP traces. Panics are reported
+//! automatically once it's initialized, command errors that aren't caused by the user's input
+//! are reported with [`capture_command_error`]. Nothing is sent without a configured DSN.
+use crate::config::SentryConfig;
+use crate::platform::ChannelIdentifier;
+use sentry::{ClientInitGuard, ClientOptions, Level};
+
+/// Events are only sent while the returned guard is alive, so it has to be kept until exit
+pub fn init(config: &SentryConfig) -> Option<ClientInitGuard> {
+    // The DSN is validated when loading the config
+    let dsn = config.get_dsn()?;
+
+    let guard = sentry::init((
+        dsn,
+        ClientOptions {
+            release: Some(crate::get_version().into()),
+            environment: config.environment.clone().map(Into::into),
+            ..Default::default()
+        },
+    ));
+    tracing::info!("Reporting errors to Sentry");
+
+    Some(guard)
+}
+
+/// The trace id links the event to the trace of the command
+pub fn capture_command_error(
+    command: &str,
+    channel: &ChannelIdentifier,
+    trace_id: &str,
+    error: &str,
+) {
+    sentry::with_scope(
+        |scope| {
+            scope.set_tag("command", command);
+            scope.set_tag("channel", channel);
+            scope.set_tag("trace_id", trace_id);
+        },
+        || sentry::capture_message(error, Level::Error),
+    );
+}
@@ -3,63 +3,87 @@ extern crate diesel;
 
 mod api;
 mod command_handler;
+mod config;
 mod database;
+mod error_tracking;
 mod platform;
 mod rpc;
 
+use clap::Parser;
 use command_handler::{get_admin_channel, CommandHandler};
+use config::Config;
 use database::Database;
 use dotenv::dotenv;
 use opentelemetry::sdk::Resource;
 use opentelemetry::KeyValue;
 use opentelemetry_otlp::WithExportConfig;
 use platform::local::Local;
-use std::env;
+use std::path::PathBuf;
+use std::process;
 use std::time::Duration;
-use tracing::metadata::LevelFilter;
-use tracing_subscriber::{prelude::*, Registry};
+use tracing_subscriber::{prelude::*, reload, EnvFilter, Registry};
 
 use platform::connector::ConnectorPlatform;
 use platform::discord::Discord;
 use platform::irc::Irc;
+use platform::supervisor;
 use platform::twitch::Twitch;
 use platform::ChatPlatform;
 
+#[derive(Parser)]
+struct Args {
+    /// Only run builtin commands, without executing custom commands or EventSub actions
+    #[arg(long)]
+    safe_mode: bool,
+    /// Path to the config file, `config.toml` is used by default if it exists
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
 #[tokio::main]
 async fn main() {
+    let args = Args::parse();
+
     dotenv().unwrap_or_default();
-    init_tracing();
 
-    let db = Database::connect(env::var("DATABASE_URL").expect("DATABASE_URL missing"))
-        .expect("Failed to connect to DB");
+    let mut config = match Config::load(args.config.clone()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to load the configuration: {e}");
+            process::exit(1);
+        }
+    };
+    config.safe_mode |= args.safe_mode;
+
+    let log_filter = init_tracing(&config);
+    let _sentry_guard = error_tracking::init(&config.sentry);
+
+    let safe_mode = config.safe_mode;
+    if safe_mode {
+        tracing::warn!("Starting in safe mode, custom commands are disabled");
+    }
+
+    let db = Database::connect(config).expect("Failed to connect to DB");
 
     db.start_cron();
 
-    let command_handler = CommandHandler::init(db).await;
+    let command_handler = CommandHandler::init(db, args.config, log_filter).await;
 
-    match ConnectorPlatform::init(command_handler.clone()).await {
-        Ok(connector) => connector.run().await,
-        Err(err) => {
-            tracing::warn!("Could not initialize connector: {err:?}");
-        }
-    }
+    supervisor::start_platform::<ConnectorPlatform>("Connector", command_handler.clone()).await;
 
-    match &command_handler.platform_handler.read().await.twitch_api {
-        Some(_) => match Twitch::init(command_handler.clone()).await {
-            Ok(twitch) => twitch.run().await,
-            Err(e) => tracing::warn!("Platform {:?}", e),
-        },
-        None => {
-            tracing::info!("Twitch is not initialized! Not connecting to chat.");
-        }
+    let twitch_initialized = command_handler
+        .platform_handler
+        .read()
+        .await
+        .twitch_api
+        .is_some();
+    if twitch_initialized {
+        supervisor::start_platform::<Twitch>("Twitch", command_handler.clone()).await;
+    } else {
+        tracing::info!("Twitch is not initialized! Not connecting to chat.");
     }
 
-    match Discord::init(command_handler.clone()).await {
-        Ok(discord) => discord.run().await,
-        Err(e) => {
-            tracing::warn!("Error loading Discord: {:?}", e);
-        }
-    };
+    supervisor::start_platform::<Discord>("Discord", command_handler.clone()).await;
 
     match Irc::init(command_handler.clone()).await {
         Ok(irc) => irc.run().await,
@@ -73,13 +97,15 @@ async fn main() {
         Err(e) => tracing::warn!("Failed to initialize the local platform: {:?}", e),
     }
 
-    if let Some(admin_channel) = get_admin_channel() {
+    if let Some(admin_channel) = get_admin_channel(&command_handler.config()) {
         let platform_handler = command_handler.platform_handler.read().await;
+        let mut startup_message = format!("Foobot2 {} up and running", get_version());
+        if safe_mode {
+            startup_message.push_str(" (safe mode)");
+        }
+
         if let Err(e) = platform_handler
-            .send_to_channel(
-                admin_channel,
-                format!("Foobot2 {} up and running", get_version()),
-            )
+            .send_to_channel(admin_channel, startup_message)
             .await
         {
             tracing::warn!("Failed to send startup message: {}", e);
@@ -91,11 +117,11 @@ async fn main() {
         command_handler.platform_handler.clone(),
         Duration::from_secs(60),
         Default::default(),
-    )
-    .await
-    .expect("Could not start GeoHub loop");
+        command_handler.status.clone(),
+    );
 
     rpc::start_server(command_handler.clone());
+    command_handler::nats_api::start(command_handler.clone());
 
     api::run(command_handler).await;
 }
@@ -109,15 +135,16 @@ pub fn get_version() -> String {
     )
 }
 
-fn init_tracing() {
-    let otlp_host = env::var("OTLP_HOST").expect("Could not load OTLP_HOST");
+/// Allows changing the log filter at runtime
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
 
+fn init_tracing(config: &Config) -> LogFilterHandle {
     let tracer = opentelemetry_otlp::new_pipeline()
         .tracing()
         .with_exporter(
             opentelemetry_otlp::new_exporter()
                 .tonic()
-                .with_endpoint(otlp_host),
+                .with_endpoint(&config.otlp_host),
         )
         .with_trace_config(
             opentelemetry::sdk::trace::config().with_resource(Resource::new(vec![KeyValue::new(
@@ -131,14 +158,16 @@ fn init_tracing() {
     let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
 
     let fmt_layer = tracing_subscriber::fmt::layer().compact();
-    let filter_layer = tracing_subscriber::EnvFilter::builder()
-        .with_default_directive(LevelFilter::INFO.into())
-        .from_env()
-        .expect("Failed to parse log directive");
+    let env_filter = config
+        .env_filter()
+        .expect("The log filter is validated when loading the config");
+    let (filter_layer, log_filter) = reload::Layer::new(env_filter);
 
     Registry::default()
+        .with(filter_layer)
         .with(fmt_layer)
         .with(telemetry)
-        .with(filter_layer)
-        .init()
+        .init();
+
+    log_filter
 }
@@ -1,14 +1,102 @@
 use super::*;
+use crate::command_handler::nats_connection::NatsConnection;
 use crate::command_handler::CommandHandler;
+use crate::database::cache::TtlCache;
 use anyhow::{anyhow, Context};
 use async_nats::Client;
 use connector_schema::{
     IncomingMessage, OutgoingMessage, PermissionsRequest, PermissionsResponse,
     INCOMING_SUBJECT_PREFIX, OUTGOING_SUBJECT_PREFIX, PERMISSIONS_SUBJECT_PREFIX,
 };
+use dashmap::DashMap;
 use futures::StreamExt;
+use std::{sync::Arc, time::Duration};
 use tracing::{debug, error, info};
 
+/// Connectors publish on this subject when permissions in a channel change,
+/// such as Matrix power levels being modified
+pub const PERMISSIONS_CHANGED_SUBJECT_PREFIX: &str = "permissions_changed.";
+
+/// Connectors announce their platform on this subject when they start
+pub const ANNOUNCE_SUBJECT: &str = "connector.announce";
+/// Published on startup, so already running connectors announce themselves again
+pub const DISCOVER_SUBJECT: &str = "connector.discover";
+
+const PERMISSIONS_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Platforms that are handled natively and cannot be provided by a connector
+const NATIVE_PLATFORMS: &[&str] = &[
+    "twitch",
+    "discord",
+    "discord_guild",
+    "irc",
+    "local",
+    "telegram",
+    "minecraft",
+];
+
+pub fn is_valid_platform_name(name: &str) -> bool {
+    !name.is_empty()
+        && !NATIVE_PLATFORMS.contains(&name)
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectorCapability {
+    Messages,
+    Permissions,
+    PermissionEvents,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectorAnnouncement {
+    pub platform: String,
+    #[serde(default)]
+    pub capabilities: Vec<ConnectorCapability>,
+}
+
+/// Connectors that have announced themselves since startup
+#[derive(Clone, Default)]
+pub struct ConnectorRegistry {
+    connectors: Arc<DashMap<String, ConnectorAnnouncement>>,
+}
+
+impl ConnectorRegistry {
+    pub fn register(&self, announcement: ConnectorAnnouncement) -> anyhow::Result<()> {
+        if !is_valid_platform_name(&announcement.platform) {
+            return Err(anyhow!("Invalid platform name {}", announcement.platform));
+        }
+
+        info!(
+            "Registered connector for {} with capabilities {:?}",
+            announcement.platform, announcement.capabilities
+        );
+        self.connectors
+            .insert(announcement.platform.clone(), announcement);
+
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<ConnectorAnnouncement> {
+        self.connectors
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    /// Returns `None` if the connector has not announced itself
+    pub fn supports(&self, platform: &str, capability: ConnectorCapability) -> Option<bool> {
+        self.connectors
+            .get(platform)
+            .map(|connector| connector.capabilities.contains(&capability))
+    }
+}
+
 pub struct ConnectorPlatform {
     command_handler: CommandHandler,
 }
@@ -20,66 +108,153 @@ impl ChatPlatform for ConnectorPlatform {
     }
 
     async fn run(self) {
-        let incoming_subject = format!("{INCOMING_SUBJECT_PREFIX}*");
-        let mut subscriber = self
-            .command_handler
-            .nats_client
-            .queue_subscribe(incoming_subject, "foobot_core".into())
+        // Connectors are only reachable over NATS, so nothing is set up until it's connected
+        let nats_client = self.command_handler.nats_client.wait().await;
+
+        let mut announce_subscriber = match nats_client.subscribe(ANNOUNCE_SUBJECT.to_owned()).await
+        {
+            Ok(subscriber) => subscriber,
+            Err(err) => {
+                error!("Could not subscribe to announce subject: {err}");
+                return;
+            }
+        };
+
+        let connectors = self.command_handler.connectors.clone();
+        let reply_client = nats_client.clone();
+        let announce_task = tokio::spawn(async move {
+            while let Some(msg) = announce_subscriber.next().await {
+                let result = serde_json::from_slice::<ConnectorAnnouncement>(&msg.payload)
+                    .context("Malformed announcement")
+                    .and_then(|announcement| connectors.register(announcement));
+
+                if let Err(err) = &result {
+                    error!("Could not register connector: {err:#}");
+                }
+
+                if let Some(reply) = msg.reply {
+                    let response = match result {
+                        Ok(()) => serde_json::json!({ "accepted": true }),
+                        Err(err) => {
+                            serde_json::json!({ "accepted": false, "error": err.to_string() })
+                        }
+                    };
+
+                    if let Err(err) = reply_client
+                        .publish(reply, response.to_string().into())
+                        .await
+                    {
+                        error!("Could not reply to announcement: {err}");
+                    }
+                }
+            }
+        });
+
+        if let Err(err) = nats_client
+            .publish(DISCOVER_SUBJECT.to_owned(), "".into())
             .await
-            .expect("Failed to subscribe to incoming subject");
-
-        tokio::spawn(async move {
-            info!("Listening to connector messages");
-            while let Some(msg) = subscriber.next().await {
-                match IncomingMessage::try_from(msg.payload.as_ref()) {
-                    Ok(incoming_message) => {
-                        debug!("Got message: {incoming_message:?}");
-                        if let Some(platform) = msg.subject.strip_prefix(INCOMING_SUBJECT_PREFIX) {
-                            let platform_ctx = ConnectorPlatformContext {
-                                nats_client: &self.command_handler.nats_client,
-                                platform,
-                                msg: &incoming_message,
+        {
+            error!("Could not request connector announcements: {err}");
+        }
+
+        let permissions_changed_subject = format!("{PERMISSIONS_CHANGED_SUBJECT_PREFIX}*");
+        let mut permissions_subscriber =
+            match nats_client.subscribe(permissions_changed_subject).await {
+                Ok(subscriber) => subscriber,
+                Err(err) => {
+                    error!("Could not subscribe to permissions subject: {err}");
+                    announce_task.abort();
+                    return;
+                }
+            };
+
+        let connector_permissions = self.command_handler.connector_permissions.clone();
+        let permissions_task = tokio::spawn(async move {
+            while let Some(msg) = permissions_subscriber.next().await {
+                let platform = match msg.subject.strip_prefix(PERMISSIONS_CHANGED_SUBJECT_PREFIX) {
+                    Some(platform) => platform,
+                    None => continue,
+                };
+
+                match serde_json::from_slice::<PermissionsChanged>(&msg.payload) {
+                    Ok(event) => connector_permissions.invalidate(platform, event),
+                    Err(err) => error!("Received malformed permissions event: {err}"),
+                }
+            }
+        });
+
+        // When sharding, every shard receives the messages and only the channel owner handles them
+        let incoming_subject = format!("{INCOMING_SUBJECT_PREFIX}*");
+        let subscriber = match self.command_handler.sharding.is_enabled() {
+            true => nats_client.subscribe(incoming_subject).await,
+            false => {
+                nats_client
+                    .queue_subscribe(incoming_subject, "foobot_core".into())
+                    .await
+            }
+        };
+        let mut subscriber = match subscriber {
+            Ok(subscriber) => subscriber,
+            Err(err) => {
+                error!("Could not subscribe to incoming subject: {err}");
+                announce_task.abort();
+                permissions_task.abort();
+                return;
+            }
+        };
+
+        info!("Listening to connector messages");
+        while let Some(msg) = subscriber.next().await {
+            match IncomingMessage::try_from(msg.payload.as_ref()) {
+                Ok(incoming_message) => {
+                    debug!("Got message: {incoming_message:?}");
+                    if let Some(platform) = msg.subject.strip_prefix(INCOMING_SUBJECT_PREFIX) {
+                        let platform_ctx = ConnectorPlatformContext {
+                            connector_permissions: &self.command_handler.connector_permissions,
+                            platform,
+                            msg: &incoming_message,
+                        };
+
+                        if let Some(content) = self
+                            .command_handler
+                            .handle_message(&incoming_message.content, platform_ctx)
+                            .await
+                            .into_text()
+                        {
+                            let outgoing_message = OutgoingMessage {
+                                channel_id: incoming_message.channel_id,
+                                content,
+                                reply: incoming_message.id,
                             };
+                            let outgoing_subject = format!("{OUTGOING_SUBJECT_PREFIX}{platform}");
 
-                            if let Some(content) = self
-                                .command_handler
-                                .handle_message(&incoming_message.content, platform_ctx)
+                            if let Err(err) = nats_client
+                                .publish(outgoing_subject, outgoing_message.into())
                                 .await
                             {
-                                let outgoing_message = OutgoingMessage {
-                                    channel_id: incoming_message.channel_id,
-                                    content,
-                                    reply: incoming_message.id,
-                                };
-                                let outgoing_subject =
-                                    format!("{OUTGOING_SUBJECT_PREFIX}{platform}");
-
-                                if let Err(err) = self
-                                    .command_handler
-                                    .nats_client
-                                    .publish(outgoing_subject, outgoing_message.into())
-                                    .await
-                                {
-                                    error!("Could not publish response: {err}");
-                                }
-
-                                let _ = self.command_handler.nats_client.flush().await;
+                                error!("Could not publish response: {err}");
                             }
-                        } else {
-                            error!("Received incoming message on an unexpected subject: {msg:?}");
+
+                            let _ = nats_client.flush().await;
                         }
+                    } else {
+                        error!("Received incoming message on an unexpected subject: {msg:?}");
                     }
-                    Err(err) => {
-                        error!("Received malformed incoming message: {err}");
-                    }
+                }
+                Err(err) => {
+                    error!("Received malformed incoming message: {err}");
                 }
             }
-        });
+        }
+
+        // Subscribed again when the platform is restarted
+        announce_task.abort();
+        permissions_task.abort();
     }
 }
 
 pub struct ConnectorPlatformContext<'a> {
-    nats_client: &'a Client,
+    connector_permissions: &'a ConnectorPermissions,
     platform: &'a str,
     msg: &'a IncomingMessage,
 }
@@ -96,24 +271,25 @@ impl Debug for ConnectorPlatformContext<'_> {
 #[async_trait]
 impl PlatformContext for ConnectorPlatformContext<'_> {
     async fn get_permissions_internal(&self) -> Permissions {
-        get_connector_permissions(
-            self.nats_client,
-            self.platform,
-            self.msg.channel_id.clone(),
-            self.msg.sender.id.clone(),
-        )
-        .await
-        .expect("Could not get permissions")
+        self.connector_permissions
+            .get(
+                self.platform,
+                self.msg.channel_id.clone(),
+                self.msg.sender.id.clone(),
+            )
+            .await
+            .unwrap_or_else(|err| {
+                error!("Could not get {} permissions: {err}", self.platform);
+                Permissions::Default
+            })
     }
 
     fn get_channel(&self) -> ChannelIdentifier {
-        ChannelIdentifier::new(self.platform, self.msg.channel_id.clone())
-            .expect("Unable to construct channel identifier")
+        ChannelIdentifier::Connector(self.platform.to_owned(), self.msg.channel_id.clone())
     }
 
     fn get_user_identifier(&self) -> UserIdentifier {
-        UserIdentifier::from_string(&format!("{}:{}", self.platform, self.msg.sender.id))
-            .expect("Unable to construct user identifier")
+        UserIdentifier::Connector(self.platform.to_owned(), self.msg.sender.id.clone())
     }
 
     fn get_display_name(&self) -> &str {
@@ -129,7 +305,82 @@ impl PlatformContext for ConnectorPlatformContext<'_> {
     }
 }
 
-pub async fn get_connector_permissions(
+#[derive(Deserialize)]
+pub struct PermissionsChanged {
+    pub channel_id: String,
+    pub user_id: Option<String>, // All users in the channel are affected when not specified
+}
+
+/// Permissions requested from connectors, cached until they expire or the connector
+/// reports a change
+#[derive(Clone)]
+pub struct ConnectorPermissions {
+    nats_client: NatsConnection,
+    connectors: ConnectorRegistry,
+    // Keyed by platform, channel id and user id
+    cache: Arc<TtlCache<(String, String, String), Permissions>>,
+}
+
+impl ConnectorPermissions {
+    pub fn new(nats_client: NatsConnection, connectors: ConnectorRegistry) -> Self {
+        Self {
+            nats_client,
+            connectors,
+            cache: Arc::new(TtlCache::new(PERMISSIONS_CACHE_TTL)),
+        }
+    }
+
+    pub async fn get(
+        &self,
+        platform: &str,
+        channel_id: String,
+        user_id: String,
+    ) -> anyhow::Result<Permissions> {
+        // Connectors that have not announced themselves are still asked, in case they are outdated
+        if self
+            .connectors
+            .supports(platform, ConnectorCapability::Permissions)
+            == Some(false)
+        {
+            return Ok(Permissions::Default);
+        }
+
+        let key = (platform.to_owned(), channel_id, user_id);
+
+        if let Some(permissions) = self.cache.get(&key) {
+            debug!("Using cached connector permissions");
+            return Ok(permissions);
+        }
+
+        let nats_client = self.nats_client.get().context("Not connected to NATS")?;
+        let permissions =
+            get_connector_permissions(&nats_client, platform, key.1.clone(), key.2.clone()).await?;
+        self.cache.remove_expired();
+        self.cache.insert(key, permissions);
+
+        Ok(permissions)
+    }
+
+    pub fn invalidate(&self, platform: &str, event: PermissionsChanged) {
+        debug!(
+            "Invalidating {platform} permissions in channel {}",
+            event.channel_id
+        );
+
+        self.cache
+            .retain(|(cached_platform, channel_id, user_id), _| {
+                !(cached_platform == platform
+                    && *channel_id == event.channel_id
+                    && event.user_id.as_ref().map_or(true, |id| id == user_id))
+            });
+    }
+
+    pub fn clear(&self) {
+        self.cache.clear();
+    }
+}
+
+async fn get_connector_permissions(
     nats_client: &Client,
     platform: &str,
     channel_id: String,
@@ -1,13 +1,24 @@
 use async_trait::async_trait;
 use futures::StreamExt;
-use std::{env, fmt::Debug, sync::Arc};
+use std::{fmt::Debug, sync::Arc};
 use twilight_gateway::{Cluster, Event, Intents};
-use twilight_http::Client;
-use twilight_model::{gateway::payload::incoming::MessageCreate, guild::Permissions};
+use twilight_http::{request::channel::reaction::RequestReactionType, Client};
+use twilight_model::{
+    channel::embed::{Embed, EmbedField},
+    gateway::payload::incoming::MessageCreate,
+    guild::Permissions,
+    http::attachment::Attachment,
+};
 
+use crate::command_handler::output::{self, CommandOutput};
+use crate::command_handler::status::ConnectionState;
 use crate::command_handler::CommandHandler;
+use crate::config::Config;
 
-use super::{ChannelIdentifier, ChatPlatform, PlatformContext, UserIdentifier};
+use super::{
+    ChannelIdentifier, ChatPlatform, ChatPlatformError, MessageIdentifier, PlatformContext,
+    UserIdentifier,
+};
 
 #[derive(Clone)]
 pub struct Discord {
@@ -36,14 +47,97 @@ impl Discord {
                 self_mention,
             };
 
-            if let Some(response) = command_handler.handle_message(&msg.content, context).await {
-                http.create_message(msg.channel_id)
-                    .reply(msg.id)
-                    .content(&response)
-                    .expect("Failed to construct message")
+            let output = command_handler.handle_message(&msg.content, context).await;
+            if output.is_nothing() {
+                return;
+            }
+
+            // DMs don't have a guild, but have the same length limit
+            let channel = ChannelIdentifier::DiscordChannel(
+                msg.guild_id
+                    .map_or_else(|| msg.channel_id.to_string(), |id| id.to_string()),
+            );
+            let platform_handler = command_handler.platform_handler.read().await;
+            let reply = platform_handler
+                .get_channel_settings(&channel)
+                .reply_to_messages;
+
+            // Messages that fail validation, such as oversized embeds, are reported the same way
+            // as failed requests
+            let result: anyhow::Result<()> = match output {
+                CommandOutput::Reaction(emoji) => {
+                    drop(platform_handler);
+                    http.create_reaction(
+                        msg.channel_id,
+                        msg.id,
+                        &RequestReactionType::Unicode { name: &emoji },
+                    )
                     .exec()
                     .await
-                    .expect("Failed to reply in Discord");
+                    .map(|_| ())
+                    .map_err(Into::into)
+                }
+                CommandOutput::Embed(embed) => {
+                    drop(platform_handler);
+                    let mut request = http.create_message(msg.channel_id);
+                    if reply {
+                        request = request.reply(msg.id);
+                    }
+
+                    async {
+                        request.embeds(&[convert_embed(embed)])?.exec().await?;
+                        Ok::<_, anyhow::Error>(())
+                    }
+                    .await
+                }
+                CommandOutput::Attachment(attachment) => {
+                    drop(platform_handler);
+                    let attachments = [Attachment::from_bytes(
+                        attachment.filename,
+                        attachment.content,
+                        0,
+                    )];
+                    let mut request = http.create_message(msg.channel_id);
+                    if reply {
+                        request = request.reply(msg.id);
+                    }
+
+                    async {
+                        request.attachments(&attachments)?.exec().await?;
+                        Ok::<_, anyhow::Error>(())
+                    }
+                    .await
+                }
+                output => {
+                    let mention = format!("<@{}>", msg.author.id);
+                    let messages = output
+                        .into_messages()
+                        .into_iter()
+                        .flat_map(|response| {
+                            let response =
+                                platform_handler.format_response(&channel, &response, &mention);
+                            platform_handler.split_message(&channel, &response)
+                        })
+                        .collect::<Vec<_>>();
+                    drop(platform_handler);
+
+                    async {
+                        for (i, message) in messages.iter().enumerate() {
+                            let mut request = http.create_message(msg.channel_id);
+                            if i == 0 && reply {
+                                request = request.reply(msg.id);
+                            }
+
+                            request.content(message)?.exec().await?;
+                        }
+                        Ok::<_, anyhow::Error>(())
+                    }
+                    .await
+                }
+            };
+
+            if let Err(e) = result {
+                tracing::warn!("Failed to reply in Discord: {e}");
             }
         });
     }
@@ -51,55 +145,104 @@ impl Discord {
 
 #[async_trait]
 impl ChatPlatform for Discord {
-    async fn init(command_handler: CommandHandler) -> Result<Box<Self>, super::ChatPlatformError> {
-        let token = env::var("DISCORD_TOKEN")?;
+    async fn init(command_handler: CommandHandler) -> Result<Box<Self>, ChatPlatformError> {
+        let config = command_handler.config();
+        let token = config
+            .discord
+            .token
+            .clone()
+            .ok_or(ChatPlatformError::MissingAuthentication)?;
+        let client_id = config
+            .discord
+            .client_id
+            .as_deref()
+            .ok_or_else(|| ChatPlatformError::MissingConfig("discord.client_id".to_owned()))?;
 
         Ok(Box::new(Self {
             token,
             command_handler,
-            prefix: Arc::new(Self::get_prefix()),
-            self_mention: Arc::new(format!(
-                "<@!{}>",
-                env::var("DISCORD_CLIENT_ID").expect("DISCORD_CLIENT_ID not specified")
-            )),
+            prefix: Arc::new(Self::get_prefix(&config)),
+            self_mention: Arc::new(format!("<@!{client_id}>")),
         }))
     }
 
     async fn run(self) {
         let intents = Intents::GUILD_MESSAGES | Intents::DIRECT_MESSAGES;
 
-        let (cluster, mut events) = Cluster::builder(self.token.clone(), intents)
-            .build()
-            .await
-            .expect("Failed to connect to Discord");
+        let (cluster, mut events) =
+            match Cluster::builder(self.token.clone(), intents).build().await {
+                Ok(cluster) => cluster,
+                Err(e) => {
+                    tracing::error!("Failed to connect to Discord: {e}");
+                    return;
+                }
+            };
+        let cluster = Arc::new(cluster);
 
         {
+            let cluster = cluster.clone();
             tokio::spawn(async move {
                 cluster.up().await;
             });
         }
 
         let http = Arc::new(Client::new(self.token.clone()));
+        let status = self.command_handler.status.clone();
 
-        tokio::spawn(async move {
-            while let Some((_, event)) = events.next().await {
-                match event {
-                    Event::ShardConnected(_) => tracing::info!("Discord shard connected"),
-                    Event::MessageCreate(msg) => self.handle_msg(*msg, http.clone()).await,
-                    _ => (),
+        while let Some((_, event)) = events.next().await {
+            match event {
+                Event::ShardConnecting(_) => {
+                    status.set_connection_state("discord", ConnectionState::Connecting)
+                }
+                Event::ShardConnected(_) => {
+                    tracing::info!("Discord shard connected");
+                    status.set_connection_state("discord", ConnectionState::Connected)
+                }
+                Event::ShardDisconnected(_) => {
+                    status.set_connection_state("discord", ConnectionState::Disconnected)
                 }
+                Event::MessageCreate(msg) => self.handle_msg(*msg, http.clone()).await,
+                _ => (),
             }
-        });
+        }
+
+        // Shuts down the shards of this cluster before a new one is started
+        cluster.down();
+        status.set_connection_state("discord", ConnectionState::Disconnected);
     }
 
-    fn get_prefix() -> String {
-        if let Ok(prefix) = env::var("DISCORD_PREFIX") {
-            prefix
-        } else if let Ok(prefix) = env::var("COMMAND_PREFIX") {
-            prefix
-        } else {
-            "!".to_string()
-        }
+    fn get_prefix(config: &Config) -> String {
+        config
+            .discord
+            .prefix
+            .clone()
+            .unwrap_or_else(|| config.command_prefix.clone())
+    }
+}
+
+fn convert_embed(embed: output::Embed) -> Embed {
+    Embed {
+        author: None,
+        color: None,
+        description: Some(embed.description),
+        fields: embed
+            .fields
+            .into_iter()
+            .map(|field| EmbedField {
+                inline: false,
+                name: field.name,
+                value: field.value,
+            })
+            .collect(),
+        footer: None,
+        image: None,
+        kind: "rich".to_owned(),
+        provider: None,
+        thumbnail: None,
+        timestamp: None,
+        title: embed.title,
+        url: embed.url,
+        video: None,
     }
 }
 
@@ -133,13 +276,19 @@ impl PlatformContext for DiscordPlatformContext<'_> {
             Some(guild_id) => {
                 let platform_handler = self.cmd.platform_handler.read().await;
 
-                let permissions = platform_handler
+                let permissions = match platform_handler
                     .discord_api
                     .as_ref()
                     .unwrap()
                     .get_permissions_in_guild(self.msg.author.id.get(), guild_id.get())
                     .await
-                    .expect("Failed to get permissions");
+                {
+                    Ok(permissions) => permissions,
+                    Err(e) => {
+                        tracing::error!("Failed to get Discord permissions: {e}");
+                        return crate::platform::Permissions::Default;
+                    }
+                };
 
                 if permissions.contains(Permissions::ADMINISTRATOR) {
                     crate::platform::Permissions::ChannelMod
@@ -169,4 +318,27 @@ impl PlatformContext for DiscordPlatformContext<'_> {
     fn get_prefixes(&self) -> Vec<&str> {
         vec![&self.prefix, &self.self_mention]
     }
+
+    fn get_subchannel(&self) -> Option<u64> {
+        self.msg.guild_id.map(|_| self.msg.channel_id.get())
+    }
+
+    fn get_message_identifier(&self) -> Option<MessageIdentifier> {
+        self.msg.guild_id.map(|_| MessageIdentifier::Discord {
+            channel_id: self.msg.channel_id.get(),
+            message_id: self.msg.id.get(),
+        })
+    }
+
+    fn supports_attachments(&self) -> bool {
+        true
+    }
+
+    fn get_reply_parent_text(&self) -> Option<&str> {
+        self.msg
+            .referenced_message
+            .as_deref()
+            .map(|message| message.content.as_str())
+            .filter(|content| !content.is_empty())
+    }
 }
@@ -1,29 +1,202 @@
+use std::fmt;
+use std::sync::Arc;
 use std::sync::RwLock;
-use std::time::Duration;
-use std::{env, sync::Arc};
 
+use crate::command_handler::status::ConnectionState;
 use crate::command_handler::CommandHandler;
+use crate::config::IrcNetworkConfig;
 use crate::platform::{PlatformContext, UserIdentifier};
 
+use super::supervisor::supervise;
 use super::{ChannelIdentifier, ChatPlatform, ChatPlatformError, Permissions};
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use futures::StreamExt;
+use irc::client::data::AccessLevel;
 use irc::client::{prelude::*, Client};
+use irc::proto::CapSubCommand;
 use tokio::task;
 use tracing::info;
 
+/// The network configured in `[irc.default]` or with the unprefixed `IRC_*` variables.
+/// Its channels and users are not prefixed with the network name
+pub const DEFAULT_NETWORK: &str = "default";
+
+/// Splits a channel or user name into the network and the name on that network
+pub fn parse_target(target: &str) -> (&str, &str) {
+    target.split_once(':').unwrap_or((DEFAULT_NETWORK, target))
+}
+
+fn format_target(network: &str, target: &str) -> String {
+    if network == DEFAULT_NETWORK {
+        target.to_owned()
+    } else {
+        format!("{network}:{target}")
+    }
+}
+
+/// Connection to a single IRC network
 #[derive(Clone)]
-pub struct Irc {
+pub struct IrcNetwork {
+    pub name: Arc<String>,
     client: Arc<RwLock<Client>>,
+    /// Used to create a new client when reconnecting, as a client's stream can only be taken once
+    config: Arc<Config>,
+    sasl_password: Option<Arc<String>>,
+}
+
+impl fmt::Debug for IrcNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IrcNetwork")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl IrcNetwork {
+    pub fn send_privmsg(&self, target: &str, message: &str) -> Result<(), irc::error::Error> {
+        self.client.read().unwrap().send_privmsg(target, message)
+    }
+
+    pub fn send_action(&self, target: &str, message: &str) -> Result<(), irc::error::Error> {
+        self.client.read().unwrap().send_action(target, message)
+    }
+
+    /// Based on the channel modes of the user, which are tracked from NAMES and MODE messages
+    pub fn get_permissions(&self, channel: &str, nickname: &str) -> Permissions {
+        let client = self.client.read().unwrap();
+
+        let access_levels = client
+            .list_users(channel)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|user| user.get_nickname().eq_ignore_ascii_case(nickname))
+            .map(|user| user.access_levels())
+            .unwrap_or_default();
+
+        if access_levels.contains(&AccessLevel::Owner) {
+            Permissions::ChannelOwner
+        } else if access_levels.iter().any(|level| {
+            matches!(
+                level,
+                AccessLevel::Admin | AccessLevel::Oper | AccessLevel::HalfOp
+            )
+        }) {
+            Permissions::ChannelMod
+        } else {
+            Permissions::Default
+        }
+    }
+
+    fn connect(&self) -> Result<ClientStream, irc::error::Error> {
+        let mut client = self.client.write().unwrap();
+
+        if self.sasl_password.is_some() {
+            // Registration is finished with CAP END once SASL authentication completes
+            client.send(Command::CAP(
+                None,
+                CapSubCommand::REQ,
+                None,
+                Some("sasl".to_owned()),
+            ))?;
+
+            let nickname = client.current_nickname().to_owned();
+            let real_name = client.config().real_name().to_owned();
+            client.send(Command::NICK(nickname.clone()))?;
+            client.send(Command::USER(nickname, "0".to_owned(), real_name))?;
+        } else {
+            client.identify()?;
+        }
+
+        client.stream()
+    }
+
+    async fn reconnect(&self) -> Result<ClientStream, irc::error::Error> {
+        let client = Client::from_config((*self.config).clone()).await?;
+        *self.client.write().unwrap() = client;
+
+        self.connect()
+    }
+
+    fn handle_sasl(&self, message: &Message) -> Result<(), irc::error::Error> {
+        let password = match &self.sasl_password {
+            Some(password) => password,
+            None => return Ok(()),
+        };
+        let client = self.client.read().unwrap();
+
+        match &message.command {
+            Command::CAP(_, CapSubCommand::ACK, _, _) => {
+                client.send(Command::AUTHENTICATE("PLAIN".to_owned()))?;
+            }
+            Command::CAP(_, CapSubCommand::NAK, _, _) => {
+                tracing::warn!("IRC network {} does not support SASL", self.name);
+                client.send(Command::CAP(None, CapSubCommand::END, None, None))?;
+            }
+            Command::AUTHENTICATE(data) if data == "+" => {
+                let nickname = client.current_nickname();
+                let credentials = format!("{nickname}\0{nickname}\0{password}");
+
+                client.send(Command::AUTHENTICATE(BASE64.encode(credentials)))?;
+            }
+            Command::Response(Response::RPL_SASLSUCCESS, _) => {
+                info!("Authenticated with SASL on IRC network {}", self.name);
+                client.send(Command::CAP(None, CapSubCommand::END, None, None))?;
+            }
+            Command::Response(Response::ERR_SASLFAIL, _) => {
+                tracing::warn!("SASL authentication failed on IRC network {}", self.name);
+                client.send(Command::CAP(None, CapSubCommand::END, None, None))?;
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct Irc {
+    networks: Vec<IrcNetwork>,
     command_prefix: Arc<String>,
     command_handler: CommandHandler,
 }
 
 impl Irc {
-    async fn handle_message(&self, message: Message) {
+    async fn run_network(self, network: IrcNetwork, reconnect: bool) -> anyhow::Result<()> {
+        let mut stream = if reconnect {
+            network.reconnect().await?
+        } else {
+            network.connect()?
+        };
+
+        tracing::info!("IRC network {} connected", network.name);
+
+        let status = self.command_handler.status.clone();
+        let status_name = format!("irc:{}", network.name);
+        status.set_connection_state(&status_name, ConnectionState::Connected);
+
+        let result = loop {
+            match stream.next().await.transpose() {
+                Ok(Some(message)) => {
+                    if let Err(e) = network.handle_sasl(&message) {
+                        tracing::warn!("IRC SASL error: {}", e);
+                    }
+
+                    self.handle_message(network.clone(), message).await;
+                }
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+
+        status.set_connection_state(&status_name, ConnectionState::Disconnected);
+
+        Ok(result?)
+    }
+
+    async fn handle_message(&self, network: IrcNetwork, message: Message) {
         let Self {
             command_handler,
-            client,
             command_prefix,
             ..
         } = self.clone();
@@ -32,97 +205,129 @@ impl Irc {
             if let Command::PRIVMSG(_, content) = &message.command {
                 let context = IrcPlatformContext {
                     message: &message,
+                    network: network.clone(),
                     command_prefix,
                 };
-                if let Some(response) = command_handler.handle_message(content, context).await {
-                    let client = client.read().unwrap();
+                let channel = context.get_channel();
+
+                let output = command_handler.handle_message(content, context).await;
 
-                    client
-                        .send_privmsg(message.response_target().unwrap(), response)
-                        .expect("Failed to send PRIVMSG");
+                for response in output.into_messages() {
+                    let platform_handler = command_handler.platform_handler.read().await;
+                    let response = platform_handler.format_response(
+                        &channel,
+                        &response,
+                        message.source_nickname().unwrap_or_default(),
+                    );
+                    let messages = platform_handler.split_message(&channel, &response);
+                    drop(platform_handler);
+
+                    for response in messages {
+                        network
+                            .send_privmsg(message.response_target().unwrap(), &response)
+                            .expect("Failed to send PRIVMSG");
+                    }
                 }
             }
         });
     }
 }
 
+async fn connect_network(
+    name: String,
+    network_config: &IrcNetworkConfig,
+) -> Result<IrcNetwork, irc::error::Error> {
+    let password = network_config.password.clone();
+    let sasl = network_config.sasl;
+
+    if network_config.channels.is_empty() {
+        tracing::info!("No channels configured for IRC network {name}");
+    }
+
+    let config = Config {
+        nickname: network_config.nickname.clone(),
+        // NickServ identification is only used when SASL is disabled
+        nick_password: if sasl { None } else { password.clone() },
+        server: network_config.server.clone(),
+        port: network_config.port,
+        alt_nicks: vec!["foobot_alt_nick".to_owned()],
+        channels: network_config.channels.clone(),
+        ..Default::default()
+    };
+
+    tracing::info!("IRC network {name} config: {:?}", config);
+
+    let client = Client::from_config(config.clone()).await?;
+
+    Ok(IrcNetwork {
+        name: Arc::new(name),
+        client: Arc::new(RwLock::new(client)),
+        config: Arc::new(config),
+        sasl_password: if sasl { password.map(Arc::new) } else { None },
+    })
+}
+
 #[async_trait]
 impl ChatPlatform for Irc {
     async fn init(command_handler: CommandHandler) -> Result<Box<Self>, ChatPlatformError> {
-        let command_prefix = Self::get_prefix();
-
-        let config = Config {
-            nickname: env::var("IRC_NICKNAME").ok(),
-            nick_password: env::var("IRC_PASSWORD").ok(),
-            server: env::var("IRC_SERVER").ok(),
-            alt_nicks: vec!["foobot_alt_nick".to_owned()],
-            channels: {
-                match env::var("IRC_CHANNELS") {
-                    Ok(channels) => {
-                        dbg!(&channels);
-                        channels.split(',').map(|s| s.to_owned()).collect()
-                    }
-                    Err(e) => {
-                        tracing::info!("Failed to load IRC channels: {}", e);
-                        vec![]
-                    }
-                }
-            },
-            ..Default::default()
-        };
+        let config = command_handler.config();
+        let command_prefix = Self::get_prefix(&config);
+
+        let mut networks = Vec::new();
+
+        for (name, network_config) in &config.irc {
+            match connect_network(name.clone(), network_config).await {
+                Ok(network) => networks.push(network),
+                Err(e) => tracing::error!("Failed to configure IRC network {name}: {e}"),
+            }
+        }
 
-        tracing::info!("IRC Config: {:?}", config);
+        if networks.is_empty() {
+            return Err(ChatPlatformError::MissingAuthentication);
+        }
 
-        let client = Client::from_config(config)
-            .await
-            .map_err(|_| ChatPlatformError::MissingAuthentication)?;
         {
             let mut platform_handler = command_handler.platform_handler.write().await;
-            platform_handler.irc_sender = Some(client.sender());
-            info!("Configured irc sender");
+            for network in &networks {
+                platform_handler
+                    .irc_networks
+                    .insert(network.name.to_string(), network.clone());
+            }
+            info!("Configured irc networks");
         }
 
         Ok(Box::new(Self {
+            networks,
             command_prefix: Arc::new(command_prefix),
             command_handler,
-            client: Arc::new(RwLock::new(client)),
         }))
     }
 
+    /// Every network is restarted separately, so one network going down doesn't affect the others
     async fn run(self) {
-        let client = self.client.clone();
-        let get_stream = move || {
-            let mut client = client.write().unwrap();
-
-            client.identify().expect("Failed to identify");
+        for network in self.networks.clone() {
+            let irc = self.clone();
+            let mut reconnect = false;
 
-            client.stream().unwrap()
-        };
-        let mut stream = get_stream();
-
-        tracing::info!("IRC connected");
+            supervise(
+                format!("IRC network {}", network.name),
+                self.command_handler.clone(),
+                move || {
+                    let irc = irc.clone();
+                    let network = network.clone();
+                    let reconnect = std::mem::replace(&mut reconnect, true);
 
-        task::spawn(async move {
-            loop {
-                match stream.next().await.transpose() {
-                    Ok(Some(message)) => {
-                        self.handle_message(message).await;
-                    }
-                    Ok(None) => (),
-                    Err(e) => {
-                        tracing::warn!("IRC error: {}", e);
-                        tokio::time::sleep(Duration::from_secs(5)).await;
-                        stream = get_stream();
-                    }
-                }
-            }
-        });
+                    async move { irc.run_network(network, reconnect).await }
+                },
+            );
+        }
     }
 }
 
 #[derive(Clone, Debug)]
 struct IrcPlatformContext<'a> {
     message: &'a Message,
+    network: IrcNetwork,
     command_prefix: Arc<String>,
 }
 
@@ -130,16 +335,26 @@ struct IrcPlatformContext<'a> {
 #[async_trait]
 impl PlatformContext for IrcPlatformContext<'_> {
     async fn get_permissions_internal(&self) -> Permissions {
-        // TODO
-        Permissions::Default
+        match &self.message.command {
+            Command::PRIVMSG(target, _) if target.starts_with(['#', '&']) => self
+                .network
+                .get_permissions(target, self.message.source_nickname().unwrap()),
+            _ => Permissions::Default,
+        }
     }
 
     fn get_channel(&self) -> ChannelIdentifier {
-        ChannelIdentifier::IrcChannel(self.message.response_target().unwrap().to_owned())
+        ChannelIdentifier::IrcChannel(format_target(
+            &self.network.name,
+            self.message.response_target().unwrap(),
+        ))
     }
 
     fn get_user_identifier(&self) -> UserIdentifier {
-        UserIdentifier::IrcName(self.message.source_nickname().unwrap().to_owned())
+        UserIdentifier::IrcName(format_target(
+            &self.network.name,
+            self.message.source_nickname().unwrap(),
+        ))
     }
 
     fn get_display_name(&self) -> &str {
@@ -150,3 +365,16 @@ impl PlatformContext for IrcPlatformContext<'_> {
         vec![&self.command_prefix]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{format_target, parse_target, DEFAULT_NETWORK};
+
+    #[test]
+    fn network_targets() {
+        assert_eq!(parse_target("#foobot"), (DEFAULT_NETWORK, "#foobot"));
+        assert_eq!(parse_target("libera:#foobot"), ("libera", "#foobot"));
+        assert_eq!(format_target(DEFAULT_NETWORK, "#foobot"), "#foobot");
+        assert_eq!(format_target("libera", "nick"), "libera:nick");
+    }
+}
@@ -1,21 +1,30 @@
-use std::{
-    env,
-    net::{IpAddr, Ipv4Addr, SocketAddr},
-};
-
+//! Control protocol for CLIs and scripts, spoken over TCP (one JSON object per line)
+//! and over the `/local/ws` websocket (one JSON object per text message).
+//!
+//! The first request on a connection has to be
+//! `{"type": "auth", "token": "...", "client": "name"}`, after which requests like
+//! `{"type": "message", "id": "1", "content": "ping"}` are handled as chat messages.
+//! The client name is the identity of the connection, so each client is its own channel.
+//! Authenticated websocket clients also receive the bot events of all channels as they happen.
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use tokio::{
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
     net::{TcpListener, TcpStream},
 };
 
-use crate::command_handler::CommandHandler;
+use crate::command_handler::{outgoing_webhooks::ChannelEvent, CommandHandler};
 
 use super::{
     ChannelIdentifier, ChatPlatform, ChatPlatformError, Permissions, PlatformContext,
     UserIdentifier,
 };
 
+const MAX_CLIENT_NAME_LENGTH: usize = 64;
+/// The connection is closed after this many invalid tokens
+const MAX_AUTH_ATTEMPTS: u32 = 3;
+
 pub struct Local {
     listener: TcpListener,
     command_handler: CommandHandler,
@@ -24,8 +33,18 @@ pub struct Local {
 #[async_trait]
 impl ChatPlatform for Local {
     async fn init(command_handler: CommandHandler) -> Result<Box<Self>, ChatPlatformError> {
-        let addr = env::var("LOCAL_PLATFORM_ADDRESS")
-            .map_err(|_| ChatPlatformError::MissingEnv(String::from("LOCAL_PLATFORM_ADDRESS")))?;
+        let config = command_handler.config();
+        let addr =
+            config.local_platform.address.clone().ok_or_else(|| {
+                ChatPlatformError::MissingConfig("local_platform.address".to_owned())
+            })?;
+
+        // Clients have to authenticate before they get an identity
+        if config.local_platform.token.is_none() {
+            return Err(ChatPlatformError::MissingConfig(
+                "local_platform.token".to_owned(),
+            ));
+        }
 
         let listener = TcpListener::bind(addr)
             .await
@@ -65,18 +84,26 @@ impl Local {
         addr: SocketAddr,
         command_handler: CommandHandler,
     ) -> anyhow::Result<()> {
+        tracing::debug!("Local client connected from {addr}");
+
+        let mut session = LocalSession::new(command_handler);
         let mut reader = BufReader::new(stream);
         let mut buf = String::new();
 
         while reader.read_line(&mut buf).await? != 0 {
-            let context = LocalPlatformContext {
-                addr_str: addr.ip().to_string(),
-                addr,
-            };
-
-            if let Some(response) = command_handler.handle_message(&buf, context).await {
-                reader.write_all(response.as_bytes()).await?;
-                reader.write_all(b"\n").await?;
+            if !buf.trim().is_empty() {
+                let response = session.handle_frame(&buf).await;
+
+                let mut frame = serde_json::to_vec(&response)?;
+                frame.push(b'\n');
+                reader.write_all(&frame).await?;
+
+                if session.auth_attempts_exceeded() {
+                    tracing::warn!(
+                        "Closing local connection from {addr} after failed auth attempts"
+                    );
+                    break;
+                }
             }
 
             buf.clear();
@@ -86,35 +113,237 @@ impl Local {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LocalRequest {
+    /// Has to be sent before any messages
+    Auth { token: String, client: String },
+    Message {
+        /// Echoed back in the response so that clients can match them up
+        #[serde(default)]
+        id: Option<String>,
+        content: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LocalResponse {
+    Authenticated {
+        client: String,
+    },
+    /// `response` is `None` when the message did not trigger anything
+    Response {
+        id: Option<String>,
+        response: Option<String>,
+    },
+    Error {
+        id: Option<String>,
+        error: String,
+    },
+    /// Not a response to a request, sent whenever something happens in a channel
+    Event {
+        event: ChannelEvent,
+    },
+}
+
+impl LocalResponse {
+    fn error(id: Option<String>, error: impl Into<String>) -> Self {
+        Self::Error {
+            id,
+            error: error.into(),
+        }
+    }
+}
+
+/// The state of a single connection, shared between the TCP and websocket transports
+pub struct LocalSession {
+    command_handler: CommandHandler,
+    /// Set once the client has authenticated
+    client: Option<String>,
+    failed_auth_attempts: u32,
+}
+
+impl LocalSession {
+    pub fn new(command_handler: CommandHandler) -> Self {
+        Self {
+            command_handler,
+            client: None,
+            failed_auth_attempts: 0,
+        }
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.client.is_some()
+    }
+
+    /// Transports close the connection once this is set
+    pub fn auth_attempts_exceeded(&self) -> bool {
+        self.failed_auth_attempts >= MAX_AUTH_ATTEMPTS
+    }
+
+    pub async fn handle_frame(&mut self, frame: &str) -> LocalResponse {
+        match serde_json::from_str::<LocalRequest>(frame) {
+            Ok(request) => self.handle_request(request).await,
+            Err(err) => LocalResponse::error(None, format!("Invalid request: {err}")),
+        }
+    }
+
+    async fn handle_request(&mut self, request: LocalRequest) -> LocalResponse {
+        match request {
+            LocalRequest::Auth { token, client } => {
+                if self.client.is_some() {
+                    return LocalResponse::error(None, "Already authenticated");
+                }
+
+                if !is_valid_client_name(&client) {
+                    return LocalResponse::error(
+                        None,
+                        "Client names may only contain letters, numbers, `.`, `-` and `_`",
+                    );
+                }
+
+                let config = self.command_handler.config();
+                match &config.local_platform.token {
+                    Some(expected_token) if tokens_match(&token, expected_token) => {
+                        tracing::debug!("Local client {client} authenticated");
+                        self.client = Some(client.clone());
+                        LocalResponse::Authenticated { client }
+                    }
+                    Some(_) => {
+                        self.failed_auth_attempts += 1;
+                        LocalResponse::error(None, "Invalid token")
+                    }
+                    None => LocalResponse::error(None, "Local platform token is not configured"),
+                }
+            }
+            LocalRequest::Message { id, content } => match &self.client {
+                Some(client) => {
+                    let context = LocalPlatformContext::new(client.clone());
+                    let response = self
+                        .command_handler
+                        .handle_message(&content, context)
+                        .await
+                        .into_text();
+
+                    LocalResponse::Response { id, response }
+                }
+                None => LocalResponse::error(id, "Not authenticated"),
+            },
+        }
+    }
+}
+
+/// Compares in constant time, so that the token can't be guessed byte by byte.
+/// Only its length can be inferred
+fn tokens_match(token: &str, expected_token: &str) -> bool {
+    token.len() == expected_token.len()
+        && token
+            .bytes()
+            .zip(expected_token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// IP addresses are valid names, so that the channels of address-based identities can be kept
+fn is_valid_client_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_CLIENT_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+}
+
 #[derive(Clone, Debug)]
-struct LocalPlatformContext {
-    pub addr: SocketAddr,
-    pub addr_str: String,
+pub struct LocalPlatformContext {
+    /// The name the client has authenticated with
+    pub client: String,
+}
+
+impl LocalPlatformContext {
+    pub fn new(client: String) -> Self {
+        Self { client }
+    }
 }
 
 #[async_trait]
 impl PlatformContext for LocalPlatformContext {
     async fn get_permissions_internal(&self) -> Permissions {
-        if self.addr.ip() == IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)) {
-            Permissions::ChannelOwner
-        } else {
-            Permissions::Default
-        }
+        // Only authenticated clients get a context, and each client is its own channel
+        Permissions::ChannelOwner
     }
 
     fn get_channel(&self) -> ChannelIdentifier {
-        ChannelIdentifier::LocalAddress(self.addr_str.clone())
+        ChannelIdentifier::LocalAddress(self.client.clone())
     }
 
     fn get_user_identifier(&self) -> UserIdentifier {
-        UserIdentifier::IpAddr(self.addr.ip())
+        UserIdentifier::LocalClient(self.client.clone())
     }
 
     fn get_display_name(&self) -> &str {
-        &self.addr_str
+        &self.client
     }
 
     fn get_prefixes(&self) -> Vec<&str> {
         vec![""]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{is_valid_client_name, tokens_match, LocalRequest, LocalResponse};
+
+    #[test]
+    fn parses_requests() {
+        assert_eq!(
+            serde_json::from_str::<LocalRequest>(
+                r#"{"type": "auth", "token": "secret", "client": "deploy-script"}"#
+            )
+            .unwrap(),
+            LocalRequest::Auth {
+                token: "secret".to_owned(),
+                client: "deploy-script".to_owned()
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<LocalRequest>(r#"{"type": "message", "content": "ping"}"#)
+                .unwrap(),
+            LocalRequest::Message {
+                id: None,
+                content: "ping".to_owned()
+            }
+        );
+        assert!(serde_json::from_str::<LocalRequest>("ping").is_err());
+    }
+
+    #[test]
+    fn serializes_responses() {
+        let response = LocalResponse::Response {
+            id: Some("1".to_owned()),
+            response: Some("pong".to_owned()),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&response).unwrap(),
+            r#"{"type":"response","id":"1","response":"pong"}"#
+        );
+    }
+
+    #[test]
+    fn validates_client_names() {
+        assert!(is_valid_client_name("deploy-script"));
+        assert!(is_valid_client_name("127.0.0.1"));
+        assert!(!is_valid_client_name(""));
+        assert!(!is_valid_client_name("::1"));
+        assert!(!is_valid_client_name("my client"));
+    }
+
+    #[test]
+    fn matches_tokens() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secreT", "secret"));
+        assert!(!tokens_match("secret2", "secret"));
+        assert!(!tokens_match("", "secret"));
+    }
+}
@@ -1,10 +1,14 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use minecraft_client_rs::Client;
-use std::env;
 
-pub fn init() -> anyhow::Result<Client> {
-    let address = env::var("MINECRAFT_RCON_ADDRESS")?;
-    let password = env::var("MINECRAFT_RCON_PASSWORD")?;
+use crate::config::MinecraftConfig;
+
+pub fn init(config: &MinecraftConfig) -> anyhow::Result<Client> {
+    let address = config
+        .rcon_address
+        .clone()
+        .context("Minecraft RCON is not configured")?;
+    let password = config.rcon_password.clone().unwrap_or_default();
 
     let mut client = Client::new(address).map_err(|e| anyhow!("{}", e))?;
 
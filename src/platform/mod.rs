@@ -3,17 +3,17 @@ pub mod discord;
 pub mod irc;
 pub mod local;
 pub mod minecraft;
+pub mod supervisor;
 pub mod twitch;
 
 use crate::command_handler::CommandHandler;
+use crate::config::Config;
 use anyhow::anyhow;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::env::{self, VarError};
 use std::fmt::{self, Debug, Display};
 use std::hash::{Hash, Hasher};
-use std::net::IpAddr;
 use std::str::FromStr;
 use tracing::error;
 
@@ -25,8 +25,8 @@ pub trait ChatPlatform {
 
     async fn run(self);
 
-    fn get_prefix() -> String {
-        env::var("COMMAND_PREFIX").unwrap_or_else(|_| "!".to_string())
+    fn get_prefix(config: &Config) -> String {
+        config.command_prefix.clone()
     }
 }
 
@@ -45,6 +45,38 @@ pub trait PlatformContext: Debug {
     fn get_server_timestamp(&self) -> Option<DateTime<Utc>> {
         None
     }
+
+    /// The message being handled, if the platform supports moderating it
+    fn get_message_identifier(&self) -> Option<MessageIdentifier> {
+        None
+    }
+
+    /// The channel within the server on platforms where the channel identifier is the whole
+    /// server, such as the text channel of a Discord guild
+    fn get_subchannel(&self) -> Option<u64> {
+        None
+    }
+
+    /// Only known on platforms that report the emotes used in a message
+    fn get_emote_count(&self) -> Option<usize> {
+        None
+    }
+
+    /// Attachments are pasted and linked on platforms that can't upload them
+    fn supports_attachments(&self) -> bool {
+        false
+    }
+
+    /// The content of the message that the handled message is a reply to
+    fn get_reply_parent_text(&self) -> Option<&str> {
+        None
+    }
+
+    /// Messages that every shard receives, such as the ones from chat connections, are only
+    /// handled by the shard that owns the channel. Others are handled by the receiving shard
+    fn is_received_by_every_shard(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Clone)]
@@ -68,17 +100,33 @@ impl Debug for ServerPlatformContext {
 #[async_trait]
 impl PlatformContext for ServerPlatformContext {
     async fn get_permissions_internal(&self) -> Permissions {
-        let user = self
+        let executing_user = self.executing_user.clone();
+        let user = match self
             .cmd
             .db
-            .get_user(&self.executing_user)
-            .expect("DB error")
-            .expect("Invalid user");
+            .run(move |db| db.get_user(&executing_user))
+            .await
+        {
+            Ok(Some(user)) => user,
+            Ok(None) => return Permissions::Default,
+            Err(e) => {
+                tracing::error!("Could not get user {}: {e}", self.executing_user);
+                return Permissions::Default;
+            }
+        };
 
-        self.cmd
+        // Server-side executions can come from users that are not on the channel's platform
+        match self
+            .cmd
             .get_permissions_in_channel(user, &self.target_channel)
             .await
-            .expect("Failed to get permissions")
+        {
+            Ok(permissions) => permissions,
+            Err(err) => {
+                tracing::debug!("Could not get permissions, using the default: {err:#}");
+                Permissions::Default
+            }
+        }
     }
 
     fn get_channel(&self) -> ChannelIdentifier {
@@ -96,13 +144,17 @@ impl PlatformContext for ServerPlatformContext {
     fn get_prefixes(&self) -> Vec<&str> {
         vec![""]
     }
+
+    fn is_received_by_every_shard(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug)]
 pub enum ChatPlatformError {
     ReqwestError(reqwest::Error),
     MissingAuthentication,
-    MissingEnv(String),
+    MissingConfig(String),
     ServiceError(String),
 }
 
@@ -112,20 +164,29 @@ impl From<reqwest::Error> for ChatPlatformError {
     }
 }
 
-impl From<VarError> for ChatPlatformError {
-    fn from(_e: VarError) -> Self {
-        ChatPlatformError::MissingAuthentication
-    }
+#[derive(Clone, Debug)]
+pub enum MessageIdentifier {
+    Twitch {
+        channel_id: String,
+        message_id: String,
+    },
+    Discord {
+        channel_id: u64,
+        message_id: u64,
+    },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum UserIdentifier {
     TwitchID(String),
     DiscordID(String),
+    GithubId(String),
+    GoogleId(String),
     IrcName(String),
     TelegramId(u64),
-    IpAddr(IpAddr),
-    MatrixId(String),
+    /// Name of a local platform client, or the address of a server hook caller
+    LocalClient(String),
+    Connector(String, String), // Platform name, user id
 }
 
 impl fmt::Display for UserIdentifier {
@@ -133,10 +194,12 @@ impl fmt::Display for UserIdentifier {
         match self {
             UserIdentifier::TwitchID(id) => write!(f, "twitch:{id}"),
             UserIdentifier::DiscordID(id) => write!(f, "discord:{id}"),
+            UserIdentifier::GithubId(id) => write!(f, "github:{id}"),
+            UserIdentifier::GoogleId(id) => write!(f, "google:{id}"),
             UserIdentifier::TelegramId(id) => write!(f, "telegram:{id}"),
             UserIdentifier::IrcName(name) => write!(f, "irc:{name}"),
-            UserIdentifier::IpAddr(addr) => write!(f, "local:{addr}"),
-            UserIdentifier::MatrixId(mxid) => write!(f, "matrix:{mxid}"),
+            UserIdentifier::LocalClient(name) => write!(f, "local:{name}"),
+            UserIdentifier::Connector(platform, id) => write!(f, "{platform}:{id}"),
         }
     }
 }
@@ -157,13 +220,17 @@ impl UserIdentifier {
             match platform {
                 "twitch" => Ok(Self::TwitchID(user_id.to_owned())),
                 "discord" => Ok(Self::DiscordID(user_id.to_owned())),
-                "matrix" => Ok(Self::MatrixId(user_id.to_owned())),
+                "github" => Ok(Self::GithubId(user_id.to_owned())),
+                "google" => Ok(Self::GoogleId(user_id.to_owned())),
                 "irc" => Ok(Self::IrcName(user_id.to_owned())),
                 "telegram" => Ok(Self::TelegramId(
                     user_id
                         .parse()
                         .map_err(|_| UserIdentifierError::InvalidId)?,
                 )),
+                platform if connector::is_valid_platform_name(platform) => {
+                    Ok(Self::Connector(platform.to_owned(), user_id.to_owned()))
+                }
                 _ => {
                     error!("Unrecognized platform {platform}");
                     Err(UserIdentifierError::InvalidPlatform)
@@ -187,7 +254,7 @@ pub enum ChannelIdentifier {
     DiscordChannel(String),
     IrcChannel(String),
     LocalAddress(String),
-    MatrixChannel(String),
+    Connector(String, String),              // Platform name, channel id
     TelegramChat((String, Option<String>)), // Chat id, chat title
     Minecraft,                              // There is a single minecraft connection
     Anonymous,                              // Used for DMs and such
@@ -202,7 +269,9 @@ impl ChannelIdentifier {
             "irc" => Ok(Self::IrcChannel(id)),
             "telegram" => Ok(Self::TelegramChat((id, None))),
             "minecraft" => Ok(Self::Minecraft),
-            "matrix" => Ok(Self::MatrixChannel(id)),
+            platform if connector::is_valid_platform_name(platform) => {
+                Ok(Self::Connector(platform.to_owned(), id))
+            }
             _ => Err(anyhow::anyhow!("invalid platform")),
         }
     }
@@ -216,7 +285,7 @@ impl ChannelIdentifier {
             ChannelIdentifier::LocalAddress(_) => Some("local"),
             ChannelIdentifier::Minecraft => Some("minecraft"),
             ChannelIdentifier::Anonymous => None,
-            ChannelIdentifier::MatrixChannel(_) => Some("matrix"),
+            ChannelIdentifier::Connector(platform, _) => Some(platform),
         }
     }
 
@@ -229,7 +298,7 @@ impl ChannelIdentifier {
             ChannelIdentifier::LocalAddress(addr) => Some(addr),
             ChannelIdentifier::Minecraft => None,
             ChannelIdentifier::Anonymous => None,
-            ChannelIdentifier::MatrixChannel(id) => Some(id),
+            ChannelIdentifier::Connector(_, id) => Some(id),
         }
     }
 
@@ -240,6 +309,79 @@ impl ChannelIdentifier {
             _ => None,
         }
     }
+
+    /// Maximum length of a single message in the platform's length unit, if it has one
+    pub fn get_message_limit(&self) -> Option<usize> {
+        match self {
+            ChannelIdentifier::TwitchChannel(_) => Some(500),
+            ChannelIdentifier::DiscordChannel(_) => Some(2000),
+            // The whole IRC line including the prefix is limited to 512 bytes
+            ChannelIdentifier::IrcChannel(_) => Some(450),
+            ChannelIdentifier::TelegramChat(_) => Some(4096),
+            ChannelIdentifier::Minecraft => Some(256),
+            ChannelIdentifier::LocalAddress(_)
+            | ChannelIdentifier::Connector(_, _)
+            | ChannelIdentifier::Anonymous => None,
+        }
+    }
+
+    /// IRC limits lines in bytes, other platforms count characters
+    pub fn get_length_unit(&self) -> LengthUnit {
+        match self {
+            ChannelIdentifier::IrcChannel(_) => LengthUnit::Bytes,
+            _ => LengthUnit::Chars,
+        }
+    }
+
+    /// Cuts the text to fit into a single message on this platform
+    pub fn truncate_message(&self, text: &str) -> String {
+        let unit = self.get_length_unit();
+
+        match self.get_message_limit() {
+            Some(limit) if unit.measure(text) > limit => unit.truncate(text, limit),
+            _ => text.to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthUnit {
+    Chars,
+    /// UTF-8 bytes, texts are still only cut on character boundaries
+    Bytes,
+}
+
+impl LengthUnit {
+    pub fn measure(self, text: &str) -> usize {
+        match self {
+            LengthUnit::Chars => text.chars().count(),
+            LengthUnit::Bytes => text.len(),
+        }
+    }
+
+    /// The byte index where the longest prefix of the text that fits into `limit` ends
+    pub fn prefix_end(self, text: &str, limit: usize) -> usize {
+        match self {
+            LengthUnit::Chars => text
+                .char_indices()
+                .nth(limit)
+                .map_or(text.len(), |(index, _)| index),
+            LengthUnit::Bytes if limit >= text.len() => text.len(),
+            LengthUnit::Bytes => (0..=limit)
+                .rev()
+                .find(|index| text.is_char_boundary(*index))
+                .unwrap_or(0),
+        }
+    }
+
+    /// Cuts the text to at most `limit` with an ellipsis at the end
+    pub fn truncate(self, text: &str, limit: usize) -> String {
+        let end = self.prefix_end(text, limit.saturating_sub(self.measure("…")));
+
+        let mut truncated = text[..end].to_owned();
+        truncated.push('…');
+        truncated
+    }
 }
 
 impl Display for ChannelIdentifier {
@@ -260,11 +402,11 @@ impl FromStr for ChannelIdentifier {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> anyhow::Result<Self> {
-        let mut split = s.split(':');
-        let platform = split
-            .next()
-            .ok_or_else(|| anyhow!("Platform not specified!"))?;
-        let id = split.next().unwrap_or_default();
+        let (platform, id) = s.split_once(':').unwrap_or((s, ""));
+
+        if platform.is_empty() {
+            return Err(anyhow!("Platform not specified!"));
+        }
 
         ChannelIdentifier::new(platform, id.to_string())
     }
@@ -278,6 +420,7 @@ impl PartialEq for ChannelIdentifier {
             (Self::IrcChannel(l0), Self::IrcChannel(r0)) => l0 == r0,
             (Self::LocalAddress(l0), Self::LocalAddress(r0)) => l0 == r0,
             (Self::TelegramChat((l0, _)), Self::TelegramChat((r0, _))) => l0 == r0,
+            (Self::Connector(l0, l1), Self::Connector(r0, r1)) => l0 == r0 && l1 == r1,
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
         }
     }
@@ -294,14 +437,18 @@ impl Hash for ChannelIdentifier {
             ChannelIdentifier::LocalAddress(addr) => addr.hash(state),
             ChannelIdentifier::Minecraft => (),
             ChannelIdentifier::Anonymous => (),
-            ChannelIdentifier::MatrixChannel(id) => id.hash(state),
+            ChannelIdentifier::Connector(platform, id) => {
+                platform.hash(state);
+                id.hash(state);
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::platform::ChannelIdentifier;
+    use crate::platform::{ChannelIdentifier, LengthUnit, UserIdentifier};
+    use std::str::FromStr;
 
     #[test]
     fn channel_identifier_eq() {
@@ -315,4 +462,51 @@ mod tests {
             ChannelIdentifier::TwitchChannel((String::from("1234"), None))
         );
     }
+
+    #[test]
+    fn truncate_message() {
+        let channel = ChannelIdentifier::TwitchChannel((String::from("1234"), None));
+
+        assert_eq!(channel.truncate_message("short"), "short");
+
+        let truncated = channel.truncate_message(&"ы".repeat(600));
+        assert_eq!(truncated.chars().count(), 500);
+        assert!(truncated.ends_with('…'));
+
+        let local = ChannelIdentifier::LocalAddress(String::from("127.0.0.1"));
+        assert_eq!(local.truncate_message(&"a".repeat(600)).len(), 600);
+
+        let irc = ChannelIdentifier::IrcChannel(String::from("#channel"));
+        let truncated = irc.truncate_message(&"ы".repeat(300));
+        assert!(truncated.len() <= 450);
+        assert!(truncated.ends_with('…'));
+    }
+
+    #[test]
+    fn length_units() {
+        assert_eq!(LengthUnit::Chars.measure("ыы"), 2);
+        assert_eq!(LengthUnit::Bytes.measure("ыы"), 4);
+
+        assert_eq!(LengthUnit::Chars.prefix_end("ыыы", 2), 4);
+        assert_eq!(LengthUnit::Bytes.prefix_end("ыыы", 3), 2);
+        assert_eq!(LengthUnit::Bytes.prefix_end("ыыы", 10), 6);
+    }
+
+    #[test]
+    fn connector_identifiers() {
+        assert_eq!(
+            ChannelIdentifier::from_str("matrix:!room:example.org").unwrap(),
+            ChannelIdentifier::Connector(String::from("matrix"), String::from("!room:example.org"))
+        );
+        assert_ne!(
+            ChannelIdentifier::Connector(String::from("matrix"), String::from("1")),
+            ChannelIdentifier::Connector(String::from("matrix"), String::from("2"))
+        );
+
+        assert_eq!(
+            UserIdentifier::from_string("matrix:@user:example.org").unwrap(),
+            UserIdentifier::Connector(String::from("matrix"), String::from("@user:example.org"))
+        );
+        assert!(UserIdentifier::from_string("Invalid Platform:123").is_err());
+    }
 }
@@ -0,0 +1,147 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use tracing::{error, info, warn};
+
+use super::ChatPlatform;
+use crate::command_handler::{get_admin_channel, CommandHandler};
+
+const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(300);
+/// A run that lasted this long counts as recovered, which resets the backoff
+const STABLE_RUN_TIME: Duration = Duration::from_secs(600);
+/// Consecutive failures after which the admin channel is notified
+const NOTIFY_AFTER_FAILURES: u32 = 3;
+
+/// Exponential backoff that doubles the delay on every attempt, up to a maximum
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            current: initial,
+        }
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(self.max);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
+
+/// Initializes and runs the platform, starting it again whenever it stops.
+/// Platforms that fail to initialize on startup are skipped, as they are usually not configured.
+pub async fn start_platform<P>(name: &'static str, command_handler: CommandHandler)
+where
+    P: ChatPlatform + Send + 'static,
+{
+    let platform = match P::init(command_handler.clone()).await {
+        Ok(platform) => platform,
+        Err(e) => {
+            warn!("Could not initialize {name}: {e:?}");
+            return;
+        }
+    };
+
+    let mut platform = Some(platform);
+    supervise(name.to_owned(), command_handler.clone(), move || {
+        let platform = platform.take();
+        let command_handler = command_handler.clone();
+
+        async move {
+            let platform = match platform {
+                Some(platform) => platform,
+                None => P::init(command_handler)
+                    .await
+                    .map_err(|e| anyhow!("Could not initialize: {e:?}"))?,
+            };
+            platform.run().await;
+
+            Ok(())
+        }
+    });
+}
+
+/// Spawns the task and restarts it with an exponential backoff whenever it ends or panics
+pub fn supervise<F, Fut>(name: String, command_handler: CommandHandler, mut task: F)
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut backoff = Backoff::new(INITIAL_RESTART_DELAY, MAX_RESTART_DELAY);
+        let mut failures = 0;
+
+        loop {
+            let started_at = Instant::now();
+
+            match tokio::spawn(task()).await {
+                Ok(Ok(())) => warn!("{name} stopped"),
+                Ok(Err(e)) => error!("{name} failed: {e:#}"),
+                Err(e) => error!("{name} panicked: {e}"),
+            }
+
+            if started_at.elapsed() >= STABLE_RUN_TIME {
+                backoff.reset();
+                failures = 0;
+            }
+            failures += 1;
+
+            let delay = backoff.next_delay();
+            info!("Restarting {name} in {delay:?}");
+
+            if failures == NOTIFY_AFTER_FAILURES {
+                notify_admin(
+                    &command_handler,
+                    format!("{name} stopped {failures} times in a row, restarting in {delay:?}"),
+                )
+                .await;
+            }
+
+            tokio::time::sleep(delay).await;
+        }
+    });
+}
+
+async fn notify_admin(command_handler: &CommandHandler, message: String) {
+    if let Some(admin_channel) = get_admin_channel(&command_handler.config()) {
+        let platform_handler = command_handler.platform_handler.read().await;
+
+        if let Err(e) = platform_handler
+            .send_to_channel(admin_channel, message)
+            .await
+        {
+            warn!("Failed to notify the admin channel: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Backoff;
+    use std::time::Duration;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+
+        let delays: Vec<_> = (0..5).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 5, 5]);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+}
@@ -1,151 +1,171 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use anyhow::Context;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use dashmap::DashMap;
 use std::fmt::Debug;
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::sync::mpsc::{self, error::SendError, UnboundedReceiver, UnboundedSender};
 use tokio::task;
-use tokio::time::sleep;
+use tokio::time::{sleep, MissedTickBehavior};
 use twitch_irc::login::{LoginCredentials, RefreshingLoginCredentials};
 use twitch_irc::message::{Badge, PrivmsgMessage, ServerMessage, TwitchUserBasics, WhisperMessage};
 use twitch_irc::{ClientConfig, SecureTCPTransport, TwitchIRCClient};
 
+use crate::command_handler::status::ConnectionState;
 use crate::command_handler::CommandHandler;
 use crate::database::Database;
 use crate::platform::{ChannelIdentifier, PlatformContext};
 
-use super::{ChatPlatform, Permissions, UserIdentifier};
+use super::{ChatPlatform, MessageIdentifier, Permissions, UserIdentifier};
 
 pub type Credentials = RefreshingLoginCredentials<Database>;
 pub type TwitchClient = TwitchIRCClient<SecureTCPTransport, Credentials>;
 
-pub const MSG_LENGTH_LIMIT: usize = 420;
+const CHANNEL_RECONCILE_INTERVAL: Duration = Duration::from_secs(300);
 
-#[derive(Clone)]
 pub struct Twitch {
     command_handler: CommandHandler,
     possible_prefixes: Arc<[String; 5]>,
     last_messages: Arc<DashMap<String, String>>,
+    chat: Option<ChatClient>,
+}
+
+/// Created on init so that messages can be queued right away, and driven by `run`
+struct ChatClient {
+    client: TwitchClient,
+    incoming_messages: UnboundedReceiver<ServerMessage>,
+    outgoing_messages: UnboundedReceiver<SenderMessage>,
+    sender: ChatSender,
 }
 
 #[async_trait]
 impl ChatPlatform for Twitch {
     async fn init(command_handler: CommandHandler) -> Result<Box<Self>, super::ChatPlatformError> {
-        let login = {
-            let platform_handler = command_handler.platform_handler.read().await;
-            let twitch_api = platform_handler
-                .twitch_api
-                .as_ref()
-                .expect("Twitch API is not initialized");
-
-            twitch_api
-                .helix_api
-                .credentials
-                .get_credentials()
-                .await
-                .map_err(|_| super::ChatPlatformError::MissingAuthentication)?
-                .login
-        };
+        let platform_handler = command_handler.platform_handler.read().await;
+        let twitch_api = platform_handler
+            .twitch_api
+            .as_ref()
+            .expect("Twitch API is not initialized");
+
+        let login = twitch_api
+            .helix_api
+            .credentials
+            .get_credentials()
+            .await
+            .map_err(|_| super::ChatPlatformError::MissingAuthentication)?
+            .login;
 
         let possible_prefixes = Arc::new([
-            Self::get_prefix(),
+            Self::get_prefix(&command_handler.config()),
             format!("{},", &login),
             format!("@{}", &login),
             format!("@{},", &login),
             login,
         ]);
 
+        let (tx, outgoing_messages) = mpsc::unbounded_channel::<SenderMessage>();
+        let sender = ChatSender {
+            tx,
+            queued: Arc::new(AtomicUsize::new(0)),
+        };
+
+        let config = ClientConfig::new_simple(twitch_api.helix_api.credentials.clone());
+        let (incoming_messages, client): (_, TwitchClient) = TwitchIRCClient::new(config);
+
+        // Replaces the sender of the previous client when restarting
+        *twitch_api.chat_sender.lock().await = Some(sender.clone());
+
+        drop(platform_handler);
+
         Ok(Box::new(Self {
             command_handler,
             possible_prefixes,
             last_messages: Arc::new(DashMap::new()),
+            chat: Some(ChatClient {
+                client,
+                incoming_messages,
+                outgoing_messages,
+                sender,
+            }),
         }))
     }
 
-    async fn run(self) {
-        let (tx, mut rx) = mpsc::unbounded_channel::<SenderMessage>();
-
-        let platform_handler = self.command_handler.platform_handler.read().await;
-        let twitch_api = platform_handler
-            .twitch_api
-            .as_ref()
-            .expect("Twitch API is not initialized");
-
-        let config = ClientConfig::new_simple(twitch_api.helix_api.credentials.clone());
-
-        let (mut incoming_messages, client): (_, TwitchClient) = TwitchIRCClient::new(config);
+    async fn run(mut self) {
+        let ChatClient {
+            client,
+            mut incoming_messages,
+            mut outgoing_messages,
+            sender: tx,
+        } = self.chat.take().expect("Twitch chat is only run once");
 
         tracing::info!("Connected to Twitch");
 
-        *twitch_api.chat_sender.lock().await = Some(tx.clone());
-
-        let channels = self.command_handler.db.get_channels().unwrap();
-
-        let channel_ids: Vec<&str> = channels
-            .iter()
-            .filter_map(|channel| {
-                if channel.platform == "twitch" {
-                    Some(channel.channel.as_str())
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        match twitch_api
-            .helix_api
-            .get_users(None, Some(&channel_ids))
-            .await
-        {
-            Ok(twitch_channels) => {
-                let mut channels = HashSet::new();
-
-                for channel in twitch_channels {
-                    channels.insert(channel.login);
-                }
+        let status = self.command_handler.status.clone();
+        status.set_connection_state("twitch", ConnectionState::Connecting);
 
-                client
-                    .set_wanted_channels(channels)
-                    .expect("Invalid channels");
-            }
-            Err(e) => {
-                tracing::warn!("Failed to fetch channels {:?}", e);
-            }
-        }
-        drop(platform_handler);
+        let command_handler = self.command_handler.clone();
+        let queued = tx.queued.clone();
 
         tokio::spawn(async move {
-            while let Some(msg) = rx.recv().await {
-                tracing::trace!("Received Twitch sender message: {:?}", msg);
-                match msg {
-                    SenderMessage::Privmsg(pm) => {
-                        if let Err(error) = send_message(pm, &client).await {
-                            tracing::error!("Failed to send message: {error}");
-                        }
-                    }
-                    SenderMessage::JoinChannel(channel_login) => {
-                        if let Err(e) = client.join(channel_login) {
-                            tracing::error!("Failed to join channel: {}", e);
+            // Updated on joins and parts, so reconnects use the current channels
+            let mut wanted_channels = HashSet::new();
+            let mut known_logins = HashMap::new();
+
+            // The first tick completes immediately, which joins the channels on startup
+            let mut reconcile_interval = tokio::time::interval(CHANNEL_RECONCILE_INTERVAL);
+            reconcile_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut shard_changes = command_handler.sharding.subscribe();
+
+            loop {
+                let reconcile = tokio::select! {
+                    msg = outgoing_messages.recv() => match msg {
+                        Some(msg) => {
+                            handle_sender_message(msg, &client, &mut wanted_channels).await;
+                            queued.fetch_sub(1, Ordering::Relaxed);
+                            false
                         }
+                        None => break,
+                    },
+                    _ = reconcile_interval.tick() => true,
+                    // Channels move to other shards when shards join or leave
+                    _ = shard_changes.changed() => true,
+                };
+
+                if reconcile {
+                    if let Err(error) = reconcile_channels(
+                        &command_handler,
+                        &client,
+                        &mut wanted_channels,
+                        &mut known_logins,
+                    )
+                    .await
+                    {
+                        tracing::warn!("Failed to reconcile Twitch channels: {error:#}");
                     }
                 }
             }
         });
 
-        tokio::spawn(async move {
-            while let Some(message) = incoming_messages.recv().await {
-                match message {
-                    ServerMessage::Privmsg(pm) => self.handle_message(pm, tx.clone()).await,
-                    ServerMessage::Whisper(whisper) => {
-                        self.handle_message(whisper, tx.clone()).await
-                    }
-                    _ => (),
+        while let Some(message) = incoming_messages.recv().await {
+            match message {
+                ServerMessage::Privmsg(pm) => self.handle_message(pm, tx.clone()).await,
+                ServerMessage::Whisper(whisper) => self.handle_message(whisper, tx.clone()).await,
+                // Sent after every successful login
+                ServerMessage::GlobalUserState(_) => {
+                    status.set_connection_state("twitch", ConnectionState::Connected)
                 }
+                ServerMessage::Reconnect(_) => {
+                    status.set_connection_state("twitch", ConnectionState::Connecting)
+                }
+                _ => (),
             }
-        });
+        }
+
+        status.set_connection_state("twitch", ConnectionState::Disconnected);
     }
 }
 
@@ -261,36 +281,77 @@ impl<T: TwitchMessage + Sync + Clone + Debug> PlatformContext for TwitchExecutio
     fn get_server_timestamp(&self) -> Option<DateTime<Utc>> {
         self.msg.get_server_timestamp()
     }
+
+    fn get_message_identifier(&self) -> Option<MessageIdentifier> {
+        self.msg
+            .get_privmsg()
+            .map(|privmsg| MessageIdentifier::Twitch {
+                channel_id: privmsg.channel_id.clone(),
+                message_id: privmsg.message_id.clone(),
+            })
+    }
+
+    fn get_emote_count(&self) -> Option<usize> {
+        self.msg.get_privmsg().map(|privmsg| privmsg.emotes.len())
+    }
+
+    fn get_reply_parent_text(&self) -> Option<&str> {
+        self.msg
+            .get_privmsg()
+            .and_then(|privmsg| get_tag(privmsg, "reply-parent-msg-body"))
+    }
+}
+
+fn get_tag<'a>(privmsg: &'a PrivmsgMessage, name: &str) -> Option<&'a str> {
+    privmsg
+        .source
+        .tags
+        .0
+        .get(name)
+        .map(String::as_str)
+        .filter(|value| !value.is_empty())
+}
+
+/// Twitch clients start replies with a mention of the author of the parent message,
+/// which would stop replies from being recognized as commands
+fn strip_reply_mention<'a>(text: &'a str, parent_login: &str) -> &'a str {
+    text.strip_prefix('@')
+        .and_then(|text| text.split_once(' '))
+        .filter(|(mention, _)| mention.eq_ignore_ascii_case(parent_login))
+        .map(|(_, rest)| rest.trim_start())
+        .unwrap_or(text)
 }
 
 impl Twitch {
     async fn handle_message<T: 'static + TwitchMessage + Send + Sync + Clone>(
         &self,
         msg: T,
-        tx: UnboundedSender<SenderMessage>,
+        tx: ChatSender,
     ) {
-        let Self {
-            command_handler,
-            last_messages,
-            possible_prefixes,
-            ..
-        } = self.clone();
+        let command_handler = self.command_handler.clone();
+        let last_messages = self.last_messages.clone();
+        let possible_prefixes = self.possible_prefixes.clone();
 
         task::spawn(async move {
-            let prefixes = if let Some(custom_prefix) = command_handler
+            let channel = ChannelIdentifier::TwitchChannel((
+                match msg.get_channel() {
+                    Some((channel_id, _)) => channel_id.to_string(),
+                    None => msg.get_sender().id.clone(),
+                },
+                None,
+            ));
+            let custom_prefix = command_handler
                 .db
-                .get_prefix_in_channel(&ChannelIdentifier::TwitchChannel((
-                    match msg.get_channel() {
-                        Some((channel_id, _)) => channel_id.to_string(),
-                        None => msg.get_sender().id.clone(),
-                    },
-                    None,
-                )))
-                .expect("DB error")
-            {
-                vec![custom_prefix]
-            } else {
-                possible_prefixes.to_vec()
+                .run(move |db| db.get_prefix_in_channel(&channel))
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::error!("Could not get channel prefix: {e}");
+                    None
+                });
+
+            let prefixes = match custom_prefix {
+                Some(custom_prefix) => vec![custom_prefix],
+                None => possible_prefixes.to_vec(),
             };
 
             let context = TwitchExecutionContext {
@@ -302,20 +363,26 @@ impl Twitch {
 
             tracing::debug!("Recieved a command at {:?}", recieved_instant);
 
-            let response = command_handler
-                .handle_message(msg.get_content(), context)
-                .await
-                .map(|s| s.replace('\n', " "));
+            let content = match msg
+                .get_privmsg()
+                .and_then(|privmsg| get_tag(privmsg, "reply-parent-user-login"))
+            {
+                Some(parent_login) => strip_reply_mention(msg.get_content(), parent_login),
+                None => msg.get_content(),
+            };
+
+            let output = command_handler.handle_message(content, context).await;
 
             tracing::debug!(
                 "Command took {}ms to process",
                 recieved_instant.elapsed().as_millis()
             );
 
-            if let Some(mut response) = response {
+            for response in output.into_messages() {
+                let mut response = response.replace('\n', " ");
                 if response.trim().is_empty() {
                     tracing::info!("Empty command response");
-                    return;
+                    continue;
                 }
 
                 let channel = match msg.get_channel() {
@@ -342,20 +409,41 @@ impl Twitch {
 
                 tracing::info!("Replying with {}", response);
 
-                if let Some(pm) = msg.get_privmsg() {
-                    tx.send(SenderMessage::Privmsg(Privmsg {
-                        channel_login: pm.channel_login.clone(),
-                        message: response,
-                        reply_to_id: Some(pm.message_id.clone()),
-                    }))
-                    .unwrap();
-                } else {
-                    tx.send(SenderMessage::Privmsg(Privmsg {
-                        channel_login: msg.get_sender().login.clone(),
-                        message: format!("/w {} {}", msg.get_sender().login, response),
-                        reply_to_id: None,
-                    }))
-                    .unwrap();
+                let channel_identifier =
+                    ChannelIdentifier::TwitchChannel((channel.to_owned(), None));
+                let platform_handler = command_handler.platform_handler.read().await;
+                let settings = platform_handler.get_channel_settings(&channel_identifier);
+
+                let response = match msg.get_privmsg() {
+                    Some(_) => platform_handler.format_response(
+                        &channel_identifier,
+                        &response,
+                        &format!("@{}", msg.get_sender().login),
+                    ),
+                    None => response,
+                };
+                let messages = platform_handler.split_message(&channel_identifier, &response);
+                drop(platform_handler);
+
+                for message in messages {
+                    if let Some(pm) = msg.get_privmsg() {
+                        tx.send(SenderMessage::Privmsg(Privmsg {
+                            channel_login: pm.channel_login.clone(),
+                            message,
+                            reply_to_id: Some(pm.message_id.clone())
+                                .filter(|_| settings.reply_to_messages),
+                            action: false,
+                        }))
+                        .unwrap();
+                    } else {
+                        tx.send(SenderMessage::Privmsg(Privmsg {
+                            channel_login: msg.get_sender().login.clone(),
+                            message: format!("/w {} {}", msg.get_sender().login, message),
+                            reply_to_id: None,
+                            action: false,
+                        }))
+                        .unwrap();
+                    }
                 }
 
                 sleep(Duration::from_secs(1)).await; // This is needed to adhere to the twitch rate limits
@@ -364,55 +452,165 @@ impl Twitch {
     }
 }
 
-async fn send_message(mut pm: Privmsg, client: &TwitchClient) -> Result<(), anyhow::Error> {
-    let send = |pm: Privmsg| {
-        let client = client.clone();
-        async move {
-            match pm.reply_to_id {
-                Some(reply_to_id) => {
-                    client
-                        .say_in_response(pm.channel_login.clone(), pm.message, Some(reply_to_id))
-                        .await
-                }
-                None => client.privmsg(pm.channel_login, pm.message).await,
+async fn send_message(pm: Privmsg, client: &TwitchClient) -> Result<(), anyhow::Error> {
+    if pm.action {
+        client.me(pm.channel_login, pm.message).await?;
+    } else {
+        match pm.reply_to_id {
+            Some(reply_to_id) => {
+                client
+                    .say_in_response(pm.channel_login, pm.message, Some(reply_to_id))
+                    .await?
             }
+            None => client.privmsg(pm.channel_login, pm.message).await?,
         }
-    };
+    }
 
-    while pm.message.len() > MSG_LENGTH_LIMIT {
-        let mut index = MSG_LENGTH_LIMIT - 1;
+    sleep(Duration::from_secs(1)).await;
+    Ok(())
+}
 
-        while !pm.message.is_char_boundary(index) {
-            index -= 1;
+async fn handle_sender_message(
+    msg: SenderMessage,
+    client: &TwitchClient,
+    wanted_channels: &mut HashSet<String>,
+) {
+    tracing::trace!("Received Twitch sender message: {:?}", msg);
+
+    match msg {
+        SenderMessage::Privmsg(pm) => {
+            if let Err(error) = send_message(pm, client).await {
+                tracing::error!("Failed to send message: {error}");
+            }
         }
+        SenderMessage::JoinChannel(channel_login) => {
+            let channel_login = channel_login.to_lowercase();
+            wanted_channels.insert(channel_login.clone());
 
-        let mut rest = pm.message.split_off(index);
+            if let Err(e) = client.set_wanted_channels(wanted_channels.clone()) {
+                wanted_channels.remove(&channel_login);
+                tracing::error!("Failed to join channel: {}", e);
+            }
+        }
+        SenderMessage::PartChannel(channel_login) => {
+            wanted_channels.remove(&channel_login.to_lowercase());
 
-        if pm.message.chars().last().map(|c| c.is_whitespace()) != Some(true) {
-            let mut words = pm.message.split_whitespace();
-            if let Some(last_word) = words.next_back() {
-                rest = format!("{}{}", last_word, rest);
+            if let Err(e) = client.set_wanted_channels(wanted_channels.clone()) {
+                tracing::error!("Failed to part channel: {}", e);
             }
-            pm.message = words.collect::<Vec<&str>>().join(" ").to_owned();
         }
+    }
+}
 
-        send(pm.clone()).await?;
+/// Compares the joined channels with the DB, following renames and rejoining dropped channels.
+/// When sharding, only the channels owned by this shard are joined
+async fn reconcile_channels(
+    command_handler: &CommandHandler,
+    client: &TwitchClient,
+    wanted_channels: &mut HashSet<String>,
+    known_logins: &mut HashMap<String, String>,
+) -> anyhow::Result<()> {
+    let channel_ids: Vec<String> = command_handler
+        .db
+        .run(|db| db.get_channels())
+        .await?
+        .into_iter()
+        .filter(|channel| channel.platform == "twitch" && channel.joined)
+        .filter(|channel| command_handler.sharding.owns_channel(Some(channel.id)))
+        .map(|channel| channel.channel)
+        .collect();
+
+    // Channels are stored by id, so the current login is always looked up from the API
+    let users = {
+        let platform_handler = command_handler.platform_handler.read().await;
+        let twitch_api = platform_handler
+            .twitch_api
+            .as_ref()
+            .context("Twitch API is not initialized")?;
 
-        pm.message = rest;
+        let ids: Vec<&str> = channel_ids.iter().map(String::as_str).collect();
+        twitch_api.helix_api.get_users_by_ids_uncached(&ids).await?
+    };
 
-        sleep(Duration::from_secs(1)).await;
+    for channel_id in &channel_ids {
+        if !users.iter().any(|user| user.id == *channel_id) {
+            tracing::warn!("Twitch channel {channel_id} was not found, it may be suspended");
+        }
+    }
+
+    let mut channels = HashSet::new();
+
+    for user in users {
+        if let Some(old_login) = known_logins.get(&user.id) {
+            if *old_login != user.login {
+                tracing::info!(
+                    "Twitch channel {} was renamed from {old_login} to {}",
+                    user.id,
+                    user.login
+                );
+            }
+        }
+
+        known_logins.insert(user.id, user.login.clone());
+        channels.insert(user.login);
+    }
+
+    // Channels that were just added are still being joined
+    let previous_channels: Vec<String> = wanted_channels.intersection(&channels).cloned().collect();
+
+    if channels != *wanted_channels {
+        tracing::info!(
+            "Updating Twitch channels: {} joined, {} parted",
+            channels.difference(wanted_channels).count(),
+            wanted_channels.difference(&channels).count()
+        );
+
+        client.set_wanted_channels(channels.clone())?;
+        *wanted_channels = channels;
+    }
+
+    for channel_login in previous_channels {
+        let (wanted, joined) = client.get_channel_status(channel_login.clone()).await;
+
+        if wanted && !joined {
+            tracing::warn!("Not joined to Twitch channel {channel_login}, rejoining");
+
+            client.part(channel_login.clone());
+            client.join(channel_login)?;
+        }
     }
-    send(pm).await?;
 
-    sleep(Duration::from_secs(1)).await;
     Ok(())
 }
 
+/// Sends messages to the chat client task and counts the ones that haven't been sent yet
+#[derive(Clone, Debug)]
+pub struct ChatSender {
+    tx: UnboundedSender<SenderMessage>,
+    queued: Arc<AtomicUsize>,
+}
+
+impl ChatSender {
+    pub fn send(&self, message: SenderMessage) -> Result<(), SendError<SenderMessage>> {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+
+        self.tx.send(message).map_err(|e| {
+            self.queued.fetch_sub(1, Ordering::Relaxed);
+            e
+        })
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub enum SenderMessage {
     Privmsg(Privmsg),
     JoinChannel(String),
+    PartChannel(String),
 }
 
 #[derive(Clone, Debug)]
@@ -420,11 +618,13 @@ pub struct Privmsg {
     pub channel_login: String,
     pub message: String,
     pub reply_to_id: Option<String>,
+    /// Sent as a `/me` message
+    pub action: bool,
 }
 
 #[cfg(test)]
 mod tests {
-    use super::TwitchExecutionContext;
+    use super::{strip_reply_mention, TwitchExecutionContext};
     use crate::platform::PlatformContext;
     use connector_schema::Permissions;
     use pretty_assertions::assert_eq;
@@ -443,4 +643,26 @@ mod tests {
         let permissions = twitch_context.get_permissions_internal().await;
         assert_eq!(permissions, Permissions::ChannelMod);
     }
+
+    #[test]
+    fn reply_parent() {
+        let raw = r"@badges=;display-name=Forsen;id=1b2c;reply-parent-display-name=Okayeg;reply-parent-msg-body=Доброго\sранку;reply-parent-msg-id=5d6e;reply-parent-user-id=123;reply-parent-user-login=okayeg;room-id=31400525;tmi-sent-ts=1676400113453;user-id=22484632 :forsen!forsen@forsen.tmi.twitch.tv PRIVMSG #supinic :@okayeg !translate";
+        let irc_message = IRCMessage::parse(raw).unwrap();
+        let privmsg = PrivmsgMessage::try_from(irc_message).unwrap();
+
+        let twitch_context = TwitchExecutionContext {
+            msg: privmsg,
+            prefixes: vec!["!".to_owned()],
+        };
+        assert_eq!(
+            twitch_context.get_reply_parent_text(),
+            Some("Доброго ранку")
+        );
+
+        assert_eq!(
+            strip_reply_mention("@Okayeg !translate", "okayeg"),
+            "!translate"
+        );
+        assert_eq!(strip_reply_mention("@forsen hi", "okayeg"), "@forsen hi");
+    }
 }
@@ -1,10 +1,27 @@
-use std::env;
+use std::{net::SocketAddr, pin::Pin};
 
-use crate::command_handler::{platform_handler::PlatformHandlerError, CommandHandler};
-use tonic::{transport::Server, Request, Response, Status};
+use crate::{
+    command_handler::{
+        outgoing_webhooks::ChannelEvent as BotChannelEvent, platform_handler::PlatformHandlerError,
+        CommandHandler,
+    },
+    database::{
+        models::{Channel, CommandMode},
+        DatabaseError,
+    },
+    platform::{ServerPlatformContext, UserIdentifier},
+};
+use futures::Stream;
+use tokio::sync::broadcast::error::RecvError;
+use tonic::{metadata::MetadataValue, transport::Server, Request, Response, Status};
 
 use self::foobot::foobot_server::{Foobot, FoobotServer};
-use foobot::{EchoRequest, EchoResponse};
+use foobot::{
+    get_user_request, ChannelEvent, DeleteCommandRequest, DeleteCommandResponse, EchoRequest,
+    EchoResponse, ExecuteCommandRequest, ExecuteCommandResponse, GetUserRequest,
+    ListChannelsRequest, ListChannelsResponse, ListCommandsRequest, ListCommandsResponse,
+    SetCommandRequest, SetCommandResponse, StreamEventsRequest, User,
+};
 
 pub mod foobot {
     tonic::include_proto!("foobot");
@@ -12,15 +29,31 @@ pub mod foobot {
 
 pub fn start_server(command_handler: CommandHandler) {
     tokio::spawn(async move {
-        let port = env::var("GRPC_PORT").unwrap_or_else(|_| String::from("50051"));
-        let addr = format!("0.0.0.0:{}", port).parse().unwrap();
+        let config = command_handler.config();
+        let grpc_config = &config.grpc;
+        let addr = SocketAddr::from(([0, 0, 0, 0], grpc_config.port));
         tracing::info!("GRPC server is listening on {}", addr);
 
-        if let Err(e) = Server::builder()
-            .add_service(FoobotServer::new(FoobotService { command_handler }))
-            .serve(addr)
-            .await
-        {
+        let token = grpc_config.token.as_ref().map(|token| {
+            MetadataValue::try_from(format!("Bearer {token}")).expect("Invalid GRPC token")
+        });
+        if token.is_none() {
+            tracing::warn!(
+                "grpc.token is not set, the GRPC server does not require authentication"
+            );
+        }
+
+        let service = FoobotServer::with_interceptor(
+            FoobotService { command_handler },
+            move |request: Request<()>| match &token {
+                Some(token) if request.metadata().get("authorization") != Some(token) => {
+                    Err(Status::unauthenticated("Invalid token"))
+                }
+                _ => Ok(request),
+            },
+        );
+
+        if let Err(e) = Server::builder().add_service(service).serve(addr).await {
             tracing::error!("GRPC server error: {}", e);
         }
     });
@@ -30,29 +63,24 @@ struct FoobotService {
     command_handler: CommandHandler,
 }
 
-#[tonic::async_trait]
-impl Foobot for FoobotService {
-    async fn send_message(
-        &self,
-        request: Request<EchoRequest>,
-    ) -> Result<Response<EchoResponse>, Status> {
-        let request = request.into_inner();
-        tracing::info!("{:?}", request);
-
-        let channel = self
-            .command_handler
+impl FoobotService {
+    async fn get_channel(&self, channel_id: u64) -> Result<Channel, Status> {
+        self.command_handler
             .db
-            .get_channel_by_id(request.channel_id)
-            .expect("DB error")
-            .ok_or_else(|| Status::not_found("Specified channel not found"))?;
+            .run(move |db| db.get_channel_by_id(channel_id))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("Specified channel not found"))
+    }
 
+    async fn send_to_channel(&self, channel: &Channel, message: String) -> Result<(), Status> {
         let platform_handler = self.command_handler.platform_handler.read().await;
 
         match platform_handler
-            .send_to_channel(channel.get_identifier(), request.message)
+            .send_to_channel(channel.get_identifier(), message)
             .await
         {
-            Ok(()) => Ok(Response::new(EchoResponse {})),
+            Ok(()) => Ok(()),
             Err(PlatformHandlerError::Unsupported) => Err(Status::unimplemented(
                 "Remotely sending messages is not supported for this platform",
             )),
@@ -63,3 +91,235 @@ impl Foobot for FoobotService {
         }
     }
 }
+
+#[tonic::async_trait]
+impl Foobot for FoobotService {
+    type StreamEventsStream = Pin<Box<dyn Stream<Item = Result<ChannelEvent, Status>> + Send>>;
+
+    async fn send_message(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        let request = request.into_inner();
+        tracing::info!("{:?}", request);
+
+        let channel = self.get_channel(request.channel_id).await?;
+        self.send_to_channel(&channel, request.message).await?;
+
+        Ok(Response::new(EchoResponse {}))
+    }
+
+    /// Runs the message as if it was sent in the channel by the given user
+    async fn execute_command(
+        &self,
+        request: Request<ExecuteCommandRequest>,
+    ) -> Result<Response<ExecuteCommandResponse>, Status> {
+        let request = request.into_inner();
+        tracing::info!("{:?}", request);
+
+        let channel = self.get_channel(request.channel_id).await?;
+        let executing_user = UserIdentifier::from_string(&request.user)
+            .map_err(|_| Status::invalid_argument("Invalid user identifier"))?;
+
+        let platform_ctx = ServerPlatformContext {
+            target_channel: channel.get_identifier(),
+            executing_user,
+            cmd: self.command_handler.clone(),
+            display_name: request.display_name,
+        };
+
+        let response = self
+            .command_handler
+            .handle_message(&request.message, platform_ctx)
+            .await
+            .into_text();
+
+        if let (Some(response), true) = (&response, request.send_response) {
+            self.send_to_channel(&channel, response.clone()).await?;
+        }
+
+        Ok(Response::new(ExecuteCommandResponse { response }))
+    }
+
+    async fn get_user(&self, request: Request<GetUserRequest>) -> Result<Response<User>, Status> {
+        let db = &self.command_handler.db;
+
+        let user = match request.into_inner().user {
+            Some(get_user_request::User::Id(id)) => db.run(move |db| db.get_user_by_id(id)).await,
+            Some(get_user_request::User::Identifier(identifier)) => {
+                let identifier = UserIdentifier::from_string(&identifier)
+                    .map_err(|_| Status::invalid_argument("Invalid user identifier"))?;
+                db.run(move |db| db.get_user(&identifier)).await
+            }
+            None => return Err(Status::invalid_argument("User not specified")),
+        }
+        .map_err(|e| Status::internal(e.to_string()))?
+        .ok_or_else(|| Status::not_found("User not found"))?;
+
+        Ok(Response::new(User {
+            id: user.id,
+            twitch_id: user.twitch_id,
+            discord_id: user.discord_id,
+            irc_name: user.irc_name,
+            telegram_id: user.telegram_id,
+        }))
+    }
+
+    /// Streams the same events as outgoing webhooks. Channel id 0 streams all channels
+    async fn stream_events(
+        &self,
+        request: Request<StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        let channel_id = request.into_inner().channel_id;
+        let events = self.command_handler.webhook_dispatcher.subscribe();
+
+        let stream = futures::stream::unfold(events, move |mut events| async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) if channel_id == 0 || event.channel_id == channel_id => {
+                        return Some((Ok(convert_event(event)), events));
+                    }
+                    Ok(_) => (),
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("GRPC event stream skipped {skipped} events");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn list_channels(
+        &self,
+        _: Request<ListChannelsRequest>,
+    ) -> Result<Response<ListChannelsResponse>, Status> {
+        let channels = self
+            .command_handler
+            .db
+            .run(|db| db.get_channels())
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .into_iter()
+            .map(|channel| foobot::Channel {
+                id: channel.id,
+                platform: channel.platform,
+                channel: channel.channel,
+                joined: channel.joined,
+            })
+            .collect();
+
+        Ok(Response::new(ListChannelsResponse { channels }))
+    }
+
+    async fn list_commands(
+        &self,
+        request: Request<ListCommandsRequest>,
+    ) -> Result<Response<ListCommandsResponse>, Status> {
+        let channel_id = self.get_channel(request.into_inner().channel_id).await?.id;
+
+        let commands = self
+            .command_handler
+            .db
+            .run(move |db| db.get_commands(channel_id))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .into_iter()
+            .map(|command| foobot::Command {
+                name: command.name,
+                action: command.action,
+                mode: command.mode.to_string(),
+                permissions: command.permissions,
+                cooldown: command.cooldown,
+            })
+            .collect();
+
+        Ok(Response::new(ListCommandsResponse { commands }))
+    }
+
+    /// Adds the command, or updates its action if it already exists
+    async fn set_command(
+        &self,
+        request: Request<SetCommandRequest>,
+    ) -> Result<Response<SetCommandResponse>, Status> {
+        let request = request.into_inner();
+        tracing::info!("{:?}", request);
+
+        let mode = request
+            .mode
+            .map(|mode| {
+                mode.parse::<CommandMode>()
+                    .map_err(|_| Status::invalid_argument(format!("Invalid mode {mode}")))
+            })
+            .transpose()?;
+
+        let channel_identifier = self.get_channel(request.channel_id).await?.get_identifier();
+
+        let created = self
+            .command_handler
+            .db
+            .run(move |db| {
+                let existing = db
+                    .get_command(&channel_identifier, &request.name)
+                    .map_err(|e| Status::internal(e.to_string()))?;
+
+                match existing {
+                    Some(_) => db.update_command_action(
+                        &channel_identifier,
+                        &request.name,
+                        &request.action,
+                    ),
+                    None => db.add_command_to_channel(
+                        &channel_identifier,
+                        &request.name,
+                        &request.action,
+                    ),
+                }
+                .map_err(|e| match e {
+                    DatabaseError::BuiltinCommandName(_) => Status::invalid_argument(e.to_string()),
+                    e => Status::internal(e.to_string()),
+                })?;
+
+                if let Some(mode) = mode {
+                    db.set_command_mode(&channel_identifier, &request.name, mode)
+                        .map_err(|e| Status::internal(e.to_string()))?;
+                }
+
+                Ok(existing.is_none())
+            })
+            .await?;
+
+        Ok(Response::new(SetCommandResponse { created }))
+    }
+
+    async fn delete_command(
+        &self,
+        request: Request<DeleteCommandRequest>,
+    ) -> Result<Response<DeleteCommandResponse>, Status> {
+        let request = request.into_inner();
+        tracing::info!("{:?}", request);
+
+        let channel_id = self.get_channel(request.channel_id).await?.id;
+
+        self.command_handler
+            .db
+            .run(move |db| db.delete_command(channel_id, &request.name))
+            .await
+            .map_err(|e| match e {
+                DatabaseError::InvalidValue => Status::not_found("Command not found"),
+                e => Status::internal(e.to_string()),
+            })?;
+
+        Ok(Response::new(DeleteCommandResponse {}))
+    }
+}
+
+fn convert_event(event: BotChannelEvent) -> ChannelEvent {
+    ChannelEvent {
+        data: serde_json::to_string(&event.data).expect("Failed to serialize event"),
+        event: event.event,
+        channel_id: event.channel_id,
+        timestamp: event.timestamp,
+    }
+}